@@ -0,0 +1,75 @@
+use std::{path::PathBuf, sync::Arc};
+
+use criterion::{Bencher, Criterion, black_box, criterion_group, criterion_main};
+use wright::{
+    ast::module::Module,
+    lexer::Lexer,
+    package::{Package, PackageModule, check_package},
+    parser::Parser,
+    source_tracking::{SourceMap, filename::FileName, source::Source},
+    vm::{self, Value, Vm, bytecode::IntTy},
+};
+
+const U64: IntTy = IntTy {
+    bits: 64,
+    signed: false,
+};
+
+/// Compile a program of wright source code to bytecode.
+fn compile(source: &'static str) -> vm::Module {
+    let map = SourceMap::new();
+    let source_ref = map.add(Source::new_from_static_str(FileName::None, source));
+    let module = Module::parse(&mut Parser::new(Lexer::new(Arc::clone(&source_ref)))).unwrap();
+
+    let package = check_package(Package {
+        root: PathBuf::new(),
+        modules: vec![PackageModule {
+            path: Vec::new(),
+            module,
+        }],
+        diagnostics: Vec::new(),
+    });
+
+    assert_eq!(package.errors(), 0);
+    vm::compile(&package.lower(), &package.resolution, &package.typeck)
+}
+
+fn bench_fib(c: &mut Criterion) {
+    let module =
+        compile("func fib(n: u64) -> u64 { if n < 2 { n } else { fib(n - 1) + fib(n - 2) } }");
+
+    let fib = module.function_named("fib").unwrap();
+
+    c.bench_function("vm fib(20)", move |b: &mut Bencher| {
+        let mut vm = Vm::new(&module);
+        b.iter(|| {
+            vm.call(fib, &[black_box(Value::Int(20, U64))], &mut std::io::sink())
+                .unwrap()
+        });
+    });
+}
+
+fn bench_loop(c: &mut Criterion) {
+    let module = compile(
+        "record Point { x: u64, y: u64 }\n\
+        func sum(n: u64) -> u64 {\n\
+            var total: u64 = 0;\n\
+            var p = Point { x: 0, y: 1 };\n\
+            for i in 0..n { p.x = i; total += p.x * p.y; }\n\
+            total\n\
+        }",
+    );
+
+    let sum = module.function_named("sum").unwrap();
+
+    c.bench_function("vm loop over a record 10000 times", move |b: &mut Bencher| {
+        let mut vm = Vm::new(&module);
+        b.iter(|| {
+            vm.call(sum, &[black_box(Value::Int(10_000, U64))], &mut std::io::sink())
+                .unwrap()
+        });
+    });
+}
+
+criterion_group!(benches, bench_fib, bench_loop);
+criterion_main!(benches);
@@ -0,0 +1,16 @@
+//! Semantic analysis passes that run over parsed [AST]s, such as privacy checking, name resolution, and type
+//! checking, along with the layout of types in memory that they determine.
+//!
+//! [AST]: crate::ast
+
+pub mod borrowck;
+pub mod builtins;
+pub mod constraints;
+pub mod exhaustiveness;
+pub mod layout;
+pub mod privacy;
+pub mod purity;
+pub mod resolve;
+#[cfg(test)]
+pub(crate) mod testing;
+pub mod typeck;
@@ -0,0 +1,812 @@
+//! Borrow checking -- making sure that references (`@x` and `@mut x`) never outlive or alias what they point to.
+//!
+//! Every function and closure body is lowered into a [control-flow graph](cfg), then checked with two dataflow
+//! analyses over it:
+//! - *Liveness* (backwards): a local is live at a point if its current value might be used later.
+//! - *Loans* (forwards): each `@` or `@mut` creates a loan of the borrowed place, and the loans flow into the
+//!   locals that hold the reference (or a value containing it, like a record or the result of a call).
+//!
+//! A loan is active wherever a live local might hold it, so a borrow lasts until the last use of the reference
+//! rather than until the end of its scope. Wherever a place is used, it must not conflict with an active loan:
+//! - A place that's borrowed mutably can't be used, or borrowed again, at all.
+//! - A place that's borrowed (either way) can't be borrowed mutably, assigned to, or moved out of.
+//! - A local can't go out of scope while it's borrowed, which means references can't outlive what they point to.
+//!
+//! Each conflict is reported with the borrow, the use that conflicts with it, and the later use of the reference
+//! that keeps the borrow active.
+
+mod cfg;
+
+use crate::{
+    analysis::{resolve::Resolution, resolve::module_bodies, typeck::TypeckResults},
+    ast::{decl::Decl, module::Module},
+    reporting::{Diagnostic, Highlight},
+    source_tracking::fragment::Fragment,
+};
+use cfg::{Body, Local, Operand, Place, Projection, Rvalue, StatementKind, Terminator};
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+
+/// Borrow check every function (and closure) in the given modules, which must have already been type checked.
+pub fn check<'m>(
+    modules: impl IntoIterator<Item = (Vec<String>, &'m Module)>,
+    resolution: &Resolution,
+    typeck: &TypeckResults,
+) -> Vec<Diagnostic> {
+    let modules: Vec<_> = modules.into_iter().collect();
+    let mut diagnostics = Vec::new();
+
+    for (_, items) in module_bodies(&modules) {
+        for func in items.iter().flat_map(Decl::functions) {
+            let Some(body) = &func.body else {
+                continue;
+            };
+
+            let (body, mut closures) = cfg::lower_function(func, body, resolution, typeck);
+            diagnostics.extend(Checker::new(&body).check());
+
+            while let Some(closure) = closures.pop() {
+                let (body, nested) = cfg::lower_closure(closure, resolution, typeck);
+                diagnostics.extend(Checker::new(&body).check());
+                closures.extend(nested);
+            }
+        }
+    }
+
+    diagnostics
+}
+
+/// A location in a [Body]: a statement, or the terminator of a block (at the index after its last statement).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct Point {
+    /// The index of the block.
+    block: usize,
+    /// The index of the statement in the block.
+    index: usize,
+}
+
+/// A borrow of a place, created by an `@` or `@mut`.
+struct Loan {
+    /// The place that's borrowed.
+    place: Place,
+    /// Whether the borrow is mutable.
+    is_mutable: bool,
+    /// The source code of the borrow.
+    fragment: Fragment,
+}
+
+/// The ways that a place can be used.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum AccessKind {
+    /// Reading (or copying) the value in the place.
+    Read,
+    /// Borrowing the place.
+    Borrow {
+        /// Whether the borrow is mutable.
+        is_mutable: bool,
+    },
+    /// Assigning to the place.
+    Write,
+    /// Moving the value out of the place.
+    Move,
+    /// The local goes out of scope.
+    StorageDead,
+}
+
+/// A use of a place at some point in a [Body].
+struct Access<'b> {
+    /// The place that's used.
+    place: &'b Place,
+    /// How the place is used.
+    kind: AccessKind,
+    /// The source code of the use.
+    fragment: &'b Fragment,
+}
+
+/// The loans that each local might hold a reference from.
+type LoanState = BTreeSet<(Local, usize)>;
+
+/// The borrow checker for a single [Body].
+struct Checker<'b> {
+    /// The body being checked.
+    body: &'b Body,
+    /// Every loan in the body.
+    loans: Vec<Loan>,
+    /// The loan created at each point that borrows a place.
+    issued: HashMap<Point, usize>,
+    /// The locals that are live before each point, by block and then by statement.
+    live: Vec<Vec<BTreeSet<Local>>>,
+    /// The loans held by each local before each point, by block and then by statement.
+    held: Vec<Vec<LoanState>>,
+}
+
+impl<'b> Checker<'b> {
+    /// Find the loans in a body and run the dataflow analyses over it.
+    fn new(body: &'b Body) -> Self {
+        let mut checker = Checker {
+            body,
+            loans: Vec::new(),
+            issued: HashMap::new(),
+            live: Vec::new(),
+            held: Vec::new(),
+        };
+
+        for (block, data) in body.blocks.iter().enumerate() {
+            for (index, statement) in data.statements.iter().enumerate() {
+                if let StatementKind::Assign(_, Rvalue::Ref { place, is_mutable }) = &statement.kind
+                {
+                    checker
+                        .issued
+                        .insert(Point { block, index }, checker.loans.len());
+                    checker.loans.push(Loan {
+                        place: place.clone(),
+                        is_mutable: *is_mutable,
+                        fragment: statement.fragment.clone(),
+                    });
+                }
+            }
+        }
+
+        checker.compute_liveness();
+        checker.compute_loans();
+        checker
+    }
+
+    /// Get the number of points in a block, including its terminator.
+    fn len(&self, block: usize) -> usize {
+        self.body.blocks[block].statements.len() + 1
+    }
+
+    /// Get the locals used at a point, along with the source code of each use.
+    fn uses(&self, point: Point) -> Vec<(Local, &'b Fragment)> {
+        let block = &self.body.blocks[point.block];
+
+        let Some(statement) = block.statements.get(point.index) else {
+            return match &block.terminator {
+                Terminator::Branch { condition, .. } => operand_uses(condition),
+                Terminator::Switch { scrutinee, .. } => operand_uses(scrutinee),
+                Terminator::Return(fragment) => vec![(Local::RETURN, fragment)],
+                Terminator::Goto(_) | Terminator::Unreachable => Vec::new(),
+            };
+        };
+
+        let StatementKind::Assign(place, rvalue) = &statement.kind else {
+            return Vec::new();
+        };
+
+        let mut uses: Vec<_> = rvalue.operands().iter().flat_map(operand_uses).collect();
+
+        if let Rvalue::Ref { place, .. } = rvalue {
+            uses.push((place.local, &place.fragment));
+        }
+
+        // Assigning to part of a local (or through a reference it holds) uses what's already in it.
+        if !place.projections.is_empty() {
+            uses.push((place.local, &place.fragment));
+        }
+
+        uses
+    }
+
+    /// Get the local that's entirely overwritten (or goes out of scope) at a point, if there is one.
+    fn def(&self, point: Point) -> Option<Local> {
+        match &self.body.blocks[point.block]
+            .statements
+            .get(point.index)?
+            .kind
+        {
+            StatementKind::Assign(place, _) if place.projections.is_empty() => Some(place.local),
+            StatementKind::StorageDead(local) => Some(*local),
+            StatementKind::Assign(..) => None,
+        }
+    }
+
+    /// Get the points that can come right after a point.
+    fn successors(&self, point: Point) -> Vec<Point> {
+        if point.index + 1 < self.len(point.block) {
+            return vec![Point {
+                block: point.block,
+                index: point.index + 1,
+            }];
+        }
+
+        (self.body.blocks[point.block]
+            .terminator
+            .successors()
+            .into_iter())
+        .map(|block| Point {
+            block: block.0,
+            index: 0,
+        })
+        .collect()
+    }
+
+    /// Compute the locals that are live before every point.
+    fn compute_liveness(&mut self) {
+        let blocks = &self.body.blocks;
+        self.live = (0..blocks.len())
+            .map(|block| vec![BTreeSet::new(); self.len(block)])
+            .collect();
+
+        let mut changed = true;
+
+        while changed {
+            changed = false;
+
+            for block in (0..blocks.len()).rev() {
+                let mut live: BTreeSet<Local> = (blocks[block].terminator.successors().iter())
+                    .flat_map(|successor| self.live[successor.0][0].iter().copied())
+                    .collect();
+
+                for index in (0..self.len(block)).rev() {
+                    let point = Point { block, index };
+
+                    if let Some(def) = self.def(point) {
+                        live.remove(&def);
+                    }
+
+                    live.extend(self.uses(point).into_iter().map(|(local, _)| local));
+
+                    if self.live[block][index] != live {
+                        self.live[block][index] = live.clone();
+                        changed = true;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Compute the loans held by every local before every point.
+    fn compute_loans(&mut self) {
+        let blocks = &self.body.blocks;
+        self.held = (0..blocks.len())
+            .map(|block| vec![LoanState::new(); self.len(block)])
+            .collect();
+
+        let mut queue = VecDeque::from([0]);
+        let mut visited = HashSet::new();
+
+        while let Some(block) = queue.pop_front() {
+            let mut state = self.held[block][0].clone();
+
+            for index in 0..self.len(block) - 1 {
+                self.transfer(Point { block, index }, &mut state);
+                self.held[block][index + 1] = state.clone();
+            }
+
+            for successor in blocks[block].terminator.successors() {
+                let entry = &mut self.held[successor.0][0];
+                let before = entry.len();
+                entry.extend(state.iter().copied());
+
+                if entry.len() != before || visited.insert(successor.0) {
+                    queue.push_back(successor.0);
+                }
+            }
+        }
+    }
+
+    /// Apply the effect of a statement to the loans held by each local.
+    fn transfer(&self, point: Point, state: &mut LoanState) {
+        let loans_of = |state: &LoanState, local: Local| -> Vec<usize> {
+            (state.range((local, 0)..=(local, usize::MAX)))
+                .map(|(_, loan)| *loan)
+                .collect()
+        };
+
+        match &self.body.blocks[point.block].statements[point.index].kind {
+            StatementKind::Assign(place, rvalue) => {
+                let incoming: Vec<usize> = match rvalue {
+                    Rvalue::Use(_) | Rvalue::Aggregate(_) => (rvalue.operands().iter())
+                        .filter_map(Operand::place)
+                        .flat_map(|operand| loans_of(state, operand.local))
+                        .collect(),
+
+                    // Borrowing through a reference depends on the loans that the reference came from.
+                    Rvalue::Ref { place, .. } => {
+                        let mut loans = vec![self.issued[&point]];
+
+                        if place.is_indirect() {
+                            loans.extend(loans_of(state, place.local));
+                        }
+
+                        loans
+                    }
+
+                    Rvalue::Operation(_) => Vec::new(),
+                };
+
+                let incoming = match self.body.locals[place.local.0].holds_references {
+                    true => incoming,
+                    false => Vec::new(),
+                };
+
+                // Assigning to part of a local adds to what it holds, rather than replacing it.
+                if place.projections.is_empty() {
+                    state.retain(|(local, _)| *local != place.local);
+                }
+
+                state.extend(incoming.into_iter().map(|loan| (place.local, loan)));
+            }
+
+            StatementKind::StorageDead(dead) => state.retain(|(local, _)| local != dead),
+        }
+    }
+
+    /// Get the places used at a point, and how they're used.
+    fn accesses(&self, point: Point) -> Vec<Access<'b>> {
+        let block = &self.body.blocks[point.block];
+
+        let Some(statement) = block.statements.get(point.index) else {
+            let operand = match &block.terminator {
+                Terminator::Branch { condition, .. } => condition,
+                Terminator::Switch { scrutinee, .. } => scrutinee,
+                _ => return Vec::new(),
+            };
+
+            return operand_access(operand).into_iter().collect();
+        };
+
+        match &statement.kind {
+            StatementKind::Assign(place, rvalue) => {
+                let mut accesses: Vec<_> = rvalue
+                    .operands()
+                    .iter()
+                    .filter_map(operand_access)
+                    .collect();
+
+                if let Rvalue::Ref { place, is_mutable } = rvalue {
+                    accesses.push(Access {
+                        place,
+                        kind: AccessKind::Borrow {
+                            is_mutable: *is_mutable,
+                        },
+                        fragment: &statement.fragment,
+                    });
+                }
+
+                accesses.push(Access {
+                    place,
+                    kind: AccessKind::Write,
+                    fragment: &place.fragment,
+                });
+
+                accesses
+            }
+
+            StatementKind::StorageDead(_) => Vec::new(),
+        }
+    }
+
+    /// Get the loans that are active before a point -- those held by a local that's live there.
+    fn active_loans(&self, point: Point) -> BTreeSet<usize> {
+        let live = &self.live[point.block][point.index];
+
+        (self.held[point.block][point.index].iter())
+            .filter(|(local, _)| live.contains(local))
+            .map(|(_, loan)| *loan)
+            .collect()
+    }
+
+    /// Find the nearest use of a reference holding the given loan, starting at a point. Returns the source of the
+    /// use, and whether it's the body returning the reference.
+    fn later_use(&self, start: Point, loan: usize) -> Option<(&'b Fragment, bool)> {
+        let mut queue = VecDeque::from([start]);
+        let mut visited = HashSet::from([start]);
+
+        while let Some(point) = queue.pop_front() {
+            let held = &self.held[point.block][point.index];
+
+            for (local, fragment) in self.uses(point) {
+                if held.contains(&(local, loan)) {
+                    let block = &self.body.blocks[point.block];
+                    let is_return = point.index == block.statements.len()
+                        && matches!(block.terminator, Terminator::Return(_));
+
+                    return Some((fragment, is_return));
+                }
+            }
+
+            for successor in self.successors(point) {
+                if visited.insert(successor) {
+                    queue.push_back(successor);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Check every point in the body for uses of places that conflict with active loans.
+    fn check(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+
+        for (block, data) in self.body.blocks.iter().enumerate() {
+            for index in 0..self.len(block) {
+                let point = Point { block, index };
+                let active = self.active_loans(point);
+
+                if active.is_empty() {
+                    continue;
+                }
+
+                // Locals going out of scope are checked separately, since they aren't places in the body.
+                let dead = match data.statements.get(index).map(|statement| &statement.kind) {
+                    Some(StatementKind::StorageDead(local)) => {
+                        Some((*local, &data.statements[index]))
+                    }
+                    _ => None,
+                };
+
+                let dead_place = dead.map(|(local, statement)| Place {
+                    local,
+                    projections: Vec::new(),
+                    fragment: statement.fragment.clone(),
+                });
+
+                let mut accesses = self.accesses(point);
+
+                if let (Some(place), Some((_, statement))) = (&dead_place, dead) {
+                    accesses.push(Access {
+                        place,
+                        kind: AccessKind::StorageDead,
+                        fragment: &statement.fragment,
+                    });
+                }
+
+                let conflict = accesses.iter().find_map(|access| {
+                    (active.iter())
+                        .find(|loan| conflicts(&self.loans[**loan], access))
+                        .map(|loan| (access, *loan))
+                });
+
+                if let Some((access, loan)) = conflict {
+                    diagnostics.push(self.report(point, access, loan));
+                }
+            }
+        }
+
+        diagnostics
+    }
+
+    /// Report a use of a place that conflicts with an active loan.
+    fn report(&self, point: Point, access: &Access, index: usize) -> Diagnostic {
+        let loan = &self.loans[index];
+        let borrowed = loan.place.fragment.as_str();
+
+        let accessed = match access.kind {
+            AccessKind::StorageDead => {
+                (self.body.locals[access.place.local.0].name.as_deref()).unwrap_or("this value")
+            }
+            _ => access.place.fragment.as_str(),
+        };
+
+        let it = match accessed == borrowed {
+            true => "it".to_owned(),
+            false => format!("`{borrowed}`"),
+        };
+
+        let (message, label) = match access.kind {
+            AccessKind::Read => (
+                format!("cannot use `{accessed}` because {it} is borrowed mutably"),
+                format!("`{accessed}` is used here"),
+            ),
+
+            AccessKind::Borrow { is_mutable: false } => (
+                format!("cannot borrow `{accessed}` because {it} is already borrowed mutably"),
+                format!("`{accessed}` is borrowed here"),
+            ),
+
+            AccessKind::Borrow { is_mutable: true } => (
+                match (loan.is_mutable, accessed == borrowed) {
+                    (true, true) => {
+                        format!("cannot borrow `{accessed}` mutably more than once at a time")
+                    }
+                    (true, false) => format!(
+                        "cannot borrow `{accessed}` mutably because `{borrowed}` is already borrowed mutably"
+                    ),
+                    (false, _) => format!(
+                        "cannot borrow `{accessed}` mutably because {it} is already borrowed"
+                    ),
+                },
+                format!("`{accessed}` is borrowed mutably here"),
+            ),
+
+            AccessKind::Write => (
+                format!("cannot assign to `{accessed}` because {it} is borrowed"),
+                format!("`{accessed}` is assigned to here"),
+            ),
+
+            AccessKind::Move => (
+                format!("cannot move out of `{accessed}` because {it} is borrowed"),
+                format!("`{accessed}` is moved here"),
+            ),
+
+            AccessKind::StorageDead => (
+                format!("`{accessed}` does not live long enough"),
+                format!("`{accessed}` goes out of scope here, while it's still borrowed"),
+            ),
+        };
+
+        let loan_label = match loan.is_mutable {
+            true => format!("`{borrowed}` is borrowed mutably here"),
+            false => format!("`{borrowed}` is borrowed here"),
+        };
+
+        let mut highlights = vec![
+            Highlight::primary(access.fragment.clone(), label),
+            Highlight::secondary(loan.fragment.clone(), loan_label),
+        ];
+
+        let later = self.later_use(point, index);
+
+        if let Some((fragment, is_return)) = later {
+            highlights.push(Highlight::secondary(
+                fragment.clone(),
+                match is_return {
+                    true => "the borrow has to last until the reference is returned here",
+                    false => "the borrow lasts until the reference is used here",
+                },
+            ));
+        }
+
+        let note = match access.kind {
+            AccessKind::Read | AccessKind::Borrow { .. } => {
+                "a value can be borrowed mutably once, or any number of times without `mut`, but not both at once"
+            }
+
+            AccessKind::StorageDead if later.is_some_and(|(_, is_return)| is_return) => {
+                "help: values declared in a function are gone once it returns, so return the value itself instead"
+            }
+
+            AccessKind::Write | AccessKind::Move | AccessKind::StorageDead => {
+                "a value can't be changed, moved, or dropped while a reference to it is still in use"
+            }
+        };
+
+        Diagnostic::error()
+            .with_message(message)
+            .with_highlights(highlights)
+            .with_notes([note])
+    }
+}
+
+/// Get the local an operand uses (if it uses one), with the source code of the use.
+fn operand_uses(operand: &Operand) -> Vec<(Local, &Fragment)> {
+    operand
+        .place()
+        .map(|place| (place.local, &place.fragment))
+        .into_iter()
+        .collect()
+}
+
+/// Get the access an operand makes to a place, if it uses one.
+fn operand_access(operand: &Operand) -> Option<Access<'_>> {
+    let (place, kind) = match operand {
+        Operand::Copy(place) => (place, AccessKind::Read),
+        Operand::Move(place) => (place, AccessKind::Move),
+        Operand::Constant => return None,
+    };
+
+    Some(Access {
+        place,
+        kind,
+        fragment: &place.fragment,
+    })
+}
+
+/// Check if an access conflicts with a loan.
+fn conflicts(loan: &Loan, access: &Access) -> bool {
+    // Shared loans only conflict with changing the place.
+    let kinds_conflict = match access.kind {
+        AccessKind::Read | AccessKind::Borrow { is_mutable: false } => loan.is_mutable,
+        _ => true,
+    };
+
+    let (borrowed, used) = (&loan.place, access.place);
+
+    if !kinds_conflict || borrowed.local != used.local {
+        return false;
+    }
+
+    // Different fields of a record don't overlap. Every other pair of steps might.
+    for pair in borrowed.projections.iter().zip(&used.projections) {
+        if let (Projection::Field(a), Projection::Field(b)) = pair {
+            if a != b {
+                return false;
+            }
+        }
+    }
+
+    // Overwriting a reference (or it going out of scope) doesn't affect what it points to.
+    let beyond = borrowed
+        .projections
+        .get(used.projections.len()..)
+        .unwrap_or_default();
+    let is_shallow = matches!(access.kind, AccessKind::Write | AccessKind::StorageDead);
+    !(is_shallow && beyond.contains(&Projection::Deref))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        analysis::testing::{check_valid_source, messages, modules},
+        reporting::Diagnostic,
+    };
+
+    /// Parse, resolve, type check, and borrow check a single module, returning the module source and the borrow
+    /// checking diagnostics.
+    fn check_source(source: &'static str) -> (String, Vec<Diagnostic>) {
+        let (module, resolution, typeck) = check_valid_source(source);
+        let diagnostics = super::check(modules(&module), &resolution, &typeck);
+        (module.matching_source.as_str().to_owned(), diagnostics)
+    }
+
+    /// Get the source code and message of each label of a diagnostic.
+    fn labels<'s>(source: &'s str, diagnostic: &'s Diagnostic) -> Vec<(&'s str, &'s str)> {
+        (diagnostic.0.labels.iter())
+            .map(|label| (&source[label.range.clone()], label.message.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_aliasing() {
+        let (source, diagnostics) = check_source(
+            "func use_ref(r: @u8) {}\n\
+            func use_mut(r: @mut u8) {}\n\
+            func main() {\n\
+                var x: u8 = 1;\n\
+                let a = @mut x; let b = @x; use_mut(a);\n\
+                let c = @mut x; let d = @mut x; use_mut(c);\n\
+                let e = @x; let f = @mut x; use_ref(e);\n\
+                let g = @mut x; let h = x; use_mut(g);\n\
+                let i = @x; let j = @x; use_ref(i); use_ref(j);\n\
+                let k = @mut x; *k = 2; let l = @mut x; *l = 3; x = 4;\n\
+            }",
+        );
+
+        assert_eq!(
+            messages(&diagnostics),
+            [
+                "cannot borrow `x` because it is already borrowed mutably",
+                "cannot borrow `x` mutably more than once at a time",
+                "cannot borrow `x` mutably because it is already borrowed",
+                "cannot use `x` because it is borrowed mutably",
+            ]
+        );
+
+        assert_eq!(
+            labels(&source, &diagnostics[0]),
+            [
+                ("@x", "`x` is borrowed here"),
+                ("@mut x", "`x` is borrowed mutably here"),
+                ("a", "the borrow lasts until the reference is used here"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_writes_and_moves() {
+        let (source, diagnostics) = check_source(
+            "record Point { x: u8, y: u8 }\n\
+            func take(p: Point) {}\n\
+            func main(p: Point, q: Point) -> u8 {\n\
+                var x: u8 = 1;\n\
+                let r = @x; x = 2; let s = *r;\n\
+                let a = @p.x; take(p); let b = *a;\n\
+                var t = q; let c = @mut t.x; t.y = 1; *c = 2;\n\
+                if s == 1 { x = 3; }\n\
+                while s == 2 { let d = @mut x; *d = 4; }\n\
+                b\n\
+            }",
+        );
+
+        assert_eq!(
+            messages(&diagnostics),
+            [
+                "cannot assign to `x` because it is borrowed",
+                "cannot move out of `p` because `p.x` is borrowed",
+            ]
+        );
+
+        assert_eq!(
+            labels(&source, &diagnostics[1]),
+            [
+                ("p", "`p` is moved here"),
+                ("@p.x", "`p.x` is borrowed here"),
+                ("*a", "the borrow lasts until the reference is used here"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_outliving() {
+        let (source, diagnostics) = check_source(
+            "func first(r: @u8) -> @u8 { r }\n\
+            func local() -> @u8 { let x: u8 = 1; first(@x) }\n\
+            func param(x: u8) -> @u8 { @x }\n\
+            func scope() -> u8 {\n\
+                let y: u8 = 1; var r = @y;\n\
+                { let z: u8 = 2; r = @z; };\n\
+                let v = *r; v\n\
+            }\n\
+            func fine(r: @mut u8) -> @mut u8 { let s = @mut *r; s }",
+        );
+
+        assert_eq!(
+            messages(&diagnostics),
+            [
+                "`x` does not live long enough",
+                "`x` does not live long enough",
+                "`z` does not live long enough",
+            ]
+        );
+
+        assert_eq!(
+            labels(&source, &diagnostics[0]),
+            [
+                ("}", "`x` goes out of scope here, while it's still borrowed"),
+                ("@x", "`x` is borrowed here"),
+                ("@u8", "the borrow has to last until the reference is returned here"),
+            ]
+        );
+
+        assert_eq!(
+            labels(&source, &diagnostics[2])[2],
+            ("*r", "the borrow lasts until the reference is used here")
+        );
+    }
+
+    #[test]
+    fn test_values_returned_through_borrows() {
+        let (_, diagnostics) = check_source(
+            "record Holder { v: u8 }\n\
+            func g(r: @u8) -> u8 { *r }\n\
+            func field() -> u8 { let x = Holder { v: 1 }; let s = @x; s.v }\n\
+            func call() -> u8 { let x: u8 = 1; g(@x) }\n\
+            func closure() -> u8 { let f = func() -> u8 { let y: u8 = 2; g(@y) }; f() }",
+        );
+
+        assert!(diagnostics.is_empty(), "{:?}", messages(&diagnostics));
+    }
+
+    #[test]
+    fn test_reborrows_and_control_flow() {
+        let (_, diagnostics) = check_source(
+            "func main(r: @mut u8, flag: bool) {\n\
+                let s = @mut *r; *r = 1; *s = 2;\n\
+                var x: u8 = 0;\n\
+                let t = if flag { @mut x } else { @mut x }; *t = 1;\n\
+                let u = @mut x;\n\
+                let f = func() { *u = 3; };\n\
+                x = 5; f();\n\
+                match x { 0 => { let v = @mut x; *v = 1; }, _ => {} }\n\
+            }",
+        );
+
+        assert_eq!(
+            messages(&diagnostics),
+            [
+                "cannot assign to `*r` because it is borrowed",
+                "cannot assign to `x` because it is borrowed",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_method_receivers() {
+        let (_, diagnostics) = check_source(
+            "record Counter { count: u64 }\n\
+            impl Counter { func bump(self: @mut Self) { self.count = self.count + 1; } func get(self: @Self) -> u64 { self.count } }\n\
+            func main() {\n\
+                var c = Counter { count: 0 };\n\
+                let r = @c; c.bump(); r.get();\n\
+                c.bump(); c.get();\n\
+            }",
+        );
+
+        assert_eq!(
+            messages(&diagnostics),
+            ["cannot borrow `c` mutably because it is already borrowed"]
+        );
+    }
+}
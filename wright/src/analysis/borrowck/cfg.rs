@@ -0,0 +1,882 @@
+//! A control-flow graph of a function body, which borrow checking runs on.
+//!
+//! Each body is lowered into [basic blocks](BasicBlock) of simple statements that read, move, borrow, and assign
+//! [places](Place) -- a local variable, or a path through the fields of one and the references it holds. Every
+//! expression with control flow (`if`, `match`, loops) becomes branches between blocks, and every intermediate
+//! value is stored in a temporary, so the order that things happen in is explicit. Locals are marked as dead when
+//! the block that declares them ends, which is where any borrows of them have to end too.
+
+use crate::{
+    analysis::{
+        resolve::{DefId, DefKind, Resolution},
+        typeck::{Receiver, TypeckResults, ty::Ty},
+    },
+    ast::{
+        decl::func::FuncDecl,
+        expr::{Atom, CallExpr, ClosureExpr, Expr, FieldExpr, MatchExpr, UnaryOperation},
+        identifier::Identifier,
+        pattern::Pattern,
+        stmt::{Block, Stmt},
+    },
+    source_tracking::fragment::Fragment,
+};
+use std::collections::HashMap;
+
+/// A local variable in a [Body] -- a binding, a parameter, or a temporary.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Local(pub usize);
+
+impl Local {
+    /// The local that holds the value a body returns.
+    pub const RETURN: Local = Local(0);
+}
+
+/// The index of a [BasicBlock] in a [Body].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BlockId(pub usize);
+
+/// A lowered function (or closure) body.
+#[derive(Debug)]
+pub struct Body {
+    /// The locals of the body. The first is always [Local::RETURN].
+    pub locals: Vec<LocalDecl>,
+    /// The basic blocks of the body. Execution starts at the first.
+    pub blocks: Vec<BasicBlock>,
+}
+
+/// The declaration of a [Local].
+#[derive(Debug)]
+pub struct LocalDecl {
+    /// The name of the binding or parameter, or [None] for temporaries (and the return value).
+    pub name: Option<String>,
+    /// Whether values of the local's type can contain references. Loans never flow into locals that can't.
+    pub holds_references: bool,
+}
+
+/// A straight-line sequence of statements, ending with a [Terminator] that says where to go next.
+#[derive(Debug)]
+pub struct BasicBlock {
+    /// The statements of the block, in order.
+    pub statements: Vec<Statement>,
+    /// What happens after the statements.
+    pub terminator: Terminator,
+}
+
+/// A single step in a [BasicBlock].
+#[derive(Debug)]
+pub struct Statement {
+    /// What the statement does.
+    pub kind: StatementKind,
+    /// The source code the statement comes from.
+    pub fragment: Fragment,
+}
+
+/// The different things a [Statement] can do.
+#[derive(Debug)]
+pub enum StatementKind {
+    /// Compute a value and store it in a place.
+    Assign(Place, Rvalue),
+    /// A local goes out of scope, so it can't be used (or borrowed) anymore.
+    StorageDead(Local),
+}
+
+/// How control leaves a [BasicBlock].
+#[derive(Debug)]
+pub enum Terminator {
+    /// Continue with another block.
+    Goto(BlockId),
+    /// Continue with one of two blocks, depending on a `bool`.
+    Branch {
+        /// The condition.
+        condition: Operand,
+        /// The block to continue with if the condition is `true`.
+        then: BlockId,
+        /// The block to continue with if the condition is `false`.
+        otherwise: BlockId,
+    },
+    /// Continue with one of several blocks, depending on a value being matched.
+    Switch {
+        /// The value being matched.
+        scrutinee: Operand,
+        /// The blocks that can be continued with.
+        targets: Vec<BlockId>,
+    },
+    /// Return [Local::RETURN] from the body. The fragment is where the value is returned -- the return type of
+    /// the function, if it has one.
+    Return(Fragment),
+    /// Control never reaches the end of this block.
+    Unreachable,
+}
+
+impl Terminator {
+    /// Get the blocks that control can continue with after this terminator.
+    pub fn successors(&self) -> Vec<BlockId> {
+        match self {
+            Terminator::Goto(target) => vec![*target],
+            Terminator::Branch {
+                then, otherwise, ..
+            } => vec![*then, *otherwise],
+            Terminator::Switch { targets, .. } => targets.clone(),
+            Terminator::Return(_) | Terminator::Unreachable => Vec::new(),
+        }
+    }
+}
+
+/// A location in memory: a local, or a path through its fields and the references it holds.
+#[derive(Clone, Debug)]
+pub struct Place {
+    /// The local the place starts at.
+    pub local: Local,
+    /// The steps taken from the local, in order.
+    pub projections: Vec<Projection>,
+    /// The source code of the place.
+    pub fragment: Fragment,
+}
+
+impl Place {
+    /// Check if this place is behind a reference, rather than being (part of) its local.
+    pub fn is_indirect(&self) -> bool {
+        self.projections.contains(&Projection::Deref)
+    }
+}
+
+/// A step in a [Place].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Projection {
+    /// Follow a reference.
+    Deref,
+    /// A field of a record.
+    Field(String),
+    /// A variant of a union. Every variant of a union overlaps with the others.
+    Variant(String),
+}
+
+/// A value used by an [Rvalue] or [Terminator].
+#[derive(Clone, Debug)]
+pub enum Operand {
+    /// Read a place without moving out of it -- copying it if the value is used.
+    Copy(Place),
+    /// Move the value out of a place.
+    Move(Place),
+    /// A value that doesn't depend on any place, such as a literal or a function.
+    Constant,
+}
+
+impl Operand {
+    /// Get the place that this operand reads, if it reads one.
+    pub fn place(&self) -> Option<&Place> {
+        match self {
+            Operand::Copy(place) | Operand::Move(place) => Some(place),
+            Operand::Constant => None,
+        }
+    }
+}
+
+/// A value computed by an assignment.
+#[derive(Debug)]
+pub enum Rvalue {
+    /// Just the value of an operand.
+    Use(Operand),
+    /// A reference to a place, i.e. `@x` or `@mut x`.
+    Ref {
+        /// The place being borrowed.
+        place: Place,
+        /// Whether the borrow is mutable.
+        is_mutable: bool,
+    },
+    /// A new value that holds the values of the operands, and so any references in them -- a record, a closure (with
+    /// the values it captures), or the result of a call (which might return any reference passed to it).
+    Aggregate(Vec<Operand>),
+    /// An arithmetic, logical, or comparison operation (or an `is` test), which doesn't produce a reference.
+    Operation(Vec<Operand>),
+}
+
+impl Rvalue {
+    /// Get the operands of this rvalue.
+    pub fn operands(&self) -> &[Operand] {
+        match self {
+            Rvalue::Use(operand) => std::slice::from_ref(operand),
+            Rvalue::Aggregate(operands) | Rvalue::Operation(operands) => operands,
+            Rvalue::Ref { .. } => &[],
+        }
+    }
+}
+
+/// Lower the body of a function into a [Body]. Closures aren't part of it -- they're returned, to be lowered with
+/// [lower_closure].
+pub fn lower_function<'a>(
+    func: &'a FuncDecl,
+    body: &'a Block,
+    res: &'a Resolution,
+    typeck: &'a TypeckResults,
+) -> (Body, Vec<&'a ClosureExpr>) {
+    let params = func.params.iter().map(|param| &param.name);
+    let returned = func.return_ty.as_ref().map(|ty| ty.matching_source());
+    let ty = (res.declared(&func.name.fragment)).and_then(|def| typeck.type_of_def(def));
+    Builder::new(res, typeck, ty).lower(params, body, returned)
+}
+
+/// Lower the body of a closure into a [Body], returning any closures inside of it. Values captured from outside of
+/// the closure are borrow checked where the closure is created, not inside of it.
+pub fn lower_closure<'a>(
+    closure: &'a ClosureExpr,
+    res: &'a Resolution,
+    typeck: &'a TypeckResults,
+) -> (Body, Vec<&'a ClosureExpr>) {
+    let params = closure.params.iter().map(|param| &param.name);
+    let returned = closure.return_ty.as_ref().map(|ty| ty.matching_source());
+    let ty = typeck.type_of_expr(&closure.matching_source);
+    Builder::new(res, typeck, ty).lower(params, &closure.body, returned)
+}
+
+/// Builds a [Body] from the AST of a function or closure.
+struct Builder<'a> {
+    /// The resolution of the body's names.
+    res: &'a Resolution,
+    /// The types of the body's expressions.
+    typeck: &'a TypeckResults,
+    /// The body being built.
+    body: Body,
+    /// The block that statements are added to.
+    current: BlockId,
+    /// The local of each binding and parameter.
+    locals: HashMap<DefId, Local>,
+    /// The bindings declared in each enclosing block, which go out of scope when it ends.
+    scopes: Vec<Vec<Local>>,
+    /// The closures found in the body.
+    closures: Vec<&'a ClosureExpr>,
+}
+
+impl<'a> Builder<'a> {
+    /// Make a builder for a new body, with the type of the function or closure if it's known. The return value only
+    /// holds references if the function's return type can.
+    fn new(res: &'a Resolution, typeck: &'a TypeckResults, ty: Option<&Ty>) -> Self {
+        let mut builder = Builder {
+            res,
+            typeck,
+            body: Body {
+                locals: vec![LocalDecl {
+                    name: None,
+                    holds_references: true,
+                }],
+                blocks: vec![BasicBlock {
+                    statements: Vec::new(),
+                    terminator: Terminator::Unreachable,
+                }],
+            },
+            current: BlockId(0),
+            locals: HashMap::new(),
+            scopes: Vec::new(),
+            closures: Vec::new(),
+        };
+
+        if let Some(Ty::Function { ret, .. }) = ty {
+            builder.body.locals[0].holds_references =
+                builder.holds_references(ret, &mut Vec::new());
+        }
+
+        builder
+    }
+
+    /// Lower a body with the given parameters into the return value, then return. The parameters go out of scope
+    /// at the end of the body.
+    fn lower(
+        mut self,
+        params: impl Iterator<Item = &'a Identifier>,
+        body: &'a Block,
+        returned: Option<&Fragment>,
+    ) -> (Body, Vec<&'a ClosureExpr>) {
+        self.scopes.push(Vec::new());
+
+        for param in params {
+            self.declare(param);
+        }
+
+        let ret = Place {
+            local: Local::RETURN,
+            projections: Vec::new(),
+            fragment: body.matching_source.clone(),
+        };
+
+        self.block_into(body, ret);
+        self.end_scope(&body.matching_source);
+
+        let returned = match (returned, &body.tail) {
+            (Some(ty), _) => ty.clone(),
+            (None, Some(tail)) => tail.matching_source().clone(),
+            (None, None) => body.matching_source.clone(),
+        };
+
+        self.terminate(Terminator::Return(returned));
+        (self.body, self.closures)
+    }
+
+    /// Declare a local for a binding or parameter in the innermost scope.
+    fn declare(&mut self, name: &Identifier) -> Option<Local> {
+        let def = self.res.declared(&name.fragment)?;
+        let ty = self.typeck.type_of_binding(&name.fragment);
+        let local = self.new_local(Some(name.fragment.as_str().to_owned()), ty);
+        self.locals.insert(def, local);
+        self.scopes.last_mut().unwrap().push(local);
+        Some(local)
+    }
+
+    /// Make a new local, with its type if it's known.
+    fn new_local(&mut self, name: Option<String>, ty: Option<&Ty>) -> Local {
+        let holds_references = ty.is_none_or(|ty| self.holds_references(ty, &mut Vec::new()));
+
+        self.body.locals.push(LocalDecl {
+            name,
+            holds_references,
+        });
+
+        Local(self.body.locals.len() - 1)
+    }
+
+    /// Make a new temporary, with a place for the given expression.
+    fn temp(&mut self, fragment: &Fragment) -> Place {
+        Place {
+            local: self.new_local(None, self.typeck.type_of_expr(fragment)),
+            projections: Vec::new(),
+            fragment: fragment.clone(),
+        }
+    }
+
+    /// Make a new (empty) block.
+    fn new_block(&mut self) -> BlockId {
+        self.body.blocks.push(BasicBlock {
+            statements: Vec::new(),
+            terminator: Terminator::Unreachable,
+        });
+
+        BlockId(self.body.blocks.len() - 1)
+    }
+
+    /// Add a statement to the current block.
+    fn push(&mut self, kind: StatementKind, fragment: &Fragment) {
+        self.body.blocks[self.current.0].statements.push(Statement {
+            kind,
+            fragment: fragment.clone(),
+        });
+    }
+
+    /// Add an assignment to the current block.
+    fn assign(&mut self, place: Place, rvalue: Rvalue, fragment: &Fragment) {
+        self.push(StatementKind::Assign(place, rvalue), fragment);
+    }
+
+    /// End the current block with the given terminator.
+    fn terminate(&mut self, terminator: Terminator) {
+        self.body.blocks[self.current.0].terminator = terminator;
+    }
+
+    /// End the current block by going to `target`, and continue adding statements to `next`.
+    fn goto(&mut self, target: BlockId, next: BlockId) {
+        self.terminate(Terminator::Goto(target));
+        self.current = next;
+    }
+
+    /// End the innermost scope at the end of the given source (i.e. the closing `}` of a block), marking its
+    /// bindings as dead in reverse order.
+    fn end_scope(&mut self, source: &Fragment) {
+        let (_, end) = source.split_at(source.len().saturating_sub(1));
+
+        for local in self.scopes.pop().unwrap().into_iter().rev() {
+            self.push(StatementKind::StorageDead(local), &end);
+        }
+    }
+
+    /// Lower a block, storing its value in `dest`.
+    fn block_into(&mut self, block: &'a Block, dest: Place) {
+        self.scopes.push(Vec::new());
+
+        for stmt in &block.stmts {
+            match stmt {
+                Stmt::Let(let_stmt) => {
+                    let value = self.operand(&let_stmt.value);
+
+                    if let Some(local) = self.declare(&let_stmt.name) {
+                        let place = Place {
+                            local,
+                            projections: Vec::new(),
+                            fragment: let_stmt.name.fragment.clone(),
+                        };
+
+                        self.assign(place, Rvalue::Use(value), &let_stmt.matching_source);
+                    }
+                }
+
+                Stmt::Assign(assign) => {
+                    let value = self.operand(&assign.value);
+                    let place = self.place(&assign.target);
+
+                    // Compound assignments read the place before writing to it.
+                    let rvalue = match assign.op {
+                        Some(_) => Rvalue::Operation(vec![Operand::Copy(place.clone()), value]),
+                        None => Rvalue::Use(value),
+                    };
+
+                    self.assign(place, rvalue, &assign.matching_source);
+                }
+
+                Stmt::Expr(expr_stmt) => {
+                    let temp = self.temp(&expr_stmt.expr.matching_source().clone());
+                    self.expr_into(&expr_stmt.expr, temp);
+                }
+            }
+        }
+
+        match &block.tail {
+            Some(tail) => self.expr_into(tail, dest),
+            None => self.assign(dest, Rvalue::Use(Operand::Constant), &block.matching_source),
+        }
+
+        self.end_scope(&block.matching_source);
+    }
+
+    /// Lower an expression, storing its value in `dest`.
+    fn expr_into(&mut self, expr: &'a Expr, dest: Place) {
+        let fragment = expr.matching_source();
+
+        let rvalue = match expr {
+            Expr::Parens(parens) => return self.expr_into(&parens.inner, dest),
+            Expr::Block(block) => return self.block_into(block, dest),
+            Expr::Unsafe(unsafe_expr) => return self.block_into(&unsafe_expr.body, dest),
+
+            Expr::If(if_expr) => {
+                let condition = self.operand(&if_expr.condition);
+                let then = self.new_block();
+                let otherwise = self.new_block();
+                let join = self.new_block();
+
+                self.terminate(Terminator::Branch {
+                    condition,
+                    then,
+                    otherwise,
+                });
+
+                self.current = then;
+                self.block_into(&if_expr.then_block, dest.clone());
+                self.goto(join, otherwise);
+
+                match &if_expr.else_branch {
+                    Some(else_branch) => self.expr_into(else_branch, dest),
+                    None => self.assign(dest, Rvalue::Use(Operand::Constant), fragment),
+                }
+
+                self.goto(join, join);
+                return;
+            }
+
+            Expr::Match(match_expr) => return self.match_into(match_expr, dest),
+
+            Expr::While(while_expr) => {
+                let header = self.new_block();
+                let body = self.new_block();
+                let exit = self.new_block();
+                self.goto(header, header);
+
+                let condition = self.operand(&while_expr.condition);
+                self.terminate(Terminator::Branch {
+                    condition,
+                    then: body,
+                    otherwise: exit,
+                });
+
+                self.current = body;
+                let temp = self.temp(&while_expr.body.matching_source);
+                self.block_into(&while_expr.body, temp);
+                self.goto(header, exit);
+                Rvalue::Use(Operand::Constant)
+            }
+
+            Expr::For(for_expr) => {
+                let start = self.operand(&for_expr.range.start);
+                let end = self.operand(&for_expr.range.end);
+                let counter = self.temp(&for_expr.range.matching_source);
+                let range = &for_expr.range.matching_source;
+                self.assign(counter.clone(), Rvalue::Operation(vec![start, end]), range);
+
+                let header = self.new_block();
+                let body = self.new_block();
+                let exit = self.new_block();
+                self.goto(header, header);
+
+                self.terminate(Terminator::Branch {
+                    condition: Operand::Copy(counter.clone()),
+                    then: body,
+                    otherwise: exit,
+                });
+
+                // The binding is a new local on every iteration.
+                self.current = body;
+                self.scopes.push(Vec::new());
+
+                if let Some(local) = self.declare(&for_expr.binding) {
+                    let place = Place {
+                        local,
+                        projections: Vec::new(),
+                        fragment: for_expr.binding.fragment.clone(),
+                    };
+
+                    let value = Rvalue::Use(Operand::Copy(counter.clone()));
+                    self.assign(place, value, &for_expr.binding.fragment);
+                }
+
+                let temp = self.temp(&for_expr.body.matching_source);
+                self.block_into(&for_expr.body, temp);
+                self.end_scope(&for_expr.body.matching_source);
+
+                let step = Rvalue::Operation(vec![Operand::Copy(counter.clone())]);
+                self.assign(counter, step, range);
+                self.goto(header, exit);
+                Rvalue::Use(Operand::Constant)
+            }
+
+            Expr::Unary(unary)
+                if matches!(
+                    unary.op,
+                    UnaryOperation::Reference | UnaryOperation::MutableReference
+                ) =>
+            {
+                Rvalue::Ref {
+                    place: self.place(&unary.operand),
+                    is_mutable: unary.op == UnaryOperation::MutableReference,
+                }
+            }
+
+            Expr::Unary(unary) if unary.op != UnaryOperation::Dereference => {
+                Rvalue::Operation(vec![self.operand(&unary.operand)])
+            }
+
+            Expr::Binary(binary) => {
+                let lhs = self.operand(&binary.lhs);
+                let rhs = self.operand(&binary.rhs);
+                Rvalue::Operation(vec![lhs, rhs])
+            }
+
+            // Testing the variant of a value only looks at it.
+            Expr::Is(is_expr) => Rvalue::Operation(vec![Operand::Copy(self.place(&is_expr.value))]),
+
+            Expr::Call(call) if self.typeck.method_call(&call.matching_source).is_some() => {
+                let Expr::Field(field) = &*call.callee else {
+                    unreachable!("method calls call a field");
+                };
+
+                let mut operands = vec![Operand::Constant, self.receiver(call, field)];
+                operands.extend(call.args.iter().map(|arg| self.operand(arg)));
+                Rvalue::Aggregate(operands)
+            }
+
+            Expr::Call(call) => {
+                let mut operands = vec![self.operand(&call.callee)];
+                operands.extend(call.args.iter().map(|arg| self.operand(arg)));
+                Rvalue::Aggregate(operands)
+            }
+
+            Expr::Record(record) => Rvalue::Aggregate(
+                (record.fields.iter())
+                    .map(|field| self.operand(&field.value))
+                    .collect(),
+            ),
+
+            Expr::Closure(closure) => {
+                self.closures.push(closure);
+                Rvalue::Aggregate(self.captures(closure))
+            }
+
+            // Places and constants.
+            _ => Rvalue::Use(self.operand(expr)),
+        };
+
+        self.assign(dest, rvalue, fragment);
+    }
+
+    /// Lower a `match`, storing the value of the arm that's taken in `dest`.
+    fn match_into(&mut self, match_expr: &'a MatchExpr, dest: Place) {
+        let scrutinee = self.place(&match_expr.scrutinee);
+        let arms: Vec<_> = match_expr.arms.iter().map(|_| self.new_block()).collect();
+        let join = self.new_block();
+
+        self.terminate(Terminator::Switch {
+            scrutinee: Operand::Copy(scrutinee.clone()),
+            targets: arms.clone(),
+        });
+
+        for (index, arm) in match_expr.arms.iter().enumerate() {
+            self.current = arms[index];
+            self.scopes.push(Vec::new());
+            self.bind_pattern(&arm.pattern, &scrutinee);
+
+            // If the guard is `false`, the next arm is tried.
+            if let Some(guard) = &arm.guard {
+                let condition = self.operand(guard);
+                let body = self.new_block();
+                let otherwise = match arms.get(index + 1) {
+                    Some(next) => *next,
+                    None => self.new_block(),
+                };
+
+                self.terminate(Terminator::Branch {
+                    condition,
+                    then: body,
+                    otherwise,
+                });
+
+                self.current = body;
+            }
+
+            self.expr_into(&arm.body, dest.clone());
+            self.end_scope(&arm.matching_source);
+            self.goto(join, join);
+        }
+    }
+
+    /// Bind the names in a pattern to the parts of the matched place.
+    fn bind_pattern(&mut self, pattern: &'a Pattern, place: &Place) {
+        match pattern {
+            Pattern::Binding(name) => {
+                let Some(local) = self.declare(name) else {
+                    return;
+                };
+
+                let binding = Place {
+                    local,
+                    projections: Vec::new(),
+                    fragment: name.fragment.clone(),
+                };
+
+                let mut place = place.clone();
+                place.fragment = name.fragment.clone();
+                let value = match self.typeck.type_of_pattern(&name.fragment) {
+                    Some(ty) if !self.is_copy(ty) => Operand::Move(place),
+                    _ => Operand::Copy(place),
+                };
+
+                self.assign(binding, Rvalue::Use(value), &name.fragment);
+            }
+
+            Pattern::Record(record) => {
+                let is_union = (self.typeck.type_of_pattern(&record.matching_source))
+                    .and_then(|ty| self.typeck.fields(ty))
+                    .is_some_and(|(is_union, _)| is_union);
+
+                for field in &record.fields {
+                    let name = field.name.fragment.as_str().to_owned();
+                    let mut field_place = place.clone();
+
+                    field_place.projections.push(match is_union {
+                        true => Projection::Variant(name),
+                        false => Projection::Field(name),
+                    });
+
+                    self.bind_pattern(&field.pattern, &field_place);
+                }
+            }
+
+            // Names can't be bound inside of `|` patterns.
+            Pattern::Wildcard(_)
+            | Pattern::Integer(_)
+            | Pattern::Boolean(_)
+            | Pattern::Range(_)
+            | Pattern::Variant(_)
+            | Pattern::Or(_) => {}
+        }
+    }
+
+    /// Get the values that a closure captures -- every local from outside of it that it uses.
+    fn captures(&mut self, closure: &'a ClosureExpr) -> Vec<Operand> {
+        let mut captures = Vec::new();
+        let mut visit = |expr: &'a Expr| self.capture(expr, &mut captures);
+        closure.body.for_each_expr(&mut visit);
+        captures
+    }
+
+    /// Add the locals from outside of a closure that an expression (inside of the closure) uses to its captures.
+    fn capture(&self, expr: &'a Expr, captures: &mut Vec<Operand>) {
+        if let Some(place) = self.local_of(expr) {
+            captures.push(Operand::Copy(place));
+        }
+
+        expr.for_each_child(&mut |child| self.capture(child, captures));
+    }
+
+    /// Lower an expression that's used as a value.
+    fn operand(&mut self, expr: &'a Expr) -> Operand {
+        if let Some(local) = self.local_of(expr) {
+            return self.use_place(expr, local);
+        }
+
+        match expr {
+            Expr::Parens(parens) => self.operand(&parens.inner),
+
+            Expr::Atom(_) | Expr::Path(_) => Operand::Constant,
+
+            Expr::Field(_) => {
+                let place = self.place(expr);
+                self.use_place(expr, place)
+            }
+
+            Expr::Unary(unary) if unary.op == UnaryOperation::Dereference => {
+                let place = self.place(expr);
+                self.use_place(expr, place)
+            }
+
+            _ => {
+                let temp = self.temp(expr.matching_source());
+                self.expr_into(expr, temp.clone());
+                Operand::Move(temp)
+            }
+        }
+    }
+
+    /// Lower the receiver of a method call, borrowing it or following references to it as the method's `self`
+    /// parameter requires.
+    fn receiver(&mut self, call: &CallExpr, field: &'a FieldExpr) -> Operand {
+        let method_call = self.typeck.method_call(&call.matching_source).unwrap();
+
+        match method_call.receiver {
+            Receiver::Value => self.operand(&field.base),
+
+            Receiver::Borrow { is_mutable } => {
+                let place = self.place(&field.base);
+                let fragment = field.base.matching_source();
+                let ty = (self.typeck.type_of_expr(fragment))
+                    .map(|ty| Ty::reference(ty.clone(), is_mutable));
+
+                let temp = Place {
+                    local: self.new_local(None, ty.as_ref()),
+                    projections: Vec::new(),
+                    fragment: fragment.clone(),
+                };
+
+                self.assign(temp.clone(), Rvalue::Ref { place, is_mutable }, fragment);
+                Operand::Move(temp)
+            }
+
+            Receiver::Deref => {
+                let mut place = self.place(&field.base);
+                let mut ty = self.typeck.type_of_expr(field.base.matching_source());
+
+                while let Some(Ty::Reference { target, .. }) = ty {
+                    place.projections.push(Projection::Deref);
+                    ty = Some(target);
+                }
+
+                match ty {
+                    Some(ty) if !self.is_copy(ty) => Operand::Move(place),
+                    _ => Operand::Copy(place),
+                }
+            }
+        }
+    }
+
+    /// Use the value in a place, moving out of it unless its type can be copied.
+    fn use_place(&self, expr: &Expr, place: Place) -> Operand {
+        match self.typeck.type_of_expr(expr.matching_source()) {
+            Some(ty) if !self.is_copy(ty) => Operand::Move(place),
+            _ => Operand::Copy(place),
+        }
+    }
+
+    /// Get the place for a local, if the expression names one.
+    fn local_of(&self, expr: &Expr) -> Option<Place> {
+        let name = match expr {
+            Expr::Atom(Atom::Identifier(identifier)) => &identifier.fragment,
+            Expr::Path(path) => &path.full_path,
+            _ => return None,
+        };
+
+        let local = *self.locals.get(&self.res.resolved(name)?)?;
+
+        Some(Place {
+            local,
+            projections: Vec::new(),
+            fragment: name.clone(),
+        })
+    }
+
+    /// Lower an expression that's used as a place -- the target of an assignment, or something being borrowed.
+    /// Expressions that aren't places are stored in a temporary.
+    fn place(&mut self, expr: &'a Expr) -> Place {
+        if let Some(place) = self.local_of(expr) {
+            return place;
+        }
+
+        let mut place = match expr {
+            Expr::Parens(parens) => self.place(&parens.inner),
+
+            // Fields are accessed through any number of references.
+            Expr::Field(field) => {
+                let mut place = self.place(&field.base);
+                let mut ty = self.typeck.type_of_expr(field.base.matching_source());
+
+                while let Some(Ty::Reference { target, .. }) = ty {
+                    place.projections.push(Projection::Deref);
+                    ty = Some(target);
+                }
+
+                let name = field.field.fragment.as_str().to_owned();
+
+                place
+                    .projections
+                    .push(match ty.and_then(|ty| self.typeck.fields(ty)) {
+                        Some((true, _)) => Projection::Variant(name),
+                        _ => Projection::Field(name),
+                    });
+
+                place
+            }
+
+            Expr::Unary(unary) if unary.op == UnaryOperation::Dereference => {
+                let mut place = self.place(&unary.operand);
+                place.projections.push(Projection::Deref);
+                place
+            }
+
+            _ => {
+                let temp = self.temp(expr.matching_source());
+                self.expr_into(expr, temp.clone());
+                temp
+            }
+        };
+
+        place.fragment = expr.matching_source().clone();
+        place
+    }
+
+    /// Check if values of a type can contain references. The records and unions that are already being checked are
+    /// in `visiting`, so recursive types terminate.
+    fn holds_references(&self, ty: &Ty, visiting: &mut Vec<DefId>) -> bool {
+        match ty {
+            Ty::Atomic(_) | Ty::Str | Ty::Void | Ty::Error | Ty::Integer(_) => false,
+            Ty::Named { def, .. } if self.res.def(*def).kind == DefKind::Enum => false,
+            Ty::Named { def, .. } if visiting.contains(def) => false,
+
+            Ty::Named { def, .. } => match self.typeck.fields(ty) {
+                Some((_, fields)) => {
+                    visiting.push(*def);
+                    let holds = (fields.iter()).any(|(_, ty)| self.holds_references(ty, visiting));
+                    visiting.pop();
+                    holds
+                }
+
+                // Abstract types could hold anything.
+                None => true,
+            },
+
+            Ty::Reference { .. } | Ty::Param(_) | Ty::Function { .. } | Ty::Infer(_) => true,
+        }
+    }
+
+    /// Check if values of a type are copied when they're used, rather than moved. Mutable references, records,
+    /// unions, and values of generic or abstract types are moved.
+    fn is_copy(&self, ty: &Ty) -> bool {
+        match ty {
+            Ty::Reference { is_mutable, .. } => !is_mutable,
+            Ty::Named { def, .. } => self.res.def(*def).kind == DefKind::Enum,
+            Ty::Param(_) => false,
+            _ => true,
+        }
+    }
+}
@@ -0,0 +1,40 @@
+//! Functions built into the compiler. These are defined in the `wright` module during [name resolution], so they
+//! can be used from every package (i.e. `wright::io::println(1)`, or `use wright::io::println;`).
+//!
+//! [name resolution]: crate::analysis::resolve
+
+/// A function built into the compiler.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Builtin {
+    /// `wright::io::print` -- write a value to standard output.
+    Print,
+    /// `wright::io::println` -- write a value to standard output, followed by a newline.
+    Println,
+}
+
+impl Builtin {
+    /// Every builtin function.
+    pub const ALL: [Builtin; 2] = [Builtin::Print, Builtin::Println];
+
+    /// Get the name of this function.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Builtin::Print => "print",
+            Builtin::Println => "println",
+        }
+    }
+
+    /// Get the path of the module this function is defined in.
+    pub const fn module(self) -> &'static [&'static str] {
+        match self {
+            Builtin::Print | Builtin::Println => &["wright", "io"],
+        }
+    }
+
+    /// Check if calling this function performs input or output, which `pure` functions can't do.
+    pub const fn is_io(self) -> bool {
+        match self {
+            Builtin::Print | Builtin::Println => true,
+        }
+    }
+}
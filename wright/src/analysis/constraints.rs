@@ -0,0 +1,293 @@
+//! Constraint verification -- checking that values used as constrained types (i.e. `u8 constrain in_range`)
+//! satisfy their constraints.
+//!
+//! Verification runs after [type checking], and is an abstract interpretation of every function body: the possible
+//! values of each integer expression are tracked as an [Interval], through arithmetic, `let` bindings, `for` loops
+//! over ranges, and the conditions of `if` and `while` (which narrow the values of the bindings they compare).
+//!
+//! The values a constraint allows are worked out from its body (a `constraint` declaration's predicate, or a `pure`
+//! function's body), which must be a single condition comparing its parameter against integer literals. Constraint
+//! declarations also allow only the values every constraint they're built from allows. Then, wherever a value is
+//! passed as an argument, bound with a type annotation, put in a record field, or returned as a constrained type:
+//! - If every value it could have is allowed, the constraint is proven and nothing more is done.
+//! - If it can never satisfy the constraint (or is known to take on a value that doesn't), that's an error.
+//! - Otherwise, a warning is reported and a [RuntimeCheck] is recorded for the value, as are values checked against
+//!   constraints that can't be analyzed. Nothing inserts these checks into the compiled program yet, so for now
+//!   such values are unverified.
+//!
+//! Diagnostics show the values that break the constraint, along with the values the constraint allows.
+//!
+//! [type checking]: crate::analysis::typeck
+//! [Interval]: interval::Interval
+
+use crate::{
+    analysis::{
+        resolve::{DefId, Resolution},
+        typeck::TypeckResults,
+    },
+    ast::module::Module,
+    reporting::Diagnostic,
+    source_tracking::fragment::Fragment,
+};
+
+pub mod interval;
+mod predicate;
+mod verifier;
+
+/// A value that couldn't be proven to satisfy a constraint at compile time, and would have to be checked when the
+/// program runs. These are only recorded -- neither MIR lowering nor the backends insert them yet.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RuntimeCheck {
+    /// The expression producing the value.
+    pub value: Fragment,
+
+    /// The constraint function the value must satisfy.
+    pub constraint: DefId,
+}
+
+/// The results of verifying constraints in a package.
+#[derive(Debug, Default)]
+pub struct ConstraintResults {
+    /// The values that would have to be checked at runtime, in source order.
+    pub runtime_checks: Vec<RuntimeCheck>,
+
+    /// Errors for values that break constraints, and warnings for values that couldn't be proven to satisfy them.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Verify every use of a constrained type in the given modules, which must have already been type checked.
+pub fn verify<'m>(
+    modules: impl IntoIterator<Item = (Vec<String>, &'m Module)>,
+    resolution: &Resolution,
+    typeck: &TypeckResults,
+) -> ConstraintResults {
+    let modules: Vec<_> = modules.into_iter().collect();
+    verifier::Verifier::run(&modules, resolution, typeck)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConstraintResults, verify};
+    use crate::{
+        analysis::testing::{check_valid_source, modules},
+        ast::module::Module,
+        reporting::Severity,
+    };
+
+    fn verify_source(source: &'static str) -> (Module, ConstraintResults) {
+        let (module, resolution, typeck) = check_valid_source(source);
+        let results = verify(modules(&module), &resolution, &typeck);
+        (module, results)
+    }
+
+    /// Get the severity, message, and primary label of every diagnostic.
+    fn summarize(results: &ConstraintResults) -> Vec<(Severity, String, String)> {
+        (results.diagnostics.iter())
+            .map(|d| (d.0.severity, d.0.message.clone(), d.0.labels[0].message.clone()))
+            .collect()
+    }
+
+    /// Add a constrained `fizzbuzz` function to the start of some source code.
+    macro_rules! with_fizzbuzz {
+        ($source:literal) => {
+            concat!(
+                "pure func in_range(i: u8) -> bool { i >= 1 && i <= 100 }\n",
+                "type FizzBuzzInteger = u8 constrain in_range;\n",
+                "func fizzbuzz(i: FizzBuzzInteger) -> u8 { i % 15 }\n",
+                $source
+            )
+        };
+    }
+
+    #[test]
+    fn test_proven() {
+        let (_, results) = verify_source(with_fizzbuzz!(
+            "func main(x: u8) {\n\
+                for i in 1..=100 { fizzbuzz(i); }\n\
+                for i in 0..100 { fizzbuzz(i + 1); }\n\
+                if x > 0 && x < 50 { fizzbuzz(x); }\n\
+                if x == 0 || x > 100 {} else { fizzbuzz(x); }\n\
+                fizzbuzz(x % 50 + 1);\n\
+                let y: FizzBuzzInteger = 7;\n\
+                fizzbuzz(y);\n\
+            }"
+        ));
+
+        assert!(results.diagnostics.is_empty(), "{:?}", summarize(&results));
+        assert!(results.runtime_checks.is_empty());
+    }
+
+    #[test]
+    fn test_refuted() {
+        let (module, results) = verify_source(with_fizzbuzz!(
+            "func main() {\n\
+                for i in 0..=100 { fizzbuzz(i); }\n\
+                fizzbuzz(200);\n\
+                let big: u8 = 150;\n\
+                if big > 10 { fizzbuzz(big - 10); }\n\
+            }"
+        ));
+
+        assert_eq!(
+            summarize(&results),
+            [
+                (
+                    Severity::Error,
+                    "value does not satisfy constraint `in_range`".to_owned(),
+                    "this takes the value 0".to_owned()
+                ),
+                (
+                    Severity::Error,
+                    "value does not satisfy constraint `in_range`".to_owned(),
+                    "this is always 200".to_owned()
+                ),
+                (
+                    Severity::Error,
+                    "value does not satisfy constraint `in_range`".to_owned(),
+                    "this is always 140".to_owned()
+                ),
+            ]
+        );
+
+        // The constraint is highlighted where the type names it.
+        let source = module.matching_source.as_str();
+        let label = &results.diagnostics[0].0.labels[1];
+        assert_eq!(&source[label.range.clone()], "in_range");
+        assert_eq!(label.message, "`in_range` only allows 1..=100");
+    }
+
+    #[test]
+    fn test_runtime_checks() {
+        let (_, results) = verify_source(with_fizzbuzz!(
+            "pure func even(i: u8) -> bool { i % 2 == 0 }\n\
+            pure func small(i: u8) -> bool { i < 10 || i > 250 }\n\
+            func half(x: u8) -> u8 constrain small { x / 2 }\n\
+            func main(x: u8, e: u8 constrain even) -> u8 constrain even {\n\
+                fizzbuzz(x);\n\
+                let y: FizzBuzzInteger = x / 3;\n\
+                if x < 5 { half(x); }\n\
+                e\n\
+            }"
+        ));
+
+        assert_eq!(
+            summarize(&results),
+            [
+                (
+                    Severity::Warning,
+                    "cannot prove constraint `small` at compile time".to_owned(),
+                    "this could be 10..=127".to_owned()
+                ),
+                (
+                    Severity::Warning,
+                    "cannot check constraint `even` at compile time".to_owned(),
+                    "only comparisons of the parameter against integer literals can be checked at compile time"
+                        .to_owned()
+                ),
+                (
+                    Severity::Warning,
+                    "cannot prove constraint `in_range` at compile time".to_owned(),
+                    "this could be 0, 101..=255".to_owned()
+                ),
+                (
+                    Severity::Warning,
+                    "cannot prove constraint `in_range` at compile time".to_owned(),
+                    "this could be 0".to_owned()
+                ),
+            ]
+        );
+
+        // Every value that couldn't be proven is recorded, including the ones checked against `even`.
+        let checked: Vec<_> = (results.runtime_checks.iter())
+            .map(|check| check.value.as_str())
+            .collect();
+        assert_eq!(checked, ["x / 2", "x", "x / 3", "e"]);
+    }
+
+    #[test]
+    fn test_constraint_decls() {
+        let (module, results) = verify_source(
+            "constraint Positive<T>(value: T) { value > 0 }\n\
+            constraint Small(value: u8): Positive { value <= 10 }\n\
+            constraint Even(value: u8) { value != 3 && value != 5 }\n\
+            constraint SmallEven = Small + Even;\n\
+            func f(x: u8 constrain SmallEven) {}\n\
+            func g(x: i8 constrain Positive) {}\n\
+            func main() { f(4); f(5); f(0); g(-3); for i in 1..=10 { f(i); } }",
+        );
+
+        assert_eq!(
+            summarize(&results),
+            [
+                (
+                    Severity::Error,
+                    "value does not satisfy constraint `SmallEven`".to_owned(),
+                    "this is always 5".to_owned()
+                ),
+                (
+                    Severity::Error,
+                    "value does not satisfy constraint `SmallEven`".to_owned(),
+                    "this is always 0".to_owned()
+                ),
+                (
+                    Severity::Error,
+                    "value does not satisfy constraint `Positive`".to_owned(),
+                    "this is always -3".to_owned()
+                ),
+                (
+                    Severity::Error,
+                    "value does not satisfy constraint `SmallEven`".to_owned(),
+                    "this takes the values 3, 5".to_owned()
+                ),
+            ]
+        );
+
+        // Generic constraints only show the values allowed by the constrained type.
+        let source = module.matching_source.as_str();
+        let labels = &results.diagnostics[0].0.labels;
+        assert_eq!(&source[labels[1].range.clone()], "SmallEven");
+        assert_eq!(labels[1].message, "`SmallEven` only allows 1..=2, 4, 6..=10");
+        assert_eq!(results.diagnostics[2].0.labels[1].message, "`Positive` only allows 1..=127");
+    }
+
+    #[test]
+    fn test_partially_analyzed_constraint_decls() {
+        // `Even` can't be analyzed, but values that break `Small` or `Positive` are still errors.
+        let (_, results) = verify_source(
+            "constraint Positive(value: u8) { value > 0 }\n\
+            constraint Small(value: u8): Positive { value <= 10 }\n\
+            constraint Even(value: u8) { value % 2 == 0 }\n\
+            constraint SmallEven = Small + Even;\n\
+            func half(x: u8 constrain SmallEven) -> u8 { x / 2 }\n\
+            func main() { half(12); half(0); half(4); }",
+        );
+
+        assert_eq!(
+            summarize(&results),
+            [
+                (
+                    Severity::Warning,
+                    "cannot check constraint `Even` at compile time".to_owned(),
+                    "only comparisons of the parameter against integer literals can be checked at compile time"
+                        .to_owned()
+                ),
+                (
+                    Severity::Error,
+                    "value does not satisfy constraint `SmallEven`".to_owned(),
+                    "this is always 12".to_owned()
+                ),
+                (
+                    Severity::Error,
+                    "value does not satisfy constraint `SmallEven`".to_owned(),
+                    "this is always 0".to_owned()
+                ),
+            ]
+        );
+
+        // Values the analyzed parts allow still aren't proven to satisfy the whole constraint.
+        let checked: Vec<_> = (results.runtime_checks.iter())
+            .map(|check| check.value.as_str())
+            .collect();
+        assert_eq!(checked, ["4"]);
+    }
+}
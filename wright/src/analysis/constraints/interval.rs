@@ -0,0 +1,331 @@
+//! Closed intervals of integers, and sets of them -- the abstract domain used for constraint verification.
+
+use std::{
+    fmt,
+    ops::{Add, Mul, Neg, Sub},
+};
+
+/// A non-empty closed interval of integers, `lo..=hi`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Interval {
+    /// The smallest value in the interval.
+    pub lo: i128,
+    /// The largest value in the interval.
+    pub hi: i128,
+}
+
+impl Interval {
+    /// Create an interval, returning [None] if it would be empty (`lo > hi`).
+    pub const fn new(lo: i128, hi: i128) -> Option<Self> {
+        match lo <= hi {
+            true => Some(Interval { lo, hi }),
+            false => None,
+        }
+    }
+
+    /// Create an interval holding a single value.
+    pub const fn single(value: i128) -> Self {
+        Interval {
+            lo: value,
+            hi: value,
+        }
+    }
+
+    /// Check if this interval holds a single value.
+    pub const fn is_single(self) -> bool {
+        self.lo == self.hi
+    }
+
+    /// Check if a value is in this interval.
+    pub const fn contains(self, value: i128) -> bool {
+        self.lo <= value && value <= self.hi
+    }
+
+    /// Check if every value in `other` is also in this interval.
+    pub const fn contains_interval(self, other: Interval) -> bool {
+        self.lo <= other.lo && other.hi <= self.hi
+    }
+
+    /// Get the values that are in both intervals, if there are any.
+    pub fn meet(self, other: Interval) -> Option<Interval> {
+        Interval::new(self.lo.max(other.lo), self.hi.min(other.hi))
+    }
+
+    /// Get the smallest interval containing both intervals.
+    pub fn hull(self, other: Interval) -> Interval {
+        Interval {
+            lo: self.lo.min(other.lo),
+            hi: self.hi.max(other.hi),
+        }
+    }
+
+    /// The interval of `a / b` (rounding towards zero) for every `a` in this interval and `b` in `other`, or
+    /// [None] if `other` contains zero.
+    pub fn checked_div(self, other: Interval) -> Option<Interval> {
+        if other.contains(0) {
+            return None;
+        }
+
+        // Division is monotonic in each argument when the divisor doesn't cross zero, so the extremes are at the
+        // corners.
+        Some(Self::from_corners(self, other, |a, b| a.checked_div(b).unwrap_or(i128::MAX)))
+    }
+
+    /// The interval of `a % b` (with the sign of `a`) for every `a` in this interval and `b` in `other`, or
+    /// [None] if `other` contains zero.
+    pub fn checked_rem(self, other: Interval) -> Option<Interval> {
+        if other.contains(0) {
+            return None;
+        }
+
+        // The result is smaller in magnitude than the largest divisor.
+        let largest = other.lo.unsigned_abs().max(other.hi.unsigned_abs());
+        let bound = i128::try_from(largest - 1).unwrap_or(i128::MAX);
+
+        // Values smaller than every divisor are left as they are.
+        let smallest = other.lo.unsigned_abs().min(other.hi.unsigned_abs());
+        if self.lo.unsigned_abs() < smallest && self.hi.unsigned_abs() < smallest {
+            return Some(self);
+        }
+
+        Some(Interval {
+            lo: match self.lo >= 0 {
+                true => 0,
+                false => self.lo.max(-bound),
+            },
+            hi: match self.hi <= 0 {
+                true => 0,
+                false => self.hi.min(bound),
+            },
+        })
+    }
+
+    /// Get the interval spanning the results of `op` applied to the corners of two intervals.
+    fn from_corners(a: Interval, b: Interval, op: impl Fn(i128, i128) -> i128) -> Interval {
+        let corners = [
+            op(a.lo, b.lo),
+            op(a.lo, b.hi),
+            op(a.hi, b.lo),
+            op(a.hi, b.hi),
+        ];
+
+        Interval {
+            lo: *corners.iter().min().unwrap(),
+            hi: *corners.iter().max().unwrap(),
+        }
+    }
+}
+
+impl Add for Interval {
+    type Output = Interval;
+
+    /// The interval of `a + b` for every `a` in this interval and `b` in `other`.
+    fn add(self, other: Interval) -> Interval {
+        Interval {
+            lo: self.lo.saturating_add(other.lo),
+            hi: self.hi.saturating_add(other.hi),
+        }
+    }
+}
+
+impl Sub for Interval {
+    type Output = Interval;
+
+    /// The interval of `a - b` for every `a` in this interval and `b` in `other`.
+    fn sub(self, other: Interval) -> Interval {
+        Interval {
+            lo: self.lo.saturating_sub(other.hi),
+            hi: self.hi.saturating_sub(other.lo),
+        }
+    }
+}
+
+impl Mul for Interval {
+    type Output = Interval;
+
+    /// The interval of `a * b` for every `a` in this interval and `b` in `other`.
+    fn mul(self, other: Interval) -> Interval {
+        Interval::from_corners(self, other, i128::saturating_mul)
+    }
+}
+
+impl Neg for Interval {
+    type Output = Interval;
+
+    /// The interval of `-a` for every `a` in this interval.
+    fn neg(self) -> Interval {
+        Interval {
+            lo: self.hi.saturating_neg(),
+            hi: self.lo.saturating_neg(),
+        }
+    }
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.is_single() {
+            true => write!(f, "{}", self.lo),
+            false => write!(f, "{}..={}", self.lo, self.hi),
+        }
+    }
+}
+
+/// A set of integers, stored as sorted intervals that don't overlap or touch.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IntervalSet {
+    intervals: Vec<Interval>,
+}
+
+impl IntervalSet {
+    /// The empty set.
+    pub const EMPTY: IntervalSet = IntervalSet {
+        intervals: Vec::new(),
+    };
+
+    /// Create a set from any intervals, merging ones that overlap or touch.
+    pub fn from_intervals(intervals: impl IntoIterator<Item = Interval>) -> Self {
+        let mut sorted: Vec<Interval> = intervals.into_iter().collect();
+        sorted.sort_by_key(|interval| interval.lo);
+
+        let mut merged: Vec<Interval> = Vec::with_capacity(sorted.len());
+
+        for interval in sorted {
+            match merged.last_mut() {
+                Some(last) if interval.lo <= last.hi.saturating_add(1) => {
+                    last.hi = last.hi.max(interval.hi)
+                }
+                _ => merged.push(interval),
+            }
+        }
+
+        IntervalSet { intervals: merged }
+    }
+
+    /// Get the intervals in this set, in order.
+    pub fn intervals(&self) -> &[Interval] {
+        &self.intervals
+    }
+
+    /// Check if this set is empty.
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    /// Get the smallest interval containing every value in this set, if it isn't empty.
+    pub fn hull(&self) -> Option<Interval> {
+        Some(Interval {
+            lo: self.intervals.first()?.lo,
+            hi: self.intervals.last()?.hi,
+        })
+    }
+
+    /// Check if every value in an interval is in this set.
+    pub fn contains_interval(&self, interval: Interval) -> bool {
+        // Intervals in the set never touch, so a contained interval must fit in just one of them.
+        self.intervals
+            .iter()
+            .any(|own| own.contains_interval(interval))
+    }
+
+    /// Get the values in either set.
+    pub fn union(&self, other: &IntervalSet) -> IntervalSet {
+        IntervalSet::from_intervals(self.intervals.iter().chain(&other.intervals).copied())
+    }
+
+    /// Get the values in both sets.
+    pub fn intersect(&self, other: &IntervalSet) -> IntervalSet {
+        IntervalSet::from_intervals(
+            self.intervals
+                .iter()
+                .flat_map(|a| other.intervals.iter().filter_map(|b| a.meet(*b))),
+        )
+    }
+
+    /// Get the values in `within` that aren't in this set.
+    pub fn complement(&self, within: Interval) -> IntervalSet {
+        let mut gaps = Vec::new();
+        let mut next = Some(within.lo);
+
+        for interval in &self.intervals {
+            if let Some(start) = next {
+                gaps.extend(Interval::new(start, interval.lo.saturating_sub(1)));
+            }
+
+            next = interval.hi.checked_add(1);
+        }
+
+        if let Some(start) = next {
+            gaps.extend(Interval::new(start, within.hi));
+        }
+
+        IntervalSet::from_intervals(gaps).intersect(&IntervalSet::from(within))
+    }
+}
+
+impl From<Interval> for IntervalSet {
+    fn from(interval: Interval) -> Self {
+        IntervalSet {
+            intervals: vec![interval],
+        }
+    }
+}
+
+impl fmt::Display for IntervalSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return f.write_str("no values");
+        }
+
+        for (index, interval) in self.intervals.iter().enumerate() {
+            if index > 0 {
+                f.write_str(", ")?;
+            }
+
+            write!(f, "{interval}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Interval, IntervalSet};
+
+    fn interval(lo: i128, hi: i128) -> Interval {
+        Interval::new(lo, hi).unwrap()
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        assert_eq!(interval(1, 10) + interval(-2, 2), interval(-1, 12));
+        assert_eq!(interval(1, 10) - interval(-2, 2), interval(-1, 12));
+        assert_eq!(interval(-3, 2) * interval(-4, 5), interval(-15, 12));
+        assert_eq!(interval(10, 20).checked_div(interval(2, 5)), Some(interval(2, 10)));
+        assert_eq!(interval(10, 20).checked_div(interval(-1, 1)), None);
+        assert_eq!(interval(0, 255).checked_rem(Interval::single(15)), Some(interval(0, 14)));
+        assert_eq!(interval(-20, 3).checked_rem(Interval::single(8)), Some(interval(-7, 3)));
+        assert_eq!(interval(2, 5).checked_rem(Interval::single(8)), Some(interval(2, 5)));
+        assert_eq!(interval(1, 2).to_string(), "1..=2");
+        assert_eq!(Interval::single(7).to_string(), "7");
+    }
+
+    #[test]
+    fn test_sets() {
+        let set = IntervalSet::from_intervals([interval(5, 10), interval(0, 2), interval(3, 3)]);
+        assert_eq!(set.intervals(), [interval(0, 3), interval(5, 10)]);
+        assert!(set.contains_interval(interval(6, 9)));
+        assert!(!set.contains_interval(interval(2, 6)));
+
+        let complement = set.complement(interval(0, 255));
+        assert_eq!(complement.to_string(), "4, 11..=255");
+        assert_eq!(
+            complement
+                .intersect(&IntervalSet::from(interval(4, 20)))
+                .to_string(),
+            "4, 11..=20"
+        );
+        assert!(set.intersect(&complement).is_empty());
+        assert_eq!(set.union(&complement), IntervalSet::from(interval(0, 255)));
+    }
+}
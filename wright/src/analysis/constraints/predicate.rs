@@ -0,0 +1,171 @@
+//! Working out which values a constraint function accepts.
+
+use super::interval::{Interval, IntervalSet};
+use crate::{
+    analysis::{
+        resolve::{DefId, Resolution},
+        typeck::{TypeckResults, ty::Ty},
+    },
+    ast::{
+        decl::func::FuncParam,
+        expr::{Atom, BinaryOperation, Expr, UnaryOperation},
+        stmt::Block,
+    },
+};
+
+/// Get the set of values that a constraint function (or the predicate of a constraint declaration) with the given
+/// parameters and body returns `true` for. Only a single integer (or generic) parameter, and a body built from
+/// comparisons of the parameter against integer literals (combined with `&&`, `||`, and `!`), can be analyzed.
+/// Otherwise, this returns a reason the constraint can't be analyzed, used as a label on its name.
+pub(super) fn allowed_values(
+    params: &[FuncParam],
+    body: Option<&Block>,
+    res: &Resolution,
+    typeck: &TypeckResults,
+) -> Result<IntervalSet, &'static str> {
+    let [param] = params else {
+        return Err("constraint functions must take exactly one parameter");
+    };
+
+    let param_def = res.declared(&param.name.fragment);
+
+    // Generic constraints could be applied to any integer type, so they're analyzed over every integer.
+    let bounds = match param_def.and_then(|def| typeck.type_of_def(def)) {
+        Some(Ty::Param(_)) => Some(Interval::new(i128::MIN, i128::MAX).unwrap()),
+        ty => ty
+            .and_then(|ty| ty.atomic())
+            .and_then(|variant| variant.integer_bounds())
+            .and_then(|(lo, hi)| Interval::new(lo, hi)),
+    };
+
+    let bounds = bounds.ok_or("only constraints on integers can be checked at compile time")?;
+
+    let Some(body) = body else {
+        return Err("this function has no body to check");
+    };
+
+    let condition = match (&body.tail, body.stmts.is_empty()) {
+        (Some(tail), true) => tail,
+        _ => {
+            return Err(
+                "only functions that are a single condition can be checked at compile time",
+            );
+        }
+    };
+
+    let predicate = Predicate {
+        res,
+        param: param_def,
+        bounds,
+    };
+
+    predicate.values(condition).ok_or(
+        "only comparisons of the parameter against integer literals can be checked at compile time",
+    )
+}
+
+/// The parameter of a constraint function being analyzed.
+struct Predicate<'r> {
+    res: &'r Resolution,
+    param: Option<DefId>,
+    bounds: Interval,
+}
+
+impl Predicate<'_> {
+    /// Get the values of the parameter that make a condition `true`, if that can be worked out.
+    fn values(&self, condition: &Expr) -> Option<IntervalSet> {
+        use BinaryOperation::*;
+
+        match condition {
+            Expr::Parens(parens) => self.values(&parens.inner),
+
+            Expr::Block(block) if block.stmts.is_empty() => self.values(block.tail.as_deref()?),
+
+            Expr::Atom(Atom::BooleanLiteral(literal)) => Some(match literal.value {
+                true => IntervalSet::from(self.bounds),
+                false => IntervalSet::EMPTY,
+            }),
+
+            Expr::Unary(unary) if unary.op == UnaryOperation::BooleanNot => {
+                Some(self.values(&unary.operand)?.complement(self.bounds))
+            }
+
+            Expr::Binary(binary) if binary.op == LogicalAnd => Some(
+                self.values(&binary.lhs)?
+                    .intersect(&self.values(&binary.rhs)?),
+            ),
+
+            Expr::Binary(binary) if binary.op == LogicalOr => {
+                Some(self.values(&binary.lhs)?.union(&self.values(&binary.rhs)?))
+            }
+
+            Expr::Binary(binary) if binary.op.is_comparison() => {
+                // Put the parameter on the left, flipping the comparison if needed.
+                let (op, constant) = match (self.is_param(&binary.lhs), self.is_param(&binary.rhs))
+                {
+                    (true, false) => (binary.op, constant(&binary.rhs)?),
+                    (false, true) => (flip(binary.op), constant(&binary.lhs)?),
+                    _ => return None,
+                };
+
+                let (lo, hi) = (self.bounds.lo, self.bounds.hi);
+
+                let set = match op {
+                    Equal => IntervalSet::from(Interval::single(constant)),
+                    NotEqual => {
+                        IntervalSet::from(Interval::single(constant)).complement(self.bounds)
+                    }
+                    Less => {
+                        IntervalSet::from_intervals(Interval::new(lo, constant.saturating_sub(1)))
+                    }
+                    LessOrEqual => IntervalSet::from_intervals(Interval::new(lo, constant)),
+                    Greater => {
+                        IntervalSet::from_intervals(Interval::new(constant.saturating_add(1), hi))
+                    }
+                    GreaterOrEqual => IntervalSet::from_intervals(Interval::new(constant, hi)),
+                    _ => unreachable!("not a comparison"),
+                };
+
+                Some(set.intersect(&IntervalSet::from(self.bounds)))
+            }
+
+            _ => None,
+        }
+    }
+
+    /// Check if an expression is the parameter (possibly in parentheses).
+    fn is_param(&self, expr: &Expr) -> bool {
+        match expr {
+            Expr::Parens(parens) => self.is_param(&parens.inner),
+            Expr::Atom(Atom::Identifier(identifier)) => {
+                self.param.is_some() && self.res.resolved(&identifier.fragment) == self.param
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Get the value of an integer literal (possibly negated or in parentheses).
+fn constant(expr: &Expr) -> Option<i128> {
+    match expr {
+        Expr::Parens(parens) => constant(&parens.inner),
+        Expr::Atom(Atom::IntegerLiteral(literal)) => i128::try_from(&literal.value).ok(),
+        Expr::Unary(unary) if unary.op == UnaryOperation::Negate => {
+            constant(&unary.operand)?.checked_neg()
+        }
+        _ => None,
+    }
+}
+
+/// Get the comparison that gives the same result with its operands swapped, i.e. `a < b` is `b > a`.
+pub(super) const fn flip(op: BinaryOperation) -> BinaryOperation {
+    use BinaryOperation::*;
+
+    match op {
+        Less => Greater,
+        LessOrEqual => GreaterOrEqual,
+        Greater => Less,
+        GreaterOrEqual => LessOrEqual,
+        other => other,
+    }
+}
@@ -0,0 +1,946 @@
+//! The abstract interpreter that tracks integer intervals through function bodies and checks constrained values.
+
+use super::{
+    ConstraintResults, RuntimeCheck,
+    interval::{Interval, IntervalSet},
+    predicate::{allowed_values, flip},
+};
+use crate::{
+    analysis::{
+        resolve::{DefId, DefKind, Resolution, module_bodies},
+        typeck::{TypeckResults, ty::Ty},
+    },
+    ast::{
+        decl::{
+            Decl, constant::ConstDecl, constraint::ConstraintDecl, func::FuncDecl,
+            record::FieldDecl, type_alias::TypeAlias,
+        },
+        expr::{
+            Atom, BinaryExpr, BinaryOperation, CallExpr, Expr, ForExpr, IfExpr, UnaryOperation,
+        },
+        identifier::Identifier,
+        module::Module,
+        stmt::{Block, Stmt},
+        ty::Type,
+    },
+    reporting::{Diagnostic, Highlight},
+    source_tracking::fragment::Fragment,
+};
+use std::collections::HashMap;
+
+/// What's known about an integer value at some point in a function.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Value {
+    /// Every value it could have.
+    interval: Interval,
+
+    /// Whether it's known to take on every value in the interval (when the code it's in runs), such as the binding
+    /// of a `for` loop over a range of literals. A value that is exact and outside of a constraint is an error
+    /// rather than just unprovable.
+    is_exact: bool,
+}
+
+impl Value {
+    /// A value that could be anything in an interval, but isn't known to take on every value in it.
+    const fn within(interval: Interval) -> Self {
+        Value {
+            interval,
+            is_exact: false,
+        }
+    }
+
+    /// Get the smallest value containing both values.
+    fn hull(self, other: Value) -> Value {
+        match self == other {
+            true => self,
+            false => Value::within(self.interval.hull(other.interval)),
+        }
+    }
+}
+
+/// The values a constraint allows, as far as it could be analyzed.
+#[derive(Clone, Debug)]
+struct Allowed {
+    /// The values allowed by every part of the constraint that could be analyzed.
+    values: IntervalSet,
+
+    /// Whether every part of the constraint could be analyzed. If not, values outside of `values` still break the
+    /// constraint, but values inside of it aren't proven to satisfy it.
+    is_complete: bool,
+}
+
+/// The state of constraint verification.
+pub(super) struct Verifier<'m, 'r> {
+    /// The result of name resolution.
+    res: &'r Resolution,
+
+    /// The result of type checking.
+    typeck: &'r TypeckResults,
+
+    /// Every function by its [DefId].
+    functions: HashMap<DefId, &'m FuncDecl>,
+
+    /// Every constraint declaration by its [DefId].
+    constraint_decls: HashMap<DefId, &'m ConstraintDecl>,
+
+    /// Every constant by its [DefId].
+    constants: HashMap<DefId, &'m ConstDecl>,
+
+    /// Every type alias by its [DefId].
+    aliases: HashMap<DefId, &'m TypeAlias>,
+
+    /// The fields of every record and the variants of every union, by the [DefId] of the record or union.
+    fields: HashMap<DefId, &'m [FieldDecl]>,
+
+    /// The values each constraint allows, for the ones that have been analyzed so far. [None] means nothing about
+    /// the constraint could be analyzed (and a warning has already been reported for it).
+    allowed: HashMap<DefId, Option<Allowed>>,
+
+    /// The type annotations of the `var` bindings in the function being verified, which assignments to them are
+    /// checked against.
+    var_types: HashMap<DefId, &'m Type>,
+
+    /// What's known about each parameter and local in scope.
+    env: HashMap<DefId, Value>,
+
+    /// The results being built.
+    results: ConstraintResults,
+}
+
+impl<'m, 'r> Verifier<'m, 'r> {
+    /// Verify every use of a constrained type in the given modules.
+    pub fn run(
+        modules: &[(Vec<String>, &'m Module)],
+        res: &'r Resolution,
+        typeck: &'r TypeckResults,
+    ) -> ConstraintResults {
+        let mut verifier = Verifier {
+            res,
+            typeck,
+            functions: HashMap::new(),
+            constraint_decls: HashMap::new(),
+            constants: HashMap::new(),
+            aliases: HashMap::new(),
+            fields: HashMap::new(),
+            allowed: HashMap::new(),
+            var_types: HashMap::new(),
+            env: HashMap::new(),
+            results: ConstraintResults::default(),
+        };
+
+        let bodies = module_bodies(modules);
+
+        let declared = |name: &Identifier| res.declared(&name.fragment);
+
+        for decl in bodies.iter().flat_map(|(_, items)| *items) {
+            for func in decl.functions() {
+                (verifier.functions).extend(declared(&func.name).map(|def| (def, func)));
+            }
+
+            match decl {
+                Decl::Constraint(constraint) => (verifier.constraint_decls)
+                    .extend(declared(&constraint.name).map(|def| (def, constraint))),
+                Decl::Constant(constant) => {
+                    (verifier.constants).extend(declared(&constant.name).map(|def| (def, constant)))
+                }
+                Decl::TypeAlias(alias) => (verifier.aliases)
+                    .extend(declared(&alias.new_type_name).map(|def| (def, alias))),
+                Decl::Record(record) => (verifier.fields)
+                    .extend(declared(&record.name).map(|def| (def, record.fields.as_slice()))),
+                Decl::Union(union) => (verifier.fields)
+                    .extend(declared(&union.name).map(|def| (def, union.variants.as_slice()))),
+                _ => {}
+            }
+        }
+
+        for decl in bodies.iter().flat_map(|(_, items)| *items) {
+            for func in decl.functions() {
+                verifier.verify_function(func);
+            }
+        }
+
+        verifier.results
+    }
+
+    /// Verify the uses of constrained types in the body of a function.
+    fn verify_function(&mut self, func: &'m FuncDecl) {
+        let Some(body) = &func.body else {
+            return;
+        };
+
+        self.env.clear();
+        self.var_types.clear();
+
+        // Constrained parameters are checked by callers, so their constraints can be assumed here.
+        for param in &func.params {
+            let Some(def) = self.res.declared(&param.name.fragment) else {
+                continue;
+            };
+
+            if let Some(bounds) = self.def_bounds(def) {
+                let value = self.assume_constraints(Value::within(bounds), &param.ty);
+                self.env.insert(def, value);
+            }
+        }
+
+        let value = self.visit_block(body);
+
+        if let (Some(return_ty), Some(tail)) = (&func.return_ty, &body.tail) {
+            self.check_value(tail.matching_source(), value, return_ty);
+        }
+    }
+
+    /// Get the bounds of the type of a definition, if it's an integer type.
+    fn def_bounds(&self, def: DefId) -> Option<Interval> {
+        type_bounds(self.typeck.type_of_def(def)?)
+    }
+
+    /// Get the bounds of the type of an expression, if it's an integer type.
+    fn expr_bounds(&self, expr: &Expr) -> Option<Interval> {
+        type_bounds(self.typeck.type_of_expr(expr.matching_source())?)
+    }
+
+    /// Get the constraints on a type, looking through type aliases, along with the path that names each
+    /// constraint.
+    fn constraints(&self, ty: &'m Type) -> Vec<(DefId, &'m Fragment)> {
+        let mut constraints = Vec::new();
+        self.collect_constraints(ty, &mut Vec::new(), &mut constraints);
+        constraints
+    }
+
+    /// Add the constraints on a type to `constraints`, skipping any aliases in `expanding` (which refer to
+    /// themselves, and have already been reported by the type checker).
+    fn collect_constraints(
+        &self,
+        ty: &'m Type,
+        expanding: &mut Vec<DefId>,
+        constraints: &mut Vec<(DefId, &'m Fragment)>,
+    ) {
+        match ty {
+            Type::Constrained(constrained) => {
+                self.collect_constraints(&constrained.base_ty, expanding, constraints);
+
+                for path in &constrained.constraining_items {
+                    if let Some(def) = self.res.resolved(&path.full_path)
+                        && (self.functions.contains_key(&def)
+                            || self.constraint_decls.contains_key(&def))
+                    {
+                        constraints.push((def, &path.full_path));
+                    }
+                }
+            }
+
+            Type::Named(named) => {
+                let Some(def) = self.res.resolved(&named.name.full_path) else {
+                    return;
+                };
+
+                if let Some(target) = self.aliases.get(&def).and_then(|a| a.target_type.as_ref())
+                    && !expanding.contains(&def)
+                {
+                    expanding.push(def);
+                    self.collect_constraints(target, expanding, constraints);
+                    expanding.pop();
+                }
+            }
+
+            _ => {}
+        }
+    }
+
+    /// Get the values a constraint function allows, analyzing it (and warning if it can't be analyzed) the first
+    /// time it's used.
+    fn allowed(&mut self, constraint: DefId) -> Option<Allowed> {
+        if let Some(allowed) = self.allowed.get(&constraint) {
+            return allowed.clone();
+        }
+
+        // Constraints built from themselves are reported by the type checker, and can't be verified.
+        self.allowed.insert(constraint, None);
+
+        let allowed =
+            match (self.functions.get(&constraint), self.constraint_decls.get(&constraint)) {
+                // Functions that aren't `pure` are reported by the purity checker, and can't be verified.
+                (Some(func), _) if !func.is_pure => None,
+                (Some(func), _) => {
+                    let values =
+                        allowed_values(&func.params, func.body.as_ref(), self.res, self.typeck);
+                    (self.analyzed(&func.name, values)).map(|values| Allowed {
+                        values,
+                        is_complete: true,
+                    })
+                }
+                (None, Some(decl)) => Some(self.allowed_by_decl(decl)),
+                (None, None) => None,
+            };
+
+        self.allowed.insert(constraint, allowed.clone());
+        allowed
+    }
+
+    /// Get the values a constraint declaration allows -- the values its predicate (if it has one) and every one of
+    /// its bases allow. Parts that can't be analyzed allow every value, so the rest are still checked.
+    fn allowed_by_decl(&mut self, decl: &'m ConstraintDecl) -> Allowed {
+        let mut allowed = Allowed {
+            values: IntervalSet::from(Interval::new(i128::MIN, i128::MAX).unwrap()),
+            is_complete: true,
+        };
+
+        if let Some(predicate) = &decl.predicate {
+            let params = std::slice::from_ref(&predicate.param);
+            let values = allowed_values(params, Some(&predicate.body), self.res, self.typeck);
+
+            match self.analyzed(&decl.name, values) {
+                Some(values) => allowed.values = values,
+                None => allowed.is_complete = false,
+            }
+        }
+
+        for base in &decl.bases {
+            match (self.res.resolved(&base.full_path)).and_then(|base| self.allowed(base)) {
+                Some(base) => {
+                    allowed.values = allowed.values.intersect(&base.values);
+                    allowed.is_complete &= base.is_complete;
+                }
+
+                None => allowed.is_complete = false,
+            }
+        }
+
+        allowed
+    }
+
+    /// Get the result of analyzing a constraint, warning that values of it are unverified if it couldn't be.
+    fn analyzed(
+        &mut self,
+        name: &Identifier,
+        values: Result<IntervalSet, &'static str>,
+    ) -> Option<IntervalSet> {
+        let reason = match values {
+            Ok(values) => return Some(values),
+            Err(reason) => reason,
+        };
+
+        self.results.diagnostics.push(
+            Diagnostic::warning()
+                .with_message(format!(
+                    "cannot check constraint `{}` at compile time",
+                    name.fragment
+                ))
+                .with_highlights([Highlight::primary(name.fragment.clone(), reason)])
+                .with_notes(["values are not checked against it when the program runs, so they are unverified"]),
+        );
+
+        None
+    }
+
+    /// Narrow a value to what the constraints on a type allow -- values of constrained types are checked
+    /// wherever they get that type, so this can be assumed wherever they're used.
+    fn assume_constraints(&mut self, mut value: Value, ty: &'m Type) -> Value {
+        for (constraint, _) in self.constraints(ty) {
+            let hull = self
+                .allowed(constraint)
+                .and_then(|allowed| allowed.values.hull());
+
+            if let Some(interval) = hull.and_then(|hull| hull.meet(value.interval)) {
+                value = Value {
+                    interval,
+                    is_exact: value.is_exact && interval == value.interval,
+                };
+            }
+        }
+
+        value
+    }
+
+    /// Check a value against the constraints on the type it's being used as, reporting values that break a
+    /// constraint and recording values that can't be proven to satisfy it.
+    fn check_value(&mut self, source: &Fragment, value: Option<Value>, ty: &'m Type) {
+        for (constraint, path) in self.constraints(ty) {
+            let runtime_check = RuntimeCheck {
+                value: source.clone(),
+                constraint,
+            };
+
+            // Values that can't be analyzed (including values of types with errors) are recorded too.
+            let (
+                Some(Allowed {
+                    values: allowed,
+                    is_complete,
+                }),
+                Some(value),
+            ) = (self.allowed(constraint), value)
+            else {
+                self.results.runtime_checks.push(runtime_check);
+                continue;
+            };
+
+            // Values allowed by the parts of a constraint that could be analyzed are only proven if all of them
+            // could be.
+            if allowed.contains_interval(value.interval) {
+                if !is_complete {
+                    self.results.runtime_checks.push(runtime_check);
+                }
+
+                continue;
+            }
+
+            let name = &self.res.def(constraint).name;
+            let violating = allowed.complement(value.interval);
+
+            // Generic constraints allow values of every integer type, so only show the ones of this value's type.
+            let allowed = match self.typeck.type_of_expr(source).and_then(type_bounds) {
+                Some(bounds) => allowed.intersect(&IntervalSet::from(bounds)),
+                None => allowed,
+            };
+            let allowed_label =
+                Highlight::secondary(path.clone(), format!("`{name}` only allows {allowed}"));
+
+            // Values that never satisfy the constraint, or that are known to take on a value that doesn't, are
+            // errors.
+            let label = if violating == IntervalSet::from(value.interval) {
+                format!("this is always {}", value.interval)
+            } else if value.is_exact {
+                match violating.intervals() {
+                    [single] if single.is_single() => format!("this takes the value {single}"),
+                    _ => format!("this takes the values {violating}"),
+                }
+            } else {
+                self.results.diagnostics.push(
+                    Diagnostic::warning()
+                        .with_message(format!("cannot prove constraint `{name}` at compile time"))
+                        .with_highlights([
+                            Highlight::primary(
+                                source.clone(),
+                                format!("this could be {violating}"),
+                            ),
+                            allowed_label,
+                        ])
+                        .with_notes([
+                            "this value is not checked when the program runs, so it is unverified",
+                        ]),
+                );
+
+                self.results.runtime_checks.push(runtime_check);
+                continue;
+            };
+
+            self.results.diagnostics.push(
+                Diagnostic::error()
+                    .with_message(format!("value does not satisfy constraint `{name}`"))
+                    .with_highlights([Highlight::primary(source.clone(), label), allowed_label]),
+            );
+        }
+    }
+
+    /// Walk the statements of a block, returning the value of its tail expression.
+    fn visit_block(&mut self, block: &'m Block) -> Option<Value> {
+        for stmt in &block.stmts {
+            match stmt {
+                Stmt::Let(let_stmt) => {
+                    let mut value = self.visit(&let_stmt.value);
+
+                    if let Some(ty) = &let_stmt.ty {
+                        self.check_value(let_stmt.value.matching_source(), value, ty);
+                        value = value.map(|value| self.assume_constraints(value, ty));
+                    }
+
+                    let Some(def) = self.res.declared(&let_stmt.name.fragment) else {
+                        continue;
+                    };
+
+                    // `var` bindings can be reassigned, so only what their type guarantees is known about them.
+                    if let_stmt.is_mutable {
+                        value = None;
+
+                        if let Some(ty) = &let_stmt.ty {
+                            self.var_types.insert(def, ty);
+                            value = (self.def_bounds(def))
+                                .map(|bounds| self.assume_constraints(Value::within(bounds), ty));
+                        }
+                    }
+
+                    if let Some(value) = value {
+                        self.env.insert(def, value);
+                    }
+                }
+
+                Stmt::Assign(assign) => {
+                    let target = self.visit(&assign.target);
+                    let mut value = self.visit(&assign.value);
+                    let mut source = assign.value.matching_source().clone();
+
+                    // The value of a compound assignment (like `+=`) is the result of the operation.
+                    if let Some(op) = assign.op {
+                        value = target
+                            .zip(value)
+                            .and_then(|(target, value)| arithmetic(op, target, value));
+                        source = Fragment::cover(assign.target.matching_source(), &source);
+                    }
+
+                    if let Some(ty) = self.place_ty(&assign.target) {
+                        self.check_value(&source, value, ty);
+                    }
+                }
+
+                Stmt::Expr(expr_stmt) => _ = self.visit(&expr_stmt.expr),
+            }
+        }
+
+        self.visit(block.tail.as_deref()?)
+    }
+
+    /// Walk an expression, checking any constrained values in it and returning what's known about its value if
+    /// it's an integer.
+    fn visit(&mut self, expr: &'m Expr) -> Option<Value> {
+        let value = match expr {
+            Expr::Atom(Atom::IntegerLiteral(literal)) => {
+                i128::try_from(&literal.value).ok().map(|value| Value {
+                    interval: Interval::single(value),
+                    is_exact: true,
+                })
+            }
+
+            Expr::Atom(Atom::Identifier(identifier)) => self.name_value(&identifier.fragment),
+            Expr::Path(path) => self.name_value(&path.full_path),
+            Expr::Atom(_) => None,
+            Expr::Parens(parens) => self.visit(&parens.inner),
+
+            Expr::Unary(unary) => {
+                let operand = self.visit(&unary.operand);
+
+                match unary.op {
+                    UnaryOperation::Negate => operand.map(|operand| Value {
+                        interval: -operand.interval,
+                        is_exact: operand.is_exact,
+                    }),
+                    _ => None,
+                }
+            }
+
+            Expr::Binary(binary) => self.visit_binary(binary),
+            Expr::Call(call) => self.visit_call(call),
+
+            Expr::Is(is_expr) => {
+                self.visit(&is_expr.value);
+                None
+            }
+
+            Expr::Field(field) => {
+                self.visit(&field.base);
+                let ty = self.field_ty(field.base.matching_source(), &field.field.fragment);
+
+                match (self.expr_bounds(expr), ty) {
+                    (Some(bounds), Some(ty)) => {
+                        Some(self.assume_constraints(Value::within(bounds), ty))
+                    }
+                    _ => None,
+                }
+            }
+
+            Expr::Record(record) => {
+                for field in &record.fields {
+                    let value = self.visit(&field.value);
+
+                    if let Some(ty) = self.field_ty(&record.matching_source, &field.name.fragment) {
+                        self.check_value(field.value.matching_source(), value, ty);
+                    }
+                }
+
+                None
+            }
+
+            Expr::Block(block) => self.visit_block(block),
+            Expr::Unsafe(unsafe_expr) => self.visit_block(&unsafe_expr.body),
+            Expr::If(if_expr) => self.visit_if(if_expr),
+
+            Expr::While(while_expr) => {
+                self.visit(&while_expr.condition);
+
+                // Bindings can't be reassigned, so anything learned from the condition holds for the whole body.
+                let saved = self.env.clone();
+
+                if self.narrow(&while_expr.condition, true) {
+                    self.visit_block(&while_expr.body);
+                }
+
+                self.env = saved;
+                None
+            }
+
+            Expr::For(for_expr) => self.visit_for(for_expr),
+
+            // The names bound by patterns aren't tracked, so uses of them get the bounds of their types.
+            Expr::Match(match_expr) => {
+                self.visit(&match_expr.scrutinee);
+
+                for arm in &match_expr.arms {
+                    let saved = self.env.clone();
+
+                    if let Some(guard) = &arm.guard {
+                        self.visit(guard);
+
+                        if !self.narrow(guard, true) {
+                            self.env = saved;
+                            continue;
+                        }
+                    }
+
+                    self.visit(&arm.body);
+                    self.env = saved;
+                }
+
+                None
+            }
+
+            // Closure parameters aren't tracked, so uses of them get the bounds of their types.
+            Expr::Closure(closure) => {
+                self.visit_block(&closure.body);
+                None
+            }
+        };
+
+        // Only integers are tracked, and values are always within the bounds of their types (anything else is an
+        // overflow, which isn't tracked).
+        let bounds = self.expr_bounds(expr)?;
+
+        Some(match value {
+            Some(value) if bounds.contains_interval(value.interval) => value,
+            _ => Value::within(bounds),
+        })
+    }
+
+    /// Get what's known about a name used as a value.
+    fn name_value(&mut self, use_site: &Fragment) -> Option<Value> {
+        let def = self.res.resolved(use_site)?;
+
+        if let Some(value) = self.env.get(&def) {
+            return Some(*value);
+        }
+
+        // Constants of constrained types hold values that satisfy their constraints.
+        let (bounds, constant) = (self.def_bounds(def)?, self.constants.get(&def)?);
+        Some(self.assume_constraints(Value::within(bounds), &constant.ty))
+    }
+
+    /// Get the declared type of the place an assignment writes to, if it has one.
+    fn place_ty(&self, target: &Expr) -> Option<&'m Type> {
+        match target {
+            Expr::Parens(parens) => self.place_ty(&parens.inner),
+            Expr::Atom(Atom::Identifier(identifier)) => {
+                let def = self.res.resolved(&identifier.fragment)?;
+                self.var_types.get(&def).copied()
+            }
+            Expr::Field(field) => {
+                self.field_ty(field.base.matching_source(), &field.field.fragment)
+            }
+            _ => None,
+        }
+    }
+
+    /// Get the declared type of a field of the record or union an expression has the type of.
+    fn field_ty(&self, expr: &Fragment, field: &Fragment) -> Option<&'m Type> {
+        let Ty::Named { def, .. } = self.typeck.type_of_expr(expr)? else {
+            return None;
+        };
+
+        let fields = self.fields.get(def)?;
+        let field = fields
+            .iter()
+            .find(|decl| decl.name.fragment.as_str() == field.as_str())?;
+        Some(&field.ty)
+    }
+
+    /// Walk a binary operation.
+    fn visit_binary(&mut self, binary: &'m BinaryExpr) -> Option<Value> {
+        use BinaryOperation::*;
+
+        let lhs = self.visit(&binary.lhs);
+
+        // The right side of `&&` and `||` only runs if the left side is (or isn't) true.
+        if binary.op.is_logical() {
+            let saved = self.env.clone();
+
+            if self.narrow(&binary.lhs, binary.op == LogicalAnd) {
+                self.visit(&binary.rhs);
+            }
+
+            self.env = saved;
+            return None;
+        }
+
+        let rhs = self.visit(&binary.rhs);
+        arithmetic(binary.op, lhs?, rhs?)
+    }
+
+    /// Walk a call, checking each argument against the constraints on its parameter.
+    fn visit_call(&mut self, call: &'m CallExpr) -> Option<Value> {
+        self.visit(&call.callee);
+        let args: Vec<_> = call.args.iter().map(|arg| self.visit(arg)).collect();
+
+        let callee = match &*call.callee {
+            Expr::Atom(Atom::Identifier(identifier)) => self.res.resolved(&identifier.fragment),
+            Expr::Path(path) => self.res.resolved(&path.full_path),
+            _ => None,
+        };
+
+        let func = *self.functions.get(&callee?)?;
+
+        for ((arg, value), param) in call.args.iter().zip(args).zip(&func.params) {
+            self.check_value(arg.matching_source(), value, &param.ty);
+        }
+
+        // The function's return value satisfies the constraints on its return type.
+        let bounds = type_bounds(self.typeck.type_of_expr(&call.matching_source)?)?;
+        Some(self.assume_constraints(Value::within(bounds), func.return_ty.as_ref()?))
+    }
+
+    /// Walk an `if` expression, narrowing the values of bindings in each branch.
+    fn visit_if(&mut self, if_expr: &'m IfExpr) -> Option<Value> {
+        self.visit(&if_expr.condition);
+        let saved = self.env.clone();
+
+        let then_value = match self.narrow(&if_expr.condition, true) {
+            true => Some(self.visit_block(&if_expr.then_block)),
+            false => None,
+        };
+
+        self.env = saved.clone();
+
+        let else_value = match self.narrow(&if_expr.condition, false) {
+            true => if_expr
+                .else_branch
+                .as_deref()
+                .map(|branch| self.visit(branch)),
+            false => None,
+        };
+
+        self.env = saved;
+
+        // Branches that can't run don't contribute to the value.
+        match (then_value, else_value) {
+            (Some(then_value), Some(else_value)) => Some(then_value?.hull(else_value?)),
+            (Some(value), None) | (None, Some(value)) => value,
+            (None, None) => None,
+        }
+    }
+
+    /// Walk a `for` loop, giving the binding every value in the range.
+    fn visit_for(&mut self, for_expr: &'m ForExpr) -> Option<Value> {
+        let range = &for_expr.range;
+        let (start, end) = (self.visit(&range.start), self.visit(&range.end));
+
+        let (Some(start), Some(end), Some(def)) =
+            (start, end, self.res.declared(&for_expr.binding.fragment))
+        else {
+            self.visit_block(&for_expr.body);
+            return None;
+        };
+
+        let hi = match range.is_inclusive {
+            true => end.interval.hi,
+            false => end.interval.hi.saturating_sub(1),
+        };
+
+        // A range that's always empty means the body never runs.
+        let interval = Interval::new(start.interval.lo, hi)?;
+
+        // Every value in the range is reached if both ends are known exactly.
+        let is_exact = start.interval.is_single() && end.interval.is_single();
+        self.env.insert(def, Value { interval, is_exact });
+        self.visit_block(&for_expr.body);
+        None
+    }
+
+    /// Narrow the values of bindings to the ones that make a condition `true` (or `false`, depending on `truth`).
+    /// Returns false if the condition can never have that value, meaning the code it guards can't run.
+    fn narrow(&mut self, condition: &Expr, truth: bool) -> bool {
+        use BinaryOperation::*;
+
+        match condition {
+            Expr::Parens(parens) => self.narrow(&parens.inner, truth),
+            Expr::Atom(Atom::BooleanLiteral(literal)) => literal.value == truth,
+
+            Expr::Unary(unary) if unary.op == UnaryOperation::BooleanNot => {
+                self.narrow(&unary.operand, !truth)
+            }
+
+            // Both sides must be true for `&&` to be true, and both false for `||` to be false.
+            Expr::Binary(binary)
+                if (binary.op == LogicalAnd && truth) || (binary.op == LogicalOr && !truth) =>
+            {
+                self.narrow(&binary.lhs, truth) && self.narrow(&binary.rhs, truth)
+            }
+
+            // Otherwise either side could decide the result, so only what holds in both cases is kept.
+            Expr::Binary(binary) if binary.op.is_logical() => {
+                let saved = self.env.clone();
+                let lhs_possible = self.narrow(&binary.lhs, truth);
+                let lhs_env = std::mem::replace(&mut self.env, saved.clone());
+                let rhs_possible = self.narrow(&binary.rhs, truth);
+
+                match (lhs_possible, rhs_possible) {
+                    (true, true) => {
+                        for (def, value) in &mut self.env {
+                            let lhs_value = lhs_env.get(def).or(saved.get(def));
+
+                            if let Some(lhs_value) = lhs_value {
+                                *value = value.hull(*lhs_value);
+                            }
+                        }
+
+                        true
+                    }
+
+                    (true, false) => {
+                        self.env = lhs_env;
+                        true
+                    }
+
+                    (false, rhs_possible) => rhs_possible,
+                }
+            }
+
+            Expr::Binary(binary) if binary.op.is_comparison() => {
+                let op = match truth {
+                    true => binary.op,
+                    false => negate(binary.op),
+                };
+
+                let lhs_possible = self.narrow_comparison(&binary.lhs, op, &binary.rhs);
+                lhs_possible && self.narrow_comparison(&binary.rhs, flip(op), &binary.lhs)
+            }
+
+            _ => true,
+        }
+    }
+
+    /// Narrow the value of a binding so that `binding op other` holds, if `binding` is a parameter or local.
+    /// Returns false if it can't hold.
+    fn narrow_comparison(&mut self, binding: &Expr, op: BinaryOperation, other: &Expr) -> bool {
+        use BinaryOperation::*;
+
+        let Some(def) = binding_def(binding, self.res) else {
+            return true;
+        };
+
+        let current = self
+            .env
+            .get(&def)
+            .copied()
+            .or_else(|| self.expr_bounds(binding).map(Value::within));
+
+        let (Some(current), Some(other)) = (current, self.peek(other)) else {
+            return true;
+        };
+
+        let (lo, hi) = (current.interval.lo, current.interval.hi);
+
+        let narrowed = match op {
+            Equal => current.interval.meet(other.interval),
+            Less => Interval::new(lo, hi.min(other.interval.hi.saturating_sub(1))),
+            LessOrEqual => Interval::new(lo, hi.min(other.interval.hi)),
+            Greater => Interval::new(lo.max(other.interval.lo.saturating_add(1)), hi),
+            GreaterOrEqual => Interval::new(lo.max(other.interval.lo), hi),
+
+            // Only values at the ends of the interval can be removed.
+            NotEqual if other.interval.is_single() && other.interval.lo == lo => {
+                Interval::new(lo + 1, hi)
+            }
+            NotEqual if other.interval.is_single() && other.interval.lo == hi => {
+                Interval::new(lo, hi - 1)
+            }
+            _ => Some(current.interval),
+        };
+
+        let Some(interval) = narrowed else {
+            return false;
+        };
+
+        // Comparing against a single value keeps every value on the right side of it.
+        let is_exact = current.is_exact && other.interval.is_single();
+        self.env.insert(def, Value { interval, is_exact });
+        true
+    }
+
+    /// Get what's known about an expression used in a condition without checking it again, which only works for
+    /// literals and bindings.
+    fn peek(&self, expr: &Expr) -> Option<Value> {
+        match expr {
+            Expr::Parens(parens) => self.peek(&parens.inner),
+
+            Expr::Atom(Atom::IntegerLiteral(literal)) => {
+                Some(Value::within(Interval::single(i128::try_from(&literal.value).ok()?)))
+            }
+
+            Expr::Unary(unary) if unary.op == UnaryOperation::Negate => {
+                let value = self.peek(&unary.operand)?;
+                Some(Value::within(-value.interval))
+            }
+
+            _ => {
+                let def = binding_def(expr, self.res)?;
+                self.env
+                    .get(&def)
+                    .copied()
+                    .or_else(|| self.expr_bounds(expr).map(Value::within))
+            }
+        }
+    }
+}
+
+/// Get what's known about the result of an arithmetic operation.
+fn arithmetic(op: BinaryOperation, lhs: Value, rhs: Value) -> Option<Value> {
+    use BinaryOperation::*;
+
+    // Adding or subtracting a single value shifts every value, so it keeps the result exact.
+    let keeps_exact = |value: Value, other: Value| value.is_exact && other.interval.is_single();
+
+    let (interval, is_exact) = match op {
+        Add => (lhs.interval + rhs.interval, keeps_exact(lhs, rhs) || keeps_exact(rhs, lhs)),
+        Subtract => (lhs.interval - rhs.interval, keeps_exact(lhs, rhs)),
+        Multiply => (lhs.interval * rhs.interval, false),
+        Divide => (lhs.interval.checked_div(rhs.interval)?, false),
+        Modulo => (lhs.interval.checked_rem(rhs.interval)?, false),
+        _ => return None,
+    };
+
+    Some(Value {
+        interval,
+        is_exact: is_exact || interval.is_single(),
+    })
+}
+
+/// Get the parameter or `let` binding an expression names, if it's just a name.
+fn binding_def(expr: &Expr, res: &Resolution) -> Option<DefId> {
+    let def = match expr {
+        Expr::Parens(parens) => return binding_def(&parens.inner, res),
+        Expr::Atom(Atom::Identifier(identifier)) => res.resolved(&identifier.fragment)?,
+        _ => return None,
+    };
+
+    // `var` bindings can be reassigned, so conditions on them don't narrow them for long.
+    matches!(res.def(def).kind, DefKind::Param | DefKind::Local { is_mutable: false })
+        .then_some(def)
+}
+
+/// Get the comparison that is true exactly when the given one is false.
+const fn negate(op: BinaryOperation) -> BinaryOperation {
+    use BinaryOperation::*;
+
+    match op {
+        Equal => NotEqual,
+        NotEqual => Equal,
+        Less => GreaterOrEqual,
+        LessOrEqual => Greater,
+        Greater => LessOrEqual,
+        GreaterOrEqual => Less,
+        other => other,
+    }
+}
+
+/// Get the bounds of an integer type.
+fn type_bounds(ty: &Ty) -> Option<Interval> {
+    let (lo, hi) = ty.atomic()?.integer_bounds()?;
+    Interval::new(lo, hi)
+}
@@ -0,0 +1,1002 @@
+//! Exhaustiveness and reachability checking for `match` expressions.
+//!
+//! Every `match` must have an arm for every value of the matched type, and every arm should be able to match some
+//! value that the arms before it don't. Both are checked with the usefulness algorithm over a matrix of patterns
+//! (one row per arm):
+//! - A pattern is *useful* after some rows if there's a value it matches that none of the rows do. An arm that
+//!   isn't useful after the arms before it can never be reached, and is reported with a warning.
+//! - A `match` is exhaustive if a `_` pattern wouldn't be useful after all of its arms. If it isn't, the values
+//!   that `_` would match are reported as example patterns that aren't covered.
+//!
+//! Patterns are compared by *constructor* -- a boolean value, an enum variant, a record, the variant of a union,
+//! or a range of integers. Integer ranges are split at the ends of every range pattern in the same column, so that
+//! each piece is either entirely inside or entirely outside of every pattern, and the pieces cover the whole
+//! domain of the integer type (i.e. `-128..=127` for `i8`). Arms with `if` guards might not match, so they're
+//! never counted as covering anything.
+//!
+//! Matches that involve type errors have already been reported, and aren't checked.
+
+use crate::{
+    analysis::{
+        constraints::interval::{Interval, IntervalSet},
+        resolve::{DefId, DefKind, Resolution, module_bodies},
+        typeck::{TypeckResults, ty::Ty},
+    },
+    ast::{
+        decl::Decl,
+        expr::{Expr, MatchExpr},
+        module::Module,
+        pattern::{IntegerPattern, Pattern},
+        ty::AtomicTyVariant,
+    },
+    reporting::{Diagnostic, Highlight},
+    source_tracking::fragment::Fragment,
+};
+
+/// The most missing patterns found for a single `match` -- past this, more examples aren't useful.
+const MAX_WITNESSES: usize = 64;
+
+/// The most missing patterns listed in a diagnostic.
+const MAX_LISTED: usize = 3;
+
+/// Check that every `match` in the given modules (which must have already been type checked) is exhaustive and has
+/// no unreachable arms.
+pub fn check<'m>(
+    modules: impl IntoIterator<Item = (Vec<String>, &'m Module)>,
+    resolution: &Resolution,
+    typeck: &TypeckResults,
+) -> Vec<Diagnostic> {
+    let modules: Vec<_> = modules.into_iter().collect();
+
+    let mut checker = Checker {
+        res: resolution,
+        typeck,
+        diagnostics: Vec::new(),
+    };
+
+    for (_, items) in module_bodies(&modules) {
+        for decl in items {
+            let predicate = match decl {
+                Decl::Constraint(constraint) => constraint.predicate.as_ref().map(|p| &p.body),
+                _ => None,
+            };
+
+            let bodies = (decl.functions().iter())
+                .filter_map(|func| func.body.as_ref())
+                .chain(predicate);
+
+            for body in bodies {
+                body.for_each_expr(&mut |expr| checker.visit(expr));
+            }
+        }
+    }
+
+    checker.diagnostics
+}
+
+/// A way of building values -- what a pattern checks before looking at the values inside of it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Ctor {
+    /// `true` or `false`.
+    Bool(bool),
+    /// The integers in a range.
+    Range(Interval),
+    /// The variant of an enum with the given index.
+    Variant(usize),
+    /// A record, with a value for each of its fields.
+    Record,
+    /// The variant of a union with the given index, with a value for that variant.
+    UnionVariant(usize),
+}
+
+impl Ctor {
+    /// Check if every value this constructor builds is also built by `other`.
+    fn is_covered_by(&self, other: &Ctor) -> bool {
+        match (self, other) {
+            (Ctor::Range(inner), Ctor::Range(outer)) => outer.contains_interval(*inner),
+            _ => self == other,
+        }
+    }
+}
+
+/// A pattern, lowered so that it only refers to constructors.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Pat {
+    /// A pattern that matches every value, i.e. `_` or a binding.
+    Wild,
+    /// A constructor, with patterns for the values inside of it (one for each field of a record, and one for the
+    /// variant of a union).
+    Ctor(Ctor, Vec<Pat>),
+    /// A choice between patterns.
+    Or(Vec<Pat>),
+}
+
+impl Pat {
+    /// Check if there's any value that both patterns match.
+    fn overlaps(&self, other: &Pat) -> bool {
+        match (self, other) {
+            (Pat::Wild, _) | (_, Pat::Wild) => true,
+            (Pat::Or(alternatives), other) | (other, Pat::Or(alternatives)) => alternatives
+                .iter()
+                .any(|alternative| alternative.overlaps(other)),
+
+            (Pat::Ctor(Ctor::Range(a), _), Pat::Ctor(Ctor::Range(b), _)) => a.meet(*b).is_some(),
+
+            (Pat::Ctor(a, a_fields), Pat::Ctor(b, b_fields)) => {
+                a == b && a_fields.iter().zip(b_fields).all(|(a, b)| a.overlaps(b))
+            }
+        }
+    }
+}
+
+/// What the values of a type look like, as far as patterns are concerned.
+enum Shape {
+    /// `bool`.
+    Bool,
+    /// An integer type, with the range of values it can hold.
+    Integer(Interval),
+    /// An enum, with its variants in order.
+    Enum(DefId, Vec<DefId>),
+    /// A record, with the types of its fields.
+    Record(DefId, Vec<(String, Ty)>),
+    /// A union, with the types of its variants.
+    Union(DefId, Vec<(String, Ty)>),
+    /// Any other type, which can only be matched by `_` or a binding.
+    Opaque,
+}
+
+/// The state of exhaustiveness checking.
+struct Checker<'a> {
+    /// The result of name resolution.
+    res: &'a Resolution,
+    /// The result of type checking.
+    typeck: &'a TypeckResults,
+    /// The errors and warnings found so far.
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Checker<'_> {
+    /// Check every `match` in an expression.
+    fn visit(&mut self, expr: &Expr) {
+        if let Expr::Match(match_expr) = expr {
+            self.check_match(match_expr);
+        }
+
+        expr.for_each_child(&mut |child| self.visit(child));
+    }
+
+    /// Get the shape of a type.
+    fn shape(&self, ty: &Ty) -> Shape {
+        match ty {
+            Ty::Atomic(AtomicTyVariant::Bool) => Shape::Bool,
+
+            Ty::Atomic(variant) => match variant.integer_bounds() {
+                Some((min, max)) => Shape::Integer(Interval { lo: min, hi: max }),
+                None => Shape::Opaque,
+            },
+
+            Ty::Named { def, .. } if self.res.def(*def).kind == DefKind::Enum => {
+                Shape::Enum(*def, self.res.variants(*def).to_vec())
+            }
+
+            Ty::Named { def, .. } => {
+                let Some((is_union, fields)) = self.typeck.fields(ty) else {
+                    return Shape::Opaque;
+                };
+
+                let fields = (fields.into_iter())
+                    .map(|(name, ty)| (name.to_owned(), ty))
+                    .collect();
+
+                match is_union {
+                    true => Shape::Union(*def, fields),
+                    false => Shape::Record(*def, fields),
+                }
+            }
+
+            _ => Shape::Opaque,
+        }
+    }
+
+    /// Get the types of the values inside of a constructor of the given type.
+    fn field_tys(&self, ty: &Ty, ctor: &Ctor) -> Vec<Ty> {
+        match (self.shape(ty), ctor) {
+            (Shape::Record(_, fields), Ctor::Record) => {
+                fields.into_iter().map(|(_, ty)| ty).collect()
+            }
+
+            (Shape::Union(_, variants), Ctor::UnionVariant(index)) => {
+                vec![variants[*index].1.clone()]
+            }
+
+            _ => Vec::new(),
+        }
+    }
+
+    /// Get every constructor of a type, splitting integer ranges at the ends of the given constructors (from the
+    /// heads of a column of the pattern matrix). Returns [None] for types that can't be matched by constructors.
+    fn all_ctors(&self, ty: &Ty, heads: &[Ctor]) -> Option<Vec<Ctor>> {
+        Some(match self.shape(ty) {
+            Shape::Bool => vec![Ctor::Bool(false), Ctor::Bool(true)],
+            Shape::Integer(domain) => split(domain, heads).into_iter().map(Ctor::Range).collect(),
+            Shape::Enum(_, variants) => (0..variants.len()).map(Ctor::Variant).collect(),
+            Shape::Record(..) => vec![Ctor::Record],
+            Shape::Union(_, variants) => (0..variants.len()).map(Ctor::UnionVariant).collect(),
+            Shape::Opaque => return None,
+        })
+    }
+
+    /// Lower a pattern that matches values of the given type. Returns [None] if the pattern has an error (which
+    /// has already been reported).
+    fn lower(&self, pattern: &Pattern, ty: &Ty) -> Option<Pat> {
+        let shape = self.shape(ty);
+
+        Some(match (pattern, &shape) {
+            (Pattern::Wildcard(_) | Pattern::Binding(_), _) => Pat::Wild,
+            (Pattern::Boolean(boolean), Shape::Bool) => {
+                Pat::Ctor(Ctor::Bool(boolean.value), vec![])
+            }
+
+            (Pattern::Integer(integer), Shape::Integer(domain)) => {
+                let value = integer_value(integer, *domain)?;
+                Pat::Ctor(Ctor::Range(Interval::single(value)), vec![])
+            }
+
+            (Pattern::Range(range), Shape::Integer(domain)) => {
+                let start = integer_value(&range.start, *domain)?;
+                let end = integer_value(&range.end, *domain)?;
+                let end = if range.is_inclusive {
+                    end
+                } else {
+                    end.checked_sub(1)?
+                };
+                Pat::Ctor(Ctor::Range(Interval::new(start, end)?), vec![])
+            }
+
+            (Pattern::Variant(path), Shape::Enum(enumeration, variants)) => {
+                let variant = self.res.resolved(&path.full_path)?;
+
+                if self.res.enum_of(variant) != Some(*enumeration) {
+                    return None;
+                }
+
+                let index = variants
+                    .iter()
+                    .position(|candidate| *candidate == variant)?;
+                Pat::Ctor(Ctor::Variant(index), vec![])
+            }
+
+            (Pattern::Record(record), Shape::Record(def, fields)) => {
+                if self.res.resolved(&record.name.full_path) != Some(*def) {
+                    return None;
+                }
+
+                let mut pats = vec![None; fields.len()];
+
+                for field in &record.fields {
+                    let name = field.name.fragment.as_str();
+                    let index = fields.iter().position(|(candidate, _)| candidate == name)?;
+
+                    // Fields matched more than once have already been reported.
+                    if pats[index].is_some() {
+                        return None;
+                    }
+
+                    pats[index] = Some(self.lower(&field.pattern, &fields[index].1)?);
+                }
+
+                let pats = pats
+                    .into_iter()
+                    .map(|pat| pat.unwrap_or(Pat::Wild))
+                    .collect();
+                Pat::Ctor(Ctor::Record, pats)
+            }
+
+            (Pattern::Record(record), Shape::Union(def, variants)) => {
+                let ([field], true) = (
+                    record.fields.as_slice(),
+                    self.res.resolved(&record.name.full_path) == Some(*def),
+                ) else {
+                    return None;
+                };
+
+                let name = field.name.fragment.as_str();
+                let index = variants
+                    .iter()
+                    .position(|(candidate, _)| candidate == name)?;
+                let pat = self.lower(&field.pattern, &variants[index].1)?;
+                Pat::Ctor(Ctor::UnionVariant(index), vec![pat])
+            }
+
+            (Pattern::Or(or), _) => Pat::Or(
+                (or.alternatives.iter())
+                    .map(|alternative| self.lower(alternative, ty))
+                    .collect::<Option<_>>()?,
+            ),
+
+            // Anything else is a type error.
+            _ => return None,
+        })
+    }
+
+    /// Check a `match` expression for unreachable arms, and that it covers every value.
+    fn check_match(&mut self, match_expr: &MatchExpr) {
+        let Some(ty) = self
+            .typeck
+            .type_of_expr(match_expr.scrutinee.matching_source())
+        else {
+            return;
+        };
+
+        if ty.references_error() {
+            return;
+        }
+
+        let Some(pats) = (match_expr.arms.iter())
+            .map(|arm| self.lower(&arm.pattern, ty))
+            .collect::<Option<Vec<Pat>>>()
+        else {
+            return;
+        };
+
+        let tys = std::slice::from_ref(ty);
+
+        // The rows of the pattern matrix, with the index of the arm each came from.
+        let mut rows: Vec<(Vec<Pat>, usize)> = Vec::new();
+
+        for (index, (arm, pat)) in match_expr.arms.iter().zip(&pats).enumerate() {
+            let matrix: Vec<Vec<Pat>> = rows.iter().map(|(row, _)| row.clone()).collect();
+
+            if !self.is_useful(&matrix, tys, std::slice::from_ref(pat)) {
+                let earlier = (rows.iter())
+                    .filter(|(row, _)| row[0].overlaps(pat))
+                    .map(|(_, arm)| match_expr.arms[*arm].pattern.matching_source());
+
+                self.report_unreachable(
+                    "unreachable `match` arm",
+                    arm.pattern.matching_source(),
+                    "this arm can never be reached",
+                    earlier.map(|fragment| (fragment.clone(), "already matched by this arm")),
+                );
+            } else if let (Pat::Or(alternatives), Pattern::Or(or)) = (pat, &arm.pattern) {
+                self.check_alternatives(match_expr, &rows, alternatives, &or.alternatives);
+            }
+
+            // Guards might not be `true`, so guarded arms don't cover anything.
+            if arm.guard.is_none() {
+                rows.push((vec![pat.clone()], index));
+            }
+        }
+
+        let matrix: Vec<Vec<Pat>> = rows.iter().map(|(row, _)| row.clone()).collect();
+        let mut witnesses = self.missing(&matrix, tys);
+
+        if witnesses.is_empty() {
+            return;
+        }
+
+        witnesses.truncate(MAX_WITNESSES);
+        let witnesses: Vec<Pat> = witnesses.into_iter().map(|mut row| row.remove(0)).collect();
+        self.report_non_exhaustive(match_expr, &pats, ty, &witnesses);
+    }
+
+    /// Check each alternative of an or-pattern against the arms before it and the alternatives before it.
+    fn check_alternatives(
+        &mut self,
+        match_expr: &MatchExpr,
+        rows: &[(Vec<Pat>, usize)],
+        alternatives: &[Pat],
+        patterns: &[Pattern],
+    ) {
+        let mut matrix: Vec<Vec<Pat>> = rows.iter().map(|(row, _)| row.clone()).collect();
+
+        for (index, (alternative, pattern)) in alternatives.iter().zip(patterns).enumerate() {
+            let tys = [self
+                .typeck
+                .type_of_pattern(pattern.matching_source())
+                .cloned()];
+            let Some(ty) = &tys[0] else { continue };
+
+            if !self.is_useful(&matrix, std::slice::from_ref(ty), std::slice::from_ref(alternative))
+            {
+                let earlier_arms = (rows.iter())
+                    .filter(|(row, _)| row[0].overlaps(alternative))
+                    .map(|(_, arm)| {
+                        let fragment = match_expr.arms[*arm].pattern.matching_source();
+                        (fragment.clone(), "already matched by this arm")
+                    });
+
+                let earlier_alternatives = (alternatives[..index].iter().zip(patterns))
+                    .filter(|(earlier, _)| earlier.overlaps(alternative))
+                    .map(|(_, earlier)| {
+                        (earlier.matching_source().clone(), "already matched by this pattern")
+                    });
+
+                self.report_unreachable(
+                    "unreachable pattern",
+                    pattern.matching_source(),
+                    "this pattern can never be matched",
+                    earlier_arms.chain(earlier_alternatives).collect::<Vec<_>>(),
+                );
+            }
+
+            matrix.push(vec![alternative.clone()]);
+        }
+    }
+
+    /// Report an unreachable arm or pattern, highlighting the earlier patterns that already match its values.
+    fn report_unreachable(
+        &mut self,
+        message: &str,
+        fragment: &Fragment,
+        label: &str,
+        earlier: impl IntoIterator<Item = (Fragment, &'static str)>,
+    ) {
+        let highlights = std::iter::once(Highlight::primary(fragment.clone(), label)).chain(
+            (earlier.into_iter()).map(|(fragment, label)| Highlight::secondary(fragment, label)),
+        );
+
+        self.diagnostics.push(
+            Diagnostic::warning()
+                .with_message(message)
+                .with_highlights(highlights)
+                .with_notes([
+                    "help: remove it, or move it before the patterns that match its values",
+                ]),
+        );
+    }
+
+    /// Report a `match` that doesn't cover every value, listing some of the values it misses.
+    fn report_non_exhaustive(
+        &mut self,
+        match_expr: &MatchExpr,
+        pats: &[Pat],
+        ty: &Ty,
+        witnesses: &[Pat],
+    ) {
+        let shown: Vec<String> = (witnesses.iter().take(MAX_LISTED))
+            .map(|witness| format!("`{}`", self.display(witness, ty)))
+            .collect();
+
+        let mut listed = match shown.len() {
+            1 => shown[0].clone(),
+            2 => format!("{} and {}", shown[0], shown[1]),
+            _ => shown.join(", "),
+        };
+
+        if witnesses.len() > MAX_LISTED {
+            listed = format!("{listed}, and {} more", witnesses.len() - MAX_LISTED);
+        } else if shown.len() > 2 {
+            let (last, rest) = shown.split_last().unwrap();
+            listed = format!("{}, and {last}", rest.join(", "));
+        }
+
+        let noun = if witnesses.len() == 1 {
+            "pattern"
+        } else {
+            "patterns"
+        };
+        let first = self.display(&witnesses[0], ty);
+
+        let mut highlights = vec![Highlight::primary(
+            match_expr.scrutinee.matching_source().clone(),
+            format!("{noun} {listed} not covered"),
+        )];
+
+        // Guarded arms that could match a missing value are why it's missing.
+        for (arm, pat) in match_expr.arms.iter().zip(pats) {
+            if arm.guard.is_some() && witnesses.iter().any(|witness| witness.overlaps(pat)) {
+                highlights.push(Highlight::secondary(
+                    arm.pattern.matching_source().clone(),
+                    "this arm only matches when its guard is `true`, so it isn't counted",
+                ));
+            }
+        }
+
+        if highlights.len() == 1
+            && let Some(last) = match_expr.arms.last()
+        {
+            highlights.push(Highlight::secondary(
+                last.pattern.matching_source().clone(),
+                format!("add an arm for `{first}` after this one"),
+            ));
+        }
+
+        self.diagnostics.push(
+            Diagnostic::error()
+                .with_message(format!("non-exhaustive `match`: {noun} {listed} not covered"))
+                .with_highlights(highlights)
+                .with_notes([
+                    format!("the matched value has type `{}`", ty.display(self.res)),
+                    "help: add arms for the missing patterns, or a `_` arm to match every other value"
+                        .to_owned(),
+                ]),
+        );
+    }
+
+    /// Display a pattern that matches values of the given type, in wright syntax.
+    fn display(&self, pat: &Pat, ty: &Ty) -> String {
+        let (ctor, fields) = match pat {
+            Pat::Wild => return "_".to_owned(),
+
+            Pat::Or(alternatives) => {
+                let alternatives: Vec<String> = (alternatives.iter())
+                    .map(|alternative| self.display(alternative, ty))
+                    .collect();
+
+                return alternatives.join(" | ");
+            }
+
+            Pat::Ctor(ctor, fields) => (ctor, fields),
+        };
+
+        match (ctor, self.shape(ty)) {
+            (Ctor::Bool(value), _) => value.to_string(),
+            (Ctor::Range(interval), _) => interval.to_string(),
+
+            (Ctor::Variant(index), Shape::Enum(enumeration, variants)) => format!(
+                "{}::{}",
+                self.res.def(enumeration).name,
+                self.res.def(variants[*index]).name
+            ),
+
+            (Ctor::Record, Shape::Record(def, field_tys)) => {
+                let given: Vec<String> = (fields.iter().zip(&field_tys))
+                    .filter(|(pat, _)| **pat != Pat::Wild)
+                    .map(|(pat, (name, ty))| format!("{name}: {}", self.display(pat, ty)))
+                    .collect();
+
+                match given.is_empty() {
+                    true => format!("{} {{}}", self.res.def(def).name),
+                    false => format!("{} {{ {} }}", self.res.def(def).name, given.join(", ")),
+                }
+            }
+
+            (Ctor::UnionVariant(index), Shape::Union(def, variants)) => {
+                let (name, ty) = &variants[*index];
+                let inner = self.display(&fields[0], ty);
+                format!("{} {{ {name}: {inner} }}", self.res.def(def).name)
+            }
+
+            _ => "_".to_owned(),
+        }
+    }
+
+    /// Check if the pattern row `row` is useful after the rows of `matrix` -- if there's a value it matches that
+    /// none of them do.
+    fn is_useful(&self, matrix: &[Vec<Pat>], tys: &[Ty], row: &[Pat]) -> bool {
+        let Some((head, rest)) = row.split_first() else {
+            return matrix.is_empty();
+        };
+
+        let matrix = expand_or(matrix);
+        let heads = head_ctors(&matrix);
+
+        match head {
+            Pat::Or(alternatives) => alternatives.iter().any(|alternative| {
+                let row: Vec<Pat> = std::iter::once(alternative.clone())
+                    .chain(rest.iter().cloned())
+                    .collect();
+
+                self.is_useful(&matrix, tys, &row)
+            }),
+
+            Pat::Ctor(ctor, fields) => {
+                // Split ranges, so that each piece is either entirely inside or entirely outside of each row.
+                let ctors = match ctor {
+                    Ctor::Range(range) => {
+                        split(*range, &heads).into_iter().map(Ctor::Range).collect()
+                    }
+                    other => vec![*other],
+                };
+
+                ctors.iter().any(|ctor| {
+                    let specialized = specialize(&matrix, ctor, fields.len());
+                    let tys: Vec<Ty> = (self.field_tys(&tys[0], ctor).into_iter())
+                        .chain(tys[1..].iter().cloned())
+                        .collect();
+                    let row: Vec<Pat> = fields.iter().chain(rest).cloned().collect();
+                    self.is_useful(&specialized, &tys, &row)
+                })
+            }
+
+            Pat::Wild => match self.complete_ctors(&tys[0], &heads) {
+                Some(ctors) => ctors.iter().any(|ctor| {
+                    let field_tys = self.field_tys(&tys[0], ctor);
+                    let specialized = specialize(&matrix, ctor, field_tys.len());
+                    let row: Vec<Pat> = (field_tys.iter().map(|_| Pat::Wild))
+                        .chain(rest.iter().cloned())
+                        .collect();
+                    let tys: Vec<Ty> = field_tys
+                        .into_iter()
+                        .chain(tys[1..].iter().cloned())
+                        .collect();
+                    self.is_useful(&specialized, &tys, &row)
+                }),
+
+                None => self.is_useful(&default_matrix(&matrix), &tys[1..], rest),
+            },
+        }
+    }
+
+    /// Get the constructors of a type if every one of them is covered by the heads of a column -- in that case,
+    /// each constructor has to be checked separately.
+    fn complete_ctors(&self, ty: &Ty, heads: &[Ctor]) -> Option<Vec<Ctor>> {
+        let ctors = self.all_ctors(ty, heads)?;
+
+        ctors
+            .iter()
+            .all(|ctor| heads.iter().any(|head| ctor.is_covered_by(head)))
+            .then_some(ctors)
+    }
+
+    /// Find rows of patterns (with the given column types) that match values none of the rows of `matrix` do.
+    /// These are the patterns a `match` is missing.
+    fn missing(&self, matrix: &[Vec<Pat>], tys: &[Ty]) -> Vec<Vec<Pat>> {
+        let Some((ty, rest)) = tys.split_first() else {
+            return match matrix.is_empty() {
+                true => vec![Vec::new()],
+                false => Vec::new(),
+            };
+        };
+
+        let matrix = expand_or(matrix);
+        let heads = head_ctors(&matrix);
+        let mut witnesses = Vec::new();
+
+        if let Some(ctors) = self.complete_ctors(ty, &heads) {
+            for ctor in ctors {
+                let field_tys = self.field_tys(ty, &ctor);
+                let arity = field_tys.len();
+                let specialized = specialize(&matrix, &ctor, arity);
+                let tys: Vec<Ty> = field_tys.into_iter().chain(rest.iter().cloned()).collect();
+
+                for mut witness in self.missing(&specialized, &tys) {
+                    let rest = witness.split_off(arity);
+                    witnesses.push(
+                        std::iter::once(Pat::Ctor(ctor, witness))
+                            .chain(rest)
+                            .collect(),
+                    );
+
+                    if witnesses.len() >= MAX_WITNESSES {
+                        return merge_ranges(witnesses);
+                    }
+                }
+            }
+
+            return merge_ranges(witnesses);
+        }
+
+        let rest_witnesses = self.missing(&default_matrix(&matrix), rest);
+
+        if rest_witnesses.is_empty() {
+            return Vec::new();
+        }
+
+        // List the constructors that no row has. If no row has any constructor, `_` is clearer -- except for
+        // enums, unions, and `bool`, which have few enough constructors to list.
+        let use_wildcard = heads.is_empty()
+            && !matches!(self.shape(ty), Shape::Bool | Shape::Enum(..) | Shape::Union(..));
+
+        let missing_heads: Vec<Pat> = match (use_wildcard, self.all_ctors(ty, &heads)) {
+            (false, Some(ctors)) => (ctors.into_iter())
+                .filter(|ctor| !heads.iter().any(|head| ctor.is_covered_by(head)))
+                .map(|ctor| {
+                    let arity = self.field_tys(ty, &ctor).len();
+                    Pat::Ctor(ctor, vec![Pat::Wild; arity])
+                })
+                .collect(),
+
+            _ => vec![Pat::Wild],
+        };
+
+        'heads: for head in missing_heads {
+            for witness in &rest_witnesses {
+                witnesses.push(
+                    std::iter::once(head.clone())
+                        .chain(witness.iter().cloned())
+                        .collect(),
+                );
+
+                if witnesses.len() >= MAX_WITNESSES {
+                    break 'heads;
+                }
+            }
+        }
+
+        merge_ranges(witnesses)
+    }
+}
+
+/// Get the value of an integer pattern, if it's in the domain of its type (out of range values have already been
+/// reported).
+fn integer_value(integer: &IntegerPattern, domain: Interval) -> Option<i128> {
+    let value = i128::try_from(integer.value()).ok()?;
+    domain.contains(value).then_some(value)
+}
+
+/// Split a range of integers at the ends of every range constructor in `heads`, so that each piece is either
+/// entirely inside or entirely outside of each of them.
+fn split(range: Interval, heads: &[Ctor]) -> Vec<Interval> {
+    let mut starts = vec![range.lo];
+
+    for head in heads {
+        if let Ctor::Range(head) = head
+            && let Some(overlap) = head.meet(range)
+        {
+            starts.push(overlap.lo);
+            starts.extend(overlap.hi.checked_add(1).filter(|next| *next <= range.hi));
+        }
+    }
+
+    starts.sort_unstable();
+    starts.dedup();
+
+    let ends = starts
+        .iter()
+        .skip(1)
+        .map(|start| start - 1)
+        .chain([range.hi]);
+    starts
+        .iter()
+        .zip(ends)
+        .map(|(lo, hi)| Interval { lo: *lo, hi })
+        .collect()
+}
+
+/// Replace every row of a matrix that starts with an or-pattern with one row for each alternative.
+fn expand_or(matrix: &[Vec<Pat>]) -> Vec<Vec<Pat>> {
+    let mut expanded = Vec::with_capacity(matrix.len());
+
+    for row in matrix {
+        match row.split_first() {
+            Some((Pat::Or(alternatives), rest)) => {
+                let rows: Vec<Vec<Pat>> = (alternatives.iter())
+                    .map(|alternative| {
+                        std::iter::once(alternative.clone()).chain(rest.iter().cloned())
+                    })
+                    .map(Iterator::collect)
+                    .collect();
+
+                expanded.extend(expand_or(&rows));
+            }
+
+            _ => expanded.push(row.clone()),
+        }
+    }
+
+    expanded
+}
+
+/// Get the constructors at the heads of the rows of a matrix (which must not start with or-patterns).
+fn head_ctors(matrix: &[Vec<Pat>]) -> Vec<Ctor> {
+    (matrix.iter())
+        .filter_map(|row| match row.first() {
+            Some(Pat::Ctor(ctor, _)) => Some(*ctor),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Keep the rows of a matrix that match values built with `ctor`, replacing their first pattern with the patterns
+/// for the values inside of it.
+fn specialize(matrix: &[Vec<Pat>], ctor: &Ctor, arity: usize) -> Vec<Vec<Pat>> {
+    (matrix.iter())
+        .filter_map(|row| {
+            let (head, rest) = row.split_first()?;
+
+            let fields = match head {
+                Pat::Ctor(head, fields) if ctor.is_covered_by(head) => fields.clone(),
+                Pat::Wild => vec![Pat::Wild; arity],
+                _ => return None,
+            };
+
+            Some(fields.into_iter().chain(rest.iter().cloned()).collect())
+        })
+        .collect()
+}
+
+/// Keep the rows of a matrix that start with a wildcard, without it.
+fn default_matrix(matrix: &[Vec<Pat>]) -> Vec<Vec<Pat>> {
+    (matrix.iter())
+        .filter(|row| row.first() == Some(&Pat::Wild))
+        .map(|row| row[1..].to_vec())
+        .collect()
+}
+
+/// Merge missing rows that start with adjacent integer ranges and are otherwise the same, i.e. `0..=9` and
+/// `10..=20` into `0..=20`.
+fn merge_ranges(witnesses: Vec<Vec<Pat>>) -> Vec<Vec<Pat>> {
+    let mut merged: Vec<(Vec<Pat>, Vec<Interval>)> = Vec::new();
+    let mut others = Vec::new();
+
+    for mut witness in witnesses {
+        let Some(Pat::Ctor(Ctor::Range(range), _)) = witness.first() else {
+            others.push(witness);
+            continue;
+        };
+
+        let range = *range;
+        let rest = witness.split_off(1);
+
+        match merged.iter_mut().find(|(other, _)| *other == rest) {
+            Some((_, ranges)) => ranges.push(range),
+            None => merged.push((rest, vec![range])),
+        }
+    }
+
+    let ranges = merged.into_iter().flat_map(|(rest, ranges)| {
+        let set = IntervalSet::from_intervals(ranges);
+
+        (set.intervals().to_vec().into_iter()).map(move |range| {
+            std::iter::once(Pat::Ctor(Ctor::Range(range), Vec::new()))
+                .chain(rest.iter().cloned())
+                .collect()
+        })
+    });
+
+    ranges.chain(others).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check;
+    use crate::{
+        analysis::testing::{check_valid_source, messages, modules},
+        reporting::{Diagnostic, Severity},
+    };
+
+    fn check_source(source: &'static str) -> Vec<Diagnostic> {
+        let (module, resolution, typeck) = check_valid_source(source);
+        check(modules(&module), &resolution, &typeck)
+    }
+
+    #[test]
+    fn test_exhaustive() {
+        let diagnostics = check_source(
+            "enum Color { Red, Green, Blue }\n\
+            union Number { small: u8, big: u64 }\n\
+            record Point { x: bool, y: bool }\n\
+            func main(c: Color, n: Number, p: Point, b: i8) -> u8 {\n\
+                let a = match c { Color::Red | Color::Green => 1, Color::Blue => 2 };\n\
+                let d = match n { Number { small: 0..=9 } => 1, Number { small } => small, Number { big: _ } => 3 };\n\
+                let e = match p { Point { x: true } => 1, Point { y: true } => 2, Point { x: false, y: false } => 3 };\n\
+                let f = match b { -128..0 => 1, 0 => 2, 1..=127 => 3 };\n\
+                a + d + e + f\n\
+            }",
+        );
+
+        assert!(diagnostics.is_empty(), "{:?}", messages(&diagnostics));
+    }
+
+    #[test]
+    fn test_missing_variants() {
+        let diagnostics = check_source(
+            "enum Color { Red, Green, Blue }\n\
+            func main(c: Color) -> u8 { match c { Color::Green => 1 } }",
+        );
+
+        assert_eq!(
+            messages(&diagnostics),
+            ["non-exhaustive `match`: patterns `Color::Red` and `Color::Blue` not covered"]
+        );
+
+        let labels = &diagnostics[0].0.labels;
+        assert_eq!(labels.len(), 2);
+        assert_eq!(labels[1].message, "add an arm for `Color::Red` after this one");
+    }
+
+    #[test]
+    fn test_integers() {
+        let diagnostics = check_source(
+            "func main(a: i8, b: u64, c: u8) -> u8 {\n\
+                let x = match a { -128..=-1 => 1, 1..=100 => 2 };\n\
+                let y = match b { 0 => 1, 2..=18446744073709551615 => 2 };\n\
+                let z = match c { _ => 1 };\n\
+                x + y + z\n\
+            }",
+        );
+
+        assert_eq!(
+            messages(&diagnostics),
+            [
+                "non-exhaustive `match`: patterns `0` and `101..=127` not covered",
+                "non-exhaustive `match`: pattern `1` not covered",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_nested() {
+        let diagnostics = check_source(
+            "union Number { small: u8, big: u64 }\n\
+            record Point { x: bool, y: bool }\n\
+            func main(n: Number, p: Point) -> u8 {\n\
+                let a = match n { Number { small: 0..=9 } => 1, Number { big: _ } => 2 };\n\
+                let b = match p { Point { x: true, y: false } => 1, Point { x: false } => 2 };\n\
+                a + b\n\
+            }",
+        );
+
+        assert_eq!(
+            messages(&diagnostics),
+            [
+                "non-exhaustive `match`: pattern `Number { small: 10..=255 }` not covered",
+                "non-exhaustive `match`: pattern `Point { x: true, y: true }` not covered",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_guards() {
+        let diagnostics = check_source(
+            "func main(a: bool, limit: u8) -> u8 {\n\
+                match a { true if limit > 2 => 1, false => 2 }\n\
+            }",
+        );
+
+        assert_eq!(messages(&diagnostics), ["non-exhaustive `match`: pattern `true` not covered"]);
+
+        let labels = &diagnostics[0].0.labels;
+        assert_eq!(labels.len(), 2);
+        assert_eq!(
+            labels[1].message,
+            "this arm only matches when its guard is `true`, so it isn't counted"
+        );
+    }
+
+    #[test]
+    fn test_many_missing() {
+        let diagnostics = check_source(
+            "enum Digit { Zero, One, Two, Three, Four, Five }\n\
+            func main(d: Digit) -> u8 { match d { Digit::Zero => 0 } }",
+        );
+
+        assert_eq!(
+            messages(&diagnostics),
+            [
+                "non-exhaustive `match`: patterns `Digit::One`, `Digit::Two`, `Digit::Three`, and 2 more not covered"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unreachable() {
+        let diagnostics = check_source(
+            "enum Color { Red, Green, Blue }\n\
+            func main(c: Color, n: u8) -> u8 {\n\
+                let a = match c { Color::Red => 1, _ => 2, Color::Blue => 3 };\n\
+                let b = match n { 0..=10 => 1, 5 | 11 => 2, 3..=7 => 3, _ => 4 };\n\
+                a + b\n\
+            }",
+        );
+
+        assert_eq!(
+            messages(&diagnostics),
+            [
+                "unreachable `match` arm",
+                "unreachable pattern",
+                "unreachable `match` arm"
+            ]
+        );
+        assert!(
+            diagnostics
+                .iter()
+                .all(|d| d.0.severity == Severity::Warning)
+        );
+
+        let labels = &diagnostics[0].0.labels;
+        assert_eq!(labels[0].message, "this arm can never be reached");
+        assert_eq!(labels[1].message, "already matched by this arm");
+        assert_eq!(labels.len(), 2);
+
+        // `5` is unreachable, but `11` isn't.
+        let labels = &diagnostics[1].0.labels;
+        assert_eq!(labels[0].message, "this pattern can never be matched");
+        assert_eq!(labels.len(), 2);
+
+        // `3..=7` overlaps both `0..=10` and `5`.
+        assert_eq!(diagnostics[2].0.labels.len(), 3);
+    }
+}
@@ -0,0 +1,1003 @@
+//! Computing how values of each type are laid out in memory -- their size, their alignment, and the offset of each
+//! of their fields -- for a target with a given pointer size.
+//!
+//! Values are laid out like this:
+//! - Atomic types are as large as they are wide, and aligned to their size (`bool` takes a byte, and `char` takes
+//!   four). References are a pointer, string slices are a pointer followed by a length, and functions are a pointer
+//!   to their code followed by a pointer to their environment.
+//! - Records are their fields, each at an offset aligned for it. Unless the record has a `repr(c)` or
+//!   `repr(packed)` clause, its fields are sorted by alignment (largest first, and otherwise in declaration order)
+//!   so that as little padding as possible is needed between them.
+//! - Enums are a tag: the index of the variant, in the smallest unsigned integer type that counts every variant.
+//!   Enums with fewer than two variants don't need a tag, and take no space.
+//! - Unions are a tag followed by the payload of their variant, at an offset aligned for every variant. A union
+//!   with a single variant is just its payload.
+//!
+//! A [`repr(...)`](crate::ast::decl::repr::Repr) clause changes this: `repr(c)` lays a type out the way C would,
+//! with fields in declaration order and a `u32` tag. An integer type in the clause (like `repr(u8)`) is used for the
+//! tag instead, and `repr(packed)` aligns the type and its fields to a single byte, leaving no padding.
+//!
+//! # Niches
+//!
+//! Some types have bit patterns that are never valid values -- a `bool` is only ever 0 or 1, a `char` is never past
+//! `0x10FFFF`, and a reference is never null. The largest range of these invalid values in a type is its [Niche]
+//! (records use the largest niche of any of their fields, and unions and enums use the values their tag never
+//! takes).
+//!
+//! A union without a `repr(c)` or integer `repr` clause, where every variant but one takes no space, doesn't need a
+//! tag if that one variant has a niche with enough invalid values for the others: the union is stored as that
+//! variant's payload, and each of the other variants as one of the payload's invalid values. This is how
+//! `union Option<T> { some: T, none: Nothing }` (with `record Nothing {}`) is no larger than a reference when `T`
+//! is one, since `none` is stored as a null pointer.
+//!
+//! # Backends
+//!
+//! None of the [code generation](crate::codegen) backends use these layouts yet: each of them still lays values out
+//! its own way (records' fields in declaration order, enums as an `i32`, and unions as an `i32` tag followed by
+//! 64-bit words), and ignores `repr(...)` clauses. Until they're moved over, these layouts describe what the
+//! language allows rather than what compiled programs do.
+
+use crate::{
+    analysis::{
+        resolve::{DefId, DefKind, Resolution, module_bodies},
+        typeck::{TypeckResults, ty::Ty},
+    },
+    ast::{
+        decl::Decl,
+        module::Module,
+        ty::{AtomicTyVariant, Type},
+    },
+};
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::HashMap,
+    fmt,
+    rc::Rc,
+};
+
+/// The size of pointers (in bytes) on the targets that layouts are computed for, unless another size is given.
+pub const DEFAULT_POINTER_SIZE: u64 = 8;
+
+/// How deeply named types can be nested inside of each other before a type is treated as [recursive], since a
+/// generic type can contain ever larger instances of itself.
+///
+/// [recursive]: LayoutError::Recursive
+const RECURSION_LIMIT: usize = 64;
+
+/// How values of a type are laid out in memory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Layout {
+    /// The size of values of the type in bytes, which is always a multiple of its alignment.
+    pub size: u64,
+
+    /// The alignment of values of the type in bytes, which is always a power of two.
+    pub align: u64,
+
+    /// The fields of a record (in declaration order), or the payloads of the variants of a union or enum (in
+    /// declaration order, where the variants of enums have `void` payloads).
+    pub fields: Vec<FieldLayout>,
+
+    /// How the variant of a union or enum is told apart from the others.
+    pub variants: Variants,
+
+    /// The largest range of invalid values in the type, if it has any invalid values.
+    pub niche: Option<Niche>,
+}
+
+/// Where a field of a record (or the payload of a variant of a union) is, and how it's laid out.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldLayout {
+    /// The name of the field or variant.
+    pub name: String,
+
+    /// The type of the field or the variant's payload.
+    pub ty: Ty,
+
+    /// The offset of the field from the start of the value, in bytes.
+    pub offset: u64,
+
+    /// The layout of the field.
+    pub layout: Rc<Layout>,
+}
+
+/// How the variant of a union or enum is told apart from the others.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Variants {
+    /// The type isn't a union or enum.
+    Fields,
+
+    /// The type is a union or enum with fewer than two variants and without an integer `repr` hint, which doesn't
+    /// need a tag.
+    Single,
+
+    /// The index of the variant is stored as an integer (the tag) at the start of the value.
+    Tagged {
+        /// The integer type of the tag.
+        tag: AtomicTyVariant,
+    },
+
+    /// The type is stored as the payload of one variant, and each other variant as an invalid value of the
+    /// payload's niche.
+    Niche {
+        /// The index of the variant whose payload holds the niche.
+        dataful: usize,
+
+        /// The niche of the payload of the dataful variant, before any of its invalid values are used (but with its
+        /// offset from the start of the union).
+        niche: Niche,
+
+        /// The value that the first variant (other than the dataful one) is stored as -- the rest follow in order.
+        start: u128,
+    },
+}
+
+impl Variants {
+    /// Get the value that's stored to mark the variant with the given index: its tag, or the invalid value of the
+    /// niche it's stored as. Returns [None] for the dataful variant of a niche, and when there's no tag or niche.
+    pub fn value_of(&self, variant: usize) -> Option<u128> {
+        match self {
+            Variants::Fields | Variants::Single => None,
+            Variants::Tagged { .. } => Some(variant as u128),
+            Variants::Niche { dataful, start, .. } => match variant.cmp(dataful) {
+                Ordering::Less => Some(start + variant as u128),
+                Ordering::Equal => None,
+                Ordering::Greater => Some(start + variant as u128 - 1),
+            },
+        }
+    }
+}
+
+/// An integer in a type (of at most 8 bytes) that only some of the bit patterns of are valid values of, which leaves
+/// the rest for the [variants of unions](Variants::Niche) that contain the type.
+///
+/// The valid values are a single range of unsigned integers, which never covers every bit pattern.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Niche {
+    /// The offset of the integer from the start of the type, in bytes.
+    pub offset: u64,
+
+    /// The size of the integer in bytes.
+    pub size: u64,
+
+    /// The smallest valid value.
+    pub start: u128,
+
+    /// The largest valid value.
+    pub end: u128,
+}
+
+impl Niche {
+    /// Make a niche of an integer with the given valid values, unless they're every bit pattern of the integer.
+    fn new(offset: u64, size: u64, start: u128, end: u128) -> Option<Niche> {
+        let niche = Niche {
+            offset,
+            size,
+            start,
+            end,
+        };
+
+        (niche.available() > 0).then_some(niche)
+    }
+
+    /// Get the largest value of the integer.
+    fn max(self) -> u128 {
+        u128::MAX >> (128 - 8 * self.size)
+    }
+
+    /// Get the number of invalid values.
+    pub fn available(self) -> u128 {
+        self.max() - self.end + self.start
+    }
+
+    /// Take `count` consecutive invalid values from just past the end of the valid values (or just before the start
+    /// of them, if there aren't enough past the end). Returns the first value taken, and what's left of the niche.
+    fn reserve(self, count: u128) -> Option<(u128, Option<Niche>)> {
+        let (first, start, end) = if self.max() - self.end >= count {
+            (self.end + 1, self.start, self.end + count)
+        } else if self.start >= count {
+            (self.start - count, self.start - count, self.end)
+        } else {
+            return None;
+        };
+
+        Some((first, Niche::new(self.offset, self.size, start, end)))
+    }
+
+    /// Move the niche to a later offset, for a field at that offset.
+    fn shifted(self, offset: u64) -> Niche {
+        Niche {
+            offset: self.offset + offset,
+            ..self
+        }
+    }
+}
+
+/// Why a type can't be laid out.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LayoutError {
+    /// The type isn't fully known -- it is (or contains) a generic parameter, or a type that wasn't inferred.
+    Unknown(Ty),
+
+    /// The type is (or contains) an abstract type (`type Name;`), which doesn't say what its values are.
+    Abstract(DefId),
+
+    /// The type contains itself without a reference in between, so its values would be infinitely large.
+    Recursive(DefId),
+
+    /// The integer type given to the tag of a union or enum by its `repr` clause can't count all of its variants.
+    TagTooSmall {
+        /// The union or enum.
+        def: DefId,
+        /// The integer type of the tag.
+        tag: AtomicTyVariant,
+        /// The number of variants.
+        variants: usize,
+    },
+}
+
+impl LayoutError {
+    /// Get a message describing this error, using the [Resolution] to look up names.
+    pub fn message(&self, resolution: &Resolution) -> String {
+        let name = |def: DefId| &resolution.def(def).name;
+
+        match self {
+            LayoutError::Unknown(ty) => {
+                format!("the layout of `{}` isn't known", ty.display(resolution))
+            }
+
+            LayoutError::Abstract(def) => {
+                format!("`{}` is an abstract type, which has no layout", name(*def))
+            }
+
+            LayoutError::Recursive(def) => format!(
+                "`{}` contains itself without a reference in between, so it has no finite size",
+                name(*def)
+            ),
+
+            LayoutError::TagTooSmall { def, tag, variants } => format!(
+                "a `{}` tag can't count the {variants} variants of `{}`",
+                tag.name(),
+                name(*def)
+            ),
+        }
+    }
+}
+
+/// The hints in the `repr(...)` clause of a record, union, or enum.
+#[derive(Clone, Copy, Debug, Default)]
+struct ReprOptions {
+    /// Whether the clause has a `c` hint.
+    c: bool,
+    /// Whether the clause has a `packed` hint.
+    packed: bool,
+    /// The integer type given to the tag, if there is one.
+    int: Option<AtomicTyVariant>,
+}
+
+/// Computes (and remembers) the layouts of the types of a package.
+#[derive(Debug)]
+pub struct LayoutCx<'a> {
+    /// The resolution of the package.
+    resolution: &'a Resolution,
+    /// The type checking results of the package.
+    typeck: &'a TypeckResults,
+    /// The size of pointers on the target, in bytes.
+    pointer_size: u64,
+    /// The `repr(...)` clause of every record, union, and enum that has one.
+    reprs: HashMap<DefId, ReprOptions>,
+    /// The layout of every type that's been laid out.
+    layouts: HashMap<Ty, Rc<Layout>>,
+    /// The named types that are being laid out, innermost last.
+    in_progress: Vec<Ty>,
+}
+
+impl<'a> LayoutCx<'a> {
+    /// Make a context for laying out the types of the given modules, which must have already been resolved and type
+    /// checked, for a target with the given pointer size (in bytes, which must be 1, 2, 4, or 8).
+    pub fn new<'m>(
+        modules: impl IntoIterator<Item = (Vec<String>, &'m Module)>,
+        resolution: &'a Resolution,
+        typeck: &'a TypeckResults,
+        pointer_size: u64,
+    ) -> Self {
+        assert!(
+            pointer_size.is_power_of_two() && pointer_size <= 8,
+            "unsupported pointer size {pointer_size}"
+        );
+
+        let modules: Vec<_> = modules.into_iter().collect();
+        let mut reprs = HashMap::new();
+
+        for (_, items) in module_bodies(&modules) {
+            for decl in items {
+                let (name, repr) = match decl {
+                    Decl::Record(record) => (&record.name, &record.repr),
+                    Decl::Union(union) => (&union.name, &union.repr),
+                    Decl::Enum(enumeration) => (&enumeration.name, &enumeration.repr),
+                    _ => continue,
+                };
+
+                let (Some(repr), Some(def)) = (repr, resolution.declared(&name.fragment)) else {
+                    continue;
+                };
+
+                let options = ReprOptions {
+                    c: repr.is_c(),
+                    packed: repr.is_packed(),
+                    int: repr.int(),
+                };
+
+                reprs.insert(def, options);
+            }
+        }
+
+        LayoutCx {
+            resolution,
+            typeck,
+            pointer_size,
+            reprs,
+            layouts: HashMap::new(),
+            in_progress: Vec::new(),
+        }
+    }
+
+    /// Get the layout of a type.
+    pub fn layout_of(&mut self, ty: &Ty) -> Result<Rc<Layout>, LayoutError> {
+        if let Some(layout) = self.layouts.get(ty) {
+            return Ok(layout.clone());
+        }
+
+        let layout = Rc::new(self.compute(ty)?);
+        self.layouts.insert(ty.clone(), layout.clone());
+        Ok(layout)
+    }
+
+    /// Lay out a type that hasn't been laid out yet.
+    fn compute(&mut self, ty: &Ty) -> Result<Layout, LayoutError> {
+        let pointer = self.pointer_size;
+
+        match ty {
+            Ty::Atomic(variant) => Ok(atomic(*variant)),
+            Ty::Reference { .. } => Ok(scalar(pointer, Niche::new(0, pointer, 1, u64::MAX.into()))),
+
+            // A pointer and a length, or a pointer to code and a pointer to an environment (which is null for
+            // functions that don't capture anything). Only the first pointer is never null.
+            Ty::Str | Ty::Function { .. } => Ok(Layout {
+                size: 2 * pointer,
+                ..scalar(pointer, Niche::new(0, pointer, 1, u64::MAX.into()))
+            }),
+
+            Ty::Void => Ok(scalar(0, None)),
+
+            Ty::Named { def, .. } => {
+                if self.in_progress.contains(ty) || self.in_progress.len() >= RECURSION_LIMIT {
+                    return Err(LayoutError::Recursive(*def));
+                }
+
+                self.in_progress.push(ty.clone());
+                let layout = self.named(ty, *def);
+                self.in_progress.pop();
+                layout
+            }
+
+            Ty::Param(_) | Ty::Infer(_) | Ty::Integer(_) | Ty::Error => {
+                Err(LayoutError::Unknown(ty.clone()))
+            }
+        }
+    }
+
+    /// Lay out a record, union, or enum.
+    fn named(&mut self, ty: &Ty, def: DefId) -> Result<Layout, LayoutError> {
+        let repr = self.reprs.get(&def).copied().unwrap_or_default();
+
+        if self.resolution.def(def).kind == DefKind::Enum {
+            return self.enumeration(def, repr);
+        }
+
+        let typeck = self.typeck;
+
+        let Some((is_union, fields)) = typeck.fields(ty) else {
+            return Err(LayoutError::Abstract(def));
+        };
+
+        let fields = (fields.into_iter())
+            .map(|(name, ty)| {
+                let layout = self.layout_of(&ty)?;
+
+                Ok(FieldLayout {
+                    name: name.to_owned(),
+                    ty,
+                    offset: 0,
+                    layout,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        match is_union {
+            true => self.union(def, repr, fields),
+            false => Ok(record(repr, fields)),
+        }
+    }
+
+    /// Lay out an enum, whose variants are all stored as a tag.
+    fn enumeration(&self, def: DefId, repr: ReprOptions) -> Result<Layout, LayoutError> {
+        let fields = (self.resolution.variants(def).iter())
+            .map(|variant| FieldLayout {
+                name: self.resolution.def(*variant).name.clone(),
+                ty: Ty::Void,
+                offset: 0,
+                layout: Rc::new(scalar(0, None)),
+            })
+            .collect();
+
+        self.tagged(def, repr, fields)
+    }
+
+    /// Lay out a union, using a niche instead of a tag if it can.
+    fn union(
+        &self,
+        def: DefId,
+        repr: ReprOptions,
+        mut variants: Vec<FieldLayout>,
+    ) -> Result<Layout, LayoutError> {
+        if repr.c || repr.int.is_some() {
+            return self.tagged(def, repr, variants);
+        }
+
+        let mut dataful =
+            (variants.iter().enumerate()).filter(|(_, variant)| variant.layout.size > 0);
+
+        let (Some((index, variant)), None) = (dataful.next(), dataful.next()) else {
+            return self.tagged(def, repr, variants);
+        };
+
+        let others = variants.len() as u128 - 1;
+
+        let Some(niche) = variant.layout.niche else {
+            return self.tagged(def, repr, variants);
+        };
+
+        let Some((start, rest)) = niche.reserve(others) else {
+            return self.tagged(def, repr, variants);
+        };
+
+        let (size, align) = (variant.layout.size, variant.layout.align);
+
+        // Zero-sized variants are stored as the niche value alone, so their payloads overlap the dataful one.
+        for variant in &mut variants {
+            variant.offset = 0;
+        }
+
+        Ok(Layout {
+            size,
+            align: if repr.packed { 1 } else { align },
+            fields: variants,
+            variants: match others {
+                0 => Variants::Single,
+                _ => Variants::Niche {
+                    dataful: index,
+                    niche,
+                    start,
+                },
+            },
+            niche: rest,
+        })
+    }
+
+    /// Lay out a union or enum with a tag, followed by the payloads of the variants (or by nothing, for enums).
+    fn tagged(
+        &self,
+        def: DefId,
+        repr: ReprOptions,
+        mut variants: Vec<FieldLayout>,
+    ) -> Result<Layout, LayoutError> {
+        let count = variants.len();
+
+        let tag = match (repr.int, repr.c) {
+            (Some(int), _) => int,
+            (None, true) => AtomicTyVariant::U32,
+            (None, false) if count < 2 => return Ok(untagged(repr, variants)),
+            (None, false) => smallest_unsigned(count),
+        };
+
+        let (_, max) = tag.integer_bounds().unwrap();
+
+        if count as i128 - 1 > max {
+            return Err(LayoutError::TagTooSmall {
+                def,
+                tag,
+                variants: count,
+            });
+        }
+
+        let tag_layout = atomic(tag);
+        let payload_align = match repr.packed {
+            true => 1,
+            false => (variants.iter())
+                .map(|variant| variant.layout.align)
+                .max()
+                .unwrap_or(1),
+        };
+
+        let payload_offset = align_to(tag_layout.size, payload_align);
+        let payload_size = (variants.iter())
+            .map(|variant| variant.layout.size)
+            .max()
+            .unwrap_or(0);
+
+        let align = match repr.packed {
+            true => 1,
+            false => tag_layout.align.max(payload_align),
+        };
+
+        for variant in &mut variants {
+            variant.offset = payload_offset;
+        }
+
+        Ok(Layout {
+            size: align_to(payload_offset + payload_size, align),
+            align,
+            fields: variants,
+            variants: Variants::Tagged { tag },
+            niche: Niche::new(0, tag_layout.size, 0, count.saturating_sub(1) as u128),
+        })
+    }
+}
+
+/// Lay out a record, sorting its fields by alignment unless its `repr(...)` clause says not to.
+fn record(repr: ReprOptions, mut fields: Vec<FieldLayout>) -> Layout {
+    let mut order: Vec<usize> = (0..fields.len()).collect();
+
+    if !repr.c && !repr.packed {
+        order.sort_by_key(|index| Reverse(fields[*index].layout.align));
+    }
+
+    let (mut end, mut align) = (0, 1);
+
+    for index in order {
+        let field = &mut fields[index];
+        let field_align = if repr.packed { 1 } else { field.layout.align };
+
+        field.offset = align_to(end, field_align);
+        end = field.offset + field.layout.size;
+        align = align.max(field_align);
+    }
+
+    let niche = largest_niche(&fields);
+
+    Layout {
+        size: align_to(end, align),
+        align,
+        fields,
+        variants: Variants::Fields,
+        niche,
+    }
+}
+
+/// Lay out a union or enum with fewer than two variants, which is just its payload (if it has one).
+fn untagged(repr: ReprOptions, variants: Vec<FieldLayout>) -> Layout {
+    let (size, align) =
+        (variants.first()).map_or((0, 1), |variant| (variant.layout.size, variant.layout.align));
+
+    Layout {
+        size,
+        align: if repr.packed { 1 } else { align },
+        niche: largest_niche(&variants),
+        fields: variants,
+        variants: Variants::Single,
+    }
+}
+
+/// Get the largest niche of any of the given fields (the first one declared, if some are just as large), at its
+/// offset from the start of the type that contains the fields.
+fn largest_niche(fields: &[FieldLayout]) -> Option<Niche> {
+    (fields.iter())
+        .filter_map(|field| Some(field.layout.niche?.shifted(field.offset)))
+        .fold(None, |largest: Option<Niche>, niche| match largest {
+            Some(largest) if largest.available() >= niche.available() => Some(largest),
+            _ => Some(niche),
+        })
+}
+
+/// Get the layout of an atomic type.
+fn atomic(variant: AtomicTyVariant) -> Layout {
+    use AtomicTyVariant::*;
+
+    let size = match variant {
+        Bool | U8 | I8 => 1,
+        U16 | I16 => 2,
+        U32 | I32 | F32 | Char => 4,
+        U64 | I64 | F64 => 8,
+    };
+
+    let niche = match variant {
+        Bool => Niche::new(0, size, 0, 1),
+        Char => Niche::new(0, size, 0, char::MAX as u128),
+        _ => None,
+    };
+
+    scalar(size, niche)
+}
+
+/// Get the layout of a value without fields or variants, aligned to its size (or to 1, for zero-sized values).
+fn scalar(size: u64, niche: Option<Niche>) -> Layout {
+    Layout {
+        size,
+        align: size.max(1),
+        fields: Vec::new(),
+        variants: Variants::Fields,
+        niche,
+    }
+}
+
+/// Get the smallest unsigned integer type that can count the given number of variants.
+fn smallest_unsigned(count: usize) -> AtomicTyVariant {
+    match count as u64 {
+        0..=0x100 => AtomicTyVariant::U8,
+        0x101..=0x1_0000 => AtomicTyVariant::U16,
+        0x1_0001..=0x1_0000_0000 => AtomicTyVariant::U32,
+        _ => AtomicTyVariant::U64,
+    }
+}
+
+/// Round an offset up to a multiple of an alignment (which must be a power of two).
+const fn align_to(offset: u64, align: u64) -> u64 {
+    (offset + align - 1) & !(align - 1)
+}
+
+/// Lower a type signature written outside of a package (such as on the command line) into a [Ty], looking up names
+/// from the root module of the package. Returns a message describing the problem if it doesn't name a type.
+pub fn lower_signature(
+    ty: &Type,
+    resolution: &Resolution,
+    typeck: &TypeckResults,
+) -> Result<Ty, String> {
+    let lower = |ty| lower_signature(ty, resolution, typeck);
+
+    match ty {
+        Type::Atomic(atomic) => Ok(Ty::Atomic(atomic.variant)),
+        Type::Reference(reference) => {
+            Ok(Ty::reference(lower(&reference.target_ty)?, reference.is_mutable))
+        }
+        Type::Constrained(constrained) => lower(&constrained.base_ty),
+
+        Type::Function(function) => Ok(Ty::Function {
+            params: function
+                .params
+                .iter()
+                .map(lower)
+                .collect::<Result<_, _>>()?,
+            ret: Box::new(match &function.return_ty {
+                Some(return_ty) => lower(return_ty)?,
+                None => Ty::Void,
+            }),
+        }),
+
+        Type::Named(named) => {
+            let path = named.name.full_path.as_str();
+            let segments: Vec<&str> = std::iter::once(&named.name.head)
+                .chain(&named.name.tail)
+                .map(|segment| segment.fragment.as_str())
+                .collect();
+
+            let def = (resolution.lookup(&[], &segments))
+                .ok_or_else(|| format!("cannot find type `{path}`"))?;
+
+            let args: Vec<Ty> = named
+                .generic_tys
+                .iter()
+                .map(lower)
+                .collect::<Result<_, _>>()?;
+
+            let (ty, expected_args) = match resolution.def(def).kind {
+                DefKind::Record | DefKind::Union => {
+                    let generics = typeck.generics(def).map_or(0, <[DefId]>::len);
+                    (Ty::Named { def, args }, generics)
+                }
+
+                DefKind::Enum => (Ty::Named { def, args }, 0),
+                DefKind::TypeAlias => (typeck.alias(def).cloned().unwrap_or(Ty::Error), 0),
+                kind => return Err(format!("expected type, found {} `{path}`", kind.describe())),
+            };
+
+            if named.generic_tys.len() != expected_args {
+                return Err(format!(
+                    "`{path}` takes {expected_args} generic argument(s), but {} were given",
+                    named.generic_tys.len()
+                ));
+            }
+
+            Ok(ty)
+        }
+    }
+}
+
+impl Layout {
+    /// Get a value that can be used to display this layout, with a line for its size and alignment, a line for each
+    /// of its fields or variants (in order of their offsets), and a line for its niche.
+    pub fn display<'a>(&'a self, resolution: &'a Resolution) -> DisplayLayout<'a> {
+        DisplayLayout {
+            layout: self,
+            resolution,
+        }
+    }
+}
+
+/// A [Layout] that can be displayed. See [Layout::display].
+#[derive(Clone, Copy, Debug)]
+pub struct DisplayLayout<'a> {
+    layout: &'a Layout,
+    resolution: &'a Resolution,
+}
+
+impl fmt::Display for DisplayLayout<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let layout = self.layout;
+        writeln!(f, "size {}, align {}", layout.size, layout.align)?;
+
+        match &layout.variants {
+            Variants::Fields if layout.fields.is_empty() => {}
+            Variants::Fields => writeln!(f, "fields:")?,
+            Variants::Single => writeln!(f, "variants, without a tag:")?,
+            Variants::Tagged { tag } => {
+                writeln!(f, "variants, told apart by a `{}` tag at offset 0:", tag.name())?
+            }
+
+            Variants::Niche { dataful, niche, .. } => writeln!(
+                f,
+                "variants, told apart by the niche of `{}` at offset {}:",
+                layout.fields[*dataful].name, niche.offset
+            )?,
+        }
+
+        let mut order: Vec<usize> = (0..layout.fields.len()).collect();
+        order.sort_by_key(|index| layout.fields[*index].offset);
+
+        for index in order {
+            let field = &layout.fields[index];
+
+            write!(
+                f,
+                "  {:>4}  {}: {} (size {}, align {})",
+                field.offset,
+                field.name,
+                field.ty.display(self.resolution),
+                field.layout.size,
+                field.layout.align
+            )?;
+
+            match (&layout.variants, layout.variants.value_of(index)) {
+                (Variants::Tagged { .. }, Some(value)) => write!(f, ", tag {value}")?,
+                (Variants::Niche { .. }, Some(value)) => write!(f, ", stored as {value:#x}")?,
+                _ => {}
+            }
+
+            writeln!(f)?;
+        }
+
+        match layout.niche {
+            Some(niche) => writeln!(
+                f,
+                "niche: {} byte(s) at offset {}, valid {:#x}..={:#x} ({} invalid value(s))",
+                niche.size,
+                niche.offset,
+                niche.start,
+                niche.end,
+                niche.available()
+            ),
+
+            None => writeln!(f, "no niche"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DEFAULT_POINTER_SIZE, LayoutCx, LayoutError, Niche, Variants, lower_signature};
+    use crate::{
+        analysis::{
+            resolve::Resolution,
+            testing::check_valid_source,
+            typeck::{TypeckResults, ty::Ty},
+        },
+        ast::{module::Module, ty::Type},
+        lexer::Lexer,
+        parser::Parser,
+    };
+
+    /// A package of a single module, checked without errors.
+    struct Package {
+        module: Module,
+        resolution: Resolution,
+        typeck: TypeckResults,
+    }
+
+    impl Package {
+        fn new(source: &'static str) -> Self {
+            let (module, resolution, typeck) = check_valid_source(source);
+
+            Package {
+                module,
+                resolution,
+                typeck,
+            }
+        }
+
+        fn cx(&self) -> LayoutCx<'_> {
+            LayoutCx::new(
+                [(Vec::new(), &self.module)],
+                &self.resolution,
+                &self.typeck,
+                DEFAULT_POINTER_SIZE,
+            )
+        }
+
+        fn ty(&self, signature: &'static str) -> Result<Ty, String> {
+            let ty = Type::parse(&mut Parser::new(Lexer::new_test(signature))).unwrap();
+            lower_signature(&ty, &self.resolution, &self.typeck)
+        }
+
+        /// Get the size, alignment, and field offsets (in declaration order) of a type.
+        fn layout(&self, signature: &'static str) -> (u64, u64, Vec<u64>) {
+            let layout = self.cx().layout_of(&self.ty(signature).unwrap()).unwrap();
+            let offsets = layout.fields.iter().map(|field| field.offset).collect();
+            (layout.size, layout.align, offsets)
+        }
+    }
+
+    #[test]
+    fn test_records() {
+        let package = Package::new(
+            "record Mixed { a: u8, b: u64, c: u16 }\n\
+            record C repr(c) { a: u8, b: u64, c: u16 }\n\
+            record Packed repr(packed) { a: u8, b: u64, c: u16 }\n\
+            record Pair<T> { a: T, b: @T }\n\
+            record Nothing {}",
+        );
+
+        assert_eq!(package.layout("Mixed"), (16, 8, vec![10, 0, 8]));
+        assert_eq!(package.layout("C"), (24, 8, vec![0, 8, 16]));
+        assert_eq!(package.layout("Packed"), (11, 1, vec![0, 1, 9]));
+        assert_eq!(package.layout("Pair<u8>"), (16, 8, vec![8, 0]));
+        assert_eq!(package.layout("Nothing"), (0, 1, vec![]));
+        assert_eq!(package.layout("func(u8) -> u8"), (16, 8, vec![]));
+
+        // The niche of a record is the largest niche of its fields -- a `bool` has more invalid values than a
+        // reference.
+        let mut cx = package.cx();
+        let pair = cx.layout_of(&package.ty("Pair<bool>").unwrap()).unwrap();
+        assert_eq!(pair.niche, Niche::new(8, 1, 0, 1));
+    }
+
+    #[test]
+    fn test_niches() {
+        let package = Package::new(
+            "record Nothing {}\n\
+            union Option<T> { some: T, none: Nothing }\n\
+            union Three<T> { value: T, a: Nothing, b: Nothing }",
+        );
+
+        let mut cx = package.cx();
+        let mut layout_of = |signature| cx.layout_of(&package.ty(signature).unwrap()).unwrap();
+
+        // `none` is a null pointer.
+        let option = layout_of("Option<@u8>");
+        assert_eq!((option.size, option.align), (8, 8));
+        assert_eq!(option.niche, None);
+        assert_eq!(option.variants.value_of(0), None);
+        assert_eq!(option.variants.value_of(1), Some(0));
+
+        // Invalid values of a `bool` and `char` come after the valid ones.
+        let option = layout_of("Option<bool>");
+        assert_eq!(option.size, 1);
+        assert_eq!(option.variants.value_of(1), Some(2));
+        assert_eq!(option.niche, Niche::new(0, 1, 0, 2));
+
+        let nested = layout_of("Option<Option<bool>>");
+        assert_eq!(nested.size, 1);
+        assert_eq!(nested.variants.value_of(1), Some(3));
+
+        let three = layout_of("Three<char>");
+        assert_eq!(three.size, 4);
+        assert_eq!(three.variants.value_of(1), Some(0x11_0000));
+        assert_eq!(three.variants.value_of(2), Some(0x11_0001));
+
+        // Integers have no niche, so they need a tag.
+        let option = layout_of("Option<u32>");
+        assert_eq!((option.size, option.align), (8, 4));
+        assert!(matches!(option.variants, Variants::Tagged { .. }));
+        assert_eq!(option.fields[0].offset, 4);
+        assert_eq!(option.niche, Niche::new(0, 1, 0, 1));
+    }
+
+    #[test]
+    fn test_tags() {
+        let package = Package::new(
+            "record Nothing {}\n\
+            enum Color { Red, Green, Blue }\n\
+            enum Unit { One }\n\
+            enum Wide repr(u16) { One }\n\
+            enum C repr(c) { A, B }\n\
+            union Number { small: u8, big: u64 }\n\
+            union Packed repr(packed) { small: u8, big: u64 }\n\
+            union Ref repr(c) { some: @u8, none: Nothing }\n\
+            union Tiny repr(u8) { value: u8 }",
+        );
+
+        assert_eq!(package.layout("Color"), (1, 1, vec![1, 1, 1]));
+        assert_eq!(package.layout("Unit"), (0, 1, vec![0]));
+        assert_eq!(package.layout("Wide"), (2, 2, vec![2]));
+        assert_eq!(package.layout("C"), (4, 4, vec![4, 4]));
+        assert_eq!(package.layout("Number"), (16, 8, vec![8, 8]));
+        assert_eq!(package.layout("Packed"), (9, 1, vec![1, 1]));
+        assert_eq!(package.layout("Ref"), (16, 8, vec![8, 8]));
+        assert_eq!(package.layout("Tiny"), (2, 1, vec![1]));
+
+        let mut cx = package.cx();
+        let color = cx.layout_of(&package.ty("Color").unwrap()).unwrap();
+        assert_eq!(color.niche, Niche::new(0, 1, 0, 2));
+        assert_eq!(color.variants.value_of(2), Some(2));
+    }
+
+    #[test]
+    fn test_errors() {
+        let package = Package::new(
+            "record List { next: List }\n\
+            record Node { next: @Node }\n\
+            type Opaque;\n\
+            record Pair<T> { a: T, b: T }",
+        );
+
+        let mut cx = package.cx();
+        let mut message = |signature| {
+            let error = cx.layout_of(&package.ty(signature).unwrap()).unwrap_err();
+            error.message(&package.resolution)
+        };
+
+        assert_eq!(
+            message("List"),
+            "`List` contains itself without a reference in between, so it has no finite size"
+        );
+        assert_eq!(message("Opaque"), "`Opaque` is an abstract type, which has no layout");
+        assert_eq!(package.layout("Node"), (8, 8, vec![0]));
+
+        assert_eq!(package.ty("Missing").unwrap_err(), "cannot find type `Missing`");
+        assert_eq!(
+            package.ty("Pair").unwrap_err(),
+            "`Pair` takes 1 generic argument(s), but 0 were given"
+        );
+    }
+
+    #[test]
+    fn test_tag_too_small() {
+        let variants: Vec<String> = (0..129).map(|index| format!("V{index}")).collect();
+        let source = format!("enum Big repr(i8) {{ {} }}", variants.join(", "));
+        let package = Package::new(Box::leak(source.into_boxed_str()));
+        let ty = package.ty("Big").unwrap();
+
+        assert!(matches!(
+            package.cx().layout_of(&ty),
+            Err(LayoutError::TagTooSmall { variants: 129, .. })
+        ));
+    }
+
+    #[test]
+    fn test_display() {
+        let package = Package::new(
+            "record Nothing {}\n\
+            union Option<T> { some: T, none: Nothing }",
+        );
+
+        let mut cx = package.cx();
+        let option = cx.layout_of(&package.ty("Option<@u8>").unwrap()).unwrap();
+
+        assert_eq!(
+            option.display(&package.resolution).to_string(),
+            "size 8, align 8\n\
+            variants, told apart by the niche of `some` at offset 0:\n\
+            \x20    0  some: @u8 (size 8, align 8)\n\
+            \x20    0  none: Nothing (size 0, align 1), stored as 0x0\n\
+            no niche\n"
+        );
+    }
+}
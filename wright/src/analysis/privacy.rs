@@ -0,0 +1,162 @@
+//! Privacy checking -- making sure that items are only used from modules that their [Visibility] allows.
+//!
+//! Name resolution builds an [ItemUse] for every place where an item declared in one module is used from another,
+//! and [checks](ItemUse::check) it as soon as the path it's used through is resolved.
+
+use crate::{
+    ast::decl::Visibility,
+    reporting::{Diagnostic, Highlight},
+    source_tracking::fragment::Fragment,
+};
+
+/// The path of a module from the root of the package, i.e. `["net", "http"]` for `net::http`.
+///
+/// The root module of a package has an empty path.
+pub type ModulePath = [String];
+
+/// A use of a declared item, as recorded during name resolution.
+#[derive(Debug)]
+pub struct ItemUse<'a> {
+    /// Where the item is used.
+    pub use_site: Fragment,
+
+    /// The module that the item is used from.
+    pub used_from: &'a ModulePath,
+
+    /// The name of the item at its declaration.
+    pub decl_name: &'a Fragment,
+
+    /// The module that the item is declared in.
+    pub declared_in: &'a ModulePath,
+
+    /// The visibility that the item was declared with.
+    pub visibility: &'a Visibility,
+}
+
+/// Get the path of the module whose children (including itself) may see an item with the given [Visibility],
+/// declared in the given module. Returns [None] for [Visibility::Public] items, which are visible everywhere.
+pub fn visibility_scope<'a>(
+    visibility: &Visibility,
+    declared_in: &'a ModulePath,
+) -> Option<&'a ModulePath> {
+    match visibility {
+        Visibility::Public { .. } => None,
+        Visibility::Private => Some(declared_in),
+        // Items declared `pub(mod)` in the root module have no parent to be visible to -- treat them like they're
+        // private to the root, which is visible everywhere in the package anyways.
+        Visibility::Module { .. } => Some(declared_in.split_last().map_or(declared_in, |(_, p)| p)),
+    }
+}
+
+/// Check if an item declared in `declared_in` with the given [Visibility] can be used from `used_from`.
+pub fn is_visible_from(
+    visibility: &Visibility,
+    declared_in: &ModulePath,
+    used_from: &ModulePath,
+) -> bool {
+    visibility_scope(visibility, declared_in).is_none_or(|scope| used_from.starts_with(scope))
+}
+
+/// Format a [ModulePath] for display in a diagnostic.
+fn display_module(path: &ModulePath) -> String {
+    if path.is_empty() {
+        "the package root".to_owned()
+    } else {
+        format!("module `{}`", path.join("::"))
+    }
+}
+
+impl ItemUse<'_> {
+    /// Check this use of an item, producing a [Diagnostic] if the item is not visible from where it's used.
+    pub fn check(&self) -> Option<Diagnostic> {
+        if is_visible_from(self.visibility, self.declared_in, self.used_from) {
+            return None;
+        }
+
+        let name = self.decl_name.as_str();
+
+        let declared_here = match self.visibility {
+            Visibility::Module { .. } => format!(
+                "`{name}` is declared `pub(mod)` here, so it is only visible within {}",
+                display_module(
+                    visibility_scope(self.visibility, self.declared_in).unwrap_or_default()
+                )
+            ),
+
+            _ => format!("`{name}` is declared here, in {}", display_module(self.declared_in)),
+        };
+
+        let suggestion = match self.visibility {
+            Visibility::Module { .. } => {
+                format!("consider changing `pub(mod)` to `pub` on the declaration of `{name}`")
+            }
+
+            _ => format!("consider making `{name}` public by adding `pub` to its declaration"),
+        };
+
+        Some(
+            Diagnostic::error()
+                .with_message(format!("`{name}` is private"))
+                .with_highlights([
+                    Highlight::primary(
+                        self.use_site.clone(),
+                        format!("private item used from {}", display_module(self.used_from)),
+                    ),
+                    Highlight::secondary(self.decl_name.clone(), declared_here),
+                ])
+                .with_notes([suggestion]),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ItemUse, is_visible_from};
+    use crate::{
+        ast::decl::{Decl, Visibility},
+        lexer::Lexer,
+        parser::Parser,
+    };
+
+    fn path(segments: &[&str]) -> Vec<String> {
+        segments.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_visibility_rules() {
+        let (root, a, a_b, c) = (path(&[]), path(&["a"]), path(&["a", "b"]), path(&["c"]));
+        let private = Visibility::Private;
+        let module = Visibility::Module {
+            matching_source: Lexer::new_test("pub(mod)").remaining,
+        };
+
+        assert!(is_visible_from(&private, &a, &a));
+        assert!(is_visible_from(&private, &a, &a_b));
+        assert!(!is_visible_from(&private, &a, &root));
+        assert!(!is_visible_from(&private, &a_b, &a));
+
+        assert!(is_visible_from(&module, &a_b, &a));
+        assert!(!is_visible_from(&module, &a_b, &c));
+        assert!(is_visible_from(&module, &a, &c));
+    }
+
+    #[test]
+    fn test_private_use_diagnostic() {
+        let mut parser = Parser::new(Lexer::new_test("type Secret = u8;"));
+        let decl = Decl::parse(&mut parser).unwrap();
+        let (declared_in, used_from) = (path(&["a"]), path(&["c"]));
+
+        let item_use = ItemUse {
+            use_site: decl.name().unwrap().fragment.clone(),
+            used_from: &used_from,
+            decl_name: &decl.name().unwrap().fragment,
+            declared_in: &declared_in,
+            visibility: decl.visibility(),
+        };
+
+        let diagnostic = item_use.check().unwrap();
+        assert_eq!(diagnostic.0.message, "`Secret` is private");
+        assert_eq!(diagnostic.0.labels.len(), 2);
+        assert!(diagnostic.0.notes[0].contains("adding `pub`"));
+    }
+}
@@ -0,0 +1,820 @@
+//! Purity checking -- making sure that `pure` functions (which every constraint must be) have no side effects.
+//!
+//! The effects of every function body are collected first. A function has an effect if it:
+//! - Assigns to a binding declared outside of it, or writes through a reference.
+//! - Calls a [Builtin] that performs I/O.
+//! - Contains an `unsafe` block.
+//! - Calls a function value (such as a parameter of function type), which might not be pure.
+//!
+//! Closures are treated as part of the function they're written in, so calling a closure bound with `let` is fine.
+//!
+//! Then every `pure` function is checked: it can't have any effects of its own, and every function it calls must also
+//! be `pure`. Calls to impure functions are reported with the chain of calls that leads from the call to an effect,
+//! so it's clear why the called function isn't pure. The bodies of `constraint` declarations are always checked the
+//! same way, and every function named as a constraint (i.e. in `u8 constrain is_even`, or as the base of a
+//! `constraint` declaration) must be declared `pure` too.
+
+use crate::{
+    analysis::{
+        builtins::Builtin,
+        resolve::{DefId, DefKind, Resolution, module_bodies},
+    },
+    ast::{
+        decl::Decl,
+        expr::{Atom, CallExpr, Expr, UnaryOperation},
+        identifier::Identifier,
+        module::Module,
+        path::Path,
+        stmt::{Block, Stmt},
+        ty::Type,
+    },
+    reporting::{Diagnostic, Highlight},
+    source_tracking::fragment::Fragment,
+};
+use std::collections::{HashMap, HashSet, VecDeque, hash_map::Entry};
+
+/// A kind of side effect that `pure` functions can't have.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum EffectKind {
+    /// An assignment to a binding declared outside of the function.
+    WriteOutside {
+        /// The name of the binding.
+        name: String,
+    },
+    /// An assignment through a reference, i.e. `*r = 1;`.
+    WriteThroughReference,
+    /// A call to a builtin function that performs I/O.
+    Io(Builtin),
+    /// An `unsafe` block.
+    Unsafe,
+    /// A call to a function value, which could be any function.
+    CallValue,
+}
+
+impl EffectKind {
+    /// Describe this effect, for use in a label on it.
+    fn describe(&self) -> String {
+        match self {
+            EffectKind::WriteOutside { name } => {
+                format!("assignment to `{name}`, which is declared outside of this function")
+            }
+            EffectKind::WriteThroughReference => "assignment through a reference".to_owned(),
+            EffectKind::Io(builtin) => format!("call to `{}`, which performs I/O", builtin.name()),
+            EffectKind::Unsafe => "`unsafe` block".to_owned(),
+            EffectKind::CallValue => "call to a function value, which might not be pure".to_owned(),
+        }
+    }
+}
+
+/// A side effect in a function body.
+#[derive(Clone, Debug)]
+struct Effect {
+    /// What kind of effect this is.
+    kind: EffectKind,
+    /// The source code that has the effect.
+    fragment: Fragment,
+}
+
+/// A call to a function known statically.
+#[derive(Debug)]
+struct Call {
+    /// The function being called.
+    callee: DefId,
+    /// The callee expression at the call site.
+    fragment: Fragment,
+}
+
+/// The direct effects of, and calls made by, a function or the body of a constraint.
+#[derive(Debug)]
+struct FunctionEffects<'m> {
+    /// The name of the function or constraint.
+    name: &'m Identifier,
+    /// Whether this is a constraint (which is always pure) rather than a function.
+    is_constraint: bool,
+    /// Whether this has to be pure.
+    is_pure: bool,
+    effects: Vec<Effect>,
+    calls: Vec<Call>,
+}
+
+/// Check every `pure` function and every constraint in the given modules, which must have already been resolved.
+pub fn check<'m>(
+    modules: impl IntoIterator<Item = (Vec<String>, &'m Module)>,
+    resolution: &Resolution,
+) -> Vec<Diagnostic> {
+    let modules: Vec<_> = modules.into_iter().collect();
+
+    let mut checker = Checker {
+        res: resolution,
+        functions: HashMap::new(),
+        order: Vec::new(),
+        types: Vec::new(),
+        bases: Vec::new(),
+        diagnostics: Vec::new(),
+    };
+
+    for (_, items) in module_bodies(&modules) {
+        for decl in items {
+            checker.collect(decl);
+        }
+    }
+
+    for def in checker.order.clone() {
+        if checker.functions[&def].is_pure {
+            checker.check_function(def);
+        }
+    }
+
+    for ty in std::mem::take(&mut checker.types) {
+        checker.check_constraints(ty);
+    }
+
+    for base in std::mem::take(&mut checker.bases) {
+        checker.check_constraint_path(base);
+    }
+
+    checker.diagnostics
+}
+
+/// The state of the purity checker.
+struct Checker<'m, 'r> {
+    res: &'r Resolution,
+
+    /// The effects of every function.
+    functions: HashMap<DefId, FunctionEffects<'m>>,
+
+    /// The functions in [Checker::functions], in source order.
+    order: Vec<DefId>,
+
+    /// Every type signature in the package, which may name constraints.
+    types: Vec<&'m Type>,
+
+    /// The bases of every constraint declaration.
+    bases: Vec<&'m Path>,
+
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'m> Checker<'m, '_> {
+    /// Collect the type signatures in a declaration, and the effects of its body if it's a function.
+    fn collect(&mut self, decl: &'m Decl) {
+        match decl {
+            Decl::Function(_) | Decl::Trait(_) | Decl::Impl(_) => {
+                if let Decl::Impl(implementation) = decl {
+                    self.types.push(&implementation.self_ty);
+                }
+
+                for func in decl.functions() {
+                    self.types.extend(func.params.iter().map(|param| &param.ty));
+                    self.types.extend(&func.return_ty);
+
+                    let params = func.params.iter().map(|param| &param.name);
+                    self.collect_body(&func.name, false, func.is_pure, params, func.body.as_ref());
+                }
+            }
+
+            Decl::Constraint(constraint) => {
+                self.bases.extend(&constraint.bases);
+
+                if let Some(predicate) = &constraint.predicate {
+                    self.types.push(&predicate.param.ty);
+
+                    let param = [&predicate.param.name];
+                    self.collect_body(&constraint.name, true, true, param, Some(&predicate.body));
+                }
+            }
+
+            Decl::Constant(constant) => self.types.push(&constant.ty),
+            Decl::TypeAlias(alias) => self.types.extend(&alias.target_type),
+            Decl::Record(record) => self
+                .types
+                .extend(record.fields.iter().map(|field| &field.ty)),
+            Decl::Union(union) => self
+                .types
+                .extend(union.variants.iter().map(|variant| &variant.ty)),
+            Decl::Import(_) | Decl::Module(_) | Decl::Enum(_) => {}
+        }
+    }
+
+    /// Collect the effects of the body of a function or constraint.
+    fn collect_body(
+        &mut self,
+        name: &'m Identifier,
+        is_constraint: bool,
+        is_pure: bool,
+        params: impl IntoIterator<Item = &'m Identifier>,
+        body: Option<&'m Block>,
+    ) {
+        let Some(def) = self.res.declared(&name.fragment) else {
+            return;
+        };
+
+        let mut collector = Collector {
+            res: self.res,
+            locals: HashSet::new(),
+            closures: HashSet::new(),
+            effects: Vec::new(),
+            calls: Vec::new(),
+            types: &mut self.types,
+        };
+
+        for param in params {
+            collector.declare(param);
+        }
+
+        if let Some(body) = body {
+            collector.visit_block(body);
+        }
+
+        let (effects, calls) = (collector.effects, collector.calls);
+
+        self.functions.insert(
+            def,
+            FunctionEffects {
+                name,
+                is_constraint,
+                is_pure,
+                effects,
+                calls,
+            },
+        );
+
+        self.order.push(def);
+    }
+
+    /// Check if a function is declared `pure`. Builtin functions aren't.
+    fn is_pure(&self, def: DefId) -> bool {
+        self.functions
+            .get(&def)
+            .is_some_and(|function| function.is_pure)
+    }
+
+    /// Report every effect in, and every call to an impure function from, a `pure` function or a constraint.
+    fn check_function(&mut self, def: DefId) {
+        let function = &self.functions[&def];
+        let name = function.name;
+
+        let (what, declared_pure) = match function.is_constraint {
+            true => (
+                "constraint",
+                format!("`{}` is a constraint, so it must be pure", name.fragment.as_str()),
+            ),
+            false => {
+                ("pure function", format!("`{}` is declared `pure` here", name.fragment.as_str()))
+            }
+        };
+
+        let declared_pure = Highlight::secondary(name.fragment.clone(), declared_pure);
+
+        for effect in &function.effects {
+            self.diagnostics.push(
+                Diagnostic::error()
+                    .with_message(format!("side effect in {what} `{}`", name.fragment.as_str()))
+                    .with_highlights([
+                        Highlight::primary(effect.fragment.clone(), effect.kind.describe()),
+                        declared_pure.clone(),
+                    ]),
+            );
+        }
+
+        for call in &function.calls {
+            // Calls to `pure` functions are fine, since those are checked on their own.
+            if self.is_pure(call.callee) {
+                continue;
+            }
+
+            let callee = &self.res.def(call.callee).name;
+
+            let mut highlights = vec![
+                Highlight::primary(call.fragment.clone(), format!("`{callee}` is not `pure`")),
+                declared_pure.clone(),
+            ];
+
+            let notes = match self.chain(call.callee) {
+                Some((calls, effect)) => {
+                    let mut names = vec![name.fragment.as_str().to_owned(), callee.clone()];
+                    let mut caller = callee;
+
+                    for step in calls {
+                        let next = &self.res.def(step.callee).name;
+                        highlights.push(Highlight::secondary(
+                            step.fragment.clone(),
+                            format!("`{caller}` calls `{next}` here"),
+                        ));
+
+                        names.push(next.clone());
+                        caller = next;
+                    }
+
+                    highlights.push(Highlight::secondary(
+                        effect.fragment.clone(),
+                        format!("{} in `{caller}`", effect.kind.describe()),
+                    ));
+
+                    if let EffectKind::Io(builtin) = effect.kind {
+                        names.push(builtin.name().to_owned());
+                    }
+
+                    let chain: Vec<String> = names.iter().map(|name| format!("`{name}`")).collect();
+                    vec![format!("call chain: {}", chain.join(" -> "))]
+                }
+
+                None => vec![format!(
+                    "help: declare `{callee}` with `pure func` if it has no side effects"
+                )],
+            };
+
+            self.diagnostics.push(
+                Diagnostic::error()
+                    .with_message(format!(
+                        "{what} `{}` calls impure function `{callee}`",
+                        name.fragment.as_str()
+                    ))
+                    .with_highlights(highlights)
+                    .with_notes(notes),
+            );
+        }
+    }
+
+    /// Find the shortest chain of calls from a function to a function with an effect, returning the calls after the
+    /// first function and the effect at the end.
+    fn chain(&self, start: DefId) -> Option<(Vec<&Call>, &Effect)> {
+        // The call that first reached each function, along with the function that made it.
+        let mut came_from: HashMap<DefId, Option<(DefId, &Call)>> = HashMap::from([(start, None)]);
+        let mut queue = VecDeque::from([start]);
+
+        while let Some(def) = queue.pop_front() {
+            let function = &self.functions[&def];
+
+            if let Some(effect) = function.effects.first() {
+                let mut calls = Vec::new();
+                let mut current = def;
+
+                while let Some(Some((caller, call))) = came_from.get(&current) {
+                    calls.push(*call);
+                    current = *caller;
+                }
+
+                calls.reverse();
+                return Some((calls, effect));
+            }
+
+            for call in &function.calls {
+                if let Entry::Vacant(entry) = came_from.entry(call.callee) {
+                    entry.insert(Some((def, call)));
+                    queue.push_back(call.callee);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Check that a path used as a constraint names a constraint declaration or a `pure` function.
+    fn check_constraint_path(&mut self, path: &Path) {
+        // Unresolved names have already been reported.
+        let Some(def) = self.res.resolved(&path.full_path) else {
+            return;
+        };
+
+        let definition = self.res.def(def);
+
+        match definition.kind {
+            DefKind::Constraint => return,
+            DefKind::Function if self.is_pure(def) => return,
+            DefKind::Function => {}
+
+            kind => {
+                self.diagnostics.push(
+                    Diagnostic::error()
+                        .with_message(format!(
+                            "expected a constraint or function, found {} `{}`",
+                            kind.describe(),
+                            definition.name
+                        ))
+                        .with_highlights([Highlight::primary(
+                            path.full_path.clone(),
+                            "not a constraint",
+                        )]),
+                );
+
+                return;
+            }
+        }
+
+        let mut highlights = vec![Highlight::primary(
+            path.full_path.clone(),
+            "functions used as constraints must be `pure`",
+        )];
+
+        highlights.extend(definition.name_fragment.clone().map(|name| {
+            Highlight::secondary(name, format!("`{}` is declared here", definition.name))
+        }));
+
+        self.diagnostics.push(
+            Diagnostic::error()
+                .with_message(format!("constraint `{}` is not a `pure` function", definition.name))
+                .with_highlights(highlights)
+                .with_notes([format!(
+                    "help: declare `{}` with `pure func` if it has no side effects",
+                    definition.name
+                )]),
+        );
+    }
+
+    /// Check that every constraint in a type signature (and the types inside it) is a constraint declaration or a
+    /// `pure` function.
+    fn check_constraints(&mut self, ty: &Type) {
+        match ty {
+            Type::Constrained(constrained) => {
+                self.check_constraints(&constrained.base_ty);
+
+                for path in &constrained.constraining_items {
+                    self.check_constraint_path(path);
+                }
+            }
+
+            Type::Reference(reference) => self.check_constraints(&reference.target_ty),
+
+            Type::Named(named) => named
+                .generic_tys
+                .iter()
+                .for_each(|arg| self.check_constraints(arg)),
+
+            Type::Function(function) => {
+                function
+                    .params
+                    .iter()
+                    .for_each(|param| self.check_constraints(param));
+
+                if let Some(return_ty) = &function.return_ty {
+                    self.check_constraints(return_ty);
+                }
+            }
+
+            Type::Atomic(_) => {}
+        }
+    }
+}
+
+/// Collects the effects and calls in a function body.
+struct Collector<'m, 'r, 't> {
+    res: &'r Resolution,
+
+    /// The bindings declared in the function (including its parameters and those in closures).
+    locals: HashSet<DefId>,
+
+    /// The `let` bindings holding closures written in the function, which are part of the function.
+    closures: HashSet<DefId>,
+
+    effects: Vec<Effect>,
+    calls: Vec<Call>,
+
+    /// Type annotations found in the body.
+    types: &'t mut Vec<&'m Type>,
+}
+
+impl<'m> Collector<'m, '_, '_> {
+    /// Record a binding as declared in the function.
+    fn declare(&mut self, name: &Identifier) {
+        self.locals.extend(self.res.declared(&name.fragment));
+    }
+
+    fn visit_block(&mut self, block: &'m Block) {
+        for stmt in &block.stmts {
+            match stmt {
+                Stmt::Let(let_stmt) => {
+                    self.types.extend(&let_stmt.ty);
+                    self.visit(&let_stmt.value);
+                    self.declare(&let_stmt.name);
+
+                    if !let_stmt.is_mutable && matches!(let_stmt.value, Expr::Closure(_)) {
+                        (self.closures).extend(self.res.declared(&let_stmt.name.fragment));
+                    }
+                }
+
+                Stmt::Assign(assign) => {
+                    self.visit(&assign.target);
+                    self.visit(&assign.value);
+                    self.check_write(&assign.target, &assign.matching_source);
+                }
+
+                Stmt::Expr(expr_stmt) => self.visit(&expr_stmt.expr),
+            }
+        }
+
+        if let Some(tail) = &block.tail {
+            self.visit(tail);
+        }
+    }
+
+    fn visit(&mut self, expr: &'m Expr) {
+        match expr {
+            Expr::Call(call) => {
+                self.visit_call(call);
+                call.args.iter().for_each(|arg| self.visit(arg));
+            }
+
+            Expr::Unsafe(unsafe_expr) => {
+                let (keyword, _) = unsafe_expr.matching_source.split_at("unsafe".len());
+                self.effects.push(Effect {
+                    kind: EffectKind::Unsafe,
+                    fragment: keyword,
+                });
+
+                self.visit_block(&unsafe_expr.body);
+            }
+
+            Expr::Block(block) => self.visit_block(block),
+
+            Expr::If(if_expr) => {
+                self.visit(&if_expr.condition);
+                self.visit_block(&if_expr.then_block);
+
+                if let Some(else_branch) = &if_expr.else_branch {
+                    self.visit(else_branch);
+                }
+            }
+
+            Expr::Match(match_expr) => {
+                self.visit(&match_expr.scrutinee);
+
+                for arm in &match_expr.arms {
+                    arm.pattern.for_each_binding(&mut |name| self.declare(name));
+                    arm.guard.iter().for_each(|guard| self.visit(guard));
+                    self.visit(&arm.body);
+                }
+            }
+
+            Expr::While(while_expr) => {
+                self.visit(&while_expr.condition);
+                self.visit_block(&while_expr.body);
+            }
+
+            Expr::For(for_expr) => {
+                self.visit(&for_expr.range.start);
+                self.visit(&for_expr.range.end);
+                self.declare(&for_expr.binding);
+                self.visit_block(&for_expr.body);
+            }
+
+            Expr::Closure(closure) => {
+                for param in &closure.params {
+                    self.declare(&param.name);
+                    self.types.extend(&param.ty);
+                }
+
+                self.types.extend(&closure.return_ty);
+                self.visit_block(&closure.body);
+            }
+
+            _ => expr.for_each_child(&mut |child| self.visit(child)),
+        }
+    }
+
+    /// Record the call or effect of calling a function.
+    fn visit_call(&mut self, call: &'m CallExpr) {
+        let callee = match &*call.callee {
+            Expr::Atom(Atom::Identifier(identifier)) => self.res.resolved(&identifier.fragment),
+            Expr::Path(path) => self.res.resolved(&path.full_path),
+
+            // Closures called where they're written are part of the function.
+            Expr::Closure(_) => return self.visit(&call.callee),
+
+            callee => {
+                self.visit(callee);
+
+                self.effects.push(Effect {
+                    kind: EffectKind::CallValue,
+                    fragment: callee.matching_source().clone(),
+                });
+
+                return;
+            }
+        };
+
+        // Unresolved names have already been reported.
+        let Some(callee) = callee else {
+            return;
+        };
+
+        let fragment = call.callee.matching_source().clone();
+
+        if let Some(builtin) = self.res.builtin(callee) {
+            if builtin.is_io() {
+                self.effects.push(Effect {
+                    kind: EffectKind::Io(builtin),
+                    fragment: call.matching_source.clone(),
+                });
+            }
+        } else if self.res.def(callee).kind == DefKind::Function {
+            self.calls.push(Call { callee, fragment });
+        } else if !self.closures.contains(&callee) {
+            self.effects.push(Effect {
+                kind: EffectKind::CallValue,
+                fragment,
+            });
+        }
+    }
+
+    /// Record the effect of assigning to a place, if it has one.
+    fn check_write(&mut self, target: &Expr, assignment: &Fragment) {
+        match target {
+            Expr::Parens(parens) => self.check_write(&parens.inner, assignment),
+            Expr::Field(field) => self.check_write(&field.base, assignment),
+
+            Expr::Unary(unary) if unary.op == UnaryOperation::Dereference => {
+                self.effects.push(Effect {
+                    kind: EffectKind::WriteThroughReference,
+                    fragment: assignment.clone(),
+                })
+            }
+
+            Expr::Atom(Atom::Identifier(identifier)) => {
+                let Some(def) = self.res.resolved(&identifier.fragment) else {
+                    return;
+                };
+
+                if !self.locals.contains(&def) {
+                    self.effects.push(Effect {
+                        kind: EffectKind::WriteOutside {
+                            name: identifier.fragment.as_str().to_owned(),
+                        },
+                        fragment: assignment.clone(),
+                    });
+                }
+            }
+
+            // Other targets are reported by the type checker.
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check;
+    use crate::{
+        analysis::testing::{messages, modules, resolve_source},
+        ast::module::Module,
+        reporting::Diagnostic,
+    };
+
+    fn check_source(source: &'static str) -> (Module, Vec<Diagnostic>) {
+        let (module, resolution) = resolve_source(source);
+        let diagnostics = check(modules(&module), &resolution);
+        (module, diagnostics)
+    }
+
+    #[test]
+    fn test_pure() {
+        let (_, diagnostics) = check_source(
+            "pure func square(x: u8) -> u8 { x * x }\n\
+            pure func sum(n: u8) -> u8 {\n\
+                var total: u8 = 0;\n\
+                for i in 0..n { total += square(i); }\n\
+                let add = func(a: u8) -> u8 { a + total };\n\
+                add(1)\n\
+            }",
+        );
+
+        assert!(diagnostics.is_empty(), "{:?}", messages(&diagnostics));
+    }
+
+    #[test]
+    fn test_direct_effects() {
+        let (module, diagnostics) = check_source(
+            "use wright::io::println;\n\
+            pure func f(r: @mut u8, g: func(u8) -> u8) -> u8 {\n\
+                println(1);\n\
+                *r = 2;\n\
+                unsafe { 3 };\n\
+                g(4)\n\
+            }",
+        );
+
+        assert!(
+            diagnostics
+                .iter()
+                .all(|d| d.0.message == "side effect in pure function `f`")
+        );
+
+        let source = module.matching_source.as_str();
+        let labels: Vec<_> = (diagnostics.iter())
+            .map(|d| (&source[d.0.labels[0].range.clone()], d.0.labels[0].message.as_str()))
+            .collect();
+
+        assert_eq!(
+            labels,
+            [
+                ("println(1)", "call to `println`, which performs I/O"),
+                ("*r = 2;", "assignment through a reference"),
+                ("unsafe", "`unsafe` block"),
+                ("g", "call to a function value, which might not be pure"),
+            ]
+        );
+
+        assert_eq!(diagnostics[0].0.labels[1].message, "`f` is declared `pure` here");
+    }
+
+    #[test]
+    fn test_call_chain() {
+        let (module, diagnostics) = check_source(
+            "pure func f() { g(); }\n\
+            func g() { h(); }\n\
+            func h() { wright::io::println(1); }\n\
+            func quiet() {}\n\
+            pure func e() { quiet(); }",
+        );
+
+        assert_eq!(
+            messages(&diagnostics),
+            [
+                "pure function `f` calls impure function `g`",
+                "pure function `e` calls impure function `quiet`",
+            ]
+        );
+
+        let source = module.matching_source.as_str();
+        let labels: Vec<_> = (diagnostics[0].0.labels.iter())
+            .map(|label| (&source[label.range.clone()], label.message.as_str()))
+            .collect();
+
+        assert_eq!(
+            labels,
+            [
+                ("g", "`g` is not `pure`"),
+                ("f", "`f` is declared `pure` here"),
+                ("h", "`g` calls `h` here"),
+                ("wright::io::println(1)", "call to `println`, which performs I/O in `h`"),
+            ]
+        );
+
+        assert_eq!(diagnostics[0].0.notes, ["call chain: `f` -> `g` -> `h` -> `println`"]);
+        assert_eq!(
+            diagnostics[1].0.notes,
+            ["help: declare `quiet` with `pure func` if it has no side effects"]
+        );
+    }
+
+    #[test]
+    fn test_constraints() {
+        let (_, diagnostics) = check_source(
+            "func even(x: u8) -> bool { x % 2 == 0 }\n\
+            pure func small(x: u8) -> bool { x < 10 }\n\
+            record Point { x: u8 }\n\
+            type Even = u8 constrain even;\n\
+            func main(a: u8 constrain small, b: u8 constrain Point) {}",
+        );
+
+        assert_eq!(
+            messages(&diagnostics),
+            [
+                "constraint `even` is not a `pure` function",
+                "expected a constraint or function, found record `Point`",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_constraint_decls() {
+        let (_, diagnostics) = check_source(
+            "func log(x: u8) -> bool { wright::io::println(x); true }\n\
+            pure func small(x: u8) -> bool { x < 10 }\n\
+            constraint Logged(x: u8) { log(x) }\n\
+            constraint Both(x: u8): small + log { true }\n\
+            constraint Fine = Logged + small;\n\
+            record Point { x: u8 }\n\
+            constraint Bad = Point;",
+        );
+
+        assert_eq!(
+            messages(&diagnostics),
+            [
+                "constraint `Logged` calls impure function `log`",
+                "constraint `log` is not a `pure` function",
+                "expected a constraint or function, found record `Point`",
+            ]
+        );
+
+        let labels = &diagnostics[0].0.labels;
+        assert_eq!(labels[1].message, "`Logged` is a constraint, so it must be pure");
+    }
+
+    #[test]
+    fn test_writes() {
+        let (_, diagnostics) = check_source(
+            "record Point { x: u8 }\n\
+            pure func f(p: Point) -> u8 {\n\
+                var q = p;\n\
+                q.x = 1;\n\
+                var n: u8 = 0;\n\
+                let bump = func() { n += 1; };\n\
+                bump();\n\
+                n\n\
+            }",
+        );
+
+        assert!(diagnostics.is_empty(), "{:?}", messages(&diagnostics));
+    }
+}
@@ -0,0 +1,498 @@
+//! Name resolution -- connecting every [Path] and identifier in the AST to the definition it refers to.
+//!
+//! Resolution builds a tree of modules (and the items declared in each), resolves `use` imports (including
+//! `use ... as` renaming), and then walks every function body with a stack of scopes so that `let` bindings shadow
+//! earlier bindings of the same name. Every definition gets a [DefId], and every use of a name is recorded in the
+//! [Resolution] by the [Fragment] of the use site.
+//!
+//! Uses of items from other modules are privacy-checked as they're resolved (see [crate::analysis::privacy]).
+//!
+//! The [Builtin] functions are defined in the `wright` module (and its submodules) of every package.
+//!
+//! [Path]: crate::ast::path::Path
+
+use crate::{
+    analysis::{builtins::Builtin, privacy::ModulePath},
+    ast::{
+        decl::{Decl, Visibility, module::ModuleDecl},
+        module::Module,
+    },
+    reporting::Diagnostic,
+    source_tracking::fragment::Fragment,
+};
+use std::collections::{BTreeMap, HashMap};
+
+mod resolver;
+
+/// A unique identifier for a definition in a [Resolution].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DefId(u32);
+
+impl DefId {
+    /// Get the index of this definition in the order definitions were created.
+    pub const fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// The different kinds of things that a name can refer to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DefKind {
+    /// A module -- either a source file or a `mod` declaration.
+    Module,
+    /// A `type` alias.
+    TypeAlias,
+    /// A `const` declaration.
+    Constant,
+    /// A `func` declaration.
+    Function,
+    /// A `constraint` declaration.
+    Constraint,
+    /// A `record` declaration.
+    Record,
+    /// A `union` declaration.
+    Union,
+    /// An `enum` declaration.
+    Enum,
+    /// A variant of an `enum`.
+    Variant,
+    /// A `trait` declaration.
+    Trait,
+    /// An `impl` block.
+    Impl,
+    /// A method declared in a `trait` or `impl` block.
+    Method,
+    /// The `Self` type of a `trait` or `impl` block.
+    SelfType,
+    /// A generic parameter of a declaration.
+    GenericParam,
+    /// A function parameter.
+    Param,
+    /// A `let` or `var` binding.
+    Local {
+        /// Whether the binding was declared with `var`.
+        is_mutable: bool,
+    },
+}
+
+impl DefKind {
+    /// Get a short description of this kind of definition, for use in diagnostics.
+    pub const fn describe(self) -> &'static str {
+        match self {
+            DefKind::Module => "module",
+            DefKind::TypeAlias => "type",
+            DefKind::Constant => "constant",
+            DefKind::Function => "function",
+            DefKind::Constraint => "constraint",
+            DefKind::Record => "record",
+            DefKind::Union => "union",
+            DefKind::Enum => "enum",
+            DefKind::Variant => "variant",
+            DefKind::Trait => "trait",
+            DefKind::Impl => "implementation",
+            DefKind::Method => "method",
+            DefKind::SelfType => "`Self` type",
+            DefKind::GenericParam => "generic parameter",
+            DefKind::Param => "parameter",
+            DefKind::Local { .. } => "local variable",
+        }
+    }
+
+    /// Check if definitions of this kind are items that can be named from other modules.
+    pub const fn is_item(self) -> bool {
+        matches!(
+            self,
+            DefKind::Module
+                | DefKind::TypeAlias
+                | DefKind::Constant
+                | DefKind::Function
+                | DefKind::Constraint
+                | DefKind::Record
+                | DefKind::Union
+                | DefKind::Enum
+                | DefKind::Trait
+        )
+    }
+}
+
+/// Something that has been defined with a name.
+#[derive(Debug)]
+pub struct Definition {
+    /// The name of the definition. This is empty for the root module.
+    pub name: String,
+
+    /// What kind of definition this is.
+    pub kind: DefKind,
+
+    /// The name of the definition at its declaration. Modules that come from source files do not have one.
+    pub name_fragment: Option<Fragment>,
+
+    /// The module that this definition is declared in. For modules, this is the parent module.
+    pub module: Vec<String>,
+}
+
+/// A name bound in a module -- either declared there or imported.
+#[derive(Clone, Debug)]
+struct Binding {
+    /// The definition that the name refers to.
+    def: DefId,
+
+    /// The visibility of the binding. [None] for modules from source files, which are always public.
+    visibility: Option<Visibility>,
+
+    /// The name at the declaration or import that introduced this binding.
+    name_fragment: Option<Fragment>,
+}
+
+/// The names bound in a module.
+#[derive(Debug, Default)]
+struct ModuleScope {
+    /// The names bound in the module, ordered for consistent suggestions.
+    names: BTreeMap<String, Binding>,
+}
+
+/// The result of name resolution over a package of modules.
+#[derive(Debug, Default)]
+pub struct Resolution {
+    /// Every definition, indexed by [DefId].
+    defs: Vec<Definition>,
+
+    /// The scope of every module, by its path.
+    modules: HashMap<Vec<String>, ModuleScope>,
+
+    /// The [DefId] of every module, by its path.
+    module_defs: HashMap<Vec<String>, DefId>,
+
+    /// The definition that every resolved use site refers to.
+    uses: HashMap<Fragment, DefId>,
+
+    /// The definition introduced by every declared name.
+    decls: HashMap<Fragment, DefId>,
+
+    /// The [Builtin] functions, by their definitions.
+    builtins: HashMap<DefId, Builtin>,
+
+    /// The variants of every enum, in declaration order.
+    variants: HashMap<DefId, Vec<DefId>>,
+
+    /// The enum that every variant belongs to.
+    variant_enums: HashMap<DefId, DefId>,
+
+    /// The `Self` type of every trait and `impl` block.
+    self_types: HashMap<DefId, DefId>,
+
+    /// Any errors found during resolution.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl Resolution {
+    /// Get a [Definition] by its [DefId].
+    pub fn def(&self, id: DefId) -> &Definition {
+        &self.defs[id.index()]
+    }
+
+    /// Iterate over every [Definition] and its [DefId].
+    pub fn defs(&self) -> impl Iterator<Item = (DefId, &Definition)> {
+        self.defs
+            .iter()
+            .enumerate()
+            .map(|(index, def)| (DefId(index as u32), def))
+    }
+
+    /// Get the [DefId] of a module by its path.
+    pub fn module_def(&self, path: &ModulePath) -> Option<DefId> {
+        self.module_defs.get(path).copied()
+    }
+
+    /// Get the path of a module from its [DefId].
+    pub fn module_path(&self, id: DefId) -> Option<Vec<String>> {
+        let def = self.def(id);
+
+        (def.kind == DefKind::Module).then(|| {
+            let mut path = def.module.clone();
+
+            if !def.name.is_empty() {
+                path.push(def.name.clone());
+            }
+
+            path
+        })
+    }
+
+    /// Get the item bound to a name in a module, including imported names.
+    pub fn item(&self, module: &ModulePath, name: &str) -> Option<DefId> {
+        Some(self.modules.get(module)?.names.get(name)?.def)
+    }
+
+    /// Get the [DefId] that a use site (an identifier or a whole [Path]) resolved to.
+    ///
+    /// For paths, the whole path is recorded as referring to the item it names, and each leading segment is
+    /// recorded as referring to the module it names.
+    ///
+    /// [Path]: crate::ast::path::Path
+    pub fn resolved(&self, use_site: &Fragment) -> Option<DefId> {
+        self.uses.get(use_site).copied()
+    }
+
+    /// Get the [Builtin] function a definition is, if it's one.
+    pub fn builtin(&self, id: DefId) -> Option<Builtin> {
+        self.builtins.get(&id).copied()
+    }
+
+    /// Get the variants of an enum, in declaration order. This is empty for anything that isn't an enum.
+    pub fn variants(&self, enumeration: DefId) -> &[DefId] {
+        self.variants.get(&enumeration).map_or(&[], Vec::as_slice)
+    }
+
+    /// Get the enum that a variant belongs to.
+    pub fn enum_of(&self, variant: DefId) -> Option<DefId> {
+        self.variant_enums.get(&variant).copied()
+    }
+
+    /// Get the definition of the `Self` type of a trait or `impl` block.
+    pub fn self_type(&self, owner: DefId) -> Option<DefId> {
+        self.self_types.get(&owner).copied()
+    }
+
+    /// Find the variant of an enum with the given name.
+    pub fn variant(&self, enumeration: DefId, name: &str) -> Option<DefId> {
+        (self.variants(enumeration).iter())
+            .copied()
+            .find(|variant| self.def(*variant).name == name)
+    }
+
+    /// Get the [DefId] introduced by a declared name (i.e. the name of a function, or of a `let` binding).
+    pub fn declared(&self, name: &Fragment) -> Option<DefId> {
+        self.decls.get(name).copied()
+    }
+
+    /// Get the [Definition] that a use site refers to -- useful for go-to-definition.
+    pub fn definition_of(&self, use_site: &Fragment) -> Option<&Definition> {
+        self.resolved(use_site).map(|id| self.def(id))
+    }
+
+    /// Iterate over every use site that refers to a definition -- useful for renaming.
+    pub fn references(&self, id: DefId) -> impl Iterator<Item = &Fragment> {
+        self.uses
+            .iter()
+            .filter(move |(_, def)| **def == id)
+            .map(|(use_site, _)| use_site)
+    }
+
+    /// Look up a path (given as a list of names) as if it were written in the given module, following imports.
+    /// Paths can go through modules and end at an enum variant.
+    ///
+    /// This does not record any uses or report any errors.
+    pub fn lookup(&self, from: &ModulePath, path: &[&str]) -> Option<DefId> {
+        let (head, rest) = path.split_first()?;
+
+        let mut def = self.item(from, head).or_else(|| self.item(&[], head))?;
+
+        for segment in rest {
+            def = match self.def(def).kind {
+                DefKind::Enum => self.variant(def, segment)?,
+                _ => self.item(&self.module_path(def)?, segment)?,
+            };
+        }
+
+        Some(def)
+    }
+}
+
+/// Resolve every name in the given modules, each with its path from the root of the package.
+///
+/// Modules are created for every ancestor of the given module paths, so that (for example) `a::b` can be used
+/// even if there is no source for the module `a`.
+pub fn resolve<'m>(modules: impl IntoIterator<Item = (Vec<String>, &'m Module)>) -> Resolution {
+    resolver::Resolver::run(modules.into_iter().collect())
+}
+
+/// Get the items of every module, along with the items of every inline module inside them, each with the path of the
+/// module they're declared in. Parent modules always come before their children.
+pub(crate) fn module_bodies<'m>(
+    modules: &[(Vec<String>, &'m Module)],
+) -> Vec<(Vec<String>, &'m [Decl])> {
+    let mut bodies = Vec::new();
+
+    for (path, module) in modules {
+        collect_bodies(path.clone(), &module.items, &mut bodies);
+    }
+
+    bodies
+}
+
+/// Add the items of a module, and then the items of every inline module inside of it, to `bodies`.
+fn collect_bodies<'m>(
+    path: Vec<String>,
+    items: &'m [Decl],
+    bodies: &mut Vec<(Vec<String>, &'m [Decl])>,
+) {
+    bodies.push((path.clone(), items));
+
+    for decl in items {
+        if let Decl::Module(ModuleDecl {
+            name,
+            body: Some(body),
+            ..
+        }) = decl
+        {
+            let mut inner = path.clone();
+            inner.push(name.fragment.as_str().to_owned());
+            collect_bodies(inner, &body.items, bodies);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DefKind, Resolution, resolve};
+    use crate::{analysis::testing::messages, ast::module::Module, lexer::Lexer, parser::Parser};
+
+    fn resolve_sources(sources: &[(&[&str], &'static str)]) -> (Vec<Module>, Resolution) {
+        let modules: Vec<Module> = sources
+            .iter()
+            .map(|(_, source)| Module::parse(&mut Parser::new(Lexer::new_test(source))).unwrap())
+            .collect();
+
+        let paths = sources
+            .iter()
+            .map(|(path, _)| path.iter().map(|s| s.to_string()).collect());
+
+        let resolution = resolve(paths.zip(&modules));
+        (modules, resolution)
+    }
+
+    #[test]
+    fn test_locals_and_shadowing() {
+        let (modules, resolution) =
+            resolve_sources(&[(&[], "func f(x: u8) -> u8 { let y = x; let y = y + 1; y }")]);
+
+        assert!(resolution.diagnostics.is_empty(), "{:?}", messages(&resolution.diagnostics));
+        let source = modules[0].matching_source.as_str();
+
+        // Find the use of `y` at the end of the body and the `y` in `y + 1`.
+        let tail_y = source.rfind("y }").unwrap();
+        let tail_def = resolution
+            .defs()
+            .find(|(id, _)| resolution.references(*id).any(|f| f.range.start == tail_y))
+            .unwrap();
+
+        let second_let = source.rfind("let y").unwrap() + 4;
+        assert_eq!(tail_def.1.name_fragment.as_ref().unwrap().range.start, second_let);
+        assert_eq!(tail_def.1.kind, DefKind::Local { is_mutable: false });
+    }
+
+    #[test]
+    fn test_imports_across_modules() {
+        let (_, resolution) = resolve_sources(&[
+            (&[], "use io::println as say; func main() { say(1); io::println(2); }"),
+            (&["io"], "pub func println(x: u8);"),
+        ]);
+
+        assert!(resolution.diagnostics.is_empty(), "{:?}", messages(&resolution.diagnostics));
+        let println = resolution.item(&["io".to_owned()], "println").unwrap();
+        assert_eq!(resolution.item(&[], "say"), Some(println));
+        // One use in the import, and two in `main` -- the `say` call resolves through the import.
+        assert_eq!(resolution.references(println).count(), 3);
+        assert_eq!(resolution.lookup(&[], &["io", "println"]), Some(println));
+    }
+
+    #[test]
+    fn test_unresolved_with_suggestion() {
+        let (_, resolution) = resolve_sources(&[
+            (&[], "func main() { let count = 1; conut; io::prinln(count); }"),
+            (&["io"], "pub func println(x: u8);"),
+        ]);
+
+        let diagnostics = &resolution.diagnostics;
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].0.message, "cannot find `conut` in this scope");
+        assert!(diagnostics[0].0.notes[0].contains("`count`"));
+        assert_eq!(diagnostics[1].0.message, "cannot find `prinln` in module `io`");
+        assert!(diagnostics[1].0.notes[0].contains("`println`"));
+    }
+
+    #[test]
+    fn test_private_item() {
+        let (_, resolution) =
+            resolve_sources(&[(&[], "use secret::key;"), (&["secret"], "func key();")]);
+
+        assert_eq!(messages(&resolution.diagnostics), ["`key` is private"]);
+    }
+
+    #[test]
+    fn test_module_decls() {
+        let (_, resolution) = resolve_sources(&[
+            (
+                &[],
+                "mod secret { pub func key(); } pub mod io; use secret::key; func main() { key(); io::println(); }",
+            ),
+            (&["io"], "pub func println();"),
+            (&["a"], "mod hidden { pub func f(); }"),
+        ]);
+
+        assert!(resolution.diagnostics.is_empty(), "{:?}", messages(&resolution.diagnostics));
+        let io = resolution.item(&[], "io").unwrap();
+        assert!(resolution.def(io).name_fragment.is_some());
+        assert_eq!(resolution.module_path(io).unwrap(), ["io"]);
+        assert!(resolution.lookup(&[], &["secret", "key"]).is_some());
+
+        let (_, resolution) = resolve_sources(&[
+            (&[], "use a::hidden::f;"),
+            (&["a"], "mod hidden { pub func f(); }"),
+        ]);
+
+        assert_eq!(messages(&resolution.diagnostics), ["`hidden` is private"]);
+
+        // Names from imports that fail to resolve are only reported once.
+        let (_, resolution) =
+            resolve_sources(&[(&[], "use io::println; func main() { println(); }")]);
+        assert_eq!(messages(&resolution.diagnostics), ["cannot find `io` in this scope"]);
+    }
+
+    #[test]
+    fn test_duplicate_definition() {
+        let (_, resolution) = resolve_sources(&[(&[], "type A; func A();")]);
+        assert_eq!(messages(&resolution.diagnostics), ["the name `A` is defined multiple times"]);
+    }
+
+    #[test]
+    fn test_enums_and_patterns() {
+        let (_, resolution) = resolve_sources(&[
+            (
+                &[],
+                "use colors::Color; func f(c: Color) -> u8 { match c { Color::Red => 1, colors::Color::Green => 2, other => 3 } }",
+            ),
+            (&["colors"], "pub enum Color { Red, Green }"),
+        ]);
+
+        assert!(resolution.diagnostics.is_empty(), "{:?}", messages(&resolution.diagnostics));
+        let color = resolution.item(&["colors".to_owned()], "Color").unwrap();
+        assert_eq!(resolution.def(color).kind, DefKind::Enum);
+        assert_eq!(resolution.variants(color).len(), 2);
+
+        let red = resolution.variant(color, "Red").unwrap();
+        assert_eq!(resolution.enum_of(red), Some(color));
+        assert_eq!(
+            resolution.lookup(&[], &["colors", "Color", "Green"]),
+            resolution.variant(color, "Green")
+        );
+
+        let (_, resolution) = resolve_sources(&[(
+            &[],
+            "enum Color { Red, Red }\n\
+            func f(c: Color, n: u8) { match c { Color::Blue => 1, _ => 2 }; match n { 1 | x => x, y | y => y }; }",
+        )]);
+
+        assert_eq!(
+            messages(&resolution.diagnostics),
+            [
+                "the variant `Red` is defined multiple times",
+                "no variant `Blue` in enum `Color`",
+                "cannot bind `x` inside a `|` pattern",
+                "cannot bind `y` inside a `|` pattern",
+                "cannot bind `y` inside a `|` pattern",
+            ]
+        );
+    }
+}
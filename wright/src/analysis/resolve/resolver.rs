@@ -0,0 +1,1270 @@
+//! The implementation of name resolution, which walks the AST and fills in a [Resolution].
+
+use super::{Binding, DefId, DefKind, Definition, ModuleScope, Resolution, module_bodies};
+use crate::{
+    analysis::{builtins::Builtin, privacy::ItemUse},
+    ast::{
+        decl::{
+            Decl, Visibility,
+            constraint::ConstraintDecl,
+            func::FuncDecl,
+            generic::{GenericParam, WherePredicate},
+            implementation::ImplDecl,
+            import::ImportDecl,
+            module::ModuleDecl,
+            record::FieldDecl,
+        },
+        expr::{Atom, Expr},
+        identifier::Identifier,
+        module::Module,
+        path::Path,
+        pattern::Pattern,
+        stmt::{Block, Stmt},
+        ty::Type,
+    },
+    reporting::{Diagnostic, Highlight},
+    source_tracking::fragment::Fragment,
+    util::edit_distance::best_match,
+};
+use std::collections::HashSet;
+
+/// One step of a resolved path -- the definition a segment resolved to, and the binding it was found through if
+/// it was found in another module (which needs to be privacy checked).
+struct Step {
+    def: DefId,
+    through: Option<(Vec<String>, Binding)>,
+}
+
+/// Why a path could not be resolved.
+enum Unresolved {
+    /// The segment at this index could not be found.
+    NotFound(usize),
+
+    /// The segment at this index resolved to something that is not a module, but more segments follow it.
+    NotAModule(usize, DefId),
+}
+
+/// The state of name resolution.
+pub(super) struct Resolver {
+    /// The resolution being built.
+    res: Resolution,
+
+    /// The path of the module currently being resolved.
+    module: Vec<String>,
+
+    /// The stack of local scopes (generic parameters, function parameters, and `let` bindings) -- later bindings
+    /// shadow earlier ones with the same name.
+    locals: Vec<(String, DefId)>,
+
+    /// The names (and the modules they're in) of imports that could not be resolved. Uses of these names have
+    /// already been reported, so they're not reported again.
+    failed_imports: HashSet<(Vec<String>, String)>,
+}
+
+impl Resolver {
+    /// Run name resolution over all the given modules.
+    pub(super) fn run(modules: Vec<(Vec<String>, &Module)>) -> Resolution {
+        let mut resolver = Resolver {
+            res: Resolution::default(),
+            module: Vec::new(),
+            locals: Vec::new(),
+            failed_imports: HashSet::new(),
+        };
+
+        // Create the module tree, including the root and every ancestor of every module.
+        resolver.define_module(&[]);
+
+        for builtin in Builtin::ALL {
+            resolver.define_builtin(builtin);
+        }
+
+        for (path, _) in &modules {
+            for depth in 1..=path.len() {
+                resolver.define_module(&path[..depth]);
+            }
+        }
+
+        // Inline modules are resolved the same way as modules from files.
+        let bodies = module_bodies(&modules);
+
+        // Define all of the items in every module.
+        for (path, items) in &bodies {
+            for decl in *items {
+                let kind = match decl {
+                    Decl::Import(_) => continue,
+                    Decl::Module(module) => {
+                        resolver.define_module_decl(path, module);
+                        continue;
+                    }
+                    Decl::Impl(implementation) => {
+                        resolver.define_impl(implementation, path);
+                        continue;
+                    }
+                    Decl::TypeAlias(_) => DefKind::TypeAlias,
+                    Decl::Constant(_) => DefKind::Constant,
+                    Decl::Function(_) => DefKind::Function,
+                    Decl::Constraint(_) => DefKind::Constraint,
+                    Decl::Record(_) => DefKind::Record,
+                    Decl::Union(_) => DefKind::Union,
+                    Decl::Enum(_) => DefKind::Enum,
+                    Decl::Trait(_) => DefKind::Trait,
+                };
+
+                let name = decl
+                    .name()
+                    .expect("every item other than an `impl` block is named");
+                let def = resolver.define(name, kind, path.clone());
+                resolver.bind(path, name, def, Some(decl.visibility().clone()));
+
+                match decl {
+                    Decl::Enum(enumeration) => {
+                        resolver.define_variants(def, &enumeration.variants, path)
+                    }
+
+                    Decl::Trait(trait_decl) => {
+                        resolver.define_self_type(def, path);
+                        resolver.define_methods(&trait_decl.methods, path);
+                    }
+
+                    _ => {}
+                }
+            }
+        }
+
+        resolver.resolve_imports(&bodies);
+
+        // Resolve the signatures and bodies of every item.
+        for (path, items) in &bodies {
+            resolver.module = path.clone();
+
+            for decl in *items {
+                match decl {
+                    Decl::Import(_) | Decl::Module(_) | Decl::Enum(_) => {}
+                    Decl::TypeAlias(alias) => {
+                        if let Some(target) = &alias.target_type {
+                            resolver.resolve_type(target);
+                        }
+                    }
+                    Decl::Constant(constant) => resolver.resolve_type(&constant.ty),
+                    Decl::Function(func) => resolver.resolve_function(func),
+                    Decl::Constraint(constraint) => resolver.resolve_constraint(constraint),
+                    Decl::Record(record) => {
+                        resolver.resolve_fields(&record.generic_params, &record.fields)
+                    }
+                    Decl::Union(union) => {
+                        resolver.resolve_fields(&union.generic_params, &union.variants)
+                    }
+                    Decl::Trait(trait_decl) => {
+                        let def = (resolver.res)
+                            .declared(&trait_decl.name.fragment)
+                            .expect("traits are defined before they are resolved");
+
+                        resolver.resolve_methods(def, &trait_decl.methods);
+                    }
+                    Decl::Impl(implementation) => resolver.resolve_impl(implementation),
+                }
+            }
+        }
+
+        resolver.res
+    }
+
+    /// Create a new [Definition].
+    fn define(&mut self, name: &Identifier, kind: DefKind, module: Vec<String>) -> DefId {
+        let id = DefId(self.res.defs.len() as u32);
+
+        self.res.defs.push(Definition {
+            name: name.fragment.as_str().to_owned(),
+            kind,
+            name_fragment: Some(name.fragment.clone()),
+            module,
+        });
+
+        self.res.decls.insert(name.fragment.clone(), id);
+        id
+    }
+
+    /// Define the variants of an enum, reporting any that are declared more than once.
+    fn define_variants(&mut self, enumeration: DefId, variants: &[Identifier], module: &[String]) {
+        let mut defined: Vec<DefId> = Vec::new();
+
+        for variant in variants {
+            let name = variant.fragment.as_str();
+            let previous = (defined.iter())
+                .find(|def| self.res.def(**def).name == name)
+                .and_then(|def| self.res.def(*def).name_fragment.clone());
+
+            if let Some(previous) = previous {
+                self.res.diagnostics.push(
+                    Diagnostic::error()
+                        .with_message(format!("the variant `{name}` is defined multiple times"))
+                        .with_highlights([
+                            Highlight::primary(
+                                variant.fragment.clone(),
+                                format!("`{name}` redefined here"),
+                            ),
+                            Highlight::secondary(
+                                previous,
+                                format!("previous definition of `{name}` here"),
+                            ),
+                        ]),
+                );
+
+                continue;
+            }
+
+            let def = self.define(variant, DefKind::Variant, module.to_vec());
+            self.res.variant_enums.insert(def, enumeration);
+            defined.push(def);
+        }
+
+        self.res.variants.insert(enumeration, defined);
+    }
+
+    /// Define an `impl` block (by its header), its `Self` type, and its methods.
+    fn define_impl(&mut self, implementation: &ImplDecl, module: &[String]) {
+        let id = DefId(self.res.defs.len() as u32);
+
+        self.res.defs.push(Definition {
+            name: "impl".to_owned(),
+            kind: DefKind::Impl,
+            name_fragment: Some(implementation.header.clone()),
+            module: module.to_vec(),
+        });
+
+        self.res.decls.insert(implementation.header.clone(), id);
+        self.define_self_type(id, module);
+        self.define_methods(&implementation.methods, module);
+    }
+
+    /// Define the `Self` type of a trait or `impl` block.
+    fn define_self_type(&mut self, owner: DefId, module: &[String]) {
+        let id = DefId(self.res.defs.len() as u32);
+
+        self.res.defs.push(Definition {
+            name: "Self".to_owned(),
+            kind: DefKind::SelfType,
+            name_fragment: None,
+            module: module.to_vec(),
+        });
+
+        self.res.self_types.insert(owner, id);
+    }
+
+    /// Define the methods of a trait or `impl` block, reporting any that are declared more than once.
+    fn define_methods(&mut self, methods: &[FuncDecl], module: &[String]) {
+        let mut defined: Vec<&FuncDecl> = Vec::new();
+
+        for method in methods {
+            let name = method.name.fragment.as_str();
+
+            if let Some(previous) = defined
+                .iter()
+                .find(|previous| previous.name.fragment.as_str() == name)
+            {
+                self.res.diagnostics.push(
+                    Diagnostic::error()
+                        .with_message(format!("the method `{name}` is defined multiple times"))
+                        .with_highlights([
+                            Highlight::primary(
+                                method.name.fragment.clone(),
+                                format!("`{name}` redefined here"),
+                            ),
+                            Highlight::secondary(
+                                previous.name.fragment.clone(),
+                                format!("previous definition of `{name}` here"),
+                            ),
+                        ]),
+                );
+            }
+
+            self.define(&method.name, DefKind::Method, module.to_vec());
+            defined.push(method);
+        }
+    }
+
+    /// Create the definition of a [Builtin] function, along with the modules it's defined in.
+    fn define_builtin(&mut self, builtin: Builtin) {
+        let module: Vec<String> = builtin
+            .module()
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+
+        for depth in 1..=module.len() {
+            self.define_module(&module[..depth]);
+        }
+
+        let id = DefId(self.res.defs.len() as u32);
+
+        self.res.defs.push(Definition {
+            name: builtin.name().to_owned(),
+            kind: DefKind::Function,
+            name_fragment: None,
+            module: module.clone(),
+        });
+
+        let binding = Binding {
+            def: id,
+            visibility: None,
+            name_fragment: None,
+        };
+
+        self.scope_mut(&module)
+            .names
+            .insert(builtin.name().to_owned(), binding);
+        self.res.builtins.insert(id, builtin);
+    }
+
+    /// Create the definition of a module from a source file (if it does not already exist), and bind it in its
+    /// parent.
+    fn define_module(&mut self, path: &[String]) {
+        if self.res.module_defs.contains_key(path) {
+            return;
+        }
+
+        let id = DefId(self.res.defs.len() as u32);
+        let (name, parent) = path
+            .split_last()
+            .map_or((String::new(), &[][..]), |(name, parent)| (name.clone(), parent));
+
+        self.res.defs.push(Definition {
+            name: name.clone(),
+            kind: DefKind::Module,
+            name_fragment: None,
+            module: parent.to_vec(),
+        });
+
+        self.res.module_defs.insert(path.to_vec(), id);
+        self.res
+            .modules
+            .insert(path.to_vec(), ModuleScope::default());
+
+        if !path.is_empty() {
+            let binding = Binding {
+                def: id,
+                visibility: None,
+                name_fragment: None,
+            };
+
+            self.scope_mut(parent).names.insert(name, binding);
+        }
+    }
+
+    /// Define the module declared by a `mod` declaration in the given parent module.
+    ///
+    /// Modules from files have already been defined (by their path) when this is called, so their definition and
+    /// binding are updated with the name and visibility from the declaration. Otherwise (for inline modules, or
+    /// `mod name;` declarations whose files could not be loaded) the module is defined here.
+    fn define_module_decl(&mut self, parent: &[String], decl: &ModuleDecl) {
+        let mut path = parent.to_vec();
+        path.push(decl.name.fragment.as_str().to_owned());
+
+        let existing = self.res.module_defs.get(&path).copied();
+
+        if let Some(id) = existing {
+            let binding = self
+                .scope_mut(parent)
+                .names
+                .get(decl.name.fragment.as_str())
+                .cloned();
+
+            // Only claim the module if nothing else has yet -- otherwise this is a duplicate.
+            if binding.is_some_and(|binding| binding.def == id && binding.name_fragment.is_none()) {
+                self.res.defs[id.index()].name_fragment = Some(decl.name.fragment.clone());
+                self.res.decls.insert(decl.name.fragment.clone(), id);
+                self.scope_mut(parent)
+                    .names
+                    .remove(decl.name.fragment.as_str());
+                self.bind(parent, &decl.name, id, Some(decl.visibility.clone()));
+                return;
+            }
+        }
+
+        let id = self.define(&decl.name, DefKind::Module, parent.to_vec());
+        self.bind(parent, &decl.name, id, Some(decl.visibility.clone()));
+
+        // If the name was already taken, the module still gets a scope of its own to resolve its body in, but it
+        // can't be named from anywhere.
+        if existing.is_none() {
+            self.res.module_defs.insert(path.clone(), id);
+            self.res.modules.insert(path, ModuleScope::default());
+        }
+    }
+
+    /// Get the scope of a module, which must exist.
+    fn scope_mut(&mut self, module: &[String]) -> &mut ModuleScope {
+        self.res
+            .modules
+            .get_mut(module)
+            .expect("modules are defined before they are used")
+    }
+
+    /// Bind a name in a module, reporting an error if it's already bound.
+    fn bind(
+        &mut self,
+        module: &[String],
+        name: &Identifier,
+        def: DefId,
+        visibility: Option<Visibility>,
+    ) {
+        let binding = Binding {
+            def,
+            visibility,
+            name_fragment: Some(name.fragment.clone()),
+        };
+
+        let existing = self
+            .scope_mut(module)
+            .names
+            .insert(name.fragment.as_str().to_owned(), binding.clone());
+
+        let Some(existing) = existing else {
+            return;
+        };
+
+        // Keep the first binding, and report the duplicate.
+        self.scope_mut(module)
+            .names
+            .insert(name.fragment.as_str().to_owned(), existing.clone());
+
+        let mut highlights = vec![Highlight::primary(
+            name.fragment.clone(),
+            format!("`{}` redefined here", name.fragment.as_str()),
+        )];
+
+        if let Some(previous) = existing.name_fragment {
+            highlights.push(Highlight::secondary(
+                previous,
+                format!("previous definition of `{}` here", name.fragment.as_str()),
+            ));
+        }
+
+        self.res.diagnostics.push(
+            Diagnostic::error()
+                .with_message(format!(
+                    "the name `{}` is defined multiple times",
+                    name.fragment.as_str()
+                ))
+                .with_highlights(highlights)
+                .with_notes(["names must be unique within a module"]),
+        );
+    }
+
+    /// Resolve every import. Imports can refer to names bound by other imports, so this repeats until no more
+    /// imports can be resolved, and then reports errors for any that are left.
+    fn resolve_imports(&mut self, bodies: &[(Vec<String>, &[Decl])]) {
+        let mut pending: Vec<(&Vec<String>, &ImportDecl)> = bodies
+            .iter()
+            .flat_map(|(path, items)| {
+                items.iter().filter_map(move |decl| match decl {
+                    Decl::Import(import) => Some((path, import)),
+                    _ => None,
+                })
+            })
+            .collect();
+
+        loop {
+            let mut progress = false;
+            let mut index = 0;
+
+            while index < pending.len() {
+                let (module, import) = pending[index];
+                let segments = path_segments(&import.imported_item);
+
+                match self.try_resolve_path(module, &segments, false) {
+                    Ok(steps) => {
+                        let def = steps.last().expect("paths have at least one segment").def;
+                        let name = import
+                            .imported_as
+                            .as_ref()
+                            .unwrap_or_else(|| import.imported_item.last());
+
+                        self.module = module.clone();
+                        self.record_path(&import.imported_item, &segments, steps);
+                        self.bind(module, name, def, Some(import.visibility.clone()));
+                        pending.swap_remove(index);
+                        progress = true;
+                    }
+
+                    Err(_) => index += 1,
+                }
+            }
+
+            if !progress {
+                break;
+            }
+        }
+
+        for (module, import) in pending {
+            self.module = module.clone();
+            self.resolve_path(&import.imported_item);
+
+            let name = import
+                .imported_as
+                .as_ref()
+                .unwrap_or_else(|| import.imported_item.last());
+
+            self.failed_imports
+                .insert((module.clone(), name.fragment.as_str().to_owned()));
+        }
+    }
+
+    /// Record the uses of each segment of a successfully resolved path, privacy checking any items used from other
+    /// modules.
+    fn record_path(&mut self, path: &Path, segments: &[&Identifier], steps: Vec<Step>) {
+        let def = steps.last().expect("paths have at least one segment").def;
+
+        for (index, (segment, step)) in segments.iter().zip(&steps).enumerate() {
+            // The last segment is recorded as part of the whole path below.
+            if index + 1 < segments.len() {
+                self.res.uses.insert(segment.fragment.clone(), step.def);
+            }
+
+            let Some((declared_in, binding)) = &step.through else {
+                continue;
+            };
+
+            let (Some(visibility), Some(decl_name)) = (&binding.visibility, &binding.name_fragment)
+            else {
+                continue;
+            };
+
+            let item_use = ItemUse {
+                use_site: segment.fragment.clone(),
+                used_from: &self.module,
+                decl_name,
+                declared_in,
+                visibility,
+            };
+
+            if let Some(diagnostic) = item_use.check() {
+                self.res.diagnostics.push(diagnostic);
+            }
+        }
+
+        self.res.uses.insert(path.full_path.clone(), def);
+    }
+
+    /// Look up a single name in the current scope -- locals, then the current module, then the root module.
+    fn lookup_in_scope(&self, name: &str, allow_locals: bool) -> Option<Step> {
+        if allow_locals {
+            if let Some((_, def)) = self.locals.iter().rev().find(|(local, _)| local == name) {
+                return Some(Step {
+                    def: *def,
+                    through: None,
+                });
+            }
+        }
+
+        if let Some(binding) = self.res.modules.get(&self.module)?.names.get(name) {
+            return Some(Step {
+                def: binding.def,
+                through: None,
+            });
+        }
+
+        // Fall back to names declared in the root of the package.
+        self.res
+            .modules
+            .get(&[][..])?
+            .names
+            .get(name)
+            .map(|binding| Step {
+                def: binding.def,
+                through: Some((Vec::new(), binding.clone())),
+            })
+    }
+
+    /// Try to resolve a path from the given module without recording anything.
+    fn try_resolve_path(
+        &mut self,
+        from: &[String],
+        segments: &[&Identifier],
+        allow_locals: bool,
+    ) -> Result<Vec<Step>, Unresolved> {
+        let current = std::mem::replace(&mut self.module, from.to_vec());
+        let (head, rest) = segments
+            .split_first()
+            .expect("paths have at least one segment");
+
+        // Locals can never be the start of a longer path.
+        let head_step =
+            self.lookup_in_scope(head.fragment.as_str(), allow_locals && rest.is_empty());
+        self.module = current;
+
+        let mut steps = vec![head_step.ok_or(Unresolved::NotFound(0))?];
+
+        for (index, segment) in rest.iter().enumerate() {
+            let previous = steps[index].def;
+
+            // Variants are named through their enum, and are visible wherever it is.
+            if self.res.def(previous).kind == DefKind::Enum {
+                let def = (self.res)
+                    .variant(previous, segment.fragment.as_str())
+                    .ok_or(Unresolved::NotFound(index + 1))?;
+
+                steps.push(Step { def, through: None });
+                continue;
+            }
+
+            let module_path = self
+                .res
+                .module_path(previous)
+                .ok_or(Unresolved::NotAModule(index, previous))?;
+
+            let binding = self
+                .res
+                .modules
+                .get(&module_path)
+                .and_then(|scope| scope.names.get(segment.fragment.as_str()))
+                .ok_or(Unresolved::NotFound(index + 1))?;
+
+            steps.push(Step {
+                def: binding.def,
+                through: Some((module_path, binding.clone())),
+            });
+        }
+
+        Ok(steps)
+    }
+
+    /// Resolve a path (or a single identifier) in a type or module-level context, reporting an error if it can't
+    /// be resolved.
+    fn resolve_path(&mut self, path: &Path) -> Option<DefId> {
+        let segments = path_segments(path);
+        self.resolve_segments(path, &segments, false)
+    }
+
+    /// Resolve the segments of a path, recording uses and reporting errors.
+    fn resolve_segments(
+        &mut self,
+        path: &Path,
+        segments: &[&Identifier],
+        allow_locals: bool,
+    ) -> Option<DefId> {
+        let module = self.module.clone();
+
+        match self.try_resolve_path(&module, segments, allow_locals) {
+            Ok(steps) => {
+                let def = steps.last().map(|step| step.def);
+                self.record_path(path, segments, steps);
+                def
+            }
+
+            Err(unresolved) => {
+                self.report_unresolved(segments, unresolved, allow_locals);
+                None
+            }
+        }
+    }
+
+    /// Report an error for a path that could not be resolved.
+    fn report_unresolved(
+        &mut self,
+        segments: &[&Identifier],
+        unresolved: Unresolved,
+        allow_locals: bool,
+    ) {
+        let diagnostic = match unresolved {
+            Unresolved::NotFound(0) => {
+                let name = segments[0].fragment.as_str();
+
+                if self
+                    .failed_imports
+                    .contains(&(self.module.clone(), name.to_owned()))
+                {
+                    return;
+                }
+
+                // Suggest names from every scope that would have been searched.
+                let mut candidates: Vec<(&str, DefId)> = Vec::new();
+
+                if allow_locals && segments.len() == 1 {
+                    candidates.extend(
+                        self.locals
+                            .iter()
+                            .rev()
+                            .map(|(name, def)| (name.as_str(), *def)),
+                    );
+                }
+
+                for module in [&self.module[..], &[]] {
+                    if let Some(scope) = self.res.modules.get(module) {
+                        candidates.extend(
+                            scope
+                                .names
+                                .iter()
+                                .map(|(name, binding)| (name.as_str(), binding.def)),
+                        );
+                    }
+                }
+
+                self.unresolved_diagnostic(
+                    segments[0],
+                    format!("cannot find `{name}` in this scope"),
+                    "not found in this scope",
+                    &candidates,
+                )
+            }
+
+            Unresolved::NotFound(index) => {
+                let name = segments[index].fragment.as_str();
+                let module_def = self
+                    .res
+                    .resolved(&segments[index - 1].fragment)
+                    .or_else(|| self.lookup_prefix(&segments[..index]))
+                    .expect("the previous segment was resolved");
+
+                if self.res.def(module_def).kind == DefKind::Enum {
+                    let enum_name = &self.res.def(module_def).name;
+                    let candidates: Vec<(&str, DefId)> = (self.res.variants(module_def).iter())
+                        .map(|variant| (self.res.def(*variant).name.as_str(), *variant))
+                        .collect();
+
+                    let diagnostic = self.unresolved_diagnostic(
+                        segments[index],
+                        format!("no variant `{name}` in enum `{enum_name}`"),
+                        &format!("not a variant of `{enum_name}`"),
+                        &candidates,
+                    );
+
+                    self.res.diagnostics.push(diagnostic);
+                    return;
+                }
+
+                let module_path = self.res.module_path(module_def).unwrap_or_default();
+
+                let candidates: Vec<(&str, DefId)> = self
+                    .res
+                    .modules
+                    .get(&module_path)
+                    .map(|scope| {
+                        scope
+                            .names
+                            .iter()
+                            .map(|(name, binding)| (name.as_str(), binding.def))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+
+                let module_name = module_path.join("::");
+
+                self.unresolved_diagnostic(
+                    segments[index],
+                    format!("cannot find `{name}` in module `{module_name}`"),
+                    &format!("not found in `{module_name}`"),
+                    &candidates,
+                )
+            }
+
+            Unresolved::NotAModule(index, def) => {
+                let def = self.res.def(def);
+                let name = segments[index].fragment.as_str();
+
+                let mut highlights = vec![Highlight::primary(
+                    segments[index].fragment.clone(),
+                    format!("`{name}` is a {}, not a module", def.kind.describe()),
+                )];
+
+                if let Some(decl) = &def.name_fragment {
+                    highlights.push(Highlight::secondary(
+                        decl.clone(),
+                        format!("`{name}` is defined here"),
+                    ));
+                }
+
+                Diagnostic::error()
+                    .with_message(format!(
+                        "expected module, found {} `{name}`",
+                        def.kind.describe()
+                    ))
+                    .with_highlights(highlights)
+            }
+        };
+
+        self.res.diagnostics.push(diagnostic);
+    }
+
+    /// Look up the definition that a prefix of a path refers to (without recording anything).
+    fn lookup_prefix(&mut self, segments: &[&Identifier]) -> Option<DefId> {
+        let module = self.module.clone();
+
+        self.try_resolve_path(&module, segments, false)
+            .ok()
+            .and_then(|steps| steps.last().map(|step| step.def))
+    }
+
+    /// Build the diagnostic for a name that was not found, suggesting the closest candidate if there is one.
+    fn unresolved_diagnostic(
+        &self,
+        segment: &Identifier,
+        message: String,
+        label: &str,
+        candidates: &[(&str, DefId)],
+    ) -> Diagnostic {
+        let name = segment.fragment.as_str();
+        let mut highlights = vec![Highlight::primary(segment.fragment.clone(), label)];
+        let mut notes = Vec::new();
+
+        if let Some(suggestion) = best_match(name, candidates.iter().map(|(name, _)| *name)) {
+            notes.push(format!("help: did you mean `{suggestion}`?"));
+
+            let def = candidates
+                .iter()
+                .find(|(candidate, _)| *candidate == suggestion)
+                .map(|(_, def)| self.res.def(*def));
+
+            if let Some(decl) = def.and_then(|def| def.name_fragment.as_ref()) {
+                highlights.push(Highlight::secondary(
+                    decl.clone(),
+                    format!(
+                        "a {} with a similar name is defined here",
+                        def.map_or("definition", |def| def.kind.describe())
+                    ),
+                ));
+            }
+        }
+
+        Diagnostic::error()
+            .with_message(message)
+            .with_highlights(highlights)
+            .with_notes(notes)
+    }
+
+    /// Resolve the paths in a type signature.
+    fn resolve_type(&mut self, ty: &Type) {
+        match ty {
+            Type::Atomic(_) => {}
+            Type::Reference(reference) => self.resolve_type(&reference.target_ty),
+
+            Type::Named(named) => {
+                let segments = path_segments(&named.name);
+                // Generic parameters are locals, but they're only ever a single identifier.
+                self.resolve_segments(&named.name, &segments, true);
+
+                for generic in &named.generic_tys {
+                    self.resolve_type(generic);
+                }
+            }
+
+            Type::Constrained(constrained) => {
+                self.resolve_type(&constrained.base_ty);
+
+                for constraint in &constrained.constraining_items {
+                    self.resolve_path(constraint);
+                }
+            }
+
+            Type::Function(function) => {
+                for param in &function.params {
+                    self.resolve_type(param);
+                }
+
+                if let Some(return_ty) = &function.return_ty {
+                    self.resolve_type(return_ty);
+                }
+            }
+        }
+    }
+
+    /// Define generic parameters as locals, and resolve their bounds.
+    fn define_generic_params(&mut self, params: &[GenericParam]) {
+        for param in params {
+            let def = self.define(&param.name, DefKind::GenericParam, self.module.clone());
+            self.locals
+                .push((param.name.fragment.as_str().to_owned(), def));
+        }
+
+        for param in params {
+            for bound in &param.bounds {
+                self.resolve_path(bound);
+            }
+        }
+    }
+
+    /// Resolve the types of the fields of a record (or the variants of a union).
+    fn resolve_fields(&mut self, generic_params: &[GenericParam], fields: &[FieldDecl]) {
+        let scope_start = self.locals.len();
+        self.define_generic_params(generic_params);
+
+        for field in fields {
+            self.resolve_type(&field.ty);
+        }
+
+        self.locals.truncate(scope_start);
+    }
+
+    /// Resolve the signature and body of a function.
+    fn resolve_function(&mut self, func: &FuncDecl) {
+        let scope_start = self.locals.len();
+
+        self.define_generic_params(&func.generic_params);
+
+        for param in &func.params {
+            self.resolve_type(&param.ty);
+        }
+
+        if let Some(return_ty) = &func.return_ty {
+            self.resolve_type(return_ty);
+        }
+
+        self.resolve_where_clause(&func.where_clause);
+
+        // Parameters are defined after all the types are resolved, so they can't shadow type names in the
+        // signature.
+        for param in &func.params {
+            let def = self.define(&param.name, DefKind::Param, self.module.clone());
+            self.locals
+                .push((param.name.fragment.as_str().to_owned(), def));
+        }
+
+        if let Some(body) = &func.body {
+            self.resolve_block(body);
+        }
+
+        self.locals.truncate(scope_start);
+    }
+
+    /// Resolve the methods of a trait or `impl` block, with `Self` in scope.
+    fn resolve_methods(&mut self, owner: DefId, methods: &[FuncDecl]) {
+        let self_type = self
+            .res
+            .self_type(owner)
+            .expect("`Self` is defined with its owner");
+        self.locals.push(("Self".to_owned(), self_type));
+
+        for method in methods {
+            self.resolve_function(method);
+        }
+
+        self.locals.pop();
+    }
+
+    /// Resolve an `impl` block -- its generic parameters, the trait and type it's for, its `where` clause, and its
+    /// methods.
+    fn resolve_impl(&mut self, implementation: &ImplDecl) {
+        let def = (self.res)
+            .declared(&implementation.header)
+            .expect("`impl` blocks are defined before they are resolved");
+
+        let scope_start = self.locals.len();
+        self.define_generic_params(&implementation.generic_params);
+
+        if let Some(trait_path) = &implementation.trait_path {
+            self.resolve_path(trait_path);
+        }
+
+        self.resolve_type(&implementation.self_ty);
+        self.resolve_where_clause(&implementation.where_clause);
+        self.resolve_methods(def, &implementation.methods);
+        self.locals.truncate(scope_start);
+    }
+
+    /// Resolve the types and bounds in a `where` clause.
+    fn resolve_where_clause(&mut self, predicates: &[WherePredicate]) {
+        for predicate in predicates {
+            self.resolve_type(&predicate.ty);
+
+            for bound in &predicate.bounds {
+                self.resolve_path(bound);
+            }
+        }
+    }
+
+    /// Resolve a constraint declaration -- its bases, and the parameter and body of its predicate if it has one.
+    fn resolve_constraint(&mut self, constraint: &ConstraintDecl) {
+        let scope_start = self.locals.len();
+
+        self.define_generic_params(&constraint.generic_params);
+
+        for base in &constraint.bases {
+            self.resolve_path(base);
+        }
+
+        if let Some(predicate) = &constraint.predicate {
+            let param = &predicate.param;
+            self.resolve_type(&param.ty);
+
+            let def = self.define(&param.name, DefKind::Param, self.module.clone());
+            self.locals
+                .push((param.name.fragment.as_str().to_owned(), def));
+
+            self.resolve_block(&predicate.body);
+        }
+
+        self.locals.truncate(scope_start);
+    }
+
+    /// Resolve a block, with its own scope for any `let` bindings in it.
+    fn resolve_block(&mut self, block: &Block) {
+        let scope_start = self.locals.len();
+
+        for stmt in &block.stmts {
+            match stmt {
+                Stmt::Let(let_stmt) => {
+                    // The value is resolved before the binding is in scope, so `let x = x;` refers to an outer `x`.
+                    self.resolve_expr(&let_stmt.value);
+
+                    if let Some(ty) = &let_stmt.ty {
+                        self.resolve_type(ty);
+                    }
+
+                    let kind = DefKind::Local {
+                        is_mutable: let_stmt.is_mutable,
+                    };
+
+                    let def = self.define(&let_stmt.name, kind, self.module.clone());
+                    self.locals
+                        .push((let_stmt.name.fragment.as_str().to_owned(), def));
+                }
+
+                Stmt::Assign(assign) => {
+                    self.resolve_expr(&assign.target);
+                    self.resolve_expr(&assign.value);
+                }
+
+                Stmt::Expr(expr_stmt) => self.resolve_expr(&expr_stmt.expr),
+            }
+        }
+
+        if let Some(tail) = &block.tail {
+            self.resolve_expr(tail);
+        }
+
+        self.locals.truncate(scope_start);
+    }
+
+    /// Resolve all the names used in an expression.
+    fn resolve_expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Atom(Atom::Identifier(identifier)) => {
+                let path = Path {
+                    full_path: identifier.fragment.clone(),
+                    head: identifier.clone(),
+                    tail: Vec::new(),
+                };
+
+                self.resolve_segments(&path, &[identifier], true);
+            }
+
+            Expr::Atom(_) => {}
+            Expr::Path(path) => {
+                self.resolve_path(path);
+            }
+            Expr::Parens(parens) => self.resolve_expr(&parens.inner),
+            Expr::Unary(unary) => self.resolve_expr(&unary.operand),
+
+            Expr::Binary(binary) => {
+                self.resolve_expr(&binary.lhs);
+                self.resolve_expr(&binary.rhs);
+            }
+
+            Expr::Call(call) => {
+                self.resolve_expr(&call.callee);
+
+                for arg in &call.args {
+                    self.resolve_expr(arg);
+                }
+            }
+
+            Expr::Is(is_expr) => {
+                self.resolve_expr(&is_expr.value);
+                self.resolve_is_target(&is_expr.target);
+            }
+
+            // Fields are resolved by the type checker, once the type of the base is known.
+            Expr::Field(field) => self.resolve_expr(&field.base),
+
+            // Like fields, the field names in record literals are resolved by the type checker.
+            Expr::Record(record) => {
+                self.resolve_path(&record.name);
+
+                for field in &record.fields {
+                    self.resolve_expr(&field.value);
+                }
+            }
+
+            Expr::Block(block) => self.resolve_block(block),
+            Expr::Unsafe(unsafe_expr) => self.resolve_block(&unsafe_expr.body),
+
+            Expr::If(if_expr) => {
+                self.resolve_expr(&if_expr.condition);
+                self.resolve_block(&if_expr.then_block);
+
+                if let Some(else_branch) = &if_expr.else_branch {
+                    self.resolve_expr(else_branch);
+                }
+            }
+
+            // The names bound by the pattern of each arm are in scope in its guard and body.
+            Expr::Match(match_expr) => {
+                self.resolve_expr(&match_expr.scrutinee);
+
+                for arm in &match_expr.arms {
+                    let scope_start = self.locals.len();
+                    self.resolve_pattern(&arm.pattern, scope_start, false);
+
+                    if let Some(guard) = &arm.guard {
+                        self.resolve_expr(guard);
+                    }
+
+                    self.resolve_expr(&arm.body);
+                    self.locals.truncate(scope_start);
+                }
+            }
+
+            Expr::While(while_expr) => {
+                self.resolve_expr(&while_expr.condition);
+                self.resolve_block(&while_expr.body);
+            }
+
+            // The binding is only in scope in the body, not in the range.
+            Expr::For(for_expr) => {
+                self.resolve_expr(&for_expr.range.start);
+                self.resolve_expr(&for_expr.range.end);
+
+                let scope_start = self.locals.len();
+                let kind = DefKind::Local { is_mutable: false };
+                let def = self.define(&for_expr.binding, kind, self.module.clone());
+                self.locals
+                    .push((for_expr.binding.fragment.as_str().to_owned(), def));
+
+                self.resolve_block(&for_expr.body);
+                self.locals.truncate(scope_start);
+            }
+
+            Expr::Closure(closure) => {
+                let scope_start = self.locals.len();
+
+                for param in &closure.params {
+                    if let Some(ty) = &param.ty {
+                        self.resolve_type(ty);
+                    }
+
+                    let def = self.define(&param.name, DefKind::Param, self.module.clone());
+                    self.locals
+                        .push((param.name.fragment.as_str().to_owned(), def));
+                }
+
+                if let Some(return_ty) = &closure.return_ty {
+                    self.resolve_type(return_ty);
+                }
+
+                self.resolve_block(&closure.body);
+                self.locals.truncate(scope_start);
+            }
+        }
+    }
+
+    /// Resolve the target of an `is` test. Variants of unions (i.e. `Number::small`) aren't definitions, so like
+    /// fields, the variant name at the end of the path is resolved by the type checker.
+    fn resolve_is_target(&mut self, target: &Type) {
+        if let Type::Named(named) = target
+            && named.generic_tys.is_empty()
+            && !named.name.tail.is_empty()
+        {
+            let segments = path_segments(&named.name);
+            let (_, prefix) = segments
+                .split_last()
+                .expect("paths have at least one segment");
+
+            let is_union_variant = self.lookup_prefix(&segments).is_none()
+                && (self.lookup_prefix(prefix))
+                    .is_some_and(|def| self.res.def(def).kind == DefKind::Union);
+
+            if is_union_variant {
+                let last = prefix.last().expect("the path has a tail");
+                let prefix_path = Path {
+                    full_path: Fragment::cover(&named.name.head.fragment, &last.fragment),
+                    head: named.name.head.clone(),
+                    tail: named.name.tail[..named.name.tail.len() - 1].to_vec(),
+                };
+
+                self.resolve_segments(&prefix_path, prefix, false);
+                return;
+            }
+        }
+
+        self.resolve_type(target);
+    }
+
+    /// Resolve the paths in a pattern, and define the names it binds. The locals from `scope_start` onwards are the
+    /// names already bound by this pattern, which can't be bound again.
+    fn resolve_pattern(&mut self, pattern: &Pattern, scope_start: usize, in_alternative: bool) {
+        match pattern {
+            Pattern::Wildcard(_)
+            | Pattern::Integer(_)
+            | Pattern::Boolean(_)
+            | Pattern::Range(_) => {}
+
+            Pattern::Binding(name) => {
+                let text = name.fragment.as_str();
+
+                let previous = self.locals[scope_start..]
+                    .iter()
+                    .find(|(local, _)| local == text)
+                    .and_then(|(_, def)| self.res.def(*def).name_fragment.clone());
+
+                // Each alternative would bind its own value, so the name wouldn't have a single definition. It's
+                // still defined (once), so that its uses aren't reported too.
+                if in_alternative {
+                    self.res.diagnostics.push(
+                        Diagnostic::error()
+                            .with_message(format!("cannot bind `{text}` inside a `|` pattern"))
+                            .with_highlights([Highlight::primary(
+                                name.fragment.clone(),
+                                "bound here",
+                            )])
+                            .with_notes([
+                                "help: match the value with `_`, or give each alternative its own arm",
+                            ]),
+                    );
+                } else if let Some(previous) = &previous {
+                    self.res.diagnostics.push(
+                        Diagnostic::error()
+                            .with_message(format!(
+                                "`{text}` is bound more than once in the same pattern"
+                            ))
+                            .with_highlights([
+                                Highlight::primary(name.fragment.clone(), "bound again here"),
+                                Highlight::secondary(previous.clone(), "first bound here"),
+                            ]),
+                    );
+                }
+
+                if previous.is_some() {
+                    return;
+                }
+
+                let kind = DefKind::Local { is_mutable: false };
+                let def = self.define(name, kind, self.module.clone());
+                self.locals.push((text.to_owned(), def));
+            }
+
+            Pattern::Variant(path) => {
+                self.resolve_path(path);
+            }
+
+            // Like record literals, the field names are resolved by the type checker.
+            Pattern::Record(record) => {
+                self.resolve_path(&record.name);
+
+                for field in &record.fields {
+                    self.resolve_pattern(&field.pattern, scope_start, in_alternative);
+                }
+            }
+
+            Pattern::Or(or) => {
+                for alternative in &or.alternatives {
+                    self.resolve_pattern(alternative, scope_start, true);
+                }
+            }
+        }
+    }
+}
+
+/// Get the segments of a [Path] in order.
+fn path_segments(path: &Path) -> Vec<&Identifier> {
+    std::iter::once(&path.head).chain(&path.tail).collect()
+}
@@ -0,0 +1,72 @@
+//! Helpers shared by the tests of the analysis passes (and the passes after them), which run on a single module of
+//! source code.
+
+use crate::{
+    analysis::{
+        resolve::{Resolution, resolve},
+        typeck::{TypeckResults, check},
+    },
+    ast::module::Module,
+    lexer::Lexer,
+    mir::Program,
+    package::{CheckedPackage, Package, PackageModule, check_package},
+    parser::Parser,
+    reporting::{Diagnostic, Severity},
+};
+use std::path::PathBuf;
+
+/// Parse a module, and resolve its names, asserting that there are no errors.
+pub fn resolve_source(source: &'static str) -> (Module, Resolution) {
+    let module = Module::parse(&mut Parser::new(Lexer::new_test(source))).unwrap();
+    let resolution = resolve(modules(&module));
+    assert!(resolution.diagnostics.is_empty(), "{:?}", resolution.diagnostics);
+    (module, resolution)
+}
+
+/// Parse, resolve, and type check a module, asserting that there are no errors resolving it. Type errors are left
+/// in the results of type checking.
+pub fn check_source(source: &'static str) -> (Module, Resolution, TypeckResults) {
+    let (module, resolution) = resolve_source(source);
+    let typeck = check(modules(&module), &resolution);
+    (module, resolution, typeck)
+}
+
+/// Parse, resolve, and type check a module, asserting that there are no errors.
+pub fn check_valid_source(source: &'static str) -> (Module, Resolution, TypeckResults) {
+    let (module, resolution, typeck) = check_source(source);
+    assert!(typeck.diagnostics.is_empty(), "{:?}", typeck.diagnostics);
+    (module, resolution, typeck)
+}
+
+/// Parse a module, run every analysis pass over it as the only module of a package, and lower it to the MIR,
+/// asserting that there are no errors.
+pub fn lower_source(source: &'static str) -> (CheckedPackage, Program) {
+    let module = Module::parse(&mut Parser::new(Lexer::new_test(source))).unwrap();
+
+    let package = check_package(Package {
+        root: PathBuf::new(),
+        modules: vec![PackageModule {
+            path: Vec::new(),
+            module,
+        }],
+        diagnostics: Vec::new(),
+    });
+
+    let errors: Vec<_> = (package.diagnostics())
+        .filter(|diagnostic| diagnostic.0.severity == Severity::Error)
+        .collect();
+
+    assert!(errors.is_empty(), "{errors:?}");
+    let program = package.lower();
+    (package, program)
+}
+
+/// Get the modules of a package made of just one module, which is its root, for passing to a pass.
+pub fn modules(module: &Module) -> [(Vec<String>, &Module); 1] {
+    [(Vec::new(), module)]
+}
+
+/// Get the message of each diagnostic.
+pub fn messages(diagnostics: &[Diagnostic]) -> Vec<&str> {
+    diagnostics.iter().map(|d| d.0.message.as_str()).collect()
+}
@@ -0,0 +1,815 @@
+//! Type checking -- computing the [Ty] of every expression and checking that types line up.
+//!
+//! The type checker runs after [name resolution], and uses the [Resolution] to find what every name refers to. It
+//! checks:
+//! - Arithmetic, bitwise, comparison, and logical operators are applied to operands of matching [atomic] types.
+//! - `@` expressions produce references, and only references are dereferenced.
+//! - Record and union literals give their fields values of the right types, and field accesses name real fields
+//!   (with the record's generic arguments substituted into field types).
+//! - Calls pass the right number of arguments of the right types.
+//! - Method calls (`shape.area()`) find their method in an inherent `impl` block for the receiver's type, or else in
+//!   a trait that the type implements (or that a generic parameter is bound by). The receiver is borrowed or
+//!   dereferenced to match the method's `self` parameter.
+//! - The trait bounds of generic functions and `impl` blocks (`T: Shape` or `where T: Shape`) are satisfied by the
+//!   types they're instantiated with.
+//! - `impl` blocks of the same trait don't overlap, and implement every method of the trait that doesn't have a
+//!   default, with the types the trait declares.
+//! - Function bodies produce values of the declared return type.
+//! - `match` patterns match values of the matched type, and every arm produces the same type.
+//! - `is` tests name a variant of the tested union or enum. A tested `let` binding or parameter is narrowed to the
+//!   variant where the test is known to be `true` (the first branch of an `if`, the body of a `while`, the right
+//!   side of `&&`, and the body of a `match` arm it guards), and only there can the variant be read like a field.
+//! - Integer literals (including negated ones, like `-129`) fit in the integer type they're inferred to have, in
+//!   expressions and in patterns.
+//!
+//! Types are inferred wherever they aren't written: `let` bindings get the type of their value, integer literals
+//! take whichever integer type they're used as (or [DEFAULT_INTEGER] if nothing decides it), closure parameters get
+//! their types from the function type expected where the closure is passed (or from how they're used), and the
+//! generic arguments of calls and record literals are inferred from the arguments and fields. Types that can't be
+//! inferred are reported at the binding that needs an annotation.
+//!
+//! Every mismatch is reported with a highlight on the expression that has the wrong type and, where there is one,
+//! a secondary highlight on the source of the expected type (i.e. a type annotation or a function's return type).
+//!
+//! [name resolution]: crate::analysis::resolve
+//! [atomic]: crate::ast::ty::AtomicTyVariant
+
+use crate::{
+    analysis::resolve::{DefId, Resolution},
+    ast::{module::Module, ty::AtomicTyVariant},
+    reporting::Diagnostic,
+    source_tracking::fragment::Fragment,
+};
+use std::collections::HashMap;
+
+mod checker;
+mod infer;
+mod literals;
+pub mod ty;
+
+use ty::Ty;
+
+/// The type given to integer literals when nothing else decides their type.
+pub const DEFAULT_INTEGER: AtomicTyVariant = AtomicTyVariant::I32;
+
+/// The fields of a record, or the variants of a union.
+#[derive(Debug)]
+struct AdtFields {
+    /// Whether this is a union (rather than a record).
+    is_union: bool,
+    /// The generic parameters of the type.
+    generics: Vec<DefId>,
+    /// The name and type of each field, in terms of the generic parameters.
+    fields: Vec<(String, Ty)>,
+}
+
+/// An `impl` block of a trait.
+#[derive(Debug)]
+struct TraitImpl {
+    /// The trait that's implemented.
+    trait_def: DefId,
+    /// The generic parameters of the `impl` block.
+    generics: Vec<DefId>,
+    /// The type the trait is implemented for, in terms of the generic parameters.
+    self_ty: Ty,
+    /// The methods defined by the `impl` block, by name.
+    methods: HashMap<String, DefId>,
+}
+
+/// A call of a method, i.e. `shape.area()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MethodCall {
+    /// The method that's called -- a method of an `impl` block, or a method of a trait (for calls through a bound
+    /// on a generic parameter, and calls of default methods).
+    pub method: DefId,
+
+    /// How the receiver (the expression before the `.`) is passed as the method's `self` parameter.
+    pub receiver: Receiver,
+}
+
+/// How the receiver of a [MethodCall] is passed as the method's `self` parameter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Receiver {
+    /// The receiver is passed as it is.
+    Value,
+
+    /// The receiver is borrowed, for methods that take `self: @Self` or `self: @mut Self` when it isn't already a
+    /// reference.
+    Borrow {
+        /// Whether the receiver is borrowed mutably.
+        is_mutable: bool,
+    },
+
+    /// The receiver is a reference, and the value it points to is passed to a method that takes `self: Self`.
+    Deref,
+}
+
+/// The results of type checking a package.
+#[derive(Debug, Default)]
+pub struct TypeckResults {
+    /// The type of every expression, by the expression's matching source.
+    expr_types: HashMap<Fragment, Ty>,
+
+    /// The type of every definition that has one -- functions, constants, parameters, and locals.
+    def_types: HashMap<DefId, Ty>,
+
+    /// The type of every binding (parameter, closure parameter, or `let`), by the binding's name.
+    binding_types: HashMap<Fragment, Ty>,
+
+    /// The type of every pattern in a `match`, by the pattern's matching source.
+    pattern_types: HashMap<Fragment, Ty>,
+
+    /// The fields (or variants) of every record and union.
+    adts: HashMap<DefId, AdtFields>,
+
+    /// The type that every type alias expands to.
+    aliases: HashMap<DefId, Ty>,
+
+    /// The method called by every method call, by the call's matching source.
+    method_calls: HashMap<Fragment, MethodCall>,
+
+    /// The variant tested for by every `is` test of a union, by the test's matching source.
+    union_tests: HashMap<Fragment, String>,
+
+    /// The trait and name of every method declared in a trait.
+    trait_methods: HashMap<DefId, (DefId, String)>,
+
+    /// Every `impl` block of a trait.
+    trait_impls: Vec<TraitImpl>,
+
+    /// Any errors found during type checking.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl TypeckResults {
+    /// Get the type of the expression with the given matching source.
+    pub fn type_of_expr(&self, expr: &Fragment) -> Option<&Ty> {
+        self.expr_types.get(expr)
+    }
+
+    /// Get the type of a definition, such as a function or a local variable.
+    pub fn type_of_def(&self, def: DefId) -> Option<&Ty> {
+        self.def_types.get(&def)
+    }
+
+    /// Get the type of the binding (parameter, closure parameter, or `let`) declared with the given name.
+    pub fn type_of_binding(&self, name: &Fragment) -> Option<&Ty> {
+        self.binding_types.get(name)
+    }
+
+    /// Get the type of a pattern in a `match`, by its matching source.
+    pub fn type_of_pattern(&self, pattern: &Fragment) -> Option<&Ty> {
+        self.pattern_types.get(pattern)
+    }
+
+    /// Get the method called by a method call, by the call's matching source. Returns [None] for other calls.
+    pub fn method_call(&self, call: &Fragment) -> Option<&MethodCall> {
+        self.method_calls.get(call)
+    }
+
+    /// Get the name of the variant tested for by an `is` test of a union, by the test's matching source. Returns
+    /// [None] for tests of enums.
+    pub fn union_test(&self, test: &Fragment) -> Option<&str> {
+        self.union_tests.get(test).map(String::as_str)
+    }
+
+    /// Get the trait that a method is declared in. Returns [None] for methods of inherent `impl` blocks, methods
+    /// of `impl` blocks of traits, and anything that isn't a method.
+    pub fn trait_of_method(&self, method: DefId) -> Option<DefId> {
+        self.trait_methods
+            .get(&method)
+            .map(|(trait_def, _)| *trait_def)
+    }
+
+    /// Find the method that a call of a trait's method runs for a receiver of the given type (without generic
+    /// parameters): the method of the `impl` block of the trait for the type, or the trait's default method if the
+    /// `impl` block doesn't define it. Returns [None] if the type doesn't implement the trait.
+    pub fn implementation(&self, method: DefId, ty: &Ty) -> Option<DefId> {
+        let (trait_def, name) = self.trait_methods.get(&method)?;
+
+        let implementation = (self.trait_impls.iter()).find(|implementation| {
+            implementation.trait_def == *trait_def
+                && checker::matches(
+                    &implementation.self_ty,
+                    &implementation.generics,
+                    ty,
+                    &mut HashMap::new(),
+                )
+        })?;
+
+        Some(implementation.methods.get(name).copied().unwrap_or(method))
+    }
+
+    /// Get the fields of a record type (or the variants of a union type) with the type's generic arguments
+    /// substituted into them, along with whether it's a union. Returns [None] for any other type.
+    pub fn fields(&self, ty: &Ty) -> Option<(bool, Vec<(&str, Ty)>)> {
+        let Ty::Named { def, args } = ty else {
+            return None;
+        };
+
+        let adt = self.adts.get(def)?;
+        let substitution = (adt.generics.iter().copied())
+            .zip(args.iter().cloned())
+            .collect();
+
+        let fields = (adt.fields.iter())
+            .map(|(name, ty)| (name.as_str(), ty.substitute(&substitution)))
+            .collect();
+
+        Some((adt.is_union, fields))
+    }
+
+    /// Get the generic parameters of a record or union. Returns [None] for any other definition.
+    pub fn generics(&self, def: DefId) -> Option<&[DefId]> {
+        self.adts.get(&def).map(|adt| adt.generics.as_slice())
+    }
+
+    /// Get the type that a type alias expands to (which is a [Ty::Named] of the alias itself for abstract types,
+    /// like `type Name;`). Returns [None] for anything that isn't a type alias.
+    pub fn alias(&self, def: DefId) -> Option<&Ty> {
+        self.aliases.get(&def)
+    }
+
+    /// Get the type to show when hovering over some source code: the type of the smallest expression or binding
+    /// name containing the given fragment, along with the source of that expression or binding.
+    pub fn type_at(&self, fragment: &Fragment) -> Option<(&Fragment, &Ty)> {
+        self.expr_types
+            .iter()
+            .chain(&self.binding_types)
+            .chain(&self.pattern_types)
+            .filter(|(source, _)| source.contains(fragment))
+            .min_by_key(|(source, _)| source.len())
+    }
+}
+
+/// Type check every function and constant in the given modules, which must have already been resolved.
+pub fn check<'m>(
+    modules: impl IntoIterator<Item = (Vec<String>, &'m Module)>,
+    resolution: &Resolution,
+) -> TypeckResults {
+    let modules: Vec<_> = modules.into_iter().collect();
+    let mut results = checker::Checker::run(&modules, resolution);
+    literals::check_ranges(&modules, &mut results);
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Receiver, TypeckResults, check, ty::Ty};
+    use crate::{
+        analysis::{
+            resolve::resolve,
+            testing::{check_source, messages},
+        },
+        ast::module::Module,
+        lexer::Lexer,
+        parser::Parser,
+    };
+
+    #[test]
+    fn test_well_typed() {
+        let (module, resolution, results) = check_source(
+            "record Pair<T> { a: T, b: T }\n\
+            union Number { small: u8, big: u64 }\n\
+            func first<T>(pair: Pair<T>) -> T { pair.a }\n\
+            func main(x: u8) -> bool {\n\
+                let r: @u8 = @x;\n\
+                let pair = Pair { a: *r, b: 2 };\n\
+                let n = Number { big: 1 };\n\
+                first(pair) + pair.b * 3 > 4 && !false\n\
+            }",
+        );
+
+        assert!(results.diagnostics.is_empty(), "{:?}", messages(&results.diagnostics));
+
+        let source = module.matching_source.as_str();
+        let start = source.find("first(pair)").unwrap();
+        let call = results
+            .expr_types
+            .iter()
+            .find(|(fragment, _)| fragment.range == (start..start + "first(pair)".len()))
+            .unwrap()
+            .1;
+        assert_eq!(call.display(&resolution).to_string(), "u8");
+    }
+
+    #[test]
+    fn test_mismatches() {
+        let (_, _, results) = check_source(
+            "func f(a: u8, b: bool) -> u16 { a }\n\
+            func main() { let x: u8 = true; f(1); f(1, 2); *x; x.y; }",
+        );
+
+        let diagnostics = &results.diagnostics;
+        assert_eq!(
+            messages(&results.diagnostics),
+            [
+                "mismatched types",
+                "mismatched types",
+                "this function takes 2 arguments but 1 argument was supplied",
+                "mismatched types",
+                "type `u8` cannot be dereferenced",
+                "no field `y` on type `u8`",
+            ]
+        );
+
+        // The return type mismatch highlights both the tail expression and the return type.
+        let labels = &diagnostics[0].0.labels;
+        assert_eq!(labels.len(), 2);
+        assert_eq!(labels[0].message, "expected `u16`, found `u8`");
+        assert_eq!(labels[1].message, "expected `u16` because of this return type");
+    }
+
+    #[test]
+    fn test_records() {
+        let (_, _, results) = check_source(
+            "record Point { x: u8, y: u8 }\n\
+            union Shape { circle: u8, square: u8 }\n\
+            func main(p: Point) {\n\
+                Point { x: 1 };\n\
+                Point { x: 1, y: 2, z: 3 };\n\
+                Shape { circle: 1, square: 2 };\n\
+                p.z;\n\
+            }",
+        );
+
+        assert_eq!(
+            messages(&results.diagnostics),
+            [
+                "missing field `y` in `Point`",
+                "record `Point` has no field named `z`",
+                "a union literal must give a value for exactly one variant",
+                "no field `z` on type `Point`",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_match() {
+        let (_, resolution, results) = check_source(
+            "enum Color { Red, Green }\n\
+            record Pair<T> { a: T, b: bool }\n\
+            union Number { small: u8, big: u64 }\n\
+            func main(c: Color, p: Pair<u16>, n: Number) -> u16 {\n\
+                let x = match p { Pair { a: 0..=9, b: true } => 1, Pair { a, b } => a };\n\
+                let y = match n { Number { big: 2 } => Color::Green, Number { small } => c };\n\
+                match y { Color::Red if x > 3 => 0, other => x }\n\
+            }",
+        );
+
+        assert!(results.diagnostics.is_empty(), "{:?}", messages(&results.diagnostics));
+        assert_eq!(binding(&results, "a").display(&resolution).to_string(), "u16");
+        assert_eq!(binding(&results, "small").display(&resolution).to_string(), "u8");
+        assert_eq!(binding(&results, "other").display(&resolution).to_string(), "Color");
+
+        let (_, _, results) = check_source(
+            "enum Color { Red, Green }\n\
+            record Point { x: u8, y: u8 }\n\
+            union Number { small: u8, big: u64 }\n\
+            func main(c: Color, p: Point, n: Number) {\n\
+                match c { true => 1, Color::Red => 2 };\n\
+                match p { Point { x: 1, x: 2 } => 1, Point { z: 1 } => 2 };\n\
+                match n { Number { small: 1, big: 2 } => 1, Number {} => 2 };\n\
+                match c { Color::Red => 1, Color::Green => false };\n\
+                match p { Point { x: 1..1 } => 1, Point { y: 300 } => 2 };\n\
+            }",
+        );
+
+        assert_eq!(
+            messages(&results.diagnostics),
+            [
+                "mismatched types",
+                "field `x` is matched more than once",
+                "record `Point` has no field named `z`",
+                "a union pattern must match exactly one variant",
+                "a union pattern must match exactly one variant",
+                "mismatched types",
+                "range pattern matches no values",
+                "integer literal is out of range for `u8`",
+            ]
+        );
+
+        let labels = &results.diagnostics[0].0.labels;
+        assert_eq!(labels[0].message, "expected `Color`, found `bool`");
+        assert_eq!(labels[1].message, "expected `Color` because of the type of the matched value");
+        assert_eq!(
+            results.diagnostics[5].0.labels[1].message,
+            "expected `{integer}` because of the type of the first arm"
+        );
+        assert_eq!(results.diagnostics[6].0.notes, ["help: use `1..=1` to match only `1`"]);
+    }
+
+    #[test]
+    fn test_is_narrowing() {
+        let (_, resolution, results) = check_source(
+            "union Number<T> { small: u8, big: T }\n\
+            enum Color { Red, Green }\n\
+            func main(n: Number<u64>, r: @Number<u64>, c: Color) -> u64 {\n\
+                let a = if n is Number::big { n.big } else { 0 };\n\
+                let b = if r is u8 && r.small > 3 { 1 } else { 2 };\n\
+                while n is u64 && c is Color::Red { let big = n.big; }\n\
+                a + b\n\
+            }",
+        );
+
+        assert!(results.diagnostics.is_empty(), "{:?}", messages(&results.diagnostics));
+        assert_eq!(binding(&results, "big").display(&resolution).to_string(), "u64");
+
+        let (_, _, results) = check_source(
+            "union Number { small: u8, big: u64, other: u64 }\n\
+            enum Color { Red, Green }\n\
+            func main(n: Number, c: Color, x: u8) {\n\
+                if n is Number::small { n.small; }\n\
+                n.small;\n\
+                if n is Number::big { n.small; }\n\
+                var m = n;\n\
+                if m is Number::small { m.small; }\n\
+                n is bool; n is u64; n is Number::tiny; c is Number::big; x is u8;\n\
+            }",
+        );
+
+        assert_eq!(
+            messages(&results.diagnostics),
+            [
+                "no field `small` on type `Number`",
+                "no field `small` on type `Number`",
+                "no field `small` on type `Number`",
+                "no variant of `Number` has type `bool`",
+                "more than one variant of `Number` has type `u64`",
+                "union `Number` has no variant named `tiny`",
+                "expected a variant of `Color`",
+                "cannot test the variant of a value of type `u8`",
+            ]
+        );
+
+        // Reading the variant after the test points back at it.
+        let diagnostic = &results.diagnostics[0].0;
+        assert_eq!(diagnostic.labels.len(), 2);
+        assert_eq!(
+            diagnostic.labels[1].message,
+            "`n` is only known to hold `small` where this test is `true`"
+        );
+        assert_eq!(diagnostic.labels[1].range.len(), "n is Number::small".len());
+
+        let diagnostic = &results.diagnostics[1].0;
+        assert_eq!(diagnostic.labels[1].message, "this tests for `big`, not `small`");
+
+        let diagnostic = &results.diagnostics[2].0;
+        assert_eq!(
+            diagnostic.notes[1],
+            "`m` is a `var` binding, so testing its variant with `is` doesn't narrow it"
+        );
+    }
+
+    /// Find the type of the binding with the given name.
+    fn binding<'a>(results: &'a TypeckResults, name: &str) -> &'a Ty {
+        let (_, ty) = (results.binding_types.iter())
+            .find(|(fragment, _)| fragment.as_str() == name)
+            .unwrap();
+        ty
+    }
+
+    #[test]
+    fn test_inference() {
+        let (_, resolution, results) = check_source(
+            "record Pair<T> { a: T, b: T }\n\
+            func apply<T>(f: func(T) -> T, x: T) -> T { f(x) }\n\
+            func main(small: u8) -> u8 {\n\
+                let count = 5;\n\
+                let byte = 7;\n\
+                let pair = Pair { a: byte, b: 2 };\n\
+                let doubled = apply(func(value) { value * 2 }, pair.a);\n\
+                let wide = byte + small;\n\
+                let total = count * 2;\n\
+                doubled + wide\n\
+            }",
+        );
+
+        // `count` is only used with itself, so it gets the default integer type.
+        assert!(results.diagnostics.is_empty(), "{:?}", messages(&results.diagnostics));
+        let show = |name| binding(&results, name).display(&resolution).to_string();
+        assert_eq!(show("count"), "i32");
+        assert_eq!(show("byte"), "u8");
+        assert_eq!(show("pair"), "Pair<u8>");
+        assert_eq!(show("value"), "u8");
+        assert_eq!(show("doubled"), "u8");
+    }
+
+    #[test]
+    fn test_hover() {
+        let (module, resolution, results) =
+            check_source("func main(x: u8) -> u8 { let y = x + 1; y }");
+
+        let source = module.matching_source.as_str();
+        let mut one = module.matching_source.clone();
+        one.range = source.find('1').unwrap()..source.find('1').unwrap() + 1;
+
+        let (fragment, ty) = results.type_at(&one).unwrap();
+        assert_eq!(fragment.as_str(), "1");
+        assert_eq!(ty.display(&resolution).to_string(), "u8");
+    }
+
+    #[test]
+    fn test_ambiguity() {
+        let (module, _, results) = check_source(
+            "record Box<T> { value: u8 }\n\
+            func empty<T>() -> Box<T> { Box { value: 0 } }\n\
+            func main() { let f = func(x) { x }; let b = empty(); let g = func(v) { v.y }; }",
+        );
+
+        assert_eq!(
+            messages(&results.diagnostics),
+            [
+                "type annotations needed for `v`",
+                "type annotations needed for `x`",
+                "type annotations needed for `b`",
+            ]
+        );
+
+        // The errors point at the bindings that need annotations.
+        let source = module.matching_source.as_str();
+        let labels = &results.diagnostics[0].0.labels;
+        assert_eq!(&source[labels[0].range.clone()], "v");
+        assert_eq!(labels[1].message, "the type must be known here to access a field");
+
+        let labels = &results.diagnostics[2].0.labels;
+        assert_eq!(&source[labels[0].range.clone()], "b");
+        assert_eq!(labels[0].message, "cannot infer all of the type `Box<_>`");
+        assert_eq!(&source[labels[1].range.clone()], "empty");
+    }
+
+    #[test]
+    fn test_assignment() {
+        let (_, _, results) = check_source(
+            "record Point { x: u8 }\n\
+            func main(p: Point, r: @mut u8, s: @Point) {\n\
+                var a: u8 = 1; let b = 2; var q = p;\n\
+                a += 1; q.x = 3; *r = 4; let t = @mut q; t.x = 5; let u: @u8 = r;\n\
+                a = true; b = 5; p.x = 6; main = main; 1 = 2; q *= 2;\n\
+                s.x = 7; let v = @mut b; let w = @mut *s; let x = @mut 8;\n\
+            }",
+        );
+
+        assert_eq!(
+            messages(&results.diagnostics),
+            [
+                "mismatched types",
+                "cannot assign to `b`, which is not declared with `var`",
+                "cannot assign to parameter `p`",
+                "cannot assign to function `main`",
+                "invalid assignment target",
+                "cannot apply `*=` to a value of type `Point`",
+                "cannot assign to `s.x`, which is behind a shared reference",
+                "cannot borrow `b` as mutable, which is not declared with `var`",
+                "cannot borrow `*s`, which is behind a shared reference",
+            ]
+        );
+
+        let diagnostic = &results.diagnostics[1].0;
+        assert_eq!(diagnostic.labels[1].message, "`b` is declared here");
+        assert_eq!(diagnostic.notes, ["help: declare `b` with `var` to allow assigning to it"]);
+
+        let diagnostic = &results.diagnostics[6].0;
+        assert_eq!(
+            diagnostic.labels[1].message,
+            "this has type `@Point`, which doesn't allow changing what it points to"
+        );
+    }
+
+    #[test]
+    fn test_operator_errors() {
+        // An operator that can't be applied is reported once, without the operands or the result mismatching too.
+        let (_, _, results) = check_source(
+            "func f(t: @i32) -> i32 { t + 1 }\n\
+            func g(t: @i32) -> bool { let n: i32 = -t; t < 1 }\n\
+            func h(t: @i32) -> i32 { t << 1 }",
+        );
+
+        assert_eq!(
+            messages(&results.diagnostics),
+            [
+                "cannot apply `+` to a value of type `@i32`",
+                "cannot apply `-` to a value of type `@i32`",
+                "cannot apply `<` to a value of type `@i32`",
+                "cannot apply `<<` to a value of type `@i32`",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_constraint_decls() {
+        let (_, _, results) = check_source(
+            "constraint Positive<T>(value: T) { value > 0 }\n\
+            constraint Small(value: u8): Positive { value }\n\
+            constraint Wide(value: u64) { value < 1000 }\n\
+            constraint Both = Small + Wide;\n\
+            constraint Loop = Cycle;\n\
+            constraint Cycle = Loop;\n\
+            func main(a: i8 constrain Positive, b: u8 constrain Small, c: u8 constrain Both) {\n\
+                let d: bool constrain Positive = true;\n\
+                Small;\n\
+            }",
+        );
+
+        assert_eq!(
+            messages(&results.diagnostics),
+            [
+                "constraint `Both` cannot be applied to `u8`",
+                "mismatched types",
+                "constraint `Loop` is built from itself",
+                "constraint `Cycle` is built from itself",
+                "constraint `Positive` cannot be applied to `bool`",
+                "expected value, found constraint `Small`",
+            ]
+        );
+
+        let diagnostic = &results.diagnostics[1].0;
+        assert_eq!(
+            diagnostic.labels[1].message,
+            "expected `bool` because constraints are conditions"
+        );
+
+        let diagnostic = &results.diagnostics[0].0;
+        assert_eq!(diagnostic.labels[0].message, "`Wide` constrains values of type `u64`");
+        assert_eq!(diagnostic.notes, ["`Both` is built from `Wide`"]);
+    }
+
+    #[test]
+    fn test_literal_ranges() {
+        let (_, _, results) = check_source(
+            "func main() {\n\
+                let a: u8 = 255; let b: u8 = 300;\n\
+                let c: i8 = -128; let d: i8 = -129; let e: i8 = -(200);\n\
+                let f: u64 = 18446744073709551616;\n\
+            }",
+        );
+
+        assert_eq!(
+            messages(&results.diagnostics),
+            [
+                "integer literal is out of range for `u8`",
+                "integer literal is out of range for `i8`",
+                "integer literal is out of range for `i8`",
+                "integer literal is out of range for `u64`",
+            ]
+        );
+
+        let diagnostic = &results.diagnostics[0].0;
+        assert_eq!(diagnostic.labels[0].message, "`300` doesn't fit in `u8`");
+        assert_eq!(
+            diagnostic.notes,
+            [
+                "`u8` can hold values from 0 to 255",
+                "help: use a wider type, like `u16`"
+            ]
+        );
+
+        let diagnostic = &results.diagnostics[1].0;
+        assert_eq!(diagnostic.labels[0].message, "`-129` doesn't fit in `i8`");
+        assert_eq!(diagnostic.notes[1], "help: use a wider type, like `i16`");
+        assert_eq!(results.diagnostics[3].0.notes[1], "no integer type can hold this value");
+    }
+
+    #[test]
+    fn test_methods() {
+        let (module, resolution, results) = check_source(
+            "record Point { x: u64, y: u64 }\n\
+            record Wrapper<T> { inner: T }\n\
+            trait Shape {\n\
+                func area(self: @Self) -> u64;\n\
+                func double(self: @Self) -> u64 { self.area() * 2 }\n\
+            }\n\
+            impl Point { func norm(self: Point) -> u64 { self.x + self.y } func grow(self: @mut Self) { self.x = 2; } }\n\
+            impl Shape for Point { func area(self: @Self) -> u64 { self.x * self.y } }\n\
+            impl<T: Shape> Shape for Wrapper<T> { func area(self: @Self) -> u64 { self.inner.area() } }\n\
+            func total<T>(shape: @T) -> u64 where T: Shape { shape.area() + shape.double() }\n\
+            func main() -> u64 {\n\
+                var p = Point { x: 1, y: 2 };\n\
+                p.grow();\n\
+                let w = Wrapper { inner: p };\n\
+                let r = @w;\n\
+                r.area() + total(@w) + p.norm()\n\
+            }",
+        );
+
+        assert!(results.diagnostics.is_empty(), "{:?}", messages(&results.diagnostics));
+
+        let source = module.matching_source.as_str();
+        let call = |text: &str| {
+            let start = source.rfind(text).unwrap();
+            let range = start..start + text.len();
+            let (_, method_call) = (results.method_calls.iter())
+                .find(|(fragment, _)| fragment.range == range)
+                .unwrap();
+
+            let method = resolution.def(method_call.method);
+            (method.name.as_str(), method_call.receiver)
+        };
+
+        assert_eq!(call("p.grow()"), ("grow", Receiver::Borrow { is_mutable: true }));
+        assert_eq!(call("r.area()"), ("area", Receiver::Value));
+        assert_eq!(call("p.norm()"), ("norm", Receiver::Value));
+        assert_eq!(call("shape.double()"), ("double", Receiver::Value));
+        assert_eq!(call("self.inner.area()"), ("area", Receiver::Borrow { is_mutable: false }));
+    }
+
+    #[test]
+    fn test_method_errors() {
+        let (module, _, results) = check_source(
+            "record Point { x: u64 }\n\
+            record Line { a: Point }\n\
+            trait Shape { func area(self: @Self) -> u64; func name(self: @Self) -> u8 { 0 } }\n\
+            trait Named { func name(self: @Self) -> u8; }\n\
+            impl Shape for Point { func area(self: @Self) -> u8 { 1 } func extra(self: @Self) {} }\n\
+            impl Shape for Point {}\n\
+            impl Named for Point { func name(self: @Self) -> u8 { 1 } }\n\
+            impl Point { func get(self: @Self) -> u64 { self.x } func new() -> Point { Point { x: 0 } } }\n\
+            func total<T: Shape>(shape: @T) -> u64 { shape.area() }\n\
+            func unbound<T>(value: @T) -> u64 { value.area() }\n\
+            func main(p: Point, line: Line) {\n\
+                p.name(); line.area(); total(@line); p.x; p.get(1);\n\
+            }",
+        );
+
+        assert_eq!(
+            messages(&results.diagnostics),
+            [
+                "method `new` has no `self` parameter",
+                "method `area` has an incompatible type for trait `Shape`",
+                "method `extra` is not a member of trait `Shape`",
+                "not all methods of trait `Shape` are implemented, missing: `area`",
+                "conflicting implementations of trait `Shape` for type `Point`",
+                "no method named `area` found for `T`",
+                "multiple applicable methods named `name` found for `Point`",
+                "no method named `area` found for `Line`",
+                "this method takes 0 arguments but 1 argument was supplied",
+                "the trait bound `Line: Shape` is not satisfied",
+            ]
+        );
+
+        let source = module.matching_source.as_str();
+        let labels = |index: usize| {
+            (results.diagnostics[index].0.labels.iter())
+                .map(|label| (&source[label.range.clone()], label.message.as_str()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            labels(4),
+            [
+                ("impl Shape for Point", "conflicting implementation for `Point`"),
+                ("impl Shape for Point", "first implementation here"),
+            ]
+        );
+
+        assert_eq!(
+            labels(9),
+            [
+                ("total", "the trait `Shape` is not implemented for `Line`"),
+                ("Shape", "required by this bound on `total`"),
+            ]
+        );
+
+        let notes = &results.diagnostics[9].0.notes;
+        assert!(notes[0].contains("`impl Shape for Point` was considered, but it's for `Point`"));
+        assert!(
+            results.diagnostics[5]
+                .0
+                .notes
+                .iter()
+                .any(|note| note.contains("`T: Shape`"))
+        );
+        assert!(results.diagnostics[6].0.notes[0].starts_with("candidate #1"));
+    }
+
+    #[test]
+    fn test_traits_across_modules() {
+        let sources = [
+            (vec!["shapes".to_owned()], "pub trait Shape { func area(self: @Self) -> u64; }"),
+            (
+                Vec::new(),
+                "use shapes::Shape;\n\
+                record Square { side: u64 }\n\
+                impl Shape for Square { func area(self: @Self) -> u64 { self.side * self.side } }\n\
+                func measure<T: Shape>(shape: @T) -> u64 { shape.area() }\n\
+                func main() -> u64 { let s = Square { side: 2 }; measure(@s) + s.area() + measure(@1) }",
+            ),
+        ];
+
+        let modules: Vec<(Vec<String>, Module)> = (sources.into_iter())
+            .map(|(path, source)| {
+                (path, Module::parse(&mut Parser::new(Lexer::new_test(source))).unwrap())
+            })
+            .collect();
+
+        let modules = || modules.iter().map(|(path, module)| (path.clone(), module));
+        let resolution = resolve(modules());
+        assert!(resolution.diagnostics.is_empty());
+        let results = check(modules(), &resolution);
+
+        assert_eq!(
+            messages(&results.diagnostics),
+            ["the trait bound `i32: Shape` is not satisfied"]
+        );
+    }
+}
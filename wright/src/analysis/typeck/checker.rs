@@ -0,0 +1,2669 @@
+//! The implementation of type checking, which walks resolved ASTs and fills in [TypeckResults].
+//!
+//! Types are inferred by unification: anything that isn't known when it's first seen (the type of an integer
+//! literal, a closure parameter without an annotation, the generic arguments of a call) gets an inference variable,
+//! and variables are bound as the checker finds the types they have to be equal to. Once a function body has been
+//! checked, unconstrained integer literals get the [default integer type](super::DEFAULT_INTEGER), and anything
+//! else still unknown is reported as needing an annotation.
+
+use super::{
+    AdtFields, DEFAULT_INTEGER, TypeckResults,
+    infer::{InferTable, VarOrigin},
+    ty::Ty,
+};
+use crate::{
+    analysis::resolve::{DefId, DefKind, Resolution, module_bodies},
+    ast::{
+        decl::{
+            Decl, constant::ConstDecl, constraint::ConstraintDecl, func::FuncDecl,
+            generic::GenericParam, record::FieldDecl, type_alias::TypeAlias,
+        },
+        expr::{
+            Atom, BinaryExpr, BinaryOperation, CallExpr, ClosureExpr, Expr, FieldExpr, ForExpr,
+            IfExpr, IsExpr, MatchExpr, RecordExpr, UnaryExpr, UnaryOperation,
+        },
+        identifier::Identifier,
+        module::Module,
+        pattern::{Pattern, RecordPattern},
+        stmt::{AssignStmt, Block, Stmt},
+        ty::{AtomicTyVariant, ConstrainedTy, NamedTy, Type},
+    },
+    reporting::{Diagnostic, Highlight},
+    source_tracking::fragment::Fragment,
+    util::edit_distance::best_match,
+};
+use std::{collections::HashMap, rc::Rc};
+pub(super) use traits::matches;
+use traits::{Bound, Impl, Obligation, Trait};
+
+mod traits;
+
+/// A declaration that the type checker needs to look at.
+#[derive(Clone, Copy)]
+enum Item<'m> {
+    Function(&'m FuncDecl),
+    Method(&'m FuncDecl),
+    Constraint(&'m ConstraintDecl),
+    Constant(&'m ConstDecl),
+    Alias(&'m TypeAlias),
+    Record(&'m [GenericParam], &'m [FieldDecl]),
+    Union(&'m [GenericParam], &'m [FieldDecl]),
+}
+
+/// The lowered signature of a function.
+struct Signature {
+    /// The generic parameters of the function.
+    generics: Vec<DefId>,
+    /// The return type.
+    ret: Ty,
+}
+
+/// The lowered parameter of a constraint.
+struct ConstraintParam {
+    /// The generic parameters of the constraint.
+    generics: Vec<DefId>,
+    /// The type of values the constraint checks, in terms of its generic parameters.
+    ty: Ty,
+}
+
+/// How a place is being used, for [Checker::check_place].
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum PlaceAccess {
+    /// The place is being assigned to.
+    Assign,
+    /// The place is being borrowed mutably, with `@mut`.
+    Borrow,
+}
+
+/// A field of a record, or a variant of a union.
+struct Field {
+    /// The name of the field.
+    name: String,
+    /// The type of the field, in terms of the record or union's generic parameters.
+    ty: Ty,
+    /// The type signature of the field at its declaration.
+    ty_fragment: Fragment,
+}
+
+/// The lowered definition of a record or union.
+struct Adt {
+    /// Whether this is a union (rather than a record).
+    is_union: bool,
+    /// The generic parameters of the type.
+    generics: Vec<DefId>,
+    /// The fields (or variants) of the type.
+    fields: Vec<Field>,
+}
+
+impl Adt {
+    /// Describe what kind of type this is, for use in diagnostics.
+    const fn describe(&self) -> &'static str {
+        match self.is_union {
+            true => "union",
+            false => "record",
+        }
+    }
+}
+
+/// Why a type is expected -- shown as a secondary highlight when there's a mismatch.
+#[derive(Clone)]
+struct Reason {
+    /// The source of the expectation, such as a type annotation.
+    fragment: Fragment,
+    /// The end of the label, following "expected `T` ", i.e. "because of this type annotation".
+    label: &'static str,
+}
+
+impl Reason {
+    /// Create a new [Reason].
+    fn new(fragment: &Fragment, label: &'static str) -> Self {
+        Reason {
+            fragment: fragment.clone(),
+            label,
+        }
+    }
+}
+
+/// The kinds of types an operator can be applied to.
+#[derive(Clone, Copy, Debug)]
+enum OperandKind {
+    /// Integer and floating point types.
+    Numeric,
+    /// Signed integer and floating point types.
+    SignedOrFloat,
+    /// Integer types.
+    Integer,
+    /// Integer types and `bool`.
+    IntegerOrBool,
+    /// Numeric types and `char`.
+    Ordered,
+    /// Any type that holds a value (not `void` or functions).
+    Equatable,
+}
+
+impl OperandKind {
+    /// Check if a (resolved) type is of this kind, returning [None] if that isn't known yet because the type is
+    /// still being inferred.
+    fn contains(self, ty: &Ty) -> Option<bool> {
+        use OperandKind::*;
+
+        let variant = match ty {
+            Ty::Error => return Some(true),
+            Ty::Infer(_) => return None,
+
+            // Integer literals can become any integer type.
+            Ty::Integer(_) => {
+                return match self {
+                    SignedOrFloat => None,
+                    _ => Some(true),
+                };
+            }
+
+            Ty::Atomic(variant) => *variant,
+            Ty::Void | Ty::Function { .. } => return Some(false),
+            _ => return Some(matches!(self, Equatable)),
+        };
+
+        Some(match self {
+            Numeric => variant.is_numeric(),
+            SignedOrFloat => variant.is_signed_integer() || variant.is_float(),
+            Integer => variant.is_integer(),
+            IntegerOrBool => variant.is_integer() || variant == AtomicTyVariant::Bool,
+            Ordered => variant.is_numeric() || variant == AtomicTyVariant::Char,
+            Equatable => true,
+        })
+    }
+}
+
+/// A requirement that an operand has a type an operator can be applied to, which is checked once the type of the
+/// operand is known.
+struct Requirement {
+    /// The type of the operand.
+    ty: Ty,
+    /// The types the operator accepts.
+    kind: OperandKind,
+    /// The operator, i.e. `+`.
+    operator: &'static str,
+    /// The operator in source code.
+    operator_fragment: Fragment,
+    /// The operand in source code.
+    operand: Fragment,
+}
+
+/// The state of type checking.
+pub(super) struct Checker<'m, 'r> {
+    /// The result of name resolution.
+    res: &'r Resolution,
+
+    /// Every declaration by its [DefId].
+    items: HashMap<DefId, Item<'m>>,
+
+    /// The signature of every function.
+    signatures: HashMap<DefId, Rc<Signature>>,
+
+    /// Every record and union.
+    adts: HashMap<DefId, Rc<Adt>>,
+
+    /// The parameter of every constraint that's been lowered so far. [None] for compositions of other constraints,
+    /// which don't have one, and for constraints whose parameter is currently being lowered.
+    constraint_params: HashMap<DefId, Option<Rc<ConstraintParam>>>,
+
+    /// The expansion of every type alias that's been lowered so far.
+    aliases: HashMap<DefId, Ty>,
+
+    /// The type aliases currently being expanded, used to detect aliases that refer to themselves.
+    alias_stack: Vec<DefId>,
+
+    /// The inference variables created so far.
+    table: InferTable,
+
+    /// The `let` bindings and closure parameters in the function being checked, by name. Types that can't be
+    /// inferred are reported at these if possible, since that's where an annotation can be added.
+    bindings: Vec<(Fragment, DefId)>,
+
+    /// The bindings narrowed to a variant of their union type where the checker currently is, because an `is` test
+    /// for the variant is known to be `true` there.
+    narrowed: Vec<(DefId, String)>,
+
+    /// The binding tested and the variant tested for by every `is` test of a union binding that can be narrowed, by
+    /// the test's matching source.
+    variant_tests: HashMap<Fragment, (DefId, String)>,
+
+    /// Operator requirements in the function being checked that couldn't be checked when they were found.
+    requirements: Vec<Requirement>,
+
+    /// Every trait.
+    traits: HashMap<DefId, Rc<Trait<'m>>>,
+
+    /// Every `impl` block of something that's a trait (or of nothing, for inherent impls).
+    impls: Vec<Rc<Impl<'m>>>,
+
+    /// The trait bounds declared by every generic function, method, and `impl` block.
+    bounds: HashMap<DefId, Vec<Bound>>,
+
+    /// The traits that every generic parameter (and the `Self` type of every trait) is bound by.
+    param_bounds: HashMap<DefId, Vec<DefId>>,
+
+    /// The type that the `Self` type of every trait and `impl` block stands for.
+    self_tys: HashMap<DefId, Ty>,
+
+    /// Trait bounds required by calls in the function being checked, checked once its types are inferred.
+    obligations: Vec<Obligation>,
+
+    /// The results being built.
+    results: TypeckResults,
+}
+
+impl<'m, 'r> Checker<'m, 'r> {
+    /// Type check all the given modules.
+    pub(super) fn run(modules: &[(Vec<String>, &'m Module)], res: &'r Resolution) -> TypeckResults {
+        let mut checker = Checker {
+            res,
+            items: HashMap::new(),
+            signatures: HashMap::new(),
+            adts: HashMap::new(),
+            constraint_params: HashMap::new(),
+            aliases: HashMap::new(),
+            alias_stack: Vec::new(),
+            table: InferTable::default(),
+            bindings: Vec::new(),
+            narrowed: Vec::new(),
+            variant_tests: HashMap::new(),
+            requirements: Vec::new(),
+            traits: HashMap::new(),
+            impls: Vec::new(),
+            bounds: HashMap::new(),
+            param_bounds: HashMap::new(),
+            self_tys: HashMap::new(),
+            obligations: Vec::new(),
+            results: TypeckResults::default(),
+        };
+
+        let mut traits = Vec::new();
+        let mut impls = Vec::new();
+
+        for (_, items) in module_bodies(modules) {
+            for decl in items {
+                let item = match decl {
+                    Decl::Import(_) | Decl::Module(_) | Decl::Enum(_) => continue,
+                    Decl::Trait(trait_decl) => {
+                        traits.push(trait_decl);
+                        checker.insert_methods(&trait_decl.methods);
+                        continue;
+                    }
+
+                    Decl::Impl(implementation) => {
+                        impls.push(implementation);
+                        checker.insert_methods(&implementation.methods);
+                        continue;
+                    }
+
+                    Decl::Function(func) => Item::Function(func),
+                    Decl::Constraint(constraint) => Item::Constraint(constraint),
+                    Decl::Constant(constant) => Item::Constant(constant),
+                    Decl::TypeAlias(alias) => Item::Alias(alias),
+                    Decl::Record(record) => Item::Record(&record.generic_params, &record.fields),
+                    Decl::Union(union) => Item::Union(&union.generic_params, &union.variants),
+                };
+
+                if let Some(def) = decl.name().and_then(|name| res.declared(&name.fragment)) {
+                    checker.items.insert(def, item);
+                }
+            }
+        }
+
+        // Lower every signature first, so that bodies can refer to items in any order.
+        let mut items: Vec<(DefId, Item)> = checker.items.iter().map(|(d, i)| (*d, *i)).collect();
+        items.sort_by_key(|(def, _)| *def);
+
+        for (def, item) in &items {
+            match item {
+                Item::Alias(_) => {
+                    checker.alias_ty(*def);
+                }
+
+                Item::Constant(constant) => {
+                    let ty = checker.lower_ty(&constant.ty);
+                    checker.results.def_types.insert(*def, ty);
+                }
+
+                Item::Function(func) => checker.lower_signature(*def, func, &[]),
+                // Methods are lowered with their trait or `impl` block.
+                Item::Method(_) => {}
+                Item::Constraint(_) => _ = checker.constraint_param(*def),
+                Item::Record(generics, fields) => checker.lower_adt(*def, false, generics, fields),
+                Item::Union(generics, variants) => {
+                    checker.lower_adt(*def, true, generics, variants)
+                }
+            }
+        }
+
+        for trait_decl in traits {
+            checker.lower_trait(trait_decl);
+        }
+
+        for implementation in impls {
+            checker.lower_impl(implementation);
+        }
+
+        checker.check_impls();
+
+        for (def, item) in &items {
+            match item {
+                Item::Function(func) | Item::Method(func) => checker.check_function(func),
+                Item::Constraint(constraint) => checker.check_constraint(*def, constraint),
+                _ => {}
+            }
+        }
+
+        // Later passes look inside records and unions (i.e. to check that a `match` covers every variant).
+        for (def, adt) in &checker.adts {
+            let fields = (adt.fields.iter())
+                .map(|field| (field.name.clone(), field.ty.clone()))
+                .collect();
+
+            let fields = AdtFields {
+                is_union: adt.is_union,
+                generics: adt.generics.clone(),
+                fields,
+            };
+
+            checker.results.adts.insert(*def, fields);
+        }
+
+        // Replace every inference variable in the results with what it was inferred to be.
+        let Checker {
+            table,
+            mut results,
+            aliases,
+            ..
+        } = checker;
+
+        results.aliases = aliases;
+
+        let types = (results.expr_types.values_mut())
+            .chain(results.def_types.values_mut())
+            .chain(results.binding_types.values_mut())
+            .chain(results.pattern_types.values_mut());
+
+        for ty in types {
+            *ty = table.resolve_final(ty);
+        }
+
+        results
+    }
+
+    /// Report an error.
+    fn error(&mut self, diagnostic: Diagnostic) {
+        self.results.diagnostics.push(diagnostic);
+    }
+
+    /// Record the methods of a trait or `impl` block as items.
+    fn insert_methods(&mut self, methods: &'m [FuncDecl]) {
+        for method in methods {
+            if let Some(def) = self.res.declared(&method.name.fragment) {
+                self.items.entry(def).or_insert(Item::Method(method));
+            }
+        }
+    }
+
+    /// Display a type (with everything inferred about it so far) for use in a diagnostic.
+    fn show(&self, ty: &Ty) -> String {
+        self.table.resolve(ty).display(self.res).to_string()
+    }
+
+    /// Get the [DefId]s of a list of generic parameters.
+    fn generic_defs(&self, params: &[GenericParam]) -> Vec<DefId> {
+        params
+            .iter()
+            .filter_map(|param| self.res.declared(&param.name.fragment))
+            .collect()
+    }
+
+    /// Record the type of a binding -- a parameter, closure parameter, or `let`.
+    fn bind(&mut self, name: &Fragment, ty: Ty) {
+        let Some(def) = self.res.declared(name) else {
+            return;
+        };
+
+        self.results.def_types.insert(def, ty.clone());
+        self.results.binding_types.insert(name.clone(), ty);
+        self.bindings.push((name.clone(), def));
+    }
+
+    /// Lower a type signature from the AST into a [Ty].
+    fn lower_ty(&mut self, ty: &Type) -> Ty {
+        match ty {
+            Type::Atomic(atomic) => Ty::Atomic(atomic.variant),
+            Type::Reference(reference) => {
+                Ty::reference(self.lower_ty(&reference.target_ty), reference.is_mutable)
+            }
+            // Constraints don't change the type of a value, only which values are allowed.
+            Type::Constrained(constrained) => {
+                let ty = self.lower_ty(&constrained.base_ty);
+                self.check_constrained(constrained, &ty);
+                ty
+            }
+            Type::Named(named) => self.lower_named(named),
+
+            Type::Function(function) => Ty::Function {
+                params: function
+                    .params
+                    .iter()
+                    .map(|param| self.lower_ty(param))
+                    .collect(),
+                ret: Box::new(match &function.return_ty {
+                    Some(return_ty) => self.lower_ty(return_ty),
+                    None => Ty::Void,
+                }),
+            },
+        }
+    }
+
+    /// Lower a named type signature, checking that it names a type and has the right number of generic arguments.
+    fn lower_named(&mut self, named: &NamedTy) -> Ty {
+        let args: Vec<Ty> = named
+            .generic_tys
+            .iter()
+            .map(|ty| self.lower_ty(ty))
+            .collect();
+
+        // Unresolved names have already been reported.
+        let Some(def) = self.res.resolved(&named.name.full_path) else {
+            return Ty::Error;
+        };
+
+        let definition = self.res.def(def);
+
+        let expected_args = match (definition.kind, self.items.get(&def)) {
+            (DefKind::GenericParam | DefKind::TypeAlias | DefKind::Enum | DefKind::SelfType, _) => {
+                0
+            }
+            (DefKind::Record, Some(Item::Record(generics, _))) => generics.len(),
+            (DefKind::Union, Some(Item::Union(generics, _))) => generics.len(),
+
+            (kind, _) => {
+                self.error(
+                    Diagnostic::error()
+                        .with_message(format!(
+                            "expected type, found {} `{}`",
+                            kind.describe(),
+                            definition.name
+                        ))
+                        .with_highlights([Highlight::primary(
+                            named.name.full_path.clone(),
+                            "not a type",
+                        )]),
+                );
+
+                return Ty::Error;
+            }
+        };
+
+        if args.len() != expected_args {
+            self.error(
+                Diagnostic::error()
+                    .with_message(format!(
+                        "wrong number of generic arguments for `{}`",
+                        definition.name
+                    ))
+                    .with_highlights([Highlight::primary(
+                        named.matching_source.clone(),
+                        format!(
+                            "expected {expected_args} generic argument{}, found {}",
+                            plural(expected_args),
+                            args.len()
+                        ),
+                    )]),
+            );
+
+            return Ty::Error;
+        }
+
+        match definition.kind {
+            DefKind::GenericParam => Ty::Param(def),
+            DefKind::SelfType => self.self_tys.get(&def).cloned().unwrap_or(Ty::Error),
+            DefKind::TypeAlias => self.alias_ty(def),
+            _ => Ty::Named { def, args },
+        }
+    }
+
+    /// Get the type that a type alias expands to. Abstract aliases (`type Name;`) are their own named type.
+    fn alias_ty(&mut self, def: DefId) -> Ty {
+        if let Some(ty) = self.aliases.get(&def) {
+            return ty.clone();
+        }
+
+        let Some(Item::Alias(alias)) = self.items.get(&def).copied() else {
+            return Ty::Error;
+        };
+
+        let Some(target) = &alias.target_type else {
+            let ty = Ty::Named {
+                def,
+                args: Vec::new(),
+            };
+
+            self.aliases.insert(def, ty.clone());
+            return ty;
+        };
+
+        if self.alias_stack.contains(&def) {
+            self.error(
+                Diagnostic::error()
+                    .with_message(format!(
+                        "type alias `{}` refers to itself",
+                        alias.new_type_name.fragment.as_str()
+                    ))
+                    .with_highlights([Highlight::primary(
+                        alias.new_type_name.fragment.clone(),
+                        "this alias expands to itself",
+                    )]),
+            );
+
+            self.aliases.insert(def, Ty::Error);
+            return Ty::Error;
+        }
+
+        self.alias_stack.push(def);
+        let ty = self.lower_ty(target);
+        self.alias_stack.pop();
+
+        // A cycle may have been found (and reported) while lowering the target.
+        self.aliases.entry(def).or_insert(ty).clone()
+    }
+
+    /// Lower the signature of a function, recording the types of its parameters and its trait bounds. Methods are
+    /// also generic over the generic parameters of their `impl` block.
+    fn lower_signature(&mut self, def: DefId, func: &FuncDecl, outer_generics: &[DefId]) {
+        let mut generics = outer_generics.to_vec();
+        generics.extend(self.generic_defs(&func.generic_params));
+
+        let bounds = self.lower_bounds(&func.generic_params, &func.where_clause);
+
+        if !bounds.is_empty() {
+            self.bounds.insert(def, bounds);
+        }
+
+        let mut params = Vec::new();
+
+        for param in &func.params {
+            let ty = self.lower_ty(&param.ty);
+            self.bind(&param.name.fragment, ty.clone());
+            params.push(ty);
+        }
+
+        let ret = match &func.return_ty {
+            Some(ty) => self.lower_ty(ty),
+            None => Ty::Void,
+        };
+
+        let fn_ty = Ty::Function {
+            params: params.clone(),
+            ret: Box::new(ret.clone()),
+        };
+
+        self.results.def_types.insert(def, fn_ty);
+        self.signatures
+            .insert(def, Rc::new(Signature { generics, ret }));
+    }
+
+    /// Lower the parameter of a constraint (the first time it's needed), recording its type.
+    fn constraint_param(&mut self, def: DefId) -> Option<Rc<ConstraintParam>> {
+        if let Some(param) = self.constraint_params.get(&def) {
+            return param.clone();
+        }
+
+        let Some(Item::Constraint(constraint)) = self.items.get(&def).copied() else {
+            return None;
+        };
+
+        // Mark the constraint as being lowered, in case its parameter's type refers to it.
+        self.constraint_params.insert(def, None);
+        let predicate = constraint.predicate.as_ref()?;
+
+        let generics = self.generic_defs(&constraint.generic_params);
+        let ty = self.lower_ty(&predicate.param.ty);
+        self.bind(&predicate.param.name.fragment, ty.clone());
+
+        let param = Rc::new(ConstraintParam { generics, ty });
+        self.constraint_params.insert(def, Some(param.clone()));
+        Some(param)
+    }
+
+    /// Check that every constraint declaration named in a constrained type can be applied to its base type.
+    /// Functions used as constraints are checked by the [purity checker](crate::analysis::purity).
+    fn check_constrained(&mut self, constrained: &ConstrainedTy, ty: &Ty) {
+        for path in &constrained.constraining_items {
+            if let Some(def) = self.res.resolved(&path.full_path) {
+                self.check_applies(def, def, ty, &path.full_path, &mut Vec::new());
+            }
+        }
+    }
+
+    /// Check that a constraint (which `top` is, or is built from) can be applied to values of a type, along with
+    /// every constraint it builds on. `visiting` holds the constraints being checked, to stop at cycles (which are
+    /// reported at their declarations).
+    fn check_applies(
+        &mut self,
+        top: DefId,
+        def: DefId,
+        ty: &Ty,
+        use_site: &Fragment,
+        visiting: &mut Vec<DefId>,
+    ) -> bool {
+        let Some(Item::Constraint(constraint)) = self.items.get(&def).copied() else {
+            return true;
+        };
+
+        if visiting.contains(&def) {
+            return true;
+        }
+
+        if let Some(param) = self.constraint_param(def) {
+            let substitution = self.instantiate_integers(&param.generics, def, use_site);
+            let expected = param.ty.substitute(&substitution);
+
+            if !self.table.unify(&expected, ty) {
+                let (name, top_name) = (&self.res.def(def).name, &self.res.def(top).name);
+                let expected = self.show(&expected);
+
+                let mut notes = Vec::new();
+
+                if def != top {
+                    notes.push(format!("`{top_name}` is built from `{name}`"));
+                }
+
+                self.error(
+                    Diagnostic::error()
+                        .with_message(format!(
+                            "constraint `{top_name}` cannot be applied to `{}`",
+                            self.show(ty)
+                        ))
+                        .with_highlights([Highlight::primary(
+                            use_site.clone(),
+                            format!("`{name}` constrains values of type `{expected}`"),
+                        )])
+                        .with_notes(notes),
+                );
+
+                return false;
+            }
+        }
+
+        visiting.push(def);
+
+        for base in &constraint.bases {
+            let applies = match self.res.resolved(&base.full_path) {
+                Some(base) => self.check_applies(top, base, ty, use_site, visiting),
+                None => true,
+            };
+
+            if !applies {
+                break;
+            }
+        }
+
+        visiting.pop();
+        true
+    }
+
+    /// Check a constraint declaration: its bases must apply to its parameter's type and can't lead back to it, and
+    /// its body must be a condition.
+    fn check_constraint(&mut self, def: DefId, constraint: &ConstraintDecl) {
+        if self.builds_on(def, def, &mut Vec::new()) {
+            self.error(
+                Diagnostic::error()
+                    .with_message(format!(
+                        "constraint `{}` is built from itself",
+                        constraint.name.fragment.as_str()
+                    ))
+                    .with_highlights([Highlight::primary(
+                        constraint.name.fragment.clone(),
+                        "this constraint's bases lead back to it",
+                    )]),
+            );
+
+            return;
+        }
+
+        let Some(predicate) = &constraint.predicate else {
+            return;
+        };
+
+        let Some(param) = self.constraint_param(def) else {
+            return;
+        };
+
+        let param_def = self.res.declared(&predicate.param.name.fragment);
+        let vars_start = self.table.len();
+        self.bindings.clear();
+
+        // Generic parameters stand for any integer type, so the body is checked with them as integers.
+        let substitution =
+            self.instantiate_integers(&param.generics, def, &constraint.name.fragment);
+        let param_ty = param.ty.substitute(&substitution);
+
+        for base in &constraint.bases {
+            if let Some(base_def) = self.res.resolved(&base.full_path) {
+                self.check_applies(base_def, base_def, &param_ty, &base.full_path, &mut Vec::new());
+            }
+        }
+
+        if let Some(param_def) = param_def {
+            self.results.def_types.insert(param_def, param_ty);
+        }
+
+        let reason = Reason::new(&constraint.name.fragment, "because constraints are conditions");
+        self.check_block(&predicate.body, &Ty::BOOL, Some(reason));
+        self.finish_inference(vars_start);
+
+        // The parameter keeps its generic type, so that the constraint is analyzed over every integer type.
+        if let Some(param_def) = param_def {
+            self.results.def_types.insert(param_def, param.ty.clone());
+        }
+    }
+
+    /// Create an integer inference variable for each of the given generic parameters of a constraint, which
+    /// stand for any integer type.
+    fn instantiate_integers(
+        &mut self,
+        generics: &[DefId],
+        constraint: DefId,
+        site: &Fragment,
+    ) -> HashMap<DefId, Ty> {
+        generics
+            .iter()
+            .map(|generic| {
+                let origin = VarOrigin {
+                    fragment: site.clone(),
+                    description: format!(
+                        "the generic parameter `{}` of `{}`",
+                        self.res.def(*generic).name,
+                        self.res.def(constraint).name
+                    ),
+                };
+
+                (*generic, self.table.new_integer_var(origin))
+            })
+            .collect()
+    }
+
+    /// Check if a constraint builds on `target`, directly or through other constraints.
+    fn builds_on(&self, def: DefId, target: DefId, visited: &mut Vec<DefId>) -> bool {
+        let Some(Item::Constraint(constraint)) = self.items.get(&def) else {
+            return false;
+        };
+
+        if visited.contains(&def) {
+            return false;
+        }
+
+        visited.push(def);
+
+        constraint.bases.iter().any(|base| {
+            self.res
+                .resolved(&base.full_path)
+                .is_some_and(|base| base == target || self.builds_on(base, target, visited))
+        })
+    }
+
+    /// Lower the fields of a record or the variants of a union.
+    fn lower_adt(
+        &mut self,
+        def: DefId,
+        is_union: bool,
+        generics: &[GenericParam],
+        fields: &[FieldDecl],
+    ) {
+        let generics = self.generic_defs(generics);
+
+        let fields = fields
+            .iter()
+            .map(|field| Field {
+                name: field.name.fragment.as_str().to_owned(),
+                ty: self.lower_ty(&field.ty),
+                ty_fragment: field.ty.matching_source().clone(),
+            })
+            .collect();
+
+        self.adts.insert(
+            def,
+            Rc::new(Adt {
+                is_union,
+                generics,
+                fields,
+            }),
+        );
+    }
+
+    /// Check the body of a function against its return type, and finish inferring the types in it.
+    fn check_function(&mut self, func: &FuncDecl) {
+        let Some(body) = &func.body else {
+            return;
+        };
+
+        let Some(signature) = self
+            .res
+            .declared(&func.name.fragment)
+            .and_then(|def| self.signatures.get(&def).cloned())
+        else {
+            return;
+        };
+
+        let reason = match &func.return_ty {
+            Some(ty) => Reason::new(ty.matching_source(), "because of this return type"),
+            None => Reason::new(&func.name.fragment, "because this function has no return type"),
+        };
+
+        let vars_start = self.table.len();
+        self.bindings.clear();
+        self.check_block(body, &signature.ret, Some(reason));
+        self.finish_inference(vars_start);
+    }
+
+    /// Finish inferring the types in a function body once it's been checked: default unconstrained integer
+    /// literals, check operators that were waiting on inference, and report any types that still aren't known.
+    fn finish_inference(&mut self, vars_start: usize) {
+        self.table
+            .default_integers(vars_start, &Ty::Atomic(DEFAULT_INTEGER));
+
+        self.check_obligations();
+
+        for requirement in std::mem::take(&mut self.requirements) {
+            let ty = self.table.resolve(&requirement.ty);
+
+            if requirement.kind.contains(&ty) == Some(false) {
+                self.operator_error(&requirement, &ty);
+            }
+        }
+
+        // Report unknown types at the bindings that contain them first, since that's where annotations go.
+        for (name, def) in std::mem::take(&mut self.bindings) {
+            let ty = self.results.def_types[&def].clone();
+
+            if !self.table.unresolved_vars(&ty).is_empty() {
+                self.report_unknown_binding(&name, &ty, None);
+            }
+        }
+
+        for var in self.table.vars_since(vars_start) {
+            if self.table.is_unbound(var) {
+                let origin = self.table.origin(var).clone();
+
+                self.error(
+                    Diagnostic::error()
+                        .with_message("type annotations needed")
+                        .with_highlights([Highlight::primary(
+                            origin.fragment,
+                            format!("cannot infer {}", origin.description),
+                        )]),
+                );
+
+                self.table.bind(var, Ty::Error);
+            }
+        }
+    }
+
+    /// Report that the type of a binding can't be inferred (optionally highlighting where it needed to be known),
+    /// and mark its unknown parts as errors so they're only reported once.
+    fn report_unknown_binding(&mut self, name: &Fragment, ty: &Ty, needed_at: Option<Highlight>) {
+        let vars = self.table.unresolved_vars(ty);
+        let resolved = self.table.resolve(ty);
+
+        let label = match resolved {
+            Ty::Infer(_) => format!("cannot infer the type of `{name}`"),
+            _ => format!("cannot infer all of the type `{}`", self.show(ty)),
+        };
+
+        let mut highlights = vec![Highlight::primary(name.clone(), label)];
+        highlights.extend(needed_at);
+
+        for var in &vars {
+            let origin = self.table.origin(*var);
+
+            if origin.fragment != *name {
+                highlights.push(Highlight::secondary(
+                    origin.fragment.clone(),
+                    format!("cannot infer {}", origin.description),
+                ));
+            }
+        }
+
+        self.error(
+            Diagnostic::error()
+                .with_message(format!("type annotations needed for `{name}`"))
+                .with_highlights(highlights)
+                .with_notes([format!("help: give `{name}` a type annotation")]),
+        );
+
+        for var in vars {
+            self.table.bind(var, Ty::Error);
+        }
+    }
+
+    /// Report that the type of an expression needs to be known (for the reason given by `label`) but hasn't been
+    /// inferred yet. If the expression names a binding, the error points at the binding.
+    fn report_unknown_expr(&mut self, expr: &Expr, ty: &Ty, label: &str) {
+        let binding = match expr {
+            Expr::Atom(Atom::Identifier(identifier)) => {
+                self.res.resolved(&identifier.fragment).and_then(|def| {
+                    self.bindings
+                        .iter()
+                        .find(|(_, binding)| *binding == def)
+                        .map(|(name, _)| name.clone())
+                })
+            }
+
+            _ => None,
+        };
+
+        let needed_at = Highlight::secondary(expr.matching_source().clone(), label);
+
+        match binding {
+            Some(name) => {
+                let binding_ty = self.results.def_types[&self.res.declared(&name).unwrap()].clone();
+                self.report_unknown_binding(&name, &binding_ty, Some(needed_at));
+            }
+
+            None => {
+                self.error(
+                    Diagnostic::error()
+                        .with_message("type annotations needed")
+                        .with_highlights([Highlight::primary(
+                            expr.matching_source().clone(),
+                            label,
+                        )]),
+                );
+            }
+        }
+
+        for var in self.table.unresolved_vars(ty) {
+            self.table.bind(var, Ty::Error);
+        }
+    }
+
+    /// Report that an expression (highlighted by `blame`) has the wrong type.
+    fn mismatch(&mut self, blame: Fragment, expected: &Ty, found: &Ty, reason: Option<Reason>) {
+        let (expected, found) = (self.show(expected), self.show(found));
+        let mut highlights = vec![Highlight::primary(
+            blame,
+            format!("expected `{expected}`, found `{found}`"),
+        )];
+
+        if let Some(reason) = reason {
+            highlights.push(Highlight::secondary(
+                reason.fragment,
+                format!("expected `{expected}` {}", reason.label),
+            ));
+        }
+
+        self.error(
+            Diagnostic::error()
+                .with_message("mismatched types")
+                .with_highlights(highlights),
+        );
+    }
+
+    /// Type an expression and unify its type with the expected type.
+    fn check_expr(&mut self, expr: &Expr, expected: &Ty, reason: Option<Reason>) -> Ty {
+        let found = self.expr_ty(expr, Some(expected));
+
+        if !self.coerce(&found, expected) {
+            self.mismatch(blame_expr(expr), expected, &found, reason);
+        }
+
+        found
+    }
+
+    /// Unify a type with the type it's expected to have, allowing mutable references to be used where shared ones
+    /// are expected.
+    fn coerce(&mut self, found: &Ty, expected: &Ty) -> bool {
+        match (self.table.shallow_resolve(found), self.table.shallow_resolve(expected)) {
+            (
+                Ty::Reference {
+                    target,
+                    is_mutable: true,
+                },
+                Ty::Reference {
+                    target: expected,
+                    is_mutable: false,
+                },
+            ) => self.table.unify(&target, &expected),
+
+            _ => self.table.unify(found, expected),
+        }
+    }
+
+    /// Type a block and unify its type with the expected type.
+    fn check_block(&mut self, block: &Block, expected: &Ty, reason: Option<Reason>) -> Ty {
+        let found = self.block_ty(block, Some(expected));
+
+        if !self.coerce(&found, expected) {
+            self.mismatch(blame_block(block), expected, &found, reason);
+        }
+
+        found
+    }
+
+    /// Compute the type of a block, checking all of its statements.
+    fn block_ty(&mut self, block: &Block, expected: Option<&Ty>) -> Ty {
+        for stmt in &block.stmts {
+            match stmt {
+                Stmt::Let(let_stmt) => {
+                    let ty = match &let_stmt.ty {
+                        Some(annotation) => {
+                            let ty = self.lower_ty(annotation);
+                            let reason = Reason::new(
+                                annotation.matching_source(),
+                                "because of this type annotation",
+                            );
+
+                            self.check_expr(&let_stmt.value, &ty, Some(reason));
+                            ty
+                        }
+
+                        None => self.expr_ty(&let_stmt.value, None),
+                    };
+
+                    self.bind(&let_stmt.name.fragment, ty);
+                }
+
+                Stmt::Assign(assign) => self.check_assign(assign),
+
+                Stmt::Expr(expr_stmt) => {
+                    self.expr_ty(&expr_stmt.expr, None);
+                }
+            }
+        }
+
+        match &block.tail {
+            Some(tail) => self.expr_ty(tail, expected),
+            None => Ty::Void,
+        }
+    }
+
+    /// Check an assignment: the target must be a place that can be assigned to, and the value must have its type.
+    fn check_assign(&mut self, assign: &AssignStmt) {
+        let target = self.expr_ty(&assign.target, None);
+        self.check_place(&assign.target, PlaceAccess::Assign);
+
+        let applies = match assign.op {
+            Some(op) => self.require(Requirement {
+                ty: target.clone(),
+                kind: match op {
+                    BinaryOperation::BitwiseAnd
+                    | BinaryOperation::BitwiseOr
+                    | BinaryOperation::BitwiseXor => OperandKind::IntegerOrBool,
+                    _ => OperandKind::Numeric,
+                },
+                operator: op.assign_symbol().unwrap_or(op.symbol()),
+                operator_fragment: assign.op_fragment.clone(),
+                operand: assign.target.matching_source().clone(),
+            }),
+
+            None => true,
+        };
+
+        let target = if applies { target } else { Ty::Error };
+        let reason = Reason::new(
+            assign.target.matching_source(),
+            "because the assignment target has this type",
+        );
+        self.check_expr(&assign.value, &target, Some(reason));
+    }
+
+    /// Check that an expression is a place that can be assigned to (or mutably borrowed, depending on `access`): a
+    /// `var` binding, a field of one, or anything behind a mutable reference.
+    fn check_place(&mut self, target: &Expr, access: PlaceAccess) {
+        let name = match target {
+            Expr::Parens(parens) => return self.check_place(&parens.inner, access),
+
+            // Fields are accessed through references automatically.
+            Expr::Field(field) => match self.reference_ty(&field.base) {
+                Some(false) => return self.behind_shared_reference(target, &field.base, access),
+                Some(true) => return,
+                None => return self.check_place(&field.base, access),
+            },
+
+            Expr::Unary(unary) if unary.op == UnaryOperation::Dereference => {
+                if self.reference_ty(&unary.operand) == Some(false) {
+                    self.behind_shared_reference(target, &unary.operand, access);
+                }
+
+                return;
+            }
+
+            Expr::Atom(Atom::Identifier(identifier)) => &identifier.fragment,
+            Expr::Path(path) => &path.full_path,
+
+            // Anything else can be borrowed mutably as a temporary.
+            _ if access == PlaceAccess::Borrow => return,
+
+            _ => {
+                self.error(
+                    Diagnostic::error()
+                        .with_message("invalid assignment target")
+                        .with_highlights([Highlight::primary(
+                            target.matching_source().clone(),
+                            "this can't be assigned to",
+                        )]),
+                );
+
+                return;
+            }
+        };
+
+        // Unresolved names have already been reported.
+        let Some(def) = self.res.resolved(name) else {
+            return;
+        };
+
+        let definition = self.res.def(def);
+
+        if definition.kind == (DefKind::Local { is_mutable: true }) {
+            return;
+        }
+
+        // Functions and constants can be borrowed mutably as temporaries, like other values.
+        if access == PlaceAccess::Borrow
+            && !matches!(definition.kind, DefKind::Local { .. } | DefKind::Param)
+        {
+            return;
+        }
+
+        let (verb, label) = match access {
+            PlaceAccess::Assign => ("assign to", "cannot assign to this"),
+            PlaceAccess::Borrow => ("borrow", "cannot borrow this as mutable"),
+        };
+
+        let mut highlights = vec![Highlight::primary(name.clone(), label)];
+        let mut diagnostic = Diagnostic::error();
+        let as_mutable = if access == PlaceAccess::Borrow {
+            " as mutable"
+        } else {
+            ""
+        };
+
+        if let DefKind::Local { .. } = definition.kind {
+            diagnostic = diagnostic
+                .with_message(format!(
+                    "cannot {verb} `{}`{as_mutable}, which is not declared with `var`",
+                    definition.name
+                ))
+                .with_notes([format!(
+                    "help: declare `{}` with `var` to allow {}",
+                    definition.name,
+                    match access {
+                        PlaceAccess::Assign => "assigning to it",
+                        PlaceAccess::Borrow => "borrowing it mutably",
+                    }
+                )]);
+        } else {
+            diagnostic = diagnostic.with_message(format!(
+                "cannot {verb} {} `{}`{as_mutable}",
+                definition.kind.describe(),
+                definition.name
+            ));
+        }
+
+        if let Some(declared) = &definition.name_fragment {
+            highlights.push(Highlight::secondary(
+                declared.clone(),
+                format!("`{}` is declared here", definition.name),
+            ));
+        }
+
+        self.error(diagnostic.with_highlights(highlights));
+    }
+
+    /// Check if the (already typed) expression is a reference, and if it is, whether it's mutable.
+    fn reference_ty(&self, expr: &Expr) -> Option<bool> {
+        let ty = self.results.expr_types.get(expr.matching_source())?;
+
+        match self.table.shallow_resolve(ty) {
+            Ty::Reference { is_mutable, .. } => Some(is_mutable),
+            _ => None,
+        }
+    }
+
+    /// Report assigning to (or mutably borrowing) a place behind a shared reference.
+    fn behind_shared_reference(&mut self, target: &Expr, reference: &Expr, access: PlaceAccess) {
+        let (message, label) = match access {
+            PlaceAccess::Assign => ("cannot assign to", "cannot assign to this"),
+            PlaceAccess::Borrow => ("cannot borrow", "cannot borrow this as mutable"),
+        };
+
+        let ty = self.results.expr_types[reference.matching_source()].clone();
+        let ty = self.show(&ty);
+
+        self.error(
+            Diagnostic::error()
+                .with_message(format!(
+                    "{message} `{}`, which is behind a shared reference",
+                    target.matching_source().as_str()
+                ))
+                .with_highlights([
+                    Highlight::primary(target.matching_source().clone(), label),
+                    Highlight::secondary(
+                        reference.matching_source().clone(),
+                        format!(
+                            "this has type `{ty}`, which doesn't allow changing what it points to"
+                        ),
+                    ),
+                ])
+                .with_notes(["help: use a mutable reference (`@mut`) instead".to_owned()]),
+        );
+    }
+
+    /// Compute the type of an expression, using the `expected` type (if there is one) as a hint for things like
+    /// closure parameters. This does not unify the type with the expected type (see [Checker::check_expr]).
+    ///
+    /// The type is recorded in the [TypeckResults].
+    fn expr_ty(&mut self, expr: &Expr, expected: Option<&Ty>) -> Ty {
+        let ty = match expr {
+            Expr::Atom(Atom::IntegerLiteral(literal)) => self.table.new_integer_var(VarOrigin {
+                fragment: literal.fragment.clone(),
+                description: "the type of this integer literal".to_owned(),
+            }),
+
+            Expr::Atom(Atom::BooleanLiteral(_)) => Ty::BOOL,
+            Expr::Atom(Atom::StringLiteral(_)) => Ty::Str,
+            Expr::Atom(Atom::Identifier(identifier)) => self.value_ty(&identifier.fragment),
+            Expr::Path(path) => self.value_ty(&path.full_path),
+            Expr::Parens(parens) => self.expr_ty(&parens.inner, expected),
+            Expr::Unary(unary) => self.unary_ty(unary, expected),
+            Expr::Binary(binary) => self.binary_ty(binary, expected),
+            Expr::Is(is_expr) => self.is_ty(is_expr),
+            Expr::Call(call) => self.call_ty(call),
+            Expr::Field(field) => self.field_ty(field),
+            Expr::Record(record) => self.record_ty(record, expected),
+            Expr::Block(block) => self.block_ty(block, expected),
+            Expr::Unsafe(unsafe_expr) => self.block_ty(&unsafe_expr.body, expected),
+            Expr::If(if_expr) => self.if_ty(if_expr, expected),
+            Expr::Closure(closure) => self.closure_ty(closure, expected),
+            Expr::For(for_expr) => self.for_ty(for_expr),
+            Expr::Match(match_expr) => self.match_ty(match_expr, expected),
+
+            Expr::While(while_expr) => {
+                self.check_expr(&while_expr.condition, &Ty::BOOL, None);
+                let depth = self.narrow(&while_expr.condition);
+                self.check_block(&while_expr.body, &Ty::Void, None);
+                self.narrowed.truncate(depth);
+                Ty::Void
+            }
+        };
+
+        self.results
+            .expr_types
+            .insert(expr.matching_source().clone(), ty.clone());
+
+        ty
+    }
+
+    /// Check a `for` loop. Both ends of the range must be the same integer type, which is the type of the binding.
+    fn for_ty(&mut self, for_expr: &ForExpr) -> Ty {
+        let range = &for_expr.range;
+        let ty = self.expr_ty(&range.start, None);
+        let reason = Reason::new(
+            range.start.matching_source(),
+            "because the start of the range has this type",
+        );
+        self.check_expr(&range.end, &ty, Some(reason));
+
+        // The range operator is between the two ends, possibly surrounded by whitespace.
+        let mut operator_fragment = range.matching_source.clone();
+        operator_fragment.range =
+            range.start.matching_source().range.end..range.end.matching_source().range.start;
+        let between = operator_fragment.as_str();
+        let (leading, trailing) = (
+            between.len() - between.trim_start().len(),
+            between.len() - between.trim_end().len(),
+        );
+        operator_fragment.range.start += leading;
+        operator_fragment.range.end -= trailing;
+
+        self.require(Requirement {
+            ty: ty.clone(),
+            kind: OperandKind::Integer,
+            operator: if range.is_inclusive { "..=" } else { ".." },
+            operator_fragment,
+            operand: range.start.matching_source().clone(),
+        });
+
+        self.bind(&for_expr.binding.fragment, ty);
+        self.check_block(&for_expr.body, &Ty::Void, None);
+        Ty::Void
+    }
+
+    /// Get the type of a name used as a value.
+    fn value_ty(&mut self, use_site: &Fragment) -> Ty {
+        // Unresolved names have already been reported.
+        let Some(def) = self.res.resolved(use_site) else {
+            return Ty::Error;
+        };
+
+        if let Some(ty) = self.results.def_types.get(&def) {
+            return ty.clone();
+        }
+
+        // The builtins all print a value of any type.
+        if let Some(builtin) = self.res.builtin(def) {
+            let param = self.table.new_var(VarOrigin {
+                fragment: use_site.clone(),
+                description: format!("the type of the value passed to `{}`", builtin.name()),
+            });
+
+            return Ty::Function {
+                params: vec![param],
+                ret: Box::new(Ty::Void),
+            };
+        }
+
+        // Enum variants are values of their enum.
+        if let Some(enumeration) = self.res.enum_of(def) {
+            return Ty::Named {
+                def: enumeration,
+                args: Vec::new(),
+            };
+        }
+
+        let definition = self.res.def(def);
+
+        if !matches!(
+            definition.kind,
+            DefKind::Local { .. } | DefKind::Param | DefKind::Function | DefKind::Constant
+        ) {
+            self.error(
+                Diagnostic::error()
+                    .with_message(format!(
+                        "expected value, found {} `{}`",
+                        definition.kind.describe(),
+                        definition.name
+                    ))
+                    .with_highlights([Highlight::primary(use_site.clone(), "not a value")]),
+            );
+        }
+
+        Ty::Error
+    }
+
+    /// Require that an operand has a type the operator can be applied to. If that isn't known yet, it's checked
+    /// once the function has been inferred.
+    ///
+    /// Returns `false` if the operand is already known not to have such a type (and the error has been reported), in
+    /// which case the operation should have the error type so that its uses aren't reported too.
+    fn require(&mut self, requirement: Requirement) -> bool {
+        let ty = self.table.resolve(&requirement.ty);
+
+        match requirement.kind.contains(&ty) {
+            Some(true) => {}
+            Some(false) => {
+                self.operator_error(&requirement, &ty);
+                return false;
+            }
+            None => self.requirements.push(requirement),
+        }
+
+        true
+    }
+
+    /// Report an operator being applied to a type it doesn't support.
+    fn operator_error(&mut self, requirement: &Requirement, ty: &Ty) {
+        let (operator, ty) = (requirement.operator, self.show(ty));
+
+        self.error(
+            Diagnostic::error()
+                .with_message(format!("cannot apply `{operator}` to a value of type `{ty}`"))
+                .with_highlights([
+                    Highlight::primary(
+                        requirement.operator_fragment.clone(),
+                        format!("`{operator}` can't be used on `{ty}`"),
+                    ),
+                    Highlight::secondary(
+                        requirement.operand.clone(),
+                        format!("this has type `{ty}`"),
+                    ),
+                ]),
+        );
+    }
+
+    /// Compute the type of a unary operation.
+    fn unary_ty(&mut self, unary: &UnaryExpr, expected: Option<&Ty>) -> Ty {
+        let operand = &unary.operand;
+
+        match unary.op {
+            UnaryOperation::Reference | UnaryOperation::MutableReference => {
+                let hint = match expected.map(|ty| self.table.shallow_resolve(ty)) {
+                    Some(Ty::Reference { target, .. }) => Some(*target),
+                    _ => None,
+                };
+
+                let is_mutable = unary.op == UnaryOperation::MutableReference;
+                let target = self.expr_ty(operand, hint.as_ref());
+
+                if is_mutable {
+                    self.check_place(operand, PlaceAccess::Borrow);
+                }
+
+                Ty::reference(target, is_mutable)
+            }
+
+            UnaryOperation::Dereference => {
+                let hint = expected.map(|ty| Ty::reference(ty.clone(), false));
+                let ty = self.expr_ty(operand, hint.as_ref());
+
+                match self.table.shallow_resolve(&ty) {
+                    Ty::Reference { target, .. } => *target,
+                    Ty::Error => Ty::Error,
+
+                    // If the type of the operand isn't known yet, it has to be a reference to something.
+                    Ty::Infer(_) => {
+                        let target = self.table.new_var(VarOrigin {
+                            fragment: operand.matching_source().clone(),
+                            description: "the type this reference points to".to_owned(),
+                        });
+
+                        self.table.unify(&ty, &Ty::reference(target.clone(), false));
+                        target
+                    }
+
+                    other => {
+                        let other = self.show(&other);
+
+                        self.error(
+                            Diagnostic::error()
+                                .with_message(format!("type `{other}` cannot be dereferenced"))
+                                .with_highlights([Highlight::primary(
+                                    operand.matching_source().clone(),
+                                    format!("this has type `{other}`, which is not a reference"),
+                                )]),
+                        );
+
+                        Ty::Error
+                    }
+                }
+            }
+
+            UnaryOperation::BooleanNot => {
+                self.check_expr(operand, &Ty::BOOL, None);
+                Ty::BOOL
+            }
+
+            UnaryOperation::Negate | UnaryOperation::BitwiseNot => {
+                let ty = self.expr_ty(operand, expected);
+
+                // The operator is the first character of the expression.
+                let mut operator_fragment = unary.matching_source.clone();
+                operator_fragment.range.end = operator_fragment.range.start + 1;
+
+                let applies = self.require(Requirement {
+                    ty: ty.clone(),
+                    kind: match unary.op {
+                        UnaryOperation::Negate => OperandKind::SignedOrFloat,
+                        _ => OperandKind::Integer,
+                    },
+                    operator: unary.op.symbol(),
+                    operator_fragment,
+                    operand: operand.matching_source().clone(),
+                });
+
+                if applies { ty } else { Ty::Error }
+            }
+        }
+    }
+
+    /// Compute the type of a binary operation.
+    fn binary_ty(&mut self, binary: &BinaryExpr, expected: Option<&Ty>) -> Ty {
+        use BinaryOperation::*;
+
+        let op = binary.op;
+
+        if op.is_logical() {
+            self.check_expr(&binary.lhs, &Ty::BOOL, None);
+
+            // The right side of `&&` is only evaluated when the left side is `true`.
+            let depth = match op {
+                LogicalAnd => self.narrow(&binary.lhs),
+                _ => self.narrowed.len(),
+            };
+
+            self.check_expr(&binary.rhs, &Ty::BOOL, None);
+            self.narrowed.truncate(depth);
+            return Ty::BOOL;
+        }
+
+        if op.is_shift() {
+            let lhs = self.expr_ty(&binary.lhs, expected);
+            let rhs = self.expr_ty(&binary.rhs, None);
+
+            let mut applies = true;
+
+            for (operand, ty) in [(&binary.lhs, lhs.clone()), (&binary.rhs, rhs)] {
+                applies &= self.require(Requirement {
+                    ty,
+                    kind: OperandKind::Integer,
+                    operator: op.symbol(),
+                    operator_fragment: binary.op_fragment.clone(),
+                    operand: operand.matching_source().clone(),
+                });
+            }
+
+            return if applies { lhs } else { Ty::Error };
+        }
+
+        // Both operands must have the same type. Comparisons don't pass the expected type down, since it's `bool`.
+        let hint = if op.is_comparison() { None } else { expected };
+        let lhs = self.expr_ty(&binary.lhs, hint);
+
+        let kind = match op {
+            Equal | NotEqual => OperandKind::Equatable,
+            Less | LessOrEqual | Greater | GreaterOrEqual => OperandKind::Ordered,
+            BitwiseAnd | BitwiseOr | BitwiseXor => OperandKind::IntegerOrBool,
+            _ => OperandKind::Numeric,
+        };
+
+        let applies = self.require(Requirement {
+            ty: lhs.clone(),
+            kind,
+            operator: op.symbol(),
+            operator_fragment: binary.op_fragment.clone(),
+            operand: binary.lhs.matching_source().clone(),
+        });
+
+        // Once the operator is known not to apply, the right side can't be expected to match the left side and the
+        // result is an error, so neither is reported again.
+        let lhs = if applies { lhs } else { Ty::Error };
+        let reason =
+            Reason::new(binary.lhs.matching_source(), "because the left side has this type");
+        self.check_expr(&binary.rhs, &lhs, Some(reason));
+
+        match op.is_comparison() {
+            true => Ty::BOOL,
+            false => lhs,
+        }
+    }
+
+    /// Create an inference variable for each of the given generic parameters of the named item.
+    fn instantiate(
+        &mut self,
+        generics: &[DefId],
+        item: DefId,
+        site: &Fragment,
+    ) -> HashMap<DefId, Ty> {
+        generics
+            .iter()
+            .map(|generic| {
+                let origin = VarOrigin {
+                    fragment: site.clone(),
+                    description: format!(
+                        "the generic parameter `{}` of `{}`",
+                        self.res.def(*generic).name,
+                        self.res.def(item).name
+                    ),
+                };
+
+                (*generic, self.table.new_var(origin))
+            })
+            .collect()
+    }
+
+    /// Compute the type of a function or method call, checking its arguments.
+    fn call_ty(&mut self, call: &CallExpr) -> Ty {
+        // Calling a field (`shape.area()`) calls a method, unless there's no method with the name but there's a field
+        // with it.
+        if let Expr::Field(field) = &*call.callee {
+            let base = self.expr_ty(&field.base, None);
+
+            if let Some(ty) = self.method_call_ty(call, field, &base) {
+                return ty;
+            }
+
+            let callee_ty = self.field_of(field, &base);
+            self.results
+                .expr_types
+                .insert(call.callee.matching_source().clone(), callee_ty.clone());
+            return self.call_with(call, None, callee_ty);
+        }
+
+        // If the callee names a function directly, its declaration gives better diagnostics and its generics.
+        let callee_def = match &*call.callee {
+            Expr::Atom(Atom::Identifier(identifier)) => self.res.resolved(&identifier.fragment),
+            Expr::Path(path) => self.res.resolved(&path.full_path),
+            _ => None,
+        };
+
+        let callee_ty = self.expr_ty(&call.callee, None);
+        self.call_with(call, callee_def, callee_ty)
+    }
+
+    /// Check the arguments of a call of a value of the given type, which is the function with the given definition
+    /// if the callee names one directly.
+    fn call_with(&mut self, call: &CallExpr, callee_def: Option<DefId>, callee_ty: Ty) -> Ty {
+        let decl = callee_def.and_then(|def| match self.items.get(&def) {
+            Some(Item::Function(func)) => Some(*func),
+            _ => None,
+        });
+
+        let (params, ret) = match self.table.shallow_resolve(&callee_ty) {
+            Ty::Function { params, ret } => (params, *ret),
+
+            // A callee whose type isn't known yet must be a function that takes these arguments.
+            Ty::Infer(_) => {
+                let params: Vec<Ty> = (call.args.iter())
+                    .map(|arg| {
+                        self.table.new_var(VarOrigin {
+                            fragment: arg.matching_source().clone(),
+                            description: "the type of this argument".to_owned(),
+                        })
+                    })
+                    .collect();
+
+                let ret = self.table.new_var(VarOrigin {
+                    fragment: call.matching_source.clone(),
+                    description: "the type returned by this call".to_owned(),
+                });
+
+                let fn_ty = Ty::Function {
+                    params: params.clone(),
+                    ret: Box::new(ret.clone()),
+                };
+
+                self.table.unify(&callee_ty, &fn_ty);
+                (params, ret)
+            }
+
+            other => {
+                if other != Ty::Error {
+                    let other = self.show(&other);
+
+                    self.error(
+                        Diagnostic::error()
+                            .with_message(format!("expected function, found `{other}`"))
+                            .with_highlights([Highlight::primary(
+                                call.callee.matching_source().clone(),
+                                format!("this has type `{other}`, which can't be called"),
+                            )]),
+                    );
+                }
+
+                for arg in &call.args {
+                    self.expr_ty(arg, None);
+                }
+
+                return Ty::Error;
+            }
+        };
+
+        // Generic functions get fresh inference variables for their generic parameters at every call.
+        let substitution = match callee_def.and_then(|def| self.signatures.get(&def).cloned()) {
+            Some(signature) if !signature.generics.is_empty() => {
+                let def = callee_def.unwrap();
+                self.instantiate(&signature.generics, def, call.callee.matching_source())
+            }
+
+            _ => HashMap::new(),
+        };
+
+        if let Some(def) = callee_def {
+            self.require_bounds(def, &substitution, call.callee.matching_source());
+        }
+
+        let params: Vec<Ty> = params
+            .iter()
+            .map(|param| param.substitute(&substitution))
+            .collect();
+        let ret = ret.substitute(&substitution);
+
+        if call.args.len() != params.len() {
+            let mut highlights = vec![Highlight::primary(
+                call.matching_source.clone(),
+                format!("expected {} argument{}", params.len(), plural(params.len())),
+            )];
+
+            if let Some(decl) = decl {
+                highlights.push(Highlight::secondary(
+                    decl.name.fragment.clone(),
+                    "function defined here",
+                ));
+            }
+
+            self.error(
+                Diagnostic::error()
+                    .with_message(format!(
+                        "this function takes {} argument{} but {} argument{} {} supplied",
+                        params.len(),
+                        plural(params.len()),
+                        call.args.len(),
+                        plural(call.args.len()),
+                        if call.args.len() == 1 { "was" } else { "were" }
+                    ))
+                    .with_highlights(highlights),
+            );
+
+            for arg in &call.args {
+                self.expr_ty(arg, None);
+            }
+
+            // The generic arguments can't be inferred from the wrong arguments, and that's already an error.
+            for var in substitution.values() {
+                self.table.unify(var, &Ty::Error);
+            }
+
+            return ret;
+        }
+
+        for (index, (arg, param)) in call.args.iter().zip(&params).enumerate() {
+            let reason = decl.map(|decl| {
+                Reason::new(
+                    decl.params[index].ty.matching_source(),
+                    "because of this parameter's type",
+                )
+            });
+
+            self.check_expr(arg, param, reason);
+        }
+
+        ret
+    }
+
+    /// Report a field name that doesn't exist, suggesting a similar one if there is one.
+    fn unknown_field(&mut self, message: String, field: &Fragment, candidates: &[&str]) {
+        let mut diagnostic = Diagnostic::error()
+            .with_message(message)
+            .with_highlights([Highlight::primary(field.clone(), "unknown field")]);
+
+        if let Some(suggestion) = best_match(field.as_str(), candidates.iter().copied()) {
+            diagnostic = diagnostic.with_notes([format!("help: did you mean `{suggestion}`?")]);
+        }
+
+        self.error(diagnostic);
+    }
+
+    /// Compute the type of a field access.
+    fn field_ty(&mut self, field: &FieldExpr) -> Ty {
+        let base = self.expr_ty(&field.base, None);
+        self.field_of(field, &base)
+    }
+
+    /// Compute the type of a field access, given the type of the value that the field is accessed on.
+    fn field_of(&mut self, field: &FieldExpr, base: &Ty) -> Ty {
+        let name = field.field.fragment.as_str();
+
+        // Fields can be accessed through references.
+        let mut target = self.table.shallow_resolve(base);
+
+        while let Ty::Reference { target: inner, .. } = target {
+            target = self.table.shallow_resolve(&inner);
+        }
+
+        match &target {
+            Ty::Error => return Ty::Error,
+
+            Ty::Infer(_) => {
+                self.report_unknown_expr(
+                    &field.base,
+                    base,
+                    "the type must be known here to access a field",
+                );
+
+                return Ty::Error;
+            }
+
+            _ => {}
+        }
+
+        if let Ty::Named { def, args } = &target
+            && let Some(adt) = self.adts.get(def).cloned()
+        {
+            let found = adt.fields.iter().find(|candidate| candidate.name == name);
+
+            match (adt.is_union, found) {
+                (false, Some(found)) => {
+                    let substitution = adt
+                        .generics
+                        .iter()
+                        .copied()
+                        .zip(args.iter().cloned())
+                        .collect();
+
+                    return found.ty.substitute(&substitution);
+                }
+
+                (true, Some(found)) => {
+                    let binding = self.narrowable_binding(&field.base);
+
+                    // Where an `is` test for the variant is known to be `true`, the binding holds it.
+                    if let Some(binding) = binding
+                        && (self.narrowed.iter())
+                            .any(|(def, variant)| *def == binding && variant == name)
+                    {
+                        let substitution = (adt.generics.iter().copied())
+                            .zip(args.iter().cloned())
+                            .collect();
+
+                        return found.ty.substitute(&substitution);
+                    }
+
+                    self.report_variant_access(field, *def, binding);
+                }
+
+                (_, None) => {
+                    let candidates: Vec<&str> =
+                        adt.fields.iter().map(|field| field.name.as_str()).collect();
+                    let message = format!("no field `{name}` on type `{}`", self.show(&target));
+                    self.unknown_field(message, &field.field.fragment, &candidates);
+                }
+            }
+
+            return Ty::Error;
+        }
+
+        let message = format!("no field `{name}` on type `{}`", self.show(&target));
+        self.unknown_field(message, &field.field.fragment, &[]);
+        Ty::Error
+    }
+
+    /// Report a variant of a union read like a field where the union isn't known to hold it, pointing at the `is`
+    /// test for the binding (if there is one) that it's outside of.
+    fn report_variant_access(&mut self, field: &FieldExpr, union: DefId, binding: Option<DefId>) {
+        let name = field.field.fragment.as_str();
+        let base = field.base.matching_source().as_str();
+        let union_name = &self.res.def(union).name;
+
+        let mut highlights = vec![Highlight::primary(
+            field.field.fragment.clone(),
+            format!("`{name}` is a variant of a union"),
+        )];
+
+        let test = (self.variant_tests.iter())
+            .filter(|(_, (def, _))| Some(*def) == binding)
+            .max_by_key(|(fragment, _)| fragment.range.start);
+
+        if let Some((fragment, (_, variant))) = test {
+            let label = match variant == name {
+                true => {
+                    format!("`{base}` is only known to hold `{name}` where this test is `true`")
+                }
+                false => format!("this tests for `{variant}`, not `{name}`"),
+            };
+
+            highlights.push(Highlight::secondary(fragment.clone(), label));
+        }
+
+        let help = match (binding, self.binding(&field.base).map(|def| self.res.def(def).kind)) {
+            (Some(_), _) => {
+                format!(
+                    "help: test for the variant first, i.e. `if {base} is {union_name}::{name} {{ ... }}`"
+                )
+            }
+
+            (None, Some(DefKind::Local { is_mutable: true })) => {
+                format!(
+                    "`{base}` is a `var` binding, so testing its variant with `is` doesn't narrow it"
+                )
+            }
+
+            (None, _) => {
+                "help: bind the value with `let`, then test its variant with `is`".to_owned()
+            }
+        };
+
+        self.error(
+            Diagnostic::error()
+                .with_message(format!("no field `{name}` on type `{union_name}`"))
+                .with_highlights(highlights)
+                .with_notes([
+                    "the variants of a union can't be accessed like fields, since a union only holds one of them at a time".to_owned(),
+                    help,
+                ]),
+        );
+    }
+
+    /// Get the binding (parameter, closure parameter, or local) that an expression names, if it's just a name.
+    fn binding(&self, expr: &Expr) -> Option<DefId> {
+        match expr {
+            Expr::Parens(parens) => self.binding(&parens.inner),
+
+            Expr::Atom(Atom::Identifier(identifier)) => {
+                self.res.resolved(&identifier.fragment).filter(|def| {
+                    matches!(self.res.def(*def).kind, DefKind::Local { .. } | DefKind::Param)
+                })
+            }
+
+            _ => None,
+        }
+    }
+
+    /// Get the binding that an expression names if `is` tests of it can narrow it -- it has to be a parameter or
+    /// `let` binding, which can't be reassigned.
+    fn narrowable_binding(&self, expr: &Expr) -> Option<DefId> {
+        self.binding(expr)
+            .filter(|def| self.res.def(*def).kind != (DefKind::Local { is_mutable: true }))
+    }
+
+    /// Narrow bindings by the `is` tests in a condition, for the code that only runs when it's `true`. Returns the
+    /// length of [Checker::narrowed] to truncate it to once that code has been checked.
+    fn narrow(&mut self, condition: &Expr) -> usize {
+        let depth = self.narrowed.len();
+        let mut tests = vec![condition];
+
+        while let Some(condition) = tests.pop() {
+            match condition {
+                Expr::Parens(parens) => tests.push(&parens.inner),
+
+                // Both sides of `&&` are `true` when it is.
+                Expr::Binary(binary) if binary.op == BinaryOperation::LogicalAnd => {
+                    tests.extend([&*binary.lhs, &*binary.rhs])
+                }
+
+                Expr::Is(is_expr) => {
+                    if let Some(test) = self.variant_tests.get(&is_expr.matching_source) {
+                        self.narrowed.push(test.clone());
+                    }
+                }
+
+                _ => {}
+            }
+        }
+
+        depth
+    }
+
+    /// Compute the type of an `is` test, checking that it tests for a variant of the value's type.
+    fn is_ty(&mut self, is_expr: &IsExpr) -> Ty {
+        let value = self.expr_ty(&is_expr.value, None);
+
+        // Values can be tested through references, like fields can be accessed through them.
+        let mut target = self.table.shallow_resolve(&value);
+
+        while let Ty::Reference { target: inner, .. } = target {
+            target = self.table.shallow_resolve(&inner);
+        }
+
+        let (def, args) = match &target {
+            Ty::Error => return Ty::BOOL,
+
+            Ty::Infer(_) => {
+                self.report_unknown_expr(
+                    &is_expr.value,
+                    &value,
+                    "the type must be known here to test its variant",
+                );
+
+                return Ty::BOOL;
+            }
+
+            Ty::Named { def, args } => (*def, args.clone()),
+            _ => return self.untestable(is_expr, &target),
+        };
+
+        if self.res.def(def).kind == DefKind::Enum {
+            self.check_enum_test(is_expr, def);
+            return Ty::BOOL;
+        }
+
+        let Some(adt) = self.adts.get(&def).cloned().filter(|adt| adt.is_union) else {
+            return self.untestable(is_expr, &target);
+        };
+
+        let variant = match self.variant_path(&is_expr.target) {
+            Some((Some(union), variant)) => self.named_variant(is_expr, def, &adt, union, variant),
+
+            // The union in the path couldn't be resolved, which has already been reported.
+            Some((None, _)) => None,
+            None => self.typed_variant(is_expr, def, &adt, &args),
+        };
+
+        if let Some(variant) = &variant {
+            (self.results.union_tests).insert(is_expr.matching_source.clone(), variant.clone());
+        }
+
+        if let (Some(variant), Some(binding)) = (variant, self.narrowable_binding(&is_expr.value)) {
+            (self.variant_tests).insert(is_expr.matching_source.clone(), (binding, variant));
+        }
+
+        Ty::BOOL
+    }
+
+    /// Report an `is` test of a value that isn't a union or enum.
+    fn untestable(&mut self, is_expr: &IsExpr, ty: &Ty) -> Ty {
+        let shown = self.show(ty);
+
+        self.error(
+            Diagnostic::error()
+                .with_message(format!("cannot test the variant of a value of type `{shown}`"))
+                .with_highlights([Highlight::primary(
+                    is_expr.value.matching_source().clone(),
+                    format!("`{shown}` isn't a union or enum"),
+                )]),
+        );
+
+        Ty::BOOL
+    }
+
+    /// If the target of an `is` test is a path to a union's variant (i.e. `Number::small`), get the union (if it
+    /// was resolved) and the name of the variant.
+    fn variant_path<'t>(&self, target: &'t Type) -> Option<(Option<DefId>, &'t Identifier)> {
+        let Type::Named(named) = target else {
+            return None;
+        };
+
+        // Variants of unions aren't definitions, so the path as a whole doesn't resolve.
+        let variant = named.name.tail.last()?;
+
+        if self.res.resolved(&named.name.full_path).is_some() {
+            return None;
+        }
+
+        let prefix = named
+            .name
+            .tail
+            .iter()
+            .rev()
+            .nth(1)
+            .unwrap_or(&named.name.head);
+        Some((self.res.resolved(&prefix.fragment), variant))
+    }
+
+    /// Check an `is` test for a variant of a union by name, returning the name if the union has that variant.
+    fn named_variant(
+        &mut self,
+        is_expr: &IsExpr,
+        def: DefId,
+        adt: &Adt,
+        union: DefId,
+        variant: &Identifier,
+    ) -> Option<String> {
+        let union_name = self.res.def(def).name.clone();
+        let name = variant.fragment.as_str();
+
+        if union != def {
+            let found = &self.res.def(union);
+
+            self.error(
+                Diagnostic::error()
+                    .with_message(format!(
+                        "expected a variant of `{union_name}`, found a variant of {} `{}`",
+                        found.kind.describe(),
+                        found.name
+                    ))
+                    .with_highlights([
+                        Highlight::primary(
+                            is_expr.target.matching_source().clone(),
+                            format!("not a variant of `{union_name}`"),
+                        ),
+                        Highlight::secondary(
+                            is_expr.value.matching_source().clone(),
+                            format!("this is a `{union_name}`"),
+                        ),
+                    ]),
+            );
+
+            return None;
+        }
+
+        if !adt.fields.iter().any(|field| field.name == name) {
+            let candidates: Vec<&str> =
+                adt.fields.iter().map(|field| field.name.as_str()).collect();
+            let message = format!("union `{union_name}` has no variant named `{name}`");
+            self.unknown_field(message, &variant.fragment, &candidates);
+            return None;
+        }
+
+        Some(name.to_owned())
+    }
+
+    /// Check an `is` test for the variant of a union with a type, returning its name if exactly one variant has
+    /// that type.
+    fn typed_variant(
+        &mut self,
+        is_expr: &IsExpr,
+        def: DefId,
+        adt: &Adt,
+        args: &[Ty],
+    ) -> Option<String> {
+        let ty = self.lower_ty(&is_expr.target);
+
+        if ty.references_error() {
+            return None;
+        }
+
+        let substitution = adt
+            .generics
+            .iter()
+            .copied()
+            .zip(args.iter().cloned())
+            .collect();
+
+        let matching: Vec<&Field> = (adt.fields.iter())
+            .filter(|field| self.table.resolve(&field.ty.substitute(&substitution)) == ty)
+            .collect();
+
+        let union_name = self.res.def(def).name.clone();
+        let shown = self.show(&ty);
+        let target = is_expr.target.matching_source().clone();
+
+        match matching.as_slice() {
+            [variant] => return Some(variant.name.clone()),
+
+            [] => self.error(
+                Diagnostic::error()
+                    .with_message(format!("no variant of `{union_name}` has type `{shown}`"))
+                    .with_highlights([Highlight::primary(
+                        target,
+                        format!("not the type of a variant of `{union_name}`"),
+                    )]),
+            ),
+
+            [first, ..] => self.error(
+                Diagnostic::error()
+                    .with_message(format!(
+                        "more than one variant of `{union_name}` has type `{shown}`"
+                    ))
+                    .with_highlights([Highlight::primary(
+                        target,
+                        format!("{} variants have this type", matching.len()),
+                    )])
+                    .with_notes([format!(
+                        "help: test for a variant by name, i.e. `{} is {union_name}::{}`",
+                        is_expr.value.matching_source(),
+                        first.name
+                    )]),
+            ),
+        }
+
+        None
+    }
+
+    /// Check an `is` test of an enum value, which must test for one of the enum's variants.
+    fn check_enum_test(&mut self, is_expr: &IsExpr, enumeration: DefId) {
+        let variant = match (&is_expr.target, self.variant_path(&is_expr.target)) {
+            // Unresolved names have already been reported.
+            (_, Some((None, _))) => return,
+
+            (Type::Named(named), None) if named.generic_tys.is_empty() => {
+                match self.res.resolved(&named.name.full_path) {
+                    Some(variant) => Some(variant),
+                    None => return,
+                }
+            }
+
+            // Anything else (like a union's variant) isn't a variant of this enum.
+            _ => None,
+        };
+
+        if variant.is_some_and(|variant| self.res.enum_of(variant) == Some(enumeration)) {
+            return;
+        }
+
+        let enum_name = &self.res.def(enumeration).name;
+        let mut notes = Vec::new();
+
+        if let Some(first) = self.res.variants(enumeration).first() {
+            let first = &self.res.def(*first).name;
+            notes.push(format!("help: test for a variant by name, i.e. `{enum_name}::{first}`"));
+        }
+
+        self.error(
+            Diagnostic::error()
+                .with_message(format!("expected a variant of `{enum_name}`"))
+                .with_highlights([
+                    Highlight::primary(
+                        is_expr.target.matching_source().clone(),
+                        format!("not a variant of `{enum_name}`"),
+                    ),
+                    Highlight::secondary(
+                        is_expr.value.matching_source().clone(),
+                        format!("this is a `{enum_name}`"),
+                    ),
+                ])
+                .with_notes(notes),
+        );
+    }
+
+    /// Compute the type of a record or union literal, checking its fields.
+    fn record_ty(&mut self, record: &RecordExpr, expected: Option<&Ty>) -> Ty {
+        let def = self.res.resolved(&record.name.full_path);
+        let adt = def.and_then(|def| self.adts.get(&def).cloned());
+
+        let (Some(def), Some(adt)) = (def, adt) else {
+            if let Some(def) = def {
+                let definition = self.res.def(def);
+
+                self.error(
+                    Diagnostic::error()
+                        .with_message(format!(
+                            "expected record or union, found {} `{}`",
+                            definition.kind.describe(),
+                            definition.name
+                        ))
+                        .with_highlights([Highlight::primary(
+                            record.name.full_path.clone(),
+                            "not a record or union",
+                        )]),
+                );
+            }
+
+            for field in &record.fields {
+                self.expr_ty(&field.value, None);
+            }
+
+            return Ty::Error;
+        };
+
+        let type_name = self.res.def(def).name.clone();
+        let substitution = self.instantiate(&adt.generics, def, &record.name.full_path);
+
+        let ty = Ty::Named {
+            def,
+            args: adt
+                .generics
+                .iter()
+                .map(|generic| substitution[generic].clone())
+                .collect(),
+        };
+
+        // Use the expected type (if it's this type) to infer generic arguments that the fields don't decide. If it
+        // isn't this type, the mismatch is reported by whatever expected it.
+        if let Some(expected) = expected
+            && let Ty::Named {
+                def: expected_def, ..
+            } = self.table.shallow_resolve(expected)
+            && expected_def == def
+        {
+            self.table.unify(&ty, expected);
+        }
+
+        let mut given: HashMap<&str, &Fragment> = HashMap::new();
+
+        for init in &record.fields {
+            let name = init.name.fragment.as_str();
+
+            let Some(field) = adt.fields.iter().find(|field| field.name == name) else {
+                let candidates: Vec<&str> = adt
+                    .fields
+                    .iter()
+                    .map(|field| field.name.as_str())
+                    .filter(|field| !given.contains_key(field))
+                    .collect();
+
+                let message =
+                    format!("{} `{type_name}` has no field named `{name}`", adt.describe());
+                self.unknown_field(message, &init.name.fragment, &candidates);
+                self.expr_ty(&init.value, None);
+                continue;
+            };
+
+            if let Some(previous) = given.insert(name, &init.name.fragment) {
+                self.error(
+                    Diagnostic::error()
+                        .with_message(format!("field `{name}` is given more than once"))
+                        .with_highlights([
+                            Highlight::primary(init.name.fragment.clone(), "given again here"),
+                            Highlight::secondary(previous.clone(), "first given here"),
+                        ]),
+                );
+            }
+
+            let field_ty = field.ty.substitute(&substitution);
+            let reason = Reason::new(&field.ty_fragment, "because of this field's type");
+            self.check_expr(&init.value, &field_ty, Some(reason));
+        }
+
+        if adt.is_union {
+            if record.fields.len() != 1 {
+                self.error(
+                    Diagnostic::error()
+                        .with_message("a union literal must give a value for exactly one variant")
+                        .with_highlights([Highlight::primary(
+                            record.matching_source.clone(),
+                            format!("{} variants given", record.fields.len()),
+                        )])
+                        .with_notes(["a union holds exactly one of its variants at a time"]),
+                );
+            }
+        } else {
+            let missing: Vec<String> = adt
+                .fields
+                .iter()
+                .filter(|field| !given.contains_key(field.name.as_str()))
+                .map(|field| format!("`{}`", field.name))
+                .collect();
+
+            if !missing.is_empty() {
+                self.error(
+                    Diagnostic::error()
+                        .with_message(format!(
+                            "missing field{} {} in `{type_name}`",
+                            plural(missing.len()),
+                            missing.join(", ")
+                        ))
+                        .with_highlights([Highlight::primary(
+                            record.name.full_path.clone(),
+                            "every field of a record must be given a value",
+                        )]),
+                );
+            }
+        }
+
+        ty
+    }
+
+    /// Compute the type of an `if` expression, checking that its branches have the same type.
+    fn if_ty(&mut self, if_expr: &IfExpr, expected: Option<&Ty>) -> Ty {
+        self.check_expr(&if_expr.condition, &Ty::BOOL, None);
+
+        // `is` tests in the condition narrow bindings in the first branch.
+        let depth = self.narrow(&if_expr.condition);
+
+        let Some(else_branch) = &if_expr.else_branch else {
+            let reason =
+                Reason::new(&if_expr.matching_source, "because this `if` has no `else` branch");
+            self.check_block(&if_expr.then_block, &Ty::Void, Some(reason));
+            self.narrowed.truncate(depth);
+            return Ty::Void;
+        };
+
+        let then_ty = self.block_ty(&if_expr.then_block, expected);
+        self.narrowed.truncate(depth);
+
+        let reason = Reason::new(
+            &blame_block(&if_expr.then_block),
+            "because of the type of the first branch",
+        );
+        self.check_expr(else_branch, &then_ty, Some(reason));
+        then_ty
+    }
+
+    /// Compute the type of a `match` expression. Every pattern must match values of the scrutinee's type, every
+    /// guard must be a `bool`, and every arm must produce the same type as the first.
+    fn match_ty(&mut self, match_expr: &MatchExpr, expected: Option<&Ty>) -> Ty {
+        let scrutinee = self.expr_ty(&match_expr.scrutinee, None);
+        let pattern_reason = Reason::new(
+            match_expr.scrutinee.matching_source(),
+            "because of the type of the matched value",
+        );
+
+        let mut first: Option<(Ty, Fragment)> = None;
+
+        for arm in &match_expr.arms {
+            self.check_pattern(&arm.pattern, &scrutinee, &pattern_reason);
+
+            let depth = match &arm.guard {
+                Some(guard) => {
+                    self.check_expr(guard, &Ty::BOOL, None);
+                    self.narrow(guard)
+                }
+
+                None => self.narrowed.len(),
+            };
+
+            match &first {
+                None => {
+                    let ty = self.expr_ty(&arm.body, expected);
+                    first = Some((ty, blame_expr(&arm.body)));
+                }
+
+                Some((ty, blame)) => {
+                    let (ty, reason) =
+                        (ty.clone(), Reason::new(blame, "because of the type of the first arm"));
+                    self.check_expr(&arm.body, &ty, Some(reason));
+                }
+            }
+
+            self.narrowed.truncate(depth);
+        }
+
+        first.map_or(Ty::Void, |(ty, _)| ty)
+    }
+
+    /// Check that a pattern matches values of the expected type, recording the types of the names it binds.
+    fn check_pattern(&mut self, pattern: &Pattern, expected: &Ty, reason: &Reason) {
+        let found = match pattern {
+            Pattern::Wildcard(_) => expected.clone(),
+            Pattern::Boolean(_) => Ty::BOOL,
+
+            Pattern::Binding(name) => {
+                self.bind(&name.fragment, expected.clone());
+                expected.clone()
+            }
+
+            Pattern::Integer(_) | Pattern::Range(_) => self.table.new_integer_var(VarOrigin {
+                fragment: pattern.matching_source().clone(),
+                description: "the type of this integer pattern".to_owned(),
+            }),
+
+            Pattern::Variant(path) => self.variant_pattern_ty(&path.full_path),
+
+            // Records unify with the expected type themselves, so that it decides their generic arguments before
+            // their fields are checked.
+            Pattern::Record(record) => self.record_pattern_ty(record, expected, reason),
+
+            Pattern::Or(or) => {
+                for alternative in &or.alternatives {
+                    self.check_pattern(alternative, expected, reason);
+                }
+
+                expected.clone()
+            }
+        };
+
+        if !self.table.unify(&found, expected) {
+            let blame = pattern.matching_source().clone();
+            self.mismatch(blame, expected, &found, Some(reason.clone()));
+        }
+
+        (self.results.pattern_types).insert(pattern.matching_source().clone(), found);
+    }
+
+    /// Get the type of an enum variant pattern.
+    fn variant_pattern_ty(&mut self, path: &Fragment) -> Ty {
+        // Unresolved names have already been reported.
+        let Some(def) = self.res.resolved(path) else {
+            return Ty::Error;
+        };
+
+        if let Some(enumeration) = self.res.enum_of(def) {
+            return Ty::Named {
+                def: enumeration,
+                args: Vec::new(),
+            };
+        }
+
+        let definition = self.res.def(def);
+
+        self.error(
+            Diagnostic::error()
+                .with_message(format!(
+                    "expected enum variant, found {} `{}`",
+                    definition.kind.describe(),
+                    definition.name
+                ))
+                .with_highlights([Highlight::primary(path.clone(), "not an enum variant")]),
+        );
+
+        Ty::Error
+    }
+
+    /// Get the type of a record or union pattern, checking the patterns for its fields.
+    fn record_pattern_ty(&mut self, record: &RecordPattern, expected: &Ty, reason: &Reason) -> Ty {
+        let def = self.res.resolved(&record.name.full_path);
+        let adt = def.and_then(|def| self.adts.get(&def).cloned());
+
+        let (Some(def), Some(adt)) = (def, adt) else {
+            if let Some(def) = def {
+                let definition = self.res.def(def);
+
+                self.error(
+                    Diagnostic::error()
+                        .with_message(format!(
+                            "expected record or union, found {} `{}`",
+                            definition.kind.describe(),
+                            definition.name
+                        ))
+                        .with_highlights([Highlight::primary(
+                            record.name.full_path.clone(),
+                            "not a record or union",
+                        )]),
+                );
+            }
+
+            for field in &record.fields {
+                self.check_pattern(&field.pattern, &Ty::Error, reason);
+            }
+
+            return Ty::Error;
+        };
+
+        let type_name = self.res.def(def).name.clone();
+        let substitution = self.instantiate(&adt.generics, def, &record.name.full_path);
+
+        let ty = Ty::Named {
+            def,
+            args: adt
+                .generics
+                .iter()
+                .map(|generic| substitution[generic].clone())
+                .collect(),
+        };
+
+        // A mismatch here is reported by the caller.
+        self.table.unify(&ty, expected);
+
+        let mut given: HashMap<&str, &Fragment> = HashMap::new();
+
+        for field_pattern in &record.fields {
+            let name = field_pattern.name.fragment.as_str();
+
+            let Some(field) = adt.fields.iter().find(|field| field.name == name) else {
+                let candidates: Vec<&str> = adt
+                    .fields
+                    .iter()
+                    .map(|field| field.name.as_str())
+                    .filter(|field| !given.contains_key(field))
+                    .collect();
+
+                let message =
+                    format!("{} `{type_name}` has no field named `{name}`", adt.describe());
+                self.unknown_field(message, &field_pattern.name.fragment, &candidates);
+                self.check_pattern(&field_pattern.pattern, &Ty::Error, reason);
+                continue;
+            };
+
+            if let Some(previous) = given.insert(name, &field_pattern.name.fragment) {
+                self.error(
+                    Diagnostic::error()
+                        .with_message(format!("field `{name}` is matched more than once"))
+                        .with_highlights([
+                            Highlight::primary(
+                                field_pattern.name.fragment.clone(),
+                                "matched again here",
+                            ),
+                            Highlight::secondary(previous.clone(), "first matched here"),
+                        ]),
+                );
+            }
+
+            let field_ty = field.ty.substitute(&substitution);
+            let reason = Reason::new(&field.ty_fragment, "because of this field's type");
+            self.check_pattern(&field_pattern.pattern, &field_ty, &reason);
+        }
+
+        // Fields left out of a record pattern match anything, but a union only holds one variant to match.
+        if adt.is_union && record.fields.len() != 1 {
+            self.error(
+                Diagnostic::error()
+                    .with_message("a union pattern must match exactly one variant")
+                    .with_highlights([Highlight::primary(
+                        record.matching_source.clone(),
+                        format!("{} variants given", record.fields.len()),
+                    )])
+                    .with_notes(["a union holds exactly one of its variants at a time"]),
+            );
+        }
+
+        ty
+    }
+
+    /// Compute the type of a closure. Parameters without type annotations get their types from the expected type
+    /// if there is one, and are inferred from how they're used otherwise.
+    fn closure_ty(&mut self, closure: &ClosureExpr, expected: Option<&Ty>) -> Ty {
+        let (expected_params, expected_ret) =
+            match expected.map(|ty| self.table.shallow_resolve(ty)) {
+                Some(Ty::Function { params, ret }) if params.len() == closure.params.len() => {
+                    (Some(params), Some(*ret))
+                }
+
+                _ => (None, None),
+            };
+
+        let mut params = Vec::new();
+
+        for (index, param) in closure.params.iter().enumerate() {
+            let context = expected_params.as_ref().map(|params| params[index].clone());
+
+            let ty = match (&param.ty, context) {
+                (Some(annotation), _) => self.lower_ty(annotation),
+                (None, Some(context)) => context,
+                (None, None) => self.table.new_var(VarOrigin {
+                    fragment: param.name.fragment.clone(),
+                    description: format!("the type of `{}`", param.name.fragment),
+                }),
+            };
+
+            self.bind(&param.name.fragment, ty.clone());
+            params.push(ty);
+        }
+
+        let (ret, reason) = match (&closure.return_ty, expected_ret) {
+            (Some(annotation), _) => (
+                self.lower_ty(annotation),
+                Some(Reason::new(annotation.matching_source(), "because of this return type")),
+            ),
+
+            (None, Some(context)) => (context, None),
+
+            (None, None) => {
+                let origin = VarOrigin {
+                    fragment: closure.matching_source.clone(),
+                    description: "the return type of this closure".to_owned(),
+                };
+
+                (self.table.new_var(origin), None)
+            }
+        };
+
+        self.check_block(&closure.body, &ret, reason);
+
+        Ty::Function {
+            params,
+            ret: Box::new(ret),
+        }
+    }
+}
+
+/// Get the fragment to highlight when an expression has the wrong type -- for blocks, this is the expression that
+/// produces the block's value.
+fn blame_expr(expr: &Expr) -> Fragment {
+    match expr {
+        Expr::Block(block) => blame_block(block),
+        other => other.matching_source().clone(),
+    }
+}
+
+/// Get the fragment to highlight when a block has the wrong type.
+fn blame_block(block: &Block) -> Fragment {
+    match &block.tail {
+        Some(tail) => blame_expr(tail),
+        None => block.matching_source.clone(),
+    }
+}
+
+/// Get the suffix to pluralize a noun for a count.
+const fn plural(count: usize) -> &'static str {
+    match count {
+        1 => "",
+        _ => "s",
+    }
+}
@@ -0,0 +1,1182 @@
+//! Traits and `impl` blocks -- lowering their headers, checking that implementations are coherent and complete,
+//! finding the method that a method call refers to, and checking trait bounds once inference has finished.
+//!
+//! Methods are looked up by the type of the receiver (with any references peeled off): a method of an inherent
+//! `impl` block for the type is used first, and otherwise a method of a trait that the type implements (which may be
+//! a default method of the trait), or of a trait that the type is bound by if it's a generic parameter. Impls are
+//! matched against types structurally, with their generic parameters matching anything, so an `impl` in any module
+//! applies to every use of its type.
+
+use super::{Checker, Item, PlaceAccess, Reason, plural};
+use crate::{
+    analysis::{
+        resolve::{DefId, DefKind},
+        typeck::{MethodCall, Receiver, TraitImpl, ty::Ty},
+    },
+    ast::{
+        decl::{
+            func::FuncDecl,
+            generic::{GenericParam, WherePredicate},
+            implementation::ImplDecl,
+            trait_decl::TraitDecl,
+        },
+        expr::{CallExpr, FieldExpr},
+        path::Path,
+    },
+    reporting::{Diagnostic, Highlight},
+    source_tracking::fragment::Fragment,
+};
+use std::{collections::HashMap, rc::Rc};
+
+/// How many `impl` blocks deep to look for an implementation of a trait, before assuming there isn't one. This
+/// stops at impls whose bounds require themselves (i.e. `impl<T> Shape for T where Box<T>: Shape`).
+const MAX_IMPL_DEPTH: usize = 32;
+
+/// The lowered declaration of a trait.
+pub(super) struct Trait<'m> {
+    /// The declaration of the trait.
+    decl: &'m TraitDecl,
+    /// The `Self` type of the trait, which stands for the implementing type in its methods.
+    self_ty: DefId,
+    /// The methods of the trait, by name.
+    methods: HashMap<String, DefId>,
+}
+
+/// The lowered header of an `impl` block.
+pub(super) struct Impl<'m> {
+    /// The definition of the `impl` block.
+    def: DefId,
+    /// The declaration of the `impl` block.
+    decl: &'m ImplDecl,
+    /// The generic parameters of the `impl` block.
+    generics: Vec<DefId>,
+    /// The trait being implemented ([None] for inherent impls).
+    trait_def: Option<DefId>,
+    /// The type the methods are implemented for, in terms of the generic parameters.
+    self_ty: Ty,
+    /// The trait bounds on the generic parameters (and the `where` clause) of the `impl` block.
+    bounds: Vec<Bound>,
+    /// The methods defined by the `impl` block, by name.
+    methods: HashMap<String, DefId>,
+}
+
+impl Impl<'_> {
+    /// Get the header of the `impl` block, i.e. `impl<T> Shape for Box<T>`, for use in diagnostics.
+    fn header(&self) -> &str {
+        self.decl.header.as_str()
+    }
+}
+
+/// A requirement that a type implements a trait, from a bound on a generic parameter or a `where` clause.
+#[derive(Clone)]
+pub(super) struct Bound {
+    /// The type that's bound, in terms of the generic parameters of the item with the bound.
+    ty: Ty,
+    /// The trait that the type has to implement.
+    trait_def: DefId,
+    /// The path to the trait in the bound.
+    fragment: Fragment,
+}
+
+/// A bound that a call requires, which is checked once the types in the function being checked are inferred.
+pub(super) struct Obligation {
+    /// The type that has to implement the trait.
+    ty: Ty,
+    /// The trait that the type has to implement.
+    trait_def: DefId,
+    /// The path to the trait in the bound that requires it.
+    bound: Fragment,
+    /// The function, method, or `impl` block that declares the bound.
+    item: DefId,
+    /// The callee of the call that requires the bound.
+    site: Fragment,
+}
+
+/// A method that a method call could refer to.
+#[derive(Clone)]
+enum Candidate<'m> {
+    /// A method defined in an `impl` block.
+    Impl(Rc<Impl<'m>>, DefId),
+    /// A default method of a trait, for an implementation that doesn't define it.
+    Default(Rc<Impl<'m>>, DefId),
+    /// A method of a trait that a generic parameter is bound by.
+    Bound(DefId, DefId),
+}
+
+impl<'m> Checker<'m, '_> {
+    /// Lower a trait declaration and the signatures of its methods.
+    pub(super) fn lower_trait(&mut self, decl: &'m TraitDecl) {
+        let Some(def) = self.res.declared(&decl.name.fragment) else {
+            return;
+        };
+
+        let self_ty = self.res.self_type(def).expect("traits have a `Self` type");
+
+        // `Self` is a generic parameter of the trait's methods that's bound by the trait itself.
+        self.self_tys.insert(self_ty, Ty::Param(self_ty));
+        self.param_bounds.insert(self_ty, vec![def]);
+
+        let methods = self.lower_methods(&decl.methods, &[], &Ty::Param(self_ty));
+
+        for (name, method) in &methods {
+            (self.results.trait_methods).insert(*method, (def, name.clone()));
+        }
+
+        self.traits.insert(
+            def,
+            Rc::new(Trait {
+                decl,
+                self_ty,
+                methods,
+            }),
+        );
+    }
+
+    /// Lower the header of an `impl` block and the signatures of its methods.
+    pub(super) fn lower_impl(&mut self, decl: &'m ImplDecl) {
+        let Some(def) = self.res.declared(&decl.header) else {
+            return;
+        };
+
+        let generics = self.generic_defs(&decl.generic_params);
+        let trait_def = decl.trait_path.as_ref().map(|path| self.lower_bound(path));
+        let self_ty = self.lower_ty(&decl.self_ty);
+        let self_def = self
+            .res
+            .self_type(def)
+            .expect("`impl` blocks have a `Self` type");
+        self.self_tys.insert(self_def, self_ty.clone());
+
+        let bounds = self.lower_bounds(&decl.generic_params, &decl.where_clause);
+        self.bounds.insert(def, bounds.clone());
+
+        let methods = self.lower_methods(&decl.methods, &generics, &self_ty);
+
+        // Implementations of something that isn't a trait (which has been reported) don't implement anything.
+        let trait_def = match trait_def {
+            Some(None) => return,
+            Some(Some(trait_def)) => Some(trait_def),
+            None => None,
+        };
+
+        if let Some(trait_def) = trait_def {
+            self.results.trait_impls.push(TraitImpl {
+                trait_def,
+                generics: generics.clone(),
+                self_ty: self_ty.clone(),
+                methods: methods.clone(),
+            });
+        }
+
+        self.impls.push(Rc::new(Impl {
+            def,
+            decl,
+            generics,
+            trait_def,
+            self_ty,
+            bounds,
+            methods,
+        }));
+    }
+
+    /// Lower the signatures of the methods of a trait or `impl` block, checking their `self` parameters. Methods of
+    /// `impl` blocks are generic over the block's generic parameters too.
+    fn lower_methods(
+        &mut self,
+        methods: &'m [FuncDecl],
+        outer_generics: &[DefId],
+        self_ty: &Ty,
+    ) -> HashMap<String, DefId> {
+        let mut lowered = HashMap::new();
+
+        for method in methods {
+            let Some(def) = self.res.declared(&method.name.fragment) else {
+                continue;
+            };
+
+            self.lower_signature(def, method, outer_generics);
+            self.check_self_param(method, self_ty);
+
+            // Methods defined more than once have already been reported.
+            (lowered.entry(method.name.fragment.as_str().to_owned())).or_insert(def);
+        }
+
+        lowered
+    }
+
+    /// Check that a method takes `self: Self`, `self: @Self`, or `self: @mut Self` as its first parameter.
+    fn check_self_param(&mut self, method: &FuncDecl, self_ty: &Ty) {
+        let name = method.name.fragment.as_str();
+
+        let Some(param) =
+            (method.params.first()).filter(|param| param.name.fragment.as_str() == "self")
+        else {
+            self.error(
+                Diagnostic::error()
+                    .with_message(format!("method `{name}` has no `self` parameter"))
+                    .with_highlights([Highlight::primary(
+                        method.name.fragment.clone(),
+                        "methods take `self` as their first parameter",
+                    )])
+                    .with_notes(["help: add a `self: @Self` parameter".to_owned()]),
+            );
+
+            return;
+        };
+
+        let Some(ty) = (self.res.declared(&param.name.fragment))
+            .and_then(|def| self.results.def_types.get(&def))
+        else {
+            return;
+        };
+
+        let is_valid = match ty {
+            Ty::Reference { target, .. } => **target == *self_ty || target.references_error(),
+            ty => *ty == *self_ty || ty.references_error() || self_ty.references_error(),
+        };
+
+        if !is_valid {
+            let ty = ty.display(self.res).to_string();
+
+            self.error(
+                Diagnostic::error()
+                    .with_message(format!("invalid `self` parameter type `{ty}`"))
+                    .with_highlights([Highlight::primary(
+                        param.ty.matching_source().clone(),
+                        "the type of `self` must be `Self`, `@Self`, or `@mut Self`",
+                    )]),
+            );
+        }
+    }
+
+    /// Lower the trait bounds on generic parameters and in a `where` clause, recording the bounds on each generic
+    /// parameter so they can be used inside the item that declares them.
+    pub(super) fn lower_bounds(
+        &mut self,
+        params: &[GenericParam],
+        predicates: &[WherePredicate],
+    ) -> Vec<Bound> {
+        let mut bounds = Vec::new();
+
+        let params = (params.iter()).filter_map(|param| {
+            Some((Ty::Param(self.res.declared(&param.name.fragment)?), &param.bounds))
+        });
+        let params: Vec<(Ty, &Vec<Path>)> = params.collect();
+
+        let predicates: Vec<(Ty, &Vec<Path>)> = (predicates.iter())
+            .map(|predicate| (self.lower_ty(&predicate.ty), &predicate.bounds))
+            .collect();
+
+        for (ty, paths) in params.into_iter().chain(predicates) {
+            for path in paths {
+                let Some(trait_def) = self.lower_bound(path) else {
+                    continue;
+                };
+
+                if let Ty::Param(param) = ty {
+                    self.param_bounds.entry(param).or_default().push(trait_def);
+                }
+
+                bounds.push(Bound {
+                    ty: ty.clone(),
+                    trait_def,
+                    fragment: path.full_path.clone(),
+                });
+            }
+        }
+
+        bounds
+    }
+
+    /// Lower the path to a trait in a bound (or in the header of an `impl` block), reporting an error if it names
+    /// something else.
+    fn lower_bound(&mut self, path: &Path) -> Option<DefId> {
+        // Unresolved names have already been reported.
+        let def = self.res.resolved(&path.full_path)?;
+        let definition = self.res.def(def);
+
+        if definition.kind == DefKind::Trait {
+            return Some(def);
+        }
+
+        self.error(
+            Diagnostic::error()
+                .with_message(format!(
+                    "expected trait, found {} `{}`",
+                    definition.kind.describe(),
+                    definition.name
+                ))
+                .with_highlights([Highlight::primary(path.full_path.clone(), "not a trait")]),
+        );
+
+        None
+    }
+
+    /// Check every `impl` block: implementations of the same trait can't overlap, inherent impls for overlapping
+    /// types can't define methods with the same name, and trait impls must implement the trait's methods.
+    pub(super) fn check_impls(&mut self) {
+        let impls = self.impls.clone();
+
+        for (index, implementation) in impls.iter().enumerate() {
+            if let Some(trait_def) = implementation.trait_def {
+                self.check_trait_methods(implementation, trait_def);
+            }
+
+            if implementation.self_ty.references_error() {
+                continue;
+            }
+
+            for earlier in &impls[..index] {
+                if earlier.trait_def != implementation.trait_def
+                    || earlier.self_ty.references_error()
+                {
+                    continue;
+                }
+
+                let generics: Vec<DefId> = (earlier.generics.iter())
+                    .chain(&implementation.generics)
+                    .copied()
+                    .collect();
+
+                if !overlap(
+                    &earlier.self_ty,
+                    &implementation.self_ty,
+                    &generics,
+                    &mut HashMap::new(),
+                ) {
+                    continue;
+                }
+
+                match implementation.trait_def {
+                    Some(trait_def) => {
+                        self.report_conflict(earlier, implementation, trait_def);
+                        break;
+                    }
+
+                    None => self.report_duplicate_methods(earlier, implementation),
+                }
+            }
+        }
+    }
+
+    /// Report an `impl` block of a trait that overlaps with an earlier one.
+    fn report_conflict(&mut self, earlier: &Impl, implementation: &Impl, trait_def: DefId) {
+        let trait_name = &self.res.def(trait_def).name;
+        let ty = implementation.self_ty.display(self.res).to_string();
+
+        self.error(
+            Diagnostic::error()
+                .with_message(format!(
+                    "conflicting implementations of trait `{trait_name}` for type `{ty}`"
+                ))
+                .with_highlights([
+                    Highlight::primary(
+                        implementation.decl.header.clone(),
+                        format!("conflicting implementation for `{ty}`"),
+                    ),
+                    Highlight::secondary(earlier.decl.header.clone(), "first implementation here"),
+                ])
+                .with_notes([format!(
+                    "a type can only implement `{trait_name}` once, so these can't both apply"
+                )]),
+        );
+    }
+
+    /// Report the methods of an inherent `impl` block that are also defined by an earlier one for an overlapping
+    /// type.
+    fn report_duplicate_methods(&mut self, earlier: &Impl, implementation: &Impl) {
+        for method in &implementation.decl.methods {
+            let name = method.name.fragment.as_str();
+
+            let Some(previous) = (earlier.methods.get(name))
+                .and_then(|def| self.res.def(*def).name_fragment.clone())
+            else {
+                continue;
+            };
+
+            self.error(
+                Diagnostic::error()
+                    .with_message(format!("duplicate definitions with name `{name}`"))
+                    .with_highlights([
+                        Highlight::primary(
+                            method.name.fragment.clone(),
+                            format!("duplicate definition of `{name}`"),
+                        ),
+                        Highlight::secondary(
+                            previous,
+                            format!("other definition of `{name}` here"),
+                        ),
+                    ]),
+            );
+        }
+    }
+
+    /// Check that an `impl` block of a trait defines every method of the trait without a default, doesn't define
+    /// anything else, and gives each method the type the trait declares.
+    fn check_trait_methods(&mut self, implementation: &Impl, trait_def: DefId) {
+        let Some(trait_info) = self.traits.get(&trait_def).cloned() else {
+            return;
+        };
+
+        let trait_name = &self.res.def(trait_def).name;
+
+        let missing: Vec<&FuncDecl> = (trait_info.decl.methods.iter())
+            .filter(|method| {
+                method.body.is_none()
+                    && !(implementation.methods).contains_key(method.name.fragment.as_str())
+            })
+            .collect();
+
+        if !missing.is_empty() {
+            let names = (missing.iter())
+                .map(|method| format!("`{}`", method.name.fragment.as_str()))
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            let mut highlights = vec![Highlight::primary(
+                implementation.decl.header.clone(),
+                format!("missing {names} in implementation"),
+            )];
+
+            highlights.extend(missing.iter().map(|method| {
+                Highlight::secondary(
+                    method.name.fragment.clone(),
+                    format!("`{}` is declared here", method.name.fragment.as_str()),
+                )
+            }));
+
+            self.error(
+                Diagnostic::error()
+                    .with_message(format!(
+                        "not all methods of trait `{trait_name}` are implemented, missing: {names}"
+                    ))
+                    .with_highlights(highlights)
+                    .with_notes([
+                        "help: implement the missing methods, or give them a default body in the trait".to_owned(),
+                    ]),
+            );
+        }
+
+        for method in &implementation.decl.methods {
+            let name = method.name.fragment.as_str();
+
+            let Some(def) = self.res.declared(&method.name.fragment) else {
+                continue;
+            };
+
+            let Some(trait_method) = trait_info.methods.get(name).copied() else {
+                self.error(
+                    Diagnostic::error()
+                        .with_message(format!(
+                            "method `{name}` is not a member of trait `{trait_name}`"
+                        ))
+                        .with_highlights([Highlight::primary(
+                            method.name.fragment.clone(),
+                            format!("not a member of `{trait_name}`"),
+                        )]),
+                );
+
+                continue;
+            };
+
+            self.check_method_matches(implementation, trait_def, def, trait_method, method);
+        }
+    }
+
+    /// Check that a method of an `impl` block has the type declared by the trait it implements, with `Self` being
+    /// the implementing type.
+    fn check_method_matches(
+        &mut self,
+        implementation: &Impl,
+        trait_def: DefId,
+        def: DefId,
+        trait_method: DefId,
+        method: &FuncDecl,
+    ) {
+        let (Some(found), Some(expected)) = (
+            self.results.def_types.get(&def).cloned(),
+            self.results.def_types.get(&trait_method).cloned(),
+        ) else {
+            return;
+        };
+
+        // The method's own generic parameters follow those of the `impl` block.
+        let own_generics = self.signatures[&def].generics[implementation.generics.len()..].to_vec();
+        let trait_generics = self.signatures[&trait_method].generics.clone();
+
+        let label = match own_generics.len() == trait_generics.len() {
+            true => {
+                let mut substitution: HashMap<DefId, Ty> = (trait_generics.iter().copied())
+                    .zip(own_generics.iter().map(|generic| Ty::Param(*generic)))
+                    .collect();
+
+                substitution
+                    .insert(self.traits[&trait_def].self_ty, implementation.self_ty.clone());
+                let expected = expected.substitute(&substitution);
+
+                if expected == found || expected.references_error() || found.references_error() {
+                    return;
+                }
+
+                format!(
+                    "expected `{}`, found `{}`",
+                    expected.display(self.res),
+                    found.display(self.res)
+                )
+            }
+
+            false => format!(
+                "expected {} generic parameter{}, found {}",
+                trait_generics.len(),
+                plural(trait_generics.len()),
+                own_generics.len()
+            ),
+        };
+
+        let name = method.name.fragment.as_str();
+        let trait_name = &self.res.def(trait_def).name;
+        let mut highlights = vec![Highlight::primary(method.name.fragment.clone(), label)];
+
+        if let Some(declared) = &self.res.def(trait_method).name_fragment {
+            highlights
+                .push(Highlight::secondary(declared.clone(), format!("`{name}` is declared here")));
+        }
+
+        self.error(
+            Diagnostic::error()
+                .with_message(format!(
+                    "method `{name}` has an incompatible type for trait `{trait_name}`"
+                ))
+                .with_highlights(highlights),
+        );
+    }
+
+    /// Compute the type of a method call, i.e. `shape.area()`, given the type of the receiver. Returns [None] if
+    /// there's no method with the name but the receiver has a field with it, which is called instead.
+    pub(super) fn method_call_ty(
+        &mut self,
+        call: &CallExpr,
+        field: &FieldExpr,
+        receiver: &Ty,
+    ) -> Option<Ty> {
+        let name = field.field.fragment.as_str();
+
+        // Methods are looked up through references, like fields.
+        let mut target = self.table.resolve(receiver);
+        let mut reference = None;
+
+        while let Ty::Reference {
+            target: inner,
+            is_mutable,
+        } = target
+        {
+            reference.get_or_insert(is_mutable);
+            target = *inner;
+        }
+
+        if matches!(target, Ty::Error | Ty::Infer(_)) {
+            return None;
+        }
+
+        let candidates = self.lookup_method(&target, name);
+
+        let candidate = match candidates.as_slice() {
+            [candidate] => candidate.clone(),
+            [] if self.has_field(&target, name) => return None,
+
+            candidates => {
+                match candidates.is_empty() {
+                    true => self.report_no_method(field, &target),
+                    false => self.report_ambiguous_method(field, &target, candidates),
+                }
+
+                for arg in &call.args {
+                    self.expr_ty(arg, None);
+                }
+
+                return Some(Ty::Error);
+            }
+        };
+
+        let site = call.callee.matching_source();
+
+        let (method, substitution) = match &candidate {
+            Candidate::Impl(implementation, method) => {
+                let generics = self.signatures[method].generics.clone();
+                let substitution = self.instantiate(&generics, *method, site);
+                let self_ty = implementation.self_ty.substitute(&substitution);
+                self.table.unify(&self_ty, &target);
+                self.require_bounds(implementation.def, &substitution, site);
+                (*method, substitution)
+            }
+
+            Candidate::Default(implementation, method) => {
+                let impl_substitution =
+                    self.instantiate(&implementation.generics, implementation.def, site);
+                let self_ty = implementation.self_ty.substitute(&impl_substitution);
+                self.table.unify(&self_ty, &target);
+                self.require_bounds(implementation.def, &impl_substitution, site);
+
+                let trait_def = implementation
+                    .trait_def
+                    .expect("default methods come from traits");
+                (*method, self.instantiate_trait_method(trait_def, *method, &target, site))
+            }
+
+            Candidate::Bound(trait_def, method) => {
+                (*method, self.instantiate_trait_method(*trait_def, *method, &target, site))
+            }
+        };
+
+        self.require_bounds(method, &substitution, site);
+
+        let Some(Ty::Function { params, ret }) = self.results.def_types.get(&method).cloned()
+        else {
+            return Some(Ty::Error);
+        };
+
+        let params: Vec<Ty> = params
+            .iter()
+            .map(|param| param.substitute(&substitution))
+            .collect();
+        let ret = ret.substitute(&substitution);
+
+        self.results.expr_types.insert(
+            site.clone(),
+            Ty::Function {
+                params: params.clone(),
+                ret: Box::new(ret.clone()),
+            },
+        );
+
+        let decl = match self.items.get(&method) {
+            Some(Item::Method(decl)) => Some(*decl),
+            _ => None,
+        };
+
+        // Methods without a `self` parameter have already been reported.
+        let Some((self_param, params)) = params.split_first() else {
+            for arg in &call.args {
+                self.expr_ty(arg, None);
+            }
+
+            return Some(Ty::Error);
+        };
+
+        let receiver = match (self.table.shallow_resolve(self_param), reference) {
+            (Ty::Reference { is_mutable, .. }, None) => {
+                if is_mutable {
+                    self.check_place(&field.base, PlaceAccess::Borrow);
+                }
+
+                Receiver::Borrow { is_mutable }
+            }
+
+            (
+                Ty::Reference {
+                    is_mutable: true, ..
+                },
+                Some(false),
+            ) => {
+                self.report_shared_receiver(field, receiver, decl);
+                Receiver::Value
+            }
+
+            (Ty::Reference { .. }, Some(_)) => Receiver::Value,
+            (_, Some(_)) => Receiver::Deref,
+            (_, None) => Receiver::Value,
+        };
+
+        if call.args.len() != params.len() {
+            let mut highlights = vec![Highlight::primary(
+                call.matching_source.clone(),
+                format!("expected {} argument{}", params.len(), plural(params.len())),
+            )];
+
+            if let Some(decl) = decl {
+                highlights
+                    .push(Highlight::secondary(decl.name.fragment.clone(), "method defined here"));
+            }
+
+            self.error(
+                Diagnostic::error()
+                    .with_message(format!(
+                        "this method takes {} argument{} but {} argument{} {} supplied",
+                        params.len(),
+                        plural(params.len()),
+                        call.args.len(),
+                        plural(call.args.len()),
+                        if call.args.len() == 1 { "was" } else { "were" }
+                    ))
+                    .with_highlights(highlights),
+            );
+
+            for arg in &call.args {
+                self.expr_ty(arg, None);
+            }
+
+            return Some(ret);
+        }
+
+        for (index, (arg, param)) in call.args.iter().zip(params).enumerate() {
+            let reason = decl.map(|decl| {
+                Reason::new(
+                    decl.params[index + 1].ty.matching_source(),
+                    "because of this parameter's type",
+                )
+            });
+
+            self.check_expr(arg, param, reason);
+        }
+
+        (self.results.method_calls)
+            .insert(call.matching_source.clone(), MethodCall { method, receiver });
+        Some(ret)
+    }
+
+    /// Create the substitution for calling a method of a trait on a type: `Self` is the type, and the method's own
+    /// generic parameters get fresh inference variables.
+    fn instantiate_trait_method(
+        &mut self,
+        trait_def: DefId,
+        method: DefId,
+        self_ty: &Ty,
+        site: &Fragment,
+    ) -> HashMap<DefId, Ty> {
+        let generics = self.signatures[&method].generics.clone();
+        let mut substitution = self.instantiate(&generics, method, site);
+        substitution.insert(self.traits[&trait_def].self_ty, self_ty.clone());
+        substitution
+    }
+
+    /// Find the methods with the given name that a receiver of the given (resolved, non-reference) type could be
+    /// calling. Inherent methods are preferred over methods from traits.
+    fn lookup_method(&self, ty: &Ty, name: &str) -> Vec<Candidate<'m>> {
+        let mut inherent = Vec::new();
+        let mut from_traits = Vec::new();
+
+        for implementation in &self.impls {
+            if !matches(&implementation.self_ty, &implementation.generics, ty, &mut HashMap::new())
+            {
+                continue;
+            }
+
+            let method = implementation.methods.get(name).copied();
+
+            match (implementation.trait_def, method) {
+                (None, Some(method)) => {
+                    inherent.push(Candidate::Impl(implementation.clone(), method))
+                }
+                (None, None) => {}
+                (Some(_), Some(method)) => {
+                    from_traits.push(Candidate::Impl(implementation.clone(), method))
+                }
+
+                (Some(trait_def), None) => {
+                    let default = (self.traits.get(&trait_def))
+                        .and_then(|trait_info| trait_info.methods.get(name));
+
+                    if let Some(method) = default {
+                        from_traits.push(Candidate::Default(implementation.clone(), *method));
+                    }
+                }
+            }
+        }
+
+        if let Ty::Param(param) = ty {
+            for trait_def in self.param_bounds.get(param).into_iter().flatten() {
+                let method = (self.traits.get(trait_def))
+                    .and_then(|trait_info| trait_info.methods.get(name));
+
+                if let Some(method) = method {
+                    from_traits.push(Candidate::Bound(*trait_def, *method));
+                }
+            }
+        }
+
+        match inherent.is_empty() {
+            true => from_traits,
+            false => inherent,
+        }
+    }
+
+    /// Check if a type is a record or union with a field (or variant) with the given name.
+    fn has_field(&self, ty: &Ty, name: &str) -> bool {
+        let Ty::Named { def, .. } = ty else {
+            return false;
+        };
+
+        (self.adts.get(def)).is_some_and(|adt| adt.fields.iter().any(|field| field.name == name))
+    }
+
+    /// Report a method call where no method with the name applies to the receiver's type, listing the impls and
+    /// traits that have a method with the name.
+    fn report_no_method(&mut self, field: &FieldExpr, ty: &Ty) {
+        let name = field.field.fragment.as_str();
+        let shown = self.show(ty);
+        let mut notes = Vec::new();
+
+        for implementation in &self.impls {
+            if implementation.methods.contains_key(name) {
+                notes.push(format!(
+                    "`{}` has a method `{name}`, but it's for `{}`",
+                    implementation.header(),
+                    implementation.self_ty.display(self.res)
+                ));
+            }
+        }
+
+        let mut traits: Vec<(&DefId, &Rc<Trait>)> = self.traits.iter().collect();
+        traits.sort_by_key(|(def, _)| **def);
+
+        for (def, trait_info) in traits {
+            if !trait_info.methods.contains_key(name) {
+                continue;
+            }
+
+            let trait_name = &self.res.def(*def).name;
+
+            notes.push(match ty {
+                Ty::Param(_) => format!(
+                    "help: the trait `{trait_name}` has a method `{name}`; add a bound to use it, i.e. `{shown}: {trait_name}`"
+                ),
+                _ => format!(
+                    "the trait `{trait_name}` has a method `{name}`, but `{shown}` doesn't implement it"
+                ),
+            });
+        }
+
+        self.error(
+            Diagnostic::error()
+                .with_message(format!("no method named `{name}` found for `{shown}`"))
+                .with_highlights([Highlight::primary(
+                    field.field.fragment.clone(),
+                    format!("method not found in `{shown}`"),
+                )])
+                .with_notes(notes),
+        );
+    }
+
+    /// Report a method call where methods with the name from more than one trait apply to the receiver's type.
+    fn report_ambiguous_method(&mut self, field: &FieldExpr, ty: &Ty, candidates: &[Candidate]) {
+        let name = field.field.fragment.as_str();
+        let shown = self.show(ty);
+
+        let notes = candidates.iter().enumerate().map(|(index, candidate)| {
+            let candidate = match candidate {
+                Candidate::Impl(implementation, _) => format!("`{}`", implementation.header()),
+                Candidate::Default(implementation, _) => {
+                    format!("the default method in `{}`", implementation.header())
+                }
+                Candidate::Bound(trait_def, _) => {
+                    format!("the bound `{shown}: {}`", self.res.def(*trait_def).name)
+                }
+            };
+
+            format!("candidate #{}: {candidate}", index + 1)
+        });
+
+        let notes: Vec<String> = notes.collect();
+
+        self.error(
+            Diagnostic::error()
+                .with_message(format!(
+                    "multiple applicable methods named `{name}` found for `{shown}`"
+                ))
+                .with_highlights([Highlight::primary(
+                    field.field.fragment.clone(),
+                    format!("multiple `{name}` methods apply"),
+                )])
+                .with_notes(notes),
+        );
+    }
+
+    /// Report a call of a method that takes `self: @mut Self` through a shared reference.
+    fn report_shared_receiver(
+        &mut self,
+        field: &FieldExpr,
+        receiver: &Ty,
+        decl: Option<&FuncDecl>,
+    ) {
+        let name = field.field.fragment.as_str();
+        let base = field.base.matching_source();
+
+        let mut highlights = vec![Highlight::primary(
+            base.clone(),
+            format!(
+                "this has type `{}`, which doesn't allow changing what it points to",
+                self.show(receiver)
+            ),
+        )];
+
+        if let Some(param) = decl.and_then(|decl| decl.params.first()) {
+            highlights.push(Highlight::secondary(
+                param.ty.matching_source().clone(),
+                format!("`{name}` takes a mutable reference"),
+            ));
+        }
+
+        self.error(
+            Diagnostic::error()
+                .with_message(format!(
+                    "cannot call `{name}` on `{}`, which is behind a shared reference",
+                    base.as_str()
+                ))
+                .with_highlights(highlights)
+                .with_notes(["help: use a mutable reference (`@mut`) instead".to_owned()]),
+        );
+    }
+
+    /// Require the trait bounds declared by a function, method, or `impl` block, with its generic parameters
+    /// substituted as they are at a call.
+    pub(super) fn require_bounds(
+        &mut self,
+        item: DefId,
+        substitution: &HashMap<DefId, Ty>,
+        site: &Fragment,
+    ) {
+        let Some(bounds) = self.bounds.get(&item) else {
+            return;
+        };
+
+        let obligations = bounds.iter().map(|bound| Obligation {
+            ty: bound.ty.substitute(substitution),
+            trait_def: bound.trait_def,
+            bound: bound.fragment.clone(),
+            item,
+            site: site.clone(),
+        });
+
+        let obligations: Vec<Obligation> = obligations.collect();
+        self.obligations.extend(obligations);
+    }
+
+    /// Check the trait bounds required by the calls in the function being checked, now that its types are known.
+    /// Types that still aren't known are reported separately.
+    pub(super) fn check_obligations(&mut self) {
+        for obligation in std::mem::take(&mut self.obligations) {
+            let ty = self.table.resolve(&obligation.ty);
+
+            if !self.table.unresolved_vars(&ty).is_empty()
+                || self.implements(&ty, obligation.trait_def, 0)
+            {
+                continue;
+            }
+
+            self.report_unsatisfied(&obligation, &ty);
+        }
+    }
+
+    /// Check if a (resolved) type implements a trait -- either it's a generic parameter bound by the trait, or an
+    /// `impl` of the trait matches it and the impl's own bounds are satisfied.
+    fn implements(&self, ty: &Ty, trait_def: DefId, depth: usize) -> bool {
+        match ty {
+            Ty::Error => true,
+            _ if depth > MAX_IMPL_DEPTH => false,
+
+            Ty::Param(param)
+                if self
+                    .param_bounds
+                    .get(param)
+                    .is_some_and(|bounds| bounds.contains(&trait_def)) =>
+            {
+                true
+            }
+
+            _ => self
+                .impls
+                .iter()
+                .filter(|implementation| implementation.trait_def == Some(trait_def))
+                .any(|implementation| {
+                    let mut substitution = HashMap::new();
+
+                    matches(
+                        &implementation.self_ty,
+                        &implementation.generics,
+                        ty,
+                        &mut substitution,
+                    ) && implementation.bounds.iter().all(|bound| {
+                        let ty = bound.ty.substitute(&substitution);
+                        self.implements(&ty, bound.trait_def, depth + 1)
+                    })
+                }),
+        }
+    }
+
+    /// Report a trait bound that isn't satisfied, listing the impls of the trait that were considered.
+    fn report_unsatisfied(&mut self, obligation: &Obligation, ty: &Ty) {
+        let trait_name = &self.res.def(obligation.trait_def).name;
+        let shown = self.show(ty);
+
+        let item = self.res.def(obligation.item);
+        let label = match item.kind {
+            DefKind::Impl => "required by this bound on an `impl` block".to_owned(),
+            _ => format!("required by this bound on `{}`", item.name),
+        };
+
+        let mut notes: Vec<String> = (self.impls.iter())
+            .filter(|implementation| implementation.trait_def == Some(obligation.trait_def))
+            .map(|implementation| {
+                let header = implementation.header();
+
+                match matches(
+                    &implementation.self_ty,
+                    &implementation.generics,
+                    ty,
+                    &mut HashMap::new(),
+                ) {
+                    true => format!(
+                        "`{header}` was considered, but its bounds aren't satisfied by `{shown}`"
+                    ),
+                    false => format!(
+                        "`{header}` was considered, but it's for `{}`",
+                        implementation.self_ty.display(self.res)
+                    ),
+                }
+            })
+            .collect();
+
+        if notes.is_empty() {
+            notes.push(format!("no type implements `{trait_name}`"));
+        }
+
+        if let Ty::Param(_) = ty {
+            notes.push(format!("help: add a bound, i.e. `{shown}: {trait_name}`"));
+        }
+
+        self.error(
+            Diagnostic::error()
+                .with_message(format!("the trait bound `{shown}: {trait_name}` is not satisfied"))
+                .with_highlights([
+                    Highlight::primary(
+                        obligation.site.clone(),
+                        format!("the trait `{trait_name}` is not implemented for `{shown}`"),
+                    ),
+                    Highlight::secondary(obligation.bound.clone(), label),
+                ])
+                .with_notes(notes),
+        );
+    }
+}
+
+/// Check if a (resolved) type matches the self type of an `impl` block, where the `impl` block's generic parameters
+/// match any type. The types the generic parameters match are recorded in the substitution.
+///
+/// Types that are still being inferred match anything, since they might become the type in the pattern.
+pub(in crate::analysis::typeck) fn matches(
+    pattern: &Ty,
+    generics: &[DefId],
+    ty: &Ty,
+    substitution: &mut HashMap<DefId, Ty>,
+) -> bool {
+    match (pattern, ty) {
+        (Ty::Param(param), _) if generics.contains(param) => match substitution.get(param) {
+            Some(previous) => *previous == *ty,
+            None => {
+                substitution.insert(*param, ty.clone());
+                true
+            }
+        },
+
+        (_, Ty::Error | Ty::Infer(_)) => true,
+        (Ty::Atomic(variant), Ty::Integer(_)) => variant.is_integer(),
+
+        (
+            Ty::Reference { target, is_mutable },
+            Ty::Reference {
+                target: ty_target,
+                is_mutable: ty_is_mutable,
+            },
+        ) => is_mutable == ty_is_mutable && matches(target, generics, ty_target, substitution),
+
+        (
+            Ty::Named { def, args },
+            Ty::Named {
+                def: ty_def,
+                args: ty_args,
+            },
+        ) => {
+            def == ty_def
+                && (args.iter().zip(ty_args))
+                    .all(|(arg, ty_arg)| matches(arg, generics, ty_arg, substitution))
+        }
+
+        (
+            Ty::Function { params, ret },
+            Ty::Function {
+                params: ty_params,
+                ret: ty_ret,
+            },
+        ) => {
+            params.len() == ty_params.len()
+                && (params.iter().zip(ty_params))
+                    .all(|(param, ty_param)| matches(param, generics, ty_param, substitution))
+                && matches(ret, generics, ty_ret, substitution)
+        }
+
+        (pattern, ty) => pattern == ty,
+    }
+}
+
+/// Check if two self types of `impl` blocks overlap -- if there's a type that both of them match, with the given
+/// generic parameters (of both impls) standing for any type.
+fn overlap(a: &Ty, b: &Ty, generics: &[DefId], substitution: &mut HashMap<DefId, Ty>) -> bool {
+    // Follow what generic parameters have been unified with so far.
+    let walk = |ty: &Ty, substitution: &HashMap<DefId, Ty>| {
+        let mut ty = ty.clone();
+
+        while let Ty::Param(param) = &ty
+            && let Some(bound) = substitution.get(param)
+        {
+            ty = bound.clone();
+        }
+
+        ty
+    };
+
+    let (a, b) = (walk(a, substitution), walk(b, substitution));
+
+    match (&a, &b) {
+        (Ty::Param(x), Ty::Param(y)) if x == y => true,
+
+        (Ty::Param(param), other) | (other, Ty::Param(param)) if generics.contains(param) => {
+            substitution.insert(*param, other.clone());
+            true
+        }
+
+        (
+            Ty::Reference { target, is_mutable },
+            Ty::Reference {
+                target: b_target,
+                is_mutable: b_is_mutable,
+            },
+        ) => is_mutable == b_is_mutable && overlap(target, b_target, generics, substitution),
+
+        (
+            Ty::Named { def, args },
+            Ty::Named {
+                def: b_def,
+                args: b_args,
+            },
+        ) => {
+            def == b_def
+                && (args.iter().zip(b_args))
+                    .all(|(arg, b_arg)| overlap(arg, b_arg, generics, substitution))
+        }
+
+        (
+            Ty::Function { params, ret },
+            Ty::Function {
+                params: b_params,
+                ret: b_ret,
+            },
+        ) => {
+            params.len() == b_params.len()
+                && (params.iter().zip(b_params))
+                    .all(|(param, b_param)| overlap(param, b_param, generics, substitution))
+                && overlap(ret, b_ret, generics, substitution)
+        }
+
+        (a, b) => a == b,
+    }
+}
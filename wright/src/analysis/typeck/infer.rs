@@ -0,0 +1,315 @@
+//! Type inference variables, and the unification table that tracks what they've been inferred to be.
+
+use super::ty::Ty;
+use crate::source_tracking::fragment::Fragment;
+
+/// A placeholder for a type that hasn't been inferred yet. See [Ty::Infer] and [Ty::Integer].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct InferVar(u32);
+
+/// Where an inference variable came from, used to explain types that couldn't be inferred.
+#[derive(Clone, Debug)]
+pub(super) struct VarOrigin {
+    /// The source that introduced the variable, such as a call to a generic function.
+    pub fragment: Fragment,
+
+    /// What the variable stands for, i.e. "the type of `x`" or "the generic parameter `T` of `first`".
+    pub description: String,
+}
+
+/// The state of a single [InferVar].
+#[derive(Debug)]
+struct VarState {
+    /// The type this variable has been unified with, if any.
+    value: Option<Ty>,
+
+    /// Whether this variable is for an integer literal, and can only become an integer type.
+    is_integer: bool,
+
+    /// Where the variable came from.
+    origin: VarOrigin,
+}
+
+/// A table of inference variables, which are unified with types (and each other) as constraints on them are found.
+#[derive(Debug, Default)]
+pub(super) struct InferTable {
+    vars: Vec<VarState>,
+}
+
+impl InferTable {
+    /// Create a new variable that can be unified with any type.
+    pub fn new_var(&mut self, origin: VarOrigin) -> Ty {
+        Ty::Infer(self.push(origin, false))
+    }
+
+    /// Create a new variable for the type of an integer literal, which can only be unified with integer types.
+    pub fn new_integer_var(&mut self, origin: VarOrigin) -> Ty {
+        Ty::Integer(self.push(origin, true))
+    }
+
+    /// Add a new variable to the table.
+    fn push(&mut self, origin: VarOrigin, is_integer: bool) -> InferVar {
+        let var = InferVar(self.vars.len() as u32);
+        self.vars.push(VarState {
+            value: None,
+            is_integer,
+            origin,
+        });
+        var
+    }
+
+    /// Get the number of variables that have been created. Every variable created after this point will be
+    /// returned by [InferTable::vars_since] with this value.
+    pub fn len(&self) -> usize {
+        self.vars.len()
+    }
+
+    /// Get every variable created since the table had the given [InferTable::len].
+    pub fn vars_since(&self, start: usize) -> impl Iterator<Item = InferVar> + use<> {
+        (start as u32..self.vars.len() as u32).map(InferVar)
+    }
+
+    /// Get the origin of a variable.
+    pub fn origin(&self, var: InferVar) -> &VarOrigin {
+        &self.vars[var.0 as usize].origin
+    }
+
+    /// Check if a variable hasn't been bound to anything yet.
+    pub fn is_unbound(&self, var: InferVar) -> bool {
+        self.vars[var.0 as usize].value.is_none()
+    }
+
+    /// Bind every integer variable created since the table had the given [InferTable::len] that is still unbound
+    /// to the given type.
+    pub fn default_integers(&mut self, start: usize, ty: &Ty) {
+        for state in &mut self.vars[start..] {
+            if state.is_integer && state.value.is_none() {
+                state.value = Some(ty.clone());
+            }
+        }
+    }
+
+    /// Set the value of an unbound variable.
+    pub fn bind(&mut self, var: InferVar, ty: Ty) {
+        debug_assert!(self.vars[var.0 as usize].value.is_none());
+        self.vars[var.0 as usize].value = Some(ty);
+    }
+
+    /// Follow variables at the top level of a type until reaching a type that isn't a bound variable.
+    pub fn shallow_resolve(&self, ty: &Ty) -> Ty {
+        let mut ty = ty.clone();
+
+        while let Ty::Infer(var) | Ty::Integer(var) = ty {
+            match &self.vars[var.0 as usize].value {
+                Some(value) => ty = value.clone(),
+                None => break,
+            }
+        }
+
+        ty
+    }
+
+    /// Replace every bound variable in a type with its value. Unbound variables are left in place.
+    pub fn resolve(&self, ty: &Ty) -> Ty {
+        match self.shallow_resolve(ty) {
+            Ty::Reference { target, is_mutable } => {
+                Ty::reference(self.resolve(&target), is_mutable)
+            }
+
+            Ty::Named { def, args } => Ty::Named {
+                def,
+                args: args.iter().map(|arg| self.resolve(arg)).collect(),
+            },
+
+            Ty::Function { params, ret } => Ty::Function {
+                params: params.iter().map(|param| self.resolve(param)).collect(),
+                ret: Box::new(self.resolve(&ret)),
+            },
+
+            other => other,
+        }
+    }
+
+    /// Fully resolve a type once inference is finished, replacing any variables that are still unbound (which
+    /// have been reported as errors) with [Ty::Error].
+    pub fn resolve_final(&self, ty: &Ty) -> Ty {
+        match self.resolve(ty) {
+            Ty::Infer(_) | Ty::Integer(_) => Ty::Error,
+            Ty::Reference { target, is_mutable } => {
+                Ty::reference(self.resolve_final(&target), is_mutable)
+            }
+
+            Ty::Named { def, args } => Ty::Named {
+                def,
+                args: args.iter().map(|arg| self.resolve_final(arg)).collect(),
+            },
+
+            Ty::Function { params, ret } => Ty::Function {
+                params: params
+                    .iter()
+                    .map(|param| self.resolve_final(param))
+                    .collect(),
+                ret: Box::new(self.resolve_final(&ret)),
+            },
+
+            other => other,
+        }
+    }
+
+    /// Get the unbound variables in a type, in the order they appear.
+    pub fn unresolved_vars(&self, ty: &Ty) -> Vec<InferVar> {
+        let mut vars = Vec::new();
+        collect_vars(&self.resolve(ty), &mut vars);
+        vars
+    }
+
+    /// Make two types equal, binding variables in either of them as needed. Returns false if the types can't be
+    /// made equal (some variables may have been bound before the conflict was found).
+    pub fn unify(&mut self, a: &Ty, b: &Ty) -> bool {
+        let (a, b) = (self.shallow_resolve(a), self.shallow_resolve(b));
+
+        match (&a, &b) {
+            // Errors have already been reported, so they unify with everything. Variables unified with an error
+            // become errors themselves, so that they aren't reported as ambiguous later.
+            (Ty::Error, other) | (other, Ty::Error) => {
+                if let Ty::Infer(var) | Ty::Integer(var) = other {
+                    self.bind(*var, Ty::Error);
+                }
+
+                true
+            }
+
+            (Ty::Infer(a_var), Ty::Infer(b_var)) | (Ty::Integer(a_var), Ty::Integer(b_var))
+                if a_var == b_var =>
+            {
+                true
+            }
+
+            // Newer variables point at older ones, so that unknown types are reported where they first came up.
+            (Ty::Infer(a_var), Ty::Infer(b_var)) => {
+                let (older, newer) = (a_var.min(b_var), a_var.max(b_var));
+                self.bind(*newer, Ty::Infer(*older));
+                true
+            }
+
+            (Ty::Infer(var), other) | (other, Ty::Infer(var)) => {
+                if occurs(*var, &self.resolve(other)) {
+                    return false;
+                }
+
+                self.bind(*var, other.clone());
+                true
+            }
+
+            (Ty::Integer(var), other) | (other, Ty::Integer(var)) => match other {
+                Ty::Integer(_) => {
+                    self.bind(*var, other.clone());
+                    true
+                }
+
+                Ty::Atomic(variant) if variant.is_integer() => {
+                    self.bind(*var, other.clone());
+                    true
+                }
+
+                _ => false,
+            },
+
+            (
+                Ty::Reference { target, is_mutable },
+                Ty::Reference {
+                    target: b_target,
+                    is_mutable: b_mutable,
+                },
+            ) => is_mutable == b_mutable && self.unify(target, b_target),
+
+            (
+                Ty::Named { def, args },
+                Ty::Named {
+                    def: b_def,
+                    args: b_args,
+                },
+            ) => {
+                def == b_def
+                    && args.len() == b_args.len()
+                    && args.iter().zip(b_args).all(|(a, b)| self.unify(a, b))
+            }
+
+            (
+                Ty::Function { params, ret },
+                Ty::Function {
+                    params: b_params,
+                    ret: b_ret,
+                },
+            ) => {
+                params.len() == b_params.len()
+                    && params.iter().zip(b_params).all(|(a, b)| self.unify(a, b))
+                    && self.unify(ret, b_ret)
+            }
+
+            _ => a == b,
+        }
+    }
+}
+
+/// Check if a variable appears in a (resolved) type -- binding the variable to that type would make an infinite
+/// type.
+fn occurs(var: InferVar, ty: &Ty) -> bool {
+    let mut vars = Vec::new();
+    collect_vars(ty, &mut vars);
+    vars.contains(&var)
+}
+
+/// Add the variables in a (resolved) type to the list, skipping any that are already in it.
+fn collect_vars(ty: &Ty, vars: &mut Vec<InferVar>) {
+    match ty {
+        Ty::Infer(var) | Ty::Integer(var) if !vars.contains(var) => vars.push(*var),
+        Ty::Reference { target, .. } => collect_vars(target, vars),
+        Ty::Named { args, .. } => args.iter().for_each(|arg| collect_vars(arg, vars)),
+
+        Ty::Function { params, ret } => {
+            params.iter().for_each(|param| collect_vars(param, vars));
+            collect_vars(ret, vars);
+        }
+
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{InferTable, VarOrigin};
+    use crate::{analysis::typeck::ty::Ty, ast::ty::AtomicTyVariant, lexer::Lexer};
+
+    fn origin() -> VarOrigin {
+        VarOrigin {
+            fragment: Lexer::new_test("").remaining,
+            description: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_unify() {
+        let mut table = InferTable::default();
+        let a = table.new_var(origin());
+        let b = table.new_var(origin());
+        let int = table.new_integer_var(origin());
+
+        assert!(table.unify(&Ty::reference(a.clone(), false), &Ty::reference(b.clone(), false)));
+        assert!(!table.unify(&Ty::reference(a.clone(), true), &Ty::reference(b.clone(), false)));
+        assert!(table.unify(&b, &int));
+        assert!(!table.unify(&a, &Ty::BOOL));
+        assert!(table.unify(&a, &Ty::Atomic(AtomicTyVariant::U16)));
+
+        assert_eq!(table.resolve(&b), Ty::Atomic(AtomicTyVariant::U16));
+        assert_eq!(table.resolve(&int), Ty::Atomic(AtomicTyVariant::U16));
+    }
+
+    #[test]
+    fn test_occurs_check() {
+        let mut table = InferTable::default();
+        let a = table.new_var(origin());
+        assert!(!table.unify(&a, &Ty::reference(a.clone(), false)));
+        assert_eq!(table.unresolved_vars(&a).len(), 1);
+    }
+}
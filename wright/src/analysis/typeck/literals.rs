@@ -0,0 +1,200 @@
+//! Checking that integer literals fit in the types they were inferred to have.
+
+use super::{TypeckResults, ty::Ty};
+use crate::{
+    analysis::resolve::module_bodies,
+    ast::{
+        decl::Decl,
+        expr::{Atom, Expr, UnaryOperation},
+        literal::IntegerLiteral,
+        module::Module,
+        pattern::{IntegerPattern, Pattern},
+        ty::AtomicTyVariant,
+    },
+    reporting::{Diagnostic, Highlight},
+    source_tracking::fragment::Fragment,
+};
+use num::BigInt;
+
+/// The integer types to suggest when a literal doesn't fit, from narrowest to widest.
+const UNSIGNED: [AtomicTyVariant; 4] = [
+    AtomicTyVariant::U8,
+    AtomicTyVariant::U16,
+    AtomicTyVariant::U32,
+    AtomicTyVariant::U64,
+];
+
+/// The signed integer types to suggest when a literal doesn't fit, from narrowest to widest.
+const SIGNED: [AtomicTyVariant; 4] = [
+    AtomicTyVariant::I8,
+    AtomicTyVariant::I16,
+    AtomicTyVariant::I32,
+    AtomicTyVariant::I64,
+];
+
+/// Check every integer literal in the given modules against the type it was inferred to have. Negated literals
+/// (i.e. `-128`) are checked as a whole, so that the most negative value of each signed type can be written.
+pub(super) fn check_ranges(modules: &[(Vec<String>, &Module)], results: &mut TypeckResults) {
+    for (_, items) in module_bodies(modules) {
+        for func in items.iter().flat_map(Decl::functions) {
+            if let Some(body) = &func.body {
+                body.for_each_expr(&mut |expr| check_expr(expr, results));
+            }
+        }
+    }
+}
+
+/// Check the integer literals in an expression.
+fn check_expr(expr: &Expr, results: &mut TypeckResults) {
+    match expr {
+        Expr::Atom(Atom::IntegerLiteral(literal)) => {
+            let ty = results.type_of_expr(&literal.fragment).cloned();
+            check_literal(literal, false, &literal.fragment, ty, results);
+        }
+
+        Expr::Unary(unary)
+            if unary.op == UnaryOperation::Negate
+                && let Some(literal) = integer_literal(&unary.operand) =>
+        {
+            let ty = results.type_of_expr(&literal.fragment).cloned();
+
+            // Negating an unsigned value is already an error.
+            if let Some(Ty::Atomic(variant)) = ty
+                && !variant.is_signed_integer()
+            {
+                return;
+            }
+
+            check_literal(literal, true, &unary.matching_source, ty, results);
+        }
+
+        Expr::Match(match_expr) => {
+            for arm in &match_expr.arms {
+                check_pattern(&arm.pattern, results);
+            }
+
+            expr.for_each_child(&mut |child| check_expr(child, results));
+        }
+
+        _ => expr.for_each_child(&mut |child| check_expr(child, results)),
+    }
+}
+
+/// Check the integer literals in a pattern, and that its ranges aren't empty.
+fn check_pattern(pattern: &Pattern, results: &mut TypeckResults) {
+    match pattern {
+        Pattern::Integer(integer) => check_integer_pattern(integer, pattern, results),
+
+        Pattern::Range(range) => {
+            check_integer_pattern(&range.start, pattern, results);
+            check_integer_pattern(&range.end, pattern, results);
+
+            let (start, end) = (range.start.value(), range.end.value());
+
+            let is_empty = match range.is_inclusive {
+                true => start > end,
+                false => start >= end,
+            };
+
+            if is_empty {
+                let mut diagnostic = Diagnostic::error()
+                    .with_message("range pattern matches no values")
+                    .with_highlights([Highlight::primary(
+                        range.matching_source.clone(),
+                        "this range is empty",
+                    )]);
+
+                if !range.is_inclusive && start == end {
+                    diagnostic = diagnostic.with_notes([format!(
+                        "help: use `{start}..={end}` to match only `{start}`"
+                    )]);
+                }
+
+                results.diagnostics.push(diagnostic);
+            }
+        }
+
+        Pattern::Record(record) => {
+            (record.fields.iter()).for_each(|field| check_pattern(&field.pattern, results))
+        }
+
+        Pattern::Or(or) => {
+            (or.alternatives.iter()).for_each(|alternative| check_pattern(alternative, results))
+        }
+
+        Pattern::Wildcard(_) | Pattern::Binding(_) | Pattern::Boolean(_) | Pattern::Variant(_) => {}
+    }
+}
+
+/// Check an integer literal in a pattern (which may be one end of a range pattern) against the pattern's type.
+fn check_integer_pattern(integer: &IntegerPattern, pattern: &Pattern, results: &mut TypeckResults) {
+    let ty = results.type_of_pattern(pattern.matching_source()).cloned();
+    check_literal(&integer.literal, integer.is_negative, &integer.matching_source, ty, results);
+}
+
+/// Get the integer literal an expression consists of, looking through parentheses.
+fn integer_literal(expr: &Expr) -> Option<&IntegerLiteral> {
+    match expr {
+        Expr::Atom(Atom::IntegerLiteral(literal)) => Some(literal),
+        Expr::Parens(parens) => integer_literal(&parens.inner),
+        _ => None,
+    }
+}
+
+/// Check that a literal (negated if `is_negated` is set, covering the `expr` fragment) fits in its inferred type.
+fn check_literal(
+    literal: &IntegerLiteral,
+    is_negated: bool,
+    expr: &Fragment,
+    ty: Option<Ty>,
+    results: &mut TypeckResults,
+) {
+    // Literals with an error type (or that failed to infer) have already been reported.
+    let Some(Ty::Atomic(variant)) = ty else {
+        return;
+    };
+
+    let Some((min, max)) = variant.integer_bounds() else {
+        return;
+    };
+
+    let value = match is_negated {
+        true => -BigInt::from(literal.value.clone()),
+        false => BigInt::from(literal.value.clone()),
+    };
+
+    if BigInt::from(min) <= value && value <= BigInt::from(max) {
+        return;
+    }
+
+    let name = variant.name();
+
+    // Suggest the narrowest type of the same signedness that fits, or a signed type for negative values.
+    let candidates = match variant.is_signed_integer() || value < BigInt::ZERO {
+        true => SIGNED,
+        false => UNSIGNED,
+    };
+
+    let wider = candidates.into_iter().find(|candidate| {
+        let (min, max) = candidate.integer_bounds().unwrap();
+        BigInt::from(min) <= value && value <= BigInt::from(max)
+    });
+
+    let help = match wider {
+        Some(wider) => format!("help: use a wider type, like `{}`", wider.name()),
+        None => "no integer type can hold this value".to_owned(),
+    };
+
+    results.diagnostics.push(
+        Diagnostic::error()
+            .with_message(format!("integer literal is out of range for `{name}`"))
+            .with_highlights([Highlight::primary(
+                expr.clone(),
+                format!("`{value}` doesn't fit in `{name}`"),
+            )])
+            .with_notes([
+                format!("`{name}` can hold values from {min} to {max}"),
+                help,
+            ]),
+    );
+}
@@ -0,0 +1,260 @@
+//! The semantic representation of types used by the type checker.
+
+use super::infer::InferVar;
+use crate::{
+    analysis::resolve::{DefId, Resolution},
+    ast::ty::AtomicTyVariant,
+};
+use std::{collections::HashMap, fmt};
+
+/// A type, after type signatures have been resolved and type aliases expanded.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Ty {
+    /// One of the atomic types, such as `u8` or `bool`.
+    Atomic(AtomicTyVariant),
+
+    /// A reference to a value of another type, i.e. `@u8` or `@mut u8`.
+    Reference {
+        /// The type of the referenced value.
+        target: Box<Ty>,
+        /// Whether the reference is mutable (`@mut`), which allows assigning through it.
+        is_mutable: bool,
+    },
+
+    /// A record, union, enum, or abstract type (`type Name;`), with its generic arguments.
+    Named {
+        /// The definition of the type.
+        def: DefId,
+        /// The generic arguments of the type, one for each of its generic parameters.
+        args: Vec<Ty>,
+    },
+
+    /// A generic type parameter, inside of the item that declares it.
+    Param(DefId),
+
+    /// The type of a function.
+    Function {
+        /// The types of the function's parameters.
+        params: Vec<Ty>,
+        /// The function's return type.
+        ret: Box<Ty>,
+    },
+
+    /// The type of string literals.
+    Str,
+
+    /// The type of expressions that don't produce a value, such as blocks without a tail expression or functions
+    /// without a return type.
+    Void,
+
+    /// A type that is still being inferred, such as the type of a `let` binding without an annotation.
+    Infer(InferVar),
+
+    /// The type of an integer literal that is still being inferred -- this can only become one of the integer
+    /// types, and becomes [DEFAULT_INTEGER] if nothing else decides it.
+    ///
+    /// [DEFAULT_INTEGER]: super::DEFAULT_INTEGER
+    Integer(InferVar),
+
+    /// The type of an expression that already has an error reported for it. This matches every other type, so
+    /// that one mistake doesn't cause a cascade of errors.
+    Error,
+}
+
+impl Ty {
+    /// The `bool` type.
+    pub const BOOL: Ty = Ty::Atomic(AtomicTyVariant::Bool);
+
+    /// Make a reference to a value of the given type.
+    pub fn reference(target: Ty, is_mutable: bool) -> Ty {
+        Ty::Reference {
+            target: Box::new(target),
+            is_mutable,
+        }
+    }
+
+    /// Get the [AtomicTyVariant] of this type if it's atomic.
+    pub const fn atomic(&self) -> Option<AtomicTyVariant> {
+        match self {
+            Ty::Atomic(variant) => Some(*variant),
+            _ => None,
+        }
+    }
+
+    /// Check if this type is (or contains) [Ty::Error].
+    pub fn references_error(&self) -> bool {
+        match self {
+            Ty::Error => true,
+            Ty::Reference { target, .. } => target.references_error(),
+            Ty::Named { args, .. } => args.iter().any(Ty::references_error),
+            Ty::Function { params, ret } => {
+                params.iter().any(Ty::references_error) || ret.references_error()
+            }
+            _ => false,
+        }
+    }
+
+    /// Check if this type mentions any of the given generic type parameters.
+    pub fn mentions_params(&self, generics: &[DefId]) -> bool {
+        match self {
+            Ty::Param(def) => generics.contains(def),
+            Ty::Reference { target, .. } => target.mentions_params(generics),
+            Ty::Named { args, .. } => args.iter().any(|arg| arg.mentions_params(generics)),
+            Ty::Function { params, ret } => {
+                params.iter().any(|param| param.mentions_params(generics))
+                    || ret.mentions_params(generics)
+            }
+            _ => false,
+        }
+    }
+
+    /// Check if a value of this type can be used where `expected` is required. [Ty::Error] is compatible with
+    /// every type.
+    pub fn is_compatible(&self, expected: &Ty) -> bool {
+        match (self, expected) {
+            (Ty::Error, _) | (_, Ty::Error) => true,
+            // Mutable references can be used where shared ones are expected, but not the other way around.
+            (
+                Ty::Reference { target, is_mutable },
+                Ty::Reference {
+                    target: expected_target,
+                    is_mutable: expected_mutable,
+                },
+            ) => (*is_mutable || !expected_mutable) && target.is_compatible(expected_target),
+
+            (
+                Ty::Named { def, args },
+                Ty::Named {
+                    def: expected_def,
+                    args: expected_args,
+                },
+            ) => {
+                def == expected_def
+                    && args.len() == expected_args.len()
+                    && args
+                        .iter()
+                        .zip(expected_args)
+                        .all(|(a, b)| a.is_compatible(b))
+            }
+
+            (
+                Ty::Function { params, ret },
+                Ty::Function {
+                    params: expected_params,
+                    ret: expected_ret,
+                },
+            ) => {
+                params.len() == expected_params.len()
+                    && params
+                        .iter()
+                        .zip(expected_params)
+                        .all(|(a, b)| a.is_compatible(b))
+                    && ret.is_compatible(expected_ret)
+            }
+
+            _ => self == expected,
+        }
+    }
+
+    /// Replace generic type parameters in this type using the given substitution. Parameters that aren't in the
+    /// substitution are left as they are.
+    pub fn substitute(&self, substitution: &HashMap<DefId, Ty>) -> Ty {
+        match self {
+            Ty::Param(def) => substitution.get(def).cloned().unwrap_or(Ty::Param(*def)),
+            Ty::Reference { target, is_mutable } => {
+                Ty::reference(target.substitute(substitution), *is_mutable)
+            }
+
+            Ty::Named { def, args } => Ty::Named {
+                def: *def,
+                args: args
+                    .iter()
+                    .map(|arg| arg.substitute(substitution))
+                    .collect(),
+            },
+
+            Ty::Function { params, ret } => Ty::Function {
+                params: params
+                    .iter()
+                    .map(|param| param.substitute(substitution))
+                    .collect(),
+                ret: Box::new(ret.substitute(substitution)),
+            },
+
+            other => other.clone(),
+        }
+    }
+
+    /// Get a value that can be used to display this type, using the [Resolution] to look up names.
+    pub fn display<'a>(&'a self, resolution: &'a Resolution) -> DisplayTy<'a> {
+        DisplayTy {
+            ty: self,
+            resolution,
+        }
+    }
+}
+
+/// A [Ty] that can be displayed as it would be written in source code. See [Ty::display].
+#[derive(Clone, Copy, Debug)]
+pub struct DisplayTy<'a> {
+    ty: &'a Ty,
+    resolution: &'a Resolution,
+}
+
+impl fmt::Display for DisplayTy<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.ty {
+            Ty::Atomic(variant) => f.write_str(variant.name()),
+            Ty::Reference { target, is_mutable } => {
+                let qualifier = if *is_mutable { "mut " } else { "" };
+                write!(f, "@{qualifier}{}", target.display(self.resolution))
+            }
+            Ty::Param(def) => f.write_str(&self.resolution.def(*def).name),
+            Ty::Str => f.write_str("str"),
+            Ty::Void => f.write_str("void"),
+            Ty::Error => f.write_str("{error}"),
+            Ty::Infer(_) => f.write_str("_"),
+            Ty::Integer(_) => f.write_str("{integer}"),
+
+            Ty::Named { def, args } => {
+                f.write_str(&self.resolution.def(*def).name)?;
+
+                if !args.is_empty() {
+                    f.write_str("<")?;
+
+                    for (index, arg) in args.iter().enumerate() {
+                        if index > 0 {
+                            f.write_str(", ")?;
+                        }
+
+                        write!(f, "{}", arg.display(self.resolution))?;
+                    }
+
+                    f.write_str(">")?;
+                }
+
+                Ok(())
+            }
+
+            Ty::Function { params, ret } => {
+                f.write_str("func(")?;
+
+                for (index, param) in params.iter().enumerate() {
+                    if index > 0 {
+                        f.write_str(", ")?;
+                    }
+
+                    write!(f, "{}", param.display(self.resolution))?;
+                }
+
+                f.write_str(")")?;
+
+                if **ret != Ty::Void {
+                    write!(f, " -> {}", ret.display(self.resolution))?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
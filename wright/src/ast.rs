@@ -3,8 +3,12 @@
 //! [Abstract syntax tree]: https://en.wikipedia.org/wiki/Abstract_syntax_tree
 
 pub mod decl;
+pub mod docs;
+pub mod expr;
 pub mod identifier;
 pub mod literal;
+pub mod module;
 pub mod path;
+pub mod pattern;
+pub mod stmt;
 pub mod ty;
-pub mod expr;
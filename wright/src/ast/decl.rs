@@ -1,5 +1,178 @@
 //! Abstract syntax trees related to top-level declarations in source code.
 
+use crate::{
+    ast::{docs::Docs, identifier::Identifier},
+    source_tracking::fragment::Fragment,
+};
+
+pub mod constant;
+pub mod constraint;
+pub mod enumeration;
+pub mod func;
+pub mod generic;
+pub mod implementation;
 pub mod import;
+pub mod module;
+pub mod record;
+pub mod repr;
+pub mod trait_decl;
 pub mod type_alias;
-pub mod constant;
+pub mod union;
+
+use constant::ConstDecl;
+use constraint::ConstraintDecl;
+use enumeration::EnumDecl;
+use func::FuncDecl;
+use implementation::ImplDecl;
+use import::ImportDecl;
+use module::ModuleDecl;
+use record::RecordDecl;
+use trait_decl::TraitDecl;
+use type_alias::TypeAlias;
+use union::UnionDecl;
+
+/// Any declaration that can appear at the top level of a module.
+#[derive(Debug)]
+#[allow(missing_docs)]
+pub enum Decl {
+    Import(ImportDecl),
+    TypeAlias(TypeAlias),
+    Constant(ConstDecl),
+    Constraint(ConstraintDecl),
+    Function(FuncDecl),
+    Module(ModuleDecl),
+    Record(RecordDecl),
+    Union(UnionDecl),
+    Enum(EnumDecl),
+    Trait(TraitDecl),
+    Impl(ImplDecl),
+}
+
+impl Decl {
+    /// Get the full matching source of this declaration, including any visibility modifier.
+    pub fn matching_source(&self) -> &Fragment {
+        match self {
+            Decl::Import(import) => &import.matching_source,
+            Decl::TypeAlias(type_alias) => &type_alias.matching_source,
+            Decl::Constant(constant) => &constant.matching_source,
+            Decl::Constraint(constraint) => &constraint.matching_source,
+            Decl::Function(function) => &function.matching_source,
+            Decl::Module(module) => &module.matching_source,
+            Decl::Record(record) => &record.matching_source,
+            Decl::Union(union) => &union.matching_source,
+            Decl::Enum(enumeration) => &enumeration.matching_source,
+            Decl::Trait(trait_decl) => &trait_decl.matching_source,
+            Decl::Impl(implementation) => &implementation.matching_source,
+        }
+    }
+
+    /// Get the [Visibility] this declaration was made with.
+    pub fn visibility(&self) -> &Visibility {
+        match self {
+            Decl::Import(import) => &import.visibility,
+            Decl::TypeAlias(type_alias) => &type_alias.visibility,
+            Decl::Constant(constant) => &constant.visibility,
+            Decl::Constraint(constraint) => &constraint.visibility,
+            Decl::Function(function) => &function.visibility,
+            Decl::Module(module) => &module.visibility,
+            Decl::Record(record) => &record.visibility,
+            Decl::Union(union) => &union.visibility,
+            Decl::Enum(enumeration) => &enumeration.visibility,
+            Decl::Trait(trait_decl) => &trait_decl.visibility,
+            Decl::Impl(implementation) => &implementation.visibility,
+        }
+    }
+
+    /// Get the documentation attached to this declaration.
+    pub fn docs(&self) -> &Docs {
+        match self {
+            Decl::Import(import) => &import.docs,
+            Decl::TypeAlias(type_alias) => &type_alias.docs,
+            Decl::Constant(constant) => &constant.docs,
+            Decl::Constraint(constraint) => &constraint.docs,
+            Decl::Function(function) => &function.docs,
+            Decl::Module(module) => &module.docs,
+            Decl::Record(record) => &record.docs,
+            Decl::Union(union) => &union.docs,
+            Decl::Enum(enumeration) => &enumeration.docs,
+            Decl::Trait(trait_decl) => &trait_decl.docs,
+            Decl::Impl(implementation) => &implementation.docs,
+        }
+    }
+
+    /// Get the name this declaration binds in its module, if it binds one ([None] for `impl` blocks).
+    ///
+    /// For imports this is either the `as ...` rename or the last segment of the imported path.
+    pub fn name(&self) -> Option<&Identifier> {
+        let name = match self {
+            Decl::Import(import) => import
+                .imported_as
+                .as_ref()
+                .unwrap_or_else(|| import.imported_item.last()),
+            Decl::TypeAlias(type_alias) => &type_alias.new_type_name,
+            Decl::Constant(constant) => &constant.name,
+            Decl::Constraint(constraint) => &constraint.name,
+            Decl::Function(function) => &function.name,
+            Decl::Module(module) => &module.name,
+            Decl::Record(record) => &record.name,
+            Decl::Union(union) => &union.name,
+            Decl::Enum(enumeration) => &enumeration.name,
+            Decl::Trait(trait_decl) => &trait_decl.name,
+            Decl::Impl(_) => return None,
+        };
+
+        Some(name)
+    }
+
+    /// Get the functions this declaration defines -- the function itself, or the methods of a trait or `impl`
+    /// block.
+    pub fn functions(&self) -> &[FuncDecl] {
+        match self {
+            Decl::Function(function) => std::slice::from_ref(function),
+            Decl::Trait(trait_decl) => &trait_decl.methods,
+            Decl::Impl(implementation) => &implementation.methods,
+            _ => &[],
+        }
+    }
+}
+
+/// The visibility of a declaration, as set by an optional `pub` or `pub(mod)` modifier in front of it.
+///
+/// Declarations without a modifier are private -- they can be used in the module they're declared in and
+/// any of that module's children, but nowhere else.
+#[derive(Debug, Clone, Default)]
+pub enum Visibility {
+    /// No visibility modifier.
+    #[default]
+    Private,
+
+    /// `pub(mod)` -- the declaration is also visible to the parent of the module it's declared in
+    /// (and all of the parent's children).
+    Module {
+        /// The matching source of the whole `pub(mod)` modifier.
+        matching_source: Fragment,
+    },
+
+    /// `pub` -- the declaration is visible everywhere.
+    Public {
+        /// The matching source of the `pub` keyword.
+        matching_source: Fragment,
+    },
+}
+
+impl Visibility {
+    /// Check if this is [Visibility::Private].
+    pub const fn is_private(&self) -> bool {
+        matches!(self, Visibility::Private)
+    }
+
+    /// Get the matching source of this visibility modifier, if there is one.
+    pub fn matching_source(&self) -> Option<&Fragment> {
+        match self {
+            Visibility::Private => None,
+            Visibility::Module { matching_source } | Visibility::Public { matching_source } => {
+                Some(matching_source)
+            }
+        }
+    }
+}
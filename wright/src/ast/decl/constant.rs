@@ -1,11 +1,29 @@
+//! Constant declarations in wright source code.
+
+use crate::ast::decl::Visibility;
+use crate::ast::docs::Docs;
 use crate::ast::identifier::Identifier;
 use crate::ast::ty::Type;
 use crate::source_tracking::fragment::Fragment;
 
+/// A `const NAME: Type = value;` declaration in wright source code.
 #[derive(Debug)]
 pub struct ConstDecl {
-    matching_source: Fragment,
-    name: Identifier,
-    ty: Type,
-    value: () // todo
+    /// Full matching source including the visibility modifier (if any).
+    pub matching_source: Fragment,
+
+    /// The visibility of this constant.
+    pub visibility: Visibility,
+
+    /// Documentation from the outer doc comments preceding this declaration.
+    pub docs: Docs,
+
+    /// The name of the constant.
+    pub name: Identifier,
+
+    /// The declared type of the constant.
+    pub ty: Type,
+
+    /// The value of the constant.
+    pub value: (), // todo
 }
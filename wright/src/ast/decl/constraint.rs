@@ -0,0 +1,55 @@
+//! Constraint declarations in wright source code.
+
+use crate::{
+    ast::{
+        decl::{Visibility, func::FuncParam, generic::GenericParam},
+        docs::Docs,
+        identifier::Identifier,
+        path::Path,
+        stmt::Block,
+    },
+    source_tracking::fragment::Fragment,
+};
+
+/// A `constraint` declaration in wright source code. These come in two forms:
+/// - A predicate on a value, optionally building on other constraints, i.e.
+///   `constraint SmallEven(value: u8): Small + Even { value != 0 }`. Values must satisfy every base constraint
+///   and make the body `true`.
+/// - A composition of other constraints, i.e. `constraint KnownRange = KnownMin + KnownMax;`. Values must satisfy
+///   every constraint it's composed of.
+///
+/// Generic constraints (i.e. `constraint Positive<T>(value: T) { value > 0 }`) get their generic arguments from the
+/// type they're applied to with `constrain`.
+#[derive(Debug)]
+pub struct ConstraintDecl {
+    /// Full matching source including the visibility modifier (if any) and the body or semicolon.
+    pub matching_source: Fragment,
+
+    /// The visibility of this constraint.
+    pub visibility: Visibility,
+
+    /// Documentation from the outer doc comments preceding this declaration.
+    pub docs: Docs,
+
+    /// The name of the constraint.
+    pub name: Identifier,
+
+    /// Generic type parameters of this constraint, if any.
+    pub generic_params: Vec<GenericParam>,
+
+    /// The parameter and body of this constraint. Compositions of other constraints don't have one.
+    pub predicate: Option<ConstraintPredicate>,
+
+    /// The constraints this one builds on (after the `:`), or is composed of (after the `=`).
+    pub bases: Vec<Path>,
+}
+
+/// The condition that a [ConstraintDecl] places on values, i.e. `(value: u8) { value % 2 == 0 }`.
+#[derive(Debug)]
+pub struct ConstraintPredicate {
+    /// The parameter that holds the value being checked.
+    pub param: FuncParam,
+
+    /// The body of the constraint, which evaluates to `true` for allowed values.
+    pub body: Block,
+}
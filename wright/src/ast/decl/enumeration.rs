@@ -0,0 +1,34 @@
+//! Enumeration declarations -- named types with a fixed set of values.
+
+use crate::{
+    ast::{
+        decl::{Visibility, repr::Repr},
+        docs::Docs,
+        identifier::Identifier,
+    },
+    source_tracking::fragment::Fragment,
+};
+
+/// An `enum Name { Variant, ... }` declaration in wright source code.
+///
+/// Each variant is one of the values of the enum, and is named with a path through the enum, i.e. `Color::Red`.
+#[derive(Debug)]
+pub struct EnumDecl {
+    /// Full matching source including the visibility modifier (if any).
+    pub matching_source: Fragment,
+
+    /// The visibility of this enum.
+    pub visibility: Visibility,
+
+    /// Documentation from the outer doc comments preceding this declaration.
+    pub docs: Docs,
+
+    /// The name of the enum type.
+    pub name: Identifier,
+
+    /// The `repr(...)` clause of the enum, if it has one.
+    pub repr: Option<Repr>,
+
+    /// The names of the variants of the enum, in declaration order.
+    pub variants: Vec<Identifier>,
+}
@@ -0,0 +1,62 @@
+//! Function declarations in wright source code.
+
+use crate::{
+    ast::{
+        decl::{
+            Visibility,
+            generic::{GenericParam, WherePredicate},
+        },
+        docs::Docs,
+        identifier::Identifier,
+        stmt::Block,
+        ty::Type,
+    },
+    source_tracking::fragment::Fragment,
+};
+
+/// A `func name(param: Type) -> Type { ... }` declaration in wright source code.
+#[derive(Debug)]
+pub struct FuncDecl {
+    /// Full matching source including the visibility modifier (if any) and the body.
+    pub matching_source: Fragment,
+
+    /// The visibility of this function.
+    pub visibility: Visibility,
+
+    /// Documentation from the outer doc comments preceding this declaration.
+    pub docs: Docs,
+
+    /// Whether this function was declared `pure`.
+    pub is_pure: bool,
+
+    /// The name of the function.
+    pub name: Identifier,
+
+    /// Generic type parameters of this function, if any.
+    pub generic_params: Vec<GenericParam>,
+
+    /// The function's parameters, in order.
+    pub params: Vec<FuncParam>,
+
+    /// The declared return type of the function ([None] if not specified).
+    pub return_ty: Option<Type>,
+
+    /// The predicates of the function's `where` clause, if it has one.
+    pub where_clause: Vec<WherePredicate>,
+
+    /// The body of the function. Abstract function declarations (`func name();`) have no body.
+    pub body: Option<Block>,
+}
+
+/// A parameter in a function signature -- i.e. `x: u8`.
+#[derive(Debug)]
+pub struct FuncParam {
+    /// The matching source of the whole parameter.
+    pub matching_source: Fragment,
+
+    /// The name of the parameter.
+    pub name: Identifier,
+
+    /// The type of the parameter.
+    pub ty: Type,
+}
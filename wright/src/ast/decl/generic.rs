@@ -0,0 +1,32 @@
+//! Generic parameters on declarations.
+
+use crate::{
+    ast::{identifier::Identifier, path::Path, ty::Type},
+    source_tracking::fragment::Fragment,
+};
+
+/// A generic type parameter declared on an item, such as `T` or `T: cmp::Ord + Clone` in `func max<T: cmp::Ord>`.
+#[derive(Debug)]
+pub struct GenericParam {
+    /// The matching source of the whole parameter, including any bounds.
+    pub matching_source: Fragment,
+
+    /// The name of the generic type parameter.
+    pub name: Identifier,
+
+    /// The traits that the type parameter is bound by (usually empty).
+    pub bounds: Vec<Path>,
+}
+
+/// A predicate in a `where` clause, such as `T: Shape` in `func area<T>(shape: @T) -> u64 where T: Shape`.
+#[derive(Debug)]
+pub struct WherePredicate {
+    /// The matching source of the whole predicate.
+    pub matching_source: Fragment,
+
+    /// The type that the predicate bounds.
+    pub ty: Type,
+
+    /// The traits that the type must implement.
+    pub bounds: Vec<Path>,
+}
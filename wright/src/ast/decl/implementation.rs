@@ -0,0 +1,51 @@
+//! Implementation blocks -- methods defined for a type, either directly or as an implementation of a trait.
+
+use crate::{
+    ast::{
+        decl::{
+            Visibility,
+            func::FuncDecl,
+            generic::{GenericParam, WherePredicate},
+        },
+        docs::Docs,
+        path::Path,
+        ty::Type,
+    },
+    source_tracking::fragment::Fragment,
+};
+
+/// An `impl` block in wright source code. These come in two forms:
+/// - Inherent implementations, i.e. `impl Point { ... }`, which define methods directly on a type.
+/// - Trait implementations, i.e. `impl<T> Shape for Box<T> where T: Shape { ... }`, which implement the methods of
+///   a trait for a type.
+#[derive(Debug)]
+pub struct ImplDecl {
+    /// Full matching source including the visibility modifier (if any).
+    pub matching_source: Fragment,
+
+    /// The visibility of this implementation. This has no effect, since methods are available wherever the type
+    /// they're implemented for is.
+    pub visibility: Visibility,
+
+    /// Documentation from the outer doc comments preceding this declaration.
+    pub docs: Docs,
+
+    /// The matching source of the header of the implementation, i.e. `impl<T> Shape for Box<T>`, which is how it's
+    /// referred to in diagnostics.
+    pub header: Fragment,
+
+    /// Generic type parameters of this implementation, if any.
+    pub generic_params: Vec<GenericParam>,
+
+    /// The trait being implemented ([None] for inherent implementations).
+    pub trait_path: Option<Path>,
+
+    /// The type that the methods are implemented for.
+    pub self_ty: Type,
+
+    /// The predicates of the implementation's `where` clause, if it has one.
+    pub where_clause: Vec<WherePredicate>,
+
+    /// The methods defined by this implementation, in declaration order.
+    pub methods: Vec<FuncDecl>,
+}
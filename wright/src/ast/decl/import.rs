@@ -5,7 +5,7 @@
 //! starting with a `::` prefix yet).
 
 use crate::{
-    ast::{identifier::Identifier, path::Path},
+    ast::{decl::Visibility, docs::Docs, identifier::Identifier, path::Path},
     source_tracking::fragment::Fragment,
 };
 
@@ -15,6 +15,12 @@ pub struct ImportDecl {
     /// The full matching source of the declaration, whitespace and all.
     pub matching_source: Fragment,
 
+    /// The visibility of this import. Public imports re-export the imported item.
+    pub visibility: Visibility,
+
+    /// Documentation from the outer doc comments preceding this declaration.
+    pub docs: Docs,
+
     /// The item being imported.
     pub imported_item: Path,
 
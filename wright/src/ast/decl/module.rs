@@ -0,0 +1,34 @@
+//! Module declarations -- `mod name;` to include a module from another file, or `mod name { ... }` for a module
+//! written inline.
+
+use crate::{
+    ast::{decl::Visibility, docs::Docs, identifier::Identifier, module::Module},
+    source_tracking::fragment::Fragment,
+};
+
+/// A `mod name;` or `mod name { ... }` declaration in a wright source file.
+#[derive(Debug)]
+pub struct ModuleDecl {
+    /// Full matching source including whitespace.
+    pub matching_source: Fragment,
+
+    /// The visibility of the declared module.
+    pub visibility: Visibility,
+
+    /// Documentation from the outer doc comments preceding this declaration.
+    pub docs: Docs,
+
+    /// The name of the declared module.
+    pub name: Identifier,
+
+    /// The body of the module, if it was written inline. Modules declared with `mod name;` are loaded from the
+    /// file `name.wr` or `name/mod.wr` (see [crate::package]).
+    pub body: Option<Module>,
+}
+
+impl ModuleDecl {
+    /// Check if this declaration includes a module from another file (rather than declaring it inline).
+    pub const fn is_external(&self) -> bool {
+        self.body.is_none()
+    }
+}
@@ -0,0 +1,50 @@
+//! Record declarations -- named product types with named fields.
+
+use crate::{
+    ast::{
+        decl::{Visibility, generic::GenericParam, repr::Repr},
+        docs::Docs,
+        identifier::Identifier,
+        ty::Type,
+    },
+    source_tracking::fragment::Fragment,
+};
+
+/// A `record Name<T> repr(...) { field: Type, ... }` declaration in wright source code, where the `repr(...)` clause
+/// is optional.
+#[derive(Debug)]
+pub struct RecordDecl {
+    /// Full matching source including the visibility modifier (if any).
+    pub matching_source: Fragment,
+
+    /// The visibility of this record.
+    pub visibility: Visibility,
+
+    /// Documentation from the outer doc comments preceding this declaration.
+    pub docs: Docs,
+
+    /// The name of the record type.
+    pub name: Identifier,
+
+    /// The generic type parameters of the record.
+    pub generic_params: Vec<GenericParam>,
+
+    /// The `repr(...)` clause of the record, if it has one.
+    pub repr: Option<Repr>,
+
+    /// The fields of the record, in declaration order.
+    pub fields: Vec<FieldDecl>,
+}
+
+/// A named and typed field of a record, or a variant of a union -- i.e. `x: u8`.
+#[derive(Debug)]
+pub struct FieldDecl {
+    /// The matching source of the name and type.
+    pub matching_source: Fragment,
+
+    /// The name of the field.
+    pub name: Identifier,
+
+    /// The type of the field.
+    pub ty: Type,
+}
@@ -0,0 +1,65 @@
+//! `repr(...)` clauses, which control how records, unions, and enums are laid out in memory.
+
+use crate::{ast::ty::AtomicTyVariant, source_tracking::fragment::Fragment};
+
+/// A `repr(...)` clause on a record, union, or enum declaration, i.e. `record Header repr(c) { ... }`.
+///
+/// Without one, the [layout engine] is free to reorder the fields of records and to store the variant of a union in
+/// the invalid values of one of its variants (a niche).
+///
+/// [layout engine]: crate::analysis::layout
+#[derive(Debug)]
+pub struct Repr {
+    /// The matching source of the whole clause, from `repr` to the closing parenthesis.
+    pub matching_source: Fragment,
+
+    /// The hints in the clause, in the order they're written.
+    pub hints: Vec<ReprHint>,
+}
+
+/// One of the hints in a `repr(...)` clause.
+#[derive(Debug)]
+pub struct ReprHint {
+    /// The matching source of the hint.
+    pub matching_source: Fragment,
+
+    /// What the hint asks for.
+    pub kind: ReprHintKind,
+}
+
+/// What a [ReprHint] asks for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReprHintKind {
+    /// `c` -- lay the type out like C would: fields in declaration order, and variants of unions and enums told
+    /// apart with a `u32` tag rather than a niche.
+    C,
+
+    /// `packed` -- align the type (and each of its fields) to a single byte, leaving no padding.
+    Packed,
+
+    /// An integer type such as `u8` -- use it for the tag of a union or enum, rather than a niche or the smallest
+    /// integer type that fits.
+    Int(AtomicTyVariant),
+}
+
+impl Repr {
+    /// Check if the clause has a [ReprHintKind::C] hint.
+    pub fn is_c(&self) -> bool {
+        self.hints.iter().any(|hint| hint.kind == ReprHintKind::C)
+    }
+
+    /// Check if the clause has a [ReprHintKind::Packed] hint.
+    pub fn is_packed(&self) -> bool {
+        self.hints
+            .iter()
+            .any(|hint| hint.kind == ReprHintKind::Packed)
+    }
+
+    /// Get the integer type of the clause's [ReprHintKind::Int] hint, if it has one.
+    pub fn int(&self) -> Option<AtomicTyVariant> {
+        self.hints.iter().find_map(|hint| match hint.kind {
+            ReprHintKind::Int(variant) => Some(variant),
+            _ => None,
+        })
+    }
+}
@@ -0,0 +1,33 @@
+//! Trait declarations -- named sets of methods that types can implement.
+
+use crate::{
+    ast::{
+        decl::{Visibility, func::FuncDecl},
+        docs::Docs,
+        identifier::Identifier,
+    },
+    source_tracking::fragment::Fragment,
+};
+
+/// A `trait Name { ... }` declaration in wright source code.
+///
+/// The methods of a trait are declared like functions, with `Self` naming the implementing type. Methods without
+/// a body (i.e. `func area(self: @Self) -> u64;`) must be defined by every `impl` of the trait, while methods with a
+/// body provide a default that implementations can use or replace.
+#[derive(Debug)]
+pub struct TraitDecl {
+    /// Full matching source including the visibility modifier (if any).
+    pub matching_source: Fragment,
+
+    /// The visibility of this trait.
+    pub visibility: Visibility,
+
+    /// Documentation from the outer doc comments preceding this declaration.
+    pub docs: Docs,
+
+    /// The name of the trait.
+    pub name: Identifier,
+
+    /// The methods of the trait, in declaration order.
+    pub methods: Vec<FuncDecl>,
+}
@@ -1,8 +1,7 @@
 //! Type alias declarations in wright source code.
-//!
 
 use crate::{
-    ast::{identifier::Identifier, ty::Type},
+    ast::{decl::Visibility, docs::Docs, identifier::Identifier, ty::Type},
     source_tracking::fragment::Fragment,
 };
 
@@ -12,6 +11,12 @@ pub struct TypeAlias {
     /// Full matching source including whitespace.
     pub matching_source: Fragment,
 
+    /// The visibility of this type alias.
+    pub visibility: Visibility,
+
+    /// Documentation from the outer doc comments preceding this declaration.
+    pub docs: Docs,
+
     /// The name of the new/aliased type.
     pub new_type_name: Identifier,
 
@@ -0,0 +1,37 @@
+//! Union declarations -- named, tagged sum types.
+
+use crate::{
+    ast::{
+        decl::{Visibility, generic::GenericParam, record::FieldDecl, repr::Repr},
+        docs::Docs,
+        identifier::Identifier,
+    },
+    source_tracking::fragment::Fragment,
+};
+
+/// A `union Name<T> { variant: Type, ... }` declaration in wright source code.
+///
+/// Unions are tagged -- a value of a union type holds exactly one of its variants, and knows which one.
+#[derive(Debug)]
+pub struct UnionDecl {
+    /// Full matching source including the visibility modifier (if any).
+    pub matching_source: Fragment,
+
+    /// The visibility of this union.
+    pub visibility: Visibility,
+
+    /// Documentation from the outer doc comments preceding this declaration.
+    pub docs: Docs,
+
+    /// The name of the union type.
+    pub name: Identifier,
+
+    /// The generic type parameters of the union.
+    pub generic_params: Vec<GenericParam>,
+
+    /// The `repr(...)` clause of the union, if it has one.
+    pub repr: Option<Repr>,
+
+    /// The variants of the union, in declaration order.
+    pub variants: Vec<FieldDecl>,
+}
@@ -0,0 +1,55 @@
+//! Documentation collected from doc comments in source code.
+//!
+//! Outer doc comments (`///` and `/** */`) document the item that follows them, while inner doc comments
+//! (`//!` and `/*! */`) document the module that contains them.
+
+use crate::source_tracking::fragment::Fragment;
+
+/// Documentation attached to a declaration or module.
+///
+/// The comment markers (and any leading `*` decorations on the lines of block comments) are stripped, along with
+/// the indentation common to all of the lines. Each remaining line is kept as a [Fragment] of the original source,
+/// so that tools can point back into the doc comment.
+#[derive(Debug, Clone, Default)]
+pub struct Docs {
+    /// The lines of documentation, in order, without line terminators. Blank lines are empty [Fragment]s.
+    pub lines: Vec<Fragment>,
+}
+
+impl Docs {
+    /// Check if there is no documentation.
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// Get the lines of this documentation as [Fragment]s of source code.
+    pub fn lines(&self) -> &[Fragment] {
+        &self.lines
+    }
+
+    /// Get the text of this documentation, with each line separated by a `\n`.
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+
+        for (index, line) in self.lines.iter().enumerate() {
+            if index > 0 {
+                text.push('\n');
+            }
+
+            text.push_str(line.as_str());
+        }
+
+        text
+    }
+
+    /// Get the first paragraph of this documentation (everything up to the first blank line), joined with spaces.
+    /// This is useful for short summaries of an item.
+    pub fn summary(&self) -> String {
+        self.lines
+            .iter()
+            .map(|line| line.as_str().trim())
+            .take_while(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
@@ -1,7 +1,134 @@
+//! Expression AST models.
+
 use crate::ast::identifier::Identifier;
-use crate::ast::literal::{BooleanLiteral, IntegerLiteral};
+use crate::ast::literal::{BooleanLiteral, IntegerLiteral, StringLiteral};
+use crate::ast::path::Path;
+use crate::ast::pattern::Pattern;
+use crate::ast::stmt::Block;
+use crate::ast::ty::Type;
 use crate::source_tracking::fragment::Fragment;
 
+/// An expression in wright source code.
+#[derive(Debug)]
+#[allow(missing_docs)]
+pub enum Expr {
+    Atom(Atom),
+    Path(Path),
+    Parens(ParensExpr),
+    Unary(UnaryExpr),
+    Binary(BinaryExpr),
+    Is(IsExpr),
+    Call(CallExpr),
+    Field(FieldExpr),
+    Record(RecordExpr),
+    Block(Block),
+    If(IfExpr),
+    Match(MatchExpr),
+    While(WhileExpr),
+    For(ForExpr),
+    Closure(ClosureExpr),
+    Unsafe(UnsafeExpr),
+}
+
+impl Expr {
+    /// Get the matching source for this expression.
+    pub fn matching_source(&self) -> &Fragment {
+        match self {
+            Expr::Atom(atom) => atom.fragment(),
+            Expr::Path(path) => &path.full_path,
+            Expr::Parens(parens) => &parens.matching_source,
+            Expr::Unary(unary) => &unary.matching_source,
+            Expr::Binary(binary) => &binary.matching_source,
+            Expr::Is(is_expr) => &is_expr.matching_source,
+            Expr::Call(call) => &call.matching_source,
+            Expr::Field(field) => &field.matching_source,
+            Expr::Record(record) => &record.matching_source,
+            Expr::Block(block) => &block.matching_source,
+            Expr::If(if_expr) => &if_expr.matching_source,
+            Expr::Match(match_expr) => &match_expr.matching_source,
+            Expr::While(while_expr) => &while_expr.matching_source,
+            Expr::For(for_expr) => &for_expr.matching_source,
+            Expr::Closure(closure) => &closure.matching_source,
+            Expr::Unsafe(unsafe_expr) => &unsafe_expr.matching_source,
+        }
+    }
+
+    /// Call `visit` on each of the expressions directly inside this one, in source order. This includes the
+    /// expressions in any blocks inside this one (see [Block::for_each_expr]).
+    pub fn for_each_child<'a>(&'a self, visit: &mut impl FnMut(&'a Expr)) {
+        match self {
+            Expr::Atom(_) | Expr::Path(_) => {}
+            Expr::Parens(parens) => visit(&parens.inner),
+            Expr::Unary(unary) => visit(&unary.operand),
+
+            Expr::Binary(binary) => {
+                visit(&binary.lhs);
+                visit(&binary.rhs);
+            }
+
+            Expr::Is(is_expr) => visit(&is_expr.value),
+
+            Expr::Call(call) => {
+                visit(&call.callee);
+                call.args.iter().for_each(visit);
+            }
+
+            Expr::Field(field) => visit(&field.base),
+            Expr::Record(record) => record.fields.iter().for_each(|field| visit(&field.value)),
+            Expr::Block(block) => block.for_each_expr(visit),
+
+            Expr::If(if_expr) => {
+                visit(&if_expr.condition);
+                if_expr.then_block.for_each_expr(visit);
+
+                if let Some(else_branch) = &if_expr.else_branch {
+                    visit(else_branch);
+                }
+            }
+
+            Expr::Match(match_expr) => {
+                visit(&match_expr.scrutinee);
+
+                for arm in &match_expr.arms {
+                    if let Some(guard) = &arm.guard {
+                        visit(guard);
+                    }
+
+                    visit(&arm.body);
+                }
+            }
+
+            Expr::While(while_expr) => {
+                visit(&while_expr.condition);
+                while_expr.body.for_each_expr(visit);
+            }
+
+            Expr::For(for_expr) => {
+                visit(&for_expr.range.start);
+                visit(&for_expr.range.end);
+                for_expr.body.for_each_expr(visit);
+            }
+
+            Expr::Closure(closure) => closure.body.for_each_expr(visit),
+            Expr::Unsafe(unsafe_expr) => unsafe_expr.body.for_each_expr(visit),
+        }
+    }
+
+    /// Check if this expression ends with a [Block], in which case it does not need a semicolon to be used as a
+    /// statement.
+    pub fn is_block_like(&self) -> bool {
+        matches!(
+            self,
+            Expr::Block(_)
+                | Expr::If(_)
+                | Expr::Match(_)
+                | Expr::While(_)
+                | Expr::For(_)
+                | Expr::Unsafe(_)
+        )
+    }
+}
+
 /// Atoms of an expression -- these are individual tokens from the lexer that are valid as an
 /// expression all on their own.
 #[derive(Debug)]
@@ -10,6 +137,7 @@ pub enum Atom {
     Identifier(Identifier),
     IntegerLiteral(IntegerLiteral),
     BooleanLiteral(BooleanLiteral),
+    StringLiteral(StringLiteral),
 }
 
 impl Atom {
@@ -19,16 +147,364 @@ impl Atom {
             Atom::Identifier(i) => &i.fragment,
             Atom::IntegerLiteral(lit) => &lit.fragment,
             Atom::BooleanLiteral(lit) => &lit.fragment,
+            Atom::StringLiteral(lit) => &lit.fragment,
         }
     }
 }
 
+/// An expression wrapped in parentheses, i.e. `(a + b)`.
+#[derive(Debug)]
+pub struct ParensExpr {
+    /// The matching source, including the parentheses.
+    pub matching_source: Fragment,
+
+    /// The expression inside the parentheses.
+    pub inner: Box<Expr>,
+}
+
+/// A unary operation applied to an expression, i.e. `-x` or `@value`.
+#[derive(Debug)]
+pub struct UnaryExpr {
+    /// The matching source, including the operator.
+    pub matching_source: Fragment,
+
+    /// The operation being applied.
+    pub op: UnaryOperation,
+
+    /// The expression that the operation is applied to.
+    pub operand: Box<Expr>,
+}
+
+/// A binary operation, i.e. `a + b`.
+#[derive(Debug)]
+pub struct BinaryExpr {
+    /// The matching source of the whole expression.
+    pub matching_source: Fragment,
+
+    /// The operation being applied.
+    pub op: BinaryOperation,
+
+    /// The fragment of the operator itself.
+    pub op_fragment: Fragment,
+
+    /// The left hand side of the operation.
+    pub lhs: Box<Expr>,
+
+    /// The right hand side of the operation.
+    pub rhs: Box<Expr>,
+}
+
+/// A test of which variant a union value holds, i.e. `value is Number::small`, or `value is u8` to test for the
+/// variant of that type. Enum values can be tested too, i.e. `color is Color::Red`.
+///
+/// Where the test is known to be `true` (in the `if` branch it's the condition of, or on the right of a `&&`), a
+/// tested `let` binding or parameter is narrowed to the variant, so it can be read like a field.
+#[derive(Debug)]
+pub struct IsExpr {
+    /// The matching source of the whole expression.
+    pub matching_source: Fragment,
+
+    /// The value being tested.
+    pub value: Box<Expr>,
+
+    /// The variant being tested for -- either a path to a variant, or the type of a union's variant.
+    pub target: Type,
+}
+
+/// A function call, i.e. `println("Hello")`.
+#[derive(Debug)]
+pub struct CallExpr {
+    /// The matching source, from the start of the callee to the closing parenthesis.
+    pub matching_source: Fragment,
+
+    /// The expression being called.
+    pub callee: Box<Expr>,
+
+    /// The arguments passed to the call.
+    pub args: Vec<Expr>,
+}
+
+/// A field access, i.e. `point.x`.
+#[derive(Debug)]
+pub struct FieldExpr {
+    /// The matching source of the whole expression.
+    pub matching_source: Fragment,
+
+    /// The expression whose field is being accessed.
+    pub base: Box<Expr>,
+
+    /// The name of the field.
+    pub field: Identifier,
+}
+
+/// A record or union literal, i.e. `Point { x: 1, y: 2 }` or `Number { small: 1 }`. Union literals give exactly
+/// one variant.
+#[derive(Debug)]
+pub struct RecordExpr {
+    /// The matching source, from the start of the name to the closing curly brace.
+    pub matching_source: Fragment,
+
+    /// The name of the record or union type.
+    pub name: Path,
+
+    /// The fields given values, in source order.
+    pub fields: Vec<FieldInit>,
+}
+
+/// The value given to a field in a [RecordExpr], i.e. `x: 1`.
+#[derive(Debug)]
+pub struct FieldInit {
+    /// The matching source of the field name and value.
+    pub matching_source: Fragment,
+
+    /// The name of the field.
+    pub name: Identifier,
+
+    /// The value of the field.
+    pub value: Expr,
+}
+
+/// An `if` expression, with an optional `else` branch.
+#[derive(Debug)]
+pub struct IfExpr {
+    /// The matching source, from the `if` to the end of the last branch.
+    pub matching_source: Fragment,
+
+    /// The condition being checked.
+    pub condition: Box<Expr>,
+
+    /// The block run if the condition is true.
+    pub then_block: Block,
+
+    /// The `else` branch -- either a [Block] or another [IfExpr] (for `else if`).
+    pub else_branch: Option<Box<Expr>>,
+}
+
+/// A `match` expression, i.e. `match color { Color::Red => 1, _ => 2 }`. The value is compared against the
+/// pattern of each arm in order, and the body of the first arm that matches (and whose guard is `true`, if it has
+/// one) is run.
+#[derive(Debug)]
+pub struct MatchExpr {
+    /// The matching source, from the `match` to the closing curly brace.
+    pub matching_source: Fragment,
+
+    /// The value being matched.
+    pub scrutinee: Box<Expr>,
+
+    /// The arms of the `match`, in source order.
+    pub arms: Vec<MatchArm>,
+}
+
+/// An arm of a [MatchExpr], i.e. `n if n > 10 => big(n)`.
+#[derive(Debug)]
+pub struct MatchArm {
+    /// The matching source, from the start of the pattern to the end of the body.
+    pub matching_source: Fragment,
+
+    /// The pattern the value is compared against.
+    pub pattern: Pattern,
+
+    /// The condition after `if`, which must also be `true` for this arm to be chosen.
+    pub guard: Option<Expr>,
+
+    /// The expression run when this arm is chosen.
+    pub body: Expr,
+}
+
+/// A `while` loop.
+#[derive(Debug)]
+pub struct WhileExpr {
+    /// The matching source, from the `while` to the end of the body.
+    pub matching_source: Fragment,
+
+    /// The condition checked before every iteration.
+    pub condition: Box<Expr>,
+
+    /// The body of the loop.
+    pub body: Block,
+}
+
+/// An `unsafe` block, i.e. `unsafe { ... }`. These can't be used in `pure` functions.
+#[derive(Debug)]
+pub struct UnsafeExpr {
+    /// The matching source, from the `unsafe` to the end of the block.
+    pub matching_source: Fragment,
+
+    /// The block.
+    pub body: Block,
+}
+
+/// A `for` loop over a range of integers, i.e. `for i in 1..=100 { ... }`.
+#[derive(Debug)]
+pub struct ForExpr {
+    /// The matching source, from the `for` to the end of the body.
+    pub matching_source: Fragment,
+
+    /// The name bound to each value in the range.
+    pub binding: Identifier,
+
+    /// The range being looped over.
+    pub range: RangeExpr,
+
+    /// The body of the loop.
+    pub body: Block,
+}
+
+/// A range of integers, i.e. `0..10` (which excludes the end) or `1..=100` (which includes it). Ranges can only be
+/// used in `for` loops.
+#[derive(Debug)]
+pub struct RangeExpr {
+    /// The matching source, from the start of the range to its end.
+    pub matching_source: Fragment,
+
+    /// The first value in the range.
+    pub start: Box<Expr>,
+
+    /// The end of the range.
+    pub end: Box<Expr>,
+
+    /// Whether the end is part of the range (`..=`) or not (`..`).
+    pub is_inclusive: bool,
+}
+
+/// An anonymous function, i.e. `func(x, y: u8) -> u8 { x + y }`. The types of parameters (and the return type) can
+/// be left out and inferred from how the closure is used.
+#[derive(Debug)]
+pub struct ClosureExpr {
+    /// The matching source, from the `func` keyword to the end of the body.
+    pub matching_source: Fragment,
+
+    /// The parameters of the closure.
+    pub params: Vec<ClosureParam>,
+
+    /// The return type, if it was given.
+    pub return_ty: Option<Type>,
+
+    /// The body of the closure.
+    pub body: Block,
+}
+
+/// A parameter of a [ClosureExpr], with an optional type annotation.
+#[derive(Debug)]
+pub struct ClosureParam {
+    /// The matching source of the parameter, including its type annotation.
+    pub matching_source: Fragment,
+
+    /// The name of the parameter.
+    pub name: Identifier,
+
+    /// The type annotation, if there is one.
+    pub ty: Option<Type>,
+}
+
+/// The operations that can be applied to a single expression.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 #[allow(missing_docs)]
 pub enum UnaryOperation {
     Reference,
+    MutableReference,
     Dereference,
     Negate,
     BooleanNot,
     BitwiseNot,
 }
+
+impl UnaryOperation {
+    /// Get the operator for this operation as it appears in source code.
+    pub const fn symbol(self) -> &'static str {
+        match self {
+            UnaryOperation::Reference => "@",
+            UnaryOperation::MutableReference => "@mut",
+            UnaryOperation::Dereference => "*",
+            UnaryOperation::Negate => "-",
+            UnaryOperation::BooleanNot => "!",
+            UnaryOperation::BitwiseNot => "~",
+        }
+    }
+}
+
+/// The operations that can be applied to two expressions.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[allow(missing_docs)]
+pub enum BinaryOperation {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+    BitwiseAnd,
+    BitwiseOr,
+    BitwiseXor,
+    ShiftLeft,
+    ShiftRight,
+    LogicalAnd,
+    LogicalOr,
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl BinaryOperation {
+    /// Get the operator for this operation as it appears in source code.
+    pub const fn symbol(self) -> &'static str {
+        use BinaryOperation::*;
+
+        match self {
+            Add => "+",
+            Subtract => "-",
+            Multiply => "*",
+            Divide => "/",
+            Modulo => "%",
+            BitwiseAnd => "&",
+            BitwiseOr => "|",
+            BitwiseXor => "^",
+            ShiftLeft => "<<",
+            ShiftRight => ">>",
+            LogicalAnd => "&&",
+            LogicalOr => "||",
+            Equal => "==",
+            NotEqual => "!=",
+            Less => "<",
+            LessOrEqual => "<=",
+            Greater => ">",
+            GreaterOrEqual => ">=",
+        }
+    }
+
+    /// Get the compound assignment operator for this operation, i.e. `+=` for [BinaryOperation::Add], if it has
+    /// one.
+    pub const fn assign_symbol(self) -> Option<&'static str> {
+        use BinaryOperation::*;
+
+        Some(match self {
+            Add => "+=",
+            Subtract => "-=",
+            Multiply => "*=",
+            Divide => "/=",
+            Modulo => "%=",
+            BitwiseAnd => "&=",
+            BitwiseOr => "|=",
+            BitwiseXor => "^=",
+            _ => return None,
+        })
+    }
+
+    /// Check if this is a comparison, which produces a `bool`.
+    pub const fn is_comparison(self) -> bool {
+        use BinaryOperation::*;
+        matches!(self, Equal | NotEqual | Less | LessOrEqual | Greater | GreaterOrEqual)
+    }
+
+    /// Check if this is `&&` or `||`, which operate on `bool`s.
+    pub const fn is_logical(self) -> bool {
+        matches!(self, BinaryOperation::LogicalAnd | BinaryOperation::LogicalOr)
+    }
+
+    /// Check if this is a bitwise shift.
+    pub const fn is_shift(self) -> bool {
+        matches!(self, BinaryOperation::ShiftLeft | BinaryOperation::ShiftRight)
+    }
+}
@@ -24,3 +24,13 @@ pub struct BooleanLiteral {
     /// The value of the boolean literal.
     pub value: bool,
 }
+
+/// A string literal from source.
+#[derive(Debug)]
+pub struct StringLiteral {
+    /// The [Fragment] of source code containing this string literal, including the quotes.
+    pub fragment: Fragment,
+
+    /// The value of the string literal, with any escape sequences replaced.
+    pub value: String,
+}
@@ -0,0 +1,19 @@
+//! The root AST node of a wright source file.
+
+use crate::{
+    ast::{decl::Decl, docs::Docs},
+    source_tracking::fragment::Fragment,
+};
+
+/// A module of wright source code -- generally a whole file -- containing a series of declarations.
+#[derive(Debug)]
+pub struct Module {
+    /// The matching source of the whole module.
+    pub matching_source: Fragment,
+
+    /// Documentation for this module, from inner doc comments (`//!` and `/*! */`) in it.
+    pub docs: Docs,
+
+    /// The declarations in this module, in source order.
+    pub items: Vec<Decl>,
+}
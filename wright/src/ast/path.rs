@@ -18,3 +18,10 @@ pub struct Path {
     /// The rest of the [Path], following the first separator.
     pub tail: Vec<Identifier>,
 }
+
+impl Path {
+    /// Get the last (right-most) [Identifier] in this [Path]. This is the [Path::head] for single-segment paths.
+    pub fn last(&self) -> &Identifier {
+        self.tail.last().unwrap_or(&self.head)
+    }
+}
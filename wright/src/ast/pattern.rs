@@ -0,0 +1,156 @@
+//! Pattern AST models -- the patterns that the arms of a `match` compare values against.
+
+use crate::{
+    ast::{
+        identifier::Identifier,
+        literal::{BooleanLiteral, IntegerLiteral},
+        path::Path,
+    },
+    source_tracking::fragment::Fragment,
+};
+use num::BigInt;
+
+/// A pattern in wright source code.
+#[derive(Debug)]
+pub enum Pattern {
+    /// `_`, which matches any value.
+    Wildcard(Fragment),
+
+    /// A name, which matches any value and binds it to the name. Single identifiers are always bindings -- enum
+    /// variants are matched with a path through their enum (see [Pattern::Variant]).
+    Binding(Identifier),
+
+    /// An integer literal, optionally negated, i.e. `-1`.
+    Integer(IntegerPattern),
+
+    /// A boolean literal.
+    Boolean(BooleanLiteral),
+
+    /// A range of integers, i.e. `1..=10` or `0..10`.
+    Range(RangePattern),
+
+    /// A path to an enum variant, i.e. `Color::Red`.
+    Variant(Path),
+
+    /// A record or union pattern, i.e. `Point { x: 0, y }` or `Number { small: n }`.
+    Record(RecordPattern),
+
+    /// A choice between patterns, i.e. `1 | 2 | 3`, which matches values that match any of them.
+    Or(OrPattern),
+}
+
+impl Pattern {
+    /// Get the matching source for this pattern.
+    pub fn matching_source(&self) -> &Fragment {
+        match self {
+            Pattern::Wildcard(fragment) => fragment,
+            Pattern::Binding(name) => &name.fragment,
+            Pattern::Integer(integer) => &integer.matching_source,
+            Pattern::Boolean(boolean) => &boolean.fragment,
+            Pattern::Range(range) => &range.matching_source,
+            Pattern::Variant(path) => &path.full_path,
+            Pattern::Record(record) => &record.matching_source,
+            Pattern::Or(or) => &or.matching_source,
+        }
+    }
+
+    /// Call `visit` on each of the names this pattern binds, in source order.
+    pub fn for_each_binding<'a>(&'a self, visit: &mut impl FnMut(&'a Identifier)) {
+        match self {
+            Pattern::Binding(name) => visit(name),
+
+            Pattern::Record(record) => {
+                (record.fields.iter()).for_each(|field| field.pattern.for_each_binding(visit))
+            }
+
+            Pattern::Or(or) => {
+                (or.alternatives.iter()).for_each(|alternative| alternative.for_each_binding(visit))
+            }
+
+            Pattern::Wildcard(_)
+            | Pattern::Integer(_)
+            | Pattern::Boolean(_)
+            | Pattern::Range(_)
+            | Pattern::Variant(_) => {}
+        }
+    }
+}
+
+/// An integer literal in a pattern, which may be negated (unlike in expressions, where negation is an operator).
+#[derive(Debug)]
+pub struct IntegerPattern {
+    /// The matching source, including the `-` if there is one.
+    pub matching_source: Fragment,
+
+    /// Whether the literal is negated.
+    pub is_negative: bool,
+
+    /// The literal.
+    pub literal: IntegerLiteral,
+}
+
+impl IntegerPattern {
+    /// Get the value this pattern matches.
+    pub fn value(&self) -> BigInt {
+        let value = BigInt::from(self.literal.value.clone());
+
+        match self.is_negative {
+            true => -value,
+            false => value,
+        }
+    }
+}
+
+/// A range of integers in a pattern, i.e. `0..10` (which excludes the end) or `1..=100` (which includes it).
+#[derive(Debug)]
+pub struct RangePattern {
+    /// The matching source, from the start of the range to its end.
+    pub matching_source: Fragment,
+
+    /// The first value in the range.
+    pub start: IntegerPattern,
+
+    /// The end of the range.
+    pub end: IntegerPattern,
+
+    /// Whether the end is part of the range (`..=`) or not (`..`).
+    pub is_inclusive: bool,
+}
+
+/// A record or union pattern, i.e. `Point { x: 0, y }`. Fields that aren't given match any value, and union
+/// patterns give exactly one variant.
+#[derive(Debug)]
+pub struct RecordPattern {
+    /// The matching source, from the start of the name to the closing curly brace.
+    pub matching_source: Fragment,
+
+    /// The name of the record or union type.
+    pub name: Path,
+
+    /// The fields given patterns, in source order.
+    pub fields: Vec<FieldPattern>,
+}
+
+/// The pattern given for a field in a [RecordPattern], i.e. `x: 0`. A field name on its own (i.e. `y`) binds the
+/// value of the field to that name.
+#[derive(Debug)]
+pub struct FieldPattern {
+    /// The matching source of the field name and pattern.
+    pub matching_source: Fragment,
+
+    /// The name of the field.
+    pub name: Identifier,
+
+    /// The pattern the field is matched against.
+    pub pattern: Pattern,
+}
+
+/// A choice between patterns, i.e. `Color::Red | Color::Blue`.
+#[derive(Debug)]
+pub struct OrPattern {
+    /// The matching source of every alternative.
+    pub matching_source: Fragment,
+
+    /// The patterns to choose between, in source order. There are always at least two.
+    pub alternatives: Vec<Pattern>,
+}
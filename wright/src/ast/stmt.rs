@@ -0,0 +1,112 @@
+//! Statement and block AST models.
+
+use crate::{
+    ast::{
+        expr::{BinaryOperation, Expr},
+        identifier::Identifier,
+        ty::Type,
+    },
+    source_tracking::fragment::Fragment,
+};
+
+/// A statement in a [Block].
+#[derive(Debug)]
+#[allow(missing_docs)]
+pub enum Stmt {
+    Let(LetStmt),
+    Assign(AssignStmt),
+    Expr(ExprStmt),
+}
+
+impl Stmt {
+    /// Get the matching source for this statement.
+    pub fn matching_source(&self) -> &Fragment {
+        match self {
+            Stmt::Let(let_stmt) => &let_stmt.matching_source,
+            Stmt::Assign(assign) => &assign.matching_source,
+            Stmt::Expr(expr_stmt) => &expr_stmt.matching_source,
+        }
+    }
+}
+
+/// A `let` or `var` binding, i.e. `let x: u8 = 5;`.
+#[derive(Debug)]
+pub struct LetStmt {
+    /// The matching source, including the semicolon.
+    pub matching_source: Fragment,
+
+    /// Whether the binding was declared with `var` (and can be re-assigned).
+    pub is_mutable: bool,
+
+    /// The name being bound.
+    pub name: Identifier,
+
+    /// The type annotation, if there is one.
+    pub ty: Option<Type>,
+
+    /// The value being bound.
+    pub value: Expr,
+}
+
+/// An assignment to a `var` binding (or a field of one, or through a reference), i.e. `x = 5;` or `x += 1;`.
+#[derive(Debug)]
+pub struct AssignStmt {
+    /// The matching source, including the semicolon.
+    pub matching_source: Fragment,
+
+    /// The place being assigned to.
+    pub target: Expr,
+
+    /// The operation of a compound assignment (i.e. [BinaryOperation::Add] for `+=`), or [None] for `=`.
+    pub op: Option<BinaryOperation>,
+
+    /// The operator in source code, i.e. `+=`.
+    pub op_fragment: Fragment,
+
+    /// The value being assigned.
+    pub value: Expr,
+}
+
+/// An expression used as a statement.
+#[derive(Debug)]
+pub struct ExprStmt {
+    /// The matching source, including the semicolon if there is one.
+    pub matching_source: Fragment,
+
+    /// The expression.
+    pub expr: Expr,
+}
+
+/// A block of statements, optionally ending with an expression that the block evaluates to.
+#[derive(Debug)]
+pub struct Block {
+    /// The matching source, including the curly braces.
+    pub matching_source: Fragment,
+
+    /// The statements in the block.
+    pub stmts: Vec<Stmt>,
+
+    /// The final expression of the block (with no semicolon), which the block evaluates to.
+    pub tail: Option<Box<Expr>>,
+}
+
+impl Block {
+    /// Call `visit` on each of the top-level expressions in this block -- `let` values, assignment targets and
+    /// values, expression statements, and the tail expression -- in source order.
+    pub fn for_each_expr<'a>(&'a self, visit: &mut impl FnMut(&'a Expr)) {
+        for stmt in &self.stmts {
+            match stmt {
+                Stmt::Let(let_stmt) => visit(&let_stmt.value),
+                Stmt::Assign(assign) => {
+                    visit(&assign.target);
+                    visit(&assign.value);
+                }
+                Stmt::Expr(expr_stmt) => visit(&expr_stmt.expr),
+            }
+        }
+
+        if let Some(tail) = &self.tail {
+            visit(tail);
+        }
+    }
+}
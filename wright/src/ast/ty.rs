@@ -9,7 +9,8 @@ pub enum Type {
     Atomic(AtomicTy),
     Reference(ReferenceTy),
     Named(NamedTy),
-    // Constrained(ConstrainedTy),
+    Constrained(ConstrainedTy),
+    Function(FunctionTy),
 }
 
 impl Type {
@@ -19,7 +20,8 @@ impl Type {
             Type::Atomic(atomic_ty) => &atomic_ty.matching_source,
             Type::Reference(reference_ty) => &reference_ty.matching_source,
             Type::Named(named_ty) => &named_ty.matching_source,
-            // Type::Constrained(constrained_ty) => &constrained_ty.matching_source,
+            Type::Constrained(constrained_ty) => &constrained_ty.matching_source,
+            Type::Function(function_ty) => &function_ty.matching_source,
         }
     }
 
@@ -47,17 +49,25 @@ impl Type {
         }
     }
 
-    // /// Attempt to "downcast" this to a constrained type signature if it is one.
-    // pub fn downcast_constrained_ty(&self) -> Option<&ConstrainedTy> {
-    //     match self {
-    //         Type::Constrained(constrained) => Some(constrained),
-    //         _ => None,
-    //     }
-    // }
+    /// Attempt to "downcast" this to a constrained type signature if it is one.
+    pub fn downcast_constrained_ty(&self) -> Option<&ConstrainedTy> {
+        match self {
+            Type::Constrained(constrained) => Some(constrained),
+            _ => None,
+        }
+    }
+
+    /// Attempt to "downcast" this to a function type signature if it is one.
+    pub fn downcast_function(&self) -> Option<&FunctionTy> {
+        match self {
+            Type::Function(function) => Some(function),
+            _ => None,
+        }
+    }
 }
 
 /// The atomic types of wright -- primitive numeric types, boolean, char, etc.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 #[allow(missing_docs)]
 pub enum AtomicTyVariant {
     Bool,
@@ -74,6 +84,67 @@ pub enum AtomicTyVariant {
     Char,
 }
 
+impl AtomicTyVariant {
+    /// Get the keyword used to write this type in source code, i.e. `u8`.
+    pub const fn name(self) -> &'static str {
+        use AtomicTyVariant::*;
+
+        match self {
+            Bool => "bool",
+            U8 => "u8",
+            I8 => "i8",
+            U16 => "u16",
+            I16 => "i16",
+            U32 => "u32",
+            I32 => "i32",
+            U64 => "u64",
+            I64 => "i64",
+            F32 => "f32",
+            F64 => "f64",
+            Char => "char",
+        }
+    }
+
+    /// Check if this is one of the integer types.
+    pub const fn is_integer(self) -> bool {
+        use AtomicTyVariant::*;
+        matches!(self, U8 | I8 | U16 | I16 | U32 | I32 | U64 | I64)
+    }
+
+    /// Get the smallest and largest values of this type if it's an integer type.
+    pub const fn integer_bounds(self) -> Option<(i128, i128)> {
+        use AtomicTyVariant::*;
+
+        Some(match self {
+            U8 => (0, u8::MAX as i128),
+            I8 => (i8::MIN as i128, i8::MAX as i128),
+            U16 => (0, u16::MAX as i128),
+            I16 => (i16::MIN as i128, i16::MAX as i128),
+            U32 => (0, u32::MAX as i128),
+            I32 => (i32::MIN as i128, i32::MAX as i128),
+            U64 => (0, u64::MAX as i128),
+            I64 => (i64::MIN as i128, i64::MAX as i128),
+            _ => return None,
+        })
+    }
+
+    /// Check if this is one of the signed integer types.
+    pub const fn is_signed_integer(self) -> bool {
+        use AtomicTyVariant::*;
+        matches!(self, I8 | I16 | I32 | I64)
+    }
+
+    /// Check if this is one of the floating point types.
+    pub const fn is_float(self) -> bool {
+        matches!(self, AtomicTyVariant::F32 | AtomicTyVariant::F64)
+    }
+
+    /// Check if this is a numeric (integer or floating point) type.
+    pub const fn is_numeric(self) -> bool {
+        self.is_integer() || self.is_float()
+    }
+}
+
 /// An atomic type signature in wright source code.
 #[derive(Debug)]
 #[allow(missing_docs)]
@@ -82,9 +153,11 @@ pub struct AtomicTy {
     pub matching_source: Fragment,
 }
 
-/// Source code for a reference type signature, such as `@u64`.
+/// Source code for a reference type signature, such as `@u64` or `@mut u64`.
 #[derive(Debug)]
 pub struct ReferenceTy {
+    /// Whether this is a mutable reference (`@mut T`), which allows assigning through it.
+    pub is_mutable: bool,
     /// The source code of the target type.
     pub target_ty: Box<Type>,
     /// The fragment of the whole reference.
@@ -108,35 +181,48 @@ pub struct NamedTy {
     // pub generic_consts: (),
 }
 
-// /// A type with a given set of constraints.
-// ///
-// /// Constraints in wright are functions that the compiler can verify are strictly [pure]
-// /// (which is informally defined here, and a point of further work eventually).
-// ///
-// /// A constrained type declaration lists a base type and then one or more "strictly pure"
-// /// functions that have a signature exactly matching T -> bool (where T is the constrained type).
-// ///
-// /// An example of this could be
-// /// ```text
-// /// pure func is_even(i: u8) -> bool {
-// ///     i % 2 == 0
-// /// }
-// ///
-// /// type EvenU8 = u8 constrain is_even;
-// /// ```
-// ///
-// /// The wright compiler can then optimize agressively around these constraints later on (I hope).
-// ///
-// /// [pure]: https://en.wikipedia.org/w/index.php?title=Pure_function&oldid=1291437073
-// #[derive(Debug)]
-// struct ConstrainedTy {
-//     /// The entire type signature from the beginning of the base type
-//     /// to the end of the last constraining item.
-//     pub matching_source: Fragment,
-
-//     /// The type being constrained.
-//     pub base_ty: Box<Type>,
-
-//     /// The functions constraining it.
-//     pub constraining_items: Vec<Path>,
-// }
+/// The type signature of a function or closure, i.e. `func(u8, u8) -> bool`.
+#[derive(Debug)]
+pub struct FunctionTy {
+    /// The matching source, from the `func` keyword to the end of the return type.
+    pub matching_source: Fragment,
+
+    /// The types of the parameters.
+    pub params: Vec<Type>,
+
+    /// The return type, if there is one.
+    pub return_ty: Option<Box<Type>>,
+}
+
+/// A type with a given set of constraints.
+///
+/// Constraints in wright are functions that the compiler can verify are strictly [pure]
+/// (which is informally defined here, and a point of further work eventually).
+///
+/// A constrained type declaration lists a base type and then one or more "strictly pure"
+/// functions that have a signature exactly matching T -> bool (where T is the constrained type).
+///
+/// An example of this could be
+/// ```text
+/// pure func is_even(i: u8) -> bool {
+///     i % 2 == 0
+/// }
+///
+/// type EvenU8 = u8 constrain is_even;
+/// ```
+///
+/// The wright compiler can then optimize agressively around these constraints later on (I hope).
+///
+/// [pure]: https://en.wikipedia.org/w/index.php?title=Pure_function&oldid=1291437073
+#[derive(Debug)]
+pub struct ConstrainedTy {
+    /// The entire type signature from the beginning of the base type
+    /// to the end of the last constraining item.
+    pub matching_source: Fragment,
+
+    /// The type being constrained.
+    pub base_ty: Box<Type>,
+
+    /// The functions constraining it.
+    pub constraining_items: Vec<Path>,
+}
@@ -1,10 +1,14 @@
 //! Command line interface for wright.
 
-use clap::{Parser, Subcommand};
-use std::{io, path::PathBuf};
+use clap::{Parser, Subcommand, ValueEnum};
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
 use wright::{
     lexer::Lexer,
-    source_tracking::{SourceMap, SourceRef, source::Source},
+    package::CheckedPackage,
+    source_tracking::{SourceMap, SourceRef, filename::FileName, source::Source},
 };
 
 /// The wright cli.
@@ -32,6 +36,57 @@ enum Command {
         command: DebugCommand,
     },
 
+    /// Load a package of wright source code and check it for errors (including type errors).
+    Check {
+        /// The root file of the package, or any file or directory in it (the package root is searched for
+        /// from there).
+        path: PathBuf,
+    },
+
+    /// Check a package and compile it to a bytecode file, which `wright run` runs (or to another kind of output,
+    /// with `--emit`).
+    Build {
+        /// The root file of the package, or any file or directory in it (the package root is searched for
+        /// from there).
+        path: PathBuf,
+
+        /// The file to write (by default, the package root's name with the extension of the kind of output).
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+
+        /// Leave out the debug info that maps the output back to the source code (`#line` directives in C, `!dbg`
+        /// metadata in LLVM IR, the `name` section of WebAssembly).
+        #[arg(long)]
+        strip: bool,
+
+        /// The kind of output to write.
+        #[arg(long, value_enum, default_value_t = Emit::Bytecode)]
+        emit: Emit,
+    },
+
+    /// Run the `main` function of a bytecode file written by `wright build` (or of a package, compiled to machine
+    /// code in memory, with `--jit`).
+    Run {
+        /// The bytecode file (or, with `--jit`, the root file of the package, or any file or directory in it).
+        file: PathBuf,
+
+        /// Check the package and compile it to machine code with Cranelift, instead of loading a bytecode file.
+        #[cfg(feature = "cranelift")]
+        #[arg(long)]
+        jit: bool,
+    },
+
+    /// Generate HTML documentation for a package of wright source code.
+    Doc {
+        /// The root file of the package, or any file or directory in it (the package root is searched for
+        /// from there).
+        path: PathBuf,
+
+        /// The directory to write the generated documentation to.
+        #[arg(short, long, default_value = "doc")]
+        out: PathBuf,
+    },
+
     /// Subcommand for showing information about this version of wright.
     Show {
         #[command(subcommand)]
@@ -39,6 +94,39 @@ enum Command {
     },
 }
 
+/// The kinds of output that `wright build` can write.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Emit {
+    /// A bytecode file (`.wbc`), which `wright run` runs.
+    Bytecode,
+    /// A self-contained C source file (`.c`).
+    C,
+    /// A textual LLVM IR module (`.ll`), for `llc` or `clang`.
+    #[value(name = "ll")]
+    Llvm,
+    /// A WebAssembly module (`.wasm`), which imports printing and trapping from the `wright::io` and
+    /// `wright::runtime` modules.
+    Wasm,
+    /// An object file (`.o`) for this machine, compiled with Cranelift, which must be linked with the runtime
+    /// (written next to it).
+    #[cfg(feature = "cranelift")]
+    Object,
+}
+
+impl Emit {
+    /// The extension of files of this kind of output.
+    const fn extension(self) -> &'static str {
+        match self {
+            Emit::Bytecode => "wbc",
+            Emit::C => "c",
+            Emit::Llvm => "ll",
+            Emit::Wasm => "wasm",
+            #[cfg(feature = "cranelift")]
+            Emit::Object => "o",
+        }
+    }
+}
+
 /// Different sub-commands that the debug sub-command supports.
 #[derive(Subcommand, Debug)]
 enum DebugCommand {
@@ -51,6 +139,37 @@ enum DebugCommand {
         // #[arg(short, long)]
         // pretty: bool,
     },
+
+    /// Print the disassembled bytecode of a file of wright source code (compiled like `wright build` does) or of
+    /// a bytecode file, with lines of source code interleaved from its debug info.
+    Bytecode {
+        /// A file of wright source code, loaded as the root of a package, or a `.wbc` file.
+        file: PathBuf,
+    },
+
+    /// Print the mid-level IR of a file (and the modules it declares), once it's been checked without errors.
+    Mir {
+        /// A file of wright source code, loaded as the root of a package.
+        file: PathBuf,
+    },
+
+    /// Print how values of a type are laid out in memory: their size and alignment, where each field (or the payload
+    /// of each variant) is, how variants are told apart, and which invalid values are left for a niche.
+    ///
+    /// The code generation backends don't use this layout yet -- they lay values out their own way.
+    Layout {
+        /// The type, written as it would be in the root module of the package (i.e. `Option<@u8>`).
+        ty: String,
+
+        /// The root file of the package, or any file or directory in it (the package root is searched for from
+        /// there).
+        #[arg(default_value = ".")]
+        path: PathBuf,
+
+        /// The size of pointers on the target, in bytes (1, 2, 4, or 8).
+        #[arg(long, default_value_t = wright::analysis::layout::DEFAULT_POINTER_SIZE)]
+        pointer_size: u64,
+    },
 }
 
 /// Different subcommands that can be used to get info about a copy of the wright CLI/compiler/etc.
@@ -88,6 +207,260 @@ fn main() -> io::Result<()> {
             }
         }
 
+        // Compile a checked file (or load a bytecode file) and disassemble it.
+        Command::Debug {
+            command: DebugCommand::Bytecode { file },
+        } => {
+            let source_map: SourceMap = SourceMap::new();
+
+            let module = match file.extension().is_some_and(|extension| extension == "wbc") {
+                true => {
+                    // Invalid modules are still shown, since they're usually what needs debugging.
+                    let module =
+                        wright::vm::wbc::read(&std::fs::read(&file)?).map_err(|error| {
+                            io::Error::new(
+                                io::ErrorKind::InvalidData,
+                                format!("{}: {error}", file.display()),
+                            )
+                        })?;
+
+                    for problem in wright::vm::verify(&module) {
+                        eprintln!("invalid bytecode: {problem}");
+                    }
+
+                    // The sources named in the debug info are read from disk if they're still there.
+                    for name in module.debug.iter().flat_map(|debug| &debug.files) {
+                        let path = PathBuf::from(name);
+
+                        if path.is_file() {
+                            source_map.add(Source::new_mapped_or_read(path)?);
+                        }
+                    }
+
+                    module
+                }
+
+                false => {
+                    let package = check_package(&source_map, &file)?;
+                    let program = package.lower();
+                    wright::vm::compile(&program, &package.resolution, &package.typeck)
+                }
+            };
+
+            print!("{}", module.disassemble(Some(&source_map)));
+        }
+
+        // Lower a checked file to MIR and print it.
+        Command::Debug {
+            command: DebugCommand::Mir { file },
+        } => {
+            let source_map: SourceMap = SourceMap::new();
+            let package = check_package(&source_map, &file)?;
+            let program = package.lower();
+
+            print!("{}", program.display(&package.resolution));
+
+            let problems: Vec<String> = program
+                .bodies
+                .iter()
+                .flat_map(wright::mir::verify)
+                .collect();
+
+            for problem in &problems {
+                eprintln!("invalid MIR: {problem}");
+            }
+
+            if !problems.is_empty() {
+                return Err(io::Error::other(format!("the MIR of {} is invalid", file.display())));
+            }
+        }
+
+        // Check a package and print the layout of a type in it.
+        Command::Debug {
+            command:
+                DebugCommand::Layout {
+                    ty,
+                    path,
+                    pointer_size,
+                },
+        } => {
+            if !pointer_size.is_power_of_two() || pointer_size > 8 {
+                return Err(io::Error::other(format!("unsupported pointer size {pointer_size}")));
+            }
+
+            let source_map: SourceMap = SourceMap::new();
+            let root = wright::package::find_package_root(&path)?;
+            let package = check_package(&source_map, &root)?;
+            let source_ref = source_map.add(Source::new_from_string(FileName::None, ty.clone()));
+            let mut parser = wright::parser::Parser::new(Lexer::new(source_ref));
+
+            let signature = wright::ast::ty::Type::parse(&mut parser).map_err(|error| {
+                let _ = error.as_diagnostic().print(&source_map);
+                io::Error::other(format!("`{ty}` is not a type signature"))
+            })?;
+
+            parser.consume_optional_whitespace();
+
+            if parser.bytes_remaining() > 0 {
+                return Err(io::Error::other(format!("`{ty}` is not a single type signature")));
+            }
+
+            let layout_ty = wright::analysis::layout::lower_signature(
+                &signature,
+                &package.resolution,
+                &package.typeck,
+            )
+            .map_err(io::Error::other)?;
+
+            let mut cx = wright::analysis::layout::LayoutCx::new(
+                package.package.module_paths(),
+                &package.resolution,
+                &package.typeck,
+                pointer_size,
+            );
+
+            let layout = (cx.layout_of(&layout_ty))
+                .map_err(|error| io::Error::other(error.message(&package.resolution)))?;
+
+            println!("layout of `{}`:", layout_ty.display(&package.resolution));
+            print!("{}", layout.display(&package.resolution));
+            println!("note: the code generation backends don't use this layout yet");
+        }
+
+        // Load a package and report any errors in it.
+        Command::Check { path } => {
+            let source_map: SourceMap = SourceMap::new();
+            let root = wright::package::find_package_root(&path)?;
+            let package = check_package(&source_map, &root)?;
+
+            println!("checked {} ({} modules)", root.display(), package.package.modules.len());
+        }
+
+        // Check a package and write its bytecode (or C source, LLVM IR, WebAssembly, or object file) to a file.
+        Command::Build {
+            path,
+            out,
+            strip,
+            emit,
+        } => {
+            let source_map: SourceMap = SourceMap::new();
+            let root = wright::package::find_package_root(&path)?;
+            let package = check_package(&source_map, &root)?;
+            let program = package.lower();
+
+            let bytes = match emit {
+                Emit::Bytecode => {
+                    let mut module =
+                        wright::vm::compile(&program, &package.resolution, &package.typeck);
+
+                    if strip {
+                        module.debug = None;
+                    }
+
+                    wright::vm::wbc::write(&module)
+                }
+
+                Emit::C => wright::codegen::c::transpile(
+                    &program,
+                    &package.resolution,
+                    &package.typeck,
+                    !strip,
+                )
+                .into_bytes(),
+
+                Emit::Llvm => wright::codegen::llvm::emit(
+                    &program,
+                    &package.resolution,
+                    &package.typeck,
+                    !strip,
+                )
+                .into_bytes(),
+
+                Emit::Wasm => wright::codegen::wasm::emit(
+                    &program,
+                    &package.resolution,
+                    &package.typeck,
+                    !strip,
+                ),
+
+                #[cfg(feature = "cranelift")]
+                Emit::Object => wright::codegen::cranelift::object(
+                    &program,
+                    &package.resolution,
+                    &package.typeck,
+                )
+                .map_err(io::Error::other)?,
+            };
+
+            let out = out.unwrap_or_else(|| root.with_extension(emit.extension()));
+            std::fs::write(&out, bytes)?;
+            println!("compiled {} to {}", root.display(), out.display());
+
+            // Object files call a runtime, which is C source to link them with.
+            #[cfg(feature = "cranelift")]
+            if emit == Emit::Object {
+                let runtime = out.with_extension("runtime.c");
+                std::fs::write(&runtime, wright::codegen::cranelift::RUNTIME)?;
+                println!(
+                    "wrote the runtime to {0}; link with `cc {1} {0} -lm`",
+                    runtime.display(),
+                    out.display()
+                );
+            }
+        }
+
+        // Load a bytecode file, and run it.
+        Command::Run {
+            file,
+            #[cfg(feature = "cranelift")]
+            jit,
+        } => {
+            // Or check a package, compile it to machine code in memory, and run that.
+            #[cfg(feature = "cranelift")]
+            if jit {
+                let source_map: SourceMap = SourceMap::new();
+                let root = wright::package::find_package_root(&file)?;
+                let package = check_package(&source_map, &root)?;
+                let program = package.lower();
+
+                return wright::codegen::cranelift::run(
+                    &program,
+                    &package.resolution,
+                    &package.typeck,
+                    &mut io::stdout().lock(),
+                )
+                .map_err(|error| io::Error::other(error.to_string()));
+            }
+
+            let bytes = std::fs::read(&file)?;
+            let module = wright::vm::wbc::load(&bytes).map_err(|error| {
+                io::Error::new(io::ErrorKind::InvalidData, format!("{}: {error}", file.display()))
+            })?;
+
+            if let Err(trap) = wright::vm::Vm::new(&module).run(&mut io::stdout().lock()) {
+                let message = match trap.location(&module) {
+                    Some((file, location)) => {
+                        format!("{trap}, at {file}:{}:{}", location.line, location.column)
+                    }
+                    None => trap.to_string(),
+                };
+
+                return Err(io::Error::other(message));
+            }
+        }
+
+        // Generate documentation for a package, reporting any problems found while loading it.
+        Command::Doc { path, out } => {
+            let source_map: SourceMap = SourceMap::new();
+            let diagnostics = wright::doc::generate(&source_map, &path, &out)?;
+
+            for diagnostic in &diagnostics {
+                diagnostic.print(&source_map).map_err(io::Error::other)?;
+            }
+
+            println!("documentation written to {}", out.join("index.html").display());
+        }
+
         Command::Show {
             command: ShowCommand::Version,
         } => {
@@ -105,3 +478,18 @@ fn main() -> io::Result<()> {
 
     Ok(())
 }
+
+/// Load a package from its root file and run every check over it, printing any diagnostics. Returns an error if
+/// any of them are errors.
+fn check_package(source_map: &SourceMap, root: &Path) -> io::Result<CheckedPackage> {
+    let package = wright::package::check_package(wright::package::load_package(source_map, root)?);
+
+    for diagnostic in package.diagnostics() {
+        diagnostic.print(source_map).map_err(io::Error::other)?;
+    }
+
+    match package.errors() {
+        0 => Ok(package),
+        errors => Err(io::Error::other(format!("found {errors} error(s) in {}", root.display()))),
+    }
+}
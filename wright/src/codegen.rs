@@ -0,0 +1,701 @@
+//! Generating code for other platforms from the MIR.
+//!
+//! Unlike the [VM](crate::vm), which runs generic functions on values that carry their type, these backends make a
+//! copy of every generic function for each set of types it's called with -- an instance of its body. Instances are
+//! found while code is generated: starting from every function that isn't generic, each call of a generic function
+//! binds its generic parameters to the types of the call's arguments, and each call of a trait method
+//! on a receiver of a known type runs the method of the implementation for that type.
+
+use crate::{
+    analysis::{
+        builtins::Builtin,
+        resolve::{DefId, DefKind, Resolution},
+        typeck::{TypeckResults, ty::Ty},
+    },
+    ast::{expr::BinaryOperation, ty::AtomicTyVariant},
+    mir::{Body, Const, Operand, Program, Value, qualified_name},
+};
+use num::BigInt;
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    fmt::Write,
+    ops::Range,
+    rc::Rc,
+};
+
+pub mod c;
+#[cfg(feature = "cranelift")]
+pub mod cranelift;
+pub mod llvm;
+pub mod wasm;
+
+/// A body of the MIR, with a type for each of the generic parameters it mentions.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct Instance {
+    /// The index of the body in [Program::bodies].
+    pub body: usize,
+    /// The type of each generic parameter, in the order of the parameters' definitions.
+    pub args: Vec<(DefId, Ty)>,
+}
+
+impl Instance {
+    /// Get the substitution of the instance's types for its generic parameters.
+    pub fn substitution(&self) -> HashMap<DefId, Ty> {
+        self.args.iter().cloned().collect()
+    }
+}
+
+/// What a call with a constant callee runs.
+#[derive(Clone, Debug)]
+pub(crate) enum Callee {
+    /// An instance of the body of a function.
+    Instance(Instance),
+    /// A builtin function.
+    Builtin(Builtin),
+    /// Nothing that can run -- the call traps with this message.
+    Missing(String),
+}
+
+/// The instances of bodies that code has been (or will be) generated for, each with a unique name.
+#[derive(Debug, Default)]
+pub(crate) struct Instances {
+    /// Every instance with its name, in the order they were found.
+    list: Vec<(Instance, String)>,
+    /// The index of every instance in the list.
+    indices: HashMap<Instance, usize>,
+    /// How many instances have been taken by [Instances::next].
+    taken: usize,
+}
+
+impl Instances {
+    /// Get the name of an instance, adding it with the given name (which must be unique) if it's new.
+    pub fn get(&mut self, instance: Instance, name: impl FnOnce() -> String) -> &str {
+        let index = match self.indices.get(&instance) {
+            Some(index) => *index,
+            None => {
+                self.list.push((instance.clone(), name()));
+                self.indices.insert(instance, self.list.len() - 1);
+                self.list.len() - 1
+            }
+        };
+
+        &self.list[index].1
+    }
+
+    /// Take the next instance that code hasn't been generated for yet.
+    pub fn next(&mut self) -> Option<(Instance, String)> {
+        let next = self.list.get(self.taken).cloned()?;
+        self.taken += 1;
+        Some(next)
+    }
+}
+
+/// The program that code is being generated for, with the results of the analysis passes.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Context<'a> {
+    /// The program.
+    pub program: &'a Program,
+    /// The result of name resolution.
+    pub resolution: &'a Resolution,
+    /// The result of type checking.
+    pub typeck: &'a TypeckResults,
+}
+
+impl Context<'_> {
+    /// Get what kind of named type a type is, if it's a record, union, or enum. Abstract types are none of these.
+    pub fn named_kind(&self, ty: &Ty) -> Option<NamedKind> {
+        let Ty::Named { def, .. } = ty else {
+            return None;
+        };
+
+        match (self.resolution.def(*def).kind, self.typeck.fields(ty)) {
+            (DefKind::Enum, _) => Some(NamedKind::Enum),
+            (_, Some((true, _))) => Some(NamedKind::Union),
+            (_, Some((false, _))) => Some(NamedKind::Record),
+            (_, None) => None,
+        }
+    }
+
+    /// Get the instances that every program has: one of each function and method that isn't generic.
+    pub fn roots(&self) -> impl Iterator<Item = Instance> + '_ {
+        (self.program.bodies.iter().enumerate())
+            .filter(|(_, body)| body.def.is_some() && generic_params(body).is_empty())
+            .map(|(body, _)| Instance {
+                body,
+                args: Vec::new(),
+            })
+    }
+
+    /// Get the instance of a body that uses the given types for its generic parameters, or the names of the
+    /// generic parameters whose types are missing.
+    pub fn instance(
+        &self,
+        body: usize,
+        substitution: &HashMap<DefId, Ty>,
+    ) -> Result<Instance, Vec<String>> {
+        let params = generic_params(&self.program.bodies[body]);
+
+        let missing: Vec<String> = (params.iter())
+            .filter(|param| substitution.get(param).is_none_or(is_generic))
+            .map(|param| self.resolution.def(*param).name.clone())
+            .collect();
+
+        if !missing.is_empty() {
+            return Err(missing);
+        }
+
+        Ok(Instance {
+            body,
+            args: (params.into_iter())
+                .map(|param| (param, substitution[&param].clone()))
+                .collect(),
+        })
+    }
+
+    /// Find what a call of a definition runs, given the (concrete) types of its arguments and result. Trait
+    /// methods run the method of the implementation for the type of their receiver.
+    pub fn callee(&self, def: DefId, args: &[Ty], ret: &Ty) -> Callee {
+        if let Some(builtin) = self.resolution.builtin(def) {
+            return Callee::Builtin(builtin);
+        }
+
+        let mut def = def;
+
+        if self.typeck.trait_of_method(def).is_some() {
+            let Some(receiver) = args.first() else {
+                return Callee::Missing(self.missing(def));
+            };
+
+            let receiver = strip_references(receiver);
+
+            def = match self.typeck.implementation(def, receiver) {
+                Some(implementation) => implementation,
+                None => {
+                    return Callee::Missing(format!(
+                        "`{}` isn't implemented for `{}`",
+                        self.name(def),
+                        receiver.display(self.resolution)
+                    ));
+                }
+            };
+        }
+
+        let Some(body) = self.program.function(def) else {
+            return Callee::Missing(self.missing(def));
+        };
+
+        let body = body.0 as usize;
+        let declared = &self.program.bodies[body];
+        let mut substitution = HashMap::new();
+
+        for (param, arg) in declared.params.iter().zip(args) {
+            bind(declared.value_ty(*param), arg, &mut substitution);
+        }
+
+        bind(&declared.ret, ret, &mut substitution);
+
+        match self.instance(body, &substitution) {
+            Ok(instance) => Callee::Instance(instance),
+            Err(missing) => Callee::Missing(format!(
+                "the types of the generic parameters of `{}` aren't known: {}",
+                self.name(def),
+                missing.join(", ")
+            )),
+        }
+    }
+
+    /// Find what a definition used as a value (of the given concrete function type) runs. Trait methods run the
+    /// method of the implementation for the type of their first parameter.
+    pub fn callee_value(&self, def: DefId, ty: &Ty) -> Callee {
+        match ty {
+            Ty::Function { params, ret } => self.callee(def, params, ret),
+            _ => self.callee(def, &[], &Ty::Error),
+        }
+    }
+
+    /// Describe why a definition without a body can't be run.
+    pub fn missing(&self, def: DefId) -> String {
+        let name = self.name(def);
+
+        match self.resolution.def(def).kind {
+            DefKind::Constant => {
+                format!(
+                    "the value of the constant `{name}` isn't known, since constants aren't evaluated yet"
+                )
+            }
+            DefKind::Method => format!(
+                "the trait method `{name}` can't be used as a value, since the type it's called on isn't known"
+            ),
+            kind => format!("`{name}` is a {} without a body", kind.describe()),
+        }
+    }
+
+    /// Get the name of a definition, as it's printed in the MIR.
+    pub fn name(&self, def: DefId) -> String {
+        match self.program.names.get(&def) {
+            Some(name) => name.clone(),
+            None => qualified_name(self.resolution, def),
+        }
+    }
+}
+
+/// How many bytes into a union its variant is, after the tag.
+pub(crate) const UNION_PAYLOAD: u32 = 8;
+
+/// The message of the trap for shifting by too many bits.
+pub(crate) const SHIFT_TRAP: &str = "shift by at least the number of bits in the type";
+
+/// The message of the trap for using a variant of a union that doesn't hold it.
+pub(crate) const VARIANT_TRAP: &str = "the union doesn't hold the variant that's used";
+
+/// What kind of named type a type is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum NamedKind {
+    /// A record.
+    Record,
+    /// A union.
+    Union,
+    /// An enum.
+    Enum,
+}
+
+/// A function generated for a type, rather than from a body.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum Helper {
+    /// Printing a record, union, or enum.
+    Print,
+    /// Checking if two records or unions are equal.
+    Eq,
+}
+
+/// A function made into a function value, which takes an environment it ignores.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum Thunk {
+    /// An instance of a body.
+    Instance(Instance),
+    /// A builtin function, for a value of the given (function) type.
+    Builtin(Builtin, Ty),
+}
+
+/// How a place is used.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Access {
+    /// Its value is loaded.
+    Load,
+    /// A value is stored to it.
+    Store,
+    /// A reference to it is made.
+    Ref,
+}
+
+/// A kind of scalar that a backend keeps the parts of values in.
+pub(crate) trait Scalar: Copy {
+    /// A 32-bit integer, which enums and the tags of unions are.
+    const I32: Self;
+    /// A 64-bit integer, which the payloads of unions are made of.
+    const I64: Self;
+
+    /// Get the scalar of an atomic type.
+    fn atomic(variant: AtomicTyVariant) -> Self;
+
+    /// Get the size of the scalar in memory, in bytes (which is also its alignment).
+    fn size(self) -> u32;
+}
+
+/// Where the scalars of a type are in memory.
+#[derive(Debug)]
+pub(crate) struct Layout<S> {
+    /// The size, in bytes.
+    pub size: u32,
+    /// The alignment, in bytes.
+    pub align: u32,
+    /// The offset and kind of every scalar.
+    pub parts: Vec<(u32, S)>,
+}
+
+impl<S> Default for Layout<S> {
+    fn default() -> Self {
+        Layout {
+            size: 0,
+            align: 0,
+            parts: Vec::new(),
+        }
+    }
+}
+
+/// A field of a record (or a variant of a union).
+#[derive(Debug)]
+pub(crate) struct Field {
+    /// The name.
+    pub name: String,
+    /// The type.
+    pub ty: Ty,
+    /// How many bytes into the record (or union) it is.
+    pub offset: u32,
+    /// The indices of its scalars in the scalars of the record (which is empty for variants).
+    pub parts: Range<usize>,
+}
+
+/// The layouts of the (concrete) types of a program, for a backend that keeps values in scalars of type `S`.
+///
+/// Records are laid out like C structs, with their fields in the order they're declared. Unions are a 32-bit tag
+/// followed by their variant at [UNION_PAYLOAD], which is moved around as 64-bit words. Function values are their
+/// code, environment, and name, and strings are their address and length.
+///
+/// These aren't the layouts of [analysis::layout](crate::analysis::layout), which `wright debug layout` prints:
+/// fields aren't reordered, unions don't store their variants in niches, and `repr(...)` clauses are ignored. Moving
+/// the backends over would change how compiled code passes values to the runtimes, so it's left for later.
+#[derive(Debug)]
+pub(crate) struct Layouts<'a, S> {
+    /// The program the types are from.
+    cx: Context<'a>,
+    /// The scalar that pointers (and sizes) are.
+    pointer: S,
+    /// The layout of every type that's been used.
+    cache: HashMap<Ty, Rc<Layout<S>>>,
+}
+
+impl<'a, S: Scalar> Layouts<'a, S> {
+    /// Start laying out the types of a program, with pointers of the given scalar.
+    pub fn new(cx: Context<'a>, pointer: S) -> Self {
+        Layouts {
+            cx,
+            pointer,
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Get the layout of a (concrete) type.
+    pub fn layout(&mut self, ty: &Ty) -> Rc<Layout<S>> {
+        if let Some(layout) = self.cache.get(ty) {
+            return layout.clone();
+        }
+
+        let scalar = |scalar: S| Layout {
+            size: scalar.size(),
+            align: scalar.size(),
+            parts: vec![(0, scalar)],
+        };
+
+        let layout = match ty {
+            Ty::Atomic(variant) => scalar(S::atomic(*variant)),
+            Ty::Reference { .. } => scalar(self.pointer),
+            Ty::Function { .. } => struct_layout((0..3).map(|_| scalar(self.pointer))),
+            Ty::Str => struct_layout((0..2).map(|_| scalar(self.pointer))),
+
+            Ty::Named { .. } => match self.cx.named_kind(ty) {
+                Some(NamedKind::Enum) => scalar(S::I32),
+
+                Some(NamedKind::Record) => {
+                    let (_, fields) = self.cx.typeck.fields(ty).unwrap_or_default();
+                    let fields: Vec<Rc<Layout<S>>> =
+                        fields.iter().map(|(_, ty)| self.layout(ty)).collect();
+                    struct_layout(fields.iter().map(|layout| Layout {
+                        size: layout.size,
+                        align: layout.align,
+                        parts: layout.parts.clone(),
+                    }))
+                }
+
+                Some(NamedKind::Union) => {
+                    let (_, variants) = self.cx.typeck.fields(ty).unwrap_or_default();
+                    let payload = (variants.iter())
+                        .map(|(_, ty)| self.layout(ty).size)
+                        .max()
+                        .unwrap_or_default()
+                        .next_multiple_of(8);
+
+                    let words = (0..payload / 8).map(|word| (UNION_PAYLOAD + word * 8, S::I64));
+
+                    Layout {
+                        size: UNION_PAYLOAD + payload,
+                        align: 8,
+                        parts: [(0, S::I32)].into_iter().chain(words).collect(),
+                    }
+                }
+
+                None => Layout::default(),
+            },
+
+            Ty::Void | Ty::Param(_) | Ty::Infer(_) | Ty::Integer(_) | Ty::Error => Layout {
+                align: 1,
+                ..Layout::default()
+            },
+        };
+
+        let layout = Rc::new(layout);
+        self.cache.insert(ty.clone(), layout.clone());
+        layout
+    }
+
+    /// Get the fields of a record (or the variants of a union), with where they are.
+    pub fn fields(&mut self, ty: &Ty) -> (bool, Vec<Field>) {
+        let (is_union, declared) = self.cx.typeck.fields(ty).unwrap_or_default();
+        let mut fields = Vec::new();
+        let (mut offset, mut part) = (0u32, 0);
+
+        for (name, ty) in declared {
+            let layout = self.layout(&ty);
+
+            let field = match is_union {
+                true => Field {
+                    name: name.to_owned(),
+                    ty,
+                    offset: UNION_PAYLOAD,
+                    parts: 0..0,
+                },
+
+                false => {
+                    offset = offset.next_multiple_of(layout.align.max(1));
+                    let field = Field {
+                        name: name.to_owned(),
+                        ty,
+                        offset,
+                        parts: part..part + layout.parts.len(),
+                    };
+
+                    offset += layout.size;
+                    part += layout.parts.len();
+                    field
+                }
+            };
+
+            fields.push(field);
+        }
+
+        (is_union, fields)
+    }
+
+    /// Get the offsets of values of the given types stored one after another (like in the environment of a
+    /// closure), and their total size.
+    pub fn offsets(&mut self, tys: &[Ty]) -> (Vec<u32>, u32) {
+        let mut offsets = Vec::new();
+        let mut size = 0u32;
+
+        for ty in tys {
+            let layout = self.layout(ty);
+            size = size.next_multiple_of(layout.align.max(1));
+            offsets.push(size);
+            size += layout.size;
+        }
+
+        (offsets, size)
+    }
+}
+
+/// Get the layout of a struct with the given fields, each aligned to its alignment.
+fn struct_layout<S: Copy>(fields: impl IntoIterator<Item = Layout<S>>) -> Layout<S> {
+    let mut layout = Layout {
+        size: 0,
+        align: 1,
+        parts: Vec::new(),
+    };
+
+    for field in fields {
+        let offset = layout.size.next_multiple_of(field.align.max(1));
+        layout.parts.extend(
+            field
+                .parts
+                .iter()
+                .map(|(part, scalar)| (offset + part, *scalar)),
+        );
+        layout.size = offset + field.size;
+        layout.align = layout.align.max(field.align);
+    }
+
+    layout.size = layout.size.next_multiple_of(layout.align);
+    layout
+}
+
+/// Split the parameters of a body into the values it captures and the parameters it's called with, given how many
+/// values it captures if it's the body of a closure. The values a closure captures are the first parameters of its
+/// body, and arrive in its environment (which the function takes before its other parameters).
+pub(crate) fn split_captures(body: &Body, captures: Option<usize>) -> (&[Value], &[Value]) {
+    (body.params).split_at(captures.unwrap_or_default().min(body.params.len()))
+}
+
+/// How the operands of a comparison are compared.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum CompareAs {
+    /// As floats. Like in Rust, `!=` is true if either side is NaN, and every other comparison is false.
+    Float,
+    /// As signed integers.
+    Signed,
+    /// As unsigned integers, or as scalars that are only compared with `==` and `!=`.
+    Unsigned,
+}
+
+/// Get how the operands of a binary operation on values of an atomic type (or of another type, if it's none) are
+/// compared, if it's a comparison.
+pub(crate) fn compare_as(
+    op: BinaryOperation,
+    atomic: Option<AtomicTyVariant>,
+) -> Option<CompareAs> {
+    let is_comparison = matches!(
+        op,
+        BinaryOperation::Equal
+            | BinaryOperation::NotEqual
+            | BinaryOperation::Less
+            | BinaryOperation::LessOrEqual
+            | BinaryOperation::Greater
+            | BinaryOperation::GreaterOrEqual
+    );
+
+    Some(match atomic {
+        _ if !is_comparison => return None,
+        Some(atomic) if atomic.is_float() => CompareAs::Float,
+        Some(atomic) if atomic.is_signed_integer() => CompareAs::Signed,
+        _ => CompareAs::Unsigned,
+    })
+}
+
+/// Get the message of the trap that an operation on integers of the given number of bits always stops at, if its
+/// right side is a constant that it can't be done with: dividing by zero, or shifting by too many bits.
+pub(crate) fn constant_trap(op: BinaryOperation, rhs: &Operand, bits: u32) -> Option<&'static str> {
+    let Operand::Const(Const::Int(constant, _)) = rhs else {
+        return None;
+    };
+
+    match op {
+        BinaryOperation::Divide | BinaryOperation::Modulo if *constant == BigInt::ZERO => {
+            Some("division by zero")
+        }
+
+        BinaryOperation::ShiftLeft | BinaryOperation::ShiftRight
+            if *constant < BigInt::ZERO || *constant >= BigInt::from(bits) =>
+        {
+            Some(SHIFT_TRAP)
+        }
+
+        _ => None,
+    }
+}
+
+/// Bind the generic parameters in a type (as it's declared) to the parts of a concrete type in the same places.
+/// Parameters that are already bound keep their type.
+pub(crate) fn bind(declared: &Ty, concrete: &Ty, substitution: &mut HashMap<DefId, Ty>) {
+    match (declared, concrete) {
+        (Ty::Param(param), _) => {
+            substitution
+                .entry(*param)
+                .or_insert_with(|| concrete.clone());
+        }
+
+        (
+            Ty::Reference { target, .. },
+            Ty::Reference {
+                target: concrete, ..
+            },
+        ) => bind(target, concrete, substitution),
+
+        (
+            Ty::Named { def, args },
+            Ty::Named {
+                def: concrete_def,
+                args: concrete_args,
+            },
+        ) if def == concrete_def => {
+            for (arg, concrete) in args.iter().zip(concrete_args) {
+                bind(arg, concrete, substitution);
+            }
+        }
+
+        (
+            Ty::Function { params, ret },
+            Ty::Function {
+                params: concrete_params,
+                ret: concrete_ret,
+            },
+        ) => {
+            for (param, concrete) in params.iter().zip(concrete_params) {
+                bind(param, concrete, substitution);
+            }
+
+            bind(ret, concrete_ret, substitution);
+        }
+
+        _ => {}
+    }
+}
+
+/// Get the generic parameters mentioned anywhere in a body.
+pub(crate) fn generic_params(body: &Body) -> BTreeSet<DefId> {
+    let mut params = BTreeSet::new();
+
+    for ty in (body.values.iter())
+        .chain(body.slots.iter().map(|slot| &slot.ty))
+        .chain([&body.ret])
+    {
+        collect_params(ty, &mut params);
+    }
+
+    params
+}
+
+/// Add the generic parameters mentioned in a type to a set.
+fn collect_params(ty: &Ty, params: &mut BTreeSet<DefId>) {
+    match ty {
+        Ty::Param(param) => _ = params.insert(*param),
+        Ty::Reference { target, .. } => collect_params(target, params),
+        Ty::Named { args, .. } => args.iter().for_each(|arg| collect_params(arg, params)),
+        Ty::Function { params: tys, ret } => {
+            tys.iter().for_each(|ty| collect_params(ty, params));
+            collect_params(ret, params);
+        }
+        _ => {}
+    }
+}
+
+/// Check if a type mentions a generic parameter anywhere.
+pub(crate) fn is_generic(ty: &Ty) -> bool {
+    let mut params = BTreeSet::new();
+    collect_params(ty, &mut params);
+    !params.is_empty()
+}
+
+/// Get the type of the value a reference (or a reference to a reference, and so on) points to.
+pub(crate) fn strip_references(mut ty: &Ty) -> &Ty {
+    while let Ty::Reference { target, .. } = ty {
+        ty = target;
+    }
+
+    ty
+}
+
+/// Get the type of the value a reference points to.
+pub(crate) fn strip_one_reference(ty: &Ty) -> Ty {
+    match ty {
+        Ty::Reference { target, .. } => (**target).clone(),
+        _ => Ty::Error,
+    }
+}
+
+/// Make a name into part of an identifier (in C, LLVM IR, and so on): ASCII letters and digits are kept, other
+/// letters are written as their code point, and anything else becomes an underscore.
+pub(crate) fn mangle(name: &str) -> String {
+    let mut mangled = String::new();
+
+    for c in name.chars() {
+        match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' => mangled.push(c),
+            _ if c.is_alphanumeric() => _ = write!(mangled, "u{:04x}", c as u32),
+            _ if !mangled.ends_with('_') => mangled.push('_'),
+            _ => {}
+        }
+    }
+
+    mangled.trim_matches('_').to_owned()
+}
+
+/// Make a name unique among the names that have been given out already, by adding a number to it if needed.
+pub(crate) fn unique_name(names: &mut HashSet<String>, name: String) -> String {
+    let mut unique = name.clone();
+    let mut number = 2;
+
+    while !names.insert(unique.clone()) {
+        unique = format!("{name}_{number}");
+        number += 1;
+    }
+
+    unique
+}
@@ -0,0 +1,1748 @@
+//! Transpiling the MIR to portable C.
+//!
+//! A program becomes a single C11 source file that only needs the C standard library. Atomic types become the
+//! types of `<stdint.h>` (and `bool`, `float`, and `double`), records become structs, unions become a struct of an
+//! enum tag and a C union of the variants, and enums become C enums. References become pointers, and function
+//! values (including closures) become a `wr_func` of the code, an environment of captured values, and a name.
+//!
+//! Every SSA value of a body becomes a local variable, as does every slot, and every basic block becomes a label,
+//! with the arguments of a jump assigned to the target block's parameters before the `goto`. Each statement of the
+//! code of a MIR instruction is preceded by a `#line` directive that points back to the `.wr` source it was lowered
+//! from, so the C compiler's diagnostics and debug info -- and the location of a trap -- refer to the wright source.
+//! (A directive only sets the line of the line after it, so one per source line would drift.)
+//!
+//! The program behaves like it does in the [VM](crate::vm): integer overflow, division by zero, and the like stop
+//! it with an error (using the checked arithmetic of the prelude), and values are printed the same way. Unlike in
+//! the VM, slots live on the C stack, so a reference to one (or a closure that captures one) must not outlive the
+//! call that made it, and running out of stack space isn't caught.
+
+use super::{
+    Access, Callee, Context, Helper, Instance, Instances, NamedKind, SHIFT_TRAP, Thunk,
+    VARIANT_TRAP, constant_trap, mangle, split_captures, strip_one_reference, unique_name,
+};
+use crate::{
+    analysis::{
+        builtins::Builtin,
+        resolve::{DefId, Resolution},
+        typeck::{TypeckResults, ty::Ty},
+    },
+    ast::{
+        expr::{BinaryOperation, UnaryOperation},
+        ty::AtomicTyVariant,
+    },
+    mir::{
+        Body, Const, Inst, Jump, Operand, Place, PlaceBase, Program, Projection, Rvalue,
+        Terminator, Value,
+    },
+    source_tracking::fragment::Fragment,
+};
+use num::BigInt;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write,
+};
+
+/// The declarations every transpiled program starts with: the types that every program uses, checked
+/// arithmetic, and printing.
+const PRELUDE: &str = include_str!("c/prelude.h");
+
+/// Transpile every body of a program (which must have been checked without errors) to a C source file, which runs
+/// the program's `main` function if it has one. Each statement is preceded by a `#line` directive that points back
+/// to the wright source it came from, unless `line_directives` is false.
+pub fn transpile(
+    program: &Program,
+    resolution: &Resolution,
+    typeck: &TypeckResults,
+    line_directives: bool,
+) -> String {
+    let cx = Context {
+        program,
+        resolution,
+        typeck,
+    };
+
+    let mut transpiler = Transpiler {
+        cx,
+        line_directives,
+        instances: Instances::default(),
+        names: prelude_names(),
+        types: HashMap::new(),
+        pending_types: Vec::new(),
+        helpers: HashMap::new(),
+        pending_helpers: Vec::new(),
+        thunks: HashMap::new(),
+        closures: HashMap::new(),
+        declarations: String::new(),
+        definitions: String::new(),
+        prototypes: String::new(),
+        helper_code: String::new(),
+        function_code: String::new(),
+    };
+
+    let roots: Vec<Instance> = cx.roots().collect();
+    let mut entry = None;
+
+    for instance in roots {
+        let body = &program.bodies[instance.body];
+        let is_entry = body.name == "main" && body.params.is_empty();
+        let name = transpiler.instance_name(instance);
+
+        if is_entry {
+            entry = Some(name);
+        }
+    }
+
+    transpiler.run();
+    transpiler.finish(entry)
+}
+
+/// Get the names that the prelude declares, which the names of the program's types and functions must not be.
+fn prelude_names() -> HashSet<String> {
+    let words = PRELUDE.split(|c: char| !c.is_ascii_alphanumeric() && c != '_');
+    let mut names: HashSet<String> = (words.filter(|word| word.starts_with("wr_")))
+        .map(str::to_owned)
+        .collect();
+
+    // The checked arithmetic functions are declared by macros.
+    for op in ["add", "sub", "mul", "div", "rem", "neg"] {
+        for ty in ["u8", "i8", "u16", "i16", "u32", "i32", "u64", "i64"] {
+            names.insert(format!("wr_{op}_{ty}"));
+        }
+    }
+
+    names
+}
+
+/// A record, union, or enum type, as it's declared in C.
+#[derive(Debug)]
+struct NamedType {
+    /// The name of the C type.
+    name: String,
+    /// What kind of type it is.
+    kind: NamedKind,
+    /// The name of the C enumerator of each variant of a union or enum.
+    tags: Vec<String>,
+    /// Whether the C type has been defined (rather than just declared) yet.
+    is_defined: bool,
+}
+
+/// The state of transpiling a program.
+struct Transpiler<'a> {
+    /// The program being transpiled.
+    cx: Context<'a>,
+    /// Whether to add `#line` directives.
+    line_directives: bool,
+    /// The instances of bodies, with the names of their C functions.
+    instances: Instances,
+    /// Every name given to a C type, function, or enumerator so far.
+    names: HashSet<String>,
+    /// Every record, union, and enum type that's been declared.
+    types: HashMap<Ty, NamedType>,
+    /// The records and unions that have been declared but not defined yet.
+    pending_types: Vec<Ty>,
+    /// The name of every helper function.
+    helpers: HashMap<(Helper, Ty), String>,
+    /// The helper functions that haven't been generated yet.
+    pending_helpers: Vec<(Helper, Ty)>,
+    /// The name of every thunk.
+    thunks: HashMap<Thunk, String>,
+    /// How many values each closure body captures, by the index of the body.
+    closures: HashMap<usize, usize>,
+    /// The declarations of types.
+    declarations: String,
+    /// The definitions of types, in an order where every type is defined before it's used by value.
+    definitions: String,
+    /// The prototype of every function.
+    prototypes: String,
+    /// The code of helper functions and thunks.
+    helper_code: String,
+    /// The code of the functions transpiled from bodies.
+    function_code: String,
+}
+
+impl<'a> Transpiler<'a> {
+    /// Transpile every instance, helper, and type, until none are left (since each one can need more).
+    fn run(&mut self) {
+        loop {
+            if let Some((instance, name)) = self.instances.next() {
+                FunctionTranspiler::new(self, instance).transpile(&name);
+            } else if let Some((helper, ty)) = self.pending_helpers.pop() {
+                self.generate_helper(helper, &ty);
+            } else if let Some(ty) = self.pending_types.pop() {
+                self.define(&ty);
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Put the pieces of the C file together.
+    fn finish(self, entry: Option<String>) -> String {
+        let mut out = String::new();
+
+        _ = writeln!(
+            out,
+            "/* Transpiled from wright by wright {}. */\n",
+            crate::build_info::PKG_VERSION
+        );
+
+        out.push_str(PRELUDE);
+
+        let sections = [
+            ("Types", self.declarations + "\n" + &self.definitions),
+            ("Functions", self.prototypes),
+            ("Printing, comparing, and function values", self.helper_code),
+        ];
+
+        for (title, section) in sections {
+            if !section.trim().is_empty() {
+                _ = write!(out, "\n/* {title}. */\n\n{}", section.trim_start());
+            }
+        }
+
+        if let Some(entry) = entry {
+            _ = write!(out, "\nint main(void) {{\n    {entry}();\n    return 0;\n}}\n");
+        }
+
+        out.push_str(&self.function_code);
+        out
+    }
+
+    /// Get the name of the C function of an instance, adding it if it's new.
+    fn instance_name(&mut self, instance: Instance) -> String {
+        let Transpiler {
+            cx,
+            instances,
+            names,
+            ..
+        } = self;
+
+        let body = &cx.program.bodies[instance.body];
+        let mut name = format!("wr_{}", mangle(&body.name));
+
+        for (_, ty) in &instance.args {
+            name.push('_');
+            name.push_str(&mangle(&ty.display(cx.resolution).to_string()));
+        }
+
+        (instances.get(instance, || unique_name(names, name))).to_owned()
+    }
+
+    /// Get the C type of a (concrete) type, defining it if it's a record or union.
+    fn c_type(&mut self, ty: &Ty) -> String {
+        let name = self.declare(ty);
+
+        if self.types.get(ty).is_some_and(|named| !named.is_defined) {
+            self.define(ty);
+        }
+
+        name
+    }
+
+    /// Get the C type of a type, declaring it if it's a record, union, or enum (but only defining enums, since
+    /// the fields of records and unions can refer back to the type).
+    fn declare(&mut self, ty: &Ty) -> String {
+        if let Some(named) = self.types.get(ty) {
+            return named.name.clone();
+        }
+
+        match ty {
+            Ty::Atomic(variant) => atomic_c_type(*variant).to_owned(),
+            Ty::Reference { target, .. } => pointer(&self.declare(target)),
+            Ty::Function { .. } => "wr_func".to_owned(),
+            Ty::Str => "wr_str".to_owned(),
+            Ty::Named { def, .. } => self.declare_named(*def, ty),
+            Ty::Void | Ty::Param(_) | Ty::Infer(_) | Ty::Integer(_) | Ty::Error => {
+                "wr_void".to_owned()
+            }
+        }
+    }
+
+    /// Declare a record, union, or enum type. Abstract types can't have values, so they're `wr_void`.
+    fn declare_named(&mut self, def: DefId, ty: &Ty) -> String {
+        let resolution = self.cx.resolution;
+        let display = ty.display(resolution).to_string();
+
+        let Some(kind) = self.cx.named_kind(ty) else {
+            return "wr_void".to_owned();
+        };
+
+        let variants: Vec<String> = match kind {
+            NamedKind::Enum => (resolution.variants(def).iter())
+                .map(|variant| resolution.def(*variant).name.clone())
+                .collect(),
+            NamedKind::Union => (self.cx.typeck.fields(ty).into_iter())
+                .flat_map(|(_, variants)| variants)
+                .map(|(name, _)| name.to_owned())
+                .collect(),
+            NamedKind::Record => Vec::new(),
+        };
+
+        let name = unique_name(&mut self.names, format!("wr_{}", mangle(&display)));
+
+        let tags: Vec<String> = (variants.iter())
+            .map(|variant| unique_name(&mut self.names, format!("{name}_{}", mangle(variant))))
+            .collect();
+
+        match kind {
+            NamedKind::Enum if tags.is_empty() => {
+                _ = writeln!(self.declarations, "typedef uint32_t {name};");
+            }
+
+            NamedKind::Enum => {
+                _ = writeln!(
+                    self.declarations,
+                    "typedef enum {name} {{ {} }} {name};",
+                    tags.join(", ")
+                );
+            }
+
+            NamedKind::Record | NamedKind::Union => {
+                _ = writeln!(self.declarations, "typedef struct {name} {name};");
+                self.pending_types.push(ty.clone());
+            }
+        }
+
+        self.types.insert(
+            ty.clone(),
+            NamedType {
+                name: name.clone(),
+                kind,
+                tags,
+                is_defined: kind == NamedKind::Enum,
+            },
+        );
+
+        name
+    }
+
+    /// Define the struct of a record or union that's been declared, after the types of its fields.
+    fn define(&mut self, ty: &Ty) {
+        let Some(named) = self.types.get_mut(ty) else {
+            return;
+        };
+
+        if named.is_defined {
+            return;
+        }
+
+        named.is_defined = true;
+        let (name, kind, tags) = (named.name.clone(), named.kind, named.tags.clone());
+
+        let (_, fields) = self.cx.typeck.fields(ty).unwrap_or_default();
+        let fields: Vec<(String, Ty)> = (fields.into_iter())
+            .map(|(field, ty)| (field.to_owned(), ty))
+            .collect();
+
+        let mut members = String::new();
+        let indent = if kind == NamedKind::Union {
+            "        "
+        } else {
+            "    "
+        };
+
+        for (field, field_ty) in &fields {
+            let field_ty = self.c_type(field_ty);
+            _ = writeln!(members, "{indent}{};", declaration(&field_ty, &field_name(field)));
+        }
+
+        // C doesn't allow empty structs or unions.
+        if fields.is_empty() {
+            _ = writeln!(members, "{indent}char unused;");
+        }
+
+        let definitions = &mut self.definitions;
+
+        match kind {
+            NamedKind::Record => {
+                _ = writeln!(definitions, "struct {name} {{\n{members}}};\n");
+            }
+
+            NamedKind::Union => {
+                let tag = match tags.is_empty() {
+                    true => "uint32_t".to_owned(),
+                    false => {
+                        _ = writeln!(definitions, "enum {name}_tag {{ {} }};", tags.join(", "));
+                        format!("enum {name}_tag")
+                    }
+                };
+
+                _ = writeln!(
+                    definitions,
+                    "struct {name} {{\n    {tag} tag;\n    union {{\n{members}    }} as;\n}};\n"
+                );
+            }
+
+            NamedKind::Enum => {}
+        }
+    }
+
+    /// Get the fields of a record (or the variants of a union).
+    fn fields(&self, ty: &Ty) -> (bool, Vec<(String, Ty)>) {
+        let (is_union, fields) = self.cx.typeck.fields(ty).unwrap_or_default();
+        let fields = (fields.into_iter())
+            .map(|(field, ty)| (field.to_owned(), ty))
+            .collect();
+
+        (is_union, fields)
+    }
+
+    /// Get the C enumerator of a variant of a union or enum.
+    fn tag(&mut self, ty: &Ty, variant: usize) -> String {
+        self.declare(ty);
+
+        (self.types.get(ty))
+            .and_then(|named| named.tags.get(variant))
+            .cloned()
+            .unwrap_or_else(|| variant.to_string())
+    }
+
+    /// Get a C statement (without the semicolon) that prints a value of a type.
+    fn print(&mut self, ty: &Ty, value: &str) -> String {
+        match ty {
+            Ty::Atomic(AtomicTyVariant::Bool) => format!("wr_print_bool({value})"),
+            Ty::Atomic(AtomicTyVariant::Char) => format!("wr_print_char({value})"),
+            Ty::Atomic(AtomicTyVariant::F32 | AtomicTyVariant::F64) => {
+                format!("wr_print_f64({value})")
+            }
+            Ty::Atomic(variant) if variant.is_signed_integer() => format!("wr_print_i64({value})"),
+            Ty::Atomic(_) => format!("wr_print_u64({value})"),
+            Ty::Reference { target, .. } => self.print(target, &format!("(*{value})")),
+            Ty::Function { .. } => format!("wr_print_func({value})"),
+            Ty::Str => format!("wr_print_str({value})"),
+            Ty::Void => format!("wr_print_void({value})"),
+
+            Ty::Named { .. } => match self.c_type(ty) == "wr_void" {
+                true => "fputs(\"<value>\", stdout)".to_owned(),
+                false => format!("{}({value})", self.helper(Helper::Print, ty)),
+            },
+
+            Ty::Param(_) | Ty::Infer(_) | Ty::Integer(_) | Ty::Error => {
+                "fputs(\"<value>\", stdout)".to_owned()
+            }
+        }
+    }
+
+    /// Get a C expression that checks if two values of a type are equal.
+    fn eq(&mut self, ty: &Ty, lhs: &str, rhs: &str) -> String {
+        match ty {
+            Ty::Atomic(_) => format!("{lhs} == {rhs}"),
+            Ty::Reference { target, .. } => self.eq(target, &format!("*{lhs}"), &format!("*{rhs}")),
+            Ty::Function { .. } => format!("{lhs}.code == {rhs}.code && {lhs}.env == {rhs}.env"),
+            Ty::Str => format!("wr_str_eq({lhs}, {rhs})"),
+
+            Ty::Named { .. } => {
+                self.declare(ty);
+
+                match self.types.get(ty).map(|named| named.kind) {
+                    Some(NamedKind::Enum) => format!("{lhs} == {rhs}"),
+                    Some(_) => format!("{}({lhs}, {rhs})", self.helper(Helper::Eq, ty)),
+                    None => "true".to_owned(),
+                }
+            }
+
+            Ty::Void | Ty::Param(_) | Ty::Infer(_) | Ty::Integer(_) | Ty::Error => {
+                "true".to_owned()
+            }
+        }
+    }
+
+    /// Get the name of a helper function for a type, adding it if it's new.
+    fn helper(&mut self, helper: Helper, ty: &Ty) -> String {
+        if let Some(name) = self.helpers.get(&(helper, ty.clone())) {
+            return name.clone();
+        }
+
+        let c_type = self.c_type(ty);
+        let prefix = match helper {
+            Helper::Print => "wr_print",
+            Helper::Eq => "wr_eq",
+        };
+
+        let name = format!("{prefix}_{}", c_type.trim_start_matches("wr_"));
+        let name = unique_name(&mut self.names, name);
+
+        let prototype = match helper {
+            Helper::Print => format!("static void {name}({c_type} value)"),
+            Helper::Eq => format!("static bool {name}({c_type} lhs, {c_type} rhs)"),
+        };
+
+        _ = writeln!(self.prototypes, "{prototype};");
+        self.helpers.insert((helper, ty.clone()), name.clone());
+        self.pending_helpers.push((helper, ty.clone()));
+        name
+    }
+
+    /// Generate the code of a helper function.
+    fn generate_helper(&mut self, helper: Helper, ty: &Ty) {
+        let name = self.helpers[&(helper, ty.clone())].clone();
+        let c_type = self.c_type(ty);
+        let display = ty.display(self.cx.resolution).to_string();
+        let kind = self.types.get(ty).map(|named| named.kind);
+        let (_, fields) = self.fields(ty);
+        let mut code = String::new();
+
+        match (helper, kind) {
+            (Helper::Print, Some(NamedKind::Record)) => {
+                _ = writeln!(code, "static void {name}({c_type} value) {{");
+                _ = writeln!(code, "    fputs({}, stdout);", c_string(&format!("{display} {{")));
+
+                for (index, (field, field_ty)) in fields.iter().enumerate() {
+                    let separator = if index > 0 { ", " } else { " " };
+                    let label = c_string(&format!("{separator}{field}: "));
+                    let print = self.print(field_ty, &format!("value.{}", field_name(field)));
+                    _ = writeln!(code, "    fputs({label}, stdout);\n    {print};");
+                }
+
+                _ = writeln!(code, "    fputs(\" }}\", stdout);\n}}\n");
+            }
+
+            (Helper::Print, Some(NamedKind::Union)) => {
+                _ = writeln!(code, "static void {name}({c_type} value) {{");
+                _ = writeln!(code, "    switch (value.tag) {{");
+
+                for (index, (variant, variant_ty)) in fields.iter().enumerate() {
+                    let tag = self.tag(ty, index);
+                    let label = c_string(&format!("{display} {{ {variant}: "));
+                    let print =
+                        self.print(variant_ty, &format!("value.as.{}", field_name(variant)));
+
+                    _ = writeln!(
+                        code,
+                        "    case {tag}:\n        fputs({label}, stdout);\n        {print};\n        break;"
+                    );
+                }
+
+                _ = writeln!(code, "    }}\n    fputs(\" }}\", stdout);\n}}\n");
+            }
+
+            (Helper::Print, _) => {
+                let tags = (self.types.get(ty)).map_or_else(Vec::new, |named| named.tags.clone());
+                let Ty::Named { def, .. } = ty else {
+                    return;
+                };
+
+                _ = writeln!(code, "static void {name}({c_type} value) {{");
+                _ = writeln!(code, "    switch (value) {{");
+
+                for (tag, variant) in tags.iter().zip(self.cx.resolution.variants(*def)) {
+                    let variant = &self.cx.resolution.def(*variant).name;
+                    let label = c_string(&format!("{display}::{variant}"));
+                    _ = writeln!(
+                        code,
+                        "    case {tag}:\n        fputs({label}, stdout);\n        break;"
+                    );
+                }
+
+                _ = writeln!(code, "    }}\n}}\n");
+            }
+
+            (Helper::Eq, Some(NamedKind::Union)) => {
+                _ = writeln!(code, "static bool {name}({c_type} lhs, {c_type} rhs) {{");
+                _ = writeln!(
+                    code,
+                    "    if (lhs.tag != rhs.tag) {{\n        return false;\n    }}\n"
+                );
+                _ = writeln!(code, "    switch (lhs.tag) {{");
+
+                for (index, (variant, variant_ty)) in fields.iter().enumerate() {
+                    let tag = self.tag(ty, index);
+                    let variant = field_name(variant);
+                    let eq = self.eq(
+                        variant_ty,
+                        &format!("lhs.as.{variant}"),
+                        &format!("rhs.as.{variant}"),
+                    );
+
+                    _ = writeln!(code, "    case {tag}:\n        return {eq};");
+                }
+
+                _ = writeln!(code, "    }}\n\n    return true;\n}}\n");
+            }
+
+            (Helper::Eq, _) => {
+                let mut checks = Vec::new();
+
+                for (field, field_ty) in &fields {
+                    let field = field_name(field);
+                    let eq = self.eq(field_ty, &format!("lhs.{field}"), &format!("rhs.{field}"));
+                    checks.push(format!("({eq})"));
+                }
+
+                if checks.is_empty() {
+                    checks.push("true".to_owned());
+                }
+
+                _ = writeln!(code, "static bool {name}({c_type} lhs, {c_type} rhs) {{");
+                _ = writeln!(code, "    return {};\n}}\n", checks.join(" && "));
+            }
+        }
+
+        self.helper_code.push_str(&code);
+    }
+
+    /// Get a C expression for a function value that runs an instance or a builtin function.
+    fn function_value(&mut self, thunk: Thunk) -> String {
+        if let Some(name) = self.thunks.get(&thunk) {
+            return name.clone();
+        }
+
+        let (label, params, ret, call) = match &thunk {
+            Thunk::Instance(instance) => {
+                let function = self.instance_name(instance.clone());
+                let body = &self.cx.program.bodies[instance.body];
+                let substitution = instance.substitution();
+
+                let params: Vec<Ty> = (body.params.iter())
+                    .map(|param| body.value_ty(*param).substitute(&substitution))
+                    .collect();
+
+                let args: Vec<String> =
+                    (0..params.len()).map(|index| format!("a{index}")).collect();
+                let call = format!("return {function}({});", args.join(", "));
+                let ret = body.ret.substitute(&substitution);
+                (format!("func {}", body.name), params, ret, call)
+            }
+
+            Thunk::Builtin(builtin, ty) => {
+                let params = match ty {
+                    Ty::Function { params, .. } => params.clone(),
+                    _ => Vec::new(),
+                };
+
+                let mut call = match params.first() {
+                    Some(param) => format!("{};\n    ", self.print(param, "a0")),
+                    None => String::new(),
+                };
+
+                if *builtin == Builtin::Println {
+                    call.push_str("putchar('\\n');\n    ");
+                }
+
+                call.push_str("return 0;");
+                (format!("func wright::io::{}", builtin.name()), params, Ty::Void, call)
+            }
+        };
+
+        let base = match &thunk {
+            Thunk::Instance(instance) => format!("{}_value", self.instance_name(instance.clone())),
+            Thunk::Builtin(builtin, _) => format!("wr_{}_value", builtin.name()),
+        };
+
+        let name = unique_name(&mut self.names, base);
+        let ret = self.c_type(&ret);
+
+        let mut declared = vec!["void *env".to_owned()];
+        for (index, param) in params.iter().enumerate() {
+            let param = self.c_type(param);
+            declared.push(declaration(&param, &format!("a{index}")));
+        }
+
+        let prototype = format!("static {ret} {name}({})", declared.join(", "));
+        _ = writeln!(self.prototypes, "{prototype};");
+        _ = writeln!(self.helper_code, "{prototype} {{\n    (void)env;\n    {call}\n}}\n");
+
+        let value = format!("WR_FUNC({name}, NULL, {})", c_string(&label));
+        self.thunks.insert(thunk, value.clone());
+        value
+    }
+}
+
+/// The state of transpiling the body of an instance.
+struct FunctionTranspiler<'t, 'a> {
+    /// The transpiler of the program.
+    t: &'t mut Transpiler<'a>,
+    /// The body.
+    body: &'a Body,
+    /// The types of the instance's generic parameters.
+    substitution: HashMap<DefId, Ty>,
+    /// How many values the body captures, if it's the body of a closure.
+    captures: Option<usize>,
+    /// The code so far.
+    out: String,
+    /// The `#line` directive of the span being transpiled, which goes before each statement.
+    directive: Option<String>,
+}
+
+impl<'t, 'a> FunctionTranspiler<'t, 'a> {
+    /// Set up the transpiling of an instance.
+    fn new(t: &'t mut Transpiler<'a>, instance: Instance) -> Self {
+        let body = &t.cx.program.bodies[instance.body];
+        let captures = t.closures.get(&instance.body).copied();
+
+        FunctionTranspiler {
+            t,
+            body,
+            substitution: instance.substitution(),
+            captures,
+            out: String::new(),
+            directive: None,
+        }
+    }
+
+    /// Transpile the body into a C function with the given name.
+    fn transpile(mut self, name: &str) {
+        let ret = self.c_type(&self.body.ret.substitute(&self.substitution));
+        let (captured, called_with) = split_captures(self.body, self.captures);
+        let mut params = Vec::new();
+
+        if self.captures.is_some() {
+            params.push("void *env".to_owned());
+        }
+
+        for param in called_with {
+            let ty = self.c_type(&self.value_ty(*param));
+            params.push(declaration(&ty, &value(*param)));
+        }
+
+        if params.is_empty() {
+            params.push("void".to_owned());
+        }
+
+        let signature = format!("static {ret} {name}({})", params.join(", "));
+        _ = writeln!(self.t.prototypes, "{signature};");
+
+        let mut env = String::new();
+
+        if !captured.is_empty() {
+            let mut members = String::new();
+
+            for (index, param) in captured.iter().enumerate() {
+                let ty = self.c_type(&self.value_ty(*param));
+                _ = writeln!(members, "    {};", declaration(&ty, &format!("c{index}")));
+                _ = writeln!(env, "    {} = captures->c{index};", value(*param));
+            }
+
+            _ = writeln!(self.t.definitions, "struct {name}_env {{\n{members}}};\n");
+            env.insert_str(0, &format!("    struct {name}_env *captures = env;\n"));
+        } else if self.captures.is_some() {
+            env.push_str("    (void)env;\n");
+        }
+
+        let mut locals = String::new();
+
+        for (index, ty) in self.body.values.iter().enumerate() {
+            let local = Value(index as u32);
+
+            if !called_with.contains(&local) && !self.is_void(local) {
+                let ty = self.c_type(&ty.substitute(&self.substitution));
+                _ = writeln!(locals, "    {};", declaration(&ty, &value(local)));
+            }
+        }
+
+        for (index, slot) in self.body.slots.iter().enumerate() {
+            let ty = self.c_type(&slot.ty.substitute(&self.substitution));
+            _ = writeln!(locals, "    {};", declaration(&ty, &format!("s{index}")));
+        }
+
+        let targets: HashSet<u32> = (self.body.blocks.iter())
+            .flat_map(|block| block.terminator.successors())
+            .map(|target| target.0)
+            .collect();
+
+        for block in self.body.block_ids() {
+            let data = self.body.block(block);
+
+            if targets.contains(&block.0) {
+                _ = writeln!(self.out, "bb{}:", block.0);
+            }
+
+            for (inst, span) in data.insts.iter().zip(&data.spans) {
+                self.locate(span);
+                self.inst(inst);
+            }
+
+            self.locate(&data.terminator_span);
+            self.terminator(&data.terminator);
+        }
+
+        if !locals.is_empty() && !env.is_empty() {
+            locals.push('\n');
+        }
+
+        _ = write!(self.t.function_code, "\n{signature} {{\n{locals}{env}{}", self.out);
+
+        self.t.function_code.push_str("}\n");
+    }
+
+    /// Get the C type of a type.
+    fn c_type(&mut self, ty: &Ty) -> String {
+        self.t.c_type(ty)
+    }
+
+    /// Get the (concrete) type of a value.
+    fn value_ty(&self, value: Value) -> Ty {
+        self.body.value_ty(value).substitute(&self.substitution)
+    }
+
+    /// Check if a value is a `void` value other than a parameter. These don't get a variable, since they can only
+    /// be `()`.
+    fn is_void(&self, local: Value) -> bool {
+        self.value_ty(local) == Ty::Void && !self.body.params.contains(&local)
+    }
+
+    /// Get the (concrete) type of an operand.
+    fn operand_ty(&self, operand: &Operand) -> Ty {
+        self.body.operand_ty(operand).substitute(&self.substitution)
+    }
+
+    /// Add a statement, preceded by the `#line` directive of its span (if there is one).
+    fn line(&mut self, statement: impl AsRef<str>) {
+        if let Some(directive) = &self.directive {
+            _ = writeln!(self.out, "{directive}");
+        }
+
+        _ = writeln!(self.out, "    {}", statement.as_ref());
+    }
+
+    /// Add a statement that stops the program with a message.
+    fn trap(&mut self, message: &str) {
+        self.line(format!("WR_TRAP({});", c_string(message)));
+    }
+
+    /// Attribute the statements added from now on to the source code of a span.
+    fn locate(&mut self, span: &Fragment) {
+        if !self.t.line_directives {
+            return;
+        }
+
+        let file = span.source.name().to_string();
+        let line = span.source.line_index(span.range.start) + 1;
+        self.directive = Some(format!("#line {line} {}", c_string(&file)));
+    }
+
+    /// Get the C expression of an operand.
+    fn operand(&mut self, operand: &Operand) -> String {
+        match operand {
+            Operand::Value(local) if self.is_void(*local) => "0".to_owned(),
+            Operand::Value(local) => value(*local),
+            Operand::Const(constant) => self.constant(constant),
+        }
+    }
+
+    /// Get the C expression of a constant. Constants that can't be made trap (and are left as a zero value).
+    fn constant(&mut self, constant: &Const) -> String {
+        match constant {
+            Const::Int(number, ty) => match ty.atomic().and_then(|ty| int_literal(ty, number)) {
+                Some(literal) => literal,
+                None => {
+                    self.trap(&format!("the integer {constant:?} doesn't fit its type"));
+                    "0".to_owned()
+                }
+            },
+
+            Const::Bool(true) => "true".to_owned(),
+            Const::Bool(false) => "false".to_owned(),
+            Const::Str(string) => format!("WR_STR({})", c_string(string)),
+            Const::Void => "0".to_owned(),
+
+            Const::Variant(_, index, ty) => {
+                let ty = ty.substitute(&self.substitution);
+                self.t.tag(&ty, *index as usize)
+            }
+
+            Const::Def(def, ty) => {
+                let ty = ty.substitute(&self.substitution);
+
+                match self.t.cx.callee_value(*def, &ty) {
+                    Callee::Instance(instance) => self.t.function_value(Thunk::Instance(instance)),
+                    Callee::Builtin(builtin) => self.t.function_value(Thunk::Builtin(builtin, ty)),
+                    Callee::Missing(message) => {
+                        self.trap(&message);
+                        "(wr_func){ 0 }".to_owned()
+                    }
+                }
+            }
+        }
+    }
+
+    /// Get the C lvalue of a place. Going through a variant of a union to load or store checks that the union
+    /// holds it, except for the last step of a place that's stored to, which changes the union to hold it.
+    /// References to variants aren't checked until they're used, like in the VM.
+    fn place(&mut self, place: &Place, access: Access) -> String {
+        let (mut lvalue, mut ty, projections) = match place.base {
+            PlaceBase::Slot(slot) => (
+                format!("s{}", slot.0),
+                self.body.slots[slot.0 as usize]
+                    .ty
+                    .substitute(&self.substitution),
+                &place.projections[..],
+            ),
+
+            PlaceBase::Value(local) => (
+                format!("(*{})", value(local)),
+                strip_one_reference(&self.value_ty(local)),
+                &place.projections[1..],
+            ),
+        };
+
+        for (index, projection) in projections.iter().enumerate() {
+            match projection {
+                Projection::Field(field) => {
+                    let (is_union, fields) = self.t.fields(&ty);
+                    let position = fields.iter().position(|(name, _)| name == field);
+                    let field_ty =
+                        position.map_or(Ty::Error, |position| fields[position].1.clone());
+
+                    if is_union {
+                        let tag = self.t.tag(&ty, position.unwrap_or_default());
+
+                        let is_last = index == projections.len() - 1;
+
+                        match access {
+                            Access::Store if is_last => self.line(format!("{lvalue}.tag = {tag};")),
+                            Access::Ref if is_last => {}
+                            _ => self.line(format!(
+                                "if ({lvalue}.tag != {tag}) WR_TRAP({});",
+                                c_string(VARIANT_TRAP)
+                            )),
+                        }
+
+                        lvalue = format!("{lvalue}.as.{}", field_name(field));
+                    } else {
+                        lvalue = format!("{lvalue}.{}", field_name(field));
+                    }
+
+                    ty = field_ty;
+                }
+
+                Projection::Deref => {
+                    lvalue = format!("(*{lvalue})");
+                    ty = strip_one_reference(&ty);
+                }
+            }
+        }
+
+        lvalue
+    }
+
+    /// Transpile an instruction.
+    fn inst(&mut self, inst: &Inst) {
+        match inst {
+            Inst::Assign(local, rvalue) => self.rvalue(*local, rvalue),
+
+            Inst::Store(place, operand) => {
+                let src = self.operand(operand);
+                let place = self.place(place, Access::Store);
+                self.line(format!("{place} = {src};"));
+            }
+
+            // Slots live until the function returns.
+            Inst::Drop(_) => {}
+        }
+    }
+
+    /// Transpile the computation of a value.
+    fn rvalue(&mut self, local: Value, rvalue: &Rvalue) {
+        let dst = value(local);
+
+        // Only calls have an effect, when they make a `void` value.
+        if self.is_void(local) {
+            if let Rvalue::Call { callee, args } = rvalue {
+                self.call(local, callee, args);
+            }
+
+            return;
+        }
+
+        match rvalue {
+            Rvalue::Use(operand) => {
+                let src = self.operand(operand);
+                self.line(format!("{dst} = {src};"));
+            }
+
+            Rvalue::Load(place) => {
+                let place = self.place(place, Access::Load);
+                self.line(format!("{dst} = {place};"));
+            }
+
+            Rvalue::Ref { place, .. } => {
+                let place = self.place(place, Access::Ref);
+                self.line(format!("{dst} = &{place};"));
+            }
+
+            Rvalue::Unary(op, operand) => self.unary(&dst, *op, operand),
+            Rvalue::Binary(op, lhs, rhs) => self.binary(&dst, *op, lhs, rhs),
+            Rvalue::Call { callee, args } => self.call(local, callee, args),
+
+            Rvalue::Aggregate { fields } => {
+                let ty = self.value_ty(local);
+                let c_type = self.c_type(&ty);
+                let (is_union, layout) = self.t.fields(&ty);
+
+                if is_union {
+                    let Some((name, operand)) = fields.first() else {
+                        return self.trap("a union is made without a variant");
+                    };
+
+                    let position = layout.iter().position(|(variant, _)| variant == name);
+                    let tag = self.t.tag(&ty, position.unwrap_or_default());
+                    let src = self.operand(operand);
+                    self.line(format!("{dst}.tag = {tag};"));
+                    return self.line(format!("{dst}.as.{} = {src};", field_name(name)));
+                }
+
+                let mut initializers = Vec::new();
+
+                // The fields are given in any order, but they're initialized in the order they're declared.
+                for (name, _) in &layout {
+                    if let Some((_, operand)) = fields.iter().find(|(field, _)| field == name) {
+                        let src = self.operand(operand);
+                        initializers.push(format!(".{} = {src}", field_name(name)));
+                    }
+                }
+
+                match initializers.is_empty() {
+                    true => self.line(format!("{dst} = ({c_type}){{ 0 }};")),
+                    false => {
+                        self.line(format!("{dst} = ({c_type}){{ {} }};", initializers.join(", ")))
+                    }
+                }
+            }
+
+            Rvalue::Field(operand, field) => {
+                let ty = self.operand_ty(operand);
+                let (is_union, fields) = self.t.fields(&ty);
+                let src = self.operand(operand);
+
+                match is_union {
+                    true => {
+                        let position = fields.iter().position(|(name, _)| name == field);
+                        let tag = self.t.tag(&ty, position.unwrap_or_default());
+                        self.line(format!(
+                            "if ({src}.tag != {tag}) WR_TRAP({});",
+                            c_string(VARIANT_TRAP)
+                        ));
+                        self.line(format!("{dst} = {src}.as.{};", field_name(field)));
+                    }
+
+                    false => self.line(format!("{dst} = {src}.{};", field_name(field))),
+                }
+            }
+
+            Rvalue::IsVariant(operand, variant) => {
+                let ty = self.operand_ty(operand);
+                let (_, fields) = self.t.fields(&ty);
+                let position = fields.iter().position(|(name, _)| name == variant);
+                let tag = self.t.tag(&ty, position.unwrap_or_default());
+                let src = self.operand(operand);
+                self.line(format!("{dst} = {src}.tag == {tag};"));
+            }
+
+            Rvalue::Closure { body, captures } => {
+                let index = body.0 as usize;
+
+                let instance = match self.t.cx.instance(index, &self.substitution) {
+                    Ok(instance) => instance,
+                    Err(_) => {
+                        return self
+                            .trap("the types of a closure's generic parameters aren't known");
+                    }
+                };
+
+                self.t.closures.insert(index, captures.len());
+                let function = self.t.instance_name(instance);
+                let label = c_string(&format!("closure {}", self.t.cx.program.bodies[index].name));
+
+                if captures.is_empty() {
+                    return self.line(format!("{dst} = WR_FUNC({function}, NULL, {label});"));
+                }
+
+                // The environment of a closure lives as long as the program does.
+                let mut statements = vec![format!(
+                    "struct {function}_env *env = wr_alloc(sizeof *env);"
+                )];
+
+                for (index, capture) in captures.iter().enumerate() {
+                    let capture = self.operand(capture);
+                    statements.push(format!("env->c{index} = {capture};"));
+                }
+
+                statements.push(format!("{dst} = WR_FUNC({function}, env, {label});"));
+                self.line("{");
+
+                for statement in statements {
+                    self.line(format!("    {statement}"));
+                }
+
+                self.line("}");
+            }
+        }
+    }
+
+    /// Transpile a unary operation.
+    fn unary(&mut self, dst: &str, op: UnaryOperation, operand: &Operand) {
+        let ty = self.operand_ty(operand);
+        let src = self.operand(operand);
+        let atomic = ty.atomic();
+
+        match (op, atomic.and_then(int_ty)) {
+            (UnaryOperation::Negate, Some(int)) => self.line(format!(
+                "if (wr_neg_{}(&{dst}, {src})) WR_TRAP(\"arithmetic overflow\");",
+                int.name
+            )),
+
+            (UnaryOperation::BitwiseNot, Some(int)) => {
+                self.line(format!("{dst} = ({})~{src};", int.c_type))
+            }
+
+            (UnaryOperation::Negate, None) if atomic.is_some_and(AtomicTyVariant::is_float) => {
+                self.line(format!("{dst} = -{src};"))
+            }
+
+            (UnaryOperation::BooleanNot | UnaryOperation::BitwiseNot, None) if ty == Ty::BOOL => {
+                self.line(format!("{dst} = !{src};"))
+            }
+
+            _ => self.unsupported(op.symbol(), &ty),
+        }
+    }
+
+    /// Transpile a binary operation.
+    fn binary(&mut self, dst: &str, op: BinaryOperation, lhs: &Operand, rhs: &Operand) {
+        let ty = self.operand_ty(lhs);
+        let rhs_ty = self.operand_ty(rhs);
+        let atomic = ty.atomic();
+        let a = self.operand(lhs);
+        let b = self.operand(rhs);
+
+        if let Some(compare) = compare_operator(op) {
+            return match (&ty, op) {
+                (Ty::Atomic(_), _) => self.line(format!("{dst} = {a} {compare} {b};")),
+
+                (_, BinaryOperation::Equal) => {
+                    let eq = self.t.eq(&ty, &a, &b);
+                    self.line(format!("{dst} = {eq};"))
+                }
+
+                (_, BinaryOperation::NotEqual) => {
+                    let eq = self.t.eq(&ty, &a, &b);
+                    self.line(format!("{dst} = !({eq});"))
+                }
+
+                _ => self.unsupported(op.symbol(), &ty),
+            };
+        }
+
+        if let Some(int) = atomic.and_then(int_ty) {
+            // Operations that always trap aren't written out, since C compilers warn about them.
+            if let Some(message) = constant_trap(op, rhs, int.bits) {
+                return self.trap(message);
+            }
+
+            let checked = |name: &str| {
+                format!(
+                    "if (wr_{name}_{}(&{dst}, {a}, {b})) WR_TRAP(\"arithmetic overflow\");",
+                    int.name
+                )
+            };
+
+            match op {
+                BinaryOperation::Add => self.line(checked("add")),
+                BinaryOperation::Subtract => self.line(checked("sub")),
+                BinaryOperation::Multiply => self.line(checked("mul")),
+
+                BinaryOperation::Divide | BinaryOperation::Modulo => {
+                    self.line(format!("if ({b} == 0) WR_TRAP(\"division by zero\");"));
+
+                    match (op, int.signed) {
+                        (BinaryOperation::Divide, true) => self.line(checked("div")),
+                        (BinaryOperation::Divide, false) => {
+                            self.line(format!("{dst} = {a} / {b};"))
+                        }
+                        (_, true) => self.line(format!("{dst} = wr_rem_{}({a}, {b});", int.name)),
+                        (_, false) => self.line(format!("{dst} = {a} % {b};")),
+                    }
+                }
+
+                BinaryOperation::BitwiseAnd => self.line(format!("{dst} = {a} & {b};")),
+                BinaryOperation::BitwiseOr => self.line(format!("{dst} = {a} | {b};")),
+                BinaryOperation::BitwiseXor => self.line(format!("{dst} = {a} ^ {b};")),
+
+                BinaryOperation::ShiftLeft | BinaryOperation::ShiftRight => {
+                    let is_signed = rhs_ty
+                        .atomic()
+                        .is_some_and(AtomicTyVariant::is_signed_integer);
+                    let bits = int.bits;
+
+                    self.line(match is_signed {
+                        true => format!(
+                            "if ({b} < 0 || {b} >= {bits}) WR_TRAP({});",
+                            c_string(SHIFT_TRAP)
+                        ),
+                        false => format!("if ({b} >= {bits}) WR_TRAP({});", c_string(SHIFT_TRAP)),
+                    });
+
+                    // Bits shifted out of the left are lost, which C only allows for unsigned integers.
+                    self.line(match op {
+                        BinaryOperation::ShiftLeft => {
+                            format!("{dst} = ({})((uint{bits}_t){a} << {b});", int.c_type)
+                        }
+                        _ => format!("{dst} = ({})({a} >> {b});", int.c_type),
+                    });
+                }
+
+                _ => self.unsupported(op.symbol(), &ty),
+            }
+        } else if atomic.is_some_and(AtomicTyVariant::is_float) {
+            let rem = match atomic {
+                Some(AtomicTyVariant::F32) => "fmodf",
+                _ => "fmod",
+            };
+
+            match op {
+                BinaryOperation::Add => self.line(format!("{dst} = {a} + {b};")),
+                BinaryOperation::Subtract => self.line(format!("{dst} = {a} - {b};")),
+                BinaryOperation::Multiply => self.line(format!("{dst} = {a} * {b};")),
+                BinaryOperation::Divide => self.line(format!("{dst} = {a} / {b};")),
+                BinaryOperation::Modulo => self.line(format!("{dst} = {rem}({a}, {b});")),
+                _ => self.unsupported(op.symbol(), &ty),
+            }
+        } else if ty == Ty::BOOL {
+            match op {
+                BinaryOperation::BitwiseAnd | BinaryOperation::LogicalAnd => {
+                    self.line(format!("{dst} = {a} && {b};"))
+                }
+                BinaryOperation::BitwiseOr | BinaryOperation::LogicalOr => {
+                    self.line(format!("{dst} = {a} || {b};"))
+                }
+                BinaryOperation::BitwiseXor => self.line(format!("{dst} = {a} != {b};")),
+                _ => self.unsupported(op.symbol(), &ty),
+            }
+        } else {
+            self.unsupported(op.symbol(), &ty);
+        }
+    }
+
+    /// Trap on an operation that isn't supported for a type, which type checking should have ruled out.
+    fn unsupported(&mut self, op: &str, ty: &Ty) {
+        let ty = ty.display(self.t.cx.resolution).to_string();
+        self.trap(&format!("`{op}` isn't supported for `{ty}`"));
+    }
+
+    /// Transpile a call.
+    fn call(&mut self, local: Value, callee: &Operand, args: &[Operand]) {
+        let assign = match self.is_void(local) {
+            true => String::new(),
+            false => format!("{} = ", value(local)),
+        };
+        let arg_tys: Vec<Ty> = args.iter().map(|arg| self.operand_ty(arg)).collect();
+
+        let Operand::Const(Const::Def(def, _)) = callee else {
+            // A function value's code takes the environment before the arguments.
+            let ty = self.operand_ty(callee);
+
+            if !matches!(ty, Ty::Function { .. }) {
+                return self.trap(&format!(
+                    "expected a function, found a value of type `{}`",
+                    ty.display(self.t.cx.resolution)
+                ));
+            }
+
+            let callee = self.operand(callee);
+            let ret = self.c_type(&self.value_ty(local));
+
+            let mut params = vec!["void *".to_owned()];
+            let mut values = vec![format!("{callee}.env")];
+
+            for (arg, ty) in args.iter().zip(&arg_tys) {
+                params.push(self.c_type(ty));
+                values.push(self.operand(arg));
+            }
+
+            return self.line(format!(
+                "{assign}(({ret} (*)({})){callee}.code)({});",
+                params.join(", "),
+                values.join(", ")
+            ));
+        };
+
+        let args: Vec<String> = args.iter().map(|arg| self.operand(arg)).collect();
+
+        match self.t.cx.callee(*def, &arg_tys, &self.value_ty(local)) {
+            Callee::Instance(instance) => {
+                let function = self.t.instance_name(instance);
+                self.line(format!("{assign}{function}({});", args.join(", ")));
+            }
+
+            Callee::Builtin(builtin) => {
+                if let Some((arg, ty)) = args.first().zip(arg_tys.first()) {
+                    let print = self.t.print(ty, arg);
+                    self.line(format!("{print};"));
+                }
+
+                if builtin == Builtin::Println {
+                    self.line("putchar('\\n');");
+                }
+
+                if !assign.is_empty() {
+                    self.line(format!("{assign}0;"));
+                }
+            }
+
+            Callee::Missing(message) => self.trap(&message),
+        }
+    }
+
+    /// Transpile a terminator.
+    fn terminator(&mut self, terminator: &Terminator) {
+        match terminator {
+            Terminator::Goto(jump) => self.jump(jump, ""),
+
+            Terminator::Branch {
+                condition,
+                then,
+                otherwise,
+            } => {
+                let condition = self.operand(condition);
+
+                if then.args.is_empty() && otherwise.args.is_empty() {
+                    self.line(format!("if ({condition}) goto bb{};", then.target.0));
+                    return self.line(format!("goto bb{};", otherwise.target.0));
+                }
+
+                self.line(format!("if ({condition}) {{"));
+                self.jump(then, "    ");
+                self.line("} else {");
+                self.jump(otherwise, "    ");
+                self.line("}");
+            }
+
+            Terminator::Switch {
+                value,
+                cases,
+                otherwise,
+            } => {
+                let ty = self.operand_ty(value);
+                let src = self.operand(value);
+                self.line(format!("switch ({src}) {{"));
+
+                for (case, target) in cases {
+                    // Cases that don't fit the type can't match, and C doesn't allow them.
+                    let case = match ty.atomic() {
+                        Some(atomic) => int_literal(atomic, case),
+                        None => u32::try_from(case).ok().map(|case| case.to_string()),
+                    };
+
+                    if let Some(case) = case {
+                        self.line(format!("case {case}: goto bb{};", target.0));
+                    }
+                }
+
+                self.line(format!("default: goto bb{};", otherwise.0));
+                self.line("}");
+            }
+
+            Terminator::Return(operand) => {
+                let src = self.operand(operand);
+                self.line(format!("return {src};"));
+            }
+
+            Terminator::Unreachable => self.trap("reached code that should be unreachable"),
+        }
+    }
+
+    /// Transpile a jump to a block, assigning its arguments to the block's parameters, with an extra indent.
+    fn jump(&mut self, jump: &Jump, indent: &str) {
+        let params = &self.body.block(jump.target).params;
+
+        // If an argument is the parameter of another argument, it would be overwritten before it's assigned, so
+        // the arguments are put in temporary variables first.
+        let conflict = (jump.args.iter().enumerate()).any(|(index, arg)| {
+            (params.iter().enumerate())
+                .any(|(other, param)| other != index && *arg == Operand::Value(*param))
+        });
+
+        if conflict {
+            self.line(format!("{indent}{{"));
+
+            for (index, (param, arg)) in params.iter().zip(&jump.args).enumerate() {
+                let ty = self.c_type(&self.value_ty(*param));
+                let arg = self.operand(arg);
+                let temp = declaration(&ty, &format!("t{index}"));
+                self.line(format!("{indent}    {temp} = {arg};"));
+            }
+
+            for (index, param) in params.iter().enumerate() {
+                if !self.is_void(*param) {
+                    self.line(format!("{indent}    {} = t{index};", value(*param)));
+                }
+            }
+
+            self.line(format!("{indent}}}"));
+        } else {
+            for (param, arg) in params.iter().zip(&jump.args) {
+                if *arg != Operand::Value(*param) && !self.is_void(*param) {
+                    let arg = self.operand(arg);
+                    self.line(format!("{indent}{} = {arg};", value(*param)));
+                }
+            }
+        }
+
+        self.line(format!("{indent}goto bb{};", jump.target.0));
+    }
+}
+
+/// An integer type, as it is in C.
+#[derive(Clone, Copy, Debug)]
+struct IntTy {
+    /// The C type.
+    c_type: &'static str,
+    /// The name of the wright type, which the prelude's arithmetic functions end with.
+    name: &'static str,
+    /// The number of bits.
+    bits: u32,
+    /// Whether it's signed.
+    signed: bool,
+}
+
+/// Get the [IntTy] of an atomic type, if it's an integer type.
+fn int_ty(variant: AtomicTyVariant) -> Option<IntTy> {
+    let bits = match variant {
+        AtomicTyVariant::U8 | AtomicTyVariant::I8 => 8,
+        AtomicTyVariant::U16 | AtomicTyVariant::I16 => 16,
+        AtomicTyVariant::U32 | AtomicTyVariant::I32 => 32,
+        AtomicTyVariant::U64 | AtomicTyVariant::I64 => 64,
+        _ => return None,
+    };
+
+    Some(IntTy {
+        c_type: atomic_c_type(variant),
+        name: variant.name(),
+        bits,
+        signed: variant.is_signed_integer(),
+    })
+}
+
+/// Get the C type of an atomic type.
+const fn atomic_c_type(variant: AtomicTyVariant) -> &'static str {
+    match variant {
+        AtomicTyVariant::Bool => "bool",
+        AtomicTyVariant::U8 => "uint8_t",
+        AtomicTyVariant::I8 => "int8_t",
+        AtomicTyVariant::U16 => "uint16_t",
+        AtomicTyVariant::I16 => "int16_t",
+        AtomicTyVariant::U32 => "uint32_t",
+        AtomicTyVariant::I32 => "int32_t",
+        AtomicTyVariant::U64 => "uint64_t",
+        AtomicTyVariant::I64 => "int64_t",
+        AtomicTyVariant::F32 => "float",
+        AtomicTyVariant::F64 => "double",
+        AtomicTyVariant::Char => "uint32_t",
+    }
+}
+
+/// Get a C literal of an integer of an atomic type, if it fits the type.
+fn int_literal(variant: AtomicTyVariant, value: &BigInt) -> Option<String> {
+    let (min, max) = variant.integer_bounds()?;
+    let value = i128::try_from(value)
+        .ok()
+        .filter(|value| (min..=max).contains(value))?;
+    let prefix = atomic_c_type(variant).trim_end_matches("_t").to_uppercase();
+
+    // The smallest signed integers can't be written as the negation of a literal, which would be too large.
+    Some(match value == min && min < 0 {
+        true => format!("{prefix}_MIN"),
+        false => format!("{prefix}_C({value})"),
+    })
+}
+
+/// Get the comparison operator of a binary operation, if it's a comparison.
+const fn compare_operator(op: BinaryOperation) -> Option<&'static str> {
+    Some(match op {
+        BinaryOperation::Equal => "==",
+        BinaryOperation::NotEqual => "!=",
+        BinaryOperation::Less => "<",
+        BinaryOperation::LessOrEqual => "<=",
+        BinaryOperation::Greater => ">",
+        BinaryOperation::GreaterOrEqual => ">=",
+        _ => return None,
+    })
+}
+
+/// Get the C variable of a value.
+fn value(value: Value) -> String {
+    format!("v{}", value.0)
+}
+
+/// Get the C type of a pointer to a C type.
+fn pointer(target: &str) -> String {
+    match target.ends_with('*') {
+        true => format!("{target}*"),
+        false => format!("{target} *"),
+    }
+}
+
+/// Declare a variable (or member) of a C type.
+fn declaration(ty: &str, name: &str) -> String {
+    match ty.ends_with('*') {
+        true => format!("{ty}{name}"),
+        false => format!("{ty} {name}"),
+    }
+}
+
+/// Get the C name of a field of a record (or variant of a union). Names that are reserved in C get an underscore
+/// added to them.
+fn field_name(name: &str) -> String {
+    /// The keywords of C, and the names defined by the prelude's headers that can't be used for members.
+    const RESERVED: &[&str] = &[
+        "auto", "bool", "break", "case", "char", "const", "continue", "default", "do", "double",
+        "else", "enum", "extern", "false", "float", "for", "goto", "if", "inline", "int", "long",
+        "register", "restrict", "return", "short", "signed", "sizeof", "static", "struct",
+        "switch", "true", "typedef", "union", "unsigned", "void", "volatile", "while", "errno",
+        "EOF", "NULL",
+    ];
+
+    let mut mangled = String::new();
+
+    for c in name.chars() {
+        match c.is_ascii_alphanumeric() || c == '_' {
+            true => mangled.push(c),
+            false => _ = write!(mangled, "u{:04x}", c as u32),
+        }
+    }
+
+    if RESERVED.contains(&mangled.as_str()) || mangled.starts_with("__") {
+        mangled.push('_');
+    }
+
+    mangled
+}
+
+/// Write a string as a C string literal. Anything but printable ASCII is escaped (as are question marks, which
+/// could otherwise start a trigraph).
+fn c_string(string: &str) -> String {
+    let mut literal = String::from("\"");
+
+    for byte in string.bytes() {
+        match byte {
+            b'"' => literal.push_str("\\\""),
+            b'\\' => literal.push_str("\\\\"),
+            b'?' => literal.push_str("\\?"),
+            b'\n' => literal.push_str("\\n"),
+            b' '..=b'~' => literal.push(byte as char),
+            _ => _ = write!(literal, "\\{byte:03o}"),
+        }
+    }
+
+    literal.push('"');
+    literal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::transpile;
+    use crate::analysis::testing::lower_source;
+    use std::{fs, process::Command};
+
+    /// Check and transpile a single module.
+    fn transpile_source(source: &'static str) -> String {
+        let (package, program) = lower_source(source);
+        transpile(&program, &package.resolution, &package.typeck, true)
+    }
+
+    /// Transpile a module, compile it with the system's C compiler, and run it, returning whether it succeeded,
+    /// what it printed, and what it printed to standard error. Returns [None] if there's no C compiler.
+    fn run(name: &str, source: &'static str) -> Option<(bool, String, String)> {
+        let code = transpile_source(source);
+
+        let dir = std::env::temp_dir().join(format!("wright-c-test-{name}-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let c_file = dir.join("main.c");
+        let executable = dir.join("main");
+        fs::write(&c_file, &code).unwrap();
+
+        let compiled = Command::new("cc")
+            .args(["-std=c11", "-Wall", "-Wextra", "-Werror", "-o"])
+            .arg(&executable)
+            .arg(&c_file)
+            .arg("-lm")
+            .output();
+
+        let Ok(compiled) = compiled else {
+            eprintln!("skipping the test, since there's no C compiler (`cc`)");
+            return None;
+        };
+
+        assert!(
+            compiled.status.success(),
+            "{}\n{code}",
+            String::from_utf8_lossy(&compiled.stderr)
+        );
+
+        let output = Command::new(&executable).output().unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        Some((
+            output.status.success(),
+            String::from_utf8(output.stdout).unwrap(),
+            String::from_utf8(output.stderr).unwrap(),
+        ))
+    }
+
+    /// Run a module, which must not trap, returning what it printed.
+    fn output(name: &str, source: &'static str) -> Option<String> {
+        let (success, out, err) = run(name, source)?;
+        assert!(success, "{err}");
+        Some(out)
+    }
+
+    #[test]
+    fn test_arithmetic_and_calls() {
+        let out = output(
+            "arithmetic",
+            "use wright::io::println;\n\
+            func fib(n: u64) -> u64 { if n < 2 { n } else { fib(n - 1) + fib(n - 2) } }\n\
+            func main() {\n\
+                let zero: u8 = 0;\n\
+                let one: u16 = 1;\n\
+                let min: i64 = -9223372036854775808;\n\
+                println(fib(20)); println(7 / 2); println(-7 % 3); println(~zero); println(one << 15);\n\
+                println(min); println(min % -1); println(1 == 2 || 3 != 4);\n\
+            }",
+        );
+
+        if let Some(out) = out {
+            assert_eq!(out, "6765\n3\n-1\n255\n32768\n-9223372036854775808\n0\ntrue\n");
+        }
+    }
+
+    #[test]
+    fn test_loops_and_slots() {
+        let out = output(
+            "loops",
+            "use wright::io::println;\n\
+            func main() {\n\
+                var total: u64 = 0;\n\
+                for i in 1..=10 { total += i; }\n\
+                var n = 0;\n\
+                while n < 3 { n += 1; }\n\
+                println(total); println(n);\n\
+            }",
+        );
+
+        if let Some(out) = out {
+            assert_eq!(out, "55\n3\n");
+        }
+    }
+
+    #[test]
+    fn test_records_and_generics() {
+        let out = output(
+            "records",
+            "use wright::io::println;\n\
+            record Point { x: u64, y: u64 }\n\
+            record Box<T> { value: T }\n\
+            impl Point { func grow(self: @mut Self) { self.x = self.x + 1; } }\n\
+            trait Shape { func area(self: @Self) -> u64; func double(self: @Self) -> u64 { self.area() * 2 } }\n\
+            impl Shape for Point { func area(self: @Self) -> u64 { self.x * self.y } }\n\
+            func total<T: Shape>(shape: @T) -> u64 { shape.area() + shape.double() }\n\
+            func unbox<T>(b: Box<T>) -> T { b.value }\n\
+            func main() {\n\
+                var p = Point { y: 3, x: 1 };\n\
+                let q = p;\n\
+                p.grow();\n\
+                println(p); println(q.x); println(p == q); println(@p != @q);\n\
+                println(p.double()); println(total(@p));\n\
+                println(unbox(Box { value: true })); println(Box { value: p });\n\
+            }",
+        );
+
+        if let Some(out) = out {
+            assert_eq!(
+                out,
+                "Point { x: 2, y: 3 }\n1\nfalse\ntrue\n12\n18\ntrue\nBox<Point> { value: Point { x: 2, y: 3 } }\n"
+            );
+        }
+    }
+
+    #[test]
+    fn test_unions_and_enums() {
+        let out = output(
+            "unions",
+            "use wright::io::println;\n\
+            union Number { small: u8, big: u64 }\n\
+            enum Color { Red, Green, Blue }\n\
+            func size(n: Number) -> u64 { match n { Number { small: 0 } => 0, Number { big } => big, _ => 1 } }\n\
+            func code(color: Color) -> u8 { match color { Color::Red => 1, Color::Green | Color::Blue => 2 } }\n\
+            func main() {\n\
+                println(size(Number { small: 0 })); println(size(Number { small: 5 })); println(size(Number { big: 9 }));\n\
+                println(code(Color::Red)); println(code(Color::Blue)); println(Color::Green);\n\
+                println(Number { big: 4 }); println(Number { small: 4 } is u8);\n\
+                println(Number { small: 4 } == Number { small: 4 }); println(Color::Red == Color::Blue);\n\
+            }",
+        );
+
+        if let Some(out) = out {
+            assert_eq!(out, "0\n1\n9\n1\n2\nColor::Green\nNumber { big: 4 }\ntrue\ntrue\nfalse\n");
+        }
+    }
+
+    #[test]
+    fn test_closures() {
+        let out = output(
+            "closures",
+            "use wright::io::println;\n\
+            func apply(f: func(u8) -> u8, x: u8) -> u8 { f(x) }\n\
+            func double(x: u8) -> u8 { x * 2 }\n\
+            func main() {\n\
+                let offset = 3;\n\
+                var count = 0;\n\
+                let add = func(x: u8) -> u8 { count += 1; x + offset };\n\
+                println(apply(add, 4) + apply(add, 1) + count);\n\
+                println(apply(double, 5));\n\
+                let print = println;\n\
+                print(\"done\");\n\
+                println(double);\n\
+            }",
+        );
+
+        if let Some(out) = out {
+            assert_eq!(out, "13\n10\ndone\n<func double>\n");
+        }
+    }
+
+    #[test]
+    fn test_traps() {
+        let cases = [
+            ("func main() -> u8 { let x: u8 = 255; x + 1 }", "arithmetic overflow"),
+            ("func main() -> i8 { let x: i8 = -128; -x }", "arithmetic overflow"),
+            (
+                "func main() -> i64 { let x: i64 = -9223372036854775808; x / -1 }",
+                "arithmetic overflow",
+            ),
+            ("func main() -> u32 { let x: u32 = 0; 1 / x }", "division by zero"),
+            (
+                "func main() -> u8 { let x: u8 = 8; 1 << x }",
+                "shift by at least the number of bits in the type",
+            ),
+        ];
+
+        for (index, (source, message)) in cases.into_iter().enumerate() {
+            let Some((success, _, err)) = run(&format!("trap-{index}"), source) else {
+                return;
+            };
+
+            assert!(!success, "{source}");
+            assert!(err.starts_with(&format!("error: {message}, at ")), "{source}: {err}");
+        }
+    }
+
+    #[test]
+    fn test_line_directives() {
+        let code = transpile_source("func main() -> u8 {\nlet x: u8 = 255;\nlet y = x + 1;\ny\n}");
+        let line = code
+            .lines()
+            .position(|line| line.contains("wr_add_u8"))
+            .unwrap();
+        assert!(code.lines().nth(line - 1).unwrap().starts_with("#line 3 "));
+
+        let Some((_, _, err)) =
+            run("lines", "func main() -> u8 {\nlet x: u8 = 255;\nlet y = x + 1;\ny\n}")
+        else {
+            return;
+        };
+
+        assert!(err.ends_with(":3\n"), "{err}");
+    }
+}
@@ -0,0 +1,239 @@
+#include <inttypes.h>
+#include <math.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+/* The value of expressions that don't produce one. */
+typedef uint8_t wr_void;
+
+/* A string: its bytes (which aren't followed by a zero) and how many there are. */
+typedef struct {
+    const char *bytes;
+    size_t length;
+} wr_str;
+
+#define WR_STR(literal) ((wr_str){ (literal), sizeof(literal) - 1 })
+
+/* A function or closure: its code, which takes the environment as its first argument, the environment (the values
+   a closure captures), and what to print it as. */
+typedef void (*wr_code)(void);
+
+typedef struct {
+    wr_code code;
+    void *env;
+    const char *name;
+} wr_func;
+
+#define WR_FUNC(code, env, name) ((wr_func){ (wr_code)(code), (env), (name) })
+
+/* Stop the program, because it did something the language doesn't define (like overflowing an integer). */
+#define WR_TRAP(message) wr_trap((message), __FILE__, __LINE__)
+
+static inline _Noreturn void wr_trap(const char *message, const char *file, int line) {
+    fflush(stdout);
+    fprintf(stderr, "error: %s, at %s:%d\n", message, file, line);
+    exit(EXIT_FAILURE);
+}
+
+/* Allocate memory that lives until the program ends. */
+static inline void *wr_alloc(size_t size) {
+    void *memory = malloc(size ? size : 1);
+
+    if (memory == NULL) {
+        WR_TRAP("out of memory");
+    }
+
+    return memory;
+}
+
+/* Arithmetic that can overflow returns whether it did, and only writes the result if it didn't. Unsigned integers
+   are multiplied as `uintmax_t`, since smaller ones are promoted to `int`, which can overflow. */
+#define WR_UNSIGNED(name, type, max)                                            \
+    static inline bool wr_add_##name(type *result, type a, type b) {            \
+        if (a > max - b) return true;                                           \
+        *result = (type)(a + b);                                                \
+        return false;                                                           \
+    }                                                                           \
+    static inline bool wr_sub_##name(type *result, type a, type b) {            \
+        if (a < b) return true;                                                 \
+        *result = (type)(a - b);                                                \
+        return false;                                                           \
+    }                                                                           \
+    static inline bool wr_mul_##name(type *result, type a, type b) {            \
+        if (b != 0 && a > max / b) return true;                                 \
+        *result = (type)((uintmax_t)a * b);                                     \
+        return false;                                                           \
+    }                                                                           \
+    static inline bool wr_neg_##name(type *result, type a) {                    \
+        if (a != 0) return true;                                                \
+        *result = 0;                                                            \
+        return false;                                                           \
+    }
+
+#define WR_SIGNED(name, type, min, max)                                         \
+    static inline bool wr_add_##name(type *result, type a, type b) {            \
+        if ((b > 0 && a > max - b) || (b < 0 && a < min - b)) return true;      \
+        *result = (type)(a + b);                                                \
+        return false;                                                           \
+    }                                                                           \
+    static inline bool wr_sub_##name(type *result, type a, type b) {            \
+        if ((b < 0 && a > max + b) || (b > 0 && a < min + b)) return true;      \
+        *result = (type)(a - b);                                                \
+        return false;                                                           \
+    }                                                                           \
+    static inline bool wr_mul_##name(type *result, type a, type b) {            \
+        if (a > 0 ? (b > 0 ? a > max / b : b < min / a)                         \
+                  : (b > 0 ? a < min / b : a != 0 && b < max / a)) return true; \
+        *result = (type)(a * b);                                                \
+        return false;                                                           \
+    }                                                                           \
+    static inline bool wr_div_##name(type *result, type a, type b) {            \
+        if (a == min && b == -1) return true;                                   \
+        *result = (type)(a / b);                                                \
+        return false;                                                           \
+    }                                                                           \
+    static inline type wr_rem_##name(type a, type b) {                          \
+        return b == -1 ? 0 : (type)(a % b);                                     \
+    }                                                                           \
+    static inline bool wr_neg_##name(type *result, type a) {                    \
+        if (a == min) return true;                                              \
+        *result = (type)-a;                                                     \
+        return false;                                                           \
+    }
+
+WR_UNSIGNED(u8, uint8_t, UINT8_MAX)
+WR_UNSIGNED(u16, uint16_t, UINT16_MAX)
+WR_UNSIGNED(u32, uint32_t, UINT32_MAX)
+WR_UNSIGNED(u64, uint64_t, UINT64_MAX)
+WR_SIGNED(i8, int8_t, INT8_MIN, INT8_MAX)
+WR_SIGNED(i16, int16_t, INT16_MIN, INT16_MAX)
+WR_SIGNED(i32, int32_t, INT32_MIN, INT32_MAX)
+WR_SIGNED(i64, int64_t, INT64_MIN, INT64_MAX)
+
+/* Strings are equal if they have the same bytes. */
+static inline bool wr_str_eq(wr_str a, wr_str b) {
+    return a.length == b.length && memcmp(a.bytes, b.bytes, a.length) == 0;
+}
+
+/* Values are printed the way `wright::io::print` prints them. */
+static inline void wr_print_str(wr_str value) {
+    fwrite(value.bytes, 1, value.length, stdout);
+}
+
+static inline void wr_print_u64(uint64_t value) {
+    printf("%" PRIu64, value);
+}
+
+static inline void wr_print_i64(int64_t value) {
+    printf("%" PRId64, value);
+}
+
+static inline void wr_print_bool(bool value) {
+    fputs(value ? "true" : "false", stdout);
+}
+
+static inline void wr_print_void(wr_void value) {
+    (void)value;
+    fputs("()", stdout);
+}
+
+static inline void wr_print_func(wr_func value) {
+    printf("<%s>", value.name);
+}
+
+static inline void wr_print_char(uint32_t value) {
+    char bytes[4];
+    int length;
+
+    if (value < 0x80) {
+        bytes[0] = (char)value;
+        length = 1;
+    } else if (value < 0x800) {
+        bytes[0] = (char)(0xC0 | (value >> 6));
+        bytes[1] = (char)(0x80 | (value & 0x3F));
+        length = 2;
+    } else if (value < 0x10000) {
+        bytes[0] = (char)(0xE0 | (value >> 12));
+        bytes[1] = (char)(0x80 | ((value >> 6) & 0x3F));
+        bytes[2] = (char)(0x80 | (value & 0x3F));
+        length = 3;
+    } else {
+        bytes[0] = (char)(0xF0 | (value >> 18));
+        bytes[1] = (char)(0x80 | ((value >> 12) & 0x3F));
+        bytes[2] = (char)(0x80 | ((value >> 6) & 0x3F));
+        bytes[3] = (char)(0x80 | (value & 0x3F));
+        length = 4;
+    }
+
+    fwrite(bytes, 1, (size_t)length, stdout);
+}
+
+/* Floating point numbers are printed with the fewest digits that read back as the same number, without an
+   exponent. */
+static inline void wr_print_f64(double value) {
+    char buffer[32], digits[20];
+    int precision, count = 0, exponent, index;
+    const char *c;
+
+    if (isnan(value)) {
+        fputs("NaN", stdout);
+        return;
+    }
+
+    if (signbit(value)) {
+        putchar('-');
+        value = -value;
+    }
+
+    if (isinf(value)) {
+        fputs("inf", stdout);
+        return;
+    }
+
+    if (value == 0) {
+        putchar('0');
+        return;
+    }
+
+    for (precision = 1; precision < 17; precision++) {
+        snprintf(buffer, sizeof buffer, "%.*e", precision - 1, value);
+
+        if (strtod(buffer, NULL) == value) {
+            break;
+        }
+    }
+
+    snprintf(buffer, sizeof buffer, "%.*e", precision - 1, value);
+
+    for (c = buffer; *c != 'e'; c++) {
+        if (*c != '.') {
+            digits[count++] = *c;
+        }
+    }
+
+    exponent = atoi(c + 1);
+
+    if (exponent < 0) {
+        fputs("0.", stdout);
+
+        for (index = 1; index < -exponent; index++) {
+            putchar('0');
+        }
+
+        fwrite(digits, 1, (size_t)count, stdout);
+    } else if (exponent + 1 >= count) {
+        fwrite(digits, 1, (size_t)count, stdout);
+
+        for (index = count; index <= exponent; index++) {
+            putchar('0');
+        }
+    } else {
+        fwrite(digits, 1, (size_t)exponent + 1, stdout);
+        putchar('.');
+        fwrite(digits + exponent + 1, 1, (size_t)(count - exponent - 1), stdout);
+    }
+}
@@ -0,0 +1,2103 @@
+//! Generating machine code from the MIR with [Cranelift](https://cranelift.dev).
+//!
+//! A program can either be compiled in memory and run right away ([run], which `wright run --jit` uses), or
+//! compiled to an object file ([object]) for the machine wright is running on. Object files call a small runtime
+//! for printing, allocating, and trapping, which is C source ([RUNTIME]) that they're linked with, like
+//! `cc main.o runtime.c -lm`. Code run in memory calls a runtime written in Rust instead, which prints to any
+//! [Write]r.
+//!
+//! Every value is a list of Cranelift values, one for each of the scalars it's made of: atomic types are one
+//! integer or float (`bool` is an `i8`, and `char` is an `i32`), references are a pointer, strings are a pointer and
+//! a length, function values (including closures) are their code, environment, and name, records are their fields
+//! one after another, enums are the `i32` index of their variant, and unions are an `i32` tag followed by enough
+//! 64-bit words to hold any of their variants. `void` is no values at all. In memory, each of the scalars is
+//! aligned to its size, and unions hold their variant 8 bytes in. Functions that return more than one scalar write
+//! it to memory that their caller passes a pointer to instead. Like the other backends, it doesn't use the
+//! layouts of [`analysis::layout`](crate::analysis::layout) yet.
+//!
+//! Slots are stack slots, and the parameters of basic blocks are the parameters of Cranelift's blocks. Integer
+//! overflow, division by zero, and the like stop the program like they do in the [VM](crate::vm), and values are
+//! printed the same way. A trap in code run in memory doesn't exit the process: the runtime records it, and the
+//! generated code returns from every call in progress (checking after each call if the program has trapped).
+//! Like in the C backend, slots live on the stack, so a reference to one must not outlive the call that made it,
+//! and very deep recursion overflows the stack instead of trapping.
+
+use super::{
+    Access, Callee, CompareAs, Context, Helper, Instance, Instances, Layouts, NamedKind,
+    SHIFT_TRAP, Scalar, Thunk, UNION_PAYLOAD, VARIANT_TRAP, compare_as, constant_trap, mangle,
+    split_captures, strip_one_reference, unique_name,
+};
+use crate::{
+    analysis::{
+        builtins::Builtin,
+        resolve::{DefId, Resolution},
+        typeck::{TypeckResults, ty::Ty},
+    },
+    ast::{
+        expr::{BinaryOperation, UnaryOperation},
+        ty::AtomicTyVariant,
+    },
+    mir::{
+        BlockId, Body, Const, Inst, Jump, Operand, Place, PlaceBase, Program, Projection, Rvalue,
+        Terminator, Value,
+    },
+    source_tracking::fragment::Fragment,
+};
+use cranelift_codegen::{
+    Context as FunctionContext,
+    ir::{
+        self, AbiParam, Block, FuncRef, InstBuilder, MemFlags, Signature, StackSlotData,
+        StackSlotKind, Type, UserFuncName,
+        condcodes::{FloatCC, IntCC},
+        types,
+    },
+    isa::OwnedTargetIsa,
+    settings::{self, Configurable},
+};
+use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext, Switch};
+use cranelift_jit::{JITBuilder, JITModule};
+use cranelift_module::{
+    DataDescription, DataId, FuncId, Linkage, Module, ModuleError, default_libcall_names,
+};
+use cranelift_object::{ObjectBuilder, ObjectModule};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    io::Write,
+};
+
+mod runtime;
+
+/// The C source of the runtime that object files from [object] call, which they must be linked with.
+pub const RUNTIME: &str = include_str!("cranelift/runtime.c");
+
+/// A trap that stopped a program run by [run].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Trap {
+    /// What went wrong.
+    pub message: String,
+    /// The name of the source file of the code that trapped.
+    pub file: String,
+    /// The line (starting at 1) of the code that trapped.
+    pub line: u32,
+}
+
+impl fmt::Display for Trap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}, at {}:{}", self.message, self.file, self.line)
+    }
+}
+
+impl std::error::Error for Trap {}
+
+/// An error compiling or running a program with Cranelift.
+#[derive(Debug)]
+pub enum Error {
+    /// Cranelift can't generate code for the machine wright is running on.
+    Target(String),
+    /// Cranelift couldn't compile a function, or couldn't put the functions together.
+    Module(Box<ModuleError>),
+    /// The object file couldn't be written.
+    Object(String),
+    /// The program has no `main` function to run.
+    NoMain,
+    /// The program trapped.
+    Trap(Trap),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Target(message) => write!(f, "unsupported target: {message}"),
+            Error::Module(error) => write!(f, "couldn't compile the program: {error}"),
+            Error::Object(message) => write!(f, "couldn't write the object file: {message}"),
+            Error::NoMain => write!(f, "the program has no `main` function"),
+            Error::Trap(trap) => trap.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<ModuleError> for Error {
+    fn from(error: ModuleError) -> Self {
+        Error::Module(Box::new(error))
+    }
+}
+
+/// Compile a program (which must have been checked without errors) to machine code in memory, and run its `main`
+/// function, writing anything it prints to the given output.
+pub fn run(
+    program: &Program,
+    resolution: &Resolution,
+    typeck: &TypeckResults,
+    out: &mut dyn Write,
+) -> Result<(), Error> {
+    let mut builder = JITBuilder::with_isa(host_isa()?, default_libcall_names());
+    builder.symbols(runtime::symbols());
+
+    let cx = Context {
+        program,
+        resolution,
+        typeck,
+    };
+
+    let mut generator = Generator::new(cx, JITModule::new(builder));
+    let main = generator.generate()?;
+    let mut module = generator.module;
+    module.finalize_definitions()?;
+
+    let result = match main {
+        Some(main) => {
+            // SAFETY: The entry function takes nothing and returns an `i32`, with the platform's C calling
+            // convention.
+            let main: extern "C" fn() -> i32 =
+                unsafe { std::mem::transmute(module.get_finalized_function(main)) };
+
+            runtime::with_state(out, || _ = main()).map_err(Error::Trap)
+        }
+
+        None => Err(Error::NoMain),
+    };
+
+    // SAFETY: Nothing that was compiled is used after this.
+    unsafe { module.free_memory() };
+    result
+}
+
+/// Compile a program (which must have been checked without errors) to an object file for the machine wright is
+/// running on. If the program has a `main` function, the object file has a C `main` function that runs it. The
+/// object file calls the functions of the [RUNTIME], which it must be linked with.
+pub fn object(
+    program: &Program,
+    resolution: &Resolution,
+    typeck: &TypeckResults,
+) -> Result<Vec<u8>, Error> {
+    let builder = ObjectBuilder::new(host_isa()?, "wright", default_libcall_names())?;
+
+    let cx = Context {
+        program,
+        resolution,
+        typeck,
+    };
+
+    let mut generator = Generator::new(cx, ObjectModule::new(builder));
+    generator.generate()?;
+
+    (generator.module.finish().emit()).map_err(|error| Error::Object(error.to_string()))
+}
+
+/// Get the target for the machine wright is running on.
+fn host_isa() -> Result<OwnedTargetIsa, Error> {
+    let mut flags = settings::builder();
+
+    // Calls to the runtime can be far away from the generated code.
+    for (flag, value) in [
+        ("opt_level", "speed"),
+        ("is_pic", "true"),
+        ("use_colocated_libcalls", "false"),
+    ] {
+        (flags.set(flag, value)).map_err(|error| Error::Target(error.to_string()))?;
+    }
+
+    let isa = cranelift_native::builder().map_err(|message| Error::Target(message.to_owned()))?;
+    (isa.finish(settings::Flags::new(flags))).map_err(|error| Error::Target(error.to_string()))
+}
+
+/// A function that's been declared but not generated yet.
+#[derive(Debug)]
+enum Pending {
+    /// A helper function for a type.
+    Helper(Helper, Ty, FuncId),
+    /// A thunk.
+    Thunk(Thunk, FuncId),
+}
+
+/// The type of a parameter or result of a runtime function, where `None` is a pointer or size.
+type RuntimeType = Option<Type>;
+
+/// The functions of the runtime, with the types of their parameters and results.
+const RUNTIME_FUNCTIONS: [(&str, &[RuntimeType], Option<RuntimeType>); 12] = [
+    ("wr_rt_trap", &[None, None, None, None, Some(types::I32)], None),
+    ("wr_rt_trapped", &[], Some(Some(types::I8))),
+    ("wr_rt_alloc", &[None], Some(None)),
+    ("wr_rt_str_eq", &[None, None, None, None], Some(Some(types::I8))),
+    ("wr_rt_fmod", &[Some(types::F64), Some(types::F64)], Some(Some(types::F64))),
+    ("wr_rt_print_str", &[None, None], None),
+    ("wr_rt_print_u64", &[Some(types::I64)], None),
+    ("wr_rt_print_i64", &[Some(types::I64)], None),
+    ("wr_rt_print_f64", &[Some(types::F64)], None),
+    ("wr_rt_print_char", &[Some(types::I32)], None),
+    ("wr_rt_print_bool", &[Some(types::I8)], None),
+    ("wr_rt_print_func", &[None], None),
+];
+
+/// The state of generating a module.
+struct Generator<'a, M: Module> {
+    /// The program being generated.
+    cx: Context<'a>,
+    /// The Cranelift module the code goes in.
+    module: M,
+    /// The type of pointers.
+    pointer: Type,
+    /// The instances of bodies, with the names of their functions.
+    instances: Instances,
+    /// Every function that's been declared, by name.
+    functions: HashMap<String, FuncId>,
+    /// Every name given to a function or data object so far.
+    names: HashSet<String>,
+    /// The layout of every type that's been used.
+    layouts: Layouts<'a, Type>,
+    /// Every helper function.
+    helpers: HashMap<(Helper, Ty), FuncId>,
+    /// Every thunk.
+    thunks: HashMap<Thunk, FuncId>,
+    /// The helpers and thunks that haven't been generated yet.
+    pending: Vec<Pending>,
+    /// How many values each closure body captures, by the index of the body.
+    closures: HashMap<usize, usize>,
+    /// The data object of every string constant.
+    strings: HashMap<String, DataId>,
+}
+
+impl<'a, M: Module> Generator<'a, M> {
+    /// Start generating a program into a module.
+    fn new(cx: Context<'a>, module: M) -> Self {
+        let pointer = module.target_config().pointer_type();
+        let names = RUNTIME_FUNCTIONS
+            .iter()
+            .map(|(name, ..)| (*name).to_owned());
+
+        Generator {
+            cx,
+            module,
+            pointer,
+            instances: Instances::default(),
+            functions: HashMap::new(),
+            names: names.chain(["main".to_owned()]).collect(),
+            layouts: Layouts::new(cx, pointer),
+            helpers: HashMap::new(),
+            thunks: HashMap::new(),
+            pending: Vec::new(),
+            closures: HashMap::new(),
+            strings: HashMap::new(),
+        }
+    }
+
+    /// Generate every function of the program, and a `main` function that runs the program's `main` function (if
+    /// it has one), returning the `main` function.
+    fn generate(&mut self) -> Result<Option<FuncId>, Error> {
+        let mut entry = None;
+
+        for instance in self.cx.roots().collect::<Vec<_>>() {
+            let body = &self.cx.program.bodies[instance.body];
+            let is_entry = body.name == "main" && body.params.is_empty();
+            let function = self.instance_function(instance);
+
+            if is_entry {
+                entry = Some((function, body.ret.clone()));
+            }
+        }
+
+        let main = match entry {
+            Some((function, ret)) => {
+                let mut signature = self.module.make_signature();
+                signature.returns.push(AbiParam::new(types::I32));
+                let main = (self.module).declare_function("main", Linkage::Export, &signature)?;
+
+                self.define(main, |e| {
+                    e.call_direct(function, &ret, Vec::new());
+                    let zero = e.b.ins().iconst(types::I32, 0);
+                    e.b.ins().return_(&[zero]);
+                })?;
+
+                Some(main)
+            }
+
+            None => None,
+        };
+
+        loop {
+            if let Some((instance, name)) = self.instances.next() {
+                let function = self.functions[&name];
+                self.define(function, |e| e.generate_body(instance))?;
+            } else if let Some(pending) = self.pending.pop() {
+                match pending {
+                    Pending::Helper(helper, ty, function) => {
+                        self.define(function, |e| e.generate_helper(helper, &ty))?
+                    }
+
+                    Pending::Thunk(thunk, function) => {
+                        self.define(function, |e| e.generate_thunk(&thunk))?
+                    }
+                }
+            } else {
+                break;
+            }
+        }
+
+        Ok(main)
+    }
+
+    /// Generate the code of a declared function.
+    fn define(
+        &mut self,
+        function: FuncId,
+        generate: impl FnOnce(&mut Emitter<'_, 'a, '_, M>),
+    ) -> Result<(), Error> {
+        let signature = (self.module.declarations().get_function_decl(function))
+            .signature
+            .clone();
+        let name = UserFuncName::user(0, function.as_u32());
+        let mut func = ir::Function::with_name_signature(name, signature);
+        let mut builder_context = FunctionBuilderContext::new();
+
+        let mut emitter = Emitter {
+            b: FunctionBuilder::new(&mut func, &mut builder_context),
+            g: self,
+            functions: HashMap::new(),
+            location: (String::new(), 0),
+            unwind: None,
+        };
+
+        let entry = emitter.b.create_block();
+        emitter.b.append_block_params_for_function_params(entry);
+        emitter.b.switch_to_block(entry);
+        generate(&mut emitter);
+        emitter.finish();
+
+        let mut context = FunctionContext::for_function(func);
+        self.module.define_function(function, &mut context)?;
+        Ok(())
+    }
+
+    /// Declare a function that's only used in the module, with a name that's made unique.
+    fn declare(&mut self, name: String, signature: &Signature) -> FuncId {
+        let name = unique_name(&mut self.names, name);
+
+        // The name is unique, so declaring the function can't conflict with anything.
+        let function = (self
+            .module
+            .declare_function(&name, Linkage::Local, signature))
+        .expect("functions with unique names can always be declared");
+
+        self.functions.insert(name, function);
+        function
+    }
+
+    /// Declare a function of the runtime, if it hasn't been already.
+    fn runtime(&mut self, name: &'static str) -> FuncId {
+        if let Some(function) = self.functions.get(name) {
+            return *function;
+        }
+
+        let (_, params, ret) = (RUNTIME_FUNCTIONS.iter())
+            .find(|(function, ..)| *function == name)
+            .expect("every runtime function is listed");
+
+        let mut signature = self.module.make_signature();
+        let pointer = self.pointer;
+
+        for param in *params {
+            signature
+                .params
+                .push(AbiParam::new(param.unwrap_or(pointer)));
+        }
+
+        if let Some(ret) = ret {
+            signature
+                .returns
+                .push(AbiParam::new(ret.unwrap_or(pointer)));
+        }
+
+        let function = (self
+            .module
+            .declare_function(name, Linkage::Import, &signature))
+        .expect("runtime functions are only declared once");
+
+        self.functions.insert(name.to_owned(), function);
+        function
+    }
+
+    /// Get the data object of a string constant (followed by a zero, so it can be passed to C), adding it if it's
+    /// new.
+    fn string(&mut self, string: &str) -> DataId {
+        if let Some(data) = self.strings.get(string) {
+            return *data;
+        }
+
+        let name = unique_name(&mut self.names, format!("wr_str_{}", self.strings.len()));
+        let data = (self
+            .module
+            .declare_data(&name, Linkage::Local, false, false))
+        .expect("data objects with unique names can always be declared");
+
+        let mut description = DataDescription::new();
+        description.define([string.as_bytes(), &[0]].concat().into_boxed_slice());
+        (self.module.define_data(data, &description)).expect("data objects are only defined once");
+
+        self.strings.insert(string.to_owned(), data);
+        data
+    }
+
+    /// Get the function of an instance, declaring it if it's new.
+    fn instance_function(&mut self, instance: Instance) -> FuncId {
+        let body = &self.cx.program.bodies[instance.body];
+        let mut name = format!("wr_{}", mangle(&body.name));
+
+        for (_, ty) in &instance.args {
+            name.push('_');
+            name.push_str(&mangle(&ty.display(self.cx.resolution).to_string()));
+        }
+
+        let Generator {
+            instances, names, ..
+        } = self;
+
+        let name = (instances.get(instance.clone(), || unique_name(names, name))).to_owned();
+
+        if let Some(function) = self.functions.get(&name) {
+            return *function;
+        }
+
+        let substitution = instance.substitution();
+        let captures = self.closures.get(&instance.body).copied();
+        let (_, params) = split_captures(body, captures);
+
+        let params: Vec<Ty> = (params.iter())
+            .map(|param| body.value_ty(*param).substitute(&substitution))
+            .collect();
+
+        let ret = body.ret.substitute(&substitution);
+        let signature = self.signature(&params, &ret, captures.is_some());
+
+        let function = (self
+            .module
+            .declare_function(&name, Linkage::Local, &signature))
+        .expect("functions with unique names can always be declared");
+
+        self.functions.insert(name, function);
+        function
+    }
+
+    /// Get the signature of a function with parameters and a result of the given types. Results of more than one
+    /// scalar are written to memory that the first parameter points to. Functions with an environment (the code of
+    /// function values) take it before their parameters.
+    fn signature(&mut self, params: &[Ty], ret: &Ty, env: bool) -> Signature {
+        let mut signature = self.module.make_signature();
+        let pointer = self.pointer;
+        let ret = self.layouts.layout(ret);
+
+        match ret.parts.len() {
+            0 | 1 => (signature.returns).extend(ret.parts.iter().map(|(_, ty)| AbiParam::new(*ty))),
+            _ => signature.params.push(AbiParam::new(pointer)),
+        }
+
+        if env {
+            signature.params.push(AbiParam::new(pointer));
+        }
+
+        for param in params {
+            let layout = self.layouts.layout(param);
+            (signature.params).extend(layout.parts.iter().map(|(_, ty)| AbiParam::new(*ty)));
+        }
+
+        signature
+    }
+
+    /// Get the helper function for a type, declaring it if it's new.
+    fn helper(&mut self, helper: Helper, ty: &Ty) -> FuncId {
+        if let Some(function) = self.helpers.get(&(helper, ty.clone())) {
+            return *function;
+        }
+
+        let layout = self.layouts.layout(ty);
+        let mut signature = self.module.make_signature();
+        let params = layout.parts.iter().map(|(_, ty)| AbiParam::new(*ty));
+
+        let prefix = match helper {
+            Helper::Print => {
+                signature.params.extend(params);
+                "wr_print"
+            }
+
+            Helper::Eq => {
+                signature.params.extend(params.clone().chain(params));
+                signature.returns.push(AbiParam::new(types::I8));
+                "wr_eq"
+            }
+        };
+
+        let display = ty.display(self.cx.resolution).to_string();
+        let function = self.declare(format!("{prefix}_{}", mangle(&display)), &signature);
+
+        self.helpers.insert((helper, ty.clone()), function);
+        self.pending
+            .push(Pending::Helper(helper, ty.clone(), function));
+        function
+    }
+
+    /// Get the function that a function value runs, declaring it if it's new, and what the value is printed as.
+    fn thunk(&mut self, thunk: Thunk) -> (FuncId, String) {
+        let (name, label, params, ret) = match &thunk {
+            Thunk::Instance(instance) => {
+                let body = &self.cx.program.bodies[instance.body];
+                let substitution = instance.substitution();
+
+                let params: Vec<Ty> = (body.params.iter())
+                    .map(|param| body.value_ty(*param).substitute(&substitution))
+                    .collect();
+
+                let ret = body.ret.substitute(&substitution);
+                let name = format!("wr_{}_value", mangle(&body.name));
+                (name, format!("func {}", body.name), params, ret)
+            }
+
+            Thunk::Builtin(builtin, ty) => {
+                let params = match ty {
+                    Ty::Function { params, .. } => params.clone(),
+                    _ => Vec::new(),
+                };
+
+                let name = format!("wr_{}_value", builtin.name());
+                (name, format!("func wright::io::{}", builtin.name()), params, Ty::Void)
+            }
+        };
+
+        if let Some(function) = self.thunks.get(&thunk) {
+            return (*function, label);
+        }
+
+        let signature = self.signature(&params, &ret, true);
+        let function = self.declare(name, &signature);
+        self.thunks.insert(thunk.clone(), function);
+        self.pending.push(Pending::Thunk(thunk, function));
+        (function, label)
+    }
+}
+
+/// The state of generating the code of one function.
+struct Emitter<'g, 'a, 'f, M: Module> {
+    /// The generator of the module.
+    g: &'g mut Generator<'a, M>,
+    /// The builder of the function.
+    b: FunctionBuilder<'f>,
+    /// The reference to every function that's been called from this one.
+    functions: HashMap<FuncId, FuncRef>,
+    /// The file and line that traps are reported at.
+    location: (String, u32),
+    /// The block that returns from the function once the program has trapped, if it's been needed.
+    unwind: Option<Block>,
+}
+
+impl<M: Module> Emitter<'_, '_, '_, M> {
+    /// Finish the function, once every block has been filled.
+    fn finish(mut self) {
+        if let Some(unwind) = self.unwind {
+            self.b.switch_to_block(unwind);
+
+            let returns: Vec<Type> = (self.b.func.signature.returns.iter())
+                .map(|param| param.value_type)
+                .collect();
+
+            let zeros: Vec<ir::Value> = returns.into_iter().map(|ty| self.zero(ty)).collect();
+            self.b.ins().return_(&zeros);
+        }
+
+        self.b.seal_all_blocks();
+        self.b.finalize();
+    }
+
+    /// Get the block that returns from the function once the program has trapped.
+    fn unwind(&mut self) -> Block {
+        *self.unwind.get_or_insert_with(|| self.b.create_block())
+    }
+
+    /// Get the reference to a function, to call it or take its address.
+    fn function(&mut self, function: FuncId) -> FuncRef {
+        if let Some(reference) = self.functions.get(&function) {
+            return *reference;
+        }
+
+        let reference = self.g.module.declare_func_in_func(function, self.b.func);
+        self.functions.insert(function, reference);
+        reference
+    }
+
+    /// Get the address of a string constant (which is followed by a zero), and its length.
+    fn string(&mut self, string: &str) -> (ir::Value, ir::Value) {
+        let data = self.g.string(string);
+        let global = self.g.module.declare_data_in_func(data, self.b.func);
+        let pointer = self.b.ins().symbol_value(self.g.pointer, global);
+        let length = self.b.ins().iconst(self.g.pointer, string.len() as i64);
+        (pointer, length)
+    }
+
+    /// Call a function of the runtime, returning its result (if it has one).
+    fn call_runtime(&mut self, name: &'static str, args: &[ir::Value]) -> Option<ir::Value> {
+        let function = self.g.runtime(name);
+        let reference = self.function(function);
+        let call = self.b.ins().call(reference, args);
+        self.b.inst_results(call).first().copied()
+    }
+
+    /// Get an integer constant of a type, given as a (signed or unsigned) number that fits it.
+    fn iconst(&mut self, ty: Type, value: i128) -> ir::Value {
+        let value = match ty.bits() {
+            64.. => value as i64,
+            bits => (value as i64) & ((1 << bits) - 1),
+        };
+
+        self.b.ins().iconst(ty, value)
+    }
+
+    /// Get the zero of a scalar type.
+    fn zero(&mut self, ty: Type) -> ir::Value {
+        match ty {
+            types::F32 => self.b.ins().f32const(0.0),
+            types::F64 => self.b.ins().f64const(0.0),
+            _ => self.b.ins().iconst(ty, 0),
+        }
+    }
+
+    /// Get the zero of every scalar of a type.
+    fn zeros(&mut self, ty: &Ty) -> Vec<ir::Value> {
+        let layout = self.g.layouts.layout(ty);
+        layout.parts.iter().map(|(_, ty)| self.zero(*ty)).collect()
+    }
+
+    /// Make memory on the stack for a value of a type, returning its address.
+    fn stack(&mut self, ty: &Ty) -> ir::Value {
+        let layout = self.g.layouts.layout(ty);
+        let align = layout.align.max(1).trailing_zeros() as u8;
+        let data = StackSlotData::new(StackSlotKind::ExplicitSlot, layout.size, align);
+        let slot = self.b.create_sized_stack_slot(data);
+        self.b.ins().stack_addr(self.g.pointer, slot, 0)
+    }
+
+    /// Load a value of a type from memory.
+    fn load(&mut self, ty: &Ty, address: ir::Value, offset: u32) -> Vec<ir::Value> {
+        let layout = self.g.layouts.layout(ty);
+
+        (layout.parts.iter())
+            .map(|(part, ty)| {
+                let offset = (offset + part) as i32;
+                self.b.ins().load(*ty, MemFlags::trusted(), address, offset)
+            })
+            .collect()
+    }
+
+    /// Store a value of a type to memory.
+    fn store(&mut self, ty: &Ty, value: &[ir::Value], address: ir::Value, offset: u32) {
+        let layout = self.g.layouts.layout(ty);
+
+        for ((part, _), value) in layout.parts.iter().zip(value) {
+            let offset = (offset + part) as i32;
+            self.b
+                .ins()
+                .store(MemFlags::trusted(), *value, address, offset);
+        }
+    }
+
+    /// Call `wr_rt_trap`, which stops the program at the current location, and return from the function.
+    fn trap_call(&mut self, message: &str) {
+        let (message, message_length) = self.string(message);
+        let (file, line) = self.location.clone();
+        let (file, file_length) = self.string(&file);
+        let line = self.b.ins().iconst(types::I32, line as i64);
+        self.call_runtime("wr_rt_trap", &[message, message_length, file, file_length, line]);
+
+        let unwind = self.unwind();
+        self.b.ins().jump(unwind, &[]);
+    }
+
+    /// Stop the program with a message. Instructions added after this go in a block that's never reached.
+    fn trap(&mut self, message: &str) {
+        self.trap_call(message);
+        let dead = self.b.create_block();
+        self.b.switch_to_block(dead);
+    }
+
+    /// Stop the program with a message if an `i8` is true.
+    fn trap_if(&mut self, condition: ir::Value, message: &str) {
+        let (trap, ok) = (self.b.create_block(), self.b.create_block());
+        self.b.ins().brif(condition, trap, &[], ok, &[]);
+        self.b.switch_to_block(trap);
+        self.trap_call(message);
+        self.b.switch_to_block(ok);
+    }
+
+    /// Return from the function if the program has trapped (in a call that was just made).
+    fn check_trapped(&mut self) {
+        let trapped = self
+            .call_runtime("wr_rt_trapped", &[])
+            .expect("`wr_rt_trapped` returns");
+        let (unwind, ok) = (self.unwind(), self.b.create_block());
+        self.b.ins().brif(trapped, unwind, &[], ok, &[]);
+        self.b.switch_to_block(ok);
+    }
+
+    /// Call a function with a result of the given type, returning the result.
+    fn call_direct(&mut self, function: FuncId, ret: &Ty, args: Vec<ir::Value>) -> Vec<ir::Value> {
+        let reference = self.function(function);
+        self.call(ret, args, |b, args| b.ins().call(reference, args))
+    }
+
+    /// Call a function with a result of the given type, using the given instruction, returning the result.
+    fn call(
+        &mut self,
+        ret: &Ty,
+        mut args: Vec<ir::Value>,
+        call: impl FnOnce(&mut FunctionBuilder, &[ir::Value]) -> ir::Inst,
+    ) -> Vec<ir::Value> {
+        let memory = match self.g.layouts.layout(ret).parts.len() {
+            0 | 1 => None,
+            _ => Some(self.stack(ret)),
+        };
+
+        if let Some(memory) = memory {
+            args.insert(0, memory);
+        }
+
+        let call = call(&mut self.b, &args);
+        let results = self.b.inst_results(call).to_vec();
+        self.check_trapped();
+
+        match memory {
+            Some(memory) => self.load(ret, memory, 0),
+            None => results,
+        }
+    }
+
+    /// Get a function value that runs a function, which is printed with the given label.
+    fn function_value(&mut self, function: FuncId, env: ir::Value, label: &str) -> Vec<ir::Value> {
+        let reference = self.function(function);
+        let code = self.b.ins().func_addr(self.g.pointer, reference);
+        let (name, _) = self.string(label);
+        vec![code, env, name]
+    }
+
+    /// Print a string constant.
+    fn print_constant(&mut self, string: &str) {
+        let (pointer, length) = self.string(string);
+        self.call_runtime("wr_rt_print_str", &[pointer, length]);
+    }
+
+    /// Print a value of a type.
+    fn print(&mut self, ty: &Ty, value: &[ir::Value]) {
+        match ty {
+            Ty::Atomic(AtomicTyVariant::Bool) => _ = self.call_runtime("wr_rt_print_bool", value),
+            Ty::Atomic(AtomicTyVariant::Char) => _ = self.call_runtime("wr_rt_print_char", value),
+            Ty::Atomic(AtomicTyVariant::F64) => _ = self.call_runtime("wr_rt_print_f64", value),
+
+            Ty::Atomic(AtomicTyVariant::F32) => {
+                let double = self.b.ins().fpromote(types::F64, value[0]);
+                self.call_runtime("wr_rt_print_f64", &[double]);
+            }
+
+            Ty::Atomic(variant) => {
+                let value = match (atomic_type(*variant), variant.is_signed_integer()) {
+                    (types::I64, _) => value[0],
+                    (_, true) => self.b.ins().sextend(types::I64, value[0]),
+                    (_, false) => self.b.ins().uextend(types::I64, value[0]),
+                };
+
+                match variant.is_signed_integer() {
+                    true => self.call_runtime("wr_rt_print_i64", &[value]),
+                    false => self.call_runtime("wr_rt_print_u64", &[value]),
+                };
+            }
+
+            Ty::Reference { target, .. } => {
+                let target_value = self.load(target, value[0], 0);
+                self.print(target, &target_value);
+            }
+
+            Ty::Function { .. } => _ = self.call_runtime("wr_rt_print_func", &value[2..]),
+            Ty::Str => _ = self.call_runtime("wr_rt_print_str", value),
+            Ty::Void => self.print_constant("()"),
+
+            Ty::Named { .. } if self.g.cx.named_kind(ty).is_some() => {
+                let helper = self.g.helper(Helper::Print, ty);
+                let reference = self.function(helper);
+                self.b.ins().call(reference, value);
+            }
+
+            _ => self.print_constant("<value>"),
+        }
+    }
+
+    /// Check if two values of a type are equal, returning the `i8`.
+    fn eq(&mut self, ty: &Ty, lhs: &[ir::Value], rhs: &[ir::Value]) -> ir::Value {
+        match ty {
+            Ty::Atomic(variant) if variant.is_float() => {
+                self.b.ins().fcmp(FloatCC::Equal, lhs[0], rhs[0])
+            }
+
+            Ty::Atomic(_) => self.b.ins().icmp(IntCC::Equal, lhs[0], rhs[0]),
+
+            Ty::Reference { target, .. } => {
+                let lhs = self.load(target, lhs[0], 0);
+                let rhs = self.load(target, rhs[0], 0);
+                self.eq(target, &lhs, &rhs)
+            }
+
+            Ty::Function { .. } => {
+                let code = self.b.ins().icmp(IntCC::Equal, lhs[0], rhs[0]);
+                let env = self.b.ins().icmp(IntCC::Equal, lhs[1], rhs[1]);
+                self.b.ins().band(code, env)
+            }
+
+            Ty::Str => {
+                let args = [lhs[0], lhs[1], rhs[0], rhs[1]];
+                self.call_runtime("wr_rt_str_eq", &args)
+                    .expect("`wr_rt_str_eq` returns")
+            }
+
+            Ty::Named { .. } => match self.g.cx.named_kind(ty) {
+                Some(NamedKind::Enum) => self.b.ins().icmp(IntCC::Equal, lhs[0], rhs[0]),
+
+                Some(_) => {
+                    let helper = self.g.helper(Helper::Eq, ty);
+                    let reference = self.function(helper);
+                    let call = self.b.ins().call(reference, &[lhs, rhs].concat());
+                    self.b.inst_results(call)[0]
+                }
+
+                None => self.b.ins().iconst(types::I8, 1),
+            },
+
+            _ => self.b.ins().iconst(types::I8, 1),
+        }
+    }
+
+    /// Generate a helper function's code.
+    fn generate_helper(&mut self, helper: Helper, ty: &Ty) {
+        let entry = self.b.current_block().expect("the entry block is started");
+        let params = self.b.block_params(entry).to_vec();
+        let display = ty.display(self.g.cx.resolution).to_string();
+        let kind = self.g.cx.named_kind(ty);
+        let (_, fields) = self.g.layouts.fields(ty);
+
+        match (helper, kind) {
+            (Helper::Print, Some(NamedKind::Record)) => {
+                self.print_constant(&format!("{display} {{"));
+
+                for (index, field) in fields.iter().enumerate() {
+                    let separator = if index > 0 { ", " } else { " " };
+                    self.print_constant(&format!("{separator}{}: ", field.name));
+                    self.print(&field.ty, &params[field.parts.clone()]);
+                }
+
+                self.print_constant(" }");
+                self.b.ins().return_(&[]);
+            }
+
+            (Helper::Print, Some(NamedKind::Union)) => {
+                let memory = self.stack(ty);
+                self.store(ty, &params, memory, 0);
+                let end = self.b.create_block();
+                let mut switch = Switch::new();
+                let mut blocks = Vec::new();
+
+                for (index, _) in fields.iter().enumerate() {
+                    let block = self.b.create_block();
+                    switch.set_entry(index as u128, block);
+                    blocks.push(block);
+                }
+
+                switch.emit(&mut self.b, params[0], end);
+
+                for (field, block) in fields.iter().zip(blocks) {
+                    self.b.switch_to_block(block);
+                    self.print_constant(&format!("{display} {{ {}: ", field.name));
+                    let value = self.load(&field.ty, memory, field.offset);
+                    self.print(&field.ty, &value);
+                    self.b.ins().jump(end, &[]);
+                }
+
+                self.b.switch_to_block(end);
+                self.print_constant(" }");
+                self.b.ins().return_(&[]);
+            }
+
+            (Helper::Print, _) => {
+                let variants = match ty {
+                    Ty::Named { def, .. } => self.g.cx.resolution.variants(*def).to_vec(),
+                    _ => Vec::new(),
+                };
+
+                let end = self.b.create_block();
+                let mut switch = Switch::new();
+                let mut blocks = Vec::new();
+
+                for (index, _) in variants.iter().enumerate() {
+                    let block = self.b.create_block();
+                    switch.set_entry(index as u128, block);
+                    blocks.push(block);
+                }
+
+                switch.emit(&mut self.b, params[0], end);
+
+                for (variant, block) in variants.iter().zip(blocks) {
+                    let variant = &self.g.cx.resolution.def(*variant).name;
+                    self.b.switch_to_block(block);
+                    self.print_constant(&format!("{display}::{variant}"));
+                    self.b.ins().jump(end, &[]);
+                }
+
+                self.b.switch_to_block(end);
+                self.b.ins().return_(&[]);
+            }
+
+            (Helper::Eq, Some(NamedKind::Union)) => {
+                let (lhs, rhs) = params.split_at(params.len() / 2);
+                let end = self.b.create_block();
+                self.b.append_block_param(end, types::I8);
+
+                let same = self.b.ins().icmp(IntCC::Equal, lhs[0], rhs[0]);
+                let compare = self.b.create_block();
+                let different = self.b.ins().iconst(types::I8, 0);
+                self.b.ins().brif(same, compare, &[], end, &[different]);
+
+                self.b.switch_to_block(compare);
+                let mut memory = Vec::new();
+
+                for side in [lhs, rhs] {
+                    let address = self.stack(ty);
+                    self.store(ty, side, address, 0);
+                    memory.push(address);
+                }
+
+                let equal = self.b.create_block();
+                let mut switch = Switch::new();
+                let mut blocks = Vec::new();
+
+                for (index, _) in fields.iter().enumerate() {
+                    let block = self.b.create_block();
+                    switch.set_entry(index as u128, block);
+                    blocks.push(block);
+                }
+
+                switch.emit(&mut self.b, lhs[0], equal);
+
+                for (field, block) in fields.iter().zip(blocks) {
+                    self.b.switch_to_block(block);
+                    let lhs = self.load(&field.ty, memory[0], field.offset);
+                    let rhs = self.load(&field.ty, memory[1], field.offset);
+                    let eq = self.eq(&field.ty, &lhs, &rhs);
+                    self.b.ins().jump(end, &[eq]);
+                }
+
+                self.b.switch_to_block(equal);
+                let one = self.b.ins().iconst(types::I8, 1);
+                self.b.ins().jump(end, &[one]);
+
+                self.b.switch_to_block(end);
+                let result = self.b.block_params(end)[0];
+                self.b.ins().return_(&[result]);
+            }
+
+            (Helper::Eq, _) => {
+                let (lhs, rhs) = params.split_at(params.len() / 2);
+                let mut result = self.b.ins().iconst(types::I8, 1);
+
+                for field in &fields {
+                    let parts = field.parts.clone();
+                    let eq = self.eq(&field.ty, &lhs[parts.clone()], &rhs[parts]);
+                    result = self.b.ins().band(result, eq);
+                }
+
+                self.b.ins().return_(&[result]);
+            }
+        }
+    }
+
+    /// Generate the code of a function value's function, which calls an instance or a builtin function.
+    fn generate_thunk(&mut self, thunk: &Thunk) {
+        let entry = self.b.current_block().expect("the entry block is started");
+        let params = self.b.block_params(entry).to_vec();
+
+        match thunk {
+            Thunk::Instance(instance) => {
+                let body = &self.g.cx.program.bodies[instance.body];
+                let ret = body.ret.substitute(&instance.substitution());
+                let function = self.g.instance_function(instance.clone());
+                let reference = self.function(function);
+
+                // The memory for the result (if it's passed) goes to the instance, but the environment doesn't.
+                let args = match self.g.layouts.layout(&ret).parts.len() {
+                    0 | 1 => params[1..].to_vec(),
+                    _ => [&params[..1], &params[2..]].concat(),
+                };
+
+                let call = self.b.ins().call(reference, &args);
+                let results = self.b.inst_results(call).to_vec();
+                self.check_trapped();
+                self.b.ins().return_(&results);
+            }
+
+            Thunk::Builtin(builtin, ty) => {
+                if let Ty::Function { params: tys, .. } = ty {
+                    if let Some(param) = tys.first() {
+                        self.print(param, &params[1..]);
+                    }
+                }
+
+                if *builtin == Builtin::Println {
+                    self.print_constant("\n");
+                }
+
+                self.b.ins().return_(&[]);
+            }
+        }
+    }
+
+    /// Generate the code of an instance of a body.
+    fn generate_body(&mut self, instance: Instance) {
+        FunctionGenerator::new(self, instance).generate();
+    }
+}
+
+/// The state of generating the function of an instance.
+struct FunctionGenerator<'e, 'g, 'a, 'f, M: Module> {
+    /// The emitter of the function.
+    e: &'e mut Emitter<'g, 'a, 'f, M>,
+    /// The body.
+    body: &'a Body,
+    /// The types of the instance's generic parameters.
+    substitution: HashMap<DefId, Ty>,
+    /// How many values the body captures, if it's the body of a closure.
+    captures: Option<usize>,
+    /// The Cranelift values of every MIR value that's been defined.
+    values: HashMap<Value, Vec<ir::Value>>,
+    /// The Cranelift block of every MIR block.
+    blocks: Vec<Block>,
+    /// The address of every slot.
+    slots: Vec<ir::Value>,
+    /// The memory the result is written to, if it's returned that way.
+    result: Option<ir::Value>,
+}
+
+impl<'e, 'g, 'a, 'f, M: Module> FunctionGenerator<'e, 'g, 'a, 'f, M> {
+    /// Set up the generating of an instance.
+    fn new(e: &'e mut Emitter<'g, 'a, 'f, M>, instance: Instance) -> Self {
+        let program = e.g.cx.program;
+        let captures = e.g.closures.get(&instance.body).copied();
+
+        FunctionGenerator {
+            e,
+            body: &program.bodies[instance.body],
+            substitution: instance.substitution(),
+            captures,
+            values: HashMap::new(),
+            blocks: Vec::new(),
+            slots: Vec::new(),
+            result: None,
+        }
+    }
+
+    /// Generate the function.
+    fn generate(mut self) {
+        let entry = self
+            .e
+            .b
+            .current_block()
+            .expect("the entry block is started");
+        let mut params = self.e.b.block_params(entry).to_vec().into_iter();
+        let ret = self.body.ret.substitute(&self.substitution);
+
+        if self.e.g.layouts.layout(&ret).parts.len() > 1 {
+            self.result = params.next();
+        }
+
+        let env = self.captures.and_then(|_| params.next());
+        let (captured, called_with) = split_captures(self.body, self.captures);
+
+        for param in called_with {
+            let count = self.e.g.layouts.layout(&self.value_ty(*param)).parts.len();
+            self.values
+                .insert(*param, params.by_ref().take(count).collect());
+        }
+
+        let first_block = self.body.block(BlockId::ENTRY);
+        self.locate(
+            first_block
+                .spans
+                .first()
+                .unwrap_or(&first_block.terminator_span),
+        );
+
+        if let (Some(env), false) = (env, captured.is_empty()) {
+            let tys: Vec<Ty> = captured.iter().map(|param| self.value_ty(*param)).collect();
+            let (offsets, _) = self.e.g.layouts.offsets(&tys);
+
+            for ((param, ty), offset) in captured.iter().zip(&tys).zip(offsets) {
+                let value = self.e.load(ty, env, offset);
+                self.values.insert(*param, value);
+            }
+        }
+
+        for slot in &self.body.slots {
+            let address = self.e.stack(&slot.ty.substitute(&self.substitution));
+            self.slots.push(address);
+        }
+
+        for block in self.body.block_ids() {
+            let cranelift_block = self.e.b.create_block();
+
+            for param in &self.body.block(block).params {
+                let layout = self.e.g.layouts.layout(&self.value_ty(*param));
+                let values = (layout.parts.iter())
+                    .map(|(_, ty)| self.e.b.append_block_param(cranelift_block, *ty))
+                    .collect();
+
+                self.values.insert(*param, values);
+            }
+
+            self.blocks.push(cranelift_block);
+        }
+
+        self.e.b.ins().jump(self.blocks[0], &[]);
+
+        for block in self.body.block_ids() {
+            let data = self.body.block(block);
+            self.e.b.switch_to_block(self.blocks[block.0 as usize]);
+
+            for (inst, span) in data.insts.iter().zip(&data.spans) {
+                self.locate(span);
+                self.inst(inst);
+            }
+
+            self.locate(&data.terminator_span);
+            self.terminator(&data.terminator);
+        }
+    }
+
+    /// Get the (concrete) type of a value.
+    fn value_ty(&self, value: Value) -> Ty {
+        self.body.value_ty(value).substitute(&self.substitution)
+    }
+
+    /// Get the (concrete) type of an operand.
+    fn operand_ty(&self, operand: &Operand) -> Ty {
+        self.body.operand_ty(operand).substitute(&self.substitution)
+    }
+
+    /// Stop the program with a message.
+    fn trap(&mut self, message: &str) {
+        self.e.trap(message);
+    }
+
+    /// Attribute the traps from now on to the source code of a span.
+    fn locate(&mut self, span: &Fragment) {
+        let line = span.source.line_index(span.range.start) + 1;
+        self.e.location = (span.source.name().to_string(), line as u32);
+    }
+
+    /// Get the Cranelift values of an operand. Values that were never defined (because the code that would have
+    /// defined them traps) are zero.
+    fn operand(&mut self, operand: &Operand) -> Vec<ir::Value> {
+        match operand {
+            Operand::Value(value) => match self.values.get(value) {
+                Some(values) => values.clone(),
+                None => self.e.zeros(&self.value_ty(*value)),
+            },
+
+            Operand::Const(constant) => self.constant(constant),
+        }
+    }
+
+    /// Get the Cranelift values of a constant. Constants that can't be made trap (and are left zero).
+    fn constant(&mut self, constant: &Const) -> Vec<ir::Value> {
+        match constant {
+            Const::Int(number, ty) => {
+                let ty = ty.substitute(&self.substitution);
+                let value = ty.atomic().and_then(|atomic| {
+                    let (min, max) = atomic.integer_bounds()?;
+                    i128::try_from(number)
+                        .ok()
+                        .filter(|value| (min..=max).contains(value))
+                        .map(|value| (atomic_type(atomic), value))
+                });
+
+                match value {
+                    Some((ty, value)) => vec![self.e.iconst(ty, value)],
+                    None => {
+                        self.trap(&format!("the integer {constant:?} doesn't fit its type"));
+                        self.e.zeros(&ty)
+                    }
+                }
+            }
+
+            Const::Bool(value) => vec![self.e.b.ins().iconst(types::I8, *value as i64)],
+            Const::Void => Vec::new(),
+            Const::Variant(_, index, _) => vec![self.e.b.ins().iconst(types::I32, *index as i64)],
+
+            Const::Str(string) => {
+                let (pointer, length) = self.e.string(string);
+                vec![pointer, length]
+            }
+
+            Const::Def(def, ty) => {
+                let ty = ty.substitute(&self.substitution);
+
+                let thunk = match self.e.g.cx.callee_value(*def, &ty) {
+                    Callee::Instance(instance) => Thunk::Instance(instance),
+                    Callee::Builtin(builtin) => Thunk::Builtin(builtin, ty),
+                    Callee::Missing(message) => {
+                        self.trap(&message);
+                        return self.e.zeros(&ty);
+                    }
+                };
+
+                let (function, label) = self.e.g.thunk(thunk);
+                let env = self.e.b.ins().iconst(self.e.g.pointer, 0);
+                self.e.function_value(function, env, &label)
+            }
+        }
+    }
+
+    /// Get the address of a place. Going through a variant of a union to load or store checks that the union holds
+    /// it, except for the last step of a place that's stored to, which changes the union to hold it. References to
+    /// variants aren't checked until they're used, like in the VM.
+    fn place(&mut self, place: &Place, access: Access) -> ir::Value {
+        let (mut address, mut ty, projections) = match place.base {
+            PlaceBase::Slot(slot) => (
+                self.slots[slot.0 as usize],
+                self.body.slots[slot.0 as usize]
+                    .ty
+                    .substitute(&self.substitution),
+                &place.projections[..],
+            ),
+
+            PlaceBase::Value(value) => (
+                self.operand(&Operand::Value(value))[0],
+                strip_one_reference(&self.value_ty(value)),
+                &place.projections[1..],
+            ),
+        };
+
+        for (index, projection) in projections.iter().enumerate() {
+            match projection {
+                Projection::Field(field) => {
+                    let (is_union, fields) = self.e.g.layouts.fields(&ty);
+                    let position = fields.iter().position(|candidate| candidate.name == *field);
+                    let (field_ty, offset) = position.map_or((Ty::Error, 0), |position| {
+                        (fields[position].ty.clone(), fields[position].offset)
+                    });
+
+                    if is_union {
+                        let position = position.unwrap_or_default() as i128;
+                        let is_last = index == projections.len() - 1;
+
+                        match access {
+                            Access::Store if is_last => {
+                                let tag = self.e.iconst(types::I32, position);
+                                self.e.b.ins().store(MemFlags::trusted(), tag, address, 0);
+                            }
+
+                            Access::Ref if is_last => {}
+
+                            _ => {
+                                let tag = (self.e.b.ins()).load(
+                                    types::I32,
+                                    MemFlags::trusted(),
+                                    address,
+                                    0,
+                                );
+                                let position = self.e.iconst(types::I32, position);
+                                let wrong = self.e.b.ins().icmp(IntCC::NotEqual, tag, position);
+                                self.e.trap_if(wrong, VARIANT_TRAP);
+                            }
+                        }
+                    }
+
+                    if offset > 0 {
+                        address = self.e.b.ins().iadd_imm(address, offset as i64);
+                    }
+
+                    ty = field_ty;
+                }
+
+                Projection::Deref => {
+                    let pointer = self.e.g.pointer;
+                    address = self
+                        .e
+                        .b
+                        .ins()
+                        .load(pointer, MemFlags::trusted(), address, 0);
+                    ty = strip_one_reference(&ty);
+                }
+            }
+        }
+
+        address
+    }
+
+    /// Generate an instruction.
+    fn inst(&mut self, inst: &Inst) {
+        match inst {
+            Inst::Assign(value, rvalue) => self.rvalue(*value, rvalue),
+
+            Inst::Store(place, operand) => {
+                let ty = self.operand_ty(operand);
+                let src = self.operand(operand);
+                let address = self.place(place, Access::Store);
+                self.e.store(&ty, &src, address, 0);
+            }
+
+            // Slots live until the function returns.
+            Inst::Drop(_) => {}
+        }
+    }
+
+    /// Define a value as the given Cranelift values.
+    fn define(&mut self, value: Value, values: Vec<ir::Value>) {
+        self.values.insert(value, values);
+    }
+
+    /// Define a value as one Cranelift value.
+    fn assign(&mut self, value: Value, result: ir::Value) {
+        self.define(value, vec![result]);
+    }
+
+    /// Generate the computation of a value.
+    fn rvalue(&mut self, value: Value, rvalue: &Rvalue) {
+        match rvalue {
+            Rvalue::Use(operand) => {
+                let src = self.operand(operand);
+                self.values.insert(value, src);
+            }
+
+            Rvalue::Load(place) => {
+                let ty = self.value_ty(value);
+                let address = self.place(place, Access::Load);
+                let loaded = self.e.load(&ty, address, 0);
+                self.values.insert(value, loaded);
+            }
+
+            Rvalue::Ref { place, .. } => {
+                let address = self.place(place, Access::Ref);
+                self.assign(value, address);
+            }
+
+            Rvalue::Unary(op, operand) => self.unary(value, *op, operand),
+            Rvalue::Binary(op, lhs, rhs) => self.binary(value, *op, lhs, rhs),
+            Rvalue::Call { callee, args } => self.call(value, callee, args),
+
+            Rvalue::Aggregate { fields } => {
+                let ty = self.value_ty(value);
+                let (is_union, layout) = self.e.g.layouts.fields(&ty);
+
+                if is_union {
+                    let Some((name, operand)) = fields.first() else {
+                        return self.trap("a union is made without a variant");
+                    };
+
+                    let Some(variant) = layout.iter().position(|field| field.name == *name) else {
+                        return self.trap(VARIANT_TRAP);
+                    };
+
+                    let src = self.operand(operand);
+                    let memory = self.e.stack(&ty);
+                    let zeros = self.e.zeros(&ty);
+                    self.e.store(&ty, &zeros, memory, 0);
+                    let tag = self.e.iconst(types::I32, variant as i128);
+                    self.e.b.ins().store(MemFlags::trusted(), tag, memory, 0);
+                    self.e
+                        .store(&layout[variant].ty, &src, memory, UNION_PAYLOAD);
+                    let union = self.e.load(&ty, memory, 0);
+                    return self.define(value, union);
+                }
+
+                // The fields are given in any order, but their values go in the order they're declared.
+                let mut parts = Vec::new();
+
+                for field in &layout {
+                    match fields.iter().find(|(name, _)| *name == field.name) {
+                        Some((_, operand)) => parts.extend(self.operand(operand)),
+                        None => parts.extend(self.e.zeros(&field.ty)),
+                    }
+                }
+
+                self.values.insert(value, parts);
+            }
+
+            Rvalue::Field(operand, field) => {
+                let ty = self.operand_ty(operand);
+                let (is_union, fields) = self.e.g.layouts.fields(&ty);
+                let src = self.operand(operand);
+
+                let Some(position) = fields.iter().position(|candidate| candidate.name == *field)
+                else {
+                    let zeros = self.e.zeros(&self.value_ty(value));
+                    return self.define(value, zeros);
+                };
+
+                if !is_union {
+                    let parts = src[fields[position].parts.clone()].to_vec();
+                    return self.define(value, parts);
+                }
+
+                let expected = self.e.iconst(types::I32, position as i128);
+                let wrong = self.e.b.ins().icmp(IntCC::NotEqual, src[0], expected);
+                self.e.trap_if(wrong, VARIANT_TRAP);
+
+                let memory = self.e.stack(&ty);
+                self.e.store(&ty, &src, memory, 0);
+                let variant = self.e.load(&fields[position].ty, memory, UNION_PAYLOAD);
+                self.values.insert(value, variant);
+            }
+
+            Rvalue::IsVariant(operand, variant) => {
+                let ty = self.operand_ty(operand);
+                let (_, fields) = self.e.g.layouts.fields(&ty);
+                let position = (fields.iter())
+                    .position(|field| field.name == *variant)
+                    .unwrap_or_default();
+                let src = self.operand(operand);
+                let is_variant = (self.e.b.ins()).icmp_imm(IntCC::Equal, src[0], position as i64);
+                self.assign(value, is_variant);
+            }
+
+            Rvalue::Closure { body, captures } => {
+                let index = body.0 as usize;
+
+                let instance = match self.e.g.cx.instance(index, &self.substitution) {
+                    Ok(instance) => instance,
+                    Err(_) => {
+                        return self
+                            .trap("the types of a closure's generic parameters aren't known");
+                    }
+                };
+
+                self.e.g.closures.insert(index, captures.len());
+                let function = self.e.g.instance_function(instance);
+                let label = format!("closure {}", self.e.g.cx.program.bodies[index].name);
+                let pointer = self.e.g.pointer;
+
+                if captures.is_empty() {
+                    let env = self.e.b.ins().iconst(pointer, 0);
+                    let closure = self.e.function_value(function, env, &label);
+                    return self.define(value, closure);
+                }
+
+                // The environment of a closure lives as long as the program does.
+                let tys: Vec<Ty> = captures
+                    .iter()
+                    .map(|capture| self.operand_ty(capture))
+                    .collect();
+                let (offsets, size) = self.e.g.layouts.offsets(&tys);
+                let size = self.e.b.ins().iconst(pointer, size as i64);
+                let env =
+                    (self.e.call_runtime("wr_rt_alloc", &[size])).expect("`wr_rt_alloc` returns");
+
+                for ((capture, ty), offset) in captures.iter().zip(&tys).zip(offsets) {
+                    let src = self.operand(capture);
+                    self.e.store(ty, &src, env, offset);
+                }
+
+                let closure = self.e.function_value(function, env, &label);
+                self.values.insert(value, closure);
+            }
+        }
+    }
+
+    /// Generate an operation on integers that can overflow, given the instruction's result and whether it
+    /// overflowed.
+    fn checked(&mut self, value: Value, (result, overflow): (ir::Value, ir::Value)) {
+        self.e.trap_if(overflow, "arithmetic overflow");
+        self.assign(value, result);
+    }
+
+    /// Generate a unary operation.
+    fn unary(&mut self, value: Value, op: UnaryOperation, operand: &Operand) {
+        let ty = self.operand_ty(operand);
+        let src = self.operand(operand);
+        let atomic = ty.atomic();
+
+        match (op, atomic.filter(|atomic| atomic.is_integer())) {
+            (UnaryOperation::Negate, Some(int)) => {
+                let zero = self.e.b.ins().iconst(atomic_type(int), 0);
+
+                let result = match int.is_signed_integer() {
+                    true => self.e.b.ins().ssub_overflow(zero, src[0]),
+                    false => self.e.b.ins().usub_overflow(zero, src[0]),
+                };
+
+                self.checked(value, result);
+            }
+
+            (UnaryOperation::BitwiseNot, Some(_)) => {
+                let result = self.e.b.ins().bnot(src[0]);
+                self.assign(value, result);
+            }
+
+            (UnaryOperation::Negate, None) if atomic.is_some_and(AtomicTyVariant::is_float) => {
+                let result = self.e.b.ins().fneg(src[0]);
+                self.assign(value, result);
+            }
+
+            (UnaryOperation::BooleanNot | UnaryOperation::BitwiseNot, None) if ty == Ty::BOOL => {
+                let result = self.e.b.ins().bxor_imm(src[0], 1);
+                self.assign(value, result);
+            }
+
+            _ => self.unsupported(op.symbol(), &ty),
+        }
+    }
+
+    /// Generate a binary operation.
+    fn binary(&mut self, value: Value, op: BinaryOperation, lhs: &Operand, rhs: &Operand) {
+        let ty = self.operand_ty(lhs);
+        let rhs_ty = self.operand_ty(rhs);
+        let atomic = ty.atomic();
+        let a = self.operand(lhs);
+        let b = self.operand(rhs);
+
+        if let Some(comparison) = compare(op, atomic) {
+            let result = match (&ty, comparison, op) {
+                (Ty::Atomic(_), Comparison::Int(cc), _) => self.e.b.ins().icmp(cc, a[0], b[0]),
+                (Ty::Atomic(_), Comparison::Float(cc), _) => self.e.b.ins().fcmp(cc, a[0], b[0]),
+                (_, _, BinaryOperation::Equal) => self.e.eq(&ty, &a, &b),
+
+                (_, _, BinaryOperation::NotEqual) => {
+                    let eq = self.e.eq(&ty, &a, &b);
+                    self.e.b.ins().bxor_imm(eq, 1)
+                }
+
+                _ => return self.unsupported(op.symbol(), &ty),
+            };
+
+            return self.assign(value, result);
+        }
+
+        if let Some(int) = atomic.filter(|atomic| atomic.is_integer()) {
+            let int_ty = atomic_type(int);
+            let bits = int_ty.bits();
+            let signed = int.is_signed_integer();
+            let (a, b) = (a[0], b[0]);
+
+            if let Some(message) = constant_trap(op, rhs, bits) {
+                return self.trap(message);
+            }
+
+            if matches!(
+                op,
+                BinaryOperation::Add | BinaryOperation::Subtract | BinaryOperation::Multiply
+            ) {
+                let ins = self.e.b.ins();
+
+                let result = match (op, signed) {
+                    (BinaryOperation::Add, true) => ins.sadd_overflow(a, b),
+                    (BinaryOperation::Add, false) => ins.uadd_overflow(a, b),
+                    (BinaryOperation::Subtract, true) => ins.ssub_overflow(a, b),
+                    (BinaryOperation::Subtract, false) => ins.usub_overflow(a, b),
+                    (_, true) => ins.smul_overflow(a, b),
+                    (_, false) => ins.umul_overflow(a, b),
+                };
+
+                return self.checked(value, result);
+            }
+
+            let ins = self.e.b.ins();
+
+            let result = match (op, signed) {
+                (BinaryOperation::Divide | BinaryOperation::Modulo, _) => {
+                    let zero = ins.icmp_imm(IntCC::Equal, b, 0);
+                    self.e.trap_if(zero, "division by zero");
+                    let minus_one = self.e.iconst(int_ty, -1);
+                    let is_minus_one = self.e.b.ins().icmp(IntCC::Equal, b, minus_one);
+
+                    match (op, signed) {
+                        (BinaryOperation::Divide, true) => {
+                            let (min, _) = int.integer_bounds().unwrap_or_default();
+                            let min = self.e.iconst(int_ty, min);
+                            let is_min = self.e.b.ins().icmp(IntCC::Equal, a, min);
+                            let overflow = self.e.b.ins().band(is_min, is_minus_one);
+                            self.e.trap_if(overflow, "arithmetic overflow");
+                            self.e.b.ins().sdiv(a, b)
+                        }
+
+                        (BinaryOperation::Divide, false) => self.e.b.ins().udiv(a, b),
+
+                        // The remainder of dividing by -1 is always 0, but `srem` of the smallest integer by -1
+                        // traps, so the division is by 1 instead.
+                        (_, true) => {
+                            let one = self.e.b.ins().iconst(int_ty, 1);
+                            let divisor = self.e.b.ins().select(is_minus_one, one, b);
+                            self.e.b.ins().srem(a, divisor)
+                        }
+
+                        (_, false) => self.e.b.ins().urem(a, b),
+                    }
+                }
+
+                (BinaryOperation::BitwiseAnd, _) => ins.band(a, b),
+                (BinaryOperation::BitwiseOr, _) => ins.bor(a, b),
+                (BinaryOperation::BitwiseXor, _) => ins.bxor(a, b),
+
+                (BinaryOperation::ShiftLeft | BinaryOperation::ShiftRight, _) => {
+                    if !rhs_ty.atomic().is_some_and(|atomic| atomic.is_integer()) {
+                        return self.unsupported(op.symbol(), &rhs_ty);
+                    }
+
+                    // Negative amounts are larger than the number of bits when they're compared as unsigned.
+                    let too_far = ins.icmp_imm(IntCC::UnsignedGreaterThanOrEqual, b, bits as i64);
+                    self.e.trap_if(too_far, SHIFT_TRAP);
+
+                    match (op, signed) {
+                        (BinaryOperation::ShiftLeft, _) => self.e.b.ins().ishl(a, b),
+                        (_, true) => self.e.b.ins().sshr(a, b),
+                        (_, false) => self.e.b.ins().ushr(a, b),
+                    }
+                }
+
+                _ => return self.unsupported(op.symbol(), &ty),
+            };
+
+            self.assign(value, result);
+        } else if atomic.is_some_and(AtomicTyVariant::is_float) {
+            let (a, b) = (a[0], b[0]);
+
+            let result = match op {
+                BinaryOperation::Add => self.e.b.ins().fadd(a, b),
+                BinaryOperation::Subtract => self.e.b.ins().fsub(a, b),
+                BinaryOperation::Multiply => self.e.b.ins().fmul(a, b),
+                BinaryOperation::Divide => self.e.b.ins().fdiv(a, b),
+
+                // Cranelift has no instruction for the remainder of floating point numbers.
+                BinaryOperation::Modulo if ty == Ty::Atomic(AtomicTyVariant::F32) => {
+                    let a = self.e.b.ins().fpromote(types::F64, a);
+                    let b = self.e.b.ins().fpromote(types::F64, b);
+                    let result = self
+                        .e
+                        .call_runtime("wr_rt_fmod", &[a, b])
+                        .expect("`wr_rt_fmod` returns");
+                    self.e.b.ins().fdemote(types::F32, result)
+                }
+
+                BinaryOperation::Modulo => self
+                    .e
+                    .call_runtime("wr_rt_fmod", &[a, b])
+                    .expect("`wr_rt_fmod` returns"),
+
+                _ => return self.unsupported(op.symbol(), &ty),
+            };
+
+            self.assign(value, result);
+        } else if ty == Ty::BOOL {
+            let (a, b) = (a[0], b[0]);
+
+            let result = match op {
+                BinaryOperation::BitwiseAnd | BinaryOperation::LogicalAnd => {
+                    self.e.b.ins().band(a, b)
+                }
+                BinaryOperation::BitwiseOr | BinaryOperation::LogicalOr => self.e.b.ins().bor(a, b),
+                BinaryOperation::BitwiseXor => self.e.b.ins().bxor(a, b),
+                _ => return self.unsupported(op.symbol(), &ty),
+            };
+
+            self.assign(value, result);
+        } else {
+            self.unsupported(op.symbol(), &ty);
+        }
+    }
+
+    /// Trap on an operation that isn't supported for a type, which type checking should have ruled out.
+    fn unsupported(&mut self, op: &str, ty: &Ty) {
+        let ty = ty.display(self.e.g.cx.resolution).to_string();
+        self.trap(&format!("`{op}` isn't supported for `{ty}`"));
+    }
+
+    /// Generate a call.
+    fn call(&mut self, value: Value, callee: &Operand, args: &[Operand]) {
+        let ret = self.value_ty(value);
+        let arg_tys: Vec<Ty> = args.iter().map(|arg| self.operand_ty(arg)).collect();
+        let mut arg_values = Vec::new();
+
+        for arg in args {
+            arg_values.extend(self.operand(arg));
+        }
+
+        let Operand::Const(Const::Def(def, _)) = callee else {
+            // A function value's code takes the environment before the arguments.
+            let ty = self.operand_ty(callee);
+
+            if !matches!(ty, Ty::Function { .. }) {
+                return self.trap(&format!(
+                    "expected a function, found a value of type `{}`",
+                    ty.display(self.e.g.cx.resolution)
+                ));
+            }
+
+            let callee = self.operand(callee);
+            let signature = self.e.g.signature(&arg_tys, &ret, true);
+            let signature = self.e.b.import_signature(signature);
+            arg_values.insert(0, callee[1]);
+
+            let result = (self.e).call(&ret, arg_values, |b, args| {
+                b.ins().call_indirect(signature, callee[0], args)
+            });
+
+            return self.define(value, result);
+        };
+
+        match self.e.g.cx.callee(*def, &arg_tys, &ret) {
+            Callee::Instance(instance) => {
+                let function = self.e.g.instance_function(instance);
+                let result = self.e.call_direct(function, &ret, arg_values);
+                self.values.insert(value, result);
+            }
+
+            Callee::Builtin(builtin) => {
+                if let Some(ty) = arg_tys.first() {
+                    let arg = self.operand(&args[0]);
+                    self.e.print(ty, &arg);
+                }
+
+                if builtin == Builtin::Println {
+                    self.e.print_constant("\n");
+                }
+
+                self.values.insert(value, Vec::new());
+            }
+
+            Callee::Missing(message) => self.trap(&message),
+        }
+    }
+
+    /// Generate a terminator.
+    fn terminator(&mut self, terminator: &Terminator) {
+        match terminator {
+            Terminator::Goto(jump) => self.jump(jump),
+
+            Terminator::Branch {
+                condition,
+                then,
+                otherwise,
+            } => {
+                let condition = self.operand(condition)[0];
+                let then_args = self.args(then);
+                let otherwise_args = self.args(otherwise);
+                let then_block = self.blocks[then.target.0 as usize];
+                let otherwise_block = self.blocks[otherwise.target.0 as usize];
+
+                (self.e.b.ins()).brif(
+                    condition,
+                    then_block,
+                    &then_args,
+                    otherwise_block,
+                    &otherwise_args,
+                );
+            }
+
+            Terminator::Switch {
+                value,
+                cases,
+                otherwise,
+            } => {
+                let ty = self.operand_ty(value);
+                let src = self.operand(value)[0];
+                let bits = self.e.b.func.dfg.value_type(src).bits();
+                let mut switch = Switch::new();
+                let mut seen = HashSet::new();
+
+                // Cases that don't fit the type can't match, and only the first case for each value can.
+                for (case, target) in cases {
+                    let case = match ty.atomic().and_then(AtomicTyVariant::integer_bounds) {
+                        Some((min, max)) => i128::try_from(case)
+                            .ok()
+                            .filter(|case| (min..=max).contains(case)),
+                        None => u32::try_from(case).ok().map(i128::from),
+                    };
+
+                    // The switch compares the bits of the value, as an unsigned number.
+                    let Some(case) = case.map(|case| case as u128 & (u128::MAX >> (128 - bits)))
+                    else {
+                        continue;
+                    };
+
+                    if seen.insert(case) {
+                        switch.set_entry(case, self.blocks[target.0 as usize]);
+                    }
+                }
+
+                let otherwise = self.blocks[otherwise.0 as usize];
+                switch.emit(&mut self.e.b, src, otherwise);
+            }
+
+            Terminator::Return(operand) => {
+                let ret = self.body.ret.substitute(&self.substitution);
+                let src = self.operand(operand);
+
+                match self.result {
+                    Some(memory) => {
+                        self.e.store(&ret, &src, memory, 0);
+                        self.e.b.ins().return_(&[]);
+                    }
+
+                    None => _ = self.e.b.ins().return_(&src),
+                }
+            }
+
+            Terminator::Unreachable => self.e.trap_call("reached code that should be unreachable"),
+        }
+    }
+
+    /// Get the Cranelift values of the arguments of a jump.
+    fn args(&mut self, jump: &Jump) -> Vec<ir::Value> {
+        let mut args = Vec::new();
+
+        for arg in &jump.args {
+            args.extend(self.operand(arg));
+        }
+
+        args
+    }
+
+    /// Generate a jump to a block.
+    fn jump(&mut self, jump: &Jump) {
+        let args = self.args(jump);
+        let block = self.blocks[jump.target.0 as usize];
+        self.e.b.ins().jump(block, &args);
+    }
+}
+
+/// How two values of an atomic type are compared.
+#[derive(Clone, Copy, Debug)]
+enum Comparison {
+    /// With `icmp`.
+    Int(IntCC),
+    /// With `fcmp`.
+    Float(FloatCC),
+}
+
+impl Scalar for Type {
+    const I32: Self = types::I32;
+    const I64: Self = types::I64;
+
+    fn atomic(variant: AtomicTyVariant) -> Self {
+        atomic_type(variant)
+    }
+
+    fn size(self) -> u32 {
+        self.bytes()
+    }
+}
+
+/// Get the Cranelift type of an atomic type.
+const fn atomic_type(variant: AtomicTyVariant) -> Type {
+    match variant {
+        AtomicTyVariant::Bool | AtomicTyVariant::U8 | AtomicTyVariant::I8 => types::I8,
+        AtomicTyVariant::U16 | AtomicTyVariant::I16 => types::I16,
+        AtomicTyVariant::U32 | AtomicTyVariant::I32 | AtomicTyVariant::Char => types::I32,
+        AtomicTyVariant::U64 | AtomicTyVariant::I64 => types::I64,
+        AtomicTyVariant::F32 => types::F32,
+        AtomicTyVariant::F64 => types::F64,
+    }
+}
+
+/// Get how values of a type are compared with a binary operation, if it's a comparison.
+fn compare(op: BinaryOperation, atomic: Option<AtomicTyVariant>) -> Option<Comparison> {
+    Some(match (op, compare_as(op, atomic)?) {
+        (BinaryOperation::Equal, CompareAs::Float) => Comparison::Float(FloatCC::Equal),
+        (BinaryOperation::NotEqual, CompareAs::Float) => Comparison::Float(FloatCC::NotEqual),
+        (BinaryOperation::Less, CompareAs::Float) => Comparison::Float(FloatCC::LessThan),
+        (BinaryOperation::LessOrEqual, CompareAs::Float) => {
+            Comparison::Float(FloatCC::LessThanOrEqual)
+        }
+        (BinaryOperation::Greater, CompareAs::Float) => Comparison::Float(FloatCC::GreaterThan),
+        (BinaryOperation::GreaterOrEqual, CompareAs::Float) => {
+            Comparison::Float(FloatCC::GreaterThanOrEqual)
+        }
+        (BinaryOperation::Equal, _) => Comparison::Int(IntCC::Equal),
+        (BinaryOperation::NotEqual, _) => Comparison::Int(IntCC::NotEqual),
+        (BinaryOperation::Less, CompareAs::Signed) => Comparison::Int(IntCC::SignedLessThan),
+        (BinaryOperation::LessOrEqual, CompareAs::Signed) => {
+            Comparison::Int(IntCC::SignedLessThanOrEqual)
+        }
+        (BinaryOperation::Greater, CompareAs::Signed) => Comparison::Int(IntCC::SignedGreaterThan),
+        (BinaryOperation::GreaterOrEqual, CompareAs::Signed) => {
+            Comparison::Int(IntCC::SignedGreaterThanOrEqual)
+        }
+        (BinaryOperation::Less, _) => Comparison::Int(IntCC::UnsignedLessThan),
+        (BinaryOperation::LessOrEqual, _) => Comparison::Int(IntCC::UnsignedLessThanOrEqual),
+        (BinaryOperation::Greater, _) => Comparison::Int(IntCC::UnsignedGreaterThan),
+        (BinaryOperation::GreaterOrEqual, _) => Comparison::Int(IntCC::UnsignedGreaterThanOrEqual),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Error, Trap, run};
+    use crate::analysis::testing::lower_source;
+
+    /// Check a single module, compile it in memory, and run it, returning what it printed and the trap that stopped
+    /// it (if any).
+    fn run_source(source: &'static str) -> (String, Option<Trap>) {
+        let (package, program) = lower_source(source);
+        let mut out = Vec::new();
+
+        let trap = match run(&program, &package.resolution, &package.typeck, &mut out) {
+            Ok(()) => None,
+            Err(Error::Trap(trap)) => Some(trap),
+            Err(error) => panic!("{error}"),
+        };
+
+        (String::from_utf8(out).unwrap(), trap)
+    }
+
+    /// Run a module, which must not trap, returning what it printed.
+    fn output(source: &'static str) -> String {
+        let (out, trap) = run_source(source);
+        assert_eq!(trap, None);
+        out
+    }
+
+    #[test]
+    fn test_arithmetic_and_calls() {
+        let out = output(
+            "use wright::io::println;\n\
+            func fib(n: u64) -> u64 { if n < 2 { n } else { fib(n - 1) + fib(n - 2) } }\n\
+            func main() {\n\
+                let zero: u8 = 0;\n\
+                let one: u16 = 1;\n\
+                let min: i64 = -9223372036854775808;\n\
+                println(fib(20)); println(7 / 2); println(-7 % 3); println(~zero); println(one << 15);\n\
+                println(min); println(min % -1); println(1 == 2 || 3 != 4);\n\
+            }",
+        );
+
+        assert_eq!(out, "6765\n3\n-1\n255\n32768\n-9223372036854775808\n0\ntrue\n");
+    }
+
+    #[test]
+    fn test_loops_and_slots() {
+        let out = output(
+            "use wright::io::println;\n\
+            func main() {\n\
+                var total: u64 = 0;\n\
+                for i in 1..=10 { total += i; }\n\
+                var n = 0;\n\
+                while n < 3 { n += 1; }\n\
+                println(total); println(n);\n\
+            }",
+        );
+
+        assert_eq!(out, "55\n3\n");
+    }
+
+    #[test]
+    fn test_records_and_generics() {
+        let out = output(
+            "use wright::io::println;\n\
+            record Point { x: u64, y: u64 }\n\
+            record Box<T> { value: T }\n\
+            impl Point { func grow(self: @mut Self) { self.x = self.x + 1; } }\n\
+            trait Shape { func area(self: @Self) -> u64; func double(self: @Self) -> u64 { self.area() * 2 } }\n\
+            impl Shape for Point { func area(self: @Self) -> u64 { self.x * self.y } }\n\
+            func total<T: Shape>(shape: @T) -> u64 { shape.area() + shape.double() }\n\
+            func unbox<T>(b: Box<T>) -> T { b.value }\n\
+            func main() {\n\
+                var p = Point { y: 3, x: 1 };\n\
+                let q = p;\n\
+                p.grow();\n\
+                println(p); println(q.x); println(p == q); println(@p != @q);\n\
+                println(p.double()); println(total(@p));\n\
+                println(unbox(Box { value: true })); println(Box { value: p });\n\
+            }",
+        );
+
+        assert_eq!(
+            out,
+            "Point { x: 2, y: 3 }\n1\nfalse\ntrue\n12\n18\ntrue\nBox<Point> { value: Point { x: 2, y: 3 } }\n"
+        );
+    }
+
+    #[test]
+    fn test_unions_and_enums() {
+        let out = output(
+            "use wright::io::println;\n\
+            union Number { small: u8, big: u64 }\n\
+            enum Color { Red, Green, Blue }\n\
+            func size(n: Number) -> u64 { match n { Number { small: 0 } => 0, Number { big } => big, _ => 1 } }\n\
+            func code(color: Color) -> u8 { match color { Color::Red => 1, Color::Green | Color::Blue => 2 } }\n\
+            func main() {\n\
+                println(size(Number { small: 0 })); println(size(Number { small: 5 })); println(size(Number { big: 9 }));\n\
+                println(code(Color::Red)); println(code(Color::Blue)); println(Color::Green);\n\
+                println(Number { big: 4 }); println(Number { small: 4 } is u8);\n\
+                println(Number { small: 4 } == Number { small: 4 }); println(Color::Red == Color::Blue);\n\
+            }",
+        );
+
+        assert_eq!(out, "0\n1\n9\n1\n2\nColor::Green\nNumber { big: 4 }\ntrue\ntrue\nfalse\n");
+    }
+
+    #[test]
+    fn test_closures() {
+        let out = output(
+            "use wright::io::println;\n\
+            func apply(f: func(u8) -> u8, x: u8) -> u8 { f(x) }\n\
+            func double(x: u8) -> u8 { x * 2 }\n\
+            func main() {\n\
+                let offset = 3;\n\
+                var count = 0;\n\
+                let add = func(x: u8) -> u8 { count += 1; x + offset };\n\
+                println(apply(add, 4) + apply(add, 1) + count);\n\
+                println(apply(double, 5));\n\
+                let print = println;\n\
+                print(\"done\");\n\
+                println(double);\n\
+            }",
+        );
+
+        assert_eq!(out, "13\n10\ndone\n<func double>\n");
+    }
+
+    #[test]
+    fn test_traps() {
+        let cases = [
+            ("func main() -> u8 { let x: u8 = 255; x + 1 }", "arithmetic overflow"),
+            ("func main() -> i8 { let x: i8 = -128; -x }", "arithmetic overflow"),
+            (
+                "func main() -> i64 { let x: i64 = -9223372036854775808; x / -1 }",
+                "arithmetic overflow",
+            ),
+            ("func main() -> u32 { let x: u32 = 0; 1 / x }", "division by zero"),
+            (
+                "func main() -> u8 { let x: u8 = 8; 1 << x }",
+                "shift by at least the number of bits in the type",
+            ),
+        ];
+
+        for (source, message) in cases {
+            let (_, trap) = run_source(source);
+            assert_eq!(trap.map(|trap| trap.message), Some(message.to_owned()), "{source}");
+        }
+    }
+
+    #[test]
+    fn test_traps_stop_the_program() {
+        let (out, trap) = run_source(
+            "use wright::io::println;\n\
+            func add(x: u8, y: u8) -> u8 {\n\
+                x + y\n\
+            }\n\
+            func main() { println(\"before\"); println(add(200, 100)); println(\"after\"); }",
+        );
+
+        let trap = trap.unwrap();
+        assert_eq!(out, "before\n");
+        assert_eq!((trap.message.as_str(), trap.line), ("arithmetic overflow", 3));
+    }
+
+    #[test]
+    fn test_no_main() {
+        let (package, program) = lower_source("func f() {}");
+        let result = run(&program, &package.resolution, &package.typeck, &mut Vec::new());
+        assert!(matches!(result, Err(Error::NoMain)));
+    }
+}
@@ -0,0 +1,157 @@
+/* The functions that object files generated by wright's Cranelift backend call. Compile this with any C11 compiler
+   and link it with the object file (for example, `cc main.o runtime.c -o main`). */
+
+#include <math.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+/* Stop the program, because it did something the language doesn't define (like overflowing an integer). */
+void wr_rt_trap(const char *message, size_t message_length, const char *file, size_t file_length, uint32_t line) {
+    fflush(stdout);
+    fprintf(stderr, "error: %.*s, at %.*s:%u\n", (int)message_length, message, (int)file_length, file,
+            (unsigned)line);
+    exit(EXIT_FAILURE);
+}
+
+/* Traps exit right away, so the program never sees one. */
+uint8_t wr_rt_trapped(void) {
+    return 0;
+}
+
+/* Allocate memory that lives until the program ends. */
+void *wr_rt_alloc(size_t size) {
+    void *memory = calloc(size ? size : 1, 1);
+
+    if (memory == NULL) {
+        fflush(stdout);
+        fputs("error: out of memory\n", stderr);
+        exit(EXIT_FAILURE);
+    }
+
+    return memory;
+}
+
+/* Strings are equal if they have the same bytes. */
+uint8_t wr_rt_str_eq(const char *a, size_t a_length, const char *b, size_t b_length) {
+    return a_length == b_length && (a_length == 0 || memcmp(a, b, a_length) == 0);
+}
+
+double wr_rt_fmod(double a, double b) {
+    return fmod(a, b);
+}
+
+/* Values are printed the way `wright::io::print` prints them. */
+void wr_rt_print_str(const char *bytes, size_t length) {
+    fwrite(bytes, 1, length, stdout);
+}
+
+void wr_rt_print_u64(uint64_t value) {
+    printf("%llu", (unsigned long long)value);
+}
+
+void wr_rt_print_i64(int64_t value) {
+    printf("%lld", (long long)value);
+}
+
+void wr_rt_print_bool(uint8_t value) {
+    fputs(value ? "true" : "false", stdout);
+}
+
+void wr_rt_print_func(const char *name) {
+    printf("<%s>", name);
+}
+
+void wr_rt_print_char(uint32_t value) {
+    char bytes[4];
+    int length;
+
+    if (value < 0x80) {
+        bytes[0] = (char)value;
+        length = 1;
+    } else if (value < 0x800) {
+        bytes[0] = (char)(0xC0 | (value >> 6));
+        bytes[1] = (char)(0x80 | (value & 0x3F));
+        length = 2;
+    } else if (value < 0x10000) {
+        bytes[0] = (char)(0xE0 | (value >> 12));
+        bytes[1] = (char)(0x80 | ((value >> 6) & 0x3F));
+        bytes[2] = (char)(0x80 | (value & 0x3F));
+        length = 3;
+    } else {
+        bytes[0] = (char)(0xF0 | (value >> 18));
+        bytes[1] = (char)(0x80 | ((value >> 12) & 0x3F));
+        bytes[2] = (char)(0x80 | ((value >> 6) & 0x3F));
+        bytes[3] = (char)(0x80 | (value & 0x3F));
+        length = 4;
+    }
+
+    fwrite(bytes, 1, (size_t)length, stdout);
+}
+
+/* Floating point numbers are printed with the fewest digits that read back as the same number, without an
+   exponent. */
+void wr_rt_print_f64(double value) {
+    char buffer[32], digits[20];
+    int precision, count = 0, exponent, index;
+    const char *c;
+
+    if (isnan(value)) {
+        fputs("NaN", stdout);
+        return;
+    }
+
+    if (signbit(value)) {
+        putchar('-');
+        value = -value;
+    }
+
+    if (isinf(value)) {
+        fputs("inf", stdout);
+        return;
+    }
+
+    if (value == 0) {
+        putchar('0');
+        return;
+    }
+
+    for (precision = 1; precision < 17; precision++) {
+        snprintf(buffer, sizeof buffer, "%.*e", precision - 1, value);
+
+        if (strtod(buffer, NULL) == value) {
+            break;
+        }
+    }
+
+    snprintf(buffer, sizeof buffer, "%.*e", precision - 1, value);
+
+    for (c = buffer; *c != 'e'; c++) {
+        if (*c != '.') {
+            digits[count++] = *c;
+        }
+    }
+
+    exponent = atoi(c + 1);
+
+    if (exponent < 0) {
+        fputs("0.", stdout);
+
+        for (index = 1; index < -exponent; index++) {
+            putchar('0');
+        }
+
+        fwrite(digits, 1, (size_t)count, stdout);
+    } else if (exponent + 1 >= count) {
+        fwrite(digits, 1, (size_t)count, stdout);
+
+        for (index = count; index <= exponent; index++) {
+            putchar('0');
+        }
+    } else {
+        fwrite(digits, 1, (size_t)exponent + 1, stdout);
+        putchar('.');
+        fwrite(digits + exponent + 1, 1, (size_t)(count - exponent - 1), stdout);
+    }
+}
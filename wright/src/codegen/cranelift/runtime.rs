@@ -0,0 +1,193 @@
+//! The functions that code generated by Cranelift calls when it's run in memory by the JIT.
+//!
+//! These do the same as the C runtime that object files are linked with ([RUNTIME](super::RUNTIME)), except that
+//! they print to the output [run](super::run) is given, and a trap doesn't exit the process -- it's recorded, and
+//! the generated code returns from every call in progress once it sees that [wr_rt_trapped] is true.
+
+use super::Trap;
+use std::{
+    cell::RefCell,
+    ffi::{CStr, c_char},
+    io::{self, Write},
+    slice,
+};
+
+/// The state of the program that's running on this thread.
+struct State {
+    /// The output the program prints to, which is only borrowed while the program runs (see [with_state]).
+    out: *mut (dyn Write + 'static),
+    /// The first error writing to the output.
+    error: Option<io::Error>,
+    /// The trap that stopped the program.
+    trap: Option<Trap>,
+    /// The memory allocated by the program, which is freed once it's done.
+    allocations: Vec<Box<[u64]>>,
+}
+
+thread_local! {
+    /// The state of the program that's running on this thread, if one is.
+    static STATE: RefCell<Option<State>> = const { RefCell::new(None) };
+}
+
+/// The symbols of the runtime functions, with their names.
+pub(super) fn symbols() -> [(&'static str, *const u8); 12] {
+    [
+        ("wr_rt_trap", wr_rt_trap as *const u8),
+        ("wr_rt_trapped", wr_rt_trapped as *const u8),
+        ("wr_rt_alloc", wr_rt_alloc as *const u8),
+        ("wr_rt_str_eq", wr_rt_str_eq as *const u8),
+        ("wr_rt_fmod", wr_rt_fmod as *const u8),
+        ("wr_rt_print_str", wr_rt_print_str as *const u8),
+        ("wr_rt_print_u64", wr_rt_print_u64 as *const u8),
+        ("wr_rt_print_i64", wr_rt_print_i64 as *const u8),
+        ("wr_rt_print_f64", wr_rt_print_f64 as *const u8),
+        ("wr_rt_print_char", wr_rt_print_char as *const u8),
+        ("wr_rt_print_bool", wr_rt_print_bool as *const u8),
+        ("wr_rt_print_func", wr_rt_print_func as *const u8),
+    ]
+}
+
+/// Run a function with the runtime set up to print to the given output, returning the trap that stopped it (if
+/// any) once it's done.
+pub(super) fn with_state(out: &mut dyn Write, run: impl FnOnce()) -> Result<(), Trap> {
+    // SAFETY: The output is only used by the runtime functions, which are only called by `run`, and the pointer
+    // to it is taken out of the state again before this function returns.
+    let out: *mut (dyn Write + '_) = out;
+    let out: *mut (dyn Write + 'static) = unsafe { std::mem::transmute(out) };
+
+    STATE.with_borrow_mut(|state| {
+        *state = Some(State {
+            out,
+            error: None,
+            trap: None,
+            allocations: Vec::new(),
+        })
+    });
+
+    run();
+
+    let state = STATE
+        .with_borrow_mut(Option::take)
+        .expect("the runtime's state was removed");
+    let flushed =
+        (state.error.map_or(Ok(()), Err)).and_then(|()| unsafe { &mut *state.out }.flush());
+
+    match (state.trap, flushed) {
+        (Some(trap), _) => Err(trap),
+        (None, Ok(())) => Ok(()),
+        (None, Err(error)) => Err(Trap {
+            message: format!("couldn't write the output: {error}"),
+            file: String::new(),
+            line: 0,
+        }),
+    }
+}
+
+/// Write bytes to the output of the running program.
+fn print(bytes: &[u8]) {
+    STATE.with_borrow_mut(|state| {
+        let Some(state) = state else {
+            return;
+        };
+
+        if state.error.is_none() && state.trap.is_none() {
+            // SAFETY: The output lives until `with_state` returns (see there).
+            if let Err(error) = unsafe { &mut *state.out }.write_all(bytes) {
+                state.error = Some(error);
+            }
+        }
+    });
+}
+
+/// Get a byte slice from a pointer and length passed by generated code.
+///
+/// # Safety
+///
+/// The pointer must point to that many bytes, which live as long as the slice is used.
+unsafe fn bytes<'a>(pointer: *const u8, length: usize) -> &'a [u8] {
+    match length {
+        0 => &[],
+        _ => unsafe { slice::from_raw_parts(pointer, length) },
+    }
+}
+
+/// Stop the program (when the generated code gets back to [run](super::run)). Only the first trap is kept.
+extern "C" fn wr_rt_trap(
+    message: *const u8,
+    message_length: usize,
+    file: *const u8,
+    file_length: usize,
+    line: u32,
+) {
+    let message = String::from_utf8_lossy(unsafe { bytes(message, message_length) }).into_owned();
+    let file = String::from_utf8_lossy(unsafe { bytes(file, file_length) }).into_owned();
+
+    STATE.with_borrow_mut(|state| {
+        if let Some(state) = state {
+            state.trap.get_or_insert(Trap {
+                message,
+                file,
+                line,
+            });
+        }
+    });
+}
+
+/// Check if the program has trapped, in which case the generated code returns from every call in progress.
+extern "C" fn wr_rt_trapped() -> u8 {
+    STATE.with_borrow(|state| state.as_ref().is_some_and(|state| state.trap.is_some())) as u8
+}
+
+/// Allocate memory that lives until the program is done.
+extern "C" fn wr_rt_alloc(size: usize) -> *mut u8 {
+    let mut memory = vec![0_u64; size.div_ceil(8).max(1)].into_boxed_slice();
+    let pointer = memory.as_mut_ptr().cast();
+
+    STATE.with_borrow_mut(|state| {
+        if let Some(state) = state {
+            state.allocations.push(memory);
+        }
+    });
+
+    pointer
+}
+
+/// Check if two strings have the same bytes.
+extern "C" fn wr_rt_str_eq(a: *const u8, a_length: usize, b: *const u8, b_length: usize) -> u8 {
+    (unsafe { bytes(a, a_length) == bytes(b, b_length) }) as u8
+}
+
+/// Get the remainder of dividing two floating point numbers, which Cranelift doesn't have an instruction for.
+extern "C" fn wr_rt_fmod(a: f64, b: f64) -> f64 {
+    a % b
+}
+
+extern "C" fn wr_rt_print_str(pointer: *const u8, length: usize) {
+    print(unsafe { bytes(pointer, length) });
+}
+
+extern "C" fn wr_rt_print_u64(value: u64) {
+    print(value.to_string().as_bytes());
+}
+
+extern "C" fn wr_rt_print_i64(value: i64) {
+    print(value.to_string().as_bytes());
+}
+
+extern "C" fn wr_rt_print_f64(value: f64) {
+    print(value.to_string().as_bytes());
+}
+
+extern "C" fn wr_rt_print_char(value: u32) {
+    let value = char::from_u32(value).unwrap_or(char::REPLACEMENT_CHARACTER);
+    print(value.encode_utf8(&mut [0; 4]).as_bytes());
+}
+
+extern "C" fn wr_rt_print_bool(value: u8) {
+    print(if value != 0 { b"true" } else { b"false" });
+}
+
+extern "C" fn wr_rt_print_func(name: *const c_char) {
+    let name = unsafe { CStr::from_ptr(name) };
+    print(format!("<{}>", name.to_string_lossy()).as_bytes());
+}
@@ -0,0 +1,2167 @@
+//! Generating textual LLVM IR from the MIR.
+//!
+//! A program becomes a single `.ll` module that only needs the C standard library, so it can be compiled with `llc`
+//! (or `clang`) without wright linking to LLVM itself. Pointers are opaque (`ptr`), which is the default since LLVM
+//! 15 (LLVM 14 reads them with `-opaque-pointers`).
+//!
+//! Atomic types become LLVM's integer and floating point types, records become named structs, unions become a named
+//! struct of an `i32` tag and enough memory for any of their variants, and enums become the index of their variant.
+//! References become pointers, and function values (including closures) become a struct of the code, an environment
+//! of captured values, and a name.
+//!
+//! The parameters of basic blocks become `phi` nodes, and every slot is an `alloca` in the entry block that's read
+//! with `load` and written with `store`. The variants of unions are also read and written through memory, and
+//! printing or comparing a union switches on its tag. Unless debug info is turned off, every instruction has a
+//! `!dbg` location that points back to the `.wr` source its MIR instruction was lowered from.
+//!
+//! The program behaves like it does in the [VM](crate::vm) and the [C backend](super::c): integer overflow,
+//! division by zero, and the like stop it with an error (using LLVM's overflow intrinsics), and values are printed
+//! the same way. Like in C, slots live on the stack, so a reference to one (or a closure that captures one) must not
+//! outlive the call that made it.
+
+use super::{
+    Access, Callee, CompareAs, Context, Helper, Instance, Instances, NamedKind, SHIFT_TRAP, Thunk,
+    VARIANT_TRAP, compare_as, constant_trap, mangle, split_captures, strip_one_reference,
+    unique_name,
+};
+use crate::{
+    analysis::{
+        builtins::Builtin,
+        resolve::{DefId, DefKind, Resolution},
+        typeck::{TypeckResults, ty::Ty},
+    },
+    ast::{
+        expr::{BinaryOperation, UnaryOperation},
+        ty::AtomicTyVariant,
+    },
+    mir::{
+        BlockId, Body, Const, Inst, Jump, Operand, Place, PlaceBase, Program, Projection, Rvalue,
+        Terminator, Value,
+    },
+    source_tracking::fragment::Fragment,
+};
+use num::BigInt;
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    fmt::Write,
+    mem,
+};
+
+/// The declarations every module starts with: the functions of the C standard library that are used, and the
+/// functions for trapping, allocating, comparing strings, and printing.
+const PRELUDE: &str = include_str!("llvm/prelude.ll");
+
+/// The LLVM type of `void` values.
+const VOID: &str = "{}";
+
+/// The LLVM type of strings: their bytes, and how many there are.
+const STR: &str = "{ ptr, i64 }";
+
+/// The LLVM type of function values: their code, environment, and name.
+const FUNC: &str = "{ ptr, ptr, ptr }";
+
+/// Generate an LLVM module from every body of a program (which must have been checked without errors), which runs
+/// the program's `main` function if it has one. Every instruction has a `!dbg` location, unless `debug_info` is
+/// false.
+pub fn emit(
+    program: &Program,
+    resolution: &Resolution,
+    typeck: &TypeckResults,
+    debug_info: bool,
+) -> String {
+    let cx = Context {
+        program,
+        resolution,
+        typeck,
+    };
+
+    let mut generator = Generator {
+        cx,
+        debug_info: debug_info.then(DebugInfo::new),
+        instances: Instances::default(),
+        names: prelude_names(),
+        types: HashMap::new(),
+        pending_types: Vec::new(),
+        helpers: HashMap::new(),
+        pending_helpers: Vec::new(),
+        thunks: HashMap::new(),
+        closures: HashMap::new(),
+        strings: HashMap::new(),
+        intrinsics: BTreeSet::new(),
+        type_definitions: String::new(),
+        constants: String::new(),
+        helper_code: String::new(),
+        function_code: String::new(),
+    };
+
+    let roots: Vec<Instance> = cx.roots().collect();
+    let mut entry = None;
+
+    for instance in roots {
+        let body = &program.bodies[instance.body];
+        let is_entry = body.name == "main" && body.params.is_empty();
+        let name = generator.instance_name(instance);
+
+        if is_entry {
+            entry = Some((name, generator.ty(&body.ret)));
+        }
+    }
+
+    generator.run();
+    generator.finish(entry)
+}
+
+/// Get the names that the prelude defines, which the names of the program's types and functions must not be.
+fn prelude_names() -> HashSet<String> {
+    let words = PRELUDE.split(|c: char| !c.is_ascii_alphanumeric() && c != '_');
+
+    (words.filter(|word| word.starts_with("wr_")))
+        .map(str::to_owned)
+        .collect()
+}
+
+/// A record, union, or enum type, as it is in LLVM.
+#[derive(Debug)]
+struct NamedType {
+    /// The LLVM type.
+    name: String,
+    /// What kind of type it is.
+    kind: NamedKind,
+}
+
+/// The debug info metadata of a module.
+#[derive(Debug)]
+struct DebugInfo {
+    /// Every metadata node, by number. The first is the compile unit (which is only written at the end, once the
+    /// file it's for is known) and the second is the type that every function has.
+    nodes: Vec<String>,
+    /// The node of every file.
+    files: HashMap<String, usize>,
+    /// The node of the first file, which the compile unit is for.
+    first_file: Option<usize>,
+    /// The node of every location, by its subprogram, line, and column.
+    locations: HashMap<(usize, usize, usize), usize>,
+}
+
+impl DebugInfo {
+    /// Start the metadata of a module.
+    fn new() -> Self {
+        DebugInfo {
+            nodes: vec![String::new(), "!DISubroutineType(types: !{})".to_owned()],
+            files: HashMap::new(),
+            first_file: None,
+            locations: HashMap::new(),
+        }
+    }
+
+    /// Add a node, returning its number.
+    fn node(&mut self, node: String) -> usize {
+        self.nodes.push(node);
+        self.nodes.len() - 1
+    }
+
+    /// Get the node of a file, adding it if it's new.
+    fn file(&mut self, name: &str) -> usize {
+        if let Some(file) = self.files.get(name) {
+            return *file;
+        }
+
+        let file = self
+            .node(format!("!DIFile(filename: \"{}\", directory: \"\")", escape(name.as_bytes())));
+
+        self.files.insert(name.to_owned(), file);
+        self.first_file.get_or_insert(file);
+        file
+    }
+
+    /// Add the subprogram of a function, which starts at a span.
+    fn subprogram(&mut self, name: &str, linkage_name: &str, span: &Fragment) -> usize {
+        let file = self.file(&span.source.name().to_string());
+        let (line, _) = line_and_column(span);
+
+        self.node(format!(
+            "distinct !DISubprogram(name: \"{}\", linkageName: \"{linkage_name}\", scope: !{file}, file: !{file}, \
+            line: {line}, type: !1, scopeLine: {line}, spFlags: DISPFlagDefinition, unit: !0)",
+            escape(name.as_bytes())
+        ))
+    }
+
+    /// Get the node of the location of a span in a subprogram, adding it if it's new.
+    fn location(&mut self, scope: usize, span: &Fragment) -> usize {
+        let (line, column) = line_and_column(span);
+
+        if let Some(location) = self.locations.get(&(scope, line, column)) {
+            return *location;
+        }
+
+        let location =
+            self.node(format!("!DILocation(line: {line}, column: {column}, scope: !{scope})"));
+
+        self.locations.insert((scope, line, column), location);
+        location
+    }
+
+    /// Write the metadata, if any function has debug info.
+    fn finish(mut self, out: &mut String) {
+        let Some(file) = self.first_file else {
+            return;
+        };
+
+        self.nodes[0] = format!(
+            "distinct !DICompileUnit(language: DW_LANG_C, file: !{file}, producer: \"wright {}\", \
+            isOptimized: false, runtimeVersion: 0, emissionKind: LineTablesOnly)",
+            crate::build_info::PKG_VERSION
+        );
+
+        let version = self.node("!{i32 2, !\"Debug Info Version\", i32 3}".to_owned());
+        let dwarf = self.node("!{i32 2, !\"Dwarf Version\", i32 4}".to_owned());
+
+        _ = writeln!(out, "\n!llvm.dbg.cu = !{{!0}}");
+        _ = writeln!(out, "!llvm.module.flags = !{{!{version}, !{dwarf}}}\n");
+
+        for (index, node) in self.nodes.iter().enumerate() {
+            _ = writeln!(out, "!{index} = {node}");
+        }
+    }
+}
+
+/// The code of an LLVM function as it's being generated.
+#[derive(Debug)]
+struct Builder {
+    /// The instructions so far, after the entry block's `alloca`s.
+    code: String,
+    /// The `alloca`s of the entry block.
+    allocas: String,
+    /// The label of the block instructions are being added to.
+    block: String,
+    /// How many temporary values and labels have been made.
+    temps: usize,
+    /// The `!dbg` attachment added to instructions (if any).
+    dbg: String,
+    /// The global string of the file, and the line, that traps are reported at.
+    location: (String, usize),
+}
+
+impl Builder {
+    /// Start the code of a function at its entry block.
+    fn new() -> Self {
+        Builder {
+            code: String::new(),
+            allocas: String::new(),
+            block: "entry".to_owned(),
+            temps: 0,
+            dbg: String::new(),
+            location: ("null".to_owned(), 0),
+        }
+    }
+
+    /// Add an instruction.
+    fn line(&mut self, inst: impl AsRef<str>) {
+        _ = writeln!(self.code, "  {}{}", inst.as_ref(), self.dbg);
+    }
+
+    /// Add an instruction that makes a temporary value, returning it.
+    fn assign(&mut self, inst: impl AsRef<str>) -> String {
+        let temp = format!("%t{}", self.temps);
+        self.temps += 1;
+        self.line(format!("{temp} = {}", inst.as_ref()));
+        temp
+    }
+
+    /// Make a new label.
+    fn label(&mut self) -> String {
+        self.temps += 1;
+        format!("l{}", self.temps - 1)
+    }
+
+    /// Start adding instructions to a new block.
+    fn start(&mut self, label: String) {
+        _ = writeln!(self.code, "\n{label}:");
+        self.block = label;
+    }
+
+    /// Allocate memory for a value of a type in the entry block, returning the pointer to it.
+    fn alloca(&mut self, ty: &str) -> String {
+        let temp = format!("%t{}", self.temps);
+        self.temps += 1;
+        _ = writeln!(self.allocas, "  {temp} = alloca {ty}");
+        temp
+    }
+
+    /// Put the function together, with the given signature (the part of the `define` after the linkage).
+    fn finish(self, signature: &str) -> String {
+        format!("\ndefine internal {signature} {{\nentry:\n{}{}}}\n", self.allocas, self.code)
+    }
+}
+
+/// The state of generating a module.
+struct Generator<'a> {
+    /// The program being generated.
+    cx: Context<'a>,
+    /// The debug info, if it's being generated.
+    debug_info: Option<DebugInfo>,
+    /// The instances of bodies, with the names of their LLVM functions.
+    instances: Instances,
+    /// Every name given to an LLVM type or function so far.
+    names: HashSet<String>,
+    /// Every record, union, and enum type that's been used.
+    types: HashMap<Ty, NamedType>,
+    /// The records and unions that haven't been defined yet.
+    pending_types: Vec<Ty>,
+    /// The name of every helper function.
+    helpers: HashMap<(Helper, Ty), String>,
+    /// The helper functions that haven't been generated yet.
+    pending_helpers: Vec<(Helper, Ty)>,
+    /// The constant of every thunk.
+    thunks: HashMap<Thunk, String>,
+    /// How many values each closure body captures, by the index of the body.
+    closures: HashMap<usize, usize>,
+    /// The global of every string constant.
+    strings: HashMap<String, String>,
+    /// The declarations of the intrinsics that are used.
+    intrinsics: BTreeSet<String>,
+    /// The definitions of named types.
+    type_definitions: String,
+    /// The global string constants.
+    constants: String,
+    /// The code of helper functions and thunks.
+    helper_code: String,
+    /// The code of the functions generated from bodies.
+    function_code: String,
+}
+
+impl<'a> Generator<'a> {
+    /// Generate every instance, helper, and type, until none are left (since each one can need more).
+    fn run(&mut self) {
+        loop {
+            if let Some((instance, name)) = self.instances.next() {
+                FunctionGenerator::new(self, instance).generate(&name);
+            } else if let Some((helper, ty)) = self.pending_helpers.pop() {
+                self.generate_helper(helper, &ty);
+            } else if let Some(ty) = self.pending_types.pop() {
+                self.define(&ty);
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Put the pieces of the module together, with a `main` function that calls the given function (which returns
+    /// a value of the given type).
+    fn finish(self, entry: Option<(String, String)>) -> String {
+        let mut out = String::new();
+
+        _ = writeln!(
+            out,
+            "; Generated from wright by wright {}.\n",
+            crate::build_info::PKG_VERSION
+        );
+
+        out.push_str(PRELUDE);
+
+        let intrinsics: String = (self.intrinsics.iter())
+            .map(|declaration| format!("{declaration}\n"))
+            .collect();
+
+        let sections = [
+            ("Types", self.type_definitions),
+            ("Constants", self.constants + "\n" + &intrinsics),
+            ("Printing, comparing, and function values", self.helper_code),
+        ];
+
+        for (title, section) in sections {
+            if !section.trim().is_empty() {
+                _ = write!(out, "\n; {title}.\n\n{}", section.trim_start());
+            }
+        }
+
+        if let Some((entry, ret)) = entry {
+            _ = write!(
+                out,
+                "\ndefine i32 @main() {{\nentry:\n  %result = call {ret} @{entry}()\n  ret i32 0\n}}\n"
+            );
+        }
+
+        out.push_str(&self.function_code);
+
+        if let Some(debug_info) = self.debug_info {
+            debug_info.finish(&mut out);
+        }
+
+        out
+    }
+
+    /// Get the name of the LLVM function of an instance, adding it if it's new.
+    fn instance_name(&mut self, instance: Instance) -> String {
+        let Generator {
+            cx,
+            instances,
+            names,
+            ..
+        } = self;
+
+        let body = &cx.program.bodies[instance.body];
+        let mut name = format!("wr_{}", mangle(&body.name));
+
+        for (_, ty) in &instance.args {
+            name.push('_');
+            name.push_str(&mangle(&ty.display(cx.resolution).to_string()));
+        }
+
+        (instances.get(instance, || unique_name(names, name))).to_owned()
+    }
+
+    /// Get the LLVM type of a (concrete) type.
+    fn ty(&mut self, ty: &Ty) -> String {
+        if let Some(named) = self.types.get(ty) {
+            return named.name.clone();
+        }
+
+        match ty {
+            Ty::Atomic(variant) => atomic_ty(*variant).to_owned(),
+            Ty::Reference { .. } => "ptr".to_owned(),
+            Ty::Function { .. } => FUNC.to_owned(),
+            Ty::Str => STR.to_owned(),
+            Ty::Named { .. } => self.declare_named(ty),
+            Ty::Void | Ty::Param(_) | Ty::Infer(_) | Ty::Integer(_) | Ty::Error => VOID.to_owned(),
+        }
+    }
+
+    /// Name a record, union, or enum type, to be defined later (named types can be used before they're defined).
+    /// Abstract types can't have values, so they're `{}`.
+    fn declare_named(&mut self, ty: &Ty) -> String {
+        let Some(kind) = self.cx.named_kind(ty) else {
+            return VOID.to_owned();
+        };
+
+        let name = match kind {
+            NamedKind::Enum => "i32".to_owned(),
+            NamedKind::Record | NamedKind::Union => {
+                let display = ty.display(self.cx.resolution).to_string();
+                let name = unique_name(&mut self.names, format!("wr_{}", mangle(&display)));
+                self.pending_types.push(ty.clone());
+                format!("%{name}")
+            }
+        };
+
+        self.types.insert(
+            ty.clone(),
+            NamedType {
+                name: name.clone(),
+                kind,
+            },
+        );
+
+        name
+    }
+
+    /// Define the struct of a record or union.
+    fn define(&mut self, ty: &Ty) {
+        let Some(named) = self.types.get(ty) else {
+            return;
+        };
+
+        let (name, kind) = (named.name.clone(), named.kind);
+        let (_, fields) = self.fields(ty);
+
+        let body = match kind {
+            NamedKind::Record => {
+                let fields: Vec<String> = fields.iter().map(|(_, ty)| self.ty(ty)).collect();
+                struct_ty(&fields)
+            }
+
+            // The variants are stored in an array of the most aligned integer type any of them need.
+            NamedKind::Union => {
+                let (size, align) = (fields.iter()).map(|(_, ty)| self.size_and_align(ty)).fold(
+                    (0, 1),
+                    |(size, align), (field_size, field_align)| {
+                        (size.max(field_size), align.max(field_align))
+                    },
+                );
+
+                format!("{{ i32, [{} x i{}] }}", size.div_ceil(align), align * 8)
+            }
+
+            NamedKind::Enum => return,
+        };
+
+        _ = writeln!(self.type_definitions, "{name} = type {body}");
+    }
+
+    /// Get the fields of a record (or the variants of a union).
+    fn fields(&self, ty: &Ty) -> (bool, Vec<(String, Ty)>) {
+        let (is_union, fields) = self.cx.typeck.fields(ty).unwrap_or_default();
+        let fields = (fields.into_iter())
+            .map(|(field, ty)| (field.to_owned(), ty))
+            .collect();
+
+        (is_union, fields)
+    }
+
+    /// Get the size and alignment of a type, in bytes, for the memory of unions. These are at least the size and
+    /// alignment on any target LLVM supports (i.e. pointers are assumed to be 64 bits).
+    fn size_and_align(&self, ty: &Ty) -> (u64, u64) {
+        match ty {
+            Ty::Atomic(variant) => {
+                let size = match variant {
+                    AtomicTyVariant::Bool | AtomicTyVariant::U8 | AtomicTyVariant::I8 => 1,
+                    AtomicTyVariant::U16 | AtomicTyVariant::I16 => 2,
+                    AtomicTyVariant::U32
+                    | AtomicTyVariant::I32
+                    | AtomicTyVariant::F32
+                    | AtomicTyVariant::Char => 4,
+                    AtomicTyVariant::U64 | AtomicTyVariant::I64 | AtomicTyVariant::F64 => 8,
+                };
+
+                (size, size)
+            }
+
+            Ty::Reference { .. } => (8, 8),
+            Ty::Function { .. } => (24, 8),
+            Ty::Str => (16, 8),
+
+            Ty::Named { def, .. } => {
+                match (self.cx.resolution.def(*def).kind, self.cx.typeck.fields(ty)) {
+                    (DefKind::Enum, _) => (4, 4),
+
+                    (_, Some((true, variants))) => {
+                        let (size, align) = (variants.iter())
+                            .map(|(_, ty)| self.size_and_align(ty))
+                            .fold((0, 1), |(size, align), (field_size, field_align)| {
+                                (size.max(field_size), align.max(field_align))
+                            });
+
+                        struct_layout([(4, 4), (size.next_multiple_of(align), align)])
+                    }
+
+                    (_, Some((false, fields))) => {
+                        struct_layout(fields.iter().map(|(_, ty)| self.size_and_align(ty)))
+                    }
+
+                    (_, None) => (0, 1),
+                }
+            }
+
+            Ty::Void | Ty::Param(_) | Ty::Infer(_) | Ty::Integer(_) | Ty::Error => (0, 1),
+        }
+    }
+
+    /// Get the global of a string constant (followed by a zero, so it can be passed to C), adding it if it's new.
+    fn string(&mut self, string: &str) -> String {
+        if let Some(global) = self.strings.get(string) {
+            return global.clone();
+        }
+
+        let global = format!("@.str.{}", self.strings.len());
+
+        _ = writeln!(
+            self.constants,
+            "{global} = private unnamed_addr constant [{} x i8] c\"{}\\00\"",
+            string.len() + 1,
+            escape(string.as_bytes())
+        );
+
+        self.strings.insert(string.to_owned(), global.clone());
+        global
+    }
+
+    /// Get the LLVM constant of a string value.
+    fn str_constant(&mut self, string: &str) -> String {
+        format!("{{ ptr {}, i64 {} }}", self.string(string), string.len())
+    }
+
+    /// Add a call of `wr_trap`, which stops the program at the builder's location, and end the block.
+    fn trap_call(&mut self, b: &mut Builder, message: &str) {
+        let message = self.string(message);
+        let (file, line) = b.location.clone();
+        b.line(format!("call void @wr_trap(ptr {message}, ptr {file}, i32 {line})"));
+        b.line("unreachable");
+    }
+
+    /// Stop the program with a message. Instructions added after this go in a block that's never reached.
+    fn trap(&mut self, b: &mut Builder, message: &str) {
+        self.trap_call(b, message);
+        let dead = b.label();
+        b.start(dead);
+    }
+
+    /// Stop the program with a message if an `i1` is true.
+    fn trap_if(&mut self, b: &mut Builder, condition: &str, message: &str) {
+        let (trap, ok) = (b.label(), b.label());
+        b.line(format!("br i1 {condition}, label %{trap}, label %{ok}"));
+        b.start(trap);
+        self.trap_call(b, message);
+        b.start(ok);
+    }
+
+    /// Print a string constant.
+    fn print_constant(&mut self, b: &mut Builder, string: &str) {
+        let string = self.str_constant(string);
+        b.line(format!("call void @wr_print_str({STR} {string})"));
+    }
+
+    /// Print a value of a type.
+    fn print(&mut self, b: &mut Builder, ty: &Ty, value: &str) {
+        match ty {
+            Ty::Atomic(AtomicTyVariant::Bool) => {
+                b.line(format!("call void @wr_print_bool(i1 {value})"))
+            }
+
+            Ty::Atomic(AtomicTyVariant::Char) => {
+                b.line(format!("call void @wr_print_char(i32 {value})"))
+            }
+
+            Ty::Atomic(AtomicTyVariant::F32) => {
+                let double = b.assign(format!("fpext float {value} to double"));
+                b.line(format!("call void @wr_print_f64(double {double})"));
+            }
+
+            Ty::Atomic(AtomicTyVariant::F64) => {
+                b.line(format!("call void @wr_print_f64(double {value})"))
+            }
+
+            Ty::Atomic(variant) => {
+                let (function, extend) = match variant.is_signed_integer() {
+                    true => ("wr_print_i64", "sext"),
+                    false => ("wr_print_u64", "zext"),
+                };
+
+                let ty = atomic_ty(*variant);
+                let value = match ty {
+                    "i64" => value.to_owned(),
+                    _ => b.assign(format!("{extend} {ty} {value} to i64")),
+                };
+
+                b.line(format!("call void @{function}(i64 {value})"));
+            }
+
+            Ty::Reference { target, .. } => {
+                let target_ty = self.ty(target);
+                let target_value = b.assign(format!("load {target_ty}, ptr {value}"));
+                self.print(b, target, &target_value);
+            }
+
+            Ty::Function { .. } => b.line(format!("call void @wr_print_func({FUNC} {value})")),
+            Ty::Str => b.line(format!("call void @wr_print_str({STR} {value})")),
+            Ty::Void => b.line(format!("call void @wr_print_void({VOID} {value})")),
+
+            Ty::Named { .. } => match self.ty(ty) == VOID {
+                true => self.print_constant(b, "<value>"),
+                false => {
+                    let ty_name = self.ty(ty);
+                    let helper = self.helper(Helper::Print, ty);
+                    b.line(format!("call void @{helper}({ty_name} {value})"));
+                }
+            },
+
+            Ty::Param(_) | Ty::Infer(_) | Ty::Integer(_) | Ty::Error => {
+                self.print_constant(b, "<value>")
+            }
+        }
+    }
+
+    /// Check if two values of a type are equal, returning the `i1`.
+    fn eq(&mut self, b: &mut Builder, ty: &Ty, lhs: &str, rhs: &str) -> String {
+        match ty {
+            Ty::Atomic(variant) if variant.is_float() => {
+                b.assign(format!("fcmp oeq {} {lhs}, {rhs}", atomic_ty(*variant)))
+            }
+
+            Ty::Atomic(variant) => {
+                b.assign(format!("icmp eq {} {lhs}, {rhs}", atomic_ty(*variant)))
+            }
+
+            Ty::Reference { target, .. } => {
+                let target_ty = self.ty(target);
+                let lhs = b.assign(format!("load {target_ty}, ptr {lhs}"));
+                let rhs = b.assign(format!("load {target_ty}, ptr {rhs}"));
+                self.eq(b, target, &lhs, &rhs)
+            }
+
+            Ty::Function { .. } => {
+                let mut same = Vec::new();
+
+                for index in 0..2 {
+                    let lhs = b.assign(format!("extractvalue {FUNC} {lhs}, {index}"));
+                    let rhs = b.assign(format!("extractvalue {FUNC} {rhs}, {index}"));
+                    same.push(b.assign(format!("icmp eq ptr {lhs}, {rhs}")));
+                }
+
+                b.assign(format!("and i1 {}, {}", same[0], same[1]))
+            }
+
+            Ty::Str => b.assign(format!("call i1 @wr_str_eq({STR} {lhs}, {STR} {rhs})")),
+
+            Ty::Named { .. } => {
+                let ty_name = self.ty(ty);
+
+                match self.types.get(ty).map(|named| named.kind) {
+                    Some(NamedKind::Enum) => b.assign(format!("icmp eq i32 {lhs}, {rhs}")),
+                    Some(_) => {
+                        let helper = self.helper(Helper::Eq, ty);
+                        b.assign(format!("call i1 @{helper}({ty_name} {lhs}, {ty_name} {rhs})"))
+                    }
+                    None => "true".to_owned(),
+                }
+            }
+
+            Ty::Void | Ty::Param(_) | Ty::Infer(_) | Ty::Integer(_) | Ty::Error => {
+                "true".to_owned()
+            }
+        }
+    }
+
+    /// Get the name of a helper function for a type, adding it if it's new.
+    fn helper(&mut self, helper: Helper, ty: &Ty) -> String {
+        if let Some(name) = self.helpers.get(&(helper, ty.clone())) {
+            return name.clone();
+        }
+
+        let ty_name = self.ty(ty);
+        let prefix = match helper {
+            Helper::Print => "wr_print",
+            Helper::Eq => "wr_eq",
+        };
+
+        let name = format!("{prefix}_{}", ty_name.trim_start_matches("%wr_"));
+        let name = unique_name(&mut self.names, name);
+
+        self.helpers.insert((helper, ty.clone()), name.clone());
+        self.pending_helpers.push((helper, ty.clone()));
+        name
+    }
+
+    /// Generate the code of a helper function.
+    fn generate_helper(&mut self, helper: Helper, ty: &Ty) {
+        let name = self.helpers[&(helper, ty.clone())].clone();
+        let ty_name = self.ty(ty);
+        let display = ty.display(self.cx.resolution).to_string();
+        let kind = self.types.get(ty).map(|named| named.kind);
+        let (_, fields) = self.fields(ty);
+        let mut b = Builder::new();
+
+        let signature = match helper {
+            Helper::Print => format!("void @{name}({ty_name} %value)"),
+            Helper::Eq => format!("i1 @{name}({ty_name} %lhs, {ty_name} %rhs)"),
+        };
+
+        match (helper, kind) {
+            (Helper::Print, Some(NamedKind::Record)) => {
+                self.print_constant(&mut b, &format!("{display} {{"));
+
+                for (index, (field, field_ty)) in fields.iter().enumerate() {
+                    let separator = if index > 0 { ", " } else { " " };
+                    self.print_constant(&mut b, &format!("{separator}{field}: "));
+                    let value = b.assign(format!("extractvalue {ty_name} %value, {index}"));
+                    self.print(&mut b, field_ty, &value);
+                }
+
+                self.print_constant(&mut b, " }");
+                b.line("ret void");
+            }
+
+            (Helper::Print, Some(NamedKind::Union)) => {
+                let memory = b.alloca(&ty_name);
+                b.line(format!("store {ty_name} %value, ptr {memory}"));
+                let payload =
+                    b.assign(format!("getelementptr {ty_name}, ptr {memory}, i32 0, i32 1"));
+                let tag = b.assign(format!("extractvalue {ty_name} %value, 0"));
+                let end = b.label();
+                let labels: Vec<String> = fields.iter().map(|_| b.label()).collect();
+
+                b.line(switch("i32", &tag, &end, labels.iter().enumerate()));
+
+                for ((variant, variant_ty), label) in fields.iter().zip(labels) {
+                    b.start(label);
+                    self.print_constant(&mut b, &format!("{display} {{ {variant}: "));
+                    let variant_ty_name = self.ty(variant_ty);
+                    let value = b.assign(format!("load {variant_ty_name}, ptr {payload}"));
+                    self.print(&mut b, variant_ty, &value);
+                    b.line(format!("br label %{end}"));
+                }
+
+                b.start(end);
+                self.print_constant(&mut b, " }");
+                b.line("ret void");
+            }
+
+            (Helper::Print, _) => {
+                let Ty::Named { def, .. } = ty else {
+                    return;
+                };
+
+                let variants = self.cx.resolution.variants(*def);
+                let end = b.label();
+                let labels: Vec<String> = variants.iter().map(|_| b.label()).collect();
+
+                b.line(switch("i32", "%value", &end, labels.iter().enumerate()));
+
+                for (variant, label) in variants.iter().zip(labels) {
+                    let variant = &self.cx.resolution.def(*variant).name;
+                    b.start(label);
+                    self.print_constant(&mut b, &format!("{display}::{variant}"));
+                    b.line(format!("br label %{end}"));
+                }
+
+                b.start(end);
+                b.line("ret void");
+            }
+
+            (Helper::Eq, Some(NamedKind::Union)) => {
+                let lhs_tag = b.assign(format!("extractvalue {ty_name} %lhs, 0"));
+                let rhs_tag = b.assign(format!("extractvalue {ty_name} %rhs, 0"));
+                let same = b.assign(format!("icmp eq i32 {lhs_tag}, {rhs_tag}"));
+                let (compare, different) = (b.label(), b.label());
+                b.line(format!("br i1 {same}, label %{compare}, label %{different}"));
+
+                b.start(different);
+                b.line("ret i1 false");
+
+                b.start(compare);
+                let mut payloads = Vec::new();
+
+                for side in ["%lhs", "%rhs"] {
+                    let memory = b.alloca(&ty_name);
+                    b.line(format!("store {ty_name} {side}, ptr {memory}"));
+                    payloads.push(
+                        b.assign(format!("getelementptr {ty_name}, ptr {memory}, i32 0, i32 1")),
+                    );
+                }
+
+                let equal = b.label();
+                let labels: Vec<String> = fields.iter().map(|_| b.label()).collect();
+
+                b.line(switch("i32", &lhs_tag, &equal, labels.iter().enumerate()));
+
+                for ((_, variant_ty), label) in fields.iter().zip(labels) {
+                    b.start(label);
+                    let variant_ty_name = self.ty(variant_ty);
+                    let lhs = b.assign(format!("load {variant_ty_name}, ptr {}", payloads[0]));
+                    let rhs = b.assign(format!("load {variant_ty_name}, ptr {}", payloads[1]));
+                    let eq = self.eq(&mut b, variant_ty, &lhs, &rhs);
+                    b.line(format!("ret i1 {eq}"));
+                }
+
+                b.start(equal);
+                b.line("ret i1 true");
+            }
+
+            (Helper::Eq, _) => {
+                let mut result = "true".to_owned();
+
+                for (index, (_, field_ty)) in fields.iter().enumerate() {
+                    let lhs = b.assign(format!("extractvalue {ty_name} %lhs, {index}"));
+                    let rhs = b.assign(format!("extractvalue {ty_name} %rhs, {index}"));
+                    let eq = self.eq(&mut b, field_ty, &lhs, &rhs);
+                    result = b.assign(format!("and i1 {result}, {eq}"));
+                }
+
+                b.line(format!("ret i1 {result}"));
+            }
+        }
+
+        self.helper_code.push_str(&b.finish(&signature));
+    }
+
+    /// Get the LLVM constant of a function value that runs an instance or a builtin function.
+    fn function_value(&mut self, thunk: Thunk) -> String {
+        if let Some(value) = self.thunks.get(&thunk) {
+            return value.clone();
+        }
+
+        let (label, params, ret) = match &thunk {
+            Thunk::Instance(instance) => {
+                let body = &self.cx.program.bodies[instance.body];
+                let substitution = instance.substitution();
+
+                let params: Vec<Ty> = (body.params.iter())
+                    .map(|param| body.value_ty(*param).substitute(&substitution))
+                    .collect();
+
+                let ret = body.ret.substitute(&substitution);
+                (format!("func {}", body.name), params, ret)
+            }
+
+            Thunk::Builtin(builtin, ty) => {
+                let params = match ty {
+                    Ty::Function { params, .. } => params.clone(),
+                    _ => Vec::new(),
+                };
+
+                (format!("func wright::io::{}", builtin.name()), params, Ty::Void)
+            }
+        };
+
+        let base = match &thunk {
+            Thunk::Instance(instance) => format!("{}_value", self.instance_name(instance.clone())),
+            Thunk::Builtin(builtin, _) => format!("wr_{}_value", builtin.name()),
+        };
+
+        let name = unique_name(&mut self.names, base);
+        let ret_ty = self.ty(&ret);
+        let mut b = Builder::new();
+        let mut declared = vec!["ptr %env".to_owned()];
+        let mut args = Vec::new();
+
+        for (index, param) in params.iter().enumerate() {
+            let param = self.ty(param);
+            declared.push(format!("{param} %a{index}"));
+            args.push(format!("{param} %a{index}"));
+        }
+
+        match &thunk {
+            Thunk::Instance(instance) => {
+                let function = self.instance_name(instance.clone());
+                let result = b.assign(format!("call {ret_ty} @{function}({})", args.join(", ")));
+                b.line(format!("ret {ret_ty} {result}"));
+            }
+
+            Thunk::Builtin(builtin, _) => {
+                if let Some(param) = params.first() {
+                    self.print(&mut b, param, "%a0");
+                }
+
+                if *builtin == Builtin::Println {
+                    b.assign("call i32 @putchar(i32 10)");
+                }
+
+                b.line(format!("ret {VOID} zeroinitializer"));
+            }
+        }
+
+        let signature = format!("{ret_ty} @{name}({})", declared.join(", "));
+        self.helper_code.push_str(&b.finish(&signature));
+
+        let label = self.string(&label);
+        let value = format!("{{ ptr @{name}, ptr null, ptr {label} }}");
+        self.thunks.insert(thunk, value.clone());
+        value
+    }
+}
+
+/// The state of generating the LLVM function of an instance.
+struct FunctionGenerator<'g, 'a> {
+    /// The generator of the module.
+    g: &'g mut Generator<'a>,
+    /// The body.
+    body: &'a Body,
+    /// The types of the instance's generic parameters.
+    substitution: HashMap<DefId, Ty>,
+    /// How many values the body captures, if it's the body of a closure.
+    captures: Option<usize>,
+    /// The code of the function.
+    b: Builder,
+    /// The LLVM value of every MIR value that's been defined.
+    values: HashMap<Value, String>,
+    /// The label of every LLVM block that jumps to each MIR block, with the arguments it passes.
+    incoming: HashMap<BlockId, Vec<(String, Vec<String>)>>,
+    /// The debug info subprogram of the function, if debug info is being generated.
+    scope: Option<usize>,
+}
+
+impl<'g, 'a> FunctionGenerator<'g, 'a> {
+    /// Set up the generating of an instance.
+    fn new(g: &'g mut Generator<'a>, instance: Instance) -> Self {
+        let body = &g.cx.program.bodies[instance.body];
+        let captures = g.closures.get(&instance.body).copied();
+
+        FunctionGenerator {
+            g,
+            body,
+            substitution: instance.substitution(),
+            captures,
+            b: Builder::new(),
+            values: HashMap::new(),
+            incoming: HashMap::new(),
+            scope: None,
+        }
+    }
+
+    /// Generate the LLVM function with the given name.
+    fn generate(mut self, name: &str) {
+        let ret = self.ty(&self.body.ret.substitute(&self.substitution));
+        let (captured, called_with) = split_captures(self.body, self.captures);
+        let mut params = Vec::new();
+
+        if self.captures.is_some() {
+            params.push("ptr %env".to_owned());
+        }
+
+        for param in called_with {
+            let ty = self.ty(&self.value_ty(*param));
+            params.push(format!("{ty} %v{}", param.0));
+            self.define(*param);
+        }
+
+        let first_block = self.body.block(BlockId::ENTRY);
+        let start = first_block
+            .spans
+            .first()
+            .unwrap_or(&first_block.terminator_span);
+
+        let mut signature = format!("{ret} @{name}({})", params.join(", "));
+
+        if let Some(debug_info) = &mut self.g.debug_info {
+            let scope = debug_info.subprogram(&self.body.name, name, start);
+            _ = write!(signature, " !dbg !{scope}");
+            self.scope = Some(scope);
+        }
+
+        self.locate(start);
+
+        if !captured.is_empty() {
+            let tys: Vec<String> = (captured.iter())
+                .map(|param| self.ty(&self.value_ty(*param)))
+                .collect();
+            let env = struct_ty(&tys);
+
+            for (index, (param, ty)) in captured.iter().zip(&tys).enumerate() {
+                let pointer =
+                    (self.b).assign(format!("getelementptr {env}, ptr %env, i32 0, i32 {index}"));
+                self.b
+                    .line(format!("%v{} = load {ty}, ptr {pointer}", param.0));
+                self.define(*param);
+            }
+        }
+
+        for (index, slot) in self.body.slots.iter().enumerate() {
+            let ty = self.ty(&slot.ty.substitute(&self.substitution));
+            _ = writeln!(self.b.allocas, "  %s{index} = alloca {ty}");
+        }
+
+        self.b.line("br label %bb0");
+
+        let mut blocks = Vec::new();
+        let mut code = mem::take(&mut self.b.code);
+
+        for block in self.body.block_ids() {
+            let data = self.body.block(block);
+            self.b.block = format!("bb{}", block.0);
+
+            for param in &data.params {
+                self.define(*param);
+            }
+
+            for (inst, span) in data.insts.iter().zip(&data.spans) {
+                self.locate(span);
+                self.inst(inst);
+            }
+
+            self.locate(&data.terminator_span);
+            self.terminator(&data.terminator);
+            blocks.push((block, mem::take(&mut self.b.code)));
+        }
+
+        // The parameters of blocks are only known once every jump to them has been generated.
+        for (block, block_code) in blocks {
+            _ = writeln!(code, "\nbb{}:", block.0);
+
+            for (index, param) in self.body.block(block).params.iter().enumerate() {
+                if self.is_void(*param) {
+                    continue;
+                }
+
+                let ty = self.ty(&self.value_ty(*param));
+                let incoming: Vec<String> = (self.incoming.get(&block).into_iter().flatten())
+                    .map(|(label, args)| format!("[ {}, %{label} ]", args[index]))
+                    .collect();
+
+                _ = writeln!(code, "  %v{} = phi {ty} {}", param.0, incoming.join(", "));
+            }
+
+            code.push_str(&block_code);
+        }
+
+        self.b.code = code;
+        self.g.function_code.push_str(&self.b.finish(&signature));
+    }
+
+    /// Get the LLVM type of a type.
+    fn ty(&mut self, ty: &Ty) -> String {
+        self.g.ty(ty)
+    }
+
+    /// Get the (concrete) type of a value.
+    fn value_ty(&self, value: Value) -> Ty {
+        self.body.value_ty(value).substitute(&self.substitution)
+    }
+
+    /// Get the (concrete) type of an operand.
+    fn operand_ty(&self, operand: &Operand) -> Ty {
+        self.body.operand_ty(operand).substitute(&self.substitution)
+    }
+
+    /// Check if a value is a `void` value. These don't get an LLVM value, since they can only be `()`.
+    fn is_void(&self, value: Value) -> bool {
+        self.value_ty(value) == Ty::Void
+    }
+
+    /// Define a value that's named after itself (a parameter of the function or of a block).
+    fn define(&mut self, value: Value) {
+        let llvm_value = match self.is_void(value) {
+            true => "zeroinitializer".to_owned(),
+            false => format!("%v{}", value.0),
+        };
+
+        self.values.insert(value, llvm_value);
+    }
+
+    /// Add an instruction that defines a value.
+    fn assign(&mut self, value: Value, inst: impl AsRef<str>) {
+        self.b.line(format!("%v{} = {}", value.0, inst.as_ref()));
+        self.values.insert(value, format!("%v{}", value.0));
+    }
+
+    /// Stop the program with a message.
+    fn trap(&mut self, message: &str) {
+        self.g.trap(&mut self.b, message);
+    }
+
+    /// Stop the program with a message if an `i1` is true.
+    fn trap_if(&mut self, condition: &str, message: &str) {
+        self.g.trap_if(&mut self.b, condition, message);
+    }
+
+    /// Attribute the instructions added from now on to the source code of a span.
+    fn locate(&mut self, span: &Fragment) {
+        let (line, _) = line_and_column(span);
+        let file = self.g.string(&span.source.name().to_string());
+        self.b.location = (file, line);
+
+        if let (Some(scope), Some(debug_info)) = (self.scope, &mut self.g.debug_info) {
+            self.b.dbg = format!(", !dbg !{}", debug_info.location(scope, span));
+        }
+    }
+
+    /// Get the LLVM value of an operand. Values that were never defined (because the code that would have defined
+    /// them traps) are `undef`.
+    fn operand(&mut self, operand: &Operand) -> String {
+        match operand {
+            Operand::Value(value) => {
+                (self.values.get(value).cloned()).unwrap_or_else(|| "undef".to_owned())
+            }
+            Operand::Const(constant) => self.constant(constant),
+        }
+    }
+
+    /// Get the LLVM constant of a constant. Constants that can't be made trap (and are left `undef`).
+    fn constant(&mut self, constant: &Const) -> String {
+        match constant {
+            Const::Int(number, ty) => match ty.atomic().and_then(|ty| int_literal(ty, number)) {
+                Some(literal) => literal,
+                None => {
+                    self.trap(&format!("the integer {constant:?} doesn't fit its type"));
+                    "undef".to_owned()
+                }
+            },
+
+            Const::Bool(value) => value.to_string(),
+            Const::Str(string) => self.g.str_constant(string),
+            Const::Void => "zeroinitializer".to_owned(),
+            Const::Variant(_, index, _) => index.to_string(),
+
+            Const::Def(def, ty) => {
+                let ty = ty.substitute(&self.substitution);
+
+                match self.g.cx.callee_value(*def, &ty) {
+                    Callee::Instance(instance) => self.g.function_value(Thunk::Instance(instance)),
+                    Callee::Builtin(builtin) => self.g.function_value(Thunk::Builtin(builtin, ty)),
+                    Callee::Missing(message) => {
+                        self.trap(&message);
+                        "undef".to_owned()
+                    }
+                }
+            }
+        }
+    }
+
+    /// Get the pointer to a place. Going through a variant of a union to load or store checks that the union holds
+    /// it, except for the last step of a place that's stored to, which changes the union to hold it. References to
+    /// variants aren't checked until they're used, like in the VM.
+    fn place(&mut self, place: &Place, access: Access) -> String {
+        let (mut pointer, mut ty, projections) = match place.base {
+            PlaceBase::Slot(slot) => (
+                format!("%s{}", slot.0),
+                self.body.slots[slot.0 as usize]
+                    .ty
+                    .substitute(&self.substitution),
+                &place.projections[..],
+            ),
+
+            PlaceBase::Value(value) => (
+                self.operand(&Operand::Value(value)),
+                strip_one_reference(&self.value_ty(value)),
+                &place.projections[1..],
+            ),
+        };
+
+        for (index, projection) in projections.iter().enumerate() {
+            match projection {
+                Projection::Field(field) => {
+                    let ty_name = self.ty(&ty);
+                    let (is_union, fields) = self.g.fields(&ty);
+                    let position = fields.iter().position(|(name, _)| name == field);
+                    let field_ty =
+                        position.map_or(Ty::Error, |position| fields[position].1.clone());
+                    let position = position.unwrap_or_default();
+
+                    if is_union {
+                        let is_last = index == projections.len() - 1;
+
+                        // The tag is the first field of the union, at the same address.
+                        match access {
+                            Access::Store if is_last => {
+                                self.b.line(format!("store i32 {position}, ptr {pointer}"))
+                            }
+                            Access::Ref if is_last => {}
+                            _ => {
+                                let tag = self.b.assign(format!("load i32, ptr {pointer}"));
+                                let wrong = self.b.assign(format!("icmp ne i32 {tag}, {position}"));
+                                self.trap_if(&wrong, VARIANT_TRAP);
+                            }
+                        }
+
+                        pointer = (self.b).assign(format!(
+                            "getelementptr {ty_name}, ptr {pointer}, i32 0, i32 1"
+                        ));
+                    } else {
+                        pointer = (self.b).assign(format!(
+                            "getelementptr {ty_name}, ptr {pointer}, i32 0, i32 {position}"
+                        ));
+                    }
+
+                    ty = field_ty;
+                }
+
+                Projection::Deref => {
+                    pointer = self.b.assign(format!("load ptr, ptr {pointer}"));
+                    ty = strip_one_reference(&ty);
+                }
+            }
+        }
+
+        pointer
+    }
+
+    /// Generate an instruction.
+    fn inst(&mut self, inst: &Inst) {
+        match inst {
+            Inst::Assign(value, rvalue) => self.rvalue(*value, rvalue),
+
+            Inst::Store(place, operand) => {
+                let ty = self.operand_ty(operand);
+                let ty = self.ty(&ty);
+                let src = self.operand(operand);
+                let place = self.place(place, Access::Store);
+                self.b.line(format!("store {ty} {src}, ptr {place}"));
+            }
+
+            // Slots live until the function returns.
+            Inst::Drop(_) => {}
+        }
+    }
+
+    /// Generate the computation of a value.
+    fn rvalue(&mut self, value: Value, rvalue: &Rvalue) {
+        // Only calls have an effect, when they make a `void` value.
+        if self.is_void(value) {
+            self.values.insert(value, "zeroinitializer".to_owned());
+
+            if let Rvalue::Call { callee, args } = rvalue {
+                self.call(value, callee, args);
+            }
+
+            return;
+        }
+
+        match rvalue {
+            Rvalue::Use(operand) => {
+                let src = self.operand(operand);
+                self.values.insert(value, src);
+            }
+
+            Rvalue::Load(place) => {
+                let ty = self.ty(&self.value_ty(value));
+                let place = self.place(place, Access::Load);
+                self.assign(value, format!("load {ty}, ptr {place}"));
+            }
+
+            Rvalue::Ref { place, .. } => {
+                let place = self.place(place, Access::Ref);
+                self.values.insert(value, place);
+            }
+
+            Rvalue::Unary(op, operand) => self.unary(value, *op, operand),
+            Rvalue::Binary(op, lhs, rhs) => self.binary(value, *op, lhs, rhs),
+            Rvalue::Call { callee, args } => self.call(value, callee, args),
+
+            Rvalue::Aggregate { fields } => {
+                let ty = self.value_ty(value);
+                let ty_name = self.ty(&ty);
+                let (is_union, layout) = self.g.fields(&ty);
+
+                if is_union {
+                    let Some((name, operand)) = fields.first() else {
+                        return self.trap("a union is made without a variant");
+                    };
+
+                    let position = (layout.iter())
+                        .position(|(variant, _)| variant == name)
+                        .unwrap_or_default();
+                    let variant_ty = self.operand_ty(operand);
+                    let variant_ty = self.ty(&variant_ty);
+                    let src = self.operand(operand);
+
+                    let memory = self.b.alloca(&ty_name);
+                    self.b
+                        .line(format!("store {ty_name} zeroinitializer, ptr {memory}"));
+                    self.b.line(format!("store i32 {position}, ptr {memory}"));
+                    let payload = (self.b)
+                        .assign(format!("getelementptr {ty_name}, ptr {memory}, i32 0, i32 1"));
+                    self.b
+                        .line(format!("store {variant_ty} {src}, ptr {payload}"));
+                    return self.assign(value, format!("load {ty_name}, ptr {memory}"));
+                }
+
+                // The fields are given in any order, but they're inserted in the order they're declared.
+                let mut inserts = Vec::new();
+
+                for (index, (name, field_ty)) in layout.iter().enumerate() {
+                    if let Some((_, operand)) = fields.iter().find(|(field, _)| field == name) {
+                        let field_ty = self.ty(field_ty);
+                        let src = self.operand(operand);
+                        inserts.push(format!("{field_ty} {src}, {index}"));
+                    }
+                }
+
+                let mut aggregate = "zeroinitializer".to_owned();
+                let count = inserts.len();
+
+                for (index, insert) in inserts.into_iter().enumerate() {
+                    let inst = format!("insertvalue {ty_name} {aggregate}, {insert}");
+
+                    match index + 1 == count {
+                        true => return self.assign(value, inst),
+                        false => aggregate = self.b.assign(inst),
+                    }
+                }
+
+                self.values.insert(value, aggregate);
+            }
+
+            Rvalue::Field(operand, field) => {
+                let ty = self.operand_ty(operand);
+                let ty_name = self.ty(&ty);
+                let (is_union, fields) = self.g.fields(&ty);
+                let position = (fields.iter())
+                    .position(|(name, _)| name == field)
+                    .unwrap_or_default();
+                let src = self.operand(operand);
+
+                if !is_union {
+                    return self.assign(value, format!("extractvalue {ty_name} {src}, {position}"));
+                }
+
+                let tag = self.b.assign(format!("extractvalue {ty_name} {src}, 0"));
+                let wrong = self.b.assign(format!("icmp ne i32 {tag}, {position}"));
+                self.trap_if(&wrong, VARIANT_TRAP);
+
+                let variant_ty = self.ty(&self.value_ty(value));
+                let memory = self.b.alloca(&ty_name);
+                self.b.line(format!("store {ty_name} {src}, ptr {memory}"));
+                let payload =
+                    (self.b).assign(format!("getelementptr {ty_name}, ptr {memory}, i32 0, i32 1"));
+                self.assign(value, format!("load {variant_ty}, ptr {payload}"));
+            }
+
+            Rvalue::IsVariant(operand, variant) => {
+                let ty = self.operand_ty(operand);
+                let ty_name = self.ty(&ty);
+                let (_, fields) = self.g.fields(&ty);
+                let position = (fields.iter())
+                    .position(|(name, _)| name == variant)
+                    .unwrap_or_default();
+                let src = self.operand(operand);
+                let tag = self.b.assign(format!("extractvalue {ty_name} {src}, 0"));
+                self.assign(value, format!("icmp eq i32 {tag}, {position}"));
+            }
+
+            Rvalue::Closure { body, captures } => {
+                let index = body.0 as usize;
+
+                let instance = match self.g.cx.instance(index, &self.substitution) {
+                    Ok(instance) => instance,
+                    Err(_) => {
+                        return self
+                            .trap("the types of a closure's generic parameters aren't known");
+                    }
+                };
+
+                self.g.closures.insert(index, captures.len());
+                let function = self.g.instance_name(instance);
+                let label =
+                    (self.g).string(&format!("closure {}", self.g.cx.program.bodies[index].name));
+                let closure = format!("{{ ptr @{function}, ptr null, ptr {label} }}");
+
+                if captures.is_empty() {
+                    self.values.insert(value, closure);
+                    return;
+                }
+
+                // The environment of a closure lives as long as the program does.
+                let mut tys = Vec::new();
+                let mut srcs = Vec::new();
+
+                for capture in captures {
+                    let ty = self.operand_ty(capture);
+                    tys.push(self.ty(&ty));
+                    srcs.push(self.operand(capture));
+                }
+
+                let env_ty = struct_ty(&tys);
+                let (file, line) = self.b.location.clone();
+                let env = self.b.assign(format!(
+                    "call ptr @wr_alloc(i64 ptrtoint (ptr getelementptr ({env_ty}, ptr null, i32 1) to i64), \
+                    ptr {file}, i32 {line})"
+                ));
+
+                for (index, (ty, src)) in tys.iter().zip(srcs).enumerate() {
+                    let pointer = (self.b)
+                        .assign(format!("getelementptr {env_ty}, ptr {env}, i32 0, i32 {index}"));
+                    self.b.line(format!("store {ty} {src}, ptr {pointer}"));
+                }
+
+                self.assign(value, format!("insertvalue {FUNC} {closure}, ptr {env}, 1"));
+            }
+        }
+    }
+
+    /// Generate an operation on integers that can overflow, using the intrinsic with the given name (such as
+    /// `sadd`), which returns the result and whether it overflowed.
+    fn checked(&mut self, value: Value, intrinsic: &str, ty: &str, lhs: &str, rhs: &str) {
+        let function = format!("llvm.{intrinsic}.with.overflow.{ty}");
+        let result = format!("{{ {ty}, i1 }}");
+
+        (self.g.intrinsics).insert(format!("declare {result} @{function}({ty}, {ty})"));
+
+        let call = self
+            .b
+            .assign(format!("call {result} @{function}({ty} {lhs}, {ty} {rhs})"));
+        let overflow = self.b.assign(format!("extractvalue {result} {call}, 1"));
+        self.trap_if(&overflow, "arithmetic overflow");
+        self.assign(value, format!("extractvalue {result} {call}, 0"));
+    }
+
+    /// Generate a unary operation.
+    fn unary(&mut self, value: Value, op: UnaryOperation, operand: &Operand) {
+        let ty = self.operand_ty(operand);
+        let src = self.operand(operand);
+        let atomic = ty.atomic();
+
+        match (op, atomic.filter(|atomic| atomic.is_integer())) {
+            (UnaryOperation::Negate, Some(int)) => {
+                let intrinsic = match int.is_signed_integer() {
+                    true => "ssub",
+                    false => "usub",
+                };
+
+                self.checked(value, intrinsic, atomic_ty(int), "0", &src);
+            }
+
+            (UnaryOperation::BitwiseNot, Some(int)) => {
+                self.assign(value, format!("xor {} {src}, -1", atomic_ty(int)))
+            }
+
+            (UnaryOperation::Negate, None) if atomic.is_some_and(AtomicTyVariant::is_float) => {
+                let ty_name = self.ty(&ty);
+                self.assign(value, format!("fneg {ty_name} {src}"))
+            }
+
+            (UnaryOperation::BooleanNot | UnaryOperation::BitwiseNot, None) if ty == Ty::BOOL => {
+                self.assign(value, format!("xor i1 {src}, true"))
+            }
+
+            _ => self.unsupported(op.symbol(), &ty),
+        }
+    }
+
+    /// Generate a binary operation.
+    fn binary(&mut self, value: Value, op: BinaryOperation, lhs: &Operand, rhs: &Operand) {
+        let ty = self.operand_ty(lhs);
+        let rhs_ty = self.operand_ty(rhs);
+        let atomic = ty.atomic();
+        let ty_name = self.ty(&ty);
+        let a = self.operand(lhs);
+        let b = self.operand(rhs);
+
+        if let Some((inst, predicate)) = compare(op, atomic) {
+            return match (&ty, op) {
+                (Ty::Atomic(_), _) => {
+                    self.assign(value, format!("{inst} {predicate} {ty_name} {a}, {b}"))
+                }
+
+                (_, BinaryOperation::Equal) => {
+                    let eq = self.g.eq(&mut self.b, &ty, &a, &b);
+                    self.values.insert(value, eq);
+                }
+
+                (_, BinaryOperation::NotEqual) => {
+                    let eq = self.g.eq(&mut self.b, &ty, &a, &b);
+                    self.assign(value, format!("xor i1 {eq}, true"))
+                }
+
+                _ => self.unsupported(op.symbol(), &ty),
+            };
+        }
+
+        if let Some(int) = atomic.filter(|atomic| atomic.is_integer()) {
+            let bits = int_bits(int);
+            let signed = int.is_signed_integer();
+
+            if let Some(message) = constant_trap(op, rhs, bits) {
+                return self.trap(message);
+            }
+
+            let sign = if signed { "s" } else { "u" };
+
+            match op {
+                BinaryOperation::Add => {
+                    self.checked(value, &format!("{sign}add"), &ty_name, &a, &b)
+                }
+                BinaryOperation::Subtract => {
+                    self.checked(value, &format!("{sign}sub"), &ty_name, &a, &b)
+                }
+                BinaryOperation::Multiply => {
+                    self.checked(value, &format!("{sign}mul"), &ty_name, &a, &b)
+                }
+
+                BinaryOperation::Divide | BinaryOperation::Modulo => {
+                    let zero = self.b.assign(format!("icmp eq {ty_name} {b}, 0"));
+                    self.trap_if(&zero, "division by zero");
+
+                    match (op, signed) {
+                        (BinaryOperation::Divide, true) => {
+                            let (min, _) = int.integer_bounds().unwrap_or_default();
+                            let is_min = self.b.assign(format!("icmp eq {ty_name} {a}, {min}"));
+                            let is_minus_one = self.b.assign(format!("icmp eq {ty_name} {b}, -1"));
+                            let overflow =
+                                self.b.assign(format!("and i1 {is_min}, {is_minus_one}"));
+                            self.trap_if(&overflow, "arithmetic overflow");
+                            self.assign(value, format!("sdiv {ty_name} {a}, {b}"));
+                        }
+
+                        (BinaryOperation::Divide, false) => {
+                            self.assign(value, format!("udiv {ty_name} {a}, {b}"))
+                        }
+
+                        // The remainder of dividing by -1 is always 0, but `srem` of the smallest integer by -1 is
+                        // undefined, so the division is by 1 instead.
+                        (_, true) => {
+                            let is_minus_one = self.b.assign(format!("icmp eq {ty_name} {b}, -1"));
+                            let divisor = (self.b).assign(format!(
+                                "select i1 {is_minus_one}, {ty_name} 1, {ty_name} {b}"
+                            ));
+                            self.assign(value, format!("srem {ty_name} {a}, {divisor}"));
+                        }
+
+                        (_, false) => self.assign(value, format!("urem {ty_name} {a}, {b}")),
+                    }
+                }
+
+                BinaryOperation::BitwiseAnd => {
+                    self.assign(value, format!("and {ty_name} {a}, {b}"))
+                }
+                BinaryOperation::BitwiseOr => self.assign(value, format!("or {ty_name} {a}, {b}")),
+                BinaryOperation::BitwiseXor => {
+                    self.assign(value, format!("xor {ty_name} {a}, {b}"))
+                }
+
+                BinaryOperation::ShiftLeft | BinaryOperation::ShiftRight => {
+                    let Some(rhs_int) = rhs_ty.atomic().filter(|atomic| atomic.is_integer()) else {
+                        return self.unsupported(op.symbol(), &rhs_ty);
+                    };
+
+                    // Negative amounts are larger than the number of bits when they're compared as unsigned.
+                    let rhs_bits = int_bits(rhs_int);
+                    let rhs_ty_name = atomic_ty(rhs_int);
+                    let too_far = self.b.assign(format!("icmp uge {rhs_ty_name} {b}, {bits}"));
+                    self.trap_if(&too_far, SHIFT_TRAP);
+
+                    let amount = match rhs_bits.cmp(&bits) {
+                        std::cmp::Ordering::Greater => self
+                            .b
+                            .assign(format!("trunc {rhs_ty_name} {b} to {ty_name}")),
+                        std::cmp::Ordering::Less => self
+                            .b
+                            .assign(format!("zext {rhs_ty_name} {b} to {ty_name}")),
+                        std::cmp::Ordering::Equal => b,
+                    };
+
+                    let inst = match (op, signed) {
+                        (BinaryOperation::ShiftLeft, _) => "shl",
+                        (_, true) => "ashr",
+                        (_, false) => "lshr",
+                    };
+
+                    self.assign(value, format!("{inst} {ty_name} {a}, {amount}"));
+                }
+
+                _ => self.unsupported(op.symbol(), &ty),
+            }
+        } else if atomic.is_some_and(AtomicTyVariant::is_float) {
+            let inst = match op {
+                BinaryOperation::Add => "fadd",
+                BinaryOperation::Subtract => "fsub",
+                BinaryOperation::Multiply => "fmul",
+                BinaryOperation::Divide => "fdiv",
+                BinaryOperation::Modulo => "frem",
+                _ => return self.unsupported(op.symbol(), &ty),
+            };
+
+            self.assign(value, format!("{inst} {ty_name} {a}, {b}"));
+        } else if ty == Ty::BOOL {
+            let inst = match op {
+                BinaryOperation::BitwiseAnd | BinaryOperation::LogicalAnd => "and",
+                BinaryOperation::BitwiseOr | BinaryOperation::LogicalOr => "or",
+                BinaryOperation::BitwiseXor => "xor",
+                _ => return self.unsupported(op.symbol(), &ty),
+            };
+
+            self.assign(value, format!("{inst} i1 {a}, {b}"));
+        } else {
+            self.unsupported(op.symbol(), &ty);
+        }
+    }
+
+    /// Trap on an operation that isn't supported for a type, which type checking should have ruled out.
+    fn unsupported(&mut self, op: &str, ty: &Ty) {
+        let ty = ty.display(self.g.cx.resolution).to_string();
+        self.trap(&format!("`{op}` isn't supported for `{ty}`"));
+    }
+
+    /// Generate a call.
+    fn call(&mut self, value: Value, callee: &Operand, args: &[Operand]) {
+        let ret = self.ty(&self.value_ty(value));
+        let arg_tys: Vec<Ty> = args.iter().map(|arg| self.operand_ty(arg)).collect();
+        let mut typed_args = Vec::new();
+
+        for (arg, ty) in args.iter().zip(&arg_tys) {
+            let ty = self.ty(ty);
+            let arg = self.operand(arg);
+            typed_args.push(format!("{ty} {arg}"));
+        }
+
+        let Operand::Const(Const::Def(def, _)) = callee else {
+            // A function value's code takes the environment before the arguments.
+            let ty = self.operand_ty(callee);
+
+            if !matches!(ty, Ty::Function { .. }) {
+                return self.trap(&format!(
+                    "expected a function, found a value of type `{}`",
+                    ty.display(self.g.cx.resolution)
+                ));
+            }
+
+            let callee = self.operand(callee);
+            let code = self.b.assign(format!("extractvalue {FUNC} {callee}, 0"));
+            let env = self.b.assign(format!("extractvalue {FUNC} {callee}, 1"));
+            typed_args.insert(0, format!("ptr {env}"));
+            return self.emit_call(value, &ret, &code, &typed_args);
+        };
+
+        match self.g.cx.callee(*def, &arg_tys, &self.value_ty(value)) {
+            Callee::Instance(instance) => {
+                let function = format!("@{}", self.g.instance_name(instance));
+                self.emit_call(value, &ret, &function, &typed_args);
+            }
+
+            Callee::Builtin(builtin) => {
+                if let Some(ty) = arg_tys.first() {
+                    let arg = self.operand(&args[0]);
+                    self.g.print(&mut self.b, ty, &arg);
+                }
+
+                if builtin == Builtin::Println {
+                    self.b.assign("call i32 @putchar(i32 10)");
+                }
+            }
+
+            Callee::Missing(message) => self.trap(&message),
+        }
+    }
+
+    /// Add a `call` instruction that defines a value (unless it's `void`).
+    fn emit_call(&mut self, value: Value, ret: &str, function: &str, args: &[String]) {
+        let inst = format!("call {ret} {function}({})", args.join(", "));
+
+        match self.is_void(value) {
+            true => _ = self.b.assign(inst),
+            false => self.assign(value, inst),
+        }
+    }
+
+    /// Generate a terminator.
+    fn terminator(&mut self, terminator: &Terminator) {
+        match terminator {
+            Terminator::Goto(jump) => self.jump(jump),
+
+            Terminator::Branch {
+                condition,
+                then,
+                otherwise,
+            } => {
+                let condition = self.operand(condition);
+
+                // A block can't pass different arguments to the same block, so each jump gets its own block.
+                if then.target == otherwise.target {
+                    let (then_label, otherwise_label) = (self.b.label(), self.b.label());
+                    (self.b).line(format!(
+                        "br i1 {condition}, label %{then_label}, label %{otherwise_label}"
+                    ));
+                    self.b.start(then_label);
+                    self.jump(then);
+                    self.b.start(otherwise_label);
+                    return self.jump(otherwise);
+                }
+
+                let then_args = self.args(then);
+                let otherwise_args = self.args(otherwise);
+                let block = self.b.block.clone();
+                let incoming = &mut self.incoming;
+                (incoming.entry(then.target).or_default()).push((block.clone(), then_args));
+                (incoming.entry(otherwise.target).or_default()).push((block, otherwise_args));
+
+                self.b.line(format!(
+                    "br i1 {condition}, label %bb{}, label %bb{}",
+                    then.target.0, otherwise.target.0
+                ));
+            }
+
+            Terminator::Switch {
+                value,
+                cases,
+                otherwise,
+            } => {
+                let ty = self.operand_ty(value);
+                let ty_name = self.ty(&ty);
+                let src = self.operand(value);
+                let mut seen = HashSet::new();
+                let mut arms = Vec::new();
+
+                // Cases that don't fit the type can't match, and only the first case for each value can.
+                for (case, target) in cases {
+                    let case = match ty.atomic() {
+                        Some(atomic) => int_literal(atomic, case),
+                        None => u32::try_from(case).ok().map(|case| case.to_string()),
+                    };
+
+                    if let Some(case) = case.filter(|case| seen.insert(case.clone())) {
+                        arms.push((case, format!("bb{}", target.0)));
+                    }
+                }
+
+                let otherwise = format!("bb{}", otherwise.0);
+                self.b.line(switch(&ty_name, &src, &otherwise, arms));
+            }
+
+            Terminator::Return(operand) => {
+                let ty = self.ty(&self.body.ret.substitute(&self.substitution));
+                let src = self.operand(operand);
+                self.b.line(format!("ret {ty} {src}"));
+            }
+
+            Terminator::Unreachable => self
+                .g
+                .trap_call(&mut self.b, "reached code that should be unreachable"),
+        }
+    }
+
+    /// Get the LLVM values of the arguments of a jump.
+    fn args(&mut self, jump: &Jump) -> Vec<String> {
+        jump.args.iter().map(|arg| self.operand(arg)).collect()
+    }
+
+    /// Generate a jump to a block, passing its arguments to the `phi` nodes of the block's parameters.
+    fn jump(&mut self, jump: &Jump) {
+        let args = self.args(jump);
+        let block = self.b.block.clone();
+        (self.incoming.entry(jump.target).or_default()).push((block, args));
+        self.b.line(format!("br label %bb{}", jump.target.0));
+    }
+}
+
+/// Get the LLVM type of an atomic type.
+const fn atomic_ty(variant: AtomicTyVariant) -> &'static str {
+    match variant {
+        AtomicTyVariant::Bool => "i1",
+        AtomicTyVariant::U8 | AtomicTyVariant::I8 => "i8",
+        AtomicTyVariant::U16 | AtomicTyVariant::I16 => "i16",
+        AtomicTyVariant::U32 | AtomicTyVariant::I32 | AtomicTyVariant::Char => "i32",
+        AtomicTyVariant::U64 | AtomicTyVariant::I64 => "i64",
+        AtomicTyVariant::F32 => "float",
+        AtomicTyVariant::F64 => "double",
+    }
+}
+
+/// Get the number of bits of an integer type.
+const fn int_bits(variant: AtomicTyVariant) -> u32 {
+    match variant {
+        AtomicTyVariant::U8 | AtomicTyVariant::I8 => 8,
+        AtomicTyVariant::U16 | AtomicTyVariant::I16 => 16,
+        AtomicTyVariant::U32 | AtomicTyVariant::I32 => 32,
+        _ => 64,
+    }
+}
+
+/// Get an LLVM literal of an integer of an atomic type, if it fits the type.
+fn int_literal(variant: AtomicTyVariant, value: &BigInt) -> Option<String> {
+    let (min, max) = variant.integer_bounds()?;
+    let value = i128::try_from(value)
+        .ok()
+        .filter(|value| (min..=max).contains(value))?;
+
+    Some(value.to_string())
+}
+
+/// Get the instruction and predicate that compare values of a type with a binary operation, if it's a comparison.
+fn compare(
+    op: BinaryOperation,
+    atomic: Option<AtomicTyVariant>,
+) -> Option<(&'static str, &'static str)> {
+    Some(match (op, compare_as(op, atomic)?) {
+        (BinaryOperation::Equal, CompareAs::Float) => ("fcmp", "oeq"),
+        (BinaryOperation::NotEqual, CompareAs::Float) => ("fcmp", "une"),
+        (BinaryOperation::Less, CompareAs::Float) => ("fcmp", "olt"),
+        (BinaryOperation::LessOrEqual, CompareAs::Float) => ("fcmp", "ole"),
+        (BinaryOperation::Greater, CompareAs::Float) => ("fcmp", "ogt"),
+        (BinaryOperation::GreaterOrEqual, CompareAs::Float) => ("fcmp", "oge"),
+        (BinaryOperation::Equal, _) => ("icmp", "eq"),
+        (BinaryOperation::NotEqual, _) => ("icmp", "ne"),
+        (BinaryOperation::Less, CompareAs::Signed) => ("icmp", "slt"),
+        (BinaryOperation::LessOrEqual, CompareAs::Signed) => ("icmp", "sle"),
+        (BinaryOperation::Greater, CompareAs::Signed) => ("icmp", "sgt"),
+        (BinaryOperation::GreaterOrEqual, CompareAs::Signed) => ("icmp", "sge"),
+        (BinaryOperation::Less, _) => ("icmp", "ult"),
+        (BinaryOperation::LessOrEqual, _) => ("icmp", "ule"),
+        (BinaryOperation::Greater, _) => ("icmp", "ugt"),
+        (BinaryOperation::GreaterOrEqual, _) => ("icmp", "uge"),
+        _ => return None,
+    })
+}
+
+/// Get a `switch` instruction on a value of a type, with the label of each case and the default label.
+fn switch<C: ToString, L: AsRef<str>>(
+    ty: &str,
+    value: &str,
+    otherwise: &str,
+    cases: impl IntoIterator<Item = (C, L)>,
+) -> String {
+    let mut inst = format!("switch {ty} {value}, label %{otherwise} [");
+
+    for (case, label) in cases {
+        _ = write!(inst, "\n    {ty} {}, label %{}", case.to_string(), label.as_ref());
+    }
+
+    inst.push_str("\n  ]");
+    inst
+}
+
+/// Get an LLVM struct type with the given fields.
+fn struct_ty(fields: &[String]) -> String {
+    match fields.is_empty() {
+        true => VOID.to_owned(),
+        false => format!("{{ {} }}", fields.join(", ")),
+    }
+}
+
+/// Get the size and alignment of a struct with fields of the given sizes and alignments.
+fn struct_layout(fields: impl IntoIterator<Item = (u64, u64)>) -> (u64, u64) {
+    let (mut size, mut align) = (0u64, 1);
+
+    for (field_size, field_align) in fields {
+        size = size.next_multiple_of(field_align) + field_size;
+        align = align.max(field_align);
+    }
+
+    (size.next_multiple_of(align), align)
+}
+
+/// Get the line and column (both starting at 1) that a span starts at.
+fn line_and_column(span: &Fragment) -> (usize, usize) {
+    let line = span.source.line_index(span.range.start);
+    let line_start = span
+        .source
+        .line_starts()
+        .get(line)
+        .copied()
+        .unwrap_or_default();
+    (line + 1, span.range.start - line_start + 1)
+}
+
+/// Escape bytes for an LLVM string (or metadata string) literal. Anything but printable ASCII is written as two hex
+/// digits after a backslash (as are quotes and backslashes).
+fn escape(bytes: &[u8]) -> String {
+    let mut escaped = String::new();
+
+    for byte in bytes {
+        match byte {
+            b'"' | b'\\' => _ = write!(escaped, "\\{byte:02X}"),
+            b' '..=b'~' => escaped.push(*byte as char),
+            _ => _ = write!(escaped, "\\{byte:02X}"),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::emit;
+    use crate::analysis::testing::lower_source;
+    use std::{fs, process::Command};
+
+    /// Check a single module and generate LLVM IR for it.
+    fn emit_source(source: &'static str) -> String {
+        let (package, program) = lower_source(source);
+        emit(&program, &package.resolution, &package.typeck, true)
+    }
+
+    /// Get the arguments `llc` needs to read opaque pointers, or [None] if there's no `llc`.
+    fn llc_args() -> Option<Vec<&'static str>> {
+        let output = Command::new("llc").arg("--version").output().ok()?;
+        let version = String::from_utf8_lossy(&output.stdout);
+        let major = (version.split("version ").nth(1))
+            .and_then(|version| version.split('.').next())
+            .and_then(|major| major.trim().parse::<u32>().ok())
+            .unwrap_or(15);
+
+        let mut args = vec!["-relocation-model=pic", "-filetype=obj"];
+
+        if major < 15 {
+            args.push("-opaque-pointers");
+        }
+
+        Some(args)
+    }
+
+    /// Generate LLVM IR for a module, compile it with `llc`, link it with the system's C compiler, and run it,
+    /// returning whether it succeeded, what it printed, and what it printed to standard error. Returns [None] if
+    /// there's no `llc` or C compiler.
+    fn run(name: &str, source: &'static str) -> Option<(bool, String, String)> {
+        let code = emit_source(source);
+
+        let Some(args) = llc_args() else {
+            eprintln!("skipping the test, since there's no `llc`");
+            return None;
+        };
+
+        let dir =
+            std::env::temp_dir().join(format!("wright-llvm-test-{name}-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let ll_file = dir.join("main.ll");
+        let object = dir.join("main.o");
+        let executable = dir.join("main");
+        fs::write(&ll_file, &code).unwrap();
+
+        let compiled = Command::new("llc")
+            .args(args)
+            .arg("-o")
+            .arg(&object)
+            .arg(&ll_file)
+            .output()
+            .unwrap();
+
+        assert!(
+            compiled.status.success(),
+            "{}\n{code}",
+            String::from_utf8_lossy(&compiled.stderr)
+        );
+
+        let Ok(linked) = Command::new("cc")
+            .arg("-o")
+            .arg(&executable)
+            .arg(&object)
+            .output()
+        else {
+            eprintln!("skipping the test, since there's no C compiler (`cc`) to link with");
+            return None;
+        };
+
+        assert!(linked.status.success(), "{}", String::from_utf8_lossy(&linked.stderr));
+
+        let output = Command::new(&executable).output().unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        Some((
+            output.status.success(),
+            String::from_utf8(output.stdout).unwrap(),
+            String::from_utf8(output.stderr).unwrap(),
+        ))
+    }
+
+    /// Run a module, which must not trap, returning what it printed.
+    fn output(name: &str, source: &'static str) -> Option<String> {
+        let (success, out, err) = run(name, source)?;
+        assert!(success, "{err}");
+        Some(out)
+    }
+    #[test]
+    fn test_arithmetic_and_calls() {
+        let out = output(
+            "arithmetic",
+            "use wright::io::println;\n\
+            func fib(n: u64) -> u64 { if n < 2 { n } else { fib(n - 1) + fib(n - 2) } }\n\
+            func main() {\n\
+                let zero: u8 = 0;\n\
+                let one: u16 = 1;\n\
+                let min: i64 = -9223372036854775808;\n\
+                println(fib(20)); println(7 / 2); println(-7 % 3); println(~zero); println(one << 15);\n\
+                println(min); println(min % -1); println(1 == 2 || 3 != 4);\n\
+            }",
+        );
+
+        if let Some(out) = out {
+            assert_eq!(out, "6765\n3\n-1\n255\n32768\n-9223372036854775808\n0\ntrue\n");
+        }
+    }
+
+    #[test]
+    fn test_loops_and_slots() {
+        let out = output(
+            "loops",
+            "use wright::io::println;\n\
+            func main() {\n\
+                var total: u64 = 0;\n\
+                for i in 1..=10 { total += i; }\n\
+                var n = 0;\n\
+                while n < 3 { n += 1; }\n\
+                println(total); println(n);\n\
+            }",
+        );
+
+        if let Some(out) = out {
+            assert_eq!(out, "55\n3\n");
+        }
+    }
+
+    #[test]
+    fn test_records_and_generics() {
+        let out = output(
+            "records",
+            "use wright::io::println;\n\
+            record Point { x: u64, y: u64 }\n\
+            record Box<T> { value: T }\n\
+            impl Point { func grow(self: @mut Self) { self.x = self.x + 1; } }\n\
+            trait Shape { func area(self: @Self) -> u64; func double(self: @Self) -> u64 { self.area() * 2 } }\n\
+            impl Shape for Point { func area(self: @Self) -> u64 { self.x * self.y } }\n\
+            func total<T: Shape>(shape: @T) -> u64 { shape.area() + shape.double() }\n\
+            func unbox<T>(b: Box<T>) -> T { b.value }\n\
+            func main() {\n\
+                var p = Point { y: 3, x: 1 };\n\
+                let q = p;\n\
+                p.grow();\n\
+                println(p); println(q.x); println(p == q); println(@p != @q);\n\
+                println(p.double()); println(total(@p));\n\
+                println(unbox(Box { value: true })); println(Box { value: p });\n\
+            }",
+        );
+
+        if let Some(out) = out {
+            assert_eq!(
+                out,
+                "Point { x: 2, y: 3 }\n1\nfalse\ntrue\n12\n18\ntrue\nBox<Point> { value: Point { x: 2, y: 3 } }\n"
+            );
+        }
+    }
+
+    #[test]
+    fn test_unions_and_enums() {
+        let out = output(
+            "unions",
+            "use wright::io::println;\n\
+            union Number { small: u8, big: u64 }\n\
+            enum Color { Red, Green, Blue }\n\
+            func size(n: Number) -> u64 { match n { Number { small: 0 } => 0, Number { big } => big, _ => 1 } }\n\
+            func code(color: Color) -> u8 { match color { Color::Red => 1, Color::Green | Color::Blue => 2 } }\n\
+            func main() {\n\
+                println(size(Number { small: 0 })); println(size(Number { small: 5 })); println(size(Number { big: 9 }));\n\
+                println(code(Color::Red)); println(code(Color::Blue)); println(Color::Green);\n\
+                println(Number { big: 4 }); println(Number { small: 4 } is u8);\n\
+                println(Number { small: 4 } == Number { small: 4 }); println(Color::Red == Color::Blue);\n\
+            }",
+        );
+
+        if let Some(out) = out {
+            assert_eq!(out, "0\n1\n9\n1\n2\nColor::Green\nNumber { big: 4 }\ntrue\ntrue\nfalse\n");
+        }
+    }
+
+    #[test]
+    fn test_closures() {
+        let out = output(
+            "closures",
+            "use wright::io::println;\n\
+            func apply(f: func(u8) -> u8, x: u8) -> u8 { f(x) }\n\
+            func double(x: u8) -> u8 { x * 2 }\n\
+            func main() {\n\
+                let offset = 3;\n\
+                var count = 0;\n\
+                let add = func(x: u8) -> u8 { count += 1; x + offset };\n\
+                println(apply(add, 4) + apply(add, 1) + count);\n\
+                println(apply(double, 5));\n\
+                let print = println;\n\
+                print(\"done\");\n\
+                println(double);\n\
+            }",
+        );
+
+        if let Some(out) = out {
+            assert_eq!(out, "13\n10\ndone\n<func double>\n");
+        }
+    }
+
+    #[test]
+    fn test_traps() {
+        let cases = [
+            ("func main() -> u8 { let x: u8 = 255; x + 1 }", "arithmetic overflow"),
+            ("func main() -> i8 { let x: i8 = -128; -x }", "arithmetic overflow"),
+            (
+                "func main() -> i64 { let x: i64 = -9223372036854775808; x / -1 }",
+                "arithmetic overflow",
+            ),
+            ("func main() -> u32 { let x: u32 = 0; 1 / x }", "division by zero"),
+            (
+                "func main() -> u8 { let x: u8 = 8; 1 << x }",
+                "shift by at least the number of bits in the type",
+            ),
+        ];
+
+        for (index, (source, message)) in cases.into_iter().enumerate() {
+            let Some((success, _, err)) = run(&format!("trap-{index}"), source) else {
+                return;
+            };
+
+            assert!(!success, "{source}");
+            assert!(err.starts_with(&format!("error: {message}, at ")), "{source}: {err}");
+        }
+    }
+
+    #[test]
+    fn test_debug_info() {
+        let source = "func main() -> u8 {\nlet x: u8 = 255;\nlet y = x + 1;\ny\n}";
+        let code = emit_source(source);
+        let call = (code.lines())
+            .find(|line| line.contains("call { i8, i1 } @llvm.uadd.with.overflow.i8("))
+            .unwrap();
+        let location = call.rsplit("!dbg !").next().unwrap();
+        let node = format!("!{location} = !DILocation(line: 3, column: ");
+        assert!(code.lines().any(|line| line.starts_with(&node)), "{code}");
+        assert!(code.contains("!DICompileUnit("));
+
+        let Some((_, _, err)) = run("lines", source) else {
+            return;
+        };
+
+        assert!(err.ends_with(":3\n"), "{err}");
+    }
+}
@@ -0,0 +1,308 @@
+; The value of expressions that don't produce one is `{}`. A string is `{ ptr, i64 }`: its bytes (which aren't
+; followed by a zero) and how many there are. A function or closure is `{ ptr, ptr, ptr }`: its code (which takes
+; the environment as its first argument), the environment (the values a closure captures), and the name it's
+; printed with.
+
+declare i32 @putchar(i32)
+declare i32 @printf(ptr, ...)
+declare i32 @dprintf(i32, ptr, ...)
+declare i32 @fflush(ptr)
+declare void @exit(i32) noreturn
+declare ptr @malloc(i64)
+declare i32 @memcmp(ptr, ptr, i64)
+declare i32 @snprintf(ptr, i64, ptr, ...)
+declare double @strtod(ptr, ptr)
+declare i32 @atoi(ptr)
+declare double @llvm.fabs.f64(double)
+
+@wr_trap_format = private unnamed_addr constant [21 x i8] c"error: %s, at %s:%d\0A\00"
+@wr_out_of_memory = private unnamed_addr constant [14 x i8] c"out of memory\00"
+@wr_string_format = private unnamed_addr constant [3 x i8] c"%s\00"
+@wr_u64_format = private unnamed_addr constant [5 x i8] c"%llu\00"
+@wr_i64_format = private unnamed_addr constant [5 x i8] c"%lld\00"
+@wr_func_format = private unnamed_addr constant [5 x i8] c"<%s>\00"
+@wr_f64_format = private unnamed_addr constant [5 x i8] c"%.*e\00"
+@wr_true = private unnamed_addr constant [5 x i8] c"true\00"
+@wr_false = private unnamed_addr constant [6 x i8] c"false\00"
+@wr_void = private unnamed_addr constant [3 x i8] c"()\00"
+@wr_nan = private unnamed_addr constant [4 x i8] c"NaN\00"
+@wr_inf = private unnamed_addr constant [4 x i8] c"inf\00"
+
+; Stop the program, because it did something the language doesn't define (like overflowing an integer).
+define internal void @wr_trap(ptr %message, ptr %file, i32 %line) cold noinline noreturn {
+  %flushed = call i32 @fflush(ptr null)
+  %printed = call i32 (i32, ptr, ...) @dprintf(i32 2, ptr @wr_trap_format, ptr %message, ptr %file, i32 %line)
+  call void @exit(i32 1)
+  unreachable
+}
+
+; Allocate memory that lives until the program ends.
+define internal ptr @wr_alloc(i64 %size, ptr %file, i32 %line) {
+entry:
+  %empty = icmp eq i64 %size, 0
+  %actual = select i1 %empty, i64 1, i64 %size
+  %memory = call ptr @malloc(i64 %actual)
+  %failed = icmp eq ptr %memory, null
+  br i1 %failed, label %trap, label %done
+
+trap:
+  call void @wr_trap(ptr @wr_out_of_memory, ptr %file, i32 %line)
+  unreachable
+
+done:
+  ret ptr %memory
+}
+
+; Strings are equal if they have the same bytes.
+define internal i1 @wr_str_eq({ ptr, i64 } %a, { ptr, i64 } %b) {
+entry:
+  %a_length = extractvalue { ptr, i64 } %a, 1
+  %b_length = extractvalue { ptr, i64 } %b, 1
+  %same_length = icmp eq i64 %a_length, %b_length
+  br i1 %same_length, label %compare, label %different
+
+compare:
+  %a_bytes = extractvalue { ptr, i64 } %a, 0
+  %b_bytes = extractvalue { ptr, i64 } %b, 0
+  %order = call i32 @memcmp(ptr %a_bytes, ptr %b_bytes, i64 %a_length)
+  %same = icmp eq i32 %order, 0
+  ret i1 %same
+
+different:
+  ret i1 false
+}
+
+; Values are printed the way `wright::io::print` prints them.
+define internal void @wr_print_str({ ptr, i64 } %value) {
+entry:
+  %bytes = extractvalue { ptr, i64 } %value, 0
+  %length = extractvalue { ptr, i64 } %value, 1
+  br label %loop
+
+loop:
+  %index = phi i64 [ 0, %entry ], [ %next, %body ]
+  %done = icmp eq i64 %index, %length
+  br i1 %done, label %end, label %body
+
+body:
+  %address = getelementptr i8, ptr %bytes, i64 %index
+  %byte = load i8, ptr %address
+  %char = zext i8 %byte to i32
+  %put = call i32 @putchar(i32 %char)
+  %next = add i64 %index, 1
+  br label %loop
+
+end:
+  ret void
+}
+
+define internal void @wr_print_u64(i64 %value) {
+  %printed = call i32 (ptr, ...) @printf(ptr @wr_u64_format, i64 %value)
+  ret void
+}
+
+define internal void @wr_print_i64(i64 %value) {
+  %printed = call i32 (ptr, ...) @printf(ptr @wr_i64_format, i64 %value)
+  ret void
+}
+
+define internal void @wr_print_bool(i1 %value) {
+  %string = select i1 %value, ptr @wr_true, ptr @wr_false
+  %printed = call i32 (ptr, ...) @printf(ptr @wr_string_format, ptr %string)
+  ret void
+}
+
+define internal void @wr_print_void({} %value) {
+  %printed = call i32 (ptr, ...) @printf(ptr @wr_string_format, ptr @wr_void)
+  ret void
+}
+
+define internal void @wr_print_func({ ptr, ptr, ptr } %value) {
+  %name = extractvalue { ptr, ptr, ptr } %value, 2
+  %printed = call i32 (ptr, ...) @printf(ptr @wr_func_format, ptr %name)
+  ret void
+}
+
+define internal void @wr_print_char(i32 %value) {
+entry:
+  %is_one = icmp ult i32 %value, 128
+  br i1 %is_one, label %one, label %check_two
+
+one:
+  %one_0 = call i32 @putchar(i32 %value)
+  ret void
+
+check_two:
+  %is_two = icmp ult i32 %value, 2048
+  br i1 %is_two, label %two, label %check_three
+
+two:
+  %two_high = lshr i32 %value, 6
+  %two_lead = or i32 %two_high, 192
+  %two_0 = call i32 @putchar(i32 %two_lead)
+  br label %last
+
+check_three:
+  %is_three = icmp ult i32 %value, 65536
+  br i1 %is_three, label %three, label %four
+
+three:
+  %three_high = lshr i32 %value, 12
+  %three_lead = or i32 %three_high, 224
+  %three_0 = call i32 @putchar(i32 %three_lead)
+  br label %second_to_last
+
+four:
+  %four_high = lshr i32 %value, 18
+  %four_lead = or i32 %four_high, 240
+  %four_0 = call i32 @putchar(i32 %four_lead)
+  %four_middle = lshr i32 %value, 12
+  %four_masked = and i32 %four_middle, 63
+  %four_next = or i32 %four_masked, 128
+  %four_1 = call i32 @putchar(i32 %four_next)
+  br label %second_to_last
+
+second_to_last:
+  %middle = lshr i32 %value, 6
+  %middle_masked = and i32 %middle, 63
+  %middle_byte = or i32 %middle_masked, 128
+  %middle_0 = call i32 @putchar(i32 %middle_byte)
+  br label %last
+
+last:
+  %low = and i32 %value, 63
+  %low_byte = or i32 %low, 128
+  %last_0 = call i32 @putchar(i32 %low_byte)
+  ret void
+}
+
+; Print a number of zeros.
+define internal void @wr_print_zeros(i64 %count) {
+entry:
+  br label %loop
+
+loop:
+  %index = phi i64 [ 0, %entry ], [ %next, %body ]
+  %done = icmp sge i64 %index, %count
+  br i1 %done, label %end, label %body
+
+body:
+  %put = call i32 @putchar(i32 48)
+  %next = add i64 %index, 1
+  br label %loop
+
+end:
+  ret void
+}
+
+; Floating point numbers are printed with the fewest digits that read back as the same number, without an
+; exponent.
+define internal void @wr_print_f64(double %value) {
+entry:
+  %buffer = alloca [32 x i8]
+  %digits = alloca [20 x i8]
+  %is_nan = fcmp uno double %value, %value
+  br i1 %is_nan, label %nan, label %sign
+
+nan:
+  %nan_0 = call i32 (ptr, ...) @printf(ptr @wr_string_format, ptr @wr_nan)
+  ret void
+
+sign:
+  %bits = bitcast double %value to i64
+  %is_negative = icmp slt i64 %bits, 0
+  br i1 %is_negative, label %minus, label %magnitude
+
+minus:
+  %minus_0 = call i32 @putchar(i32 45)
+  br label %magnitude
+
+magnitude:
+  %absolute = call double @llvm.fabs.f64(double %value)
+  %is_inf = fcmp oeq double %absolute, 0x7FF0000000000000
+  br i1 %is_inf, label %inf, label %check_zero
+
+inf:
+  %inf_0 = call i32 (ptr, ...) @printf(ptr @wr_string_format, ptr @wr_inf)
+  ret void
+
+check_zero:
+  %is_zero = fcmp oeq double %absolute, 0.0
+  br i1 %is_zero, label %zero, label %search
+
+zero:
+  %zero_0 = call i32 @putchar(i32 48)
+  ret void
+
+search:
+  %precision = phi i32 [ 1, %check_zero ], [ %next_precision, %search ]
+  %decimals = sub i32 %precision, 1
+  %formatted = call i32 (ptr, i64, ptr, ...) @snprintf(ptr %buffer, i64 32, ptr @wr_f64_format, i32 %decimals, double %absolute)
+  %parsed = call double @strtod(ptr %buffer, ptr null)
+  %is_exact = fcmp oeq double %parsed, %absolute
+  %is_last = icmp eq i32 %precision, 17
+  %is_found = or i1 %is_exact, %is_last
+  %next_precision = add i32 %precision, 1
+  br i1 %is_found, label %collect, label %search
+
+collect:
+  %index = phi i64 [ 0, %search ], [ %next_index, %collect_digit ]
+  %count = phi i64 [ 0, %search ], [ %next_count, %collect_digit ]
+  %char_address = getelementptr i8, ptr %buffer, i64 %index
+  %char = load i8, ptr %char_address
+  %is_exponent = icmp eq i8 %char, 101
+  br i1 %is_exponent, label %read_exponent, label %collect_digit
+
+collect_digit:
+  ; The decimal point is overwritten by the next digit.
+  %digit_address = getelementptr i8, ptr %digits, i64 %count
+  store i8 %char, ptr %digit_address
+  %is_point = icmp eq i8 %char, 46
+  %step = select i1 %is_point, i64 0, i64 1
+  %next_count = add i64 %count, %step
+  %next_index = add i64 %index, 1
+  br label %collect
+
+read_exponent:
+  %exponent_address = getelementptr i8, ptr %char_address, i64 1
+  %exponent_i32 = call i32 @atoi(ptr %exponent_address)
+  %exponent = sext i32 %exponent_i32 to i64
+  %is_fraction = icmp slt i64 %exponent, 0
+  br i1 %is_fraction, label %fraction, label %check_whole
+
+fraction:
+  %fraction_0 = call i32 @putchar(i32 48)
+  %fraction_1 = call i32 @putchar(i32 46)
+  %negated = sub i64 0, %exponent
+  %leading_zeros = sub i64 %negated, 1
+  call void @wr_print_zeros(i64 %leading_zeros)
+  %fraction_digits = insertvalue { ptr, i64 } undef, ptr %digits, 0
+  %fraction_string = insertvalue { ptr, i64 } %fraction_digits, i64 %count, 1
+  call void @wr_print_str({ ptr, i64 } %fraction_string)
+  ret void
+
+check_whole:
+  %integer_digits = add i64 %exponent, 1
+  %is_whole = icmp sge i64 %integer_digits, %count
+  br i1 %is_whole, label %whole, label %decimal
+
+whole:
+  %whole_digits = insertvalue { ptr, i64 } undef, ptr %digits, 0
+  %whole_string = insertvalue { ptr, i64 } %whole_digits, i64 %count, 1
+  call void @wr_print_str({ ptr, i64 } %whole_string)
+  %trailing_zeros = sub i64 %integer_digits, %count
+  call void @wr_print_zeros(i64 %trailing_zeros)
+  ret void
+
+decimal:
+  %integer_start = insertvalue { ptr, i64 } undef, ptr %digits, 0
+  %integer_string = insertvalue { ptr, i64 } %integer_start, i64 %integer_digits, 1
+  call void @wr_print_str({ ptr, i64 } %integer_string)
+  %point = call i32 @putchar(i32 46)
+  %fraction_address = getelementptr i8, ptr %digits, i64 %integer_digits
+  %fraction_count = sub i64 %count, %integer_digits
+  %decimal_start = insertvalue { ptr, i64 } undef, ptr %fraction_address, 0
+  %decimal_string = insertvalue { ptr, i64 } %decimal_start, i64 %fraction_count, 1
+  call void @wr_print_str({ ptr, i64 } %decimal_string)
+  ret void
+}
@@ -0,0 +1,2844 @@
+//! Generating binary WebAssembly modules from the MIR.
+//!
+//! A program becomes a single `.wasm` module, which exports its linear memory and a `main` function that runs the
+//! program's `main` function (if it has one). Printing and trapping are imported from the host: the builtins of
+//! `wright::io` print through functions of the `wright::io` module (`print_str`, `print_i64`, `print_u64`,
+//! `print_f64`, `print_char`, and `print_bool`), and the `wright::runtime` module provides `trap` (which gets the
+//! message, file name, and line, and must not return) and `fmod`, since WebAssembly has no remainder of floats.
+//!
+//! Every value is a list of WebAssembly values, one for each of the scalars it's made of: `bool`, `char`, and the
+//! integers of 32 bits or less are an `i32` (sign-extended if they're signed, and zero-extended otherwise), 64-bit
+//! integers are an `i64`, and floats are an `f32` or `f64`. References are an `i32` address, strings are an address
+//! and a length, function values (including closures) are an index into the module's table, an environment, and the
+//! address of their name, records are their fields one after another, enums are the `i32` index of their variant,
+//! and unions are an `i32` tag followed by enough 64-bit words to hold any of their variants. Functions return every
+//! scalar of their result, since WebAssembly functions can return more than one value. Like the other backends, it
+//! doesn't use the layouts of [`analysis::layout`](crate::analysis::layout) yet.
+//!
+//! Linear memory starts with the string constants, followed by a stack that grows down (where slots live, which
+//! traps with a stack overflow when it's full) and a heap that closures' environments are allocated from, which
+//! grows the memory as needed. Blocks of the MIR are the cases of a `br_table` in a loop, and their parameters are
+//! locals that a jump sets before it goes back to the top. Integer overflow, division by zero, and the like stop
+//! the program like they do in the [VM](crate::vm), and values are printed the same way. Unless debug info is turned
+//! off, a `name` section names every function.
+
+use super::{
+    Access, Callee, CompareAs, Context, Helper, Instance, Instances, Layouts, NamedKind,
+    SHIFT_TRAP, Thunk, UNION_PAYLOAD, VARIANT_TRAP, compare_as, constant_trap, mangle,
+    split_captures, strip_one_reference, unique_name,
+};
+use crate::{
+    analysis::{
+        builtins::Builtin,
+        resolve::{DefId, Resolution},
+        typeck::{TypeckResults, ty::Ty},
+    },
+    ast::{
+        expr::{BinaryOperation, UnaryOperation},
+        ty::AtomicTyVariant,
+    },
+    mir::{
+        BlockId, Body, Const, Inst, Jump, Operand, Place, PlaceBase, Program, Projection, Rvalue,
+        Terminator, Value,
+    },
+    source_tracking::fragment::Fragment,
+};
+use encode::{Code, ValType, op};
+use std::collections::{HashMap, HashSet};
+
+mod encode;
+
+/// The address of the first string constant, which leaves the null address unused.
+const DATA_START: u32 = 16;
+
+/// The size of the stack, in bytes.
+const STACK_SIZE: u32 = 1 << 20;
+
+/// The size of a page of linear memory, in bytes.
+const PAGE_SIZE: u32 = 1 << 16;
+
+/// The global holding the address of the top of the stack.
+const STACK_POINTER: u32 = 0;
+
+/// The global holding the address of the next free byte of the heap.
+const HEAP_POINTER: u32 = 1;
+
+/// The (immutable) global holding the lowest address the stack can grow down to.
+const STACK_LIMIT: u32 = 2;
+
+/// A function imported from the host: its module, name, and the types of its parameters and results.
+type Import = (&'static str, &'static str, &'static [ValType], &'static [ValType]);
+
+/// The functions imported from the host, whose indices come before every function of the module.
+const IMPORTS: [Import; 8] = [
+    ("wright::io", "print_str", &[ValType::I32, ValType::I32], &[]),
+    ("wright::io", "print_i64", &[ValType::I64], &[]),
+    ("wright::io", "print_u64", &[ValType::I64], &[]),
+    ("wright::io", "print_f64", &[ValType::F64], &[]),
+    ("wright::io", "print_char", &[ValType::I32], &[]),
+    ("wright::io", "print_bool", &[ValType::I32], &[]),
+    ("wright::runtime", "trap", &[ValType::I32; 5], &[]),
+    ("wright::runtime", "fmod", &[ValType::F64, ValType::F64], &[ValType::F64]),
+];
+
+/// Generate a WebAssembly module from every body of a program (which must have been checked without errors), which
+/// exports a `main` function that runs the program's `main` function if it has one. Every function is named in a
+/// `name` section, unless `debug_info` is false.
+pub fn emit(
+    program: &Program,
+    resolution: &Resolution,
+    typeck: &TypeckResults,
+    debug_info: bool,
+) -> Vec<u8> {
+    let cx = Context {
+        program,
+        resolution,
+        typeck,
+    };
+
+    let mut generator = Generator::new(cx);
+    let entry = generator.generate();
+    generator.finish(entry, debug_info)
+}
+
+/// How a scalar is stored in memory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Scalar {
+    /// A signed byte.
+    I8,
+    /// An unsigned byte (which is also a `bool`).
+    U8,
+    /// A signed 16-bit integer.
+    I16,
+    /// An unsigned 16-bit integer.
+    U16,
+    /// A 32-bit integer (signed or not), a `char`, or an address.
+    I32,
+    /// A 64-bit integer (signed or not).
+    I64,
+    /// A 32-bit float.
+    F32,
+    /// A 64-bit float.
+    F64,
+}
+
+impl Scalar {
+    /// The type of the WebAssembly value the scalar is.
+    const fn val_type(self) -> ValType {
+        match self {
+            Scalar::I8 | Scalar::U8 | Scalar::I16 | Scalar::U16 | Scalar::I32 => ValType::I32,
+            Scalar::I64 => ValType::I64,
+            Scalar::F32 => ValType::F32,
+            Scalar::F64 => ValType::F64,
+        }
+    }
+
+    /// The size of the scalar in memory, in bytes (which is also its alignment).
+    const fn size(self) -> u32 {
+        match self {
+            Scalar::I8 | Scalar::U8 => 1,
+            Scalar::I16 | Scalar::U16 => 2,
+            Scalar::I32 | Scalar::F32 => 4,
+            Scalar::I64 | Scalar::F64 => 8,
+        }
+    }
+
+    /// The instruction that loads the scalar.
+    const fn load(self) -> u8 {
+        match self {
+            Scalar::I8 => op::I32_LOAD8_S,
+            Scalar::U8 => op::I32_LOAD8_U,
+            Scalar::I16 => op::I32_LOAD16_S,
+            Scalar::U16 => op::I32_LOAD16_U,
+            Scalar::I32 => op::I32_LOAD,
+            Scalar::I64 => op::I64_LOAD,
+            Scalar::F32 => op::F32_LOAD,
+            Scalar::F64 => op::F64_LOAD,
+        }
+    }
+
+    /// The instruction that stores the scalar.
+    const fn store(self) -> u8 {
+        match self {
+            Scalar::I8 | Scalar::U8 => op::I32_STORE8,
+            Scalar::I16 | Scalar::U16 => op::I32_STORE16,
+            Scalar::I32 => op::I32_STORE,
+            Scalar::I64 => op::I64_STORE,
+            Scalar::F32 => op::F32_STORE,
+            Scalar::F64 => op::F64_STORE,
+        }
+    }
+}
+
+/// A function that the generated code needs, which the host doesn't provide.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Runtime {
+    /// Allocating memory on the heap, growing the memory if it's full.
+    Alloc,
+    /// Checking if two strings are equal.
+    StrEq,
+}
+
+/// A function that's been declared but not generated yet.
+#[derive(Debug)]
+enum Pending {
+    /// A helper function for a type.
+    Helper(Helper, Ty, u32),
+    /// A thunk.
+    Thunk(Thunk, u32),
+    /// A runtime function.
+    Runtime(Runtime, u32),
+}
+
+/// The types of the parameters and results of a function.
+type FuncType = (Vec<ValType>, Vec<ValType>);
+
+/// A function of the module.
+#[derive(Debug)]
+struct Function {
+    /// The name, for the `name` section.
+    name: String,
+    /// The index of its type.
+    ty: u32,
+    /// The encoded locals and code, once it's been generated.
+    body: Vec<u8>,
+}
+
+/// The state of generating a module.
+struct Generator<'a> {
+    /// The program being generated.
+    cx: Context<'a>,
+    /// The instances of bodies, with the names of their functions.
+    instances: Instances,
+    /// The index of every function that's been declared for an instance, by name.
+    functions_by_name: HashMap<String, u32>,
+    /// Every name given to a function so far.
+    names: HashSet<String>,
+    /// Every function type, without duplicates.
+    types: Vec<FuncType>,
+    /// The index of every function type.
+    type_indices: HashMap<FuncType, u32>,
+    /// Every function of the module (which come after the imports).
+    functions: Vec<Function>,
+    /// The layout of every type that's been used.
+    layouts: Layouts<'a, Scalar>,
+    /// Every helper function.
+    helpers: HashMap<(Helper, Ty), u32>,
+    /// Every thunk.
+    thunks: HashMap<Thunk, u32>,
+    /// Every runtime function.
+    runtime: HashMap<Runtime, u32>,
+    /// The helpers, thunks, and runtime functions that haven't been generated yet.
+    pending: Vec<Pending>,
+    /// How many values each closure body captures, by the index of the body.
+    closures: HashMap<usize, usize>,
+    /// The functions in the table, which function values hold the index of.
+    table: Vec<u32>,
+    /// The index in the table of every function in it.
+    table_indices: HashMap<u32, u32>,
+    /// The string constants, which start at [DATA_START].
+    data: Vec<u8>,
+    /// The address of every string constant.
+    strings: HashMap<String, u32>,
+    /// The address of every name of a function value, which is stored after its length.
+    labels: HashMap<String, u32>,
+}
+
+impl<'a> Generator<'a> {
+    /// Start generating a program.
+    fn new(cx: Context<'a>) -> Self {
+        let mut generator = Generator {
+            cx,
+            instances: Instances::default(),
+            functions_by_name: HashMap::new(),
+            names: HashSet::from(["main".to_owned()]),
+            types: Vec::new(),
+            type_indices: HashMap::new(),
+            functions: Vec::new(),
+            layouts: Layouts::new(cx, Scalar::I32),
+            helpers: HashMap::new(),
+            thunks: HashMap::new(),
+            runtime: HashMap::new(),
+            pending: Vec::new(),
+            closures: HashMap::new(),
+            table: Vec::new(),
+            table_indices: HashMap::new(),
+            data: Vec::new(),
+            strings: HashMap::new(),
+            labels: HashMap::new(),
+        };
+
+        for (_, _, params, results) in IMPORTS {
+            generator.func_type(params.to_vec(), results.to_vec());
+        }
+
+        generator
+    }
+
+    /// Generate every function of the program, and a `main` function that runs the program's `main` function (if
+    /// it has one), returning the index of the `main` function.
+    fn generate(&mut self) -> Option<u32> {
+        let mut entry = None;
+
+        for instance in self.cx.roots().collect::<Vec<_>>() {
+            let body = &self.cx.program.bodies[instance.body];
+            let is_entry = body.name == "main" && body.params.is_empty();
+            let function = self.instance_function(instance);
+
+            if is_entry {
+                entry = Some(function);
+            }
+        }
+
+        let main = entry.map(|function| {
+            let ty = self.func_type(Vec::new(), Vec::new());
+            let main = self.declare("main".to_owned(), ty);
+
+            self.define(main, |e| {
+                let results = e.call(function, &[]);
+
+                // The program's `main` function can return anything, which the host doesn't need.
+                for result in results {
+                    e.code.get(result);
+                    e.code.op(op::DROP);
+                }
+
+                e.ret(&[]);
+            });
+
+            main
+        });
+
+        loop {
+            if let Some((instance, name)) = self.instances.next() {
+                let function = self.functions_by_name[&name];
+                self.define(function, |e| FunctionGenerator::new(e, instance).generate());
+            } else if let Some(pending) = self.pending.pop() {
+                match pending {
+                    Pending::Helper(helper, ty, function) => {
+                        self.define(function, |e| e.generate_helper(helper, &ty))
+                    }
+
+                    Pending::Thunk(thunk, function) => {
+                        self.define(function, |e| e.generate_thunk(&thunk))
+                    }
+
+                    Pending::Runtime(runtime, function) => {
+                        self.define(function, |e| e.generate_runtime(runtime))
+                    }
+                }
+            } else {
+                break;
+            }
+        }
+
+        main
+    }
+
+    /// Get the index of a function type, adding it if it's new.
+    fn func_type(&mut self, params: Vec<ValType>, results: Vec<ValType>) -> u32 {
+        let ty = (params, results);
+
+        if let Some(index) = self.type_indices.get(&ty) {
+            return *index;
+        }
+
+        let index = self.types.len() as u32;
+        self.types.push(ty.clone());
+        self.type_indices.insert(ty, index);
+        index
+    }
+
+    /// Get the type of a function (imported or not).
+    fn type_of(&self, function: u32) -> &FuncType {
+        let ty = match (function as usize).checked_sub(IMPORTS.len()) {
+            Some(index) => self.functions[index].ty,
+            // The types of the imports were added first, in order.
+            None => {
+                self.type_indices[&(
+                    IMPORTS[function as usize].2.to_vec(),
+                    IMPORTS[function as usize].3.to_vec(),
+                )]
+            }
+        };
+
+        &self.types[ty as usize]
+    }
+
+    /// Get the index of an imported function.
+    fn import(&self, name: &str) -> u32 {
+        (IMPORTS.iter())
+            .position(|(_, import, ..)| *import == name)
+            .expect("every import is listed") as u32
+    }
+
+    /// Declare a function with a name that's made unique, returning its index.
+    fn declare(&mut self, name: String, ty: u32) -> u32 {
+        let name = unique_name(&mut self.names, name);
+
+        self.functions.push(Function {
+            name,
+            ty,
+            body: Vec::new(),
+        });
+
+        (IMPORTS.len() + self.functions.len() - 1) as u32
+    }
+
+    /// Generate the code of a declared function.
+    fn define(&mut self, function: u32, generate: impl FnOnce(&mut Emitter<'_, 'a>)) {
+        let count = self.type_of(function).0.len() as u32;
+
+        let mut emitter = Emitter {
+            g: self,
+            code: Code::default(),
+            params: count,
+            locals: Vec::new(),
+            frame: 0,
+            saved: count,
+            frame_pointer: count + 1,
+            location: (String::new(), 0),
+            entry_location: None,
+        };
+
+        // The first two locals hold the stack pointer from before the call, and the address of the frame.
+        emitter.locals.extend([ValType::I32, ValType::I32]);
+        generate(&mut emitter);
+
+        let body = emitter.finish();
+        self.functions[function as usize - IMPORTS.len()].body = body;
+    }
+
+    /// Get the address of a string constant, adding it if it's new.
+    fn string(&mut self, string: &str) -> u32 {
+        if let Some(address) = self.strings.get(string) {
+            return *address;
+        }
+
+        let address = DATA_START + self.data.len() as u32;
+        self.data.extend_from_slice(string.as_bytes());
+        self.strings.insert(string.to_owned(), address);
+        address
+    }
+
+    /// Get the address of the name of a function value (which its length is stored just before), adding it if it's
+    /// new.
+    fn label(&mut self, label: &str) -> u32 {
+        if let Some(address) = self.labels.get(label) {
+            return *address;
+        }
+
+        let text = format!("<{label}>");
+        let padding = self.data.len().next_multiple_of(4) - self.data.len();
+        self.data.extend(std::iter::repeat_n(0, padding));
+        self.data.extend((text.len() as u32).to_le_bytes());
+
+        let address = DATA_START + self.data.len() as u32;
+        self.data.extend_from_slice(text.as_bytes());
+        self.labels.insert(label.to_owned(), address);
+        address
+    }
+
+    /// Get the index of a function in the table, adding it if it's new.
+    fn element(&mut self, function: u32) -> u32 {
+        if let Some(index) = self.table_indices.get(&function) {
+            return *index;
+        }
+
+        let index = self.table.len() as u32;
+        self.table.push(function);
+        self.table_indices.insert(function, index);
+        index
+    }
+
+    /// Get the function of an instance, declaring it if it's new.
+    fn instance_function(&mut self, instance: Instance) -> u32 {
+        let body = &self.cx.program.bodies[instance.body];
+        let mut name = mangle(&body.name);
+
+        for (_, ty) in &instance.args {
+            name.push('_');
+            name.push_str(&mangle(&ty.display(self.cx.resolution).to_string()));
+        }
+
+        let Generator {
+            instances, names, ..
+        } = self;
+
+        let name = (instances.get(instance.clone(), || unique_name(names, name))).to_owned();
+
+        if let Some(function) = self.functions_by_name.get(&name) {
+            return *function;
+        }
+
+        let substitution = instance.substitution();
+        let captures = self.closures.get(&instance.body).copied();
+        let (_, params) = split_captures(body, captures);
+
+        let params: Vec<Ty> = (params.iter())
+            .map(|param| body.value_ty(*param).substitute(&substitution))
+            .collect();
+
+        let ret = body.ret.substitute(&substitution);
+        let ty = self.signature(&params, &ret, captures.is_some());
+
+        // The name is already unique, so it's kept as it is.
+        self.functions.push(Function {
+            name: name.clone(),
+            ty,
+            body: Vec::new(),
+        });
+
+        let function = (IMPORTS.len() + self.functions.len() - 1) as u32;
+        self.functions_by_name.insert(name, function);
+        function
+    }
+
+    /// Get the type of a function with parameters and a result of the given types, which returns every scalar of
+    /// its result. Functions with an environment (the code of function values) take it before their parameters.
+    fn signature(&mut self, params: &[Ty], ret: &Ty, env: bool) -> u32 {
+        let mut param_types = Vec::new();
+
+        if env {
+            param_types.push(ValType::I32);
+        }
+
+        for param in params {
+            param_types.extend(self.val_types(param));
+        }
+
+        let results = self.val_types(ret);
+        self.func_type(param_types, results)
+    }
+
+    /// Get the types of the WebAssembly values of a type.
+    fn val_types(&mut self, ty: &Ty) -> Vec<ValType> {
+        let layout = self.layouts.layout(ty);
+        layout
+            .parts
+            .iter()
+            .map(|(_, scalar)| scalar.val_type())
+            .collect()
+    }
+
+    /// Get the helper function for a type, declaring it if it's new.
+    fn helper(&mut self, helper: Helper, ty: &Ty) -> u32 {
+        if let Some(function) = self.helpers.get(&(helper, ty.clone())) {
+            return *function;
+        }
+
+        let params = self.val_types(ty);
+
+        let (prefix, ty_index) = match helper {
+            Helper::Print => ("print", self.func_type(params, Vec::new())),
+            Helper::Eq => {
+                ("eq", self.func_type([params.clone(), params].concat(), vec![ValType::I32]))
+            }
+        };
+
+        let display = ty.display(self.cx.resolution).to_string();
+        let function = self.declare(format!("{prefix}_{}", mangle(&display)), ty_index);
+
+        self.helpers.insert((helper, ty.clone()), function);
+        self.pending
+            .push(Pending::Helper(helper, ty.clone(), function));
+        function
+    }
+
+    /// Get the function that a function value runs, declaring it if it's new, and what the value is printed as.
+    fn thunk(&mut self, thunk: Thunk) -> (u32, String) {
+        let (name, label, params, ret) = match &thunk {
+            Thunk::Instance(instance) => {
+                let body = &self.cx.program.bodies[instance.body];
+                let substitution = instance.substitution();
+
+                let params: Vec<Ty> = (body.params.iter())
+                    .map(|param| body.value_ty(*param).substitute(&substitution))
+                    .collect();
+
+                let ret = body.ret.substitute(&substitution);
+                let name = format!("{}_value", mangle(&body.name));
+                (name, format!("func {}", body.name), params, ret)
+            }
+
+            Thunk::Builtin(builtin, ty) => {
+                let params = match ty {
+                    Ty::Function { params, .. } => params.clone(),
+                    _ => Vec::new(),
+                };
+
+                let name = format!("{}_value", builtin.name());
+                (name, format!("func wright::io::{}", builtin.name()), params, Ty::Void)
+            }
+        };
+
+        if let Some(function) = self.thunks.get(&thunk) {
+            return (*function, label);
+        }
+
+        let ty = self.signature(&params, &ret, true);
+        let function = self.declare(name, ty);
+        self.thunks.insert(thunk.clone(), function);
+        self.pending.push(Pending::Thunk(thunk, function));
+        (function, label)
+    }
+
+    /// Get a runtime function, declaring it if it's new.
+    fn runtime(&mut self, runtime: Runtime) -> u32 {
+        if let Some(function) = self.runtime.get(&runtime) {
+            return *function;
+        }
+
+        let (name, ty) = match runtime {
+            Runtime::Alloc => ("alloc", self.func_type(vec![ValType::I32], vec![ValType::I32])),
+            Runtime::StrEq => ("str_eq", self.func_type(vec![ValType::I32; 4], vec![ValType::I32])),
+        };
+
+        let function = self.declare(name.to_owned(), ty);
+        self.runtime.insert(runtime, function);
+        self.pending.push(Pending::Runtime(runtime, function));
+        function
+    }
+
+    /// Encode the module, with a `main` function to export (if there is one).
+    fn finish(self, main: Option<u32>, debug_info: bool) -> Vec<u8> {
+        let mut out = b"\0asm".to_vec();
+        out.extend(1u32.to_le_bytes());
+
+        // Linear memory holds the string constants, then the stack (which grows down), then the heap.
+        let stack_limit = (DATA_START + self.data.len() as u32).next_multiple_of(16);
+        let stack_top = stack_limit + STACK_SIZE;
+        let pages = stack_top.div_ceil(PAGE_SIZE);
+
+        let mut section = Vec::new();
+        encode::u32(&mut section, self.types.len() as u32);
+
+        for (params, results) in &self.types {
+            section.push(0x60);
+
+            for types in [params, results] {
+                encode::u32(&mut section, types.len() as u32);
+                section.extend(types.iter().map(|ty| ty.byte()));
+            }
+        }
+
+        encode::section(&mut out, 1, &section);
+
+        let mut section = Vec::new();
+        encode::u32(&mut section, IMPORTS.len() as u32);
+
+        for (index, (module, name, ..)) in IMPORTS.iter().enumerate() {
+            encode::name(&mut section, module);
+            encode::name(&mut section, name);
+            section.push(0x00);
+            let ty = self.type_of(index as u32);
+            encode::u32(&mut section, self.type_indices[ty]);
+        }
+
+        encode::section(&mut out, 2, &section);
+
+        let mut section = Vec::new();
+        encode::u32(&mut section, self.functions.len() as u32);
+
+        for function in &self.functions {
+            encode::u32(&mut section, function.ty);
+        }
+
+        encode::section(&mut out, 3, &section);
+
+        let mut section = vec![1, 0x70, 0x00];
+        encode::u32(&mut section, self.table.len() as u32);
+        encode::section(&mut out, 4, &section);
+
+        let mut section = vec![1, 0x00];
+        encode::u32(&mut section, pages);
+        encode::section(&mut out, 5, &section);
+
+        let mut section = vec![3];
+
+        for (mutable, value) in [(true, stack_top), (true, stack_top), (false, stack_limit)] {
+            section.extend([ValType::I32.byte(), mutable as u8]);
+            let mut init = Code::default();
+            init.i32_const(value as i32);
+            init.op(op::END);
+            section.extend(init.bytes);
+        }
+
+        encode::section(&mut out, 6, &section);
+
+        let mut section = Vec::new();
+        let exports = [("memory", 0x02, Some(0)), ("main", 0x00, main)];
+        let exports: Vec<_> = (exports.into_iter())
+            .filter_map(|(name, kind, index)| Some((name, kind, index?)))
+            .collect();
+        encode::u32(&mut section, exports.len() as u32);
+
+        for (name, kind, index) in exports {
+            encode::name(&mut section, name);
+            section.push(kind);
+            encode::u32(&mut section, index);
+        }
+
+        encode::section(&mut out, 7, &section);
+
+        if !self.table.is_empty() {
+            let mut section = vec![1, 0x00];
+            let mut offset = Code::default();
+            offset.i32_const(0);
+            offset.op(op::END);
+            section.extend(offset.bytes);
+            encode::u32(&mut section, self.table.len() as u32);
+
+            for function in &self.table {
+                encode::u32(&mut section, *function);
+            }
+
+            encode::section(&mut out, 9, &section);
+        }
+
+        let mut section = Vec::new();
+        encode::u32(&mut section, self.functions.len() as u32);
+
+        for function in &self.functions {
+            encode::u32(&mut section, function.body.len() as u32);
+            section.extend_from_slice(&function.body);
+        }
+
+        encode::section(&mut out, 10, &section);
+
+        let mut section = vec![1, 0x00];
+        let mut offset = Code::default();
+        offset.i32_const(DATA_START as i32);
+        offset.op(op::END);
+        section.extend(offset.bytes);
+        encode::u32(&mut section, self.data.len() as u32);
+        section.extend_from_slice(&self.data);
+        encode::section(&mut out, 11, &section);
+
+        if debug_info {
+            let mut section = Vec::new();
+            encode::name(&mut section, "name");
+
+            let mut names = Vec::new();
+            encode::u32(&mut names, (IMPORTS.len() + self.functions.len()) as u32);
+
+            let imports = IMPORTS
+                .iter()
+                .map(|(module, name, ..)| format!("{module}::{name}"));
+            let functions = self.functions.iter().map(|function| function.name.clone());
+
+            for (index, name) in imports.chain(functions).enumerate() {
+                encode::u32(&mut names, index as u32);
+                encode::name(&mut names, &name);
+            }
+
+            encode::section(&mut section, 1, &names);
+            encode::section(&mut out, 0, &section);
+        }
+
+        out
+    }
+}
+
+/// An address in linear memory: the value of a local plus a constant offset.
+#[derive(Clone, Copy, Debug)]
+struct Address {
+    /// The local holding the base address.
+    base: u32,
+    /// The offset from the base address.
+    offset: u32,
+}
+
+/// The state of generating the code of one function.
+struct Emitter<'g, 'a> {
+    /// The generator of the module.
+    g: &'g mut Generator<'a>,
+    /// The code, after the prologue (which is added once the size of the frame is known).
+    code: Code,
+    /// How many parameters the function has (which are its first locals).
+    params: u32,
+    /// The types of the locals after the parameters.
+    locals: Vec<ValType>,
+    /// The size of the function's frame on the stack.
+    frame: u32,
+    /// The local holding the stack pointer from before the call, which is restored when the function returns.
+    saved: u32,
+    /// The local holding the address of the frame.
+    frame_pointer: u32,
+    /// The file and line that traps are reported at.
+    location: (String, u32),
+    /// The location of the start of the function, where a stack overflow is reported.
+    entry_location: Option<(String, u32)>,
+}
+
+impl Emitter<'_, '_> {
+    /// Finish the function, returning its encoded locals and code.
+    fn finish(mut self) -> Vec<u8> {
+        let code = std::mem::take(&mut self.code);
+        self.frame = self.frame.next_multiple_of(8);
+        self.location = self.entry_location.take().unwrap_or_default();
+
+        self.code.indexed(op::GLOBAL_GET, STACK_POINTER);
+        self.code.indexed(op::LOCAL_TEE, self.saved);
+        self.code.i32_const(self.frame as i32);
+        self.code.op(op::I32_SUB);
+        self.code.indexed(op::LOCAL_TEE, self.frame_pointer);
+        self.code.indexed(op::GLOBAL_SET, STACK_POINTER);
+
+        if self.frame > 0 {
+            self.code.get(self.frame_pointer);
+            self.code.indexed(op::GLOBAL_GET, STACK_LIMIT);
+            self.code.op(op::I32_LT_U);
+            self.trap_if("stack overflow");
+        }
+
+        self.code.bytes.extend(code.bytes);
+        self.code.op(op::END);
+
+        // Locals are declared in runs of the same type.
+        let mut runs: Vec<(u32, ValType)> = Vec::new();
+
+        for ty in &self.locals {
+            match runs.last_mut() {
+                Some((count, last)) if last == ty => *count += 1,
+                _ => runs.push((1, *ty)),
+            }
+        }
+
+        let mut body = Vec::new();
+        encode::u32(&mut body, runs.len() as u32);
+
+        for (count, ty) in runs {
+            encode::u32(&mut body, count);
+            body.push(ty.byte());
+        }
+
+        body.extend(self.code.bytes);
+        body
+    }
+
+    /// Add a local of a type, returning its index.
+    fn local(&mut self, ty: ValType) -> u32 {
+        self.locals.push(ty);
+        self.params + self.locals.len() as u32 - 1
+    }
+
+    /// Add a local of a type, and set it to the value on top of the stack.
+    fn set_new(&mut self, ty: ValType) -> u32 {
+        let local = self.local(ty);
+        self.code.set(local);
+        local
+    }
+
+    /// Get the locals that hold the zero of every scalar of a type, which are new (so they're never set).
+    fn zeros(&mut self, ty: &Ty) -> Vec<u32> {
+        let types = self.g.val_types(ty);
+        types.into_iter().map(|ty| self.local(ty)).collect()
+    }
+
+    /// Get a local holding an integer constant of a scalar type, given as a (signed or unsigned) number that fits
+    /// it.
+    fn iconst(&mut self, scalar: Scalar, value: i128) -> u32 {
+        match scalar.val_type() {
+            ValType::I64 => {
+                self.code.i64_const(value as i64);
+                self.set_new(ValType::I64)
+            }
+
+            _ => {
+                self.code.i32_const(value as i32);
+                self.set_new(ValType::I32)
+            }
+        }
+    }
+
+    /// Make memory in the function's frame for a value of a type, returning its address.
+    fn stack(&mut self, ty: &Ty) -> Address {
+        let layout = self.g.layouts.layout(ty);
+        let offset = self.frame.next_multiple_of(layout.align.max(1));
+        self.frame = offset + layout.size;
+
+        Address {
+            base: self.frame_pointer,
+            offset,
+        }
+    }
+
+    /// Load a value of a type from memory.
+    fn load(&mut self, ty: &Ty, address: Address) -> Vec<u32> {
+        let layout = self.g.layouts.layout(ty);
+
+        (layout.parts.iter())
+            .map(|(offset, scalar)| {
+                self.code.get(address.base);
+                let align = scalar.size().trailing_zeros();
+                self.code
+                    .memory(scalar.load(), align, address.offset + offset);
+                self.set_new(scalar.val_type())
+            })
+            .collect()
+    }
+
+    /// Store a value of a type to memory.
+    fn store(&mut self, ty: &Ty, value: &[u32], address: Address) {
+        let layout = self.g.layouts.layout(ty);
+
+        for ((offset, scalar), value) in layout.parts.iter().zip(value) {
+            self.code.get(address.base);
+            self.code.get(*value);
+            let align = scalar.size().trailing_zeros();
+            self.code
+                .memory(scalar.store(), align, address.offset + offset);
+        }
+    }
+
+    /// Load the `i32` at an address onto the stack.
+    fn load_i32(&mut self, address: Address) {
+        self.code.get(address.base);
+        self.code.memory(op::I32_LOAD, 2, address.offset);
+    }
+
+    /// Push the address and length of a string constant onto the stack.
+    fn push_string(&mut self, string: &str) {
+        let address = self.g.string(string);
+        self.code.i32_const(address as i32);
+        self.code.i32_const(string.len() as i32);
+    }
+
+    /// Call the host's `trap`, which stops the program at the current location. Instructions added after this are
+    /// never reached.
+    fn trap(&mut self, message: &str) {
+        let (file, line) = self.location.clone();
+        self.push_string(message);
+        self.push_string(&file);
+        self.code.i32_const(line as i32);
+        let trap = self.g.import("trap");
+        self.code.indexed(op::CALL, trap);
+        self.code.op(op::UNREACHABLE);
+    }
+
+    /// Stop the program with a message if the `i32` on top of the stack is true.
+    fn trap_if(&mut self, message: &str) {
+        self.code.structured(op::IF);
+        self.trap(message);
+        self.code.op(op::END);
+    }
+
+    /// Call a function with the given arguments, returning the locals holding its results.
+    fn call(&mut self, function: u32, args: &[u32]) -> Vec<u32> {
+        for arg in args {
+            self.code.get(*arg);
+        }
+
+        self.code.indexed(op::CALL, function);
+        let results = self.g.type_of(function).1.clone();
+        self.take_results(&results)
+    }
+
+    /// Move values of the given types from the top of the stack to new locals.
+    fn take_results(&mut self, results: &[ValType]) -> Vec<u32> {
+        let locals: Vec<u32> = results.iter().map(|ty| self.local(*ty)).collect();
+
+        for local in locals.iter().rev() {
+            self.code.set(*local);
+        }
+
+        locals
+    }
+
+    /// Call an imported function with the given arguments.
+    fn call_import(&mut self, name: &str, args: &[u32]) -> Vec<u32> {
+        let function = self.g.import(name);
+        self.call(function, args)
+    }
+
+    /// Return the given values from the function.
+    fn ret(&mut self, values: &[u32]) {
+        for value in values {
+            self.code.get(*value);
+        }
+
+        self.code.get(self.saved);
+        self.code.indexed(op::GLOBAL_SET, STACK_POINTER);
+        self.code.op(op::RETURN);
+    }
+
+    /// Get a function value that runs a function, which is printed with the given label.
+    fn function_value(&mut self, function: u32, env: u32, label: &str) -> Vec<u32> {
+        let index = self.g.element(function);
+        let label = self.g.label(label);
+        let index = self.iconst(Scalar::I32, index.into());
+        let label = self.iconst(Scalar::I32, label.into());
+        vec![index, env, label]
+    }
+
+    /// Print a string constant.
+    fn print_constant(&mut self, string: &str) {
+        self.push_string(string);
+        let print = self.g.import("print_str");
+        self.code.indexed(op::CALL, print);
+    }
+
+    /// Print a value of a type.
+    fn print(&mut self, ty: &Ty, value: &[u32]) {
+        match ty {
+            Ty::Atomic(AtomicTyVariant::Bool) => _ = self.call_import("print_bool", value),
+            Ty::Atomic(AtomicTyVariant::Char) => _ = self.call_import("print_char", value),
+            Ty::Atomic(AtomicTyVariant::F64) => _ = self.call_import("print_f64", value),
+
+            Ty::Atomic(AtomicTyVariant::F32) => {
+                self.code.get(value[0]);
+                self.code.op(op::F64_PROMOTE_F32);
+                let print = self.g.import("print_f64");
+                self.code.indexed(op::CALL, print);
+            }
+
+            Ty::Atomic(variant) => {
+                self.code.get(value[0]);
+
+                match (atomic_scalar(*variant), variant.is_signed_integer()) {
+                    (Scalar::I64, _) => {}
+                    (_, true) => self.code.op(op::I64_EXTEND_I32_S),
+                    (_, false) => self.code.op(op::I64_EXTEND_I32_U),
+                }
+
+                let print = match variant.is_signed_integer() {
+                    true => self.g.import("print_i64"),
+                    false => self.g.import("print_u64"),
+                };
+
+                self.code.indexed(op::CALL, print);
+            }
+
+            Ty::Reference { target, .. } => {
+                let target_value = self.load(
+                    target,
+                    Address {
+                        base: value[0],
+                        offset: 0,
+                    },
+                );
+
+                self.print(target, &target_value);
+            }
+
+            // The name of a function value is stored after its length.
+            Ty::Function { .. } => {
+                self.code.get(value[2]);
+                self.code.get(value[2]);
+                self.code.i32_const(4);
+                self.code.op(op::I32_SUB);
+                self.code.memory(op::I32_LOAD, 2, 0);
+                let print = self.g.import("print_str");
+                self.code.indexed(op::CALL, print);
+            }
+
+            Ty::Str => _ = self.call_import("print_str", value),
+            Ty::Void => self.print_constant("()"),
+
+            Ty::Named { .. } if self.g.cx.named_kind(ty).is_some() => {
+                let helper = self.g.helper(Helper::Print, ty);
+                self.call(helper, value);
+            }
+
+            _ => self.print_constant("<value>"),
+        }
+    }
+
+    /// Check if two values of a type are equal, returning the local holding the `i32`.
+    fn eq(&mut self, ty: &Ty, lhs: &[u32], rhs: &[u32]) -> u32 {
+        let compare = |e: &mut Self, op: u8| {
+            e.code.get(lhs[0]);
+            e.code.get(rhs[0]);
+            e.code.op(op);
+            e.set_new(ValType::I32)
+        };
+
+        match ty {
+            Ty::Atomic(variant) => {
+                let op = match atomic_scalar(*variant).val_type() {
+                    ValType::I32 => op::I32_EQ,
+                    ValType::I64 => op::I64_EQ,
+                    ValType::F32 => op::F32_EQ,
+                    ValType::F64 => op::F64_EQ,
+                };
+
+                compare(self, op)
+            }
+
+            Ty::Reference { target, .. } => {
+                let lhs = self.load(
+                    target,
+                    Address {
+                        base: lhs[0],
+                        offset: 0,
+                    },
+                );
+
+                let rhs = self.load(
+                    target,
+                    Address {
+                        base: rhs[0],
+                        offset: 0,
+                    },
+                );
+
+                self.eq(target, &lhs, &rhs)
+            }
+
+            Ty::Function { .. } => {
+                for index in 0..2 {
+                    self.code.get(lhs[index]);
+                    self.code.get(rhs[index]);
+                    self.code.op(op::I32_EQ);
+                }
+
+                self.code.op(op::I32_AND);
+                self.set_new(ValType::I32)
+            }
+
+            Ty::Str => {
+                let str_eq = self.g.runtime(Runtime::StrEq);
+                self.call(str_eq, &[lhs, rhs].concat())[0]
+            }
+
+            Ty::Named { .. } => match self.g.cx.named_kind(ty) {
+                Some(NamedKind::Enum) => compare(self, op::I32_EQ),
+
+                Some(_) => {
+                    let helper = self.g.helper(Helper::Eq, ty);
+                    self.call(helper, &[lhs, rhs].concat())[0]
+                }
+
+                None => self.iconst(Scalar::I32, 1),
+            },
+
+            _ => self.iconst(Scalar::I32, 1),
+        }
+    }
+
+    /// Check if the `i32` local holding the tag of a union is the given variant, leaving the result on the stack.
+    fn is_variant(&mut self, tag: u32, variant: usize) {
+        self.code.get(tag);
+        self.code.i32_const(variant as i32);
+        self.code.op(op::I32_EQ);
+    }
+
+    /// Generate a helper function's code.
+    fn generate_helper(&mut self, helper: Helper, ty: &Ty) {
+        let params: Vec<u32> = (0..self.params).collect();
+        let display = ty.display(self.g.cx.resolution).to_string();
+        let kind = self.g.cx.named_kind(ty);
+        let (_, fields) = self.g.layouts.fields(ty);
+
+        match (helper, kind) {
+            (Helper::Print, Some(NamedKind::Record)) => {
+                self.print_constant(&format!("{display} {{"));
+
+                for (index, field) in fields.iter().enumerate() {
+                    let separator = if index > 0 { ", " } else { " " };
+                    self.print_constant(&format!("{separator}{}: ", field.name));
+                    self.print(&field.ty, &params[field.parts.clone()]);
+                }
+
+                self.print_constant(" }");
+                self.ret(&[]);
+            }
+
+            (Helper::Print, Some(NamedKind::Union)) => {
+                let memory = self.stack(ty);
+                self.store(ty, &params, memory);
+
+                for (index, field) in fields.iter().enumerate() {
+                    self.is_variant(params[0], index);
+                    self.code.structured(op::IF);
+                    self.print_constant(&format!("{display} {{ {}: ", field.name));
+                    let value = self.load(
+                        &field.ty,
+                        Address {
+                            offset: memory.offset + field.offset,
+                            ..memory
+                        },
+                    );
+                    self.print(&field.ty, &value);
+                    self.code.op(op::END);
+                }
+
+                self.print_constant(" }");
+                self.ret(&[]);
+            }
+
+            (Helper::Print, _) => {
+                let variants = match ty {
+                    Ty::Named { def, .. } => self.g.cx.resolution.variants(*def).to_vec(),
+                    _ => Vec::new(),
+                };
+
+                for (index, variant) in variants.iter().enumerate() {
+                    let variant = &self.g.cx.resolution.def(*variant).name;
+                    self.is_variant(params[0], index);
+                    self.code.structured(op::IF);
+                    self.print_constant(&format!("{display}::{variant}"));
+                    self.code.op(op::END);
+                }
+
+                self.ret(&[]);
+            }
+
+            (Helper::Eq, Some(NamedKind::Union)) => {
+                let (lhs, rhs) = params.split_at(params.len() / 2);
+                let result = self.local(ValType::I32);
+
+                self.code.get(lhs[0]);
+                self.code.get(rhs[0]);
+                self.code.op(op::I32_EQ);
+                self.code.structured(op::IF);
+
+                // Unions that hold the same variant are equal if the variants are (or if it's unknown).
+                self.code.i32_const(1);
+                self.code.set(result);
+
+                let mut memory = Vec::new();
+
+                for side in [lhs, rhs] {
+                    let address = self.stack(ty);
+                    self.store(ty, side, address);
+                    memory.push(address);
+                }
+
+                for (index, field) in fields.iter().enumerate() {
+                    self.is_variant(lhs[0], index);
+                    self.code.structured(op::IF);
+
+                    let [lhs, rhs] = [memory[0], memory[1]].map(|address| Address {
+                        offset: address.offset + field.offset,
+                        ..address
+                    });
+
+                    let lhs = self.load(&field.ty, lhs);
+                    let rhs = self.load(&field.ty, rhs);
+                    let eq = self.eq(&field.ty, &lhs, &rhs);
+                    self.code.get(eq);
+                    self.code.set(result);
+                    self.code.op(op::END);
+                }
+
+                self.code.op(op::END);
+                self.ret(&[result]);
+            }
+
+            (Helper::Eq, _) => {
+                let (lhs, rhs) = params.split_at(params.len() / 2);
+                self.code.i32_const(1);
+
+                for field in &fields {
+                    let parts = field.parts.clone();
+                    let eq = self.eq(&field.ty, &lhs[parts.clone()], &rhs[parts]);
+                    self.code.get(eq);
+                    self.code.op(op::I32_AND);
+                }
+
+                let result = self.set_new(ValType::I32);
+                self.ret(&[result]);
+            }
+        }
+    }
+
+    /// Generate the code of a function value's function, which calls an instance or a builtin function.
+    fn generate_thunk(&mut self, thunk: &Thunk) {
+        let params: Vec<u32> = (0..self.params).collect();
+
+        match thunk {
+            Thunk::Instance(instance) => {
+                let function = self.g.instance_function(instance.clone());
+                let results = self.call(function, &params[1..]);
+                self.ret(&results);
+            }
+
+            Thunk::Builtin(builtin, ty) => {
+                if let Ty::Function { params: tys, .. } = ty {
+                    if let Some(param) = tys.first() {
+                        self.print(param, &params[1..]);
+                    }
+                }
+
+                if *builtin == Builtin::Println {
+                    self.print_constant("\n");
+                }
+
+                self.ret(&[]);
+            }
+        }
+    }
+
+    /// Generate the code of a runtime function.
+    fn generate_runtime(&mut self, runtime: Runtime) {
+        match runtime {
+            // The heap only grows, and memory is aligned to 8 bytes.
+            Runtime::Alloc => {
+                let size = 0;
+                self.code.indexed(op::GLOBAL_GET, HEAP_POINTER);
+                self.code.i32_const(7);
+                self.code.op(op::I32_ADD);
+                self.code.i32_const(-8);
+                self.code.op(op::I32_AND);
+                let address = self.set_new(ValType::I32);
+
+                self.code.get(address);
+                self.code.get(size);
+                self.code.op(op::I32_ADD);
+                let end = self.set_new(ValType::I32);
+                self.code.get(end);
+                self.code.indexed(op::GLOBAL_SET, HEAP_POINTER);
+
+                let memory_end = |e: &mut Self| {
+                    e.code.memory_size_or_grow(op::MEMORY_SIZE);
+                    e.code.i32_const(16);
+                    e.code.op(op::I32_SHL);
+                };
+
+                self.code.get(end);
+                memory_end(self);
+                self.code.op(op::I32_GT_U);
+                self.code.structured(op::IF);
+
+                // Grow the memory by enough pages to fit the end of the allocation.
+                self.code.get(end);
+                memory_end(self);
+                self.code.op(op::I32_SUB);
+                self.code.i32_const(PAGE_SIZE as i32 - 1);
+                self.code.op(op::I32_ADD);
+                self.code.i32_const(16);
+                self.code.op(op::I32_SHR_U);
+                self.code.memory_size_or_grow(op::MEMORY_GROW);
+                self.code.i32_const(-1);
+                self.code.op(op::I32_EQ);
+                self.trap_if("out of memory");
+
+                self.code.op(op::END);
+                self.ret(&[address]);
+            }
+
+            Runtime::StrEq => {
+                let (lhs, lhs_length, rhs, rhs_length) = (0, 1, 2, 3);
+                let zero = self.iconst(Scalar::I32, 0);
+                let one = self.iconst(Scalar::I32, 1);
+                let index = self.local(ValType::I32);
+
+                self.code.get(lhs_length);
+                self.code.get(rhs_length);
+                self.code.op(op::I32_NE);
+                self.code.structured(op::IF);
+                self.ret(&[zero]);
+                self.code.op(op::END);
+
+                self.code.structured(op::LOOP);
+                self.code.get(index);
+                self.code.get(lhs_length);
+                self.code.op(op::I32_EQ);
+                self.code.structured(op::IF);
+                self.ret(&[one]);
+                self.code.op(op::END);
+
+                for side in [lhs, rhs] {
+                    self.code.get(side);
+                    self.code.get(index);
+                    self.code.op(op::I32_ADD);
+                    self.code.memory(op::I32_LOAD8_U, 0, 0);
+                }
+
+                self.code.op(op::I32_NE);
+                self.code.structured(op::IF);
+                self.ret(&[zero]);
+                self.code.op(op::END);
+
+                self.code.get(index);
+                self.code.i32_const(1);
+                self.code.op(op::I32_ADD);
+                self.code.set(index);
+                self.code.indexed(op::BR, 0);
+                self.code.op(op::END);
+                self.code.op(op::UNREACHABLE);
+            }
+        }
+    }
+}
+
+/// The state of generating the function of an instance.
+struct FunctionGenerator<'e, 'g, 'a> {
+    /// The emitter of the function.
+    e: &'e mut Emitter<'g, 'a>,
+    /// The body.
+    body: &'a Body,
+    /// The types of the instance's generic parameters.
+    substitution: HashMap<DefId, Ty>,
+    /// How many values the body captures, if it's the body of a closure.
+    captures: Option<usize>,
+    /// The locals of every MIR value that's been defined.
+    values: HashMap<Value, Vec<u32>>,
+    /// The address of every slot.
+    slots: Vec<Address>,
+    /// The local holding the index of the block to run next.
+    next: u32,
+    /// The index of the block being generated.
+    current: u32,
+}
+
+impl<'e, 'g, 'a> FunctionGenerator<'e, 'g, 'a> {
+    /// Set up the generating of an instance.
+    fn new(e: &'e mut Emitter<'g, 'a>, instance: Instance) -> Self {
+        let program = e.g.cx.program;
+        let captures = e.g.closures.get(&instance.body).copied();
+
+        FunctionGenerator {
+            e,
+            body: &program.bodies[instance.body],
+            substitution: instance.substitution(),
+            captures,
+            values: HashMap::new(),
+            slots: Vec::new(),
+            next: 0,
+            current: 0,
+        }
+    }
+
+    /// Generate the function.
+    fn generate(mut self) {
+        let mut params = 0..self.e.params;
+        let env = self.captures.and_then(|_| params.next());
+        let (captured, called_with) = split_captures(self.body, self.captures);
+
+        for param in called_with {
+            let count = self.e.g.layouts.layout(&self.value_ty(*param)).parts.len();
+            self.values
+                .insert(*param, params.by_ref().take(count).collect());
+        }
+
+        let first_block = self.body.block(BlockId::ENTRY);
+        self.locate(
+            first_block
+                .spans
+                .first()
+                .unwrap_or(&first_block.terminator_span),
+        );
+
+        self.e.entry_location = Some(self.e.location.clone());
+
+        if let (Some(env), false) = (env, captured.is_empty()) {
+            let tys: Vec<Ty> = captured.iter().map(|param| self.value_ty(*param)).collect();
+            let (offsets, _) = self.e.g.layouts.offsets(&tys);
+
+            for ((param, ty), offset) in captured.iter().zip(&tys).zip(offsets) {
+                let value = self.e.load(ty, Address { base: env, offset });
+                self.values.insert(*param, value);
+            }
+        }
+
+        for slot in &self.body.slots {
+            let address = self.e.stack(&slot.ty.substitute(&self.substitution));
+            self.slots.push(address);
+        }
+
+        let blocks: Vec<BlockId> = self.body.block_ids().collect();
+
+        for block in &blocks {
+            for param in &self.body.block(*block).params {
+                let locals = self.e.zeros(&self.value_ty(*param));
+                self.values.insert(*param, locals);
+            }
+        }
+
+        // Every block is the code after the end of a `block`, and the `br_table` in the innermost one jumps to
+        // the end of the block for the block to run. Jumps set the block to run, and go back to the loop.
+        self.next = self.e.local(ValType::I32);
+        self.e.code.structured(op::LOOP);
+
+        for _ in &blocks {
+            self.e.code.structured(op::BLOCK);
+        }
+
+        let count = blocks.len() as u32;
+        self.e.code.get(self.next);
+        self.e
+            .code
+            .br_table(&(0..count).collect::<Vec<_>>(), count - 1);
+
+        for block in blocks {
+            let data = self.body.block(block);
+            self.current = block.0;
+            self.e.code.op(op::END);
+
+            for (inst, span) in data.insts.iter().zip(&data.spans) {
+                self.locate(span);
+                self.inst(inst);
+            }
+
+            self.locate(&data.terminator_span);
+            self.terminator(&data.terminator);
+        }
+
+        self.e.code.op(op::END);
+        self.e.code.op(op::UNREACHABLE);
+    }
+
+    /// Get the (concrete) type of a value.
+    fn value_ty(&self, value: Value) -> Ty {
+        self.body.value_ty(value).substitute(&self.substitution)
+    }
+
+    /// Get the (concrete) type of an operand.
+    fn operand_ty(&self, operand: &Operand) -> Ty {
+        self.body.operand_ty(operand).substitute(&self.substitution)
+    }
+
+    /// Stop the program with a message.
+    fn trap(&mut self, message: &str) {
+        self.e.trap(message);
+    }
+
+    /// Attribute the traps from now on to the source code of a span.
+    fn locate(&mut self, span: &Fragment) {
+        let line = span.source.line_index(span.range.start) + 1;
+        self.e.location = (span.source.name().to_string(), line as u32);
+    }
+
+    /// Get the locals of an operand. Values that were never defined (because the code that would have defined them
+    /// traps) are zero.
+    fn operand(&mut self, operand: &Operand) -> Vec<u32> {
+        match operand {
+            Operand::Value(value) => match self.values.get(value) {
+                Some(values) => values.clone(),
+                None => self.e.zeros(&self.value_ty(*value)),
+            },
+
+            Operand::Const(constant) => self.constant(constant),
+        }
+    }
+
+    /// Get the locals of a constant. Constants that can't be made trap (and are left zero).
+    fn constant(&mut self, constant: &Const) -> Vec<u32> {
+        match constant {
+            Const::Int(number, ty) => {
+                let ty = ty.substitute(&self.substitution);
+                let value = ty.atomic().and_then(|atomic| {
+                    let (min, max) = atomic.integer_bounds()?;
+                    i128::try_from(number)
+                        .ok()
+                        .filter(|value| (min..=max).contains(value))
+                        .map(|value| (atomic_scalar(atomic), value))
+                });
+
+                match value {
+                    Some((scalar, value)) => vec![self.e.iconst(scalar, value)],
+                    None => {
+                        self.trap(&format!("the integer {constant:?} doesn't fit its type"));
+                        self.e.zeros(&ty)
+                    }
+                }
+            }
+
+            Const::Bool(value) => vec![self.e.iconst(Scalar::U8, *value as i128)],
+            Const::Void => Vec::new(),
+            Const::Variant(_, index, _) => vec![self.e.iconst(Scalar::I32, *index as i128)],
+
+            Const::Str(string) => {
+                let address = self.e.g.string(string);
+                let address = self.e.iconst(Scalar::I32, address.into());
+                let length = self.e.iconst(Scalar::I32, string.len() as i128);
+                vec![address, length]
+            }
+
+            Const::Def(def, ty) => {
+                let ty = ty.substitute(&self.substitution);
+
+                let thunk = match self.e.g.cx.callee_value(*def, &ty) {
+                    Callee::Instance(instance) => Thunk::Instance(instance),
+                    Callee::Builtin(builtin) => Thunk::Builtin(builtin, ty.clone()),
+                    Callee::Missing(message) => {
+                        self.trap(&message);
+                        return self.e.zeros(&ty);
+                    }
+                };
+
+                let (function, label) = self.e.g.thunk(thunk);
+                let env = self.e.iconst(Scalar::I32, 0);
+                self.e.function_value(function, env, &label)
+            }
+        }
+    }
+
+    /// Get the address of a place. Going through a variant of a union to load or store checks that the union holds
+    /// it, except for the last step of a place that's stored to, which changes the union to hold it. References to
+    /// variants aren't checked until they're used, like in the VM.
+    fn place(&mut self, place: &Place, access: Access) -> Address {
+        let (mut address, mut ty, projections) = match place.base {
+            PlaceBase::Slot(slot) => (
+                self.slots[slot.0 as usize],
+                self.body.slots[slot.0 as usize]
+                    .ty
+                    .substitute(&self.substitution),
+                &place.projections[..],
+            ),
+
+            PlaceBase::Value(value) => (
+                Address {
+                    base: self.operand(&Operand::Value(value))[0],
+                    offset: 0,
+                },
+                strip_one_reference(&self.value_ty(value)),
+                &place.projections[1..],
+            ),
+        };
+
+        for (index, projection) in projections.iter().enumerate() {
+            match projection {
+                Projection::Field(field) => {
+                    let (is_union, fields) = self.e.g.layouts.fields(&ty);
+                    let position = fields.iter().position(|candidate| candidate.name == *field);
+                    let (field_ty, offset) = position.map_or((Ty::Error, 0), |position| {
+                        (fields[position].ty.clone(), fields[position].offset)
+                    });
+
+                    if is_union {
+                        let position = position.unwrap_or_default() as i32;
+                        let is_last = index == projections.len() - 1;
+
+                        match access {
+                            Access::Store if is_last => {
+                                self.e.code.get(address.base);
+                                self.e.code.i32_const(position);
+                                self.e.code.memory(op::I32_STORE, 2, address.offset);
+                            }
+
+                            Access::Ref if is_last => {}
+
+                            _ => {
+                                self.e.load_i32(address);
+                                self.e.code.i32_const(position);
+                                self.e.code.op(op::I32_NE);
+                                self.e.trap_if(VARIANT_TRAP);
+                            }
+                        }
+                    }
+
+                    address.offset += offset;
+                    ty = field_ty;
+                }
+
+                Projection::Deref => {
+                    self.e.load_i32(address);
+
+                    address = Address {
+                        base: self.e.set_new(ValType::I32),
+                        offset: 0,
+                    };
+
+                    ty = strip_one_reference(&ty);
+                }
+            }
+        }
+
+        address
+    }
+
+    /// Generate an instruction.
+    fn inst(&mut self, inst: &Inst) {
+        match inst {
+            Inst::Assign(value, rvalue) => self.rvalue(*value, rvalue),
+
+            Inst::Store(place, operand) => {
+                let ty = self.operand_ty(operand);
+                let src = self.operand(operand);
+                let address = self.place(place, Access::Store);
+                self.e.store(&ty, &src, address);
+            }
+
+            // Slots live until the function returns.
+            Inst::Drop(_) => {}
+        }
+    }
+
+    /// Define a value as the given locals.
+    fn define(&mut self, value: Value, locals: Vec<u32>) {
+        self.values.insert(value, locals);
+    }
+
+    /// Define a value as the value on top of the stack, which has the given type.
+    fn assign(&mut self, value: Value, ty: ValType) {
+        let local = self.e.set_new(ty);
+        self.define(value, vec![local]);
+    }
+
+    /// Generate the computation of a value.
+    fn rvalue(&mut self, value: Value, rvalue: &Rvalue) {
+        match rvalue {
+            Rvalue::Use(operand) => {
+                let src = self.operand(operand);
+                self.define(value, src);
+            }
+
+            Rvalue::Load(place) => {
+                let ty = self.value_ty(value);
+                let address = self.place(place, Access::Load);
+                let loaded = self.e.load(&ty, address);
+                self.define(value, loaded);
+            }
+
+            Rvalue::Ref { place, .. } => {
+                let address = self.place(place, Access::Ref);
+                self.e.code.get(address.base);
+                self.e.code.i32_const(address.offset as i32);
+                self.e.code.op(op::I32_ADD);
+                self.assign(value, ValType::I32);
+            }
+
+            Rvalue::Unary(op, operand) => self.unary(value, *op, operand),
+            Rvalue::Binary(op, lhs, rhs) => self.binary(value, *op, lhs, rhs),
+            Rvalue::Call { callee, args } => self.call(value, callee, args),
+
+            Rvalue::Aggregate { fields } => {
+                let ty = self.value_ty(value);
+                let (is_union, layout) = self.e.g.layouts.fields(&ty);
+
+                if is_union {
+                    let Some((name, operand)) = fields.first() else {
+                        return self.trap("a union is made without a variant");
+                    };
+
+                    let Some(variant) = layout.iter().position(|field| field.name == *name) else {
+                        return self.trap(VARIANT_TRAP);
+                    };
+
+                    let src = self.operand(operand);
+                    let memory = self.e.stack(&ty);
+                    let zeros = self.e.zeros(&ty);
+                    self.e.store(&ty, &zeros, memory);
+                    self.e.code.get(memory.base);
+                    self.e.code.i32_const(variant as i32);
+                    self.e.code.memory(op::I32_STORE, 2, memory.offset);
+
+                    let payload = Address {
+                        offset: memory.offset + UNION_PAYLOAD,
+                        ..memory
+                    };
+
+                    self.e.store(&layout[variant].ty, &src, payload);
+                    let union = self.e.load(&ty, memory);
+                    return self.define(value, union);
+                }
+
+                // The fields are given in any order, but their values go in the order they're declared.
+                let mut parts = Vec::new();
+
+                for field in &layout {
+                    match fields.iter().find(|(name, _)| *name == field.name) {
+                        Some((_, operand)) => parts.extend(self.operand(operand)),
+                        None => parts.extend(self.e.zeros(&field.ty)),
+                    }
+                }
+
+                self.define(value, parts);
+            }
+
+            Rvalue::Field(operand, field) => {
+                let ty = self.operand_ty(operand);
+                let (is_union, fields) = self.e.g.layouts.fields(&ty);
+                let src = self.operand(operand);
+
+                let Some(position) = fields.iter().position(|candidate| candidate.name == *field)
+                else {
+                    let zeros = self.e.zeros(&self.value_ty(value));
+                    return self.define(value, zeros);
+                };
+
+                if !is_union {
+                    let parts = src[fields[position].parts.clone()].to_vec();
+                    return self.define(value, parts);
+                }
+
+                self.e.is_variant(src[0], position);
+                self.e.code.op(op::I32_EQZ);
+                self.e.trap_if(VARIANT_TRAP);
+
+                let memory = self.e.stack(&ty);
+                self.e.store(&ty, &src, memory);
+
+                let payload = Address {
+                    offset: memory.offset + UNION_PAYLOAD,
+                    ..memory
+                };
+
+                let variant = self.e.load(&fields[position].ty, payload);
+                self.define(value, variant);
+            }
+
+            Rvalue::IsVariant(operand, variant) => {
+                let ty = self.operand_ty(operand);
+                let (_, fields) = self.e.g.layouts.fields(&ty);
+                let position = (fields.iter())
+                    .position(|field| field.name == *variant)
+                    .unwrap_or_default();
+                let src = self.operand(operand);
+                self.e.is_variant(src[0], position);
+                self.assign(value, ValType::I32);
+            }
+
+            Rvalue::Closure { body, captures } => {
+                let index = body.0 as usize;
+
+                let instance = match self.e.g.cx.instance(index, &self.substitution) {
+                    Ok(instance) => instance,
+                    Err(_) => {
+                        return self
+                            .trap("the types of a closure's generic parameters aren't known");
+                    }
+                };
+
+                self.e.g.closures.insert(index, captures.len());
+                let function = self.e.g.instance_function(instance);
+                let label = format!("closure {}", self.e.g.cx.program.bodies[index].name);
+
+                if captures.is_empty() {
+                    let env = self.e.iconst(Scalar::I32, 0);
+                    let closure = self.e.function_value(function, env, &label);
+                    return self.define(value, closure);
+                }
+
+                // The environment of a closure lives as long as the program does.
+                let tys: Vec<Ty> = captures
+                    .iter()
+                    .map(|capture| self.operand_ty(capture))
+                    .collect();
+                let (offsets, size) = self.e.g.layouts.offsets(&tys);
+                let size = self.e.iconst(Scalar::I32, size.into());
+                let alloc = self.e.g.runtime(Runtime::Alloc);
+                let env = self.e.call(alloc, &[size])[0];
+
+                for ((capture, ty), offset) in captures.iter().zip(&tys).zip(offsets) {
+                    let src = self.operand(capture);
+                    self.e.store(ty, &src, Address { base: env, offset });
+                }
+
+                let closure = self.e.function_value(function, env, &label);
+                self.define(value, closure);
+            }
+        }
+    }
+
+    /// Generate a unary operation.
+    fn unary(&mut self, value: Value, op: UnaryOperation, operand: &Operand) {
+        let ty = self.operand_ty(operand);
+        let src = self.operand(operand);
+        let atomic = ty.atomic();
+
+        match (op, atomic.filter(|atomic| atomic.is_integer())) {
+            (UnaryOperation::Negate, Some(int)) => {
+                let zero = self.e.iconst(atomic_scalar(int), 0);
+                let result = self.checked(BinaryOperation::Subtract, int, zero, src[0]);
+                self.define(value, vec![result]);
+            }
+
+            (UnaryOperation::BitwiseNot, Some(int)) => {
+                let scalar = atomic_scalar(int);
+                self.e.code.get(src[0]);
+
+                match scalar {
+                    Scalar::I64 => {
+                        self.e.code.i64_const(-1);
+                        self.e.code.op(op::I64_XOR);
+                    }
+
+                    _ => {
+                        self.e.code.i32_const(-1);
+                        self.e.code.op(op::I32_XOR);
+                        self.canonicalize(scalar);
+                    }
+                }
+
+                self.assign(value, scalar.val_type());
+            }
+
+            (UnaryOperation::Negate, None) if atomic == Some(AtomicTyVariant::F32) => {
+                self.e.code.get(src[0]);
+                self.e.code.op(op::F32_NEG);
+                self.assign(value, ValType::F32);
+            }
+
+            (UnaryOperation::Negate, None) if atomic == Some(AtomicTyVariant::F64) => {
+                self.e.code.get(src[0]);
+                self.e.code.op(op::F64_NEG);
+                self.assign(value, ValType::F64);
+            }
+
+            (UnaryOperation::BooleanNot | UnaryOperation::BitwiseNot, None) if ty == Ty::BOOL => {
+                self.e.code.get(src[0]);
+                self.e.code.op(op::I32_EQZ);
+                self.assign(value, ValType::I32);
+            }
+
+            _ => self.unsupported(op.symbol(), &ty),
+        }
+    }
+
+    /// Sign- or zero-extend the low bits of the `i32` on top of the stack that a scalar of less than 32 bits keeps,
+    /// so it's in the form every value of the scalar is kept in.
+    fn canonicalize(&mut self, scalar: Scalar) {
+        match scalar {
+            Scalar::I8 => self.e.code.op(op::I32_EXTEND8_S),
+            Scalar::I16 => self.e.code.op(op::I32_EXTEND16_S),
+
+            Scalar::U8 | Scalar::U16 => {
+                self.e.code.i32_const((1 << (scalar.size() * 8)) - 1);
+                self.e.code.op(op::I32_AND);
+            }
+
+            _ => {}
+        }
+    }
+
+    /// Generate an addition, subtraction, or multiplication of integers that traps if it overflows, returning the
+    /// local holding the result.
+    fn checked(&mut self, op: BinaryOperation, int: AtomicTyVariant, a: u32, b: u32) -> u32 {
+        let scalar = atomic_scalar(int);
+        let signed = int.is_signed_integer();
+
+        let opcode = |wide: bool| match (op, wide) {
+            (BinaryOperation::Add, false) => op::I32_ADD,
+            (BinaryOperation::Subtract, false) => op::I32_SUB,
+            (_, false) => op::I32_MUL,
+            (BinaryOperation::Add, true) => op::I64_ADD,
+            (BinaryOperation::Subtract, true) => op::I64_SUB,
+            (_, true) => op::I64_MUL,
+        };
+
+        match scalar {
+            // The result of narrow integers fits an `i32`, and overflowed if it's changed by canonicalizing it.
+            Scalar::I8 | Scalar::U8 | Scalar::I16 | Scalar::U16 => {
+                self.e.code.get(a);
+                self.e.code.get(b);
+                self.e.code.op(opcode(false));
+                let result = self.e.set_new(ValType::I32);
+
+                self.e.code.get(result);
+                self.canonicalize(scalar);
+                self.e.code.get(result);
+                self.e.code.op(op::I32_NE);
+                self.e.trap_if("arithmetic overflow");
+                result
+            }
+
+            // The result of 32-bit integers fits an `i64`, and overflowed if it doesn't fit 32 bits.
+            Scalar::I32 => {
+                let extend = match signed {
+                    true => op::I64_EXTEND_I32_S,
+                    false => op::I64_EXTEND_I32_U,
+                };
+
+                for operand in [a, b] {
+                    self.e.code.get(operand);
+                    self.e.code.op(extend);
+                }
+
+                self.e.code.op(opcode(true));
+                let wide = self.e.set_new(ValType::I64);
+
+                self.e.code.get(wide);
+
+                match signed {
+                    true => self.e.code.op(op::I64_EXTEND32_S),
+                    false => {
+                        self.e.code.i64_const(u32::MAX.into());
+                        self.e.code.op(op::I64_AND);
+                    }
+                }
+
+                self.e.code.get(wide);
+                self.e.code.op(op::I64_NE);
+                self.e.trap_if("arithmetic overflow");
+
+                self.e.code.get(wide);
+                self.e.code.op(op::I32_WRAP_I64);
+                self.e.set_new(ValType::I32)
+            }
+
+            _ => {
+                self.e.code.get(a);
+                self.e.code.get(b);
+                self.e.code.op(opcode(true));
+                let result = self.e.set_new(ValType::I64);
+                self.overflowed_64(op, signed, a, b, result);
+                self.e.trap_if("arithmetic overflow");
+                result
+            }
+        }
+    }
+
+    /// Push whether an operation on 64-bit integers overflowed, given its operands and (wrapped) result.
+    fn overflowed_64(&mut self, op: BinaryOperation, signed: bool, a: u32, b: u32, result: u32) {
+        let code = &mut self.e.code;
+
+        match (op, signed) {
+            // Unsigned addition overflowed if the result wrapped around past an operand.
+            (BinaryOperation::Add, false) => {
+                code.get(result);
+                code.get(a);
+                code.op(op::I64_LT_U);
+            }
+
+            (BinaryOperation::Subtract, false) => {
+                code.get(a);
+                code.get(b);
+                code.op(op::I64_LT_U);
+            }
+
+            // Signed addition overflowed if the result's sign differs from both operands'.
+            (BinaryOperation::Add, true) => {
+                for operand in [a, b] {
+                    code.get(operand);
+                    code.get(result);
+                    code.op(op::I64_XOR);
+                }
+
+                code.op(op::I64_AND);
+                code.i64_const(0);
+                code.op(op::I64_LT_S);
+            }
+
+            // Signed subtraction overflowed if the operands' signs differ, and the result's differs from the first.
+            (BinaryOperation::Subtract, true) => {
+                code.get(a);
+                code.get(b);
+                code.op(op::I64_XOR);
+                code.get(a);
+                code.get(result);
+                code.op(op::I64_XOR);
+                code.op(op::I64_AND);
+                code.i64_const(0);
+                code.op(op::I64_LT_S);
+            }
+
+            // Multiplication overflowed if dividing the result by a nonzero operand doesn't give the other one back.
+            // Signed multiplication by -1 only overflows for the smallest integer, which dividing can't check.
+            (_, _) => {
+                let overflowed = self.e.local(ValType::I32);
+                let code = &mut self.e.code;
+                code.get(a);
+                code.op(op::I64_EQZ);
+                code.op(op::I32_EQZ);
+                code.structured(op::IF);
+
+                if signed {
+                    code.get(a);
+                    code.i64_const(-1);
+                    code.op(op::I64_EQ);
+                    code.structured(op::IF);
+                    code.get(b);
+                    code.i64_const(i64::MIN);
+                    code.op(op::I64_EQ);
+                    code.set(overflowed);
+                    code.op(op::ELSE);
+                }
+
+                code.get(result);
+                code.get(a);
+                code.op(if signed { op::I64_DIV_S } else { op::I64_DIV_U });
+                code.get(b);
+                code.op(op::I64_NE);
+                code.set(overflowed);
+
+                if signed {
+                    code.op(op::END);
+                }
+
+                code.op(op::END);
+                code.get(overflowed);
+            }
+        }
+    }
+
+    /// Generate a binary operation.
+    fn binary(&mut self, value: Value, op: BinaryOperation, lhs: &Operand, rhs: &Operand) {
+        let ty = self.operand_ty(lhs);
+        let rhs_ty = self.operand_ty(rhs);
+        let atomic = ty.atomic();
+        let a = self.operand(lhs);
+        let b = self.operand(rhs);
+
+        if let Some(comparison) = compare(op, atomic) {
+            match &ty {
+                Ty::Atomic(_) => {
+                    self.e.code.get(a[0]);
+                    self.e.code.get(b[0]);
+                    self.e.code.op(comparison);
+                    self.assign(value, ValType::I32);
+                }
+
+                _ if op == BinaryOperation::Equal => {
+                    let eq = self.e.eq(&ty, &a, &b);
+                    self.define(value, vec![eq]);
+                }
+
+                _ if op == BinaryOperation::NotEqual => {
+                    let eq = self.e.eq(&ty, &a, &b);
+                    self.e.code.get(eq);
+                    self.e.code.op(op::I32_EQZ);
+                    self.assign(value, ValType::I32);
+                }
+
+                _ => self.unsupported(op.symbol(), &ty),
+            }
+
+            return;
+        }
+
+        if let Some(int) = atomic.filter(|atomic| atomic.is_integer()) {
+            let scalar = atomic_scalar(int);
+            let wide = scalar == Scalar::I64;
+            let bits = scalar.size() * 8;
+            let signed = int.is_signed_integer();
+            let (a, b) = (a[0], b[0]);
+
+            if let Some(message) = constant_trap(op, rhs, bits) {
+                return self.trap(message);
+            }
+
+            let choose = |narrow: u8, wide_op: u8| if wide { wide_op } else { narrow };
+
+            match op {
+                BinaryOperation::Add | BinaryOperation::Subtract | BinaryOperation::Multiply => {
+                    let result = self.checked(op, int, a, b);
+                    return self.define(value, vec![result]);
+                }
+
+                BinaryOperation::Divide | BinaryOperation::Modulo => {
+                    self.e.code.get(b);
+                    self.e.code.op(choose(op::I32_EQZ, op::I64_EQZ));
+                    self.e.trap_if("division by zero");
+
+                    // The remainder of the smallest integer divided by -1 is 0 in WebAssembly, so only dividing
+                    // overflows.
+                    if signed && op == BinaryOperation::Divide {
+                        let (min, _) = int.integer_bounds().unwrap_or_default();
+
+                        for (operand, constant) in [(a, min), (b, -1)] {
+                            self.e.code.get(operand);
+
+                            match wide {
+                                true => self.e.code.i64_const(constant as i64),
+                                false => self.e.code.i32_const(constant as i32),
+                            }
+
+                            self.e.code.op(choose(op::I32_EQ, op::I64_EQ));
+                        }
+
+                        self.e.code.op(op::I32_AND);
+                        self.e.trap_if("arithmetic overflow");
+                    }
+
+                    let opcode = match (op, signed) {
+                        (BinaryOperation::Divide, true) => choose(op::I32_DIV_S, op::I64_DIV_S),
+                        (BinaryOperation::Divide, false) => choose(op::I32_DIV_U, op::I64_DIV_U),
+                        (_, true) => choose(op::I32_REM_S, op::I64_REM_S),
+                        (_, false) => choose(op::I32_REM_U, op::I64_REM_U),
+                    };
+
+                    self.e.code.get(a);
+                    self.e.code.get(b);
+                    self.e.code.op(opcode);
+                }
+
+                BinaryOperation::BitwiseAnd
+                | BinaryOperation::BitwiseOr
+                | BinaryOperation::BitwiseXor => {
+                    let opcode = match op {
+                        BinaryOperation::BitwiseAnd => choose(op::I32_AND, op::I64_AND),
+                        BinaryOperation::BitwiseOr => choose(op::I32_OR, op::I64_OR),
+                        _ => choose(op::I32_XOR, op::I64_XOR),
+                    };
+
+                    self.e.code.get(a);
+                    self.e.code.get(b);
+                    self.e.code.op(opcode);
+                }
+
+                BinaryOperation::ShiftLeft | BinaryOperation::ShiftRight => {
+                    let Some(amount) = rhs_ty.atomic().filter(|atomic| atomic.is_integer()) else {
+                        return self.unsupported(op.symbol(), &rhs_ty);
+                    };
+
+                    // Negative amounts are larger than the number of bits when they're compared as unsigned.
+                    let amount_wide = atomic_scalar(amount) == Scalar::I64;
+                    self.e.code.get(b);
+
+                    match amount_wide {
+                        true => {
+                            self.e.code.i64_const(bits.into());
+                            self.e.code.op(op::I64_GE_U);
+                        }
+
+                        false => {
+                            self.e.code.i32_const(bits as i32);
+                            self.e.code.op(op::I32_GE_U);
+                        }
+                    }
+
+                    self.e.trap_if(SHIFT_TRAP);
+
+                    self.e.code.get(a);
+                    self.e.code.get(b);
+
+                    match (wide, amount_wide) {
+                        (true, false) => self.e.code.op(op::I64_EXTEND_I32_U),
+                        (false, true) => self.e.code.op(op::I32_WRAP_I64),
+                        _ => {}
+                    }
+
+                    let opcode = match (op, signed) {
+                        (BinaryOperation::ShiftLeft, _) => choose(op::I32_SHL, op::I64_SHL),
+                        (_, true) => choose(op::I32_SHR_S, op::I64_SHR_S),
+                        (_, false) => choose(op::I32_SHR_U, op::I64_SHR_U),
+                    };
+
+                    self.e.code.op(opcode);
+
+                    // Shifting left can move bits past the narrow integer's bits.
+                    if op == BinaryOperation::ShiftLeft {
+                        self.canonicalize(scalar);
+                    }
+                }
+
+                _ => return self.unsupported(op.symbol(), &ty),
+            }
+
+            self.assign(value, scalar.val_type());
+        } else if let Some(float @ (AtomicTyVariant::F32 | AtomicTyVariant::F64)) = atomic {
+            let is_f32 = float == AtomicTyVariant::F32;
+            let choose = |f32_op: u8, f64_op: u8| if is_f32 { f32_op } else { f64_op };
+
+            let opcode = match op {
+                BinaryOperation::Add => choose(op::F32_ADD, op::F64_ADD),
+                BinaryOperation::Subtract => choose(op::F32_SUB, op::F64_SUB),
+                BinaryOperation::Multiply => choose(op::F32_MUL, op::F64_MUL),
+                BinaryOperation::Divide => choose(op::F32_DIV, op::F64_DIV),
+
+                // WebAssembly has no instruction for the remainder of floating point numbers.
+                BinaryOperation::Modulo => {
+                    for operand in [a[0], b[0]] {
+                        self.e.code.get(operand);
+
+                        if is_f32 {
+                            self.e.code.op(op::F64_PROMOTE_F32);
+                        }
+                    }
+
+                    let fmod = self.e.g.import("fmod");
+                    self.e.code.indexed(op::CALL, fmod);
+
+                    if is_f32 {
+                        self.e.code.op(op::F32_DEMOTE_F64);
+                    }
+
+                    return self.assign(value, atomic_scalar(float).val_type());
+                }
+
+                _ => return self.unsupported(op.symbol(), &ty),
+            };
+
+            self.e.code.get(a[0]);
+            self.e.code.get(b[0]);
+            self.e.code.op(opcode);
+            self.assign(value, atomic_scalar(float).val_type());
+        } else if ty == Ty::BOOL {
+            let opcode = match op {
+                BinaryOperation::BitwiseAnd | BinaryOperation::LogicalAnd => op::I32_AND,
+                BinaryOperation::BitwiseOr | BinaryOperation::LogicalOr => op::I32_OR,
+                BinaryOperation::BitwiseXor => op::I32_XOR,
+                _ => return self.unsupported(op.symbol(), &ty),
+            };
+
+            self.e.code.get(a[0]);
+            self.e.code.get(b[0]);
+            self.e.code.op(opcode);
+            self.assign(value, ValType::I32);
+        } else {
+            self.unsupported(op.symbol(), &ty);
+        }
+    }
+
+    /// Trap on an operation that isn't supported for a type, which type checking should have ruled out.
+    fn unsupported(&mut self, op: &str, ty: &Ty) {
+        let ty = ty.display(self.e.g.cx.resolution).to_string();
+        self.trap(&format!("`{op}` isn't supported for `{ty}`"));
+    }
+
+    /// Generate a call.
+    fn call(&mut self, value: Value, callee: &Operand, args: &[Operand]) {
+        let ret = self.value_ty(value);
+        let arg_tys: Vec<Ty> = args.iter().map(|arg| self.operand_ty(arg)).collect();
+        let mut arg_values = Vec::new();
+
+        for arg in args {
+            arg_values.extend(self.operand(arg));
+        }
+
+        let Operand::Const(Const::Def(def, _)) = callee else {
+            // A function value's code takes the environment before the arguments.
+            let ty = self.operand_ty(callee);
+
+            if !matches!(ty, Ty::Function { .. }) {
+                return self.trap(&format!(
+                    "expected a function, found a value of type `{}`",
+                    ty.display(self.e.g.cx.resolution)
+                ));
+            }
+
+            let callee = self.operand(callee);
+            let ty_index = self.e.g.signature(&arg_tys, &ret, true);
+            self.e.code.get(callee[1]);
+
+            for arg in arg_values {
+                self.e.code.get(arg);
+            }
+
+            self.e.code.get(callee[0]);
+            self.e.code.call_indirect(ty_index);
+            let results = self.e.g.types[ty_index as usize].1.clone();
+            let results = self.e.take_results(&results);
+            return self.define(value, results);
+        };
+
+        match self.e.g.cx.callee(*def, &arg_tys, &ret) {
+            Callee::Instance(instance) => {
+                let function = self.e.g.instance_function(instance);
+                let results = self.e.call(function, &arg_values);
+                self.define(value, results);
+            }
+
+            Callee::Builtin(builtin) => {
+                if let Some(ty) = arg_tys.first() {
+                    let arg = self.operand(&args[0]);
+                    self.e.print(ty, &arg);
+                }
+
+                if builtin == Builtin::Println {
+                    self.e.print_constant("\n");
+                }
+
+                self.define(value, Vec::new());
+            }
+
+            Callee::Missing(message) => self.trap(&message),
+        }
+    }
+
+    /// Generate a terminator.
+    fn terminator(&mut self, terminator: &Terminator) {
+        match terminator {
+            Terminator::Goto(jump) => self.jump(jump, 0),
+
+            Terminator::Branch {
+                condition,
+                then,
+                otherwise,
+            } => {
+                let condition = self.operand(condition)[0];
+                self.e.code.get(condition);
+                self.e.code.structured(op::IF);
+                self.jump(then, 1);
+                self.e.code.op(op::ELSE);
+                self.jump(otherwise, 1);
+                self.e.code.op(op::END);
+                self.e.code.op(op::UNREACHABLE);
+            }
+
+            Terminator::Switch {
+                value,
+                cases,
+                otherwise,
+            } => {
+                let ty = self.operand_ty(value);
+                let src = self.operand(value)[0];
+                let scalar = ty.atomic().map_or(Scalar::I32, atomic_scalar);
+                let mut seen = HashSet::new();
+
+                // Cases that don't fit the type can't match, and only the first case for each value can.
+                for (case, target) in cases {
+                    let case = match ty.atomic().and_then(AtomicTyVariant::integer_bounds) {
+                        Some((min, max)) => i128::try_from(case)
+                            .ok()
+                            .filter(|case| (min..=max).contains(case)),
+                        None => u32::try_from(case).ok().map(i128::from),
+                    };
+
+                    let Some(case) = case.filter(|case| seen.insert(*case)) else {
+                        continue;
+                    };
+
+                    self.e.code.get(src);
+
+                    match scalar {
+                        Scalar::I64 => {
+                            self.e.code.i64_const(case as i64);
+                            self.e.code.op(op::I64_EQ);
+                        }
+
+                        _ => {
+                            self.e.code.i32_const(case as i32);
+                            self.e.code.op(op::I32_EQ);
+                        }
+                    }
+
+                    self.e.code.structured(op::IF);
+                    self.jump(&Jump::to(*target), 1);
+                    self.e.code.op(op::END);
+                }
+
+                self.jump(&Jump::to(*otherwise), 0);
+            }
+
+            Terminator::Return(operand) => {
+                let src = self.operand(operand);
+                self.e.ret(&src);
+            }
+
+            Terminator::Unreachable => self.trap("reached code that should be unreachable"),
+        }
+    }
+
+    /// Generate a jump to a block, from inside the given number of `if`s. The arguments are all pushed before any
+    /// of the target's parameters are set, since they can be those parameters.
+    fn jump(&mut self, jump: &Jump, depth: u32) {
+        let mut args = Vec::new();
+
+        for arg in &jump.args {
+            args.extend(self.operand(arg));
+        }
+
+        let mut params = Vec::new();
+
+        for param in &self.body.block(jump.target).params {
+            match self.values.get(param) {
+                Some(locals) => params.extend(locals.iter().copied()),
+                None => params.extend(self.e.zeros(&self.value_ty(*param))),
+            }
+        }
+
+        for arg in &args {
+            self.e.code.get(*arg);
+        }
+
+        for param in params.iter().rev() {
+            self.e.code.set(*param);
+        }
+
+        self.e.code.i32_const(jump.target.0 as i32);
+        self.e.code.set(self.next);
+
+        // Every block after the current one is still open, inside the loop.
+        let count = self.body.block_ids().count() as u32;
+        self.e
+            .code
+            .indexed(op::BR, count - 1 - self.current + depth);
+    }
+}
+
+impl super::Scalar for Scalar {
+    const I32: Self = Scalar::I32;
+    const I64: Self = Scalar::I64;
+
+    fn atomic(variant: AtomicTyVariant) -> Self {
+        atomic_scalar(variant)
+    }
+
+    fn size(self) -> u32 {
+        Scalar::size(self)
+    }
+}
+
+/// Get how a scalar of an atomic type is stored.
+const fn atomic_scalar(variant: AtomicTyVariant) -> Scalar {
+    match variant {
+        AtomicTyVariant::Bool | AtomicTyVariant::U8 => Scalar::U8,
+        AtomicTyVariant::I8 => Scalar::I8,
+        AtomicTyVariant::U16 => Scalar::U16,
+        AtomicTyVariant::I16 => Scalar::I16,
+        AtomicTyVariant::U32 | AtomicTyVariant::I32 | AtomicTyVariant::Char => Scalar::I32,
+        AtomicTyVariant::U64 | AtomicTyVariant::I64 => Scalar::I64,
+        AtomicTyVariant::F32 => Scalar::F32,
+        AtomicTyVariant::F64 => Scalar::F64,
+    }
+}
+
+/// Get the instruction that compares two values of an atomic type with a binary operation, if it's a comparison.
+/// Values of other types can only be compared with `==` and `!=` (the instruction for which is then for `i32`s).
+fn compare(op: BinaryOperation, atomic: Option<AtomicTyVariant>) -> Option<u8> {
+    let ty = atomic.map_or(ValType::I32, |atomic| atomic_scalar(atomic).val_type());
+    let is_signed = compare_as(op, atomic)? == CompareAs::Signed;
+
+    Some(match (ty, op, is_signed) {
+        (ValType::F32, BinaryOperation::Equal, _) => op::F32_EQ,
+        (ValType::F32, BinaryOperation::NotEqual, _) => op::F32_NE,
+        (ValType::F32, BinaryOperation::Less, _) => op::F32_LT,
+        (ValType::F32, BinaryOperation::LessOrEqual, _) => op::F32_LE,
+        (ValType::F32, BinaryOperation::Greater, _) => op::F32_GT,
+        (ValType::F32, BinaryOperation::GreaterOrEqual, _) => op::F32_GE,
+        (ValType::F64, BinaryOperation::Equal, _) => op::F64_EQ,
+        (ValType::F64, BinaryOperation::NotEqual, _) => op::F64_NE,
+        (ValType::F64, BinaryOperation::Less, _) => op::F64_LT,
+        (ValType::F64, BinaryOperation::LessOrEqual, _) => op::F64_LE,
+        (ValType::F64, BinaryOperation::Greater, _) => op::F64_GT,
+        (ValType::F64, BinaryOperation::GreaterOrEqual, _) => op::F64_GE,
+        (ValType::I64, BinaryOperation::Equal, _) => op::I64_EQ,
+        (ValType::I64, BinaryOperation::NotEqual, _) => op::I64_NE,
+        (ValType::I64, BinaryOperation::Less, true) => op::I64_LT_S,
+        (ValType::I64, BinaryOperation::LessOrEqual, true) => op::I64_LE_S,
+        (ValType::I64, BinaryOperation::Greater, true) => op::I64_GT_S,
+        (ValType::I64, BinaryOperation::GreaterOrEqual, true) => op::I64_GE_S,
+        (ValType::I64, BinaryOperation::Less, false) => op::I64_LT_U,
+        (ValType::I64, BinaryOperation::LessOrEqual, false) => op::I64_LE_U,
+        (ValType::I64, BinaryOperation::Greater, false) => op::I64_GT_U,
+        (ValType::I64, BinaryOperation::GreaterOrEqual, false) => op::I64_GE_U,
+        (_, BinaryOperation::Equal, _) => op::I32_EQ,
+        (_, BinaryOperation::NotEqual, _) => op::I32_NE,
+        (_, BinaryOperation::Less, true) => op::I32_LT_S,
+        (_, BinaryOperation::LessOrEqual, true) => op::I32_LE_S,
+        (_, BinaryOperation::Greater, true) => op::I32_GT_S,
+        (_, BinaryOperation::GreaterOrEqual, true) => op::I32_GE_S,
+        (_, BinaryOperation::Less, false) => op::I32_LT_U,
+        (_, BinaryOperation::LessOrEqual, false) => op::I32_LE_U,
+        (_, BinaryOperation::Greater, false) => op::I32_GT_U,
+        (_, BinaryOperation::GreaterOrEqual, false) => op::I32_GE_U,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::emit;
+    use crate::analysis::testing::lower_source;
+    use wasmi::{Caller, Engine, Extern, Linker, Module, Store};
+
+    /// What the host has seen the module do: what it printed, and the message and line of the trap that stopped it
+    /// (if any).
+    #[derive(Default)]
+    struct Host {
+        out: String,
+        trap: Option<(String, u32)>,
+    }
+
+    /// Read a string out of the memory of the module that's calling the host.
+    fn read(caller: &Caller<'_, Host>, address: i32, length: i32) -> String {
+        let memory = (caller.get_export("memory"))
+            .and_then(Extern::into_memory)
+            .unwrap();
+        let bytes = &memory.data(caller)[address as usize..(address + length) as usize];
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    /// Check a single module, and generate a WebAssembly module from it, which must be valid.
+    fn compile(source: &'static str, debug_info: bool) -> Vec<u8> {
+        let (package, program) = lower_source(source);
+        let bytes = emit(&program, &package.resolution, &package.typeck, debug_info);
+        wasmparser::validate(&bytes).unwrap();
+        bytes
+    }
+
+    /// Compile a single module and run its `main` function with wasmi, returning what it printed and the message
+    /// and line of the trap that stopped it (if any).
+    fn run_source(source: &'static str) -> (String, Option<(String, u32)>) {
+        let bytes = compile(source, true);
+        let engine = Engine::default();
+        let module = Module::new(&engine, &bytes[..]).unwrap();
+        let mut store = Store::new(&engine, Host::default());
+        let mut linker = Linker::<Host>::new(&engine);
+
+        (linker.func_wrap(
+            "wright::io",
+            "print_str",
+            |mut caller: Caller<'_, Host>, address, length| {
+                let string = read(&caller, address, length);
+                caller.data_mut().out.push_str(&string);
+            },
+        ))
+        .unwrap();
+
+        (linker.func_wrap(
+            "wright::io",
+            "print_i64",
+            |mut caller: Caller<'_, Host>, value: i64| {
+                caller.data_mut().out.push_str(&value.to_string());
+            },
+        ))
+        .unwrap();
+
+        (linker.func_wrap(
+            "wright::io",
+            "print_u64",
+            |mut caller: Caller<'_, Host>, value: i64| {
+                caller.data_mut().out.push_str(&(value as u64).to_string());
+            },
+        ))
+        .unwrap();
+
+        (linker.func_wrap(
+            "wright::io",
+            "print_f64",
+            |mut caller: Caller<'_, Host>, value: f64| {
+                caller.data_mut().out.push_str(&value.to_string());
+            },
+        ))
+        .unwrap();
+
+        (linker.func_wrap(
+            "wright::io",
+            "print_char",
+            |mut caller: Caller<'_, Host>, value: i32| {
+                let value = char::from_u32(value as u32).unwrap();
+                caller.data_mut().out.push(value);
+            },
+        ))
+        .unwrap();
+
+        (linker.func_wrap(
+            "wright::io",
+            "print_bool",
+            |mut caller: Caller<'_, Host>, value: i32| {
+                caller.data_mut().out.push_str(&(value != 0).to_string());
+            },
+        ))
+        .unwrap();
+
+        (linker.func_wrap(
+            "wright::runtime",
+            "trap",
+            |mut caller: Caller<'_, Host>, message, message_length, _: i32, _: i32, line: i32| {
+                let message = read(&caller, message, message_length);
+                caller.data_mut().trap = Some((message.clone(), line as u32));
+                Err::<(), _>(wasmi::Error::new(message))
+            },
+        ))
+        .unwrap();
+
+        (linker.func_wrap("wright::runtime", "fmod", |a: f64, b: f64| a % b)).unwrap();
+
+        let instance = (linker.instantiate(&mut store, &module))
+            .unwrap()
+            .start(&mut store)
+            .unwrap();
+        let main = instance.get_typed_func::<(), ()>(&store, "main").unwrap();
+        let result = main.call(&mut store, ());
+        let Host { out, trap } = store.into_data();
+        assert_eq!(result.is_err(), trap.is_some(), "{result:?}");
+        (out, trap)
+    }
+
+    /// Run a module, which must not trap, returning what it printed.
+    fn output(source: &'static str) -> String {
+        let (out, trap) = run_source(source);
+        assert_eq!(trap, None);
+        out
+    }
+
+    #[test]
+    fn test_arithmetic_and_calls() {
+        let out = output(
+            "use wright::io::println;\n\
+            func fib(n: u64) -> u64 { if n < 2 { n } else { fib(n - 1) + fib(n - 2) } }\n\
+            func main() {\n\
+                let zero: u8 = 0;\n\
+                let one: u16 = 1;\n\
+                let min: i64 = -9223372036854775808;\n\
+                let big: u32 = 4000000000;\n\
+                println(fib(20)); println(7 / 2); println(-7 % 3); println(~zero); println(one << 15);\n\
+                println(min); println(min % -1); println(1 == 2 || 3 != 4); println(big + 1);\n\
+                println(\"done\" == \"done\"); println(\"done\" != \"don\");\n\
+            }",
+        );
+
+        assert_eq!(
+            out,
+            "6765\n3\n-1\n255\n32768\n-9223372036854775808\n0\ntrue\n4000000001\ntrue\ntrue\n"
+        );
+    }
+
+    #[test]
+    fn test_loops_and_slots() {
+        let out = output(
+            "use wright::io::println;\n\
+            func main() {\n\
+                var total: u64 = 0;\n\
+                for i in 1..=10 { total += i; }\n\
+                var n = 0;\n\
+                while n < 3 { n += 1; }\n\
+                println(total); println(n);\n\
+            }",
+        );
+
+        assert_eq!(out, "55\n3\n");
+    }
+
+    #[test]
+    fn test_records_and_generics() {
+        let out = output(
+            "use wright::io::println;\n\
+            record Point { x: u64, y: u64 }\n\
+            record Box<T> { value: T }\n\
+            impl Point { func grow(self: @mut Self) { self.x = self.x + 1; } }\n\
+            trait Shape { func area(self: @Self) -> u64; func double(self: @Self) -> u64 { self.area() * 2 } }\n\
+            impl Shape for Point { func area(self: @Self) -> u64 { self.x * self.y } }\n\
+            func total<T: Shape>(shape: @T) -> u64 { shape.area() + shape.double() }\n\
+            func unbox<T>(b: Box<T>) -> T { b.value }\n\
+            func main() {\n\
+                var p = Point { y: 3, x: 1 };\n\
+                let q = p;\n\
+                p.grow();\n\
+                println(p); println(q.x); println(p == q); println(@p != @q);\n\
+                println(p.double()); println(total(@p));\n\
+                println(unbox(Box { value: true })); println(Box { value: p });\n\
+            }",
+        );
+
+        assert_eq!(
+            out,
+            "Point { x: 2, y: 3 }\n1\nfalse\ntrue\n12\n18\ntrue\nBox<Point> { value: Point { x: 2, y: 3 } }\n"
+        );
+    }
+
+    #[test]
+    fn test_unions_and_enums() {
+        let out = output(
+            "use wright::io::println;\n\
+            union Number { small: u8, big: u64 }\n\
+            enum Color { Red, Green, Blue }\n\
+            func size(n: Number) -> u64 { match n { Number { small: 0 } => 0, Number { big } => big, _ => 1 } }\n\
+            func code(color: Color) -> u8 { match color { Color::Red => 1, Color::Green | Color::Blue => 2 } }\n\
+            func main() {\n\
+                println(size(Number { small: 0 })); println(size(Number { small: 5 })); println(size(Number { big: 9 }));\n\
+                println(code(Color::Red)); println(code(Color::Blue)); println(Color::Green);\n\
+                println(Number { big: 4 }); println(Number { small: 4 } is u8);\n\
+                println(Number { small: 4 } == Number { small: 4 }); println(Color::Red == Color::Blue);\n\
+            }",
+        );
+
+        assert_eq!(out, "0\n1\n9\n1\n2\nColor::Green\nNumber { big: 4 }\ntrue\ntrue\nfalse\n");
+    }
+
+    #[test]
+    fn test_closures() {
+        let out = output(
+            "use wright::io::println;\n\
+            func apply(f: func(u8) -> u8, x: u8) -> u8 { f(x) }\n\
+            func double(x: u8) -> u8 { x * 2 }\n\
+            func main() {\n\
+                let offset = 3;\n\
+                var count = 0;\n\
+                let add = func(x: u8) -> u8 { count += 1; x + offset };\n\
+                println(apply(add, 4) + apply(add, 1) + count);\n\
+                println(apply(double, 5));\n\
+                let print = println;\n\
+                print(\"done\");\n\
+                println(double);\n\
+            }",
+        );
+
+        assert_eq!(out, "13\n10\ndone\n<func double>\n");
+    }
+
+    #[test]
+    fn test_traps() {
+        let cases = [
+            ("func main() -> u8 { let x: u8 = 255; x + 1 }", "arithmetic overflow"),
+            ("func main() -> i8 { let x: i8 = -128; -x }", "arithmetic overflow"),
+            ("func main() -> u32 { let x: u32 = 65536; x * x }", "arithmetic overflow"),
+            (
+                "func main() -> i64 { let x: i64 = 9223372036854775807; x + 1 }",
+                "arithmetic overflow",
+            ),
+            ("func main() -> u64 { let x: u64 = 4294967296; x * x }", "arithmetic overflow"),
+            (
+                "func main() -> i64 { let x: i64 = -9223372036854775808; x / -1 }",
+                "arithmetic overflow",
+            ),
+            (
+                "func main() -> i64 { let x: i64 = -9223372036854775808; x * -1 }",
+                "arithmetic overflow",
+            ),
+            ("func main() -> u32 { let x: u32 = 0; 1 / x }", "division by zero"),
+            (
+                "func main() -> u8 { let x: u8 = 8; 1 << x }",
+                "shift by at least the number of bits in the type",
+            ),
+        ];
+
+        for (source, message) in cases {
+            let (_, trap) = run_source(source);
+            assert_eq!(trap.map(|(message, _)| message), Some(message.to_owned()), "{source}");
+        }
+    }
+
+    #[test]
+    fn test_traps_stop_the_program() {
+        let (out, trap) = run_source(
+            "use wright::io::println;\n\
+            func add(x: u8, y: u8) -> u8 {\n\
+                x + y\n\
+            }\n\
+            func main() { println(\"before\"); println(add(200, 100)); println(\"after\"); }",
+        );
+
+        assert_eq!(out, "before\n");
+        assert_eq!(trap, Some(("arithmetic overflow".to_owned(), 3)));
+    }
+
+    #[test]
+    fn test_sections() {
+        let source = "func helper() -> u8 { 1 } func main() -> u8 { helper() }";
+        let mut names = Vec::new();
+        let mut imports = Vec::new();
+        let mut exports = Vec::new();
+
+        for payload in wasmparser::Parser::new(0).parse_all(&compile(source, true)) {
+            match payload.unwrap() {
+                wasmparser::Payload::ImportSection(reader) => {
+                    for import in reader {
+                        let import = import.unwrap();
+                        imports.push(format!("{}::{}", import.module, import.name));
+                    }
+                }
+
+                wasmparser::Payload::ExportSection(reader) => {
+                    for export in reader {
+                        exports.push(export.unwrap().name.to_owned());
+                    }
+                }
+
+                wasmparser::Payload::CustomSection(reader) => {
+                    let wasmparser::KnownCustom::Name(reader) = reader.as_known() else {
+                        continue;
+                    };
+
+                    for name in reader {
+                        if let wasmparser::Name::Function(map) = name.unwrap() {
+                            names.extend(
+                                map.into_iter()
+                                    .map(|naming| naming.unwrap().name.to_owned()),
+                            );
+                        }
+                    }
+                }
+
+                _ => {}
+            }
+        }
+
+        assert!(imports.contains(&"wright::io::print_str".to_owned()));
+        assert!(imports.contains(&"wright::runtime::trap".to_owned()));
+        assert_eq!(exports, ["memory", "main"]);
+        assert!(names.contains(&"helper".to_owned()), "{names:?}");
+
+        // Without debug info, there's no `name` section.
+        let stripped = compile(source, false);
+        assert!(stripped.len() < compile(source, true).len());
+        assert!(!stripped.windows(4).any(|window| window == b"name"));
+    }
+}
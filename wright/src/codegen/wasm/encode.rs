@@ -0,0 +1,289 @@
+//! Encoding WebAssembly's binary format: numbers, sections, and the instructions of function bodies.
+
+/// The type of a WebAssembly value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(super) enum ValType {
+    /// A 32-bit integer (which is also a pointer, or a smaller integer, `bool`, or `char`).
+    I32,
+    /// A 64-bit integer.
+    I64,
+    /// A 32-bit float.
+    F32,
+    /// A 64-bit float.
+    F64,
+}
+
+impl ValType {
+    /// The byte that encodes this type.
+    pub const fn byte(self) -> u8 {
+        match self {
+            ValType::I32 => 0x7F,
+            ValType::I64 => 0x7E,
+            ValType::F32 => 0x7D,
+            ValType::F64 => 0x7C,
+        }
+    }
+}
+
+/// The opcodes of the instructions that the backend uses.
+#[allow(missing_docs)]
+pub(super) mod op {
+    pub const UNREACHABLE: u8 = 0x00;
+    pub const BLOCK: u8 = 0x02;
+    pub const LOOP: u8 = 0x03;
+    pub const IF: u8 = 0x04;
+    pub const ELSE: u8 = 0x05;
+    pub const END: u8 = 0x0B;
+    pub const BR: u8 = 0x0C;
+    pub const BR_TABLE: u8 = 0x0E;
+    pub const RETURN: u8 = 0x0F;
+    pub const CALL: u8 = 0x10;
+    pub const CALL_INDIRECT: u8 = 0x11;
+    pub const DROP: u8 = 0x1A;
+    pub const LOCAL_GET: u8 = 0x20;
+    pub const LOCAL_SET: u8 = 0x21;
+    pub const LOCAL_TEE: u8 = 0x22;
+    pub const GLOBAL_GET: u8 = 0x23;
+    pub const GLOBAL_SET: u8 = 0x24;
+    pub const I32_LOAD: u8 = 0x28;
+    pub const I64_LOAD: u8 = 0x29;
+    pub const F32_LOAD: u8 = 0x2A;
+    pub const F64_LOAD: u8 = 0x2B;
+    pub const I32_LOAD8_S: u8 = 0x2C;
+    pub const I32_LOAD8_U: u8 = 0x2D;
+    pub const I32_LOAD16_S: u8 = 0x2E;
+    pub const I32_LOAD16_U: u8 = 0x2F;
+    pub const I32_STORE: u8 = 0x36;
+    pub const I64_STORE: u8 = 0x37;
+    pub const F32_STORE: u8 = 0x38;
+    pub const F64_STORE: u8 = 0x39;
+    pub const I32_STORE8: u8 = 0x3A;
+    pub const I32_STORE16: u8 = 0x3B;
+    pub const MEMORY_SIZE: u8 = 0x3F;
+    pub const MEMORY_GROW: u8 = 0x40;
+    pub const I32_CONST: u8 = 0x41;
+    pub const I64_CONST: u8 = 0x42;
+    pub const I32_EQZ: u8 = 0x45;
+    pub const I32_EQ: u8 = 0x46;
+    pub const I32_NE: u8 = 0x47;
+    pub const I32_LT_S: u8 = 0x48;
+    pub const I32_LT_U: u8 = 0x49;
+    pub const I32_GT_S: u8 = 0x4A;
+    pub const I32_GT_U: u8 = 0x4B;
+    pub const I32_LE_S: u8 = 0x4C;
+    pub const I32_LE_U: u8 = 0x4D;
+    pub const I32_GE_S: u8 = 0x4E;
+    pub const I32_GE_U: u8 = 0x4F;
+    pub const I64_EQZ: u8 = 0x50;
+    pub const I64_EQ: u8 = 0x51;
+    pub const I64_NE: u8 = 0x52;
+    pub const I64_LT_S: u8 = 0x53;
+    pub const I64_LT_U: u8 = 0x54;
+    pub const I64_GT_S: u8 = 0x55;
+    pub const I64_GT_U: u8 = 0x56;
+    pub const I64_LE_S: u8 = 0x57;
+    pub const I64_LE_U: u8 = 0x58;
+    pub const I64_GE_S: u8 = 0x59;
+    pub const I64_GE_U: u8 = 0x5A;
+    pub const F32_EQ: u8 = 0x5B;
+    pub const F32_NE: u8 = 0x5C;
+    pub const F32_LT: u8 = 0x5D;
+    pub const F32_GT: u8 = 0x5E;
+    pub const F32_LE: u8 = 0x5F;
+    pub const F32_GE: u8 = 0x60;
+    pub const F64_EQ: u8 = 0x61;
+    pub const F64_NE: u8 = 0x62;
+    pub const F64_LT: u8 = 0x63;
+    pub const F64_GT: u8 = 0x64;
+    pub const F64_LE: u8 = 0x65;
+    pub const F64_GE: u8 = 0x66;
+    pub const I32_ADD: u8 = 0x6A;
+    pub const I32_SUB: u8 = 0x6B;
+    pub const I32_MUL: u8 = 0x6C;
+    pub const I32_DIV_S: u8 = 0x6D;
+    pub const I32_DIV_U: u8 = 0x6E;
+    pub const I32_REM_S: u8 = 0x6F;
+    pub const I32_REM_U: u8 = 0x70;
+    pub const I32_AND: u8 = 0x71;
+    pub const I32_OR: u8 = 0x72;
+    pub const I32_XOR: u8 = 0x73;
+    pub const I32_SHL: u8 = 0x74;
+    pub const I32_SHR_S: u8 = 0x75;
+    pub const I32_SHR_U: u8 = 0x76;
+    pub const I64_ADD: u8 = 0x7C;
+    pub const I64_SUB: u8 = 0x7D;
+    pub const I64_MUL: u8 = 0x7E;
+    pub const I64_DIV_S: u8 = 0x7F;
+    pub const I64_DIV_U: u8 = 0x80;
+    pub const I64_REM_S: u8 = 0x81;
+    pub const I64_REM_U: u8 = 0x82;
+    pub const I64_AND: u8 = 0x83;
+    pub const I64_OR: u8 = 0x84;
+    pub const I64_XOR: u8 = 0x85;
+    pub const I64_SHL: u8 = 0x86;
+    pub const I64_SHR_S: u8 = 0x87;
+    pub const I64_SHR_U: u8 = 0x88;
+    pub const F32_NEG: u8 = 0x8C;
+    pub const F32_ADD: u8 = 0x92;
+    pub const F32_SUB: u8 = 0x93;
+    pub const F32_MUL: u8 = 0x94;
+    pub const F32_DIV: u8 = 0x95;
+    pub const F64_NEG: u8 = 0x9A;
+    pub const F64_ADD: u8 = 0xA0;
+    pub const F64_SUB: u8 = 0xA1;
+    pub const F64_MUL: u8 = 0xA2;
+    pub const F64_DIV: u8 = 0xA3;
+    pub const I32_WRAP_I64: u8 = 0xA7;
+    pub const I64_EXTEND_I32_S: u8 = 0xAC;
+    pub const I64_EXTEND_I32_U: u8 = 0xAD;
+    pub const F32_DEMOTE_F64: u8 = 0xB6;
+    pub const F64_PROMOTE_F32: u8 = 0xBB;
+    pub const I32_EXTEND8_S: u8 = 0xC0;
+    pub const I32_EXTEND16_S: u8 = 0xC1;
+    pub const I64_EXTEND32_S: u8 = 0xC4;
+}
+
+/// Append an unsigned LEB128 number.
+pub(super) fn u32(out: &mut Vec<u8>, mut value: u32) {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+
+        if value == 0 {
+            return out.push(byte);
+        }
+
+        out.push(byte | 0x80);
+    }
+}
+
+/// Append a signed LEB128 number.
+pub(super) fn i64(out: &mut Vec<u8>, mut value: i64) {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+
+        // Stop once the rest of the number is the sign bit of this byte.
+        if (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0) {
+            return out.push(byte);
+        }
+
+        out.push(byte | 0x80);
+    }
+}
+
+/// Append a name (or any string), which is its length followed by its UTF-8 bytes.
+pub(super) fn name(out: &mut Vec<u8>, name: &str) {
+    u32(out, name.len() as u32);
+    out.extend_from_slice(name.as_bytes());
+}
+
+/// Append a section (or subsection), which is its ID and size followed by its contents.
+pub(super) fn section(out: &mut Vec<u8>, id: u8, contents: &[u8]) {
+    out.push(id);
+    u32(out, contents.len() as u32);
+    out.extend_from_slice(contents);
+}
+
+/// The instructions of a function body, as they're added.
+#[derive(Debug, Default)]
+pub(super) struct Code {
+    /// The encoded instructions.
+    pub bytes: Vec<u8>,
+}
+
+impl Code {
+    /// Add an instruction without immediates.
+    pub fn op(&mut self, op: u8) {
+        self.bytes.push(op);
+    }
+
+    /// Add an instruction with an index (of a local, global, function, or label) as its immediate.
+    pub fn indexed(&mut self, op: u8, index: u32) {
+        self.bytes.push(op);
+        u32(&mut self.bytes, index);
+    }
+
+    /// Get a local.
+    pub fn get(&mut self, local: u32) {
+        self.indexed(op::LOCAL_GET, local);
+    }
+
+    /// Set a local to the value on top of the stack.
+    pub fn set(&mut self, local: u32) {
+        self.indexed(op::LOCAL_SET, local);
+    }
+
+    /// Add a `block`, `loop`, or `if` without parameters or results.
+    pub fn structured(&mut self, op: u8) {
+        self.bytes.extend([op, 0x40]);
+    }
+
+    /// Add an `i32.const`.
+    pub fn i32_const(&mut self, value: i32) {
+        self.bytes.push(op::I32_CONST);
+        i64(&mut self.bytes, value.into());
+    }
+
+    /// Add an `i64.const`.
+    pub fn i64_const(&mut self, value: i64) {
+        self.bytes.push(op::I64_CONST);
+        i64(&mut self.bytes, value);
+    }
+
+    /// Add a load or store, with the log2 of its alignment and its offset from the address.
+    pub fn memory(&mut self, op: u8, align: u32, offset: u32) {
+        self.bytes.push(op);
+        u32(&mut self.bytes, align);
+        u32(&mut self.bytes, offset);
+    }
+
+    /// Add a `call_indirect` of a function in the table, with the index of its type.
+    pub fn call_indirect(&mut self, ty: u32) {
+        self.indexed(op::CALL_INDIRECT, ty);
+        self.bytes.push(0);
+    }
+
+    /// Add a `br_table` to the given labels, or to the default label if the index is past them.
+    pub fn br_table(&mut self, labels: &[u32], default: u32) {
+        self.bytes.push(op::BR_TABLE);
+        u32(&mut self.bytes, labels.len() as u32);
+
+        for label in labels.iter().chain([&default]) {
+            u32(&mut self.bytes, *label);
+        }
+    }
+
+    /// Add a `memory.size` or `memory.grow`.
+    pub fn memory_size_or_grow(&mut self, op: u8) {
+        self.bytes.extend([op, 0]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{i64, u32};
+
+    #[test]
+    fn test_leb128() {
+        let encode_u32 = |value| {
+            let mut out = Vec::new();
+            u32(&mut out, value);
+            out
+        };
+
+        let encode_i64 = |value| {
+            let mut out = Vec::new();
+            i64(&mut out, value);
+            out
+        };
+
+        assert_eq!(encode_u32(0), [0]);
+        assert_eq!(encode_u32(127), [0x7F]);
+        assert_eq!(encode_u32(624485), [0xE5, 0x8E, 0x26]);
+        assert_eq!(encode_i64(-1), [0x7F]);
+        assert_eq!(encode_i64(63), [0x3F]);
+        assert_eq!(encode_i64(64), [0xC0, 0x00]);
+        assert_eq!(encode_i64(-123456), [0xC0, 0xBB, 0x78]);
+    }
+}
@@ -0,0 +1,110 @@
+//! HTML documentation generation for wright source code.
+//!
+//! The generator loads a [package] the same way the compiler does, and writes one HTML page per module (with an
+//! anchor for every item) to an output directory. Doc comments are rendered from Markdown, and paths in item
+//! signatures are linked to the items that [name resolution] resolves them to.
+//!
+//! [package]: crate::package
+//! [name resolution]: crate::analysis::resolve
+
+use crate::{
+    analysis::resolve::resolve,
+    package::{find_package_root, load_package},
+    reporting::Diagnostic,
+    source_tracking::SourceMap,
+};
+use std::{fs, io, path::Path};
+
+pub mod index;
+mod render;
+
+use index::ItemIndex;
+
+/// The wright logo, copied into every generated documentation directory. The assets are kept in the crate (copies
+/// of the ones in `pages/static/assets`) so that it can be packaged on its own.
+const LOGO_SVG: &[u8] = include_bytes!("../assets/wright_logo.svg");
+
+/// The wright favicon, copied into every generated documentation directory.
+const FAVICON_PNG: &[u8] = include_bytes!("../assets/favicon.png");
+
+/// Generate HTML documentation for the package that `path` (its root file, or any file or directory in it) is
+/// part of, writing it to `output_dir`.
+///
+/// The documentation for the root module is written to `index.html` in the `output_dir`. Any [Diagnostic]s
+/// produced while loading the package and resolving names in it are returned -- modules that fail to parse are
+/// left out of the documentation, and paths that can't be resolved aren't linked.
+pub fn generate(map: &SourceMap, path: &Path, output_dir: &Path) -> io::Result<Vec<Diagnostic>> {
+    let package = load_package(map, &find_package_root(path)?)?;
+    let resolution = resolve(package.module_paths());
+    let index = ItemIndex::build(&package.modules, &resolution);
+
+    let static_dir = output_dir.join("static");
+    fs::create_dir_all(&static_dir)?;
+    fs::write(static_dir.join("wright_logo.svg"), LOGO_SVG)?;
+    fs::write(static_dir.join("favicon.png"), FAVICON_PNG)?;
+    fs::write(static_dir.join("style.css"), render::STYLESHEET)?;
+
+    for module_path in index.modules() {
+        let page = output_dir.join(index::page_for(module_path));
+        // Pages are always nested in the output directory.
+        fs::create_dir_all(page.parent().unwrap_or(output_dir))?;
+        fs::write(page, render::module_page(&index, module_path))?;
+    }
+
+    Ok(package
+        .diagnostics
+        .into_iter()
+        .chain(resolution.diagnostics)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::generate;
+    use crate::source_tracking::SourceMap;
+    use std::fs;
+
+    #[test]
+    fn test_generate() {
+        let dir = std::env::temp_dir().join(format!("wright-doc-test-{}", std::process::id()));
+        let (input, output) = (dir.join("src"), dir.join("out"));
+        fs::create_dir_all(input.join("math")).unwrap();
+
+        fs::write(
+            input.join("main.wr"),
+            "//! The *root* module.\n\nmod math;\nuse math::EvenU8 as Even;\n\n\
+            /// Returns an [EvenU8](math::EvenU8).\npub func half(n: Even) -> u8;\n",
+        )
+        .unwrap();
+
+        fs::write(
+            input.join("math/mod.wr"),
+            "/// Is `n` even?\npub pure func is_even(n: u8) -> bool;\n\
+            /// An even byte.\npub type EvenU8 = u8 constrain is_even;\n",
+        )
+        .unwrap();
+
+        // Files that no `mod` declaration includes aren't part of the package.
+        fs::write(input.join("stray.wr"), "pub func stray();\n").unwrap();
+
+        let map = SourceMap::new();
+        let diagnostics = generate(&map, &input, &output).unwrap();
+        assert!(diagnostics.is_empty());
+
+        let root = fs::read_to_string(output.join("index.html")).unwrap();
+        assert!(root.contains("<em>root</em>"));
+        assert!(root.contains(r##"<a href="math/index.html#type.EvenU8">EvenU8</a>"##));
+        assert!(root.contains(r##"<a href="math/index.html#type.EvenU8">Even</a>"##));
+        assert!(root.contains(r#"<a href="math/index.html">math</a>"#));
+        assert!(!root.contains("stray"));
+        assert!(!output.join("main").exists());
+        assert!(!output.join("stray").exists());
+
+        let math = fs::read_to_string(output.join("math/index.html")).unwrap();
+        assert!(math.contains(r#"id="func.is_even""#));
+        assert!(math.contains(r##"constrain</span> <a href="#func.is_even">is_even</a>"##));
+        assert!(output.join("static/wright_logo.svg").exists());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,250 @@
+//! An index of every documented module, used to find the pages and anchors that resolved paths link to.
+
+use crate::{
+    analysis::resolve::{DefId, DefKind, Resolution},
+    ast::{
+        decl::{Decl, module::ModuleDecl},
+        module::Module,
+    },
+    package::PackageModule,
+    source_tracking::fragment::Fragment,
+};
+use std::{collections::BTreeMap, path::PathBuf};
+
+/// The different kinds of item that get their own anchor (or page) in generated documentation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(missing_docs)]
+pub enum ItemKind {
+    Module,
+    TypeAlias,
+    Constant,
+    Function,
+    Constraint,
+    Record,
+    Union,
+    Enum,
+    Trait,
+}
+
+impl ItemKind {
+    /// Get the kind of item a declaration introduces, or [None] if it's an import or `impl` block (which are not
+    /// their own items) or a module (which gets its own page rather than an anchor).
+    pub fn of(decl: &Decl) -> Option<Self> {
+        match decl {
+            Decl::Import(_) | Decl::Module(_) | Decl::Impl(_) => None,
+            Decl::TypeAlias(_) => Some(ItemKind::TypeAlias),
+            Decl::Constant(_) => Some(ItemKind::Constant),
+            Decl::Function(_) => Some(ItemKind::Function),
+            Decl::Constraint(_) => Some(ItemKind::Constraint),
+            Decl::Record(_) => Some(ItemKind::Record),
+            Decl::Union(_) => Some(ItemKind::Union),
+            Decl::Enum(_) => Some(ItemKind::Enum),
+            Decl::Trait(_) => Some(ItemKind::Trait),
+        }
+    }
+
+    /// Get the prefix used for the HTML anchors of items of this kind, i.e. `func` for `#func.main`.
+    pub const fn anchor_prefix(self) -> &'static str {
+        match self {
+            ItemKind::Module => "mod",
+            ItemKind::TypeAlias => "type",
+            ItemKind::Constant => "const",
+            ItemKind::Function => "func",
+            ItemKind::Constraint => "constraint",
+            ItemKind::Record => "record",
+            ItemKind::Union => "union",
+            ItemKind::Enum => "enum",
+            ItemKind::Trait => "trait",
+        }
+    }
+}
+
+/// The path (relative to the output directory) of the page documenting a module.
+pub fn page_for(module_path: &[String]) -> PathBuf {
+    module_path.iter().collect::<PathBuf>().join("index.html")
+}
+
+/// An index of all the modules being documented, along with the resolution of every name in them.
+#[derive(Debug)]
+pub struct ItemIndex<'a> {
+    /// Every module, including modules that only exist because they have documented submodules.
+    modules: BTreeMap<Vec<String>, Option<&'a Module>>,
+
+    /// The result of name resolution over the documented modules.
+    res: &'a Resolution,
+}
+
+impl<'a> ItemIndex<'a> {
+    /// Build an index of the given modules (including inline modules declared in them), whose names have been
+    /// resolved in `res`.
+    pub fn build(modules: &'a [PackageModule], res: &'a Resolution) -> Self {
+        let mut index = ItemIndex {
+            modules: BTreeMap::from([(Vec::new(), None)]),
+            res,
+        };
+
+        for PackageModule { path, module } in modules {
+            // Make sure every ancestor module gets a page.
+            for depth in 1..path.len() {
+                index.modules.entry(path[..depth].to_vec()).or_default();
+            }
+
+            index.add_module(path.clone(), module);
+        }
+
+        index
+    }
+
+    /// Add a module to the index, including any inline modules declared in it.
+    fn add_module(&mut self, path: Vec<String>, module: &'a Module) {
+        for decl in &module.items {
+            if let Decl::Module(ModuleDecl {
+                name,
+                body: Some(body),
+                ..
+            }) = decl
+            {
+                let mut inner = path.clone();
+                inner.push(name.fragment.as_str().to_owned());
+                self.add_module(inner, body);
+            }
+        }
+
+        self.modules.insert(path, Some(module));
+    }
+
+    /// Iterate over the paths of every module in the index, parents before children.
+    pub fn modules(&self) -> impl Iterator<Item = &Vec<String>> {
+        self.modules.keys()
+    }
+
+    /// Get the parsed source of a module, if there is one.
+    pub fn module(&self, path: &[String]) -> Option<&'a Module> {
+        self.modules.get(path).copied().flatten()
+    }
+
+    /// Get the paths of the direct children of a module.
+    pub fn children<'s>(&'s self, parent: &'s [String]) -> impl Iterator<Item = &'s Vec<String>> {
+        self.modules
+            .keys()
+            .filter(move |path| path.len() == parent.len() + 1 && path.starts_with(parent))
+    }
+
+    /// Get the full path and kind of the documented item (or module) that a definition is. Enum variants are
+    /// documented with their enum.
+    fn item(&self, def: DefId) -> Option<(Vec<String>, ItemKind)> {
+        let definition = self.res.def(def);
+
+        let kind = match definition.kind {
+            DefKind::Module => {
+                let path = self.res.module_path(def)?;
+                return self
+                    .modules
+                    .contains_key(&path)
+                    .then_some((path, ItemKind::Module));
+            }
+
+            DefKind::Variant => return self.item(self.res.enum_of(def)?),
+            DefKind::TypeAlias => ItemKind::TypeAlias,
+            DefKind::Constant => ItemKind::Constant,
+            DefKind::Function => ItemKind::Function,
+            DefKind::Constraint => ItemKind::Constraint,
+            DefKind::Record => ItemKind::Record,
+            DefKind::Union => ItemKind::Union,
+            DefKind::Enum => ItemKind::Enum,
+            DefKind::Trait => ItemKind::Trait,
+            _ => return None,
+        };
+
+        // Builtin functions aren't declared in any documented module.
+        if self.res.builtin(def).is_some() || !self.modules.contains_key(&definition.module) {
+            return None;
+        }
+
+        let mut path = definition.module.clone();
+        path.push(definition.name.clone());
+        Some((path, kind))
+    }
+
+    /// Get the full path and kind of the item that a path in the source (see [Resolution::resolved]) resolved to.
+    pub fn resolved(&self, use_site: &Fragment) -> Option<(Vec<String>, ItemKind)> {
+        self.item(self.res.resolved(use_site)?)
+    }
+
+    /// Look up a path written in the module at `from` (see [Resolution::lookup]), getting the full path and kind
+    /// of the item it refers to.
+    pub fn resolve(&self, from: &[String], path: &[&str]) -> Option<(Vec<String>, ItemKind)> {
+        self.item(self.res.lookup(from, path)?)
+    }
+
+    /// Get the link to an item (or module) from the page of the module at `from`.
+    pub fn href(&self, from: &[String], target: &[String], kind: ItemKind) -> String {
+        if kind == ItemKind::Module {
+            return format!("{}{}", "../".repeat(from.len()), url_for(&page_for(target)));
+        }
+
+        let (name, module) = target.split_last().expect("items always have a name");
+        let anchor = format!("#{}.{name}", kind.anchor_prefix());
+
+        if module == from {
+            anchor
+        } else {
+            format!("{}{}{anchor}", "../".repeat(from.len()), url_for(&page_for(module)))
+        }
+    }
+}
+
+/// Convert a relative file path into a relative URL (always using `/` as a separator).
+fn url_for(path: &std::path::Path) -> String {
+    path.iter()
+        .map(|component| component.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ItemIndex, ItemKind};
+    use crate::{
+        analysis::resolve::resolve, ast::module::Module, lexer::Lexer, package::PackageModule,
+        parser::Parser,
+    };
+
+    fn module(path: &[&str], source: &'static str) -> PackageModule {
+        PackageModule {
+            path: path.iter().map(|s| s.to_string()).collect(),
+            module: Module::parse(&mut Parser::new(Lexer::new_test(source))).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_resolve() {
+        let modules = [
+            module(&[], "use net::http::Request as Req; func main();"),
+            module(&["net", "http"], "pub type Request; pub enum Method { Get }"),
+        ];
+
+        let res = resolve(modules.iter().map(|m| (m.path.clone(), &m.module)));
+        let index = ItemIndex::build(&modules, &res);
+        let root: Vec<String> = Vec::new();
+        let request = vec!["net".to_owned(), "http".to_owned(), "Request".to_owned()];
+
+        assert_eq!(index.modules().count(), 3);
+        assert_eq!(index.resolve(&root, &["Req"]), Some((request.clone(), ItemKind::TypeAlias)));
+        assert_eq!(index.resolve(&root, &["main"]).unwrap().1, ItemKind::Function);
+        assert_eq!(index.resolve(&request[..1], &["http"]).unwrap().1, ItemKind::Module);
+        assert_eq!(
+            index.resolve(&request[..2], &["Method", "Get"]).unwrap().0,
+            ["net", "http", "Method"]
+        );
+        assert!(index.resolve(&root, &["Request"]).is_none());
+        assert!(index.resolve(&root, &["print"]).is_none());
+
+        assert_eq!(
+            index.href(&root, &request, ItemKind::TypeAlias),
+            "net/http/index.html#type.Request"
+        );
+        assert_eq!(index.href(&request[..2], &request, ItemKind::TypeAlias), "#type.Request");
+        assert_eq!(index.href(&request[..2], &[], ItemKind::Module), "../../index.html");
+    }
+}
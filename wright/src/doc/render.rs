@@ -0,0 +1,673 @@
+//! Rendering of documentation pages to HTML.
+
+use super::index::{ItemIndex, ItemKind};
+use crate::ast::{
+    decl::{
+        Decl, Visibility, constraint::ConstraintDecl, func::FuncDecl, generic::GenericParam,
+        import::ImportDecl, record::FieldDecl, repr::Repr,
+    },
+    docs::Docs,
+    identifier::Identifier,
+    module::Module,
+    path::Path,
+    ty::Type,
+};
+use pulldown_cmark::{BrokenLink, CowStr, Event, Options, Tag};
+use std::fmt::Write;
+
+/// The stylesheet shared by all generated pages.
+pub const STYLESHEET: &str = "\
+body { margin: 0; display: flex; font-family: sans-serif; line-height: 1.5; color: #222; }
+nav.sidebar { min-width: 14em; padding: 1em; background: #f5f3f7; border-right: 1px solid #ddd; }
+nav.sidebar img.logo { width: 8em; display: block; margin: 0 auto 1em; }
+nav.sidebar ul { list-style: none; padding: 0; }
+main { padding: 1em 2em; max-width: 60em; }
+h1 .path { font-family: monospace; }
+h2 { border-bottom: 1px solid #ddd; }
+pre.signature { background: #f5f3f7; padding: 0.5em 1em; overflow-x: auto; }
+pre.signature .kw { color: #8959a8; font-weight: bold; }
+section.item { margin-bottom: 2em; }
+table.summary td { padding: 0.2em 1em 0.2em 0; vertical-align: top; }
+code, pre { font-family: monospace; }
+a { color: #3873ad; text-decoration: none; }
+a:hover { text-decoration: underline; }
+";
+
+/// Escape text for inclusion in HTML.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// Format a module path for display, using `root` for the root module.
+fn display_module(path: &[String]) -> String {
+    if path.is_empty() {
+        "root".to_owned()
+    } else {
+        path.join("::")
+    }
+}
+
+/// The context that a page is rendered in -- used for resolving and linking paths.
+struct PageContext<'i, 'a> {
+    /// The index of all the documented items.
+    index: &'i ItemIndex<'a>,
+
+    /// The path of the module that this page documents.
+    module: &'i [String],
+
+    /// The names of generic parameters in scope, which should never be linked.
+    generics: Vec<&'i str>,
+}
+
+impl PageContext<'_, '_> {
+    /// Get a link to the item that a path written in this page's module refers to, if it can be resolved.
+    fn link_to(&self, segments: &[&str]) -> Option<String> {
+        if segments.len() == 1 && self.generics.contains(&segments[0]) {
+            return None;
+        }
+
+        self.index
+            .resolve(self.module, segments)
+            .map(|(target, kind)| self.index.href(self.module, &target, kind))
+    }
+
+    /// Get a link to the item named by a Markdown link (i.e. `[net::Request]` or ``[`is_even()`]``).
+    fn link_to_reference(&self, reference: &str) -> Option<String> {
+        let reference = reference.trim_matches('`');
+        let reference = reference.strip_suffix("()").unwrap_or(reference);
+        let segments: Vec<&str> = reference.split("::").collect();
+
+        let is_identifier = |segment: &&str| {
+            !segment.is_empty() && segment.chars().all(|c| c.is_alphanumeric() || c == '_')
+        };
+
+        if !segments.iter().all(is_identifier) {
+            return None;
+        }
+
+        self.link_to(&segments)
+    }
+
+    /// Render a [Path], linking to the item that name resolution resolved it to (if it's documented).
+    fn path(&self, path: &Path, out: &mut String) {
+        let segments: Vec<&str> = std::iter::once(&path.head)
+            .chain(&path.tail)
+            .map(|segment| segment.fragment.as_str())
+            .collect();
+
+        let text = escape(&segments.join("::"));
+
+        let href = (self.index.resolved(&path.full_path))
+            .map(|(target, kind)| self.index.href(self.module, &target, kind));
+
+        match href {
+            Some(href) => write!(out, r#"<a href="{href}">{text}</a>"#).unwrap(),
+            None => out.push_str(&text),
+        }
+    }
+
+    /// Render a type signature.
+    fn ty(&self, ty: &Type, out: &mut String) {
+        match ty {
+            Type::Atomic(atomic) => {
+                write!(out, r#"<span class="kw">{}</span>"#, atomic.matching_source.as_str())
+                    .unwrap()
+            }
+
+            Type::Reference(reference) => {
+                out.push('@');
+
+                if reference.is_mutable {
+                    out.push_str(r#"<span class="kw">mut</span> "#);
+                }
+
+                self.ty(&reference.target_ty, out);
+            }
+
+            Type::Named(named) => {
+                self.path(&named.name, out);
+
+                if !named.generic_tys.is_empty() {
+                    out.push_str("&lt;");
+
+                    for (index, generic) in named.generic_tys.iter().enumerate() {
+                        if index > 0 {
+                            out.push_str(", ");
+                        }
+
+                        self.ty(generic, out);
+                    }
+
+                    out.push_str("&gt;");
+                }
+            }
+
+            Type::Constrained(constrained) => {
+                self.ty(&constrained.base_ty, out);
+                out.push_str(r#" <span class="kw">constrain</span> "#);
+                self.constraint_list(&constrained.constraining_items, out);
+            }
+
+            Type::Function(function) => {
+                out.push_str(r#"<span class="kw">func</span>("#);
+
+                for (index, param) in function.params.iter().enumerate() {
+                    if index > 0 {
+                        out.push_str(", ");
+                    }
+
+                    self.ty(param, out);
+                }
+
+                out.push(')');
+
+                if let Some(return_ty) = &function.return_ty {
+                    out.push_str(" -&gt; ");
+                    self.ty(return_ty, out);
+                }
+            }
+        }
+    }
+
+    /// Render a list of generic parameters (including the angle brackets), if there are any.
+    fn generic_params(&self, params: &[GenericParam], out: &mut String) {
+        if params.is_empty() {
+            return;
+        }
+
+        out.push_str("&lt;");
+
+        for (index, param) in params.iter().enumerate() {
+            if index > 0 {
+                out.push_str(", ");
+            }
+
+            out.push_str(&escape(param.name.fragment.as_str()));
+
+            for (index, bound) in param.bounds.iter().enumerate() {
+                out.push_str(if index == 0 { ": " } else { " + " });
+                self.path(bound, out);
+            }
+        }
+
+        out.push_str("&gt;");
+    }
+
+    /// Render a `repr(...)` clause (with a space in front of it), if there is one.
+    fn repr(&self, repr: Option<&Repr>, out: &mut String) {
+        let Some(repr) = repr else {
+            return;
+        };
+
+        let hints: Vec<_> = (repr.hints.iter())
+            .map(|hint| escape(hint.matching_source.as_str()))
+            .collect();
+
+        write!(out, r#" <span class="kw">repr</span>({})"#, hints.join(", ")).unwrap();
+    }
+
+    /// Render the signature of a function.
+    fn func_signature(&self, func: &FuncDecl, out: &mut String) {
+        if func.is_pure {
+            out.push_str(r#"<span class="kw">pure</span> "#);
+        }
+
+        write!(out, r#"<span class="kw">func</span> {}"#, escape(func.name.fragment.as_str()))
+            .unwrap();
+        self.generic_params(&func.generic_params, out);
+        out.push('(');
+
+        for (index, param) in func.params.iter().enumerate() {
+            if index > 0 {
+                out.push_str(", ");
+            }
+
+            write!(out, "{}: ", escape(param.name.fragment.as_str())).unwrap();
+            self.ty(&param.ty, out);
+        }
+
+        out.push(')');
+
+        if let Some(return_ty) = &func.return_ty {
+            out.push_str(" -&gt; ");
+            self.ty(return_ty, out);
+        }
+
+        for (index, predicate) in func.where_clause.iter().enumerate() {
+            out.push_str(if index == 0 {
+                r#" <span class="kw">where</span> "#
+            } else {
+                ", "
+            });
+            self.ty(&predicate.ty, out);
+            out.push_str(": ");
+            self.constraint_list(&predicate.bounds, out);
+        }
+    }
+
+    /// Render the signature of a constraint, without its body.
+    fn constraint_signature(&self, constraint: &ConstraintDecl, out: &mut String) {
+        write!(
+            out,
+            r#"<span class="kw">constraint</span> {}"#,
+            escape(constraint.name.fragment.as_str())
+        )
+        .unwrap();
+        self.generic_params(&constraint.generic_params, out);
+
+        let Some(predicate) = &constraint.predicate else {
+            out.push_str(" = ");
+            self.constraint_list(&constraint.bases, out);
+            return;
+        };
+
+        write!(out, "({}: ", escape(predicate.param.name.fragment.as_str())).unwrap();
+        self.ty(&predicate.param.ty, out);
+        out.push(')');
+
+        if !constraint.bases.is_empty() {
+            out.push_str(": ");
+            self.constraint_list(&constraint.bases, out);
+        }
+    }
+
+    /// Render paths to constraints, separated by `+`.
+    fn constraint_list(&self, constraints: &[Path], out: &mut String) {
+        for (index, constraint) in constraints.iter().enumerate() {
+            if index > 0 {
+                out.push_str(" + ");
+            }
+
+            self.path(constraint, out);
+        }
+    }
+
+    /// Render the signature of an import.
+    fn import_signature(&self, import: &ImportDecl, out: &mut String) {
+        out.push_str(r#"<span class="kw">use</span> "#);
+        self.path(&import.imported_item, out);
+
+        if let Some(imported_as) = &import.imported_as {
+            write!(out, r#" <span class="kw">as</span> {}"#, escape(imported_as.fragment.as_str()))
+                .unwrap();
+        }
+    }
+
+    /// Render the full signature of a declaration.
+    fn signature(&self, decl: &Decl) -> String {
+        let mut out = String::new();
+
+        match decl.visibility() {
+            Visibility::Private => {}
+            Visibility::Module { .. } => {
+                out.push_str(r#"<span class="kw">pub</span>(<span class="kw">mod</span>) "#)
+            }
+            Visibility::Public { .. } => out.push_str(r#"<span class="kw">pub</span> "#),
+        }
+
+        match decl {
+            Decl::Import(import) => self.import_signature(import, &mut out),
+
+            Decl::TypeAlias(alias) => {
+                write!(
+                    out,
+                    r#"<span class="kw">type</span> {}"#,
+                    escape(alias.new_type_name.fragment.as_str())
+                )
+                .unwrap();
+
+                if let Some(target) = &alias.target_type {
+                    out.push_str(" = ");
+                    self.ty(target, &mut out);
+                }
+            }
+
+            Decl::Constant(constant) => {
+                write!(
+                    out,
+                    r#"<span class="kw">const</span> {}: "#,
+                    escape(constant.name.fragment.as_str())
+                )
+                .unwrap();
+                self.ty(&constant.ty, &mut out);
+            }
+
+            Decl::Function(func) => {
+                let generics = func
+                    .generic_params
+                    .iter()
+                    .map(|param| param.name.fragment.as_str());
+                let ctx = PageContext {
+                    generics: self.generics.iter().copied().chain(generics).collect(),
+                    ..*self
+                };
+
+                ctx.func_signature(func, &mut out);
+            }
+
+            Decl::Constraint(constraint) => {
+                let generics = constraint
+                    .generic_params
+                    .iter()
+                    .map(|param| param.name.fragment.as_str());
+                let ctx = PageContext {
+                    generics: self.generics.iter().copied().chain(generics).collect(),
+                    ..*self
+                };
+
+                ctx.constraint_signature(constraint, &mut out);
+            }
+
+            Decl::Module(module) => {
+                write!(
+                    out,
+                    r#"<span class="kw">mod</span> {}"#,
+                    escape(module.name.fragment.as_str())
+                )
+                .unwrap();
+            }
+
+            // Records, unions, and enums end with their fields or variants rather than a semicolon.
+            Decl::Record(record) => {
+                self.fields_signature(
+                    "record",
+                    &record.name,
+                    &record.generic_params,
+                    record.repr.as_ref(),
+                    &record.fields,
+                    &mut out,
+                );
+                return out;
+            }
+
+            Decl::Union(union) => {
+                self.fields_signature(
+                    "union",
+                    &union.name,
+                    &union.generic_params,
+                    union.repr.as_ref(),
+                    &union.variants,
+                    &mut out,
+                );
+                return out;
+            }
+
+            Decl::Enum(enumeration) => {
+                write!(
+                    out,
+                    r#"<span class="kw">enum</span> {}"#,
+                    escape(enumeration.name.fragment.as_str())
+                )
+                .unwrap();
+                self.repr(enumeration.repr.as_ref(), &mut out);
+                out.push_str(" {");
+
+                for variant in &enumeration.variants {
+                    write!(out, "\n    {},", escape(variant.fragment.as_str())).unwrap();
+                }
+
+                out.push_str("\n}");
+                return out;
+            }
+
+            Decl::Trait(trait_decl) => {
+                write!(
+                    out,
+                    r#"<span class="kw">trait</span> {} {{"#,
+                    escape(trait_decl.name.fragment.as_str())
+                )
+                .unwrap();
+
+                for method in &trait_decl.methods {
+                    let generics =
+                        (method.generic_params.iter()).map(|param| param.name.fragment.as_str());
+                    let ctx = PageContext {
+                        generics: (self.generics.iter().copied())
+                            .chain(std::iter::once("Self"))
+                            .chain(generics)
+                            .collect(),
+                        ..*self
+                    };
+
+                    out.push_str("\n    ");
+                    ctx.func_signature(method, &mut out);
+                    out.push(';');
+                }
+
+                out.push_str("\n}");
+                return out;
+            }
+
+            // `impl` blocks aren't items of their own, so they aren't documented.
+            Decl::Impl(_) => {}
+        }
+
+        out.push(';');
+        out
+    }
+
+    /// Render the signature of a record or union, including its fields.
+    fn fields_signature(
+        &self,
+        keyword: &str,
+        name: &Identifier,
+        generic_params: &[GenericParam],
+        repr: Option<&Repr>,
+        fields: &[FieldDecl],
+        out: &mut String,
+    ) {
+        write!(out, r#"<span class="kw">{keyword}</span> {}"#, escape(name.fragment.as_str()))
+            .unwrap();
+        self.generic_params(generic_params, out);
+        self.repr(repr, out);
+
+        let generics = generic_params
+            .iter()
+            .map(|param| param.name.fragment.as_str());
+        let ctx = PageContext {
+            generics: self.generics.iter().copied().chain(generics).collect(),
+            ..*self
+        };
+
+        out.push_str(" {\n");
+
+        for field in fields {
+            write!(out, "    {}: ", escape(field.name.fragment.as_str())).unwrap();
+            ctx.ty(&field.ty, out);
+            out.push_str(",\n");
+        }
+
+        out.push('}');
+    }
+
+    /// Render doc comments from Markdown to HTML, linking any paths that name documented items.
+    fn docs(&self, docs: &Docs) -> String {
+        let text = docs.text();
+
+        let callback = |link: BrokenLink<'_>| {
+            self.link_to_reference(&link.reference)
+                .map(|href| (CowStr::from(href), CowStr::from("")))
+        };
+
+        let parser = pulldown_cmark::Parser::new_with_broken_link_callback(
+            &text,
+            Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH,
+            Some(callback),
+        )
+        .map(|event| match event {
+            // Allow links to be written as paths, i.e. `[the request type](net::Request)`.
+            Event::Start(Tag::Link {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) => {
+                let dest_url = self
+                    .link_to_reference(&dest_url)
+                    .map(CowStr::from)
+                    .unwrap_or(dest_url);
+
+                Event::Start(Tag::Link {
+                    link_type,
+                    dest_url,
+                    title,
+                    id,
+                })
+            }
+
+            event => event,
+        });
+
+        let mut html = String::new();
+        pulldown_cmark::html::push_html(&mut html, parser);
+        html
+    }
+}
+
+/// The heading and item kind of each section of a module page, in order.
+const SECTIONS: &[(&str, ItemKind)] = &[
+    ("Records", ItemKind::Record),
+    ("Unions", ItemKind::Union),
+    ("Enums", ItemKind::Enum),
+    ("Type Aliases", ItemKind::TypeAlias),
+    ("Constants", ItemKind::Constant),
+    ("Functions", ItemKind::Function),
+    ("Constraints", ItemKind::Constraint),
+    ("Traits", ItemKind::Trait),
+];
+
+/// Render the page documenting the module at `module_path`. The module may not have any source if it only exists
+/// to contain other modules.
+pub fn module_page(index: &ItemIndex, module_path: &[String]) -> String {
+    let ctx = PageContext {
+        index,
+        module: module_path,
+        generics: Vec::new(),
+    };
+
+    let module: Option<&Module> = index.module(module_path);
+    let prefix = "../".repeat(module_path.len());
+    let title = escape(&display_module(module_path));
+    let mut out = String::new();
+
+    write!(
+        out,
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{title} - Wright Documentation</title>
+<link rel="icon" href="{prefix}static/favicon.png">
+<link rel="stylesheet" href="{prefix}static/style.css">
+</head>
+<body>
+<nav class="sidebar">
+<a href="{prefix}index.html"><img class="logo" src="{prefix}static/wright_logo.svg" alt="wright logo"></a>
+<ul>
+"#
+    )
+    .unwrap();
+
+    for other in index.modules() {
+        let href = index.href(module_path, other, ItemKind::Module);
+        writeln!(out, r#"<li><a href="{href}">{}</a></li>"#, escape(&display_module(other)))
+            .unwrap();
+    }
+
+    write!(
+        out,
+        "</ul>\n</nav>\n<main>\n<h1>Module <span class=\"path\">{title}</span></h1>\n"
+    )
+    .unwrap();
+
+    if let Some(module) = module {
+        out.push_str(&ctx.docs(&module.docs));
+    }
+
+    // List submodules with the summary line of their docs.
+    let children: Vec<&Vec<String>> = index.children(module_path).collect();
+
+    if !children.is_empty() {
+        out.push_str("<h2 id=\"modules\">Modules</h2>\n<table class=\"summary\">\n");
+
+        for child in children {
+            let href = index.href(module_path, child, ItemKind::Module);
+            let name = escape(child.last().map(String::as_str).unwrap_or_default());
+            let summary = escape(
+                &index
+                    .module(child)
+                    .map(|child| child.docs.summary())
+                    .unwrap_or_default(),
+            );
+
+            writeln!(out, r#"<tr><td><a href="{href}">{name}</a></td><td>{summary}</td></tr>"#)
+                .unwrap();
+        }
+
+        out.push_str("</table>\n");
+    }
+
+    if let Some(module) = module {
+        // Public imports are re-exports.
+        let reexports: Vec<&Decl> = module
+            .items
+            .iter()
+            .filter(|decl| matches!(decl, Decl::Import(_)) && !decl.visibility().is_private())
+            .collect();
+
+        if !reexports.is_empty() {
+            out.push_str("<h2 id=\"reexports\">Re-exports</h2>\n");
+
+            for decl in reexports {
+                writeln!(out, r#"<pre class="signature">{}</pre>"#, ctx.signature(decl)).unwrap();
+            }
+        }
+
+        for (heading, kind) in SECTIONS {
+            let items: Vec<&Decl> = module
+                .items
+                .iter()
+                .filter(|decl| ItemKind::of(decl) == Some(*kind))
+                .collect();
+
+            if items.is_empty() {
+                continue;
+            }
+
+            writeln!(out, r#"<h2 id="{}s">{heading}</h2>"#, kind.anchor_prefix()).unwrap();
+
+            for decl in items {
+                let name = decl
+                    .name()
+                    .expect("only named declarations have an item kind");
+                let anchor = format!("{}.{}", kind.anchor_prefix(), name.fragment.as_str());
+
+                write!(
+                    out,
+                    "<section class=\"item\" id=\"{}\">\n<pre class=\"signature\">{}</pre>\n{}</section>\n",
+                    escape(&anchor),
+                    ctx.signature(decl),
+                    ctx.docs(decl.docs()),
+                )
+                .unwrap();
+            }
+        }
+    }
+
+    out.push_str("</main>\n</body>\n</html>\n");
+    out
+}
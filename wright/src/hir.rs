@@ -0,0 +1,755 @@
+//! The high-level intermediate representation (HIR) -- a resolved, desugared form of a package's functions that
+//! later phases lower from instead of the [AST].
+//!
+//! Every node of the HIR (expressions, statements, blocks, patterns, type signatures, and bindings) lives in one
+//! arena in [Hir], and refers to the nodes inside it by [HirId]. Names are resolved: a path is either a [DefId] or
+//! the [HirId] of the binding it reads. Some constructs are desugared into simpler ones:
+//!
+//! - `for i in a..b { ... }` becomes a block that stores the start and end of the range in bindings of its own, and a
+//!   `loop` that binds `i`, runs the body, and increments the counter until it reaches the end.
+//! - `while condition { ... }` becomes a `loop` around an `if` that `break`s once the condition is `false`.
+//! - `else if` becomes an `else` block whose tail is the inner `if`.
+//! - Parentheses are dropped.
+//!
+//! Every node has a [Span] in a side table, so diagnostics from later phases can still point at the source. Nodes
+//! made by desugaring point at the construct they came from, and record which [Desugaring] made them.
+//!
+//! [AST]: crate::ast
+
+use crate::{
+    analysis::resolve::{DefId, Resolution},
+    ast::{
+        expr::{BinaryOperation, UnaryOperation},
+        module::Module,
+        ty::AtomicTyVariant,
+    },
+    source_tracking::fragment::Fragment,
+};
+use num::{BigInt, BigUint};
+
+mod lower;
+
+/// The ID of a node in the [Hir].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct HirId(pub u32);
+
+/// The HIR of every function (and method) in a package.
+#[derive(Debug, Default)]
+pub struct Hir {
+    /// Every function and method, in the order they're declared.
+    pub functions: Vec<Function>,
+    /// Every node, by [HirId].
+    nodes: Vec<Node>,
+    /// The span of every node, by [HirId].
+    spans: Vec<Span>,
+}
+
+impl Hir {
+    /// Get a node.
+    pub fn node(&self, id: HirId) -> &Node {
+        &self.nodes[id.0 as usize]
+    }
+
+    /// Get the span of a node.
+    pub fn span(&self, id: HirId) -> &Span {
+        &self.spans[id.0 as usize]
+    }
+
+    /// Get the source code a node came from.
+    pub fn fragment(&self, id: HirId) -> &Fragment {
+        &self.span(id).fragment
+    }
+
+    /// Get the function with the given definition.
+    pub fn function(&self, def: DefId) -> Option<&Function> {
+        self.functions.iter().find(|function| function.def == def)
+    }
+
+    /// Get an expression node.
+    ///
+    /// # Panics
+    /// - If the node isn't an expression.
+    pub fn expr(&self, id: HirId) -> &Expr {
+        match self.node(id) {
+            Node::Expr(expr) => expr,
+            node => panic!("expected an expression, found {node:?}"),
+        }
+    }
+
+    /// Get a statement node.
+    ///
+    /// # Panics
+    /// - If the node isn't a statement.
+    pub fn stmt(&self, id: HirId) -> &Stmt {
+        match self.node(id) {
+            Node::Stmt(stmt) => stmt,
+            node => panic!("expected a statement, found {node:?}"),
+        }
+    }
+
+    /// Get a block node.
+    ///
+    /// # Panics
+    /// - If the node isn't a block.
+    pub fn block(&self, id: HirId) -> &Block {
+        match self.node(id) {
+            Node::Block(block) => block,
+            node => panic!("expected a block, found {node:?}"),
+        }
+    }
+
+    /// Get a pattern node.
+    ///
+    /// # Panics
+    /// - If the node isn't a pattern.
+    pub fn pattern(&self, id: HirId) -> &Pattern {
+        match self.node(id) {
+            Node::Pattern(pattern) => pattern,
+            node => panic!("expected a pattern, found {node:?}"),
+        }
+    }
+
+    /// Get a type signature node.
+    ///
+    /// # Panics
+    /// - If the node isn't a type signature.
+    pub fn ty(&self, id: HirId) -> &Type {
+        match self.node(id) {
+            Node::Type(ty) => ty,
+            node => panic!("expected a type signature, found {node:?}"),
+        }
+    }
+
+    /// Get a binding node.
+    ///
+    /// # Panics
+    /// - If the node isn't a binding.
+    pub fn binding(&self, id: HirId) -> &Binding {
+        match self.node(id) {
+            Node::Binding(binding) => binding,
+            node => panic!("expected a binding, found {node:?}"),
+        }
+    }
+
+    /// Call `visit` on each of the nodes directly inside a node, in the order they're evaluated.
+    pub fn for_each_child(&self, id: HirId, visit: &mut impl FnMut(HirId)) {
+        let mut visit_param = |param: &Param| {
+            visit(param.binding);
+            param.ty.into_iter().for_each(&mut *visit);
+        };
+
+        match self.node(id) {
+            Node::Expr(expr) => match expr {
+                Expr::Literal(_) | Expr::Path(_) | Expr::Break => {}
+                Expr::Unary { operand, .. } => visit(*operand),
+
+                Expr::Binary { lhs, rhs, .. } => {
+                    visit(*lhs);
+                    visit(*rhs);
+                }
+
+                Expr::Is { value, target } => {
+                    visit(*value);
+
+                    if let IsTarget::Type(ty) = target {
+                        visit(*ty);
+                    }
+                }
+
+                Expr::Call { callee, args } => {
+                    visit(*callee);
+                    args.iter().copied().for_each(visit);
+                }
+
+                Expr::Field { base, .. } => visit(*base),
+                Expr::Record { fields, .. } => fields.iter().for_each(|(_, value)| visit(*value)),
+                Expr::Block(block) | Expr::Loop(block) | Expr::Unsafe(block) => visit(*block),
+
+                Expr::If {
+                    condition,
+                    then_block,
+                    else_block,
+                } => {
+                    visit(*condition);
+                    visit(*then_block);
+                    else_block.iter().copied().for_each(visit);
+                }
+
+                Expr::Match { scrutinee, arms } => {
+                    visit(*scrutinee);
+
+                    for arm in arms {
+                        visit(arm.pattern);
+                        arm.guard.iter().copied().for_each(&mut *visit);
+                        visit(arm.body);
+                    }
+                }
+
+                Expr::Closure {
+                    params,
+                    return_ty,
+                    body,
+                } => {
+                    params.iter().for_each(&mut visit_param);
+                    return_ty.iter().copied().for_each(&mut *visit);
+                    visit(*body);
+                }
+            },
+
+            Node::Stmt(stmt) => match *stmt {
+                Stmt::Let { binding, ty, value } => {
+                    visit(value);
+                    ty.into_iter().for_each(&mut *visit);
+                    visit(binding);
+                }
+
+                Stmt::Assign { target, value, .. } => {
+                    visit(target);
+                    visit(value);
+                }
+
+                Stmt::Expr(expr) => visit(expr),
+            },
+
+            Node::Block(block) => {
+                block.stmts.iter().copied().for_each(&mut *visit);
+                block.tail.into_iter().for_each(visit);
+            }
+
+            Node::Pattern(pattern) => match pattern {
+                Pattern::Binding(binding) => visit(*binding),
+                Pattern::Record { fields, .. } => {
+                    fields.iter().for_each(|(_, pattern)| visit(*pattern))
+                }
+                Pattern::Or(alternatives) => alternatives.iter().copied().for_each(visit),
+
+                Pattern::Wildcard
+                | Pattern::Integer(_)
+                | Pattern::Boolean(_)
+                | Pattern::Range { .. }
+                | Pattern::Variant(_) => {}
+            },
+
+            Node::Type(ty) => match ty {
+                Type::Atomic(_) => {}
+                Type::Named { args, .. } => args.iter().copied().for_each(visit),
+                Type::Reference { target, .. } => visit(*target),
+                Type::Constrained { base, .. } => visit(*base),
+
+                Type::Function { params, ret } => {
+                    params.iter().copied().for_each(&mut *visit);
+                    ret.iter().copied().for_each(visit);
+                }
+            },
+
+            Node::Binding(_) => {}
+        }
+    }
+}
+
+/// Where a node of the [Hir] came from.
+#[derive(Clone, Debug)]
+pub struct Span {
+    /// The source code of the node, or of the construct it was desugared from.
+    pub fragment: Fragment,
+    /// The desugaring that made the node, or [None] if it was written in the source.
+    pub desugaring: Option<Desugaring>,
+}
+
+/// A construct that's desugared into simpler nodes when lowering to the [Hir].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Desugaring {
+    /// A `for` loop.
+    ForLoop,
+    /// A `while` loop.
+    WhileLoop,
+    /// An `else if` branch.
+    ElseIf,
+}
+
+/// What a path in the [Hir] refers to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Res {
+    /// A [Binding] -- a parameter, `let`, closure parameter, or a name bound by a pattern.
+    Local(HirId),
+    /// An item, enum variant, or builtin.
+    Def(DefId),
+    /// A name that didn't resolve (which has already been reported).
+    Err,
+}
+
+/// A function or method.
+#[derive(Debug)]
+pub struct Function {
+    /// The definition of the function.
+    pub def: DefId,
+    /// Whether the function is `pure`.
+    pub is_pure: bool,
+    /// The parameters of the function.
+    pub params: Vec<Param>,
+    /// The return type, if the function has one.
+    pub return_ty: Option<HirId>,
+    /// The body of the function ([None] for functions without one, like builtins).
+    pub body: Option<HirId>,
+}
+
+/// A parameter of a [Function] or closure.
+#[derive(Clone, Copy, Debug)]
+pub struct Param {
+    /// The [Binding] of the parameter.
+    pub binding: HirId,
+    /// The type annotation of the parameter (which closure parameters may leave out).
+    pub ty: Option<HirId>,
+}
+
+/// A node in the [Hir].
+#[derive(Debug)]
+#[allow(missing_docs)]
+pub enum Node {
+    Expr(Expr),
+    Stmt(Stmt),
+    Block(Block),
+    Pattern(Pattern),
+    Type(Type),
+    Binding(Binding),
+}
+
+/// A name bound to a value -- a parameter, `let`, closure parameter, the binding of a pattern, or a binding made
+/// by desugaring.
+#[derive(Debug)]
+pub struct Binding {
+    /// The name of the binding. Bindings made by desugaring have names that can't be written in source code, like
+    /// `<end>`.
+    pub name: String,
+    /// The definition of the binding from name resolution ([None] for bindings made by desugaring).
+    pub def: Option<DefId>,
+    /// Whether the binding can be assigned to.
+    pub is_mutable: bool,
+}
+
+/// A literal value.
+#[derive(Debug)]
+#[allow(missing_docs)]
+pub enum Literal {
+    Integer(BigUint),
+    Boolean(bool),
+    String(String),
+}
+
+/// An expression.
+#[derive(Debug)]
+pub enum Expr {
+    /// A literal.
+    Literal(Literal),
+    /// A name or path.
+    Path(Res),
+    /// A unary operation, i.e. `-x` or `@mut x`.
+    Unary {
+        /// The operation.
+        op: UnaryOperation,
+        /// The expression the operation is applied to.
+        operand: HirId,
+    },
+    /// A binary operation, i.e. `a + b`.
+    Binary {
+        /// The operation.
+        op: BinaryOperation,
+        /// The left hand side.
+        lhs: HirId,
+        /// The right hand side.
+        rhs: HirId,
+    },
+    /// A test of the variant of a union or enum value, i.e. `n is Number::small`.
+    Is {
+        /// The value being tested.
+        value: HirId,
+        /// The variant being tested for.
+        target: IsTarget,
+    },
+    /// A call of a function or method. Method calls (`shape.area()`) call a [Expr::Field], and are told apart by
+    /// the type checker.
+    Call {
+        /// The expression being called.
+        callee: HirId,
+        /// The arguments.
+        args: Vec<HirId>,
+    },
+    /// A field access, i.e. `point.x`.
+    Field {
+        /// The expression whose field is accessed.
+        base: HirId,
+        /// The name of the field.
+        field: String,
+    },
+    /// A record or union literal, i.e. `Point { x: 1, y: 2 }`.
+    Record {
+        /// The record or union type.
+        ty: Res,
+        /// The name and value of each field, in source order.
+        fields: Vec<(String, HirId)>,
+    },
+    /// A block.
+    Block(HirId),
+    /// An `if` expression. `else if` branches are desugared into an `else` block holding the inner `if`.
+    If {
+        /// The condition.
+        condition: HirId,
+        /// The block run if the condition is `true`.
+        then_block: HirId,
+        /// The block run if the condition is `false`.
+        else_block: Option<HirId>,
+    },
+    /// A `match` expression.
+    Match {
+        /// The value being matched.
+        scrutinee: HirId,
+        /// The arms, in source order.
+        arms: Vec<Arm>,
+    },
+    /// A loop that runs its block until it reaches a [Expr::Break]. Only made by desugaring.
+    Loop(HirId),
+    /// Leave the innermost [Expr::Loop]. Only made by desugaring.
+    Break,
+    /// A closure.
+    Closure {
+        /// The parameters.
+        params: Vec<Param>,
+        /// The return type, if it was given.
+        return_ty: Option<HirId>,
+        /// The body.
+        body: HirId,
+    },
+    /// An `unsafe` block.
+    Unsafe(HirId),
+}
+
+/// The variant tested for by an [Expr::Is].
+#[derive(Clone, Debug)]
+pub enum IsTarget {
+    /// A variant of an enum, i.e. `Color::Red`.
+    EnumVariant(Res),
+    /// A variant of a union given by name, i.e. `Number::small`.
+    UnionVariant {
+        /// The union.
+        union: Res,
+        /// The name of the variant.
+        variant: String,
+    },
+    /// The variant of a union with the given type, i.e. `u8`.
+    Type(HirId),
+}
+
+/// An arm of an [Expr::Match].
+#[derive(Clone, Copy, Debug)]
+pub struct Arm {
+    /// The pattern.
+    pub pattern: HirId,
+    /// The guard, if there is one.
+    pub guard: Option<HirId>,
+    /// The body.
+    pub body: HirId,
+}
+
+/// A statement.
+#[derive(Clone, Copy, Debug)]
+pub enum Stmt {
+    /// A `let` or `var` binding.
+    Let {
+        /// The [Binding].
+        binding: HirId,
+        /// The type annotation, if there is one.
+        ty: Option<HirId>,
+        /// The value.
+        value: HirId,
+    },
+    /// An assignment, i.e. `x = 1;` or `x += 1;`.
+    Assign {
+        /// The place being assigned to.
+        target: HirId,
+        /// The operation of a compound assignment, or [None] for `=`.
+        op: Option<BinaryOperation>,
+        /// The value.
+        value: HirId,
+    },
+    /// An expression used as a statement.
+    Expr(HirId),
+}
+
+/// A block of statements, with an optional tail expression that it evaluates to.
+#[derive(Debug)]
+pub struct Block {
+    /// The statements.
+    pub stmts: Vec<HirId>,
+    /// The tail expression.
+    pub tail: Option<HirId>,
+}
+
+/// A pattern in a `match` arm.
+#[derive(Debug)]
+pub enum Pattern {
+    /// `_`.
+    Wildcard,
+    /// A name that binds the value, with its [Binding].
+    Binding(HirId),
+    /// An integer, i.e. `-1`.
+    Integer(BigInt),
+    /// `true` or `false`.
+    Boolean(bool),
+    /// A range of integers, i.e. `1..=10`.
+    Range {
+        /// The start of the range.
+        start: BigInt,
+        /// The end of the range.
+        end: BigInt,
+        /// Whether the end is part of the range.
+        is_inclusive: bool,
+    },
+    /// An enum variant.
+    Variant(Res),
+    /// A record or union pattern, i.e. `Point { x: 0, y }`.
+    Record {
+        /// The record or union type.
+        ty: Res,
+        /// The name and pattern of each field, in source order.
+        fields: Vec<(String, HirId)>,
+    },
+    /// A choice between patterns.
+    Or(Vec<HirId>),
+}
+
+/// A type signature.
+#[derive(Debug)]
+pub enum Type {
+    /// An atomic type, i.e. `u8`.
+    Atomic(AtomicTyVariant),
+    /// A reference type, i.e. `@mut u8`.
+    Reference {
+        /// The type of the referenced value.
+        target: HirId,
+        /// Whether the reference is mutable.
+        is_mutable: bool,
+    },
+    /// A named type, with its generic arguments.
+    Named {
+        /// The type (or generic parameter, or type alias).
+        res: Res,
+        /// The generic arguments.
+        args: Vec<HirId>,
+    },
+    /// A type with constraints, i.e. `u8 constrain is_even`.
+    Constrained {
+        /// The type being constrained.
+        base: HirId,
+        /// The constraints.
+        constraints: Vec<Res>,
+    },
+    /// A function type, i.e. `func(u8) -> bool`.
+    Function {
+        /// The parameter types.
+        params: Vec<HirId>,
+        /// The return type, if there is one.
+        ret: Option<HirId>,
+    },
+}
+
+/// Lower the functions and methods in the given modules to the [Hir]. The modules should have been resolved (and
+/// checked) without errors.
+pub fn lower<'m>(
+    modules: impl IntoIterator<Item = (Vec<String>, &'m Module)>,
+    resolution: &Resolution,
+) -> Hir {
+    let modules: Vec<_> = modules.into_iter().collect();
+    lower::Lowerer::run(&modules, resolution)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Desugaring, Expr, Hir, HirId, Node, Res, Stmt, lower};
+    use crate::analysis::testing::{modules, resolve_source};
+
+    /// Parse, resolve, and lower a single module, returning the HIR and the source.
+    fn lower_source(source: &'static str) -> (Hir, String) {
+        let (module, resolution) = resolve_source(source);
+        let hir = lower(modules(&module), &resolution);
+        (hir, module.matching_source.as_str().to_owned())
+    }
+
+    /// Get the tail expression of the body of the only function.
+    fn body_tail(hir: &Hir) -> HirId {
+        let body = hir.functions[0].body.unwrap();
+        hir.block(body).tail.unwrap()
+    }
+
+    #[test]
+    fn test_for_loop() {
+        let (hir, _) = lower_source("func main() { for i in 0..10 { i; } }");
+
+        // The loop is a block holding the counter and end of the range, and the `loop` itself.
+        let Expr::Block(block) = hir.expr(body_tail(&hir)) else {
+            panic!("expected a block");
+        };
+
+        assert_eq!(hir.span(*block).desugaring, Some(Desugaring::ForLoop));
+        let block = hir.block(*block);
+        assert_eq!(block.stmts.len(), 2);
+
+        let Stmt::Let {
+            binding: counter, ..
+        } = hir.stmt(block.stmts[0])
+        else {
+            panic!("expected a `let`");
+        };
+
+        assert!(hir.binding(*counter).is_mutable);
+        assert_eq!(hir.binding(*counter).def, None);
+
+        let Expr::Loop(body) = hir.expr(block.tail.unwrap()) else {
+            panic!("expected a `loop`");
+        };
+
+        let Expr::If {
+            condition,
+            then_block,
+            else_block: Some(else_block),
+        } = hir.expr(hir.block(*body).tail.unwrap())
+        else {
+            panic!("expected an `if`");
+        };
+
+        assert!(matches!(hir.expr(*condition), Expr::Binary { .. }));
+        assert!(matches!(hir.expr(hir.block(*else_block).tail.unwrap()), Expr::Break));
+
+        // `i` is bound to the counter, the body is run, and then the counter is incremented.
+        let then_block = hir.block(*then_block);
+        assert_eq!(then_block.stmts.len(), 3);
+
+        let Stmt::Let { binding, value, .. } = hir.stmt(then_block.stmts[0]) else {
+            panic!("expected a `let`");
+        };
+
+        assert_eq!(hir.binding(*binding).name, "i");
+        assert!(hir.binding(*binding).def.is_some());
+        assert!(matches!(hir.expr(*value), Expr::Path(Res::Local(local)) if local == counter));
+        assert_eq!(hir.fragment(*binding).as_str(), "i");
+
+        // The body reads the `i` binding.
+        let Stmt::Expr(body) = hir.stmt(then_block.stmts[1]) else {
+            panic!("expected the body");
+        };
+
+        let Expr::Block(body) = hir.expr(*body) else {
+            panic!("expected a block");
+        };
+
+        assert_eq!(hir.span(*body).desugaring, None);
+        let Stmt::Expr(read) = hir.stmt(hir.block(*body).stmts[0]) else {
+            panic!("expected an expression statement");
+        };
+
+        assert!(matches!(hir.expr(*read), Expr::Path(Res::Local(local)) if local == binding));
+        assert!(matches!(hir.stmt(then_block.stmts[2]), Stmt::Assign { op: Some(_), .. }));
+    }
+
+    #[test]
+    fn test_inclusive_for_loop() {
+        let (hir, _) = lower_source("func main() { for i in 0..=255 {} }");
+
+        let Expr::Block(block) = hir.expr(body_tail(&hir)) else {
+            panic!("expected a block");
+        };
+
+        let Expr::Loop(body) = hir.expr(hir.block(*block).tail.unwrap()) else {
+            panic!("expected a `loop`");
+        };
+
+        let Expr::If { then_block, .. } = hir.expr(hir.block(*body).tail.unwrap()) else {
+            panic!("expected an `if`");
+        };
+
+        // The loop stops after the end of the range, before incrementing the counter past it.
+        let then_block = hir.block(*then_block);
+        assert_eq!(then_block.stmts.len(), 4);
+        let Stmt::Expr(stop) = hir.stmt(then_block.stmts[2]) else {
+            panic!("expected an expression statement");
+        };
+
+        assert!(matches!(
+            hir.expr(*stop),
+            Expr::If {
+                else_block: None,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_while_and_else_if() {
+        let (hir, source) = lower_source(
+            "func main(x: u8) -> u8 { while (x > 1) {} if x == 1 { 1 } else if x == 2 { 2 } else { 3 } }",
+        );
+
+        let body = hir.block(hir.functions[0].body.unwrap());
+        let Stmt::Expr(while_loop) = hir.stmt(body.stmts[0]) else {
+            panic!("expected an expression statement");
+        };
+
+        let Expr::Loop(loop_body) = hir.expr(*while_loop) else {
+            panic!("expected a `loop`");
+        };
+
+        assert_eq!(hir.span(*while_loop).desugaring, Some(Desugaring::WhileLoop));
+        let Expr::If { condition, .. } = hir.expr(hir.block(*loop_body).tail.unwrap()) else {
+            panic!("expected an `if`");
+        };
+
+        // Parentheses are dropped.
+        assert!(matches!(hir.expr(*condition), Expr::Binary { .. }));
+        assert_eq!(hir.fragment(*condition).as_str(), "x > 1");
+
+        let Expr::If {
+            else_block: Some(else_block),
+            ..
+        } = hir.expr(body.tail.unwrap())
+        else {
+            panic!("expected an `if`");
+        };
+
+        assert_eq!(hir.span(*else_block).desugaring, Some(Desugaring::ElseIf));
+        let inner = hir.block(*else_block).tail.unwrap();
+        assert!(matches!(
+            hir.expr(inner),
+            Expr::If {
+                else_block: Some(_),
+                ..
+            }
+        ));
+        assert_eq!(
+            hir.fragment(inner).as_str(),
+            &source[source.find("if x == 2").unwrap()..source.len() - 2]
+        );
+    }
+
+    #[test]
+    fn test_spans() {
+        let (hir, _) = lower_source(
+            "record Point { x: u8 }\n\
+            func f(p: @Point, n: u8) -> u8 { let y = match n { 0 => p.x, m => m + 1 }; f(p, y) }",
+        );
+
+        // Every node that wasn't made by desugaring points at the source it came from.
+        let mut count = 0;
+
+        for index in 0..hir.nodes.len() {
+            let id = HirId(index as u32);
+            assert_eq!(hir.span(id).desugaring, None);
+
+            if let Node::Binding(binding) = hir.node(id) {
+                assert_eq!(hir.fragment(id).as_str(), binding.name);
+                count += 1;
+            }
+        }
+
+        assert_eq!(count, 4);
+        assert_eq!(hir.fragment(body_tail(&hir)).as_str(), "f(p, y)");
+    }
+}
@@ -0,0 +1,578 @@
+//! Lowering resolved ASTs to the [Hir].
+
+use super::{
+    Arm, Binding, Block, Desugaring, Expr, Function, Hir, HirId, IsTarget, Literal, Node, Param,
+    Pattern, Res, Span, Stmt, Type,
+};
+use crate::{
+    analysis::resolve::{DefId, DefKind, Resolution, module_bodies},
+    ast::{
+        decl::{Decl, func::FuncDecl},
+        expr::{self, Atom, BinaryOperation, ForExpr, IfExpr, IsExpr, WhileExpr},
+        identifier::Identifier,
+        module::Module,
+        pattern, stmt, ty,
+    },
+    source_tracking::fragment::Fragment,
+};
+use num::BigUint;
+use std::collections::HashMap;
+
+/// The state of lowering to the [Hir].
+pub(super) struct Lowerer<'r> {
+    /// The result of name resolution.
+    res: &'r Resolution,
+    /// The HIR being built.
+    hir: Hir,
+    /// The [Binding] node of every local definition lowered so far.
+    bindings: HashMap<DefId, HirId>,
+    /// The desugaring that nodes are currently being made by, if any.
+    desugaring: Option<Desugaring>,
+}
+
+impl<'r> Lowerer<'r> {
+    /// Lower every function and method in the given modules.
+    pub(super) fn run(modules: &[(Vec<String>, &Module)], res: &'r Resolution) -> Hir {
+        let mut lowerer = Lowerer {
+            res,
+            hir: Hir::default(),
+            bindings: HashMap::new(),
+            desugaring: None,
+        };
+
+        for (_, items) in module_bodies(modules) {
+            for func in items.iter().flat_map(Decl::functions) {
+                if let Some(function) = lowerer.lower_function(func) {
+                    lowerer.hir.functions.push(function);
+                }
+            }
+        }
+
+        lowerer.hir
+    }
+
+    /// Add a node, pointing at the given source code (with the current desugaring, if any).
+    fn alloc(&mut self, node: Node, fragment: &Fragment) -> HirId {
+        let id = HirId(self.hir.nodes.len() as u32);
+        self.hir.nodes.push(node);
+
+        self.hir.spans.push(Span {
+            fragment: fragment.clone(),
+            desugaring: self.desugaring,
+        });
+
+        id
+    }
+
+    /// Add an expression node.
+    fn alloc_expr(&mut self, expr: Expr, fragment: &Fragment) -> HirId {
+        self.alloc(Node::Expr(expr), fragment)
+    }
+
+    /// Add a block node.
+    fn alloc_block(
+        &mut self,
+        stmts: Vec<HirId>,
+        tail: Option<HirId>,
+        fragment: &Fragment,
+    ) -> HirId {
+        self.alloc(Node::Block(Block { stmts, tail }), fragment)
+    }
+
+    /// Lower nodes made by a desugaring.
+    fn desugar<T>(&mut self, desugaring: Desugaring, lower: impl FnOnce(&mut Self) -> T) -> T {
+        let outer = self.desugaring.replace(desugaring);
+        let result = lower(self);
+        self.desugaring = outer;
+        result
+    }
+
+    /// Lower the nodes inside a desugared construct that were written in the source.
+    fn in_source<T>(&mut self, lower: impl FnOnce(&mut Self) -> T) -> T {
+        let outer = self.desugaring.take();
+        let result = lower(self);
+        self.desugaring = outer;
+        result
+    }
+
+    /// Lower a function or method.
+    fn lower_function(&mut self, func: &FuncDecl) -> Option<Function> {
+        let def = self.res.declared(&func.name.fragment)?;
+
+        let params = (func.params.iter())
+            .map(|param| Param {
+                binding: self.binding(&param.name, false),
+                ty: Some(self.lower_ty(&param.ty)),
+            })
+            .collect();
+
+        let return_ty = func.return_ty.as_ref().map(|ty| self.lower_ty(ty));
+        let body = func.body.as_ref().map(|body| self.lower_block(body));
+
+        Some(Function {
+            def,
+            is_pure: func.is_pure,
+            params,
+            return_ty,
+            body,
+        })
+    }
+
+    /// Add the [Binding] for a name declared in the source.
+    fn binding(&mut self, name: &Identifier, is_mutable: bool) -> HirId {
+        let def = self.res.declared(&name.fragment);
+
+        let id = self.alloc(
+            Node::Binding(Binding {
+                name: name.fragment.as_str().to_owned(),
+                def,
+                is_mutable,
+            }),
+            &name.fragment,
+        );
+
+        if let Some(def) = def {
+            self.bindings.insert(def, id);
+        }
+
+        id
+    }
+
+    /// Add a [Binding] made by desugaring.
+    fn synthetic_binding(&mut self, name: &str, is_mutable: bool, fragment: &Fragment) -> HirId {
+        let binding = Binding {
+            name: name.to_owned(),
+            def: None,
+            is_mutable,
+        };
+
+        self.alloc(Node::Binding(binding), fragment)
+    }
+
+    /// Get what the name or path at a use site refers to.
+    fn res(&self, use_site: &Fragment) -> Res {
+        match self.res.resolved(use_site) {
+            Some(def) => match self.bindings.get(&def) {
+                Some(binding) => Res::Local(*binding),
+                None => Res::Def(def),
+            },
+
+            None => Res::Err,
+        }
+    }
+
+    /// Lower a type signature.
+    fn lower_ty(&mut self, ty: &ty::Type) -> HirId {
+        let lowered = match ty {
+            ty::Type::Atomic(atomic) => Type::Atomic(atomic.variant),
+
+            ty::Type::Reference(reference) => Type::Reference {
+                target: self.lower_ty(&reference.target_ty),
+                is_mutable: reference.is_mutable,
+            },
+
+            ty::Type::Named(named) => Type::Named {
+                res: self.res(&named.name.full_path),
+                args: named
+                    .generic_tys
+                    .iter()
+                    .map(|arg| self.lower_ty(arg))
+                    .collect(),
+            },
+
+            ty::Type::Constrained(constrained) => Type::Constrained {
+                base: self.lower_ty(&constrained.base_ty),
+                constraints: (constrained.constraining_items.iter())
+                    .map(|path| self.res(&path.full_path))
+                    .collect(),
+            },
+
+            ty::Type::Function(function) => Type::Function {
+                params: function
+                    .params
+                    .iter()
+                    .map(|param| self.lower_ty(param))
+                    .collect(),
+                ret: function.return_ty.as_ref().map(|ret| self.lower_ty(ret)),
+            },
+        };
+
+        self.alloc(Node::Type(lowered), ty.matching_source())
+    }
+
+    /// Lower a block.
+    fn lower_block(&mut self, block: &stmt::Block) -> HirId {
+        let stmts = block
+            .stmts
+            .iter()
+            .map(|stmt| self.lower_stmt(stmt))
+            .collect();
+        let tail = block.tail.as_ref().map(|tail| self.lower_expr(tail));
+        self.alloc_block(stmts, tail, &block.matching_source)
+    }
+
+    /// Lower a statement.
+    fn lower_stmt(&mut self, stmt: &stmt::Stmt) -> HirId {
+        let lowered = match stmt {
+            // The value is lowered first, since the binding isn't in scope in it.
+            stmt::Stmt::Let(let_stmt) => {
+                let value = self.lower_expr(&let_stmt.value);
+                let ty = let_stmt.ty.as_ref().map(|ty| self.lower_ty(ty));
+
+                Stmt::Let {
+                    binding: self.binding(&let_stmt.name, let_stmt.is_mutable),
+                    ty,
+                    value,
+                }
+            }
+
+            stmt::Stmt::Assign(assign) => Stmt::Assign {
+                target: self.lower_expr(&assign.target),
+                op: assign.op,
+                value: self.lower_expr(&assign.value),
+            },
+
+            stmt::Stmt::Expr(expr_stmt) => Stmt::Expr(self.lower_expr(&expr_stmt.expr)),
+        };
+
+        self.alloc(Node::Stmt(lowered), stmt.matching_source())
+    }
+
+    /// Lower an expression.
+    fn lower_expr(&mut self, expr: &expr::Expr) -> HirId {
+        let lowered = match expr {
+            expr::Expr::Atom(Atom::Identifier(identifier)) => {
+                Expr::Path(self.res(&identifier.fragment))
+            }
+            expr::Expr::Atom(Atom::IntegerLiteral(literal)) => {
+                Expr::Literal(Literal::Integer(literal.value.clone()))
+            }
+            expr::Expr::Atom(Atom::BooleanLiteral(literal)) => {
+                Expr::Literal(Literal::Boolean(literal.value))
+            }
+            expr::Expr::Atom(Atom::StringLiteral(literal)) => {
+                Expr::Literal(Literal::String(literal.value.clone()))
+            }
+
+            expr::Expr::Path(path) => Expr::Path(self.res(&path.full_path)),
+            expr::Expr::Parens(parens) => return self.lower_expr(&parens.inner),
+
+            expr::Expr::Unary(unary) => Expr::Unary {
+                op: unary.op,
+                operand: self.lower_expr(&unary.operand),
+            },
+
+            expr::Expr::Binary(binary) => Expr::Binary {
+                op: binary.op,
+                lhs: self.lower_expr(&binary.lhs),
+                rhs: self.lower_expr(&binary.rhs),
+            },
+
+            expr::Expr::Is(is_expr) => Expr::Is {
+                value: self.lower_expr(&is_expr.value),
+                target: self.lower_is_target(is_expr),
+            },
+
+            expr::Expr::Call(call) => Expr::Call {
+                callee: self.lower_expr(&call.callee),
+                args: call.args.iter().map(|arg| self.lower_expr(arg)).collect(),
+            },
+
+            expr::Expr::Field(field) => Expr::Field {
+                base: self.lower_expr(&field.base),
+                field: field.field.fragment.as_str().to_owned(),
+            },
+
+            expr::Expr::Record(record) => Expr::Record {
+                ty: self.res(&record.name.full_path),
+                fields: (record.fields.iter())
+                    .map(|field| {
+                        (field.name.fragment.as_str().to_owned(), self.lower_expr(&field.value))
+                    })
+                    .collect(),
+            },
+
+            expr::Expr::Block(block) => Expr::Block(self.lower_block(block)),
+            expr::Expr::If(if_expr) => self.lower_if(if_expr),
+
+            expr::Expr::Match(match_expr) => {
+                let scrutinee = self.lower_expr(&match_expr.scrutinee);
+
+                let arms = (match_expr.arms.iter())
+                    .map(|arm| Arm {
+                        pattern: self.lower_pattern(&arm.pattern),
+                        guard: arm.guard.as_ref().map(|guard| self.lower_expr(guard)),
+                        body: self.lower_expr(&arm.body),
+                    })
+                    .collect();
+
+                Expr::Match { scrutinee, arms }
+            }
+
+            expr::Expr::While(while_expr) => return self.lower_while(while_expr),
+            expr::Expr::For(for_expr) => return self.lower_for(for_expr),
+
+            expr::Expr::Closure(closure) => {
+                let params = (closure.params.iter())
+                    .map(|param| Param {
+                        binding: self.binding(&param.name, false),
+                        ty: param.ty.as_ref().map(|ty| self.lower_ty(ty)),
+                    })
+                    .collect();
+
+                Expr::Closure {
+                    params,
+                    return_ty: closure.return_ty.as_ref().map(|ty| self.lower_ty(ty)),
+                    body: self.lower_block(&closure.body),
+                }
+            }
+
+            expr::Expr::Unsafe(unsafe_expr) => Expr::Unsafe(self.lower_block(&unsafe_expr.body)),
+        };
+
+        self.alloc_expr(lowered, expr.matching_source())
+    }
+
+    /// Lower the variant tested for by an `is` expression.
+    fn lower_is_target(&mut self, is_expr: &IsExpr) -> IsTarget {
+        let ty::Type::Named(named) = &is_expr.target else {
+            return IsTarget::Type(self.lower_ty(&is_expr.target));
+        };
+
+        if let Some(def) = self.res.resolved(&named.name.full_path)
+            && self.res.def(def).kind == DefKind::Variant
+        {
+            return IsTarget::EnumVariant(Res::Def(def));
+        }
+
+        // A path that doesn't resolve, but whose prefix is a union, names a variant of the union.
+        if let Some(last) = named.name.tail.last()
+            && self.res.resolved(&named.name.full_path).is_none()
+        {
+            let prefix = match named.name.tail.len() {
+                1 => &named.name.head.fragment,
+                len => &named.name.tail[len - 2].fragment,
+            };
+
+            let prefix = Fragment::cover(&named.name.head.fragment, prefix);
+
+            return IsTarget::UnionVariant {
+                union: self.res(&prefix),
+                variant: last.fragment.as_str().to_owned(),
+            };
+        }
+
+        IsTarget::Type(self.lower_ty(&is_expr.target))
+    }
+
+    /// Lower an `if` expression, desugaring `else if` into an `else` block holding the inner `if`.
+    fn lower_if(&mut self, if_expr: &IfExpr) -> Expr {
+        let condition = self.lower_expr(&if_expr.condition);
+        let then_block = self.lower_block(&if_expr.then_block);
+
+        let else_block = if_expr.else_branch.as_ref().map(|branch| match &**branch {
+            expr::Expr::Block(block) => self.lower_block(block),
+
+            branch => {
+                let inner = self.lower_expr(branch);
+                self.desugar(Desugaring::ElseIf, |this| {
+                    this.alloc_block(Vec::new(), Some(inner), branch.matching_source())
+                })
+            }
+        });
+
+        Expr::If {
+            condition,
+            then_block,
+            else_block,
+        }
+    }
+
+    /// Make a block that only runs [Expr::Break].
+    fn break_block(&mut self, fragment: &Fragment) -> HirId {
+        let break_expr = self.alloc_expr(Expr::Break, fragment);
+        self.alloc_block(Vec::new(), Some(break_expr), fragment)
+    }
+
+    /// Lower a `while` loop into a `loop` around an `if` that `break`s once the condition is `false`:
+    ///
+    /// ```text
+    /// loop { if condition { body } else { break } }
+    /// ```
+    fn lower_while(&mut self, while_expr: &WhileExpr) -> HirId {
+        let condition = self.lower_expr(&while_expr.condition);
+        let body = self.lower_block(&while_expr.body);
+        let fragment = &while_expr.matching_source;
+
+        self.desugar(Desugaring::WhileLoop, |this| {
+            let else_block = this.break_block(fragment);
+
+            let if_expr = Expr::If {
+                condition,
+                then_block: body,
+                else_block: Some(else_block),
+            };
+
+            let if_expr = this.alloc_expr(if_expr, fragment);
+            let loop_body = this.alloc_block(Vec::new(), Some(if_expr), fragment);
+            this.alloc_expr(Expr::Loop(loop_body), fragment)
+        })
+    }
+
+    /// Lower a `for` loop over a range into a `loop` with a counter:
+    ///
+    /// ```text
+    /// {
+    ///     var <counter> = start;
+    ///     let <end> = end;
+    ///     loop { if <counter> < <end> { let i = <counter>; body; <counter> += 1; } else { break } }
+    /// }
+    /// ```
+    ///
+    /// Inclusive ranges compare with `<=`, and `break` after the body if the counter is the end, so that it doesn't
+    /// overflow when the end is the largest value of its type. The counter and end are integers of the same type as
+    /// the loop's binding, so they point at it.
+    fn lower_for(&mut self, for_expr: &ForExpr) -> HirId {
+        let range = &for_expr.range;
+        let fragment = &for_expr.matching_source;
+        let binding_fragment = &for_expr.binding.fragment;
+
+        let start = self.lower_expr(&range.start);
+        let end = self.lower_expr(&range.end);
+
+        self.desugar(Desugaring::ForLoop, |this| {
+            let counter = this.synthetic_binding("<counter>", true, binding_fragment);
+            let end_binding = this.synthetic_binding("<end>", false, binding_fragment);
+
+            let read = |this: &mut Self, binding| {
+                this.alloc_expr(Expr::Path(Res::Local(binding)), binding_fragment)
+            };
+
+            let counter_let = Stmt::Let {
+                binding: counter,
+                ty: None,
+                value: start,
+            };
+
+            let end_let = Stmt::Let {
+                binding: end_binding,
+                ty: None,
+                value: end,
+            };
+
+            let counter_let = this.alloc(Node::Stmt(counter_let), range.start.matching_source());
+            let end_let = this.alloc(Node::Stmt(end_let), range.end.matching_source());
+
+            // Bind the loop's name to the counter, and run the body.
+            let value = read(this, counter);
+            let binding = this.in_source(|this| this.binding(&for_expr.binding, false));
+
+            let binding_let = Stmt::Let {
+                binding,
+                ty: None,
+                value,
+            };
+
+            let mut stmts = vec![this.alloc(Node::Stmt(binding_let), binding_fragment)];
+            let body = this.in_source(|this| this.lower_block(&for_expr.body));
+            let body = this.alloc_expr(Expr::Block(body), &for_expr.body.matching_source);
+            stmts.push(this.alloc(Node::Stmt(Stmt::Expr(body)), &for_expr.body.matching_source));
+
+            if range.is_inclusive {
+                let (lhs, rhs) = (read(this, counter), read(this, end_binding));
+
+                let is_end = Expr::Binary {
+                    op: BinaryOperation::Equal,
+                    lhs,
+                    rhs,
+                };
+
+                let is_end = this.alloc_expr(is_end, &range.matching_source);
+                let then_block = this.break_block(fragment);
+
+                let stop = Expr::If {
+                    condition: is_end,
+                    then_block,
+                    else_block: None,
+                };
+
+                let stop = this.alloc_expr(stop, fragment);
+                stmts.push(this.alloc(Node::Stmt(Stmt::Expr(stop)), fragment));
+            }
+
+            let target = read(this, counter);
+            let one = this
+                .alloc_expr(Expr::Literal(Literal::Integer(BigUint::from(1u8))), binding_fragment);
+
+            let increment = Stmt::Assign {
+                target,
+                op: Some(BinaryOperation::Add),
+                value: one,
+            };
+
+            stmts.push(this.alloc(Node::Stmt(increment), fragment));
+            let then_block = this.alloc_block(stmts, None, fragment);
+
+            let (lhs, rhs) = (read(this, counter), read(this, end_binding));
+
+            let op = match range.is_inclusive {
+                true => BinaryOperation::LessOrEqual,
+                false => BinaryOperation::Less,
+            };
+
+            let condition = this.alloc_expr(Expr::Binary { op, lhs, rhs }, &range.matching_source);
+            let else_block = this.break_block(fragment);
+
+            let if_expr = Expr::If {
+                condition,
+                then_block,
+                else_block: Some(else_block),
+            };
+
+            let if_expr = this.alloc_expr(if_expr, fragment);
+            let loop_body = this.alloc_block(Vec::new(), Some(if_expr), fragment);
+            let loop_expr = this.alloc_expr(Expr::Loop(loop_body), fragment);
+            let block = this.alloc_block(vec![counter_let, end_let], Some(loop_expr), fragment);
+            this.alloc_expr(Expr::Block(block), fragment)
+        })
+    }
+
+    /// Lower a pattern.
+    fn lower_pattern(&mut self, pattern: &pattern::Pattern) -> HirId {
+        let lowered = match pattern {
+            pattern::Pattern::Wildcard(_) => Pattern::Wildcard,
+            pattern::Pattern::Binding(name) => Pattern::Binding(self.binding(name, false)),
+            pattern::Pattern::Integer(integer) => Pattern::Integer(integer.value()),
+            pattern::Pattern::Boolean(boolean) => Pattern::Boolean(boolean.value),
+
+            pattern::Pattern::Range(range) => Pattern::Range {
+                start: range.start.value(),
+                end: range.end.value(),
+                is_inclusive: range.is_inclusive,
+            },
+
+            pattern::Pattern::Variant(path) => Pattern::Variant(self.res(&path.full_path)),
+
+            pattern::Pattern::Record(record) => Pattern::Record {
+                ty: self.res(&record.name.full_path),
+                fields: (record.fields.iter())
+                    .map(|field| {
+                        (
+                            field.name.fragment.as_str().to_owned(),
+                            self.lower_pattern(&field.pattern),
+                        )
+                    })
+                    .collect(),
+            },
+
+            pattern::Pattern::Or(or) => Pattern::Or(
+                or.alternatives
+                    .iter()
+                    .map(|alternative| self.lower_pattern(alternative))
+                    .collect(),
+            ),
+        };
+
+        self.alloc(Node::Pattern(lowered), pattern.matching_source())
+    }
+}
@@ -13,7 +13,8 @@ pub const MULTI_LINE_COMMENT_END: &str = "*/";
 
 /// Attempt to match a sinlgle line comment from the start of the [Lexer::remaining] fragment.
 /// Return a [usize] and optionally a [TokenTy]. The [usize] indicates how many bytes were in the comment.
-/// The [TokenTy] (if it's not [None]) should be either [TokenTy::InnerDocComment] or [TokenTy::OuterDocComment].
+/// The [TokenTy] (if it's not [None]) should be either [TokenTy::OuterDocComment] (for `///` comments) or
+/// [TokenTy::InnerDocComment] (for `//!` comments).
 ///
 /// If the [TokenTy] is not [None], the lexer should consume the specified number of bytes (by the [usize]) and
 /// Produce a token with the [variant](super::token::Token::variant) from this function.
@@ -31,8 +32,8 @@ pub fn try_match_single_line_comment(lexer: &Lexer) -> (usize, Option<TokenTy>)
         // We consumed it successfully, read through a newline or the end of the forked lexer if we get there.
 
         // First determine if this is a doc comment of some kind.
-        let is_inner_doc: bool = fork.matches("/") && !fork.matches("//");
-        let is_outer_doc: bool = fork.matches("!");
+        let is_outer_doc: bool = fork.matches("/") && !fork.matches("//");
+        let is_inner_doc: bool = fork.matches("!");
 
         // The consume until a newline, carraige return, or the end of the source fragment.
         while !fork.remaining.is_empty() && !fork.matches("\r") && !fork.matches("\n") {
@@ -60,8 +61,8 @@ pub fn try_match_single_line_comment(lexer: &Lexer) -> (usize, Option<TokenTy>)
 
 /// Attempt to match a block comment from the start of the [Lexer::remaining] fragment.
 /// Return a [usize] and optionally a [TokenTy]. The [usize] indicates how many bytes were in the comment.
-/// The [TokenTy] (if it's not [None]) should be [TokenTy::InnerBlockDocComment], [TokenTy::OuterBlockDocComment], or
-/// [TokenTy::UnterminatedBlockComment].
+/// The [TokenTy] (if it's not [None]) should be [TokenTy::OuterBlockDocComment] (for `/**` comments),
+/// [TokenTy::InnerBlockDocComment] (for `/*!` comments), or [TokenTy::UnterminatedBlockComment].
 ///
 /// If the [TokenTy] is not [None], the lexer should consume the specified number of bytes (by the [usize]) and
 /// Produce a token with the [variant](super::token::Token::variant) from this function.
@@ -82,9 +83,9 @@ pub fn try_match_block_comment(lexer: &Lexer) -> (usize, Option<TokenTy>) {
     // Try to parse the start of a multi-line comment.
     if fork.consume(MULTI_LINE_COMMENT_START) {
         // Check if this is a doc comment.
-        let is_outer_doc: bool = fork.matches("!");
+        let is_inner_doc: bool = fork.matches("!");
         // Use this to indicate that more than one following asterix is not a doc comment.
-        let is_inner_doc: bool = fork.matches("*") && !fork.matches("**");
+        let is_outer_doc: bool = fork.matches("*") && !fork.matches("**");
 
         // Consume until we see the end of the doc comment. If we run out of characters, consider the
         // comment unterminated.
@@ -144,4 +145,20 @@ mod tests {
         assert!(lexer.next_token().is_none());
         assert_eq!(lexer.remaining.len(), 0);
     }
+
+    #[test]
+    fn doc_comments() {
+        use crate::lexer::token::TokenTy;
+
+        for (source, variant) in [
+            ("/// outer", TokenTy::OuterDocComment),
+            ("//! inner", TokenTy::InnerDocComment),
+            ("/** outer */", TokenTy::OuterBlockDocComment),
+            ("/*! inner */", TokenTy::InnerBlockDocComment),
+        ] {
+            let mut lexer = Lexer::new_test(source);
+            assert_eq!(lexer.next_token().unwrap().variant, variant);
+            assert_eq!(lexer.remaining.len(), 0);
+        }
+    }
 }
@@ -30,6 +30,7 @@ fn identifier_or_keyword(fragment: Fragment) -> TokenTy {
 
         "use" => KwUse,
         "as" => KwAs,
+        "is" => KwIs,
         "mod" => KwMod,
         "pub" => KwPub,
 
@@ -44,6 +45,7 @@ fn identifier_or_keyword(fragment: Fragment) -> TokenTy {
 
         "let" => KwLet,
         "var" => KwVar,
+        "mut" => KwMut,
 
         "true" => KwTrue,
         "false" => KwFalse,
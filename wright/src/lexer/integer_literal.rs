@@ -31,12 +31,12 @@ pub fn try_consume_integer_literal(lexer: &mut Lexer) -> Option<Token> {
                 'o' => 8,
                 _ => unreachable!("the prefix byte is checked above"),
             };
+
+            // The first character after a prefix is required to be a digit, not underscore.
+            bytes_consumed += chars.next_if(|c| c.is_digit(radix))?.len_utf8();
         }
     }
 
-    // The first character after the optional prefix is required to be a digit, not underscore.
-    bytes_consumed += chars.next_if(|c| c.is_digit(radix))?.len_utf8();
-
     // Add the rest of the integer literal.
     bytes_consumed += chars
         .take_while(|c| c.is_digit(radix) || *c == '_')
@@ -63,6 +63,14 @@ mod tests {
         assert_eq!(lexer.remaining.as_str(), ".");
     }
 
+    #[test]
+    fn single_digit() {
+        let mut lexer = Lexer::new_test("1 0");
+        assert_eq!(lexer.next_token().unwrap().variant, TokenTy::IntegerLiteral);
+        lexer.next_token();
+        assert_eq!(lexer.next_token().unwrap().fragment.as_str(), "0");
+    }
+
     #[test]
     fn cant_start_with_underscore() {
         let mut lexer = Lexer::new_test("0x__10");
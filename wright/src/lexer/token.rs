@@ -41,7 +41,7 @@ pub enum TokenTy {
     At,
     Tilde,
     Semi,
-    Dot,
+    Dot, DotDot, DotDotEq,
     Comma,
     Hash,
     Question,
@@ -76,6 +76,7 @@ pub enum TokenTy {
     KwTrait,
     KwUse,
     KwAs,
+    KwIs,
     KwConst,
     KwMod,
     KwIf,
@@ -92,6 +93,7 @@ pub enum TokenTy {
 
     KwLet,
     KwVar,
+    KwMut,
 
     // Keyword primitive types.
     KwBool,
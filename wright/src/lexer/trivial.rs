@@ -5,6 +5,9 @@ use super::{
     token::{Token, TokenTy},
 };
 
+/// Trivial tokens that are three ASCII characters and can be matched directly against the input source code.
+pub const THREE_ASCII_TRIVIAL_TOKENS: &[(&[u8; 3], TokenTy)] = &[(b"..=", TokenTy::DotDotEq)];
+
 /// Trivial tokens that are two ASCII characters and can be matched directly
 /// against the input source code.
 pub const TWO_ASCII_TRIVIAL_TOKENS: &[(&[u8; 2], TokenTy)] = &[
@@ -28,6 +31,7 @@ pub const TWO_ASCII_TRIVIAL_TOKENS: &[(&[u8; 2], TokenTy)] = &[
     (b"*=", TokenTy::StarEq),
     (b"+=", TokenTy::PlusEq),
     (b"/=", TokenTy::DivEq),
+    (b"..", TokenTy::DotDot),
 ];
 
 /// Single ASCII character trivial tokens that can be matched directly against
@@ -58,6 +62,8 @@ pub const SINGLE_ASCII_CHAR_TRIVIAL_TOKENS: &[(u8, TokenTy)] = &[
     (b'^', TokenTy::Xor),
     (b'*', TokenTy::Star),
     (b'%', TokenTy::Mod),
+    (b'.', TokenTy::Dot),
+    (b'~', TokenTy::Tilde),
 ];
 
 /// Attempt to consume a "trivial" token from the start of the [Lexer]'s [Lexer::remaining] fragment.
@@ -72,6 +78,19 @@ pub fn try_consume_trivial_token(lexer: &mut Lexer) -> Option<Token> {
         return None;
     }
 
+    // Attempt to match any three-byte ASCII trivial tokens first, since matching is greedy.
+    if bytes_remaining >= 3 {
+        // SAFETY: We just checked length.
+        let bytes: &[u8] = unsafe { lexer.remaining.as_str().as_bytes().get_unchecked(0..3) };
+
+        for (pattern, kind) in THREE_ASCII_TRIVIAL_TOKENS {
+            if bytes == *pattern {
+                // These are all ASCII, so this is on a character boundary.
+                return Some(lexer.split_token_unchecked(3, *kind));
+            }
+        }
+    }
+
     // Attempt to match any two-byte ASCII trivial tokens.
     // This must be done before single-ascii byte tokens since matching is greedy.
     if bytes_remaining >= 2 {
@@ -131,4 +150,12 @@ mod tests {
         assert_eq!(plus_token.variant, TokenTy::Plus);
         assert_eq!(plus_token.fragment.len(), 1);
     }
+
+    #[test]
+    fn range_tokens() {
+        let mut lexer = Lexer::new_test("..=..");
+        assert_eq!(lexer.next_token().unwrap().variant, TokenTy::DotDotEq);
+        assert_eq!(lexer.next_token().unwrap().variant, TokenTy::DotDot);
+        assert_eq!(lexer.bytes_remaining(), 0);
+    }
 }
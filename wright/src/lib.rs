@@ -50,6 +50,27 @@ pub mod ast;
 #[cfg(feature = "parser")]
 pub mod parser;
 
+#[cfg(feature = "parser")]
+pub mod package;
+
+#[cfg(feature = "analysis")]
+pub mod analysis;
+
+#[cfg(feature = "analysis")]
+pub mod hir;
+
+#[cfg(feature = "analysis")]
+pub mod mir;
+
+#[cfg(feature = "vm")]
+pub mod vm;
+
+#[cfg(feature = "codegen")]
+pub mod codegen;
+
+#[cfg(feature = "doc")]
+pub mod doc;
+
 pub mod util;
 
 // pub mod repl;
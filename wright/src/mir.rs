@@ -0,0 +1,476 @@
+//! The mid-level intermediate representation (MIR) -- a control-flow graph of basic blocks over typed SSA values,
+//! lowered from the [Hir] once a package has been checked without errors. Code generation, and any optimizations,
+//! run over the MIR.
+//!
+//! Every [Value] is assigned exactly once, either by an instruction or as a parameter of a block (which is how
+//! values flow into the blocks where control flow joins, instead of phi nodes). Bindings that are assigned to
+//! (`var`s), or that are borrowed, live in [Slot]s instead, which are read and written through [Place]s, and end
+//! with an explicit [Inst::Drop] where their scope ends.
+//!
+//! The textual form (see [Program::display]) is printed by `wright debug mir`.
+
+use crate::{
+    analysis::{
+        resolve::{DefId, Resolution},
+        typeck::{TypeckResults, ty::Ty},
+    },
+    ast::expr::{BinaryOperation, UnaryOperation},
+    hir::Hir,
+    source_tracking::fragment::Fragment,
+};
+use num::BigInt;
+use std::collections::HashMap;
+
+mod display;
+mod lower;
+mod verify;
+
+pub use display::DisplayProgram;
+#[cfg(any(feature = "vm", feature = "codegen"))]
+pub(crate) use display::qualified_name;
+pub use verify::verify;
+
+/// The MIR of every function, method, and closure in a package.
+#[derive(Debug, Default)]
+pub struct Program {
+    /// Every body, by [BodyId]. Functions come first, in the order they're declared, followed by closures.
+    pub bodies: Vec<Body>,
+    /// The name of every function and method in the package (including ones without bodies), as they're printed.
+    pub names: HashMap<DefId, String>,
+}
+
+impl Program {
+    /// Get a body.
+    pub fn body(&self, id: BodyId) -> &Body {
+        &self.bodies[id.0 as usize]
+    }
+
+    /// Get the body of the function or method with the given definition.
+    pub fn function(&self, def: DefId) -> Option<BodyId> {
+        (self.bodies.iter())
+            .position(|body| body.def == Some(def))
+            .map(|index| BodyId(index as u32))
+    }
+
+    /// Display the textual form of this program, using the given resolution for the names of types.
+    pub fn display<'a>(&'a self, resolution: &'a Resolution) -> DisplayProgram<'a> {
+        DisplayProgram {
+            program: self,
+            resolution,
+        }
+    }
+}
+
+/// The ID of a [Body] in a [Program].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BodyId(pub u32);
+
+/// The ID of a [BasicBlock] in a [Body]. The entry block is always [BlockId::ENTRY].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BlockId(pub u32);
+
+impl BlockId {
+    /// The block that a body starts at.
+    pub const ENTRY: BlockId = BlockId(0);
+}
+
+/// An SSA value in a [Body].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Value(pub u32);
+
+/// A slot of memory in a [Body], for a binding that's assigned to or borrowed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Slot(pub u32);
+
+/// The code of a function, method, or closure.
+#[derive(Debug)]
+pub struct Body {
+    /// The function or method this is the body of ([None] for closures).
+    pub def: Option<DefId>,
+    /// The name of the body, i.e. `main` or `main::{closure#0}`.
+    pub name: String,
+    /// The parameters, which are defined at the start of the entry block. Closures take the values they capture
+    /// before their own parameters.
+    pub params: Vec<Value>,
+    /// The return type.
+    pub ret: Ty,
+    /// The type of every value, by [Value].
+    pub values: Vec<Ty>,
+    /// Every slot, by [Slot].
+    pub slots: Vec<SlotDecl>,
+    /// Every basic block, by [BlockId].
+    pub blocks: Vec<BasicBlock>,
+}
+
+impl Body {
+    /// Get the type of a value.
+    pub fn value_ty(&self, value: Value) -> &Ty {
+        &self.values[value.0 as usize]
+    }
+
+    /// Get a basic block.
+    pub fn block(&self, id: BlockId) -> &BasicBlock {
+        &self.blocks[id.0 as usize]
+    }
+
+    /// Get the type of an operand.
+    pub fn operand_ty(&self, operand: &Operand) -> Ty {
+        match operand {
+            Operand::Value(value) => self.value_ty(*value).clone(),
+            Operand::Const(constant) => constant.ty(),
+        }
+    }
+
+    /// Get the IDs of every block, in order.
+    pub fn block_ids(&self) -> impl Iterator<Item = BlockId> {
+        (0..self.blocks.len() as u32).map(BlockId)
+    }
+}
+
+/// A slot of memory.
+#[derive(Debug)]
+pub struct SlotDecl {
+    /// The name of the binding the slot is for ([None] for temporaries).
+    pub name: Option<String>,
+    /// The type of the value in the slot.
+    pub ty: Ty,
+}
+
+/// A straight line of instructions, ending with a [Terminator].
+#[derive(Debug)]
+pub struct BasicBlock {
+    /// The values passed to this block by the terminators that jump to it.
+    pub params: Vec<Value>,
+    /// The instructions, in order.
+    pub insts: Vec<Inst>,
+    /// Where control goes after the instructions.
+    pub terminator: Terminator,
+    /// The source code each instruction was lowered from, in the same order as [BasicBlock::insts].
+    pub spans: Vec<Fragment>,
+    /// The source code the terminator was lowered from.
+    pub terminator_span: Fragment,
+}
+
+/// An instruction in a [BasicBlock].
+#[derive(Debug)]
+pub enum Inst {
+    /// Compute a value.
+    Assign(Value, Rvalue),
+    /// Write a value to a place.
+    Store(Place, Operand),
+    /// End the lifetime of a slot, at the end of the scope of its binding.
+    Drop(Slot),
+}
+
+/// A location in memory: a slot, or what a reference points to, followed by fields.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Place {
+    /// Where the place starts.
+    pub base: PlaceBase,
+    /// The steps taken from the base, in order.
+    pub projections: Vec<Projection>,
+}
+
+impl Place {
+    /// Make a place for a whole slot.
+    pub fn slot(slot: Slot) -> Place {
+        Place {
+            base: PlaceBase::Slot(slot),
+            projections: Vec::new(),
+        }
+    }
+
+    /// Make a place for what a reference points to.
+    pub fn deref(reference: Value) -> Place {
+        Place {
+            base: PlaceBase::Value(reference),
+            projections: vec![Projection::Deref],
+        }
+    }
+}
+
+/// The start of a [Place].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlaceBase {
+    /// A slot.
+    Slot(Slot),
+    /// A reference value, which must be followed by [Projection::Deref].
+    Value(Value),
+}
+
+/// A step in a [Place].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Projection {
+    /// Follow a reference.
+    Deref,
+    /// A field of a record, or a variant of a union.
+    Field(String),
+}
+
+/// A value used by an instruction or terminator.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Operand {
+    /// An SSA value.
+    Value(Value),
+    /// A constant.
+    Const(Const),
+}
+
+/// A constant value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Const {
+    /// An integer of the given type.
+    Int(BigInt, Ty),
+    /// `true` or `false`.
+    Bool(bool),
+    /// A string.
+    Str(String),
+    /// The value of expressions that don't produce one.
+    Void,
+    /// A function, method, builtin, or `const`, with its type.
+    Def(DefId, Ty),
+    /// A variant of an enum, with its index in the enum and the enum's type.
+    Variant(DefId, u32, Ty),
+}
+
+impl Const {
+    /// Get the type of this constant.
+    pub fn ty(&self) -> Ty {
+        match self {
+            Const::Int(_, ty) | Const::Def(_, ty) | Const::Variant(_, _, ty) => ty.clone(),
+            Const::Bool(_) => Ty::BOOL,
+            Const::Str(_) => Ty::Str,
+            Const::Void => Ty::Void,
+        }
+    }
+}
+
+/// The computation of a value by an [Inst::Assign].
+#[derive(Debug)]
+pub enum Rvalue {
+    /// Just an operand.
+    Use(Operand),
+    /// Read a place.
+    Load(Place),
+    /// Take a reference to a place.
+    Ref {
+        /// The place.
+        place: Place,
+        /// Whether the reference is mutable.
+        is_mutable: bool,
+    },
+    /// A unary operation. References and dereferences are [Rvalue::Ref] and [Rvalue::Load] instead.
+    Unary(UnaryOperation, Operand),
+    /// A binary operation. `&&` and `||` are lowered to branches instead.
+    Binary(BinaryOperation, Operand, Operand),
+    /// Call a function (or a closure).
+    Call {
+        /// The function or closure.
+        callee: Operand,
+        /// The arguments.
+        args: Vec<Operand>,
+    },
+    /// Make a record, or a union holding one of its variants.
+    Aggregate {
+        /// The name and value of each field given.
+        fields: Vec<(String, Operand)>,
+    },
+    /// Read a field of a record value, or a variant of a union value.
+    Field(Operand, String),
+    /// Check if a union value holds the given variant.
+    IsVariant(Operand, String),
+    /// Make a closure, capturing the given values.
+    Closure {
+        /// The body of the closure.
+        body: BodyId,
+        /// The captured values, passed to the body before its parameters.
+        captures: Vec<Operand>,
+    },
+}
+
+/// The end of a [BasicBlock].
+#[derive(Debug)]
+pub enum Terminator {
+    /// Continue with a block.
+    Goto(Jump),
+    /// Continue with one of two blocks, depending on a `bool`.
+    Branch {
+        /// The condition.
+        condition: Operand,
+        /// Where to go if the condition is `true`.
+        then: Jump,
+        /// Where to go if the condition is `false`.
+        otherwise: Jump,
+    },
+    /// Continue with the block for the value of an integer or enum, or a default block.
+    Switch {
+        /// The value being switched on.
+        value: Operand,
+        /// The block for each value (the index of the variant, for enums).
+        cases: Vec<(BigInt, BlockId)>,
+        /// The block for any other value.
+        otherwise: BlockId,
+    },
+    /// Return from the body.
+    Return(Operand),
+    /// Control never reaches the end of this block.
+    Unreachable,
+}
+
+impl Terminator {
+    /// Get the jumps this terminator can make, to blocks with the arguments passed to them.
+    pub fn jumps(&self) -> Vec<(BlockId, &[Operand])> {
+        match self {
+            Terminator::Goto(jump) => vec![(jump.target, &jump.args)],
+            Terminator::Branch {
+                then, otherwise, ..
+            } => {
+                vec![
+                    (then.target, &then.args),
+                    (otherwise.target, &otherwise.args),
+                ]
+            }
+            Terminator::Switch {
+                cases, otherwise, ..
+            } => (cases.iter().map(|(_, target)| (*target, &[][..])))
+                .chain([(*otherwise, &[][..])])
+                .collect(),
+            Terminator::Return(_) | Terminator::Unreachable => Vec::new(),
+        }
+    }
+
+    /// Get the blocks that control can continue with after this terminator.
+    pub fn successors(&self) -> Vec<BlockId> {
+        self.jumps().into_iter().map(|(target, _)| target).collect()
+    }
+}
+
+/// A jump to a block, with the arguments for its parameters.
+#[derive(Debug)]
+pub struct Jump {
+    /// The block.
+    pub target: BlockId,
+    /// The arguments.
+    pub args: Vec<Operand>,
+}
+
+impl Jump {
+    /// Make a jump to a block without parameters.
+    pub fn to(target: BlockId) -> Jump {
+        Jump {
+            target,
+            args: Vec::new(),
+        }
+    }
+}
+
+/// Lower every function and method in the [Hir] (and the closures inside of them) to MIR. The package must have
+/// been checked without errors.
+pub fn lower(hir: &Hir, resolution: &Resolution, typeck: &TypeckResults) -> Program {
+    lower::lower(hir, resolution, typeck)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        BasicBlock, BlockId, Body, Const, Inst, Jump, Operand, Rvalue, Terminator, Value, verify,
+    };
+    use crate::{
+        analysis::typeck::ty::Ty,
+        ast::expr::UnaryOperation,
+        source_tracking::{filename::FileName, source::Source},
+    };
+    use std::sync::Arc;
+
+    /// Make a body that takes a `bool`, with the given blocks.
+    fn body(blocks: Vec<BasicBlock>, values: usize) -> Body {
+        Body {
+            def: None,
+            name: "test".to_owned(),
+            params: vec![Value(0)],
+            ret: Ty::BOOL,
+            values: vec![Ty::BOOL; values],
+            slots: Vec::new(),
+            blocks,
+        }
+    }
+
+    fn block(params: Vec<Value>, insts: Vec<Inst>, terminator: Terminator) -> BasicBlock {
+        let span = Arc::new(Source::new_from_static_str(FileName::None, "")).as_fragment();
+
+        BasicBlock {
+            params,
+            spans: vec![span.clone(); insts.len()],
+            insts,
+            terminator,
+            terminator_span: span,
+        }
+    }
+
+    #[test]
+    fn test_verify() {
+        // Branch on the parameter, and join with the other `bool`.
+        let valid = body(
+            vec![
+                block(
+                    Vec::new(),
+                    vec![Inst::Assign(
+                        Value(1),
+                        Rvalue::Unary(UnaryOperation::BooleanNot, Operand::Value(Value(0))),
+                    )],
+                    Terminator::Branch {
+                        condition: Operand::Value(Value(0)),
+                        then: Jump {
+                            target: BlockId(1),
+                            args: vec![Operand::Value(Value(1))],
+                        },
+                        otherwise: Jump {
+                            target: BlockId(1),
+                            args: vec![Operand::Const(Const::Bool(true))],
+                        },
+                    },
+                ),
+                block(vec![Value(2)], Vec::new(), Terminator::Return(Operand::Value(Value(2)))),
+            ],
+            3,
+        );
+
+        assert_eq!(verify(&valid), Vec::<String>::new());
+
+        // Jump with the wrong number of arguments, and use a value whose definition doesn't dominate the use.
+        let invalid = body(
+            vec![
+                block(
+                    Vec::new(),
+                    Vec::new(),
+                    Terminator::Branch {
+                        condition: Operand::Value(Value(0)),
+                        then: Jump::to(BlockId(1)),
+                        otherwise: Jump {
+                            target: BlockId(2),
+                            args: vec![Operand::Const(Const::Bool(true))],
+                        },
+                    },
+                ),
+                block(
+                    Vec::new(),
+                    vec![Inst::Assign(
+                        Value(1),
+                        Rvalue::Use(Operand::Const(Const::Bool(true))),
+                    )],
+                    Terminator::Goto(Jump::to(BlockId(2))),
+                ),
+                block(Vec::new(), Vec::new(), Terminator::Return(Operand::Value(Value(1)))),
+            ],
+            2,
+        );
+
+        assert_eq!(
+            verify(&invalid),
+            [
+                "in `test`, bb0 passes 1 argument to bb2, which takes 0",
+                "in `test`, %1 is used in bb2, but its definition in bb1 doesn't dominate it",
+            ]
+        );
+    }
+}
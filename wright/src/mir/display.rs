@@ -0,0 +1,362 @@
+//! The textual form of the MIR, as printed by `wright debug mir`:
+//!
+//! ```text
+//! fn sum(%0: u8) -> u8 {
+//!     slot _0: u8 // total
+//!
+//! bb0:
+//!     store _0, 0_u8
+//!     %1: u8 = load _0
+//!     return %1
+//! }
+//! ```
+
+use super::{
+    BasicBlock, Body, Const, Inst, Jump, Operand, Place, PlaceBase, Program, Projection, Rvalue,
+    Terminator,
+};
+use crate::{
+    analysis::{
+        resolve::{DefId, Resolution},
+        typeck::ty::Ty,
+    },
+    ast::expr::{BinaryOperation, UnaryOperation},
+};
+use std::fmt::{self, Display, Write};
+
+/// A [Program] that can be displayed in its textual form. See [Program::display].
+#[derive(Clone, Copy, Debug)]
+pub struct DisplayProgram<'a> {
+    pub(super) program: &'a Program,
+    pub(super) resolution: &'a Resolution,
+}
+
+impl Display for DisplayProgram<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, body) in self.program.bodies.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+
+            self.body(f, body)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl DisplayProgram<'_> {
+    /// Write a body.
+    fn body(&self, f: &mut fmt::Formatter<'_>, body: &Body) -> fmt::Result {
+        write!(f, "fn {}(", body.name)?;
+
+        for (index, param) in body.params.iter().enumerate() {
+            if index > 0 {
+                f.write_str(", ")?;
+            }
+
+            write!(f, "%{}: {}", param.0, self.ty(body.value_ty(*param)))?;
+        }
+
+        f.write_str(")")?;
+
+        if body.ret != Ty::Void {
+            write!(f, " -> {}", self.ty(&body.ret))?;
+        }
+
+        writeln!(f, " {{")?;
+
+        for (index, slot) in body.slots.iter().enumerate() {
+            write!(f, "    slot _{index}: {}", self.ty(&slot.ty))?;
+
+            if let Some(name) = &slot.name {
+                write!(f, " // {name}")?;
+            }
+
+            writeln!(f)?;
+        }
+
+        for (index, block) in body.blocks.iter().enumerate() {
+            if index > 0 || !body.slots.is_empty() {
+                writeln!(f)?;
+            }
+
+            self.block(f, body, index, block)?;
+        }
+
+        writeln!(f, "}}")
+    }
+
+    /// Write a basic block.
+    fn block(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        body: &Body,
+        index: usize,
+        block: &BasicBlock,
+    ) -> fmt::Result {
+        write!(f, "bb{index}")?;
+
+        if !block.params.is_empty() {
+            f.write_str("(")?;
+
+            for (index, param) in block.params.iter().enumerate() {
+                if index > 0 {
+                    f.write_str(", ")?;
+                }
+
+                write!(f, "%{}: {}", param.0, self.ty(body.value_ty(*param)))?;
+            }
+
+            f.write_str(")")?;
+        }
+
+        writeln!(f, ":")?;
+
+        for inst in &block.insts {
+            f.write_str("    ")?;
+
+            match inst {
+                Inst::Assign(value, rvalue) => {
+                    write!(f, "%{}: {} = ", value.0, self.ty(body.value_ty(*value)))?;
+                    self.rvalue(f, rvalue)?;
+                }
+
+                Inst::Store(place, operand) => {
+                    write!(f, "store {}, {}", self.place(place), self.operand(operand))?
+                }
+
+                Inst::Drop(slot) => write!(f, "drop _{}", slot.0)?,
+            }
+
+            writeln!(f)?;
+        }
+
+        f.write_str("    ")?;
+
+        match &block.terminator {
+            Terminator::Goto(jump) => write!(f, "goto {}", self.jump(jump))?,
+
+            Terminator::Branch {
+                condition,
+                then,
+                otherwise,
+            } => write!(
+                f,
+                "branch {}, {}, {}",
+                self.operand(condition),
+                self.jump(then),
+                self.jump(otherwise)
+            )?,
+
+            Terminator::Switch {
+                value,
+                cases,
+                otherwise,
+            } => {
+                write!(f, "switch {} [", self.operand(value))?;
+
+                for (index, (case, target)) in cases.iter().enumerate() {
+                    if index > 0 {
+                        f.write_str(", ")?;
+                    }
+
+                    write!(f, "{case} => bb{}", target.0)?;
+                }
+
+                write!(f, "], otherwise bb{}", otherwise.0)?;
+            }
+
+            Terminator::Return(value) => write!(f, "return {}", self.operand(value))?,
+            Terminator::Unreachable => f.write_str("unreachable")?,
+        }
+
+        writeln!(f)
+    }
+
+    /// Write an rvalue.
+    fn rvalue(&self, f: &mut fmt::Formatter<'_>, rvalue: &Rvalue) -> fmt::Result {
+        match rvalue {
+            Rvalue::Use(operand) => write!(f, "{}", self.operand(operand)),
+            Rvalue::Load(place) => write!(f, "load {}", self.place(place)),
+
+            Rvalue::Ref { place, is_mutable } => {
+                let qualifier = if *is_mutable { "mut " } else { "" };
+                write!(f, "ref {qualifier}{}", self.place(place))
+            }
+
+            Rvalue::Unary(op, operand) => {
+                write!(f, "{} {}", unary_mnemonic(*op), self.operand(operand))
+            }
+
+            Rvalue::Binary(op, lhs, rhs) => {
+                write!(f, "{} {}, {}", binary_mnemonic(*op), self.operand(lhs), self.operand(rhs))
+            }
+
+            Rvalue::Call { callee, args } => {
+                write!(f, "call {}(", self.operand(callee))?;
+                self.list(f, args)?;
+                f.write_str(")")
+            }
+
+            Rvalue::Aggregate { fields } => {
+                f.write_str("record {")?;
+
+                for (index, (name, operand)) in fields.iter().enumerate() {
+                    let separator = if index > 0 { "," } else { "" };
+                    write!(f, "{separator} {name}: {}", self.operand(operand))?;
+                }
+
+                f.write_str(" }")
+            }
+
+            Rvalue::Field(operand, field) => write!(f, "field {}.{field}", self.operand(operand)),
+            Rvalue::IsVariant(operand, variant) => {
+                write!(f, "is_variant {}.{variant}", self.operand(operand))
+            }
+
+            Rvalue::Closure { body, captures } => {
+                write!(f, "closure {} [", self.program.body(*body).name)?;
+                self.list(f, captures)?;
+                f.write_str("]")
+            }
+        }
+    }
+
+    /// Write a comma separated list of operands.
+    fn list(&self, f: &mut fmt::Formatter<'_>, operands: &[Operand]) -> fmt::Result {
+        for (index, operand) in operands.iter().enumerate() {
+            if index > 0 {
+                f.write_str(", ")?;
+            }
+
+            write!(f, "{}", self.operand(operand))?;
+        }
+
+        Ok(())
+    }
+
+    /// Format a jump.
+    fn jump(&self, jump: &Jump) -> String {
+        let mut text = format!("bb{}", jump.target.0);
+
+        if !jump.args.is_empty() {
+            text.push('(');
+
+            for (index, arg) in jump.args.iter().enumerate() {
+                if index > 0 {
+                    text.push_str(", ");
+                }
+
+                let _ = write!(text, "{}", self.operand(arg));
+            }
+
+            text.push(')');
+        }
+
+        text
+    }
+
+    /// Format a place. Fields of dereferenced places are wrapped in parentheses, i.e. `(*%1).x`.
+    fn place(&self, place: &Place) -> String {
+        let mut text = match place.base {
+            PlaceBase::Slot(slot) => format!("_{}", slot.0),
+            PlaceBase::Value(value) => format!("%{}", value.0),
+        };
+
+        for (index, projection) in place.projections.iter().enumerate() {
+            match projection {
+                Projection::Deref if index + 1 < place.projections.len() => {
+                    text = format!("(*{text})")
+                }
+                Projection::Deref => text = format!("*{text}"),
+                Projection::Field(field) => text = format!("{text}.{field}"),
+            }
+        }
+
+        text
+    }
+
+    /// Format an operand.
+    fn operand(&self, operand: &Operand) -> String {
+        match operand {
+            Operand::Value(value) => format!("%{}", value.0),
+            Operand::Const(Const::Int(value, ty)) => format!("{value}_{}", self.ty(ty)),
+            Operand::Const(Const::Bool(value)) => value.to_string(),
+            Operand::Const(Const::Str(value)) => format!("{value:?}"),
+            Operand::Const(Const::Void) => "()".to_owned(),
+            Operand::Const(Const::Def(def, _)) => self.def_name(*def),
+
+            Operand::Const(Const::Variant(variant, _, _)) => {
+                let name = &self.resolution.def(*variant).name;
+
+                match self.resolution.enum_of(*variant) {
+                    Some(enumeration) => format!("{}::{name}", self.def_name(enumeration)),
+                    None => name.clone(),
+                }
+            }
+        }
+    }
+
+    /// Get the name of a definition: its name in the program for functions and methods, and otherwise its name
+    /// qualified by its module.
+    fn def_name(&self, def: DefId) -> String {
+        match self.program.names.get(&def) {
+            Some(name) => name.clone(),
+            None => qualified_name(self.resolution, def),
+        }
+    }
+
+    /// Display a type.
+    fn ty<'t>(&'t self, ty: &'t Ty) -> impl Display + 't {
+        ty.display(self.resolution)
+    }
+}
+
+/// Get the name of a definition qualified by the module it's declared in, i.e. `shapes::area`.
+pub(crate) fn qualified_name(resolution: &Resolution, def: DefId) -> String {
+    let def = resolution.def(def);
+    let mut path = def.module.clone();
+    path.push(def.name.clone());
+    path.join("::")
+}
+
+/// Get the mnemonic of a unary operation.
+const fn unary_mnemonic(op: UnaryOperation) -> &'static str {
+    match op {
+        UnaryOperation::Negate => "neg",
+        UnaryOperation::BooleanNot => "not",
+        UnaryOperation::BitwiseNot => "bitnot",
+
+        // These are lowered to loads and references.
+        UnaryOperation::Reference
+        | UnaryOperation::MutableReference
+        | UnaryOperation::Dereference => op.symbol(),
+    }
+}
+
+/// Get the mnemonic of a binary operation.
+const fn binary_mnemonic(op: BinaryOperation) -> &'static str {
+    match op {
+        BinaryOperation::Add => "add",
+        BinaryOperation::Subtract => "sub",
+        BinaryOperation::Multiply => "mul",
+        BinaryOperation::Divide => "div",
+        BinaryOperation::Modulo => "rem",
+        BinaryOperation::BitwiseAnd => "and",
+        BinaryOperation::BitwiseOr => "or",
+        BinaryOperation::BitwiseXor => "xor",
+        BinaryOperation::ShiftLeft => "shl",
+        BinaryOperation::ShiftRight => "shr",
+        BinaryOperation::Equal => "eq",
+        BinaryOperation::NotEqual => "ne",
+        BinaryOperation::Less => "lt",
+        BinaryOperation::LessOrEqual => "le",
+        BinaryOperation::Greater => "gt",
+        BinaryOperation::GreaterOrEqual => "ge",
+
+        // These are lowered to branches.
+        BinaryOperation::LogicalAnd => "land",
+        BinaryOperation::LogicalOr => "lor",
+    }
+}
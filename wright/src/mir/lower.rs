@@ -0,0 +1,1643 @@
+//! Lowering the [Hir] to MIR.
+
+use super::{
+    BasicBlock, BlockId, Body, BodyId, Const, Inst, Jump, Operand, Place, PlaceBase, Program,
+    Projection, Rvalue, Slot, SlotDecl, Terminator, Value, display::qualified_name,
+};
+use crate::{
+    analysis::{
+        resolve::{DefId, DefKind, Resolution},
+        typeck::{Receiver, TypeckResults, ty::Ty},
+    },
+    ast::expr::{BinaryOperation, UnaryOperation},
+    hir::{Expr, Hir, HirId, IsTarget, Literal, Node, Param, Pattern, Res, Stmt},
+    source_tracking::fragment::Fragment,
+};
+use num::BigInt;
+use std::collections::{HashMap, HashSet};
+
+/// Lower every function and method with a body, followed by the closures inside of them.
+pub(super) fn lower(hir: &Hir, res: &Resolution, typeck: &TypeckResults) -> Program {
+    let cx = Context { hir, res, typeck };
+    let mut program = Program::default();
+
+    for function in &hir.functions {
+        let name = function_name(cx, function.def, &function.params);
+        program.names.insert(function.def, name);
+    }
+
+    let functions: Vec<_> = (hir.functions.iter())
+        .filter(|function| function.body.is_some())
+        .collect();
+
+    let mut closures = Closures {
+        first: functions.len() as u32,
+        pending: Vec::new(),
+    };
+
+    for function in functions {
+        let ret = match typeck.type_of_def(function.def) {
+            Some(Ty::Function { ret, .. }) => (**ret).clone(),
+            _ => Ty::Error,
+        };
+
+        let block = function.body.unwrap();
+        let span = hir.fragment(block).clone();
+        let builder = Builder::new(cx, program.names[&function.def].clone(), ret, span);
+        let body = builder.build(Some(function.def), &[], &function.params, block, &mut closures);
+        program.bodies.push(body);
+    }
+
+    // Closures can contain closures of their own, which are added to the end as they're found.
+    let mut next = 0;
+
+    while let Some(closure) = closures.pending.get(next).cloned() {
+        let Expr::Closure { params, body, .. } = cx.hir.expr(closure.expr) else {
+            unreachable!("closures are only queued for closure expressions")
+        };
+
+        let ret = match cx.expr_ty(closure.expr) {
+            Ty::Function { ret, .. } => *ret,
+            _ => Ty::Error,
+        };
+
+        let span = cx.hir.fragment(closure.expr).clone();
+        let builder = Builder::new(cx, closure.name, ret, span);
+        let body = builder.build(None, &closure.captures, params, *body, &mut closures);
+        program.bodies.push(body);
+        next += 1;
+    }
+
+    program
+}
+
+/// Get the name of the body of a function or method: its name qualified by its module, and for methods, by the
+/// type of their `self` parameter (i.e. `shapes::Circle::area`).
+fn function_name(cx: Context, def: DefId, params: &[Param]) -> String {
+    let definition = cx.res.def(def);
+
+    if definition.kind != DefKind::Method {
+        return qualified_name(cx.res, def);
+    }
+
+    let mut self_ty = params.first().map(|param| cx.binding_ty(param.binding));
+
+    while let Some(Ty::Reference { target, .. }) = self_ty {
+        self_ty = Some(*target);
+    }
+
+    let owner = match self_ty {
+        // The `Self` type of a trait's default methods is named after the trait.
+        Some(Ty::Param(param)) if cx.res.def(param).kind == DefKind::SelfType => (cx.res.defs())
+            .find(|(owner, _)| cx.res.self_type(*owner) == Some(param))
+            .map(|(owner, _)| cx.res.def(owner).name.clone()),
+
+        Some(ty @ Ty::Named { .. }) => Some(ty.display(cx.res).to_string()),
+        _ => None,
+    };
+
+    let mut path = definition.module.clone();
+    path.extend(owner);
+    path.push(definition.name.clone());
+    path.join("::")
+}
+
+/// The results of the earlier phases that lowering reads from.
+#[derive(Clone, Copy)]
+struct Context<'a> {
+    hir: &'a Hir,
+    res: &'a Resolution,
+    typeck: &'a TypeckResults,
+}
+
+impl Context<'_> {
+    /// Get the type of a binding.
+    fn binding_ty(&self, binding: HirId) -> Ty {
+        let fragment = self.hir.fragment(binding);
+
+        (self.typeck.type_of_binding(fragment))
+            .or_else(|| self.typeck.type_of_pattern(fragment))
+            .or_else(|| {
+                (self.hir.binding(binding).def).and_then(|def| self.typeck.type_of_def(def))
+            })
+            .cloned()
+            .unwrap_or(Ty::Error)
+    }
+
+    /// Get the type of an expression. The type checker only saw the expressions written in the source, so the
+    /// types of ones made by desugaring are worked out from what's inside of them.
+    fn expr_ty(&self, id: HirId) -> Ty {
+        let span = self.hir.span(id);
+
+        if span.desugaring.is_none()
+            && let Some(ty) = self.typeck.type_of_expr(&span.fragment)
+        {
+            return ty.clone();
+        }
+
+        match self.hir.expr(id) {
+            Expr::Path(Res::Local(binding)) => self.binding_ty(*binding),
+            Expr::Literal(Literal::Boolean(_)) => Ty::BOOL,
+            Expr::Literal(Literal::String(_)) => Ty::Str,
+
+            // Integer literals made by desugaring point at the binding they're used with.
+            Expr::Literal(Literal::Integer(_)) => (self.typeck.type_of_binding(&span.fragment))
+                .cloned()
+                .unwrap_or(Ty::Error),
+
+            Expr::Binary { op, lhs, .. } => match op {
+                BinaryOperation::Add
+                | BinaryOperation::Subtract
+                | BinaryOperation::Multiply
+                | BinaryOperation::Divide
+                | BinaryOperation::Modulo
+                | BinaryOperation::BitwiseAnd
+                | BinaryOperation::BitwiseOr
+                | BinaryOperation::BitwiseXor
+                | BinaryOperation::ShiftLeft
+                | BinaryOperation::ShiftRight => self.expr_ty(*lhs),
+                _ => Ty::BOOL,
+            },
+
+            Expr::Block(block) => match self.hir.block(*block).tail {
+                Some(tail) => self.expr_ty(tail),
+                None => Ty::Void,
+            },
+
+            _ => Ty::Void,
+        }
+    }
+
+    /// Find the binding that a place expression starts at, if it starts at a binding rather than at what a
+    /// reference points to.
+    fn place_root(&self, id: HirId) -> Option<HirId> {
+        match self.hir.expr(id) {
+            Expr::Path(Res::Local(binding)) => Some(*binding),
+
+            Expr::Field { base, .. } => match self.expr_ty(*base) {
+                Ty::Reference { .. } => None,
+                _ => self.place_root(*base),
+            },
+
+            _ => None,
+        }
+    }
+
+    /// Find the bindings in a body that need a [Slot]: ones that are assigned to, and ones that are borrowed.
+    fn slotted_bindings(&self, root: HirId) -> HashSet<HirId> {
+        let mut slotted = HashSet::new();
+        let mut stack = vec![root];
+
+        while let Some(id) = stack.pop() {
+            match self.hir.node(id) {
+                Node::Binding(binding) if binding.is_mutable => {
+                    slotted.insert(id);
+                }
+
+                Node::Expr(Expr::Unary {
+                    op: UnaryOperation::Reference | UnaryOperation::MutableReference,
+                    operand,
+                }) => slotted.extend(self.place_root(*operand)),
+
+                Node::Expr(Expr::Call { callee, .. }) => {
+                    let receiver = self.typeck.method_call(self.hir.fragment(id));
+
+                    if let (Some(call), Expr::Field { base, .. }) =
+                        (receiver, self.hir.expr(*callee))
+                        && let Receiver::Borrow { .. } = call.receiver
+                    {
+                        slotted.extend(self.place_root(*base));
+                    }
+                }
+
+                _ => {}
+            }
+
+            self.hir.for_each_child(id, &mut |child| stack.push(child));
+        }
+
+        slotted
+    }
+
+    /// Find the bindings from outside of a closure that it uses, in the order they're first used.
+    fn captures(&self, closure: HirId) -> Vec<HirId> {
+        let mut inner = HashSet::new();
+        let mut used = Vec::new();
+        let mut stack = vec![closure];
+
+        while let Some(id) = stack.pop() {
+            match self.hir.node(id) {
+                Node::Binding(_) => {
+                    inner.insert(id);
+                }
+
+                Node::Expr(Expr::Path(Res::Local(binding))) => used.push(*binding),
+                _ => {}
+            }
+
+            let mut children = Vec::new();
+            self.hir
+                .for_each_child(id, &mut |child| children.push(child));
+            stack.extend(children.into_iter().rev());
+        }
+
+        let mut captures = Vec::new();
+
+        for binding in used {
+            if !inner.contains(&binding) && !captures.contains(&binding) {
+                captures.push(binding);
+            }
+        }
+
+        captures
+    }
+}
+
+/// A closure whose body hasn't been lowered yet.
+#[derive(Clone)]
+struct PendingClosure {
+    /// The closure expression.
+    expr: HirId,
+    /// The name of the body.
+    name: String,
+    /// The captured bindings, with how the body reaches them.
+    captures: Vec<(HirId, Capture)>,
+}
+
+/// How a closure's body reaches a binding it captures.
+#[derive(Clone, Copy)]
+enum Capture {
+    /// A copy of the binding's value is passed in.
+    Value,
+    /// A reference to the binding's slot is passed in.
+    Reference {
+        /// Whether the reference is mutable.
+        is_mutable: bool,
+    },
+}
+
+/// The closures found while lowering.
+struct Closures {
+    /// The [BodyId] of the first closure, after every function.
+    first: u32,
+    /// Every closure found so far, in the order their bodies are added to the [Program].
+    pending: Vec<PendingClosure>,
+}
+
+/// Where the value of a binding lives.
+#[derive(Clone)]
+enum Local {
+    /// In an SSA value (or a constant).
+    Value(Operand),
+    /// In a slot.
+    Slot(Slot),
+    /// Behind a reference, for slots of an enclosing body captured by a closure.
+    Indirect(Value),
+}
+
+/// A basic block that's being built.
+struct BlockData {
+    params: Vec<Value>,
+    insts: Vec<Inst>,
+    terminator: Option<Terminator>,
+    /// The source code of each instruction.
+    spans: Vec<Fragment>,
+    /// The source code of the terminator, once it's set.
+    terminator_span: Option<Fragment>,
+}
+
+/// The state of lowering one body.
+struct Builder<'a> {
+    cx: Context<'a>,
+    /// The name of the body.
+    name: String,
+    /// The return type.
+    ret: Ty,
+    /// The type of every value.
+    values: Vec<Ty>,
+    /// Every slot.
+    slots: Vec<SlotDecl>,
+    /// Every block.
+    blocks: Vec<BlockData>,
+    /// The block that instructions are added to.
+    current: BlockId,
+    /// Where the value of every binding in scope lives.
+    locals: HashMap<HirId, Local>,
+    /// The bindings that need a slot.
+    slotted: HashSet<HirId>,
+    /// The slots made in each scope, innermost last, which are dropped when the scope ends.
+    scopes: Vec<Vec<Slot>>,
+    /// The block after each loop being lowered, innermost last, with the number of scopes outside of it.
+    loops: Vec<(BlockId, usize)>,
+    /// The number of closures in this body so far.
+    closure_count: usize,
+    /// The source code of the statement or expression being lowered, which instructions are attributed to.
+    span: Fragment,
+}
+
+impl<'a> Builder<'a> {
+    /// Start building a body, attributing instructions to the given source code until a statement or expression
+    /// is lowered.
+    fn new(cx: Context<'a>, name: String, ret: Ty, span: Fragment) -> Self {
+        let mut builder = Builder {
+            cx,
+            name,
+            ret,
+            values: Vec::new(),
+            slots: Vec::new(),
+            blocks: Vec::new(),
+            current: BlockId::ENTRY,
+            locals: HashMap::new(),
+            slotted: HashSet::new(),
+            scopes: Vec::new(),
+            loops: Vec::new(),
+            closure_count: 0,
+            span,
+        };
+
+        builder.new_block();
+        builder
+    }
+
+    /// Lower a body, given the bindings it captures (for closures), its parameters, and its block.
+    fn build(
+        mut self,
+        def: Option<DefId>,
+        captures: &[(HirId, Capture)],
+        params: &[Param],
+        block: HirId,
+        closures: &mut Closures,
+    ) -> Body {
+        self.slotted = self.cx.slotted_bindings(block);
+        self.scopes.push(Vec::new());
+        let mut values = Vec::new();
+
+        for (binding, capture) in captures {
+            let ty = self.cx.binding_ty(*binding);
+
+            let (value, local) = match capture {
+                Capture::Value => {
+                    let value = self.new_value(ty);
+                    (value, Local::Value(Operand::Value(value)))
+                }
+
+                Capture::Reference { is_mutable } => {
+                    let value = self.new_value(Ty::reference(ty, *is_mutable));
+                    (value, Local::Indirect(value))
+                }
+            };
+
+            values.push(value);
+            self.locals.insert(*binding, local);
+        }
+
+        for param in params {
+            let value = self.new_value(self.cx.binding_ty(param.binding));
+            values.push(value);
+            self.bind(param.binding, Operand::Value(value));
+        }
+
+        let value = self.lower_block(block, closures);
+        self.pop_scope();
+        self.terminate(Terminator::Return(value));
+        self.simplify();
+        self.finish(def, values)
+    }
+
+    /// Make a new value of the given type.
+    fn new_value(&mut self, ty: Ty) -> Value {
+        self.values.push(ty);
+        Value(self.values.len() as u32 - 1)
+    }
+
+    /// Make a new block, without switching to it.
+    fn new_block(&mut self) -> BlockId {
+        self.blocks.push(BlockData {
+            params: Vec::new(),
+            insts: Vec::new(),
+            terminator: None,
+            spans: Vec::new(),
+            terminator_span: None,
+        });
+
+        BlockId(self.blocks.len() as u32 - 1)
+    }
+
+    /// Make a new block that takes a parameter of the given type (unless it's `void`), returning the block and the
+    /// value that it's given.
+    fn new_join(&mut self, ty: Ty) -> (BlockId, Operand) {
+        let block = self.new_block();
+
+        if ty == Ty::Void {
+            return (block, Operand::Const(Const::Void));
+        }
+
+        let param = self.new_value(ty);
+        self.blocks[block.0 as usize].params.push(param);
+        (block, Operand::Value(param))
+    }
+
+    /// Make a jump to a block made by [Builder::new_join], passing it a value if it takes one.
+    fn jump_with(&self, target: BlockId, value: Operand) -> Jump {
+        match self.blocks[target.0 as usize].params.is_empty() {
+            true => Jump::to(target),
+            false => Jump {
+                target,
+                args: vec![value],
+            },
+        }
+    }
+
+    /// Add an instruction to the current block.
+    fn push(&mut self, inst: Inst) {
+        let data = &mut self.blocks[self.current.0 as usize];
+        data.insts.push(inst);
+        data.spans.push(self.span.clone());
+    }
+
+    /// Compute a value of the given type in the current block.
+    fn assign(&mut self, rvalue: Rvalue, ty: Ty) -> Operand {
+        let value = self.new_value(ty);
+        self.push(Inst::Assign(value, rvalue));
+        Operand::Value(value)
+    }
+
+    /// End the current block, and continue in a new one that nothing jumps to (which is removed at the end if
+    /// nothing ever does).
+    fn terminate(&mut self, terminator: Terminator) {
+        self.set_terminator(self.current, terminator);
+        self.current = self.new_block();
+    }
+
+    /// Set the terminator of a block, without leaving the current one.
+    fn set_terminator(&mut self, block: BlockId, terminator: Terminator) {
+        let data = &mut self.blocks[block.0 as usize];
+        data.terminator = Some(terminator);
+        data.terminator_span = Some(self.span.clone());
+    }
+
+    /// Continue in the given block.
+    fn switch_to(&mut self, block: BlockId) {
+        self.current = block;
+    }
+
+    /// Make a slot, dropped at the end of the current scope.
+    fn new_slot(&mut self, name: Option<String>, ty: Ty) -> Slot {
+        self.slots.push(SlotDecl { name, ty });
+        let slot = Slot(self.slots.len() as u32 - 1);
+        self.scopes.last_mut().unwrap().push(slot);
+        slot
+    }
+
+    /// Bind a binding to a value, storing it in a slot if the binding needs one.
+    fn bind(&mut self, binding: HirId, value: Operand) {
+        let local = match self.slotted.contains(&binding) {
+            true => {
+                let name = self.cx.hir.binding(binding).name.clone();
+                let slot = self.new_slot(Some(name), self.cx.binding_ty(binding));
+                self.push(Inst::Store(Place::slot(slot), value));
+                Local::Slot(slot)
+            }
+
+            false => Local::Value(value),
+        };
+
+        self.locals.insert(binding, local);
+    }
+
+    /// Drop the slots of the scopes from the given depth inwards, innermost first.
+    fn drop_scopes(&mut self, depth: usize) {
+        let slots: Vec<Slot> = (self.scopes[depth..].iter().rev())
+            .flat_map(|scope| scope.iter().rev().copied())
+            .collect();
+
+        for slot in slots {
+            self.push(Inst::Drop(slot));
+        }
+    }
+
+    /// End the innermost scope, dropping its slots.
+    fn pop_scope(&mut self) {
+        self.drop_scopes(self.scopes.len() - 1);
+        self.scopes.pop();
+    }
+
+    /// Lower a block in a scope of its own.
+    fn lower_block(&mut self, block: HirId, closures: &mut Closures) -> Operand {
+        self.scopes.push(Vec::new());
+        let data = self.cx.hir.block(block);
+
+        for stmt in &data.stmts {
+            self.lower_stmt(*stmt, closures);
+        }
+
+        let value = match data.tail {
+            Some(tail) => self.lower_expr(tail, closures),
+            None => Operand::Const(Const::Void),
+        };
+
+        self.pop_scope();
+        value
+    }
+
+    /// Lower a statement.
+    fn lower_stmt(&mut self, stmt: HirId, closures: &mut Closures) {
+        let outer = std::mem::replace(&mut self.span, self.cx.hir.fragment(stmt).clone());
+        self.lower_stmt_kind(stmt, closures);
+        self.span = outer;
+    }
+
+    /// Lower a statement, attributing its instructions to the current span.
+    fn lower_stmt_kind(&mut self, stmt: HirId, closures: &mut Closures) {
+        match *self.cx.hir.stmt(stmt) {
+            Stmt::Let { binding, value, .. } => {
+                let value = self.lower_expr(value, closures);
+                self.bind(binding, value);
+            }
+
+            Stmt::Assign { target, op, value } => {
+                let place = self.lower_place(target, closures);
+                let value = self.lower_expr(value, closures);
+
+                let value = match op {
+                    Some(op) => {
+                        let ty = self.cx.expr_ty(target);
+                        let current = self.assign(Rvalue::Load(place.clone()), ty.clone());
+                        self.assign(Rvalue::Binary(op, current, value), ty)
+                    }
+
+                    None => value,
+                };
+
+                self.push(Inst::Store(place, value));
+            }
+
+            Stmt::Expr(expr) => {
+                self.lower_expr(expr, closures);
+            }
+        }
+    }
+
+    /// Lower an expression, and get its value.
+    fn lower_expr(&mut self, id: HirId, closures: &mut Closures) -> Operand {
+        let outer = std::mem::replace(&mut self.span, self.cx.hir.fragment(id).clone());
+        let value = self.lower_expr_kind(id, closures);
+        self.span = outer;
+        value
+    }
+
+    /// Lower an expression, attributing its instructions to the current span.
+    fn lower_expr_kind(&mut self, id: HirId, closures: &mut Closures) -> Operand {
+        let ty = self.cx.expr_ty(id);
+
+        match self.cx.hir.expr(id) {
+            Expr::Literal(Literal::Integer(value)) => {
+                Operand::Const(Const::Int(BigInt::from(value.clone()), ty))
+            }
+            Expr::Literal(Literal::Boolean(value)) => Operand::Const(Const::Bool(*value)),
+            Expr::Literal(Literal::String(value)) => Operand::Const(Const::Str(value.clone())),
+
+            Expr::Path(Res::Local(binding)) => match self.locals[binding].clone() {
+                Local::Value(value) => value,
+                Local::Slot(slot) => self.assign(Rvalue::Load(Place::slot(slot)), ty),
+                Local::Indirect(reference) => {
+                    self.assign(Rvalue::Load(Place::deref(reference)), ty)
+                }
+            },
+
+            Expr::Path(Res::Def(def)) => match self.cx.res.def(*def).kind {
+                DefKind::Variant => Operand::Const(self.variant(*def, ty)),
+                _ => Operand::Const(Const::Def(*def, ty)),
+            },
+
+            Expr::Path(Res::Err) => Operand::Const(Const::Void),
+
+            Expr::Unary { op, operand } => match op {
+                UnaryOperation::Reference | UnaryOperation::MutableReference => {
+                    let place = self.lower_place(*operand, closures);
+                    let is_mutable = *op == UnaryOperation::MutableReference;
+                    self.assign(Rvalue::Ref { place, is_mutable }, ty)
+                }
+
+                UnaryOperation::Dereference => {
+                    let reference = self.lower_value(*operand, closures);
+                    self.assign(Rvalue::Load(Place::deref(reference)), ty)
+                }
+
+                // Negated literals are folded, so that the most negative value of a type is a constant.
+                UnaryOperation::Negate
+                    if matches!(self.cx.hir.expr(*operand), Expr::Literal(Literal::Integer(_))) =>
+                {
+                    let Expr::Literal(Literal::Integer(value)) = self.cx.hir.expr(*operand) else {
+                        unreachable!()
+                    };
+
+                    Operand::Const(Const::Int(-BigInt::from(value.clone()), ty))
+                }
+
+                _ => {
+                    let operand = self.lower_expr(*operand, closures);
+                    self.assign(Rvalue::Unary(*op, operand), ty)
+                }
+            },
+
+            Expr::Binary { op, lhs, rhs } => match op {
+                BinaryOperation::LogicalAnd | BinaryOperation::LogicalOr => {
+                    self.lower_short_circuit(*op, *lhs, *rhs, closures)
+                }
+
+                _ => {
+                    let lhs = self.lower_expr(*lhs, closures);
+                    let rhs = self.lower_expr(*rhs, closures);
+                    self.assign(Rvalue::Binary(*op, lhs, rhs), ty)
+                }
+            },
+
+            Expr::Is { value, target } => {
+                let (value, value_ty) = self.lower_through_references(*value, closures);
+
+                let rvalue = match target {
+                    IsTarget::EnumVariant(Res::Def(variant)) => Rvalue::Binary(
+                        BinaryOperation::Equal,
+                        value,
+                        Operand::Const(self.variant(*variant, value_ty)),
+                    ),
+
+                    IsTarget::UnionVariant { variant, .. } => {
+                        Rvalue::IsVariant(value, variant.clone())
+                    }
+
+                    _ => {
+                        let fragment = self.cx.hir.fragment(id);
+                        let variant = self.cx.typeck.union_test(fragment).unwrap_or_default();
+                        Rvalue::IsVariant(value, variant.to_owned())
+                    }
+                };
+
+                self.assign(rvalue, Ty::BOOL)
+            }
+
+            Expr::Call { callee, args } => {
+                let method_call = self
+                    .cx
+                    .typeck
+                    .method_call(self.cx.hir.fragment(id))
+                    .copied();
+
+                let (callee, mut operands) = match (method_call, self.cx.hir.expr(*callee)) {
+                    (Some(call), Expr::Field { base, .. }) => {
+                        let receiver = match call.receiver {
+                            Receiver::Value => self.lower_expr(*base, closures),
+
+                            Receiver::Borrow { is_mutable } => {
+                                let place = self.lower_place(*base, closures);
+                                let ty = Ty::reference(self.cx.expr_ty(*base), is_mutable);
+                                self.assign(Rvalue::Ref { place, is_mutable }, ty)
+                            }
+
+                            Receiver::Deref => self.lower_through_references(*base, closures).0,
+                        };
+
+                        let method_ty = self.cx.typeck.type_of_def(call.method).cloned();
+                        let callee =
+                            Operand::Const(Const::Def(call.method, method_ty.unwrap_or(Ty::Error)));
+                        (callee, vec![receiver])
+                    }
+
+                    _ => (self.lower_expr(*callee, closures), Vec::new()),
+                };
+
+                for arg in args {
+                    operands.push(self.lower_expr(*arg, closures));
+                }
+
+                self.assign(
+                    Rvalue::Call {
+                        callee,
+                        args: operands,
+                    },
+                    ty,
+                )
+            }
+
+            Expr::Field { base, field } => match self.is_in_memory(id) {
+                true => {
+                    let place = self.lower_place(id, closures);
+                    self.assign(Rvalue::Load(place), ty)
+                }
+
+                false => {
+                    let base = self.lower_expr(*base, closures);
+                    self.assign(Rvalue::Field(base, field.clone()), ty)
+                }
+            },
+
+            Expr::Record { fields, .. } => {
+                let fields = (fields.iter())
+                    .map(|(name, value)| (name.clone(), self.lower_expr(*value, closures)))
+                    .collect();
+
+                self.assign(Rvalue::Aggregate { fields }, ty)
+            }
+
+            Expr::Block(block) | Expr::Unsafe(block) => self.lower_block(*block, closures),
+
+            Expr::If {
+                condition,
+                then_block,
+                else_block,
+            } => {
+                let condition = self.lower_expr(*condition, closures);
+                let (then_target, else_target) = (self.new_block(), self.new_block());
+                let (join, result) = self.new_join(ty);
+
+                self.set_terminator(
+                    self.current,
+                    Terminator::Branch {
+                        condition,
+                        then: Jump::to(then_target),
+                        otherwise: Jump::to(else_target),
+                    },
+                );
+
+                self.switch_to(then_target);
+                let value = self.lower_block(*then_block, closures);
+                self.terminate(Terminator::Goto(self.jump_with(join, value)));
+
+                self.switch_to(else_target);
+
+                let value = match else_block {
+                    Some(block) => self.lower_block(*block, closures),
+                    None => Operand::Const(Const::Void),
+                };
+
+                self.terminate(Terminator::Goto(self.jump_with(join, value)));
+                self.switch_to(join);
+                result
+            }
+
+            Expr::Match { scrutinee, arms } => self.lower_match(*scrutinee, arms, ty, closures),
+
+            Expr::Loop(block) => {
+                let (header, exit) = (self.new_block(), self.new_block());
+                self.terminate(Terminator::Goto(Jump::to(header)));
+
+                self.switch_to(header);
+                self.loops.push((exit, self.scopes.len()));
+                self.lower_block(*block, closures);
+                self.loops.pop();
+                self.terminate(Terminator::Goto(Jump::to(header)));
+
+                self.switch_to(exit);
+                Operand::Const(Const::Void)
+            }
+
+            Expr::Break => {
+                let (exit, depth) = *self
+                    .loops
+                    .last()
+                    .expect("`break` is only made inside of loops");
+                self.drop_scopes(depth);
+                self.terminate(Terminator::Goto(Jump::to(exit)));
+                Operand::Const(Const::Void)
+            }
+
+            Expr::Closure { .. } => {
+                let mut captures = Vec::new();
+                let mut operands = Vec::new();
+
+                for binding in self.cx.captures(id) {
+                    let Some(local) = self.locals.get(&binding).cloned() else {
+                        continue;
+                    };
+
+                    let (capture, operand) = match local {
+                        Local::Value(value) => (Capture::Value, value),
+
+                        Local::Slot(slot) => {
+                            let is_mutable = self.cx.hir.binding(binding).is_mutable;
+                            let ty = Ty::reference(self.cx.binding_ty(binding), is_mutable);
+                            let place = Place::slot(slot);
+                            (
+                                Capture::Reference { is_mutable },
+                                self.assign(Rvalue::Ref { place, is_mutable }, ty),
+                            )
+                        }
+
+                        Local::Indirect(reference) => {
+                            let is_mutable = matches!(
+                                self.values[reference.0 as usize],
+                                Ty::Reference {
+                                    is_mutable: true,
+                                    ..
+                                }
+                            );
+                            (Capture::Reference { is_mutable }, Operand::Value(reference))
+                        }
+                    };
+
+                    captures.push((binding, capture));
+                    operands.push(operand);
+                }
+
+                let body = BodyId(closures.first + closures.pending.len() as u32);
+
+                closures.pending.push(PendingClosure {
+                    expr: id,
+                    name: format!("{}::{{closure#{}}}", self.name, self.closure_count),
+                    captures,
+                });
+
+                self.closure_count += 1;
+
+                self.assign(
+                    Rvalue::Closure {
+                        body,
+                        captures: operands,
+                    },
+                    ty,
+                )
+            }
+        }
+    }
+
+    /// Lower an expression, and get its value as an SSA value rather than a constant.
+    fn lower_value(&mut self, id: HirId, closures: &mut Closures) -> Value {
+        match self.lower_expr(id, closures) {
+            Operand::Value(value) => value,
+
+            operand => {
+                let ty = self.cx.expr_ty(id);
+                let Operand::Value(value) = self.assign(Rvalue::Use(operand), ty) else {
+                    unreachable!()
+                };
+
+                value
+            }
+        }
+    }
+
+    /// Lower an expression, following references until reaching a value that isn't one (like fields are accessed
+    /// and variants are tested through references). Returns the value and its type.
+    fn lower_through_references(&mut self, id: HirId, closures: &mut Closures) -> (Operand, Ty) {
+        match self.cx.expr_ty(id) {
+            Ty::Reference { .. } => {
+                let (place, ty) = self.lower_referenced_place(id, closures);
+                (self.assign(Rvalue::Load(place), ty.clone()), ty)
+            }
+
+            ty => (self.lower_expr(id, closures), ty),
+        }
+    }
+
+    /// Lower an expression of a reference type to the place it points to (through any number of references),
+    /// along with the type of the place.
+    fn lower_referenced_place(&mut self, id: HirId, closures: &mut Closures) -> (Place, Ty) {
+        let Ty::Reference { target, .. } = self.cx.expr_ty(id) else {
+            unreachable!("only expressions of reference types point to places")
+        };
+
+        let mut place = Place::deref(self.lower_value(id, closures));
+        let mut ty = *target;
+
+        while let Ty::Reference { target, .. } = ty {
+            place.projections.push(Projection::Deref);
+            ty = *target;
+        }
+
+        (place, ty)
+    }
+
+    /// Check if a field access reads from memory (a slot, or through a reference) rather than from an SSA value.
+    fn is_in_memory(&self, id: HirId) -> bool {
+        match self.cx.hir.expr(id) {
+            Expr::Path(Res::Local(binding)) => {
+                !matches!(self.locals.get(binding), Some(Local::Value(_)))
+            }
+            Expr::Unary {
+                op: UnaryOperation::Dereference,
+                ..
+            } => true,
+
+            Expr::Field { base, .. } => {
+                matches!(self.cx.expr_ty(*base), Ty::Reference { .. }) || self.is_in_memory(*base)
+            }
+
+            _ => false,
+        }
+    }
+
+    /// Lower a place expression, which is assigned to or borrowed. Expressions that aren't places are stored in a
+    /// temporary slot.
+    fn lower_place(&mut self, id: HirId, closures: &mut Closures) -> Place {
+        match self.cx.hir.expr(id) {
+            Expr::Path(Res::Local(binding)) => match self.locals[binding].clone() {
+                Local::Slot(slot) => return Place::slot(slot),
+                Local::Indirect(reference) => return Place::deref(reference),
+                Local::Value(_) => {}
+            },
+
+            Expr::Unary {
+                op: UnaryOperation::Dereference,
+                operand,
+            } => return Place::deref(self.lower_value(*operand, closures)),
+
+            Expr::Field { base, field } => {
+                let mut place = match self.cx.expr_ty(*base) {
+                    Ty::Reference { .. } => self.lower_referenced_place(*base, closures).0,
+                    _ => self.lower_place(*base, closures),
+                };
+
+                place.projections.push(Projection::Field(field.clone()));
+                return place;
+            }
+
+            _ => {}
+        }
+
+        let value = self.lower_expr(id, closures);
+        let slot = self.new_slot(None, self.cx.expr_ty(id));
+        self.push(Inst::Store(Place::slot(slot), value));
+        Place::slot(slot)
+    }
+
+    /// Make the constant for an enum variant.
+    fn variant(&self, variant: DefId, ty: Ty) -> Const {
+        let enumeration = self.cx.res.enum_of(variant);
+        let variants = enumeration.map(|enumeration| self.cx.res.variants(enumeration));
+        let index =
+            variants.and_then(|variants| variants.iter().position(|other| *other == variant));
+        Const::Variant(variant, index.unwrap_or_default() as u32, ty)
+    }
+
+    /// Lower `&&` or `||`, which only evaluates its right side if the left side doesn't decide the result.
+    fn lower_short_circuit(
+        &mut self,
+        op: BinaryOperation,
+        lhs: HirId,
+        rhs: HirId,
+        closures: &mut Closures,
+    ) -> Operand {
+        let condition = self.lower_expr(lhs, closures);
+        let rhs_block = self.new_block();
+        let (join, result) = self.new_join(Ty::BOOL);
+
+        let decided = Jump {
+            target: join,
+            args: vec![Operand::Const(Const::Bool(
+                op == BinaryOperation::LogicalOr,
+            ))],
+        };
+
+        let (then, otherwise) = match op {
+            BinaryOperation::LogicalAnd => (Jump::to(rhs_block), decided),
+            _ => (decided, Jump::to(rhs_block)),
+        };
+
+        self.set_terminator(
+            self.current,
+            Terminator::Branch {
+                condition,
+                then,
+                otherwise,
+            },
+        );
+
+        self.switch_to(rhs_block);
+        let value = self.lower_expr(rhs, closures);
+        self.terminate(Terminator::Goto(self.jump_with(join, value)));
+        self.switch_to(join);
+        result
+    }
+
+    /// Lower a `match`. Matches on integers and enums whose arms only have literals and variants (and maybe a last
+    /// arm that matches everything) become a [Terminator::Switch]; the rest test each arm's pattern in turn.
+    fn lower_match(
+        &mut self,
+        scrutinee: HirId,
+        arms: &[crate::hir::Arm],
+        ty: Ty,
+        closures: &mut Closures,
+    ) -> Operand {
+        let scrutinee_ty = self.cx.expr_ty(scrutinee);
+        let value = self.lower_expr(scrutinee, closures);
+        let (join, result) = self.new_join(ty);
+
+        if let Some(cases) = self.switch_cases(&scrutinee_ty, arms) {
+            let arm_blocks: Vec<BlockId> = arms.iter().map(|_| self.new_block()).collect();
+            let mut seen = HashSet::new();
+
+            let switch_cases = (cases.iter().enumerate())
+                .flat_map(|(arm, cases)| cases.iter().map(move |case| (case.clone(), arm)))
+                .filter(|(case, _)| seen.insert(case.clone()))
+                .map(|(case, arm)| (case, arm_blocks[arm]))
+                .collect();
+
+            let otherwise = match cases.len() < arms.len() {
+                true => arm_blocks[arms.len() - 1],
+                false => {
+                    let block = self.new_block();
+                    self.set_terminator(block, Terminator::Unreachable);
+                    block
+                }
+            };
+
+            self.set_terminator(
+                self.current,
+                Terminator::Switch {
+                    value: value.clone(),
+                    cases: switch_cases,
+                    otherwise,
+                },
+            );
+
+            for (arm, block) in arms.iter().zip(arm_blocks) {
+                self.switch_to(block);
+                self.scopes.push(Vec::new());
+
+                if let Pattern::Binding(binding) = self.cx.hir.pattern(arm.pattern) {
+                    self.bind(*binding, value.clone());
+                }
+
+                let body = self.lower_expr(arm.body, closures);
+                self.pop_scope();
+                self.terminate(Terminator::Goto(self.jump_with(join, body)));
+            }
+
+            self.switch_to(join);
+            return result;
+        }
+
+        for arm in arms {
+            let next = self.new_block();
+            let mut bindings = Vec::new();
+            self.test_pattern(arm.pattern, value.clone(), &scrutinee_ty, next, &mut bindings);
+
+            self.scopes.push(Vec::new());
+
+            for (binding, value) in bindings {
+                self.bind(binding, value);
+            }
+
+            if let Some(guard) = arm.guard {
+                let condition = self.lower_expr(guard, closures);
+                let (matched, failed) = (self.new_block(), self.new_block());
+
+                self.set_terminator(
+                    self.current,
+                    Terminator::Branch {
+                        condition,
+                        then: Jump::to(matched),
+                        otherwise: Jump::to(failed),
+                    },
+                );
+
+                // The arm's bindings end before trying the next arm.
+                self.switch_to(failed);
+                self.drop_scopes(self.scopes.len() - 1);
+                self.set_terminator(failed, Terminator::Goto(Jump::to(next)));
+                self.switch_to(matched);
+            }
+
+            let body = self.lower_expr(arm.body, closures);
+            self.pop_scope();
+            self.terminate(Terminator::Goto(self.jump_with(join, body)));
+            self.switch_to(next);
+        }
+
+        // The arms are exhaustive, so control never gets past the last one.
+        self.terminate(Terminator::Unreachable);
+        self.switch_to(join);
+        result
+    }
+
+    /// Get the values each arm of a `match` covers, if it can be lowered to a [Terminator::Switch]. Only the last
+    /// arm may match everything, in which case it has no values of its own.
+    fn switch_cases(&self, ty: &Ty, arms: &[crate::hir::Arm]) -> Option<Vec<Vec<BigInt>>> {
+        let is_switchable = match ty {
+            Ty::Atomic(atomic) => atomic.is_integer(),
+            Ty::Named { def, .. } => self.cx.res.def(*def).kind == DefKind::Enum,
+            _ => false,
+        };
+
+        if !is_switchable || arms.iter().any(|arm| arm.guard.is_some()) {
+            return None;
+        }
+
+        let mut cases = Vec::new();
+
+        for (index, arm) in arms.iter().enumerate() {
+            let mut values = Vec::new();
+
+            if !self.switch_values(arm.pattern, &mut values) {
+                let is_catch_all = matches!(
+                    self.cx.hir.pattern(arm.pattern),
+                    Pattern::Wildcard | Pattern::Binding(_)
+                );
+
+                if is_catch_all && index == arms.len() - 1 {
+                    return Some(cases);
+                }
+
+                return None;
+            }
+
+            cases.push(values);
+        }
+
+        Some(cases)
+    }
+
+    /// Collect the switch values of a pattern made of integers and variants, returning `false` for other patterns.
+    fn switch_values(&self, pattern: HirId, values: &mut Vec<BigInt>) -> bool {
+        match self.cx.hir.pattern(pattern) {
+            Pattern::Integer(value) => values.push(value.clone()),
+
+            Pattern::Variant(Res::Def(variant)) => match self.variant(*variant, Ty::Error) {
+                Const::Variant(_, index, _) => values.push(BigInt::from(index)),
+                _ => return false,
+            },
+
+            Pattern::Or(alternatives) => {
+                return (alternatives.iter())
+                    .all(|alternative| self.switch_values(*alternative, values));
+            }
+
+            _ => return false,
+        }
+
+        true
+    }
+
+    /// Branch to `fail` unless a value of the given type matches a pattern. Control continues in a block where it
+    /// does, and the bindings of the pattern are collected to be bound there.
+    fn test_pattern(
+        &mut self,
+        pattern: HirId,
+        value: Operand,
+        ty: &Ty,
+        fail: BlockId,
+        bindings: &mut Vec<(HirId, Operand)>,
+    ) {
+        let condition = match self.cx.hir.pattern(pattern) {
+            Pattern::Wildcard => return,
+
+            Pattern::Binding(binding) => {
+                bindings.push((*binding, value));
+                return;
+            }
+
+            Pattern::Integer(integer) => {
+                let integer = Operand::Const(Const::Int(integer.clone(), ty.clone()));
+                self.assign(Rvalue::Binary(BinaryOperation::Equal, value, integer), Ty::BOOL)
+            }
+
+            Pattern::Boolean(true) => value,
+            Pattern::Boolean(false) => {
+                self.assign(Rvalue::Unary(UnaryOperation::BooleanNot, value), Ty::BOOL)
+            }
+
+            Pattern::Range {
+                start,
+                end,
+                is_inclusive,
+            } => {
+                let start = Operand::Const(Const::Int(start.clone(), ty.clone()));
+                let above = Rvalue::Binary(BinaryOperation::GreaterOrEqual, value.clone(), start);
+                let above = self.assign(above, Ty::BOOL);
+                self.branch_or_fail(above, fail);
+
+                let op = match is_inclusive {
+                    true => BinaryOperation::LessOrEqual,
+                    false => BinaryOperation::Less,
+                };
+
+                let end = Operand::Const(Const::Int(end.clone(), ty.clone()));
+                self.assign(Rvalue::Binary(op, value, end), Ty::BOOL)
+            }
+
+            Pattern::Variant(res) => {
+                let Res::Def(variant) = res else { return };
+                let variant = Operand::Const(self.variant(*variant, ty.clone()));
+                self.assign(Rvalue::Binary(BinaryOperation::Equal, value, variant), Ty::BOOL)
+            }
+
+            Pattern::Record { fields, .. } => {
+                let Some((is_union, field_tys)) = self.cx.typeck.fields(ty) else {
+                    return;
+                };
+
+                for (name, field_pattern) in fields {
+                    if is_union {
+                        let is_variant =
+                            self.assign(Rvalue::IsVariant(value.clone(), name.clone()), Ty::BOOL);
+                        self.branch_or_fail(is_variant, fail);
+                    }
+
+                    let field_ty = (field_tys.iter())
+                        .find(|(field, _)| field == name)
+                        .map_or(Ty::Error, |(_, ty)| ty.clone());
+
+                    let field =
+                        self.assign(Rvalue::Field(value.clone(), name.clone()), field_ty.clone());
+                    self.test_pattern(*field_pattern, field, &field_ty, fail, bindings);
+                }
+
+                return;
+            }
+
+            Pattern::Or(alternatives) => {
+                let matched = self.new_block();
+
+                for (index, alternative) in alternatives.iter().enumerate() {
+                    let alternative_fail = match index == alternatives.len() - 1 {
+                        true => fail,
+                        false => self.new_block(),
+                    };
+
+                    self.test_pattern(*alternative, value.clone(), ty, alternative_fail, bindings);
+                    self.terminate(Terminator::Goto(Jump::to(matched)));
+                    self.switch_to(alternative_fail);
+                }
+
+                self.switch_to(matched);
+                return;
+            }
+        };
+
+        self.branch_or_fail(condition, fail);
+    }
+
+    /// Continue in a new block if a condition is `true`, and go to `fail` if it isn't.
+    fn branch_or_fail(&mut self, condition: Operand, fail: BlockId) {
+        let matched = self.new_block();
+
+        self.set_terminator(
+            self.current,
+            Terminator::Branch {
+                condition,
+                then: Jump::to(matched),
+                otherwise: Jump::to(fail),
+            },
+        );
+
+        self.switch_to(matched);
+    }
+
+    /// Get the successors of a block. Blocks that were never terminated are only made after control has left
+    /// them (see [Builder::terminate]), so have none.
+    fn successors(&self, block: BlockId) -> Vec<BlockId> {
+        (self.blocks[block.0 as usize].terminator.as_ref())
+            .map(Terminator::successors)
+            .unwrap_or_default()
+    }
+
+    /// Find the blocks that control can reach, and count the jumps to each block from them.
+    fn predecessor_counts(&self) -> (HashSet<BlockId>, Vec<usize>) {
+        let mut counts = vec![0; self.blocks.len()];
+        let mut reachable = HashSet::new();
+        let mut stack = vec![BlockId::ENTRY];
+
+        while let Some(block) = stack.pop() {
+            if reachable.insert(block) {
+                for successor in self.successors(block) {
+                    counts[successor.0 as usize] += 1;
+                    stack.push(successor);
+                }
+            }
+        }
+
+        (reachable, counts)
+    }
+
+    /// Remove the blocks that lowering makes which only jump somewhere else: jumps to empty blocks go straight to
+    /// where the empty block goes, and blocks that are only jumped to from one other block are merged into it.
+    fn simplify(&mut self) {
+        let is_forwarder = |data: &BlockData| {
+            data.params.is_empty()
+                && data.insts.is_empty()
+                && matches!(&data.terminator, Some(Terminator::Goto(jump)) if jump.args.is_empty())
+        };
+
+        let mut forward: HashMap<BlockId, BlockId> = HashMap::new();
+
+        for block in self.block_range() {
+            if block != BlockId::ENTRY
+                && is_forwarder(&self.blocks[block.0 as usize])
+                && let Some(Terminator::Goto(jump)) = &self.blocks[block.0 as usize].terminator
+            {
+                forward.insert(block, jump.target);
+            }
+        }
+
+        let resolve = |mut block: BlockId| {
+            let mut seen = HashSet::new();
+
+            // Empty loops forward to themselves, so stop once a block comes up again.
+            while let Some(target) = forward.get(&block) {
+                if !seen.insert(block) {
+                    break;
+                }
+
+                block = *target;
+            }
+
+            block
+        };
+
+        for data in &mut self.blocks {
+            match &mut data.terminator {
+                Some(Terminator::Goto(jump)) => jump.target = resolve(jump.target),
+
+                Some(Terminator::Branch {
+                    then, otherwise, ..
+                }) => {
+                    then.target = resolve(then.target);
+                    otherwise.target = resolve(otherwise.target);
+                }
+
+                Some(Terminator::Switch {
+                    cases, otherwise, ..
+                }) => {
+                    cases
+                        .iter_mut()
+                        .for_each(|(_, target)| *target = resolve(*target));
+                    *otherwise = resolve(*otherwise);
+                }
+
+                _ => {}
+            }
+        }
+
+        let (reachable, counts) = self.predecessor_counts();
+
+        for block in self.block_range().filter(|block| reachable.contains(block)) {
+            while let Some(Terminator::Goto(jump)) = &self.blocks[block.0 as usize].terminator
+                && jump.args.is_empty()
+                && jump.target != block
+                && jump.target != BlockId::ENTRY
+                && counts[jump.target.0 as usize] == 1
+                && self.blocks[jump.target.0 as usize].params.is_empty()
+            {
+                let target = jump.target;
+                let merged = std::mem::replace(
+                    &mut self.blocks[target.0 as usize],
+                    BlockData {
+                        params: Vec::new(),
+                        insts: Vec::new(),
+                        terminator: Some(Terminator::Unreachable),
+                        spans: Vec::new(),
+                        terminator_span: None,
+                    },
+                );
+
+                let data = &mut self.blocks[block.0 as usize];
+                data.insts.extend(merged.insts);
+                data.spans.extend(merged.spans);
+                data.terminator = merged.terminator;
+                data.terminator_span = merged.terminator_span;
+            }
+        }
+    }
+
+    /// Get the IDs of every block made so far.
+    fn block_range(&self) -> impl Iterator<Item = BlockId> + use<> {
+        (0..self.blocks.len() as u32).map(BlockId)
+    }
+
+    /// Finish the body: remove blocks that control never reaches, and number the blocks in the order control
+    /// reaches them (and the values in the order they're defined).
+    fn finish(self, def: Option<DefId>, params: Vec<Value>) -> Body {
+        // Number the reachable blocks in reverse postorder.
+        let mut postorder = Vec::new();
+        let mut visited = HashSet::new();
+        let mut stack = vec![(BlockId::ENTRY, false)];
+
+        while let Some((block, is_done)) = stack.pop() {
+            if is_done {
+                postorder.push(block);
+                continue;
+            }
+
+            if !visited.insert(block) {
+                continue;
+            }
+
+            stack.push((block, true));
+
+            let successors = self.successors(block);
+            stack.extend(successors.into_iter().map(|successor| (successor, false)));
+        }
+
+        let order: Vec<BlockId> = postorder.into_iter().rev().collect();
+
+        let block_ids: HashMap<BlockId, BlockId> = (order.iter().enumerate())
+            .map(|(index, block)| (*block, BlockId(index as u32)))
+            .collect();
+
+        let mut renumber = Renumber {
+            blocks: block_ids,
+            values: HashMap::new(),
+            types: Vec::new(),
+            old_types: self.values,
+        };
+
+        // Number the values in the order they're defined, before rewriting their uses.
+        let params = params
+            .into_iter()
+            .map(|param| renumber.define(param))
+            .collect();
+
+        for block in &order {
+            let data = &self.blocks[block.0 as usize];
+            for param in &data.params {
+                renumber.define(*param);
+            }
+
+            for inst in &data.insts {
+                if let Inst::Assign(value, _) = inst {
+                    renumber.define(*value);
+                }
+            }
+        }
+
+        let mut blocks_data: Vec<Option<BlockData>> = self.blocks.into_iter().map(Some).collect();
+        let mut blocks = Vec::new();
+
+        for block in order {
+            let data = blocks_data[block.0 as usize].take().unwrap();
+            let params = data
+                .params
+                .into_iter()
+                .map(|param| renumber.value(param))
+                .collect();
+
+            let insts = (data.insts.into_iter())
+                .map(|inst| renumber.inst(inst))
+                .collect();
+
+            let terminator =
+                renumber.terminator(data.terminator.unwrap_or(Terminator::Unreachable));
+
+            // Blocks that are never terminated end where their last instruction does (or with the body, if they're
+            // empty).
+            let terminator_span = (data.terminator_span)
+                .or_else(|| data.spans.last().cloned())
+                .unwrap_or_else(|| self.span.clone());
+
+            blocks.push(BasicBlock {
+                params,
+                insts,
+                terminator,
+                spans: data.spans,
+                terminator_span,
+            });
+        }
+
+        Body {
+            def,
+            name: self.name,
+            params,
+            ret: self.ret,
+            values: renumber.types,
+            slots: self.slots,
+            blocks,
+        }
+    }
+}
+
+/// Renumbering the blocks and values of a body, once unreachable blocks are removed.
+struct Renumber {
+    /// The new ID of every reachable block.
+    blocks: HashMap<BlockId, BlockId>,
+    /// The new number of every value defined so far.
+    values: HashMap<Value, Value>,
+    /// The type of every value, by its new number.
+    types: Vec<Ty>,
+    /// The type of every value, by its old number.
+    old_types: Vec<Ty>,
+}
+
+impl Renumber {
+    /// Give a value the next number.
+    fn define(&mut self, value: Value) -> Value {
+        let new = Value(self.types.len() as u32);
+        self.types.push(self.old_types[value.0 as usize].clone());
+        self.values.insert(value, new);
+        new
+    }
+
+    /// Get the new number of a value. Values that are used without being defined anywhere reachable (which the
+    /// verifier reports) keep their old number.
+    fn value(&self, value: Value) -> Value {
+        self.values.get(&value).copied().unwrap_or(value)
+    }
+
+    fn block(&self, block: BlockId) -> BlockId {
+        self.blocks[&block]
+    }
+
+    fn operand(&self, operand: Operand) -> Operand {
+        match operand {
+            Operand::Value(value) => Operand::Value(self.value(value)),
+            constant => constant,
+        }
+    }
+
+    fn place(&self, place: Place) -> Place {
+        let base = match place.base {
+            PlaceBase::Value(value) => PlaceBase::Value(self.value(value)),
+            slot => slot,
+        };
+
+        Place { base, ..place }
+    }
+
+    fn inst(&self, inst: Inst) -> Inst {
+        match inst {
+            Inst::Assign(value, rvalue) => Inst::Assign(self.value(value), self.rvalue(rvalue)),
+
+            Inst::Store(place, operand) => Inst::Store(self.place(place), self.operand(operand)),
+            Inst::Drop(slot) => Inst::Drop(slot),
+        }
+    }
+
+    fn rvalue(&self, rvalue: Rvalue) -> Rvalue {
+        match rvalue {
+            Rvalue::Use(operand) => Rvalue::Use(self.operand(operand)),
+            Rvalue::Load(place) => Rvalue::Load(self.place(place)),
+
+            Rvalue::Ref { place, is_mutable } => Rvalue::Ref {
+                place: self.place(place),
+                is_mutable,
+            },
+
+            Rvalue::Unary(op, operand) => Rvalue::Unary(op, self.operand(operand)),
+            Rvalue::Binary(op, lhs, rhs) => {
+                Rvalue::Binary(op, self.operand(lhs), self.operand(rhs))
+            }
+
+            Rvalue::Call { callee, args } => Rvalue::Call {
+                callee: self.operand(callee),
+                args: args.into_iter().map(|arg| self.operand(arg)).collect(),
+            },
+
+            Rvalue::Aggregate { fields } => Rvalue::Aggregate {
+                fields: (fields.into_iter())
+                    .map(|(name, operand)| (name, self.operand(operand)))
+                    .collect(),
+            },
+
+            Rvalue::Field(operand, field) => Rvalue::Field(self.operand(operand), field),
+            Rvalue::IsVariant(operand, variant) => {
+                Rvalue::IsVariant(self.operand(operand), variant)
+            }
+
+            Rvalue::Closure { body, captures } => Rvalue::Closure {
+                body,
+                captures: captures
+                    .into_iter()
+                    .map(|capture| self.operand(capture))
+                    .collect(),
+            },
+        }
+    }
+
+    fn jump(&self, jump: Jump) -> Jump {
+        Jump {
+            target: self.block(jump.target),
+            args: jump.args.into_iter().map(|arg| self.operand(arg)).collect(),
+        }
+    }
+
+    fn terminator(&self, terminator: Terminator) -> Terminator {
+        match terminator {
+            Terminator::Goto(jump) => Terminator::Goto(self.jump(jump)),
+
+            Terminator::Branch {
+                condition,
+                then,
+                otherwise,
+            } => Terminator::Branch {
+                condition: self.operand(condition),
+                then: self.jump(then),
+                otherwise: self.jump(otherwise),
+            },
+
+            Terminator::Switch {
+                value,
+                cases,
+                otherwise,
+            } => Terminator::Switch {
+                value: self.operand(value),
+                cases: (cases.into_iter())
+                    .map(|(case, target)| (case, self.block(target)))
+                    .collect(),
+                otherwise: self.block(otherwise),
+            },
+
+            Terminator::Return(value) => Terminator::Return(self.operand(value)),
+            Terminator::Unreachable => Terminator::Unreachable,
+        }
+    }
+}
@@ -0,0 +1,347 @@
+//! Checking that a [Body] is well formed -- a bug in lowering (or in a pass over the MIR) shouldn't reach code
+//! generation.
+
+use super::{BlockId, Body, Inst, Operand, Place, PlaceBase, Rvalue, Terminator, Value};
+use crate::analysis::typeck::ty::Ty;
+use std::collections::HashMap;
+
+/// Check that a body is well formed, returning a message for each problem found:
+///
+/// - Every value is defined exactly once, and its definition dominates every use of it.
+/// - Every jump passes as many arguments as its target block takes, of the right types.
+/// - Conditions of branches are `bool`s, and returned values have the body's return type.
+/// - Every block and slot that's referred to exists, and the entry block takes no parameters.
+pub fn verify(body: &Body) -> Vec<String> {
+    Verifier::new(body).run()
+}
+
+/// Where a value is defined.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Definition {
+    /// A parameter of the body.
+    Param,
+    /// A parameter of a block.
+    BlockParam(BlockId),
+    /// The instruction at the given index in a block.
+    Inst(BlockId, usize),
+}
+
+/// The state of verifying a body.
+struct Verifier<'b> {
+    /// The body being verified.
+    body: &'b Body,
+    /// Where each value is defined.
+    definitions: HashMap<Value, Definition>,
+    /// The blocks that dominate each reachable block ([None] for unreachable blocks).
+    dominators: Vec<Option<Vec<bool>>>,
+    /// The problems found.
+    errors: Vec<String>,
+}
+
+impl<'b> Verifier<'b> {
+    /// Set up the verification of a body.
+    fn new(body: &'b Body) -> Self {
+        Verifier {
+            body,
+            definitions: HashMap::new(),
+            dominators: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// Verify the body.
+    fn run(mut self) -> Vec<String> {
+        if !self.body.blocks.is_empty() && !self.body.block(BlockId::ENTRY).params.is_empty() {
+            self.error("the entry block takes parameters".to_owned());
+        }
+
+        self.check_targets();
+
+        if !self.errors.is_empty() {
+            return self.errors;
+        }
+
+        self.collect_definitions();
+        self.compute_dominators();
+
+        for block in self.body.block_ids() {
+            self.check_block(block);
+        }
+
+        self.errors
+    }
+
+    /// Record a problem.
+    fn error(&mut self, message: String) {
+        self.errors
+            .push(format!("in `{}`, {message}", self.body.name));
+    }
+
+    /// Check that every jump goes to a block that exists.
+    fn check_targets(&mut self) {
+        for block in self.body.block_ids() {
+            for target in self.body.block(block).terminator.successors() {
+                if target.0 as usize >= self.body.blocks.len() {
+                    self.error(format!(
+                        "bb{} jumps to bb{}, which doesn't exist",
+                        block.0, target.0
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Record where every value is defined, reporting values that are defined more than once.
+    fn collect_definitions(&mut self) {
+        let define = |this: &mut Self, value: Value, definition| {
+            if value.0 as usize >= this.body.values.len() {
+                this.error(format!("%{} is defined, but has no type", value.0));
+            } else if this.definitions.insert(value, definition).is_some() {
+                this.error(format!("%{} is defined more than once", value.0));
+            }
+        };
+
+        for param in &self.body.params {
+            define(self, *param, Definition::Param);
+        }
+
+        for block in self.body.block_ids() {
+            for param in &self.body.block(block).params {
+                define(self, *param, Definition::BlockParam(block));
+            }
+
+            for (index, inst) in self.body.block(block).insts.iter().enumerate() {
+                if let Inst::Assign(value, _) = inst {
+                    define(self, *value, Definition::Inst(block, index));
+                }
+            }
+        }
+    }
+
+    /// Compute the dominators of every reachable block, by iterating to a fixed point.
+    fn compute_dominators(&mut self) {
+        let count = self.body.blocks.len();
+        let mut predecessors = vec![Vec::new(); count];
+        let mut reachable = vec![false; count];
+        let mut stack = vec![BlockId::ENTRY];
+
+        while let Some(block) = stack.pop() {
+            if std::mem::replace(&mut reachable[block.0 as usize], true) {
+                continue;
+            }
+
+            for successor in self.body.block(block).terminator.successors() {
+                predecessors[successor.0 as usize].push(block);
+                stack.push(successor);
+            }
+        }
+
+        let mut dominators: Vec<Option<Vec<bool>>> = (0..count)
+            .map(|index| reachable[index].then(|| vec![true; count]))
+            .collect();
+
+        if let Some(entry) = dominators.first_mut() {
+            *entry = Some((0..count).map(|index| index == 0).collect());
+        }
+
+        let mut changed = true;
+
+        while changed {
+            changed = false;
+
+            for index in 1..count {
+                if !reachable[index] {
+                    continue;
+                }
+
+                let mut new = vec![true; count];
+
+                for predecessor in &predecessors[index] {
+                    if let Some(dominated) = &dominators[predecessor.0 as usize] {
+                        new.iter_mut()
+                            .zip(dominated)
+                            .for_each(|(new, dominated)| *new &= *dominated);
+                    }
+                }
+
+                new[index] = true;
+
+                if dominators[index].as_ref() != Some(&new) {
+                    dominators[index] = Some(new);
+                    changed = true;
+                }
+            }
+        }
+
+        self.dominators = dominators;
+    }
+
+    /// Check the instructions and terminator of a block.
+    fn check_block(&mut self, block: BlockId) {
+        // Unreachable blocks are never run, so anything they use is fine.
+        if self.dominators[block.0 as usize].is_none() {
+            return;
+        }
+
+        let data = self.body.block(block);
+
+        for (index, inst) in data.insts.iter().enumerate() {
+            match inst {
+                Inst::Assign(_, rvalue) => self.check_rvalue(rvalue, block, index),
+
+                Inst::Store(place, operand) => {
+                    self.check_place(place, block, index);
+                    self.check_operand(operand, block, index);
+                }
+
+                Inst::Drop(slot) => {
+                    if slot.0 as usize >= self.body.slots.len() {
+                        self.error(format!("bb{} drops _{}, which doesn't exist", block.0, slot.0));
+                    }
+                }
+            }
+        }
+
+        let end = data.insts.len();
+
+        match &data.terminator {
+            Terminator::Branch { condition, .. } => {
+                self.check_operand(condition, block, end);
+                self.check_ty(condition, &Ty::BOOL, || {
+                    format!("the condition of the branch in bb{}", block.0)
+                });
+            }
+
+            Terminator::Switch { value, .. } => self.check_operand(value, block, end),
+
+            Terminator::Return(value) => {
+                self.check_operand(value, block, end);
+                let ret = self.body.ret.clone();
+                self.check_ty(value, &ret, || format!("the value returned in bb{}", block.0));
+            }
+
+            Terminator::Goto(_) | Terminator::Unreachable => {}
+        }
+
+        for (target, args) in data.terminator.jumps() {
+            let params = &self.body.block(target).params;
+
+            if params.len() != args.len() {
+                self.error(format!(
+                    "bb{} passes {} argument{} to bb{}, which takes {}",
+                    block.0,
+                    args.len(),
+                    if args.len() == 1 { "" } else { "s" },
+                    target.0,
+                    params.len()
+                ));
+
+                continue;
+            }
+
+            for (index, (arg, param)) in args.iter().zip(params).enumerate() {
+                self.check_operand(arg, block, end);
+                let ty = self.body.value_ty(*param).clone();
+                self.check_ty(arg, &ty, || {
+                    format!("argument {index} of the jump from bb{} to bb{}", block.0, target.0)
+                });
+            }
+        }
+    }
+
+    /// Check the operands and places used by an rvalue.
+    fn check_rvalue(&mut self, rvalue: &Rvalue, block: BlockId, index: usize) {
+        match rvalue {
+            Rvalue::Use(operand)
+            | Rvalue::Unary(_, operand)
+            | Rvalue::Field(operand, _)
+            | Rvalue::IsVariant(operand, _) => self.check_operand(operand, block, index),
+
+            Rvalue::Load(place) | Rvalue::Ref { place, .. } => {
+                self.check_place(place, block, index)
+            }
+
+            Rvalue::Binary(_, lhs, rhs) => {
+                self.check_operand(lhs, block, index);
+                self.check_operand(rhs, block, index);
+            }
+
+            Rvalue::Call { callee, args } => {
+                self.check_operand(callee, block, index);
+                args.iter()
+                    .for_each(|arg| self.check_operand(arg, block, index));
+            }
+
+            Rvalue::Aggregate { fields } => {
+                (fields.iter()).for_each(|(_, operand)| self.check_operand(operand, block, index))
+            }
+
+            Rvalue::Closure { captures, .. } => {
+                (captures.iter()).for_each(|capture| self.check_operand(capture, block, index))
+            }
+        }
+    }
+
+    /// Check the base of a place.
+    fn check_place(&mut self, place: &Place, block: BlockId, index: usize) {
+        match place.base {
+            PlaceBase::Slot(slot) => {
+                if slot.0 as usize >= self.body.slots.len() {
+                    self.error(format!("bb{} uses _{}, which doesn't exist", block.0, slot.0));
+                }
+            }
+
+            PlaceBase::Value(value) => self.check_use(value, block, index),
+        }
+    }
+
+    /// Check an operand.
+    fn check_operand(&mut self, operand: &Operand, block: BlockId, index: usize) {
+        if let Operand::Value(value) = operand {
+            self.check_use(*value, block, index);
+        }
+    }
+
+    /// Check that a value used by the instruction at the given index of a block (or its terminator, if the index
+    /// is the number of instructions) is defined somewhere that dominates the use.
+    fn check_use(&mut self, value: Value, block: BlockId, index: usize) {
+        let dominates = match self.definitions.get(&value) {
+            None => {
+                self.error(format!("%{} is used in bb{}, but never defined", value.0, block.0));
+                return;
+            }
+
+            Some(Definition::Param) => true,
+            Some(Definition::BlockParam(defined)) => self.dominates(*defined, block),
+            Some(Definition::Inst(defined, defined_at)) if *defined == block => *defined_at < index,
+            Some(Definition::Inst(defined, _)) => self.dominates(*defined, block),
+        };
+
+        if !dominates {
+            let defined = match self.definitions[&value] {
+                Definition::BlockParam(defined) | Definition::Inst(defined, _) => defined,
+                Definition::Param => BlockId::ENTRY,
+            };
+
+            self.error(format!(
+                "%{} is used in bb{}, but its definition in bb{} doesn't dominate it",
+                value.0, block.0, defined.0
+            ));
+        }
+    }
+
+    /// Check if a block dominates another (reachable) block.
+    fn dominates(&self, dominator: BlockId, block: BlockId) -> bool {
+        (self.dominators[block.0 as usize].as_ref())
+            .is_some_and(|dominators| dominators[dominator.0 as usize])
+    }
+
+    /// Check that an operand has the expected type, describing where it's used if it doesn't.
+    fn check_ty(&mut self, operand: &Operand, expected: &Ty, describe: impl FnOnce() -> String) {
+        let found = self.body.operand_ty(operand);
+
+        if found != *expected && !found.references_error() && !expected.references_error() {
+            self.error(format!("{} has type {found:?}, but {expected:?} is expected", describe()));
+        }
+    }
+}
@@ -0,0 +1,524 @@
+//! Loading a package of wright source code that's split across multiple files.
+//!
+//! A package starts from a single root file. Every `mod name;` declaration in a loaded file includes the module
+//! `name` from either `name.wr` or `name/mod.wr`, found relative to the directory of the declaring file's module --
+//! the directory the file is in for root files and `mod.wr` files, or a directory named after the file otherwise
+//! (so `mod http;` in `net.wr` loads `net/http.wr` or `net/http/mod.wr`). Declarations inside inline
+//! `mod name { ... }` modules look one directory further down, in `name/`.
+//!
+//! Problems with `mod` declarations (missing, ambiguous, or cyclic module files) are reported as [Diagnostic]s
+//! pointing at the declaration.
+//!
+//! Once a package is loaded, `check_package` runs every analysis pass over it, the same way `wright check` and
+//! `wright build` do, and the checked package can be lowered to the MIR for the VM or a backend.
+
+#[cfg(feature = "analysis")]
+use crate::{
+    analysis::{
+        borrowck,
+        constraints::{self, ConstraintResults},
+        exhaustiveness, purity,
+        resolve::{Resolution, resolve},
+        typeck::{self, TypeckResults},
+    },
+    hir,
+    mir::{self, Program},
+    reporting::Severity,
+};
+use crate::{
+    ast::{
+        decl::{Decl, module::ModuleDecl},
+        identifier::Identifier,
+        module::Module,
+    },
+    lexer::Lexer,
+    parser::Parser,
+    reporting::{Diagnostic, Highlight},
+    source_tracking::{SourceMap, filename::FileName, source::Source},
+};
+use std::{
+    collections::HashMap,
+    io,
+    path::{Path, PathBuf},
+};
+
+/// The file extension of wright source files.
+pub const SOURCE_EXTENSION: &str = "wr";
+
+/// The names of the files that can be the root of a package, in order of preference.
+pub const ROOT_FILE_NAMES: &[&str] = &["main.wr", "lib.wr"];
+
+/// The name of the file that holds a module whose submodules are stored next to it (i.e. `net/mod.wr`).
+pub const MODULE_FILE_NAME: &str = "mod.wr";
+
+/// A module loaded from a file in a [Package].
+#[derive(Debug)]
+pub struct PackageModule {
+    /// The path of the module from the root of the package. The root module has an empty path.
+    pub path: Vec<String>,
+
+    /// The parsed module.
+    pub module: Module,
+}
+
+/// A package of wright source files, loaded from its root file by following `mod name;` declarations.
+#[derive(Debug)]
+pub struct Package {
+    /// The root file of the package.
+    pub root: PathBuf,
+
+    /// Every module that was loaded from a file, in the order they were loaded (starting with the root).
+    pub modules: Vec<PackageModule>,
+
+    /// Errors (and warnings) found while loading and parsing the package.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Find the root file of the package that a path is part of.
+///
+/// If `path` is a directory, it and then each of its ancestors are searched for a root file (see
+/// [ROOT_FILE_NAMES]). If `path` is a file, its ancestors are searched in the same way -- and if no root file is
+/// found, the file is treated as the root of a single-file package.
+pub fn find_package_root(path: &Path) -> io::Result<PathBuf> {
+    // The ancestors of a relative path end at the current directory rather than the ones above it, so walk the
+    // absolute path instead.
+    let absolute = path.canonicalize().map_err(|_| {
+        io::Error::new(io::ErrorKind::NotFound, format!("{} does not exist", path.display()))
+    })?;
+
+    let start = match absolute.is_file() {
+        true => absolute.parent().unwrap_or(&absolute),
+        false => &absolute,
+    };
+
+    for dir in start.ancestors() {
+        let root = ROOT_FILE_NAMES
+            .iter()
+            .map(|name| dir.join(name))
+            .find(|candidate| candidate.is_file());
+
+        if let Some(root) = root {
+            return Ok(root);
+        }
+    }
+
+    if absolute.is_file() {
+        Ok(absolute)
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!(
+                "could not find a package root ({}) in {} or any of its parents",
+                ROOT_FILE_NAMES.join(" or "),
+                path.display()
+            ),
+        ))
+    }
+}
+
+/// Load a package from its root file, adding every loaded file to the [SourceMap].
+///
+/// Only a failure to read the root file is returned as an [io::Error] -- problems with any other file are reported
+/// as [Diagnostic]s on the `mod` declaration that includes it.
+pub fn load_package(map: &SourceMap, root: &Path) -> io::Result<Package> {
+    let mut loader = Loader {
+        map,
+        modules: Vec::new(),
+        diagnostics: Vec::new(),
+        loaded: HashMap::new(),
+        stack: Vec::new(),
+    };
+
+    let root_source = Source::new_mapped_or_read(root.to_owned())?;
+    let directory = root.parent().map(Path::to_owned).unwrap_or_default();
+    loader.load_file(root_source, Vec::new(), directory);
+
+    Ok(Package {
+        root: root.to_owned(),
+        modules: loader.modules,
+        diagnostics: loader.diagnostics,
+    })
+}
+
+impl Package {
+    /// Get every module of the package with its path, the way the analysis passes take them.
+    pub fn module_paths(&self) -> impl Iterator<Item = (Vec<String>, &Module)> + Clone {
+        (self.modules.iter()).map(|module| (module.path.clone(), &module.module))
+    }
+}
+
+/// A [Package] that every analysis pass has been run over, by [check_package].
+#[cfg(feature = "analysis")]
+#[derive(Debug)]
+pub struct CheckedPackage {
+    /// The package.
+    pub package: Package,
+
+    /// The result of name resolution.
+    pub resolution: Resolution,
+
+    /// The result of type checking.
+    pub typeck: TypeckResults,
+
+    /// Diagnostics from checking which functions are pure.
+    pub purity: Vec<Diagnostic>,
+
+    /// The result of verifying constrained types.
+    pub constraints: ConstraintResults,
+
+    /// Diagnostics from checking that every `match` is exhaustive.
+    pub exhaustiveness: Vec<Diagnostic>,
+
+    /// Diagnostics from borrow checking.
+    pub borrowck: Vec<Diagnostic>,
+}
+
+#[cfg(feature = "analysis")]
+impl CheckedPackage {
+    /// Get every diagnostic: those from loading the package first, and then those of each pass in the order they
+    /// ran.
+    pub fn diagnostics(&self) -> impl Iterator<Item = &Diagnostic> {
+        (self.package.diagnostics.iter())
+            .chain(&self.resolution.diagnostics)
+            .chain(&self.typeck.diagnostics)
+            .chain(&self.purity)
+            .chain(&self.constraints.diagnostics)
+            .chain(&self.exhaustiveness)
+            .chain(&self.borrowck)
+    }
+
+    /// Count the diagnostics that are errors.
+    pub fn errors(&self) -> usize {
+        (self.diagnostics())
+            .filter(|diagnostic| diagnostic.0.severity == Severity::Error)
+            .count()
+    }
+
+    /// Lower the package to the MIR, which only makes sense if it has no [errors](CheckedPackage::errors).
+    pub fn lower(&self) -> Program {
+        let hir = hir::lower(self.package.module_paths(), &self.resolution);
+        mir::lower(&hir, &self.resolution, &self.typeck)
+    }
+}
+
+/// Run every analysis pass over a package: name resolution, type checking, purity checking, constraint
+/// verification, exhaustiveness checking, and borrow checking. Every pass runs even if an earlier one found errors,
+/// so that as many problems as possible are reported at once.
+#[cfg(feature = "analysis")]
+pub fn check_package(package: Package) -> CheckedPackage {
+    let resolution = resolve(package.module_paths());
+    let purity = purity::check(package.module_paths(), &resolution);
+    let typeck = typeck::check(package.module_paths(), &resolution);
+    let constraints = constraints::verify(package.module_paths(), &resolution, &typeck);
+    let exhaustiveness = exhaustiveness::check(package.module_paths(), &resolution, &typeck);
+    let borrowck = borrowck::check(package.module_paths(), &resolution, &typeck);
+
+    CheckedPackage {
+        package,
+        resolution,
+        typeck,
+        purity,
+        constraints,
+        exhaustiveness,
+        borrowck,
+    }
+}
+
+/// A `mod name;` declaration waiting for its file to be loaded.
+struct PendingModule {
+    /// The name of the declared module, for error reporting.
+    name: Identifier,
+
+    /// The path of the declared module from the package root.
+    path: Vec<String>,
+
+    /// The directory to look for the module's file in.
+    directory: PathBuf,
+}
+
+/// The state of loading a [Package].
+struct Loader<'m> {
+    /// The [SourceMap] that loaded files are added to.
+    map: &'m SourceMap,
+
+    /// The modules loaded so far.
+    modules: Vec<PackageModule>,
+
+    /// Diagnostics from loading and parsing.
+    diagnostics: Vec<Diagnostic>,
+
+    /// The module path of every file loaded so far, by the canonical path of the file.
+    loaded: HashMap<PathBuf, Vec<String>>,
+
+    /// The canonical paths of the files currently being loaded, from the root down. Used to detect cycles.
+    stack: Vec<PathBuf>,
+}
+
+impl Loader<'_> {
+    /// Parse a source file as the module at the given path, and then load all of the modules it declares with
+    /// `mod name;`. The `directory` is where those modules' files are looked for.
+    fn load_file(&mut self, source: Source, path: Vec<String>, directory: PathBuf) {
+        let canonical = canonical_path(source.name());
+        let source_ref = self.map.add(source);
+        let mut parser = Parser::new(Lexer::new(source_ref));
+        let parsed = Module::parse(&mut parser);
+
+        self.diagnostics.extend(parser.take_warnings());
+
+        let module = match parsed {
+            Ok(module) => module,
+            Err(err) => {
+                self.diagnostics.push(err.as_diagnostic());
+                return;
+            }
+        };
+
+        let mut pending = Vec::new();
+        collect_pending(&module.items, &path, &directory, &mut pending);
+
+        if let Some(canonical) = &canonical {
+            self.loaded.insert(canonical.clone(), path.clone());
+            self.stack.push(canonical.clone());
+        }
+
+        self.modules.push(PackageModule { path, module });
+
+        for module in pending {
+            self.load_module(module);
+        }
+
+        if canonical.is_some() {
+            self.stack.pop();
+        }
+    }
+
+    /// Find and load the file for a `mod name;` declaration, reporting an error at the declaration if it can't be
+    /// loaded.
+    fn load_module(&mut self, pending: PendingModule) {
+        let name = pending.name.fragment.as_str();
+        let flat = pending.directory.join(format!("{name}.{SOURCE_EXTENSION}"));
+        let nested = pending.directory.join(name).join(MODULE_FILE_NAME);
+
+        let (file, directory) = match (flat.is_file(), nested.is_file()) {
+            (true, false) => (flat, pending.directory.join(name)),
+            (false, true) => (nested, pending.directory.join(name)),
+
+            (false, false) => {
+                self.diagnostics.push(
+                    Diagnostic::error()
+                        .with_message(format!("file not found for module `{name}`"))
+                        .with_highlights([Highlight::primary(
+                            pending.name.fragment.clone(),
+                            "no file for this module",
+                        )])
+                        .with_notes([format!(
+                            "help: create the module's file at `{}` or `{}`",
+                            flat.display(),
+                            nested.display()
+                        )]),
+                );
+
+                return;
+            }
+
+            (true, true) => {
+                self.diagnostics.push(
+                    Diagnostic::error()
+                        .with_message(format!(
+                            "file for module `{name}` found at both `{}` and `{}`",
+                            flat.display(),
+                            nested.display()
+                        ))
+                        .with_highlights([Highlight::primary(
+                            pending.name.fragment.clone(),
+                            "this module's file is ambiguous",
+                        )])
+                        .with_notes(["help: delete or rename one of the files"]),
+                );
+
+                return;
+            }
+        };
+
+        let canonical = file.canonicalize().unwrap_or_else(|_| file.clone());
+
+        if let Some(position) = self.stack.iter().position(|loading| *loading == canonical) {
+            let chain: Vec<String> = self.stack[position..]
+                .iter()
+                .chain(std::iter::once(&canonical))
+                .map(|file| format!("`{}`", file.display()))
+                .collect();
+
+            self.diagnostics.push(
+                Diagnostic::error()
+                    .with_message(format!("cyclic module declaration of `{name}`"))
+                    .with_highlights([Highlight::primary(
+                        pending.name.fragment.clone(),
+                        format!("`{}` is already being loaded", file.display()),
+                    )])
+                    .with_notes([format!("the cycle is {}", chain.join(" -> "))]),
+            );
+
+            return;
+        }
+
+        if let Some(existing) = self.loaded.get(&canonical) {
+            let existing = match existing.is_empty() {
+                true => "the package root".to_owned(),
+                false => format!("module `{}`", existing.join("::")),
+            };
+
+            self.diagnostics.push(
+                Diagnostic::error()
+                    .with_message(format!("`{}` is already loaded as {existing}", file.display()))
+                    .with_highlights([Highlight::primary(
+                        pending.name.fragment.clone(),
+                        "this module's file is included more than once",
+                    )])
+                    .with_notes(["each file can only be included in a package once"]),
+            );
+
+            return;
+        }
+
+        match Source::new_mapped_or_read(file.clone()) {
+            Ok(source) => self.load_file(source, pending.path, directory),
+            Err(err) => self.diagnostics.push(
+                Diagnostic::error()
+                    .with_message(format!("could not read `{}`: {err}", file.display()))
+                    .with_highlights([Highlight::primary(
+                        pending.name.fragment,
+                        "this module could not be loaded",
+                    )]),
+            ),
+        }
+    }
+}
+
+/// Get the canonical path of a source file, if it's a real file.
+fn canonical_path(name: &FileName) -> Option<PathBuf> {
+    match name {
+        FileName::Real(path) => Some(path.canonicalize().unwrap_or_else(|_| path.clone())),
+        _ => None,
+    }
+}
+
+/// Collect every `mod name;` declaration in a list of items, including inside inline modules.
+fn collect_pending(
+    items: &[Decl],
+    path: &[String],
+    directory: &Path,
+    pending: &mut Vec<PendingModule>,
+) {
+    for decl in items {
+        let Decl::Module(ModuleDecl { name, body, .. }) = decl else {
+            continue;
+        };
+
+        let mut module_path = path.to_vec();
+        module_path.push(name.fragment.as_str().to_owned());
+
+        match body {
+            Some(body) => collect_pending(
+                &body.items,
+                &module_path,
+                &directory.join(name.fragment.as_str()),
+                pending,
+            ),
+
+            None => pending.push(PendingModule {
+                name: name.clone(),
+                path: module_path,
+                directory: directory.to_owned(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_package_root, load_package};
+    use crate::source_tracking::SourceMap;
+    use std::{fs, path::PathBuf};
+
+    /// Create a fresh directory of source files for a test.
+    fn package(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("wright-package-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        for (file, content) in files {
+            let file = dir.join(file);
+            fs::create_dir_all(file.parent().unwrap()).unwrap();
+            fs::write(file, content).unwrap();
+        }
+
+        dir
+    }
+
+    #[test]
+    fn test_load_package() {
+        let dir = package(
+            "load",
+            &[
+                ("main.wr", "mod net;\nmod util { mod strings; }\n"),
+                ("net/mod.wr", "mod http;\n"),
+                ("net/http.wr", "pub func get();\n"),
+                ("util/strings.wr", "pub type Str;\n"),
+            ],
+        );
+
+        let root = find_package_root(&dir.join("net")).unwrap();
+        assert_eq!(root, dir.canonicalize().unwrap().join("main.wr"));
+
+        let map = SourceMap::new();
+        let package = load_package(&map, &root).unwrap();
+        assert!(package.diagnostics.is_empty());
+
+        let paths: Vec<String> = package.modules.iter().map(|m| m.path.join("::")).collect();
+        assert_eq!(paths, ["", "net", "net::http", "util::strings"]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_root_of_indirect_path() {
+        let dir = package("indirect", &[("a/main.wr", ""), ("b/single.wr", "func main();\n")]);
+
+        // `a/..` is not an ancestor of `b`, so this is a single-file package rather than part of `a`'s package.
+        let root = find_package_root(&dir.join("a/../b/single.wr")).unwrap();
+        assert_eq!(root, dir.canonicalize().unwrap().join("b/single.wr"));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_module_errors() {
+        let dir = package(
+            "errors",
+            &[
+                ("main.wr", "mod missing;\nmod both;\nmod main;\n"),
+                ("both.wr", ""),
+                ("both/mod.wr", ""),
+            ],
+        );
+
+        let map = SourceMap::new();
+        let package = load_package(&map, &dir.join("main.wr")).unwrap();
+        let messages: Vec<&str> = package
+            .diagnostics
+            .iter()
+            .map(|d| d.0.message.as_str())
+            .collect();
+
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0], "file not found for module `missing`");
+        assert!(messages[1].starts_with("file for module `both` found at both"));
+        assert_eq!(messages[2], "cyclic module declaration of `main`");
+
+        // Errors point at the name in the declaration.
+        assert_eq!(package.diagnostics[0].0.labels[0].range, 4..11);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
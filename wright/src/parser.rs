@@ -8,15 +8,21 @@ use error::{ParserError, ParserErrorKind};
 use super::lexer::Lexer;
 use crate::{
     lexer::token::{Token, TokenTy},
+    reporting::Diagnostic,
     source_tracking::fragment::Fragment,
 };
 use std::collections::VecDeque;
 
 mod decl;
+mod docs;
 pub mod error;
+mod expr;
 mod identifier;
 mod literal;
+mod module;
 mod path;
+mod pattern;
+mod stmt;
 mod ty;
 
 /// The [Parser] struct wraps a [Lexer] and adds lookahead and functions that are useful for parsing.
@@ -24,6 +30,11 @@ mod ty;
 pub struct Parser {
     lexer: Lexer,
     lookahead: VecDeque<Token>,
+    warnings: Vec<Diagnostic>,
+
+    /// Whether record literals (i.e. `Point { x: 1 }`) are currently disallowed, since they would be ambiguous with
+    /// a following block (such as in the condition of an `if`).
+    no_record_literals: bool,
 }
 
 impl Parser {
@@ -32,9 +43,42 @@ impl Parser {
         Parser {
             lexer,
             lookahead: VecDeque::new(),
+            warnings: Vec::new(),
+            no_record_literals: false,
         }
     }
 
+    /// Record a warning produced while parsing. Warnings don't stop parsing -- they can be retrieved
+    /// using [Parser::take_warnings].
+    pub fn warn(&mut self, warning: Diagnostic) {
+        self.warnings.push(warning);
+    }
+
+    /// Take all of the warnings this [Parser] has produced so far.
+    pub fn take_warnings(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    /// Run a parsing function with record literals allowed or disallowed, restoring the previous setting after.
+    ///
+    /// Record literals are disallowed in `if` and `while` conditions (where `x { ... }` is a condition followed by a
+    /// block) and allowed again inside any parentheses or blocks nested in them.
+    pub(crate) fn with_record_literals<T>(
+        &mut self,
+        allowed: bool,
+        parse: impl FnOnce(&mut Parser) -> T,
+    ) -> T {
+        let previous = std::mem::replace(&mut self.no_record_literals, !allowed);
+        let result = parse(self);
+        self.no_record_literals = previous;
+        result
+    }
+
+    /// Check if record literals are allowed at the current position (see [Parser::with_record_literals]).
+    pub(crate) fn record_literals_allowed(&self) -> bool {
+        !self.no_record_literals
+    }
+
     /// Get the [Lexer] that's wrapped.
     pub fn lexer(&self) -> &Lexer {
         &self.lexer
@@ -104,6 +148,37 @@ impl Parser {
         unsafe { std::hint::unreachable_unchecked() }
     }
 
+    /// If the next non-whitespace [Token] is a [TokenTy::GtGt], split it into two [TokenTy::Gt]s in the lookahead
+    /// buffer. This is used where a `>` closes a list of generics, so that nested generics like `Opt<Opt<T>>` can
+    /// close both lists.
+    pub fn split_closing_angle(&mut self) {
+        if self.peek_next_not_whitespace().map(|t| t.variant) != Some(TokenTy::GtGt) {
+            return;
+        }
+
+        // The token we just peeked is in the lookahead buffer, after any whitespace.
+        let index = self
+            .lookahead
+            .iter()
+            .position(|t| t.variant == TokenTy::GtGt)
+            .expect("peeked token is in the lookahead buffer");
+
+        let (first, second) = self.lookahead[index].fragment.split_at(1);
+
+        self.lookahead[index] = Token {
+            variant: TokenTy::Gt,
+            fragment: first,
+        };
+
+        self.lookahead.insert(
+            index + 1,
+            Token {
+                variant: TokenTy::Gt,
+                fragment: second,
+            },
+        );
+    }
+
     /// Get the number of remaining bytes on this parser. This is potentially useful for checking
     /// if a parser has advanced between two calls (or checking if a parser has reached end of input).
     pub fn bytes_remaining(&self) -> usize {
@@ -241,6 +316,39 @@ impl Parser {
             .all(|(seq_item, lookahead_item)| *seq_item == lookahead_item)
     }
 
+    /// Parse a comma separated list of items (allowing a trailing comma) that ends with the given `close` token.
+    /// The opening token (if any) should already be consumed. Whitespace is allowed between everything.
+    ///
+    /// Returns the parsed items and the closing [Token]. If neither a comma nor the closing token follows an item,
+    /// an error of the given `unterminated` kind is returned.
+    pub(crate) fn parse_comma_separated<T>(
+        &mut self,
+        close: TokenTy,
+        unterminated: ParserErrorKind,
+        mut parse_item: impl FnMut(&mut Parser) -> Result<T, ParserError>,
+    ) -> Result<(Vec<T>, Token), ParserError> {
+        let mut items = Vec::new();
+
+        loop {
+            self.consume_optional_whitespace();
+            if let Some(close) = self.next_if_is(close) {
+                return Ok((items, close));
+            }
+
+            items.push(parse_item(self)?);
+            self.consume_optional_whitespace();
+
+            if self.next_if_is(TokenTy::Comma).is_some() {
+                continue;
+            }
+
+            match self.next_if_is(close) {
+                Some(close) => return Ok((items, close)),
+                None => return Err(unterminated.at(self.peek_fragment_or_rest_cloned())),
+            }
+        }
+    }
+
     /// Consume & remove all whitespace tokens from the front of the parser.
     pub fn consume_optional_whitespace(&mut self) {
         // Iterate until the next token is not whitespace.
@@ -1,3 +1,300 @@
 //! Declaration parsing.
 
+use crate::{
+    ast::{
+        decl::{
+            Decl, Visibility, constraint::ConstraintDecl, enumeration::EnumDecl, func::FuncDecl,
+            implementation::ImplDecl, import::ImportDecl, module::ModuleDecl, record::RecordDecl,
+            trait_decl::TraitDecl, type_alias::TypeAlias, union::UnionDecl,
+        },
+        docs::Docs,
+    },
+    lexer::token::{Token, TokenTy},
+    parser::{
+        Parser,
+        docs::OUTER_DOC_COMMENTS,
+        error::{ParserError, ParserErrorKind},
+    },
+    source_tracking::fragment::Fragment,
+};
+
+mod constraint;
+mod enumeration;
+mod func;
+mod generic;
+mod implementation;
 mod import;
+mod module;
+mod record;
+mod repr;
+mod trait_decl;
+mod type_alias;
+mod union;
+
+/// The keywords that can start a declaration (following any doc comments and visibility modifier).
+pub const DECL_KEYWORDS: &[TokenTy] = &[
+    TokenTy::KwUse,
+    TokenTy::KwType,
+    TokenTy::KwPure,
+    TokenTy::KwFunc,
+    TokenTy::KwMod,
+    TokenTy::KwRecord,
+    TokenTy::KwUnion,
+    TokenTy::KwEnum,
+    TokenTy::KwConstraint,
+    TokenTy::KwTrait,
+    TokenTy::KwImpl,
+];
+
+impl Decl {
+    /// Parse any kind of declaration, with optional doc comments and a visibility modifier in front of it.
+    ///
+    /// The parser is left unadvanced if the next tokens (past any doc comments and visibility modifier) do not
+    /// start a declaration.
+    pub fn parse(parser: &mut Parser) -> Result<Self, ParserError> {
+        match peek_decl_keyword(parser) {
+            Some(TokenTy::KwUse) => ImportDecl::parse(parser).map(Decl::Import),
+            Some(TokenTy::KwType) => TypeAlias::parse(parser).map(Decl::TypeAlias),
+            Some(TokenTy::KwPure | TokenTy::KwFunc) => FuncDecl::parse(parser).map(Decl::Function),
+            Some(TokenTy::KwMod) => ModuleDecl::parse(parser).map(Decl::Module),
+            Some(TokenTy::KwRecord) => RecordDecl::parse(parser).map(Decl::Record),
+            Some(TokenTy::KwUnion) => UnionDecl::parse(parser).map(Decl::Union),
+            Some(TokenTy::KwEnum) => EnumDecl::parse(parser).map(Decl::Enum),
+            Some(TokenTy::KwConstraint) => ConstraintDecl::parse(parser).map(Decl::Constraint),
+            Some(TokenTy::KwTrait) => TraitDecl::parse(parser).map(Decl::Trait),
+            Some(TokenTy::KwImpl) => ImplDecl::parse(parser).map(Decl::Impl),
+            _ => {
+                Err(ParserErrorKind::ExpectedDeclaration.at(parser.peek_fragment_or_rest_cloned()))
+            }
+        }
+    }
+}
+
+impl Visibility {
+    /// Parse an optional `pub` or `pub(mod)` visibility modifier.
+    ///
+    /// If the next token is not `pub` this returns [Visibility::Private] without advancing the parser.
+    pub fn parse(parser: &mut Parser) -> Result<Self, ParserError> {
+        let Some(pub_kw) = parser.next_if_is(TokenTy::KwPub) else {
+            return Ok(Visibility::Private);
+        };
+
+        // Check for a `(mod)` restriction.
+        if !parser.matches_ignore_whitespace(&[TokenTy::LeftParen]) {
+            return Ok(Visibility::Public {
+                matching_source: pub_kw.fragment,
+            });
+        }
+
+        // Chew through whitespace and the opening paren.
+        parser.consume_optional_whitespace();
+        parser.advance(1);
+        parser.consume_optional_whitespace();
+
+        if parser.next_if_is(TokenTy::KwMod).is_none() {
+            return Err(ParserErrorKind::ExpectedVisibilityRestriction
+                .at(parser.peek_fragment_or_rest_cloned())
+                .with_help("the only visibility restriction currently supported is `pub(mod)`"));
+        }
+
+        parser.consume_optional_whitespace();
+
+        match parser.next_if_is(TokenTy::RightParen) {
+            Some(close_paren) => Ok(Visibility::Module {
+                matching_source: Fragment::cover(&pub_kw.fragment, &close_paren.fragment),
+            }),
+
+            None => Err(ParserErrorKind::UnterminatedVisibilityRestriction
+                .at(parser.peek_fragment_or_rest_cloned())),
+        }
+    }
+}
+
+/// Check if the next tokens of the [Parser] (past any doc comments and visibility modifier) start a declaration.
+pub fn at_decl(parser: &mut Parser) -> bool {
+    peek_decl_keyword(parser).is_some_and(|variant| DECL_KEYWORDS.contains(&variant))
+}
+
+/// Peek the [TokenTy] of the first token after any doc comments, visibility modifier, and whitespace, without
+/// advancing the [Parser]. This is used to decide which declaration parser to hand off to.
+fn peek_decl_keyword(parser: &mut Parser) -> Option<TokenTy> {
+    let mut k: usize = 0;
+
+    // Skip any doc comments and the whitespace between them.
+    while let Some(variant) = parser.lookahead(k).map(|t| t.variant) {
+        if variant != TokenTy::Whitespace && !OUTER_DOC_COMMENTS.contains(&variant) {
+            break;
+        }
+
+        k += 1;
+    }
+
+    if parser.lookahead(k)?.variant == TokenTy::KwPub {
+        k += 1;
+
+        while parser.lookahead(k)?.variant == TokenTy::Whitespace {
+            k += 1;
+        }
+
+        // Skip a visibility restriction if there is one.
+        if parser.lookahead(k)?.variant == TokenTy::LeftParen {
+            while parser.lookahead(k)?.variant != TokenTy::RightParen {
+                k += 1;
+            }
+
+            k += 1;
+
+            while parser.lookahead(k)?.variant == TokenTy::Whitespace {
+                k += 1;
+            }
+        }
+    }
+
+    parser.lookahead(k).map(|t| t.variant)
+}
+
+/// The parts of source code that start every declaration.
+pub(crate) struct DeclStart {
+    /// Docs from any outer doc comments before the declaration.
+    pub docs: Docs,
+    /// The declaration's visibility.
+    pub visibility: Visibility,
+    /// The keyword introducing the declaration.
+    pub keyword: Token,
+    /// The fragment that the declaration starts at (either the visibility modifier or the keyword).
+    pub start: Fragment,
+}
+
+/// Parse the optional doc comments and visibility modifier at the start of a declaration followed by the keyword
+/// that introduces it (i.e. `/// docs` then `pub use` or `type`). Any of the given `keywords` are accepted.
+///
+/// If none of these are found, the parser is left unadvanced and an error of the given `expected` kind is returned.
+pub(crate) fn parse_decl_start(
+    parser: &mut Parser,
+    keywords: &[TokenTy],
+    expected: ParserErrorKind,
+) -> Result<DeclStart, ParserError> {
+    let docs = Docs::parse_outer(parser);
+
+    if !docs.is_empty() {
+        parser.consume_optional_whitespace();
+    }
+
+    let visibility = Visibility::parse(parser)?;
+
+    if !visibility.is_private() {
+        parser.consume_at_least_one_whitespace()?;
+    }
+
+    let keyword: Token = match parser.peek_variant() {
+        Some(variant) if keywords.contains(&variant) => {
+            // SAFETY: We just peeked a keyword, which is never an unknown token.
+            unsafe { parser.next_token().unwrap_unchecked().unwrap_unchecked() }
+        }
+
+        _ => return Err(expected.at(parser.peek_fragment_or_rest_cloned())),
+    };
+
+    let start = visibility
+        .matching_source()
+        .unwrap_or(&keyword.fragment)
+        .clone();
+
+    Ok(DeclStart {
+        docs,
+        visibility,
+        keyword,
+        start,
+    })
+}
+
+/// Parse the braced list of methods in a `trait` or `impl` block, i.e. `{ func area(self: @Self) -> u64; }`,
+/// returning them along with the closing curly brace.
+pub(crate) fn parse_method_list(
+    parser: &mut Parser,
+) -> Result<(Vec<FuncDecl>, Token), ParserError> {
+    if parser.next_if_is(TokenTy::LeftCurly).is_none() {
+        return Err(ParserErrorKind::ExpectedMethodList.at(parser.peek_fragment_or_rest_cloned()));
+    }
+
+    let mut methods = Vec::new();
+
+    loop {
+        parser.consume_optional_whitespace();
+
+        if let Some(close) = parser.next_if_is(TokenTy::RightCurly) {
+            return Ok((methods, close));
+        }
+
+        if parser.peek_variant().is_none() {
+            return Err(
+                ParserErrorKind::UnterminatedMethodList.at(parser.peek_fragment_or_rest_cloned())
+            );
+        }
+
+        methods.push(FuncDecl::parse(parser)?);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::decl::{Decl, Visibility},
+        lexer::Lexer,
+        parser::{Parser, error::ParserErrorKind},
+    };
+
+    #[test]
+    fn test_visibility() {
+        let mut parser = Parser::new(Lexer::new_test("pub"));
+        assert!(matches!(Visibility::parse(&mut parser).unwrap(), Visibility::Public { .. }));
+
+        let mut parser = Parser::new(Lexer::new_test("pub ( mod )"));
+        let visibility = Visibility::parse(&mut parser).unwrap();
+        assert!(matches!(visibility, Visibility::Module { .. }));
+        assert_eq!(visibility.matching_source().unwrap().as_str(), "pub ( mod )");
+
+        let mut parser = Parser::new(Lexer::new_test("use"));
+        assert!(Visibility::parse(&mut parser).unwrap().is_private());
+        assert_eq!(parser.bytes_remaining(), 3);
+    }
+
+    #[test]
+    fn test_bad_visibility_restriction() {
+        let mut parser = Parser::new(Lexer::new_test("pub(crate)"));
+        let err = Visibility::parse(&mut parser).unwrap_err();
+        assert_eq!(err.kind, ParserErrorKind::ExpectedVisibilityRestriction);
+    }
+
+    #[test]
+    fn test_pub_decls() {
+        let mut parser = Parser::new(Lexer::new_test("pub(mod) use wright::util;"));
+        let decl = Decl::parse(&mut parser).unwrap();
+        assert!(matches!(decl, Decl::Import(_)));
+        assert!(matches!(decl.visibility(), Visibility::Module { .. }));
+        assert_eq!(decl.matching_source().as_str(), "pub(mod) use wright::util;");
+        assert_eq!(decl.name().unwrap().fragment.as_str(), "util");
+
+        let mut parser = Parser::new(Lexer::new_test("pub type Void;"));
+        let decl = Decl::parse(&mut parser).unwrap();
+        assert!(matches!(decl.visibility(), Visibility::Public { .. }));
+        assert_eq!(decl.name().unwrap().fragment.as_str(), "Void");
+    }
+
+    #[test]
+    fn test_documented_decl() {
+        let mut parser =
+            Parser::new(Lexer::new_test("/// An empty type.\n/// Really.\npub type Void;"));
+        let decl = Decl::parse(&mut parser).unwrap();
+        assert_eq!(decl.docs().text(), "An empty type.\nReally.");
+        assert_eq!(decl.matching_source().as_str(), "pub type Void;");
+    }
+
+    #[test]
+    fn test_not_a_decl() {
+        let mut parser = Parser::new(Lexer::new_test("pub 12"));
+        let err = Decl::parse(&mut parser).unwrap_err();
+        assert_eq!(err.kind, ParserErrorKind::ExpectedDeclaration);
+        assert_eq!(parser.bytes_remaining(), 6);
+    }
+}
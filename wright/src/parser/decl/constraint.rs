@@ -0,0 +1,170 @@
+//! Parser implementation for constraint declarations.
+
+use crate::{
+    ast::{
+        decl::{
+            constraint::{ConstraintDecl, ConstraintPredicate},
+            func::FuncParam,
+            generic::GenericParam,
+        },
+        identifier::Identifier,
+        stmt::Block,
+        ty::ConstrainedTy,
+    },
+    lexer::token::TokenTy,
+    parser::{
+        Parser,
+        decl::parse_decl_start,
+        error::{ParserError, ParserErrorKind},
+    },
+    source_tracking::fragment::Fragment,
+};
+
+impl ConstraintDecl {
+    /// Parse a constraint declaration, including any doc comments and visibility modifier in front of it.
+    ///
+    /// Both predicates (`constraint Name<T>(value: T): Base + Other { ... }`) and compositions
+    /// (`constraint Name = Base + Other;`) are accepted.
+    pub fn parse(parser: &mut Parser) -> Result<Self, ParserError> {
+        let start = parse_decl_start(
+            parser,
+            &[TokenTy::KwConstraint],
+            ParserErrorKind::ExpectedConstraintDeclaration,
+        )?;
+
+        parser.consume_at_least_one_whitespace()?;
+        let name = Identifier::parse(parser)?;
+        parser.consume_optional_whitespace();
+        let generic_params = GenericParam::parse_list(parser)?;
+        parser.consume_optional_whitespace();
+
+        let (predicate, bases, end) = match parser.peek_variant() {
+            Some(TokenTy::Eq) => {
+                parser.advance(1);
+                let bases = ConstrainedTy::parse_constraining_items(parser)?;
+                parser.consume_optional_whitespace();
+
+                let Some(semi) = parser.next_if_is(TokenTy::Semi) else {
+                    return Err(ParserErrorKind::ConstraintMustEndWithSemicolon
+                        .at(parser.peek_fragment_or_rest_cloned()));
+                };
+
+                (None, bases, semi.fragment)
+            }
+
+            Some(TokenTy::LeftParen) => {
+                parser.advance(1);
+                parser.consume_optional_whitespace();
+                let param = FuncParam::parse(parser)?;
+                parser.consume_optional_whitespace();
+
+                // Constraints check a single value, so there's exactly one parameter.
+                if parser.next_if_is(TokenTy::RightParen).is_none() {
+                    return Err(ParserErrorKind::UnterminatedConstraintParameter
+                        .at(parser.peek_fragment_or_rest_cloned())
+                        .with_help("constraints take exactly one parameter"));
+                }
+
+                // Parse the constraints this one builds on, if there are any.
+                let bases = match parser.matches_ignore_whitespace(&[TokenTy::Colon]) {
+                    true => {
+                        parser.consume_optional_whitespace();
+                        parser.advance(1);
+                        ConstrainedTy::parse_constraining_items(parser)?
+                    }
+
+                    false => Vec::new(),
+                };
+
+                parser.consume_optional_whitespace();
+
+                if parser.peek_variant() != Some(TokenTy::LeftCurly) {
+                    return Err(ParserErrorKind::ExpectedConstraintBody
+                        .at(parser.peek_fragment_or_rest_cloned()));
+                }
+
+                let body = Block::parse(parser)?;
+                let end = body.matching_source.clone();
+                (Some(ConstraintPredicate { param, body }), bases, end)
+            }
+
+            _ => {
+                return Err(ParserErrorKind::ExpectedConstraintDefinition
+                    .at(parser.peek_fragment_or_rest_cloned()));
+            }
+        };
+
+        Ok(ConstraintDecl {
+            matching_source: Fragment::cover(&start.start, &end),
+            visibility: start.visibility,
+            docs: start.docs,
+            name,
+            generic_params,
+            predicate,
+            bases,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::decl::{Decl, constraint::ConstraintDecl},
+        lexer::Lexer,
+        parser::{Parser, error::ParserErrorKind},
+    };
+
+    #[test]
+    fn test_predicate() {
+        let mut parser = Parser::new(Lexer::new_test(
+            "pub constraint KnownMax<T: Ord>(value: T): Small + math::Even { value <= 10 }",
+        ));
+
+        let Decl::Constraint(constraint) = Decl::parse(&mut parser).unwrap() else {
+            panic!("expected a constraint declaration");
+        };
+
+        assert!(!constraint.visibility.is_private());
+        assert_eq!(constraint.name.fragment.as_str(), "KnownMax");
+        assert_eq!(constraint.generic_params.len(), 1);
+        assert_eq!(constraint.bases.len(), 2);
+        assert_eq!(constraint.bases[1].full_path.as_str(), "math::Even");
+
+        let predicate = constraint.predicate.unwrap();
+        assert_eq!(predicate.param.matching_source.as_str(), "value: T");
+        assert_eq!(predicate.body.matching_source.as_str(), "{ value <= 10 }");
+        assert_eq!(parser.bytes_remaining(), 0);
+    }
+
+    #[test]
+    fn test_composition() {
+        let mut parser =
+            Parser::new(Lexer::new_test("constraint KnownRange = KnownMin + KnownMax;"));
+        let constraint = ConstraintDecl::parse(&mut parser).unwrap();
+        assert!(constraint.predicate.is_none());
+        assert_eq!(constraint.bases.len(), 2);
+        assert_eq!(
+            constraint.matching_source.as_str(),
+            "constraint KnownRange = KnownMin + KnownMax;"
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let cases = [
+            ("constraint Even;", ParserErrorKind::ExpectedConstraintDefinition),
+            ("constraint Even = Small", ParserErrorKind::ConstraintMustEndWithSemicolon),
+            (
+                "constraint Even(a: u8, b: u8) {}",
+                ParserErrorKind::UnterminatedConstraintParameter,
+            ),
+            ("constraint Even(a: u8);", ParserErrorKind::ExpectedConstraintBody),
+            ("constraint Even = ;", ParserErrorKind::ExpectedConstraint),
+        ];
+
+        for (source, kind) in cases {
+            let mut parser = Parser::new(Lexer::new_test(source));
+            assert_eq!(ConstraintDecl::parse(&mut parser).unwrap_err().kind, kind, "{source}");
+        }
+    }
+}
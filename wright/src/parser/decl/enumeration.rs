@@ -0,0 +1,76 @@
+//! Parser implementation for `enum` declarations.
+
+use crate::{
+    ast::{
+        decl::{enumeration::EnumDecl, repr::Repr},
+        identifier::Identifier,
+    },
+    lexer::token::TokenTy,
+    parser::{
+        Parser,
+        decl::parse_decl_start,
+        error::{ParserError, ParserErrorKind},
+    },
+    source_tracking::fragment::Fragment,
+};
+
+impl EnumDecl {
+    /// Parse an enum declaration, including any doc comments and visibility modifier in front of it.
+    pub fn parse(parser: &mut Parser) -> Result<Self, ParserError> {
+        let start =
+            parse_decl_start(parser, &[TokenTy::KwEnum], ParserErrorKind::ExpectedEnumDeclaration)?;
+
+        parser.consume_at_least_one_whitespace()?;
+        let name = Identifier::parse(parser)?;
+        parser.consume_optional_whitespace();
+        let repr = Repr::parse_optional(parser, true)?;
+        parser.consume_optional_whitespace();
+
+        if parser.next_if_is(TokenTy::LeftCurly).is_none() {
+            return Err(
+                ParserErrorKind::ExpectedVariantList.at(parser.peek_fragment_or_rest_cloned())
+            );
+        }
+
+        let (variants, close) = parser.parse_comma_separated(
+            TokenTy::RightCurly,
+            ParserErrorKind::UnterminatedVariantList,
+            Identifier::parse,
+        )?;
+
+        Ok(EnumDecl {
+            matching_source: Fragment::cover(&start.start, &close.fragment),
+            visibility: start.visibility,
+            docs: start.docs,
+            name,
+            repr,
+            variants,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::decl::{Decl, enumeration::EnumDecl},
+        lexer::Lexer,
+        parser::{Parser, error::ParserErrorKind},
+    };
+
+    #[test]
+    fn test_enum() {
+        let mut parser = Parser::new(Lexer::new_test("pub enum Color { Red, Green, Blue, }"));
+        let Decl::Enum(enumeration) = Decl::parse(&mut parser).unwrap() else {
+            panic!("expected an enum");
+        };
+
+        assert_eq!(enumeration.name.fragment.as_str(), "Color");
+        assert_eq!(enumeration.variants.len(), 3);
+        assert_eq!(enumeration.variants[2].fragment.as_str(), "Blue");
+        assert_eq!(parser.bytes_remaining(), 0);
+
+        let mut parser = Parser::new(Lexer::new_test("enum Color;"));
+        let err = EnumDecl::parse(&mut parser).unwrap_err();
+        assert_eq!(err.kind, ParserErrorKind::ExpectedVariantList);
+    }
+}
@@ -0,0 +1,194 @@
+//! Parser implementation for function declarations.
+
+use crate::{
+    ast::{
+        decl::{
+            func::{FuncDecl, FuncParam},
+            generic::{GenericParam, WherePredicate},
+        },
+        identifier::Identifier,
+        stmt::Block,
+        ty::Type,
+    },
+    lexer::token::TokenTy,
+    parser::{
+        Parser,
+        decl::parse_decl_start,
+        error::{ParserError, ParserErrorKind},
+    },
+    source_tracking::fragment::Fragment,
+};
+
+impl FuncDecl {
+    /// Parse a function declaration, including any doc comments, visibility modifier, and `pure` keyword
+    /// in front of it.
+    pub fn parse(parser: &mut Parser) -> Result<Self, ParserError> {
+        let start = parse_decl_start(
+            parser,
+            &[TokenTy::KwPure, TokenTy::KwFunc],
+            ParserErrorKind::ExpectedFunctionDeclaration,
+        )?;
+
+        let is_pure = start.keyword.variant == TokenTy::KwPure;
+
+        if is_pure {
+            parser.consume_at_least_one_whitespace()?;
+
+            if parser.next_if_is(TokenTy::KwFunc).is_none() {
+                return Err(ParserErrorKind::ExpectedFunctionDeclaration
+                    .at(parser.peek_fragment_or_rest_cloned()));
+            }
+        }
+
+        parser.consume_at_least_one_whitespace()?;
+        let name = Identifier::parse(parser)?;
+        parser.consume_optional_whitespace();
+        let generic_params = GenericParam::parse_list(parser)?;
+        parser.consume_optional_whitespace();
+
+        if parser.next_if_is(TokenTy::LeftParen).is_none() {
+            return Err(ParserErrorKind::ExpectedFunctionParameters
+                .at(parser.peek_fragment_or_rest_cloned()));
+        }
+
+        let (params, close_paren) = parser.parse_comma_separated(
+            TokenTy::RightParen,
+            ParserErrorKind::UnterminatedFunctionParameters,
+            FuncParam::parse,
+        )?;
+
+        // Parse the return type if there is one.
+        let return_ty = if parser.matches_ignore_whitespace(&[TokenTy::SingleArrow]) {
+            parser.consume_optional_whitespace();
+            parser.advance(1);
+            parser.consume_optional_whitespace();
+            Some(Type::parse(parser)?)
+        } else {
+            None
+        };
+
+        let where_clause = WherePredicate::parse_clause(parser)?;
+        parser.consume_optional_whitespace();
+
+        let signature_end = (where_clause
+            .last()
+            .map(|predicate| &predicate.matching_source))
+        .or(return_ty.as_ref().map(Type::matching_source))
+        .unwrap_or(&close_paren.fragment)
+        .clone();
+
+        let (body, end) = match parser.peek_variant() {
+            Some(TokenTy::Semi) => {
+                // SAFETY: We just peeked the semicolon.
+                let semi = unsafe { parser.next_token().unwrap_unchecked().unwrap_unchecked() };
+                (None, semi.fragment)
+            }
+
+            Some(TokenTy::LeftCurly) => {
+                let body = Block::parse(parser)?;
+                let end = body.matching_source.clone();
+                (Some(body), end)
+            }
+
+            _ => {
+                return Err(
+                    ParserErrorKind::ExpectedFunctionBody.at(parser.peek_fragment_or_rest_cloned())
+                );
+            }
+        };
+
+        debug_assert!(signature_end.range.end <= end.range.start);
+
+        Ok(FuncDecl {
+            matching_source: Fragment::cover(&start.start, &end),
+            visibility: start.visibility,
+            docs: start.docs,
+            is_pure,
+            name,
+            generic_params,
+            params,
+            return_ty,
+            where_clause,
+            body,
+        })
+    }
+}
+
+impl FuncParam {
+    /// Parse a function parameter, i.e. `x: u8`.
+    pub fn parse(parser: &mut Parser) -> Result<Self, ParserError> {
+        let name = Identifier::parse(parser)?;
+
+        if !parser.matches_ignore_whitespace(&[TokenTy::Colon]) {
+            return Err(
+                ParserErrorKind::ExpectedTypeAnnotation.at(parser.peek_fragment_or_rest_cloned())
+            );
+        }
+
+        parser.consume_optional_whitespace();
+        parser.advance(1);
+        parser.consume_optional_whitespace();
+        let ty = Type::parse(parser)?;
+
+        Ok(FuncParam {
+            matching_source: Fragment::cover(&name.fragment, ty.matching_source()),
+            name,
+            ty,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{decl::func::FuncDecl, ty::AtomicTyVariant},
+        lexer::Lexer,
+        parser::{Parser, error::ParserErrorKind},
+    };
+
+    #[test]
+    fn test_func() {
+        let mut parser = Parser::new(Lexer::new_test(
+            "pub func add<T: Add>(a: T, b: T) -> T { if a { { b } } }",
+        ));
+
+        let func = FuncDecl::parse(&mut parser).unwrap();
+        assert!(!func.is_pure);
+        assert_eq!(func.name.fragment.as_str(), "add");
+        assert_eq!(func.generic_params.len(), 1);
+        assert_eq!(func.params.len(), 2);
+        assert_eq!(func.params[1].matching_source.as_str(), "b: T");
+        assert_eq!(func.return_ty.unwrap().matching_source().as_str(), "T");
+        assert_eq!(func.body.unwrap().matching_source.as_str(), "{ if a { { b } } }");
+        assert_eq!(parser.bytes_remaining(), 0);
+    }
+
+    #[test]
+    fn test_pure_abstract_func() {
+        let mut parser = Parser::new(Lexer::new_test("pure func is_even(i: u8, ) -> bool;"));
+        let func = FuncDecl::parse(&mut parser).unwrap();
+        assert!(func.is_pure);
+        assert!(func.body.is_none());
+        assert_eq!(func.params[0].ty.downcast_primitive().unwrap().variant, AtomicTyVariant::U8);
+    }
+
+    #[test]
+    fn test_where_clause() {
+        let mut parser = Parser::new(Lexer::new_test(
+            "func area<T>(shape: @T) -> u64 where T: Shape { shape.area() }",
+        ));
+
+        let func = FuncDecl::parse(&mut parser).unwrap();
+        assert_eq!(func.where_clause.len(), 1);
+        assert_eq!(func.where_clause[0].bounds[0].full_path.as_str(), "Shape");
+        assert!(func.body.is_some());
+        assert_eq!(parser.bytes_remaining(), 0);
+    }
+
+    #[test]
+    fn test_unterminated_body() {
+        let mut parser = Parser::new(Lexer::new_test("func main() { {}"));
+        let err = FuncDecl::parse(&mut parser).unwrap_err();
+        assert_eq!(err.kind, ParserErrorKind::UnterminatedBlock);
+    }
+}
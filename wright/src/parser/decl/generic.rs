@@ -0,0 +1,173 @@
+//! Parsing for generic parameters on declarations.
+
+use crate::{
+    ast::{
+        decl::generic::{GenericParam, WherePredicate},
+        identifier::Identifier,
+        path::Path,
+        ty::Type,
+    },
+    lexer::token::TokenTy,
+    parser::{
+        Parser,
+        error::{ParserError, ParserErrorKind},
+    },
+    source_tracking::fragment::Fragment,
+};
+
+impl GenericParam {
+    /// Parse a single generic parameter, i.e. `T` or `T: cmp::Ord + Clone`.
+    pub fn parse(parser: &mut Parser) -> Result<Self, ParserError> {
+        let name = Identifier::parse(parser)?;
+
+        let bounds = match parser.matches_ignore_whitespace(&[TokenTy::Colon]) {
+            true => parse_bounds(parser)?,
+            false => Vec::new(),
+        };
+
+        let matching_source = match bounds.last() {
+            Some(last) => Fragment::cover(&name.fragment, &last.full_path),
+            None => name.fragment.clone(),
+        };
+
+        Ok(GenericParam {
+            matching_source,
+            name,
+            bounds,
+        })
+    }
+
+    /// Parse an optional list of generic parameters, i.e. `<T, U: Clone>`. If the next token is not a `<`,
+    /// an empty list is returned and the parser is not advanced.
+    pub fn parse_list(parser: &mut Parser) -> Result<Vec<Self>, ParserError> {
+        if parser.next_if_is(TokenTy::Lt).is_none() {
+            return Ok(Vec::new());
+        }
+
+        parser
+            .parse_comma_separated(
+                TokenTy::Gt,
+                ParserErrorKind::UnterminatedGenericParameters,
+                GenericParam::parse,
+            )
+            .map(|(params, _)| params)
+    }
+}
+
+impl WherePredicate {
+    /// Parse a single predicate of a `where` clause, i.e. `T: Shape` or `Box<T>: Shape + Clone`.
+    pub fn parse(parser: &mut Parser) -> Result<Self, ParserError> {
+        let ty = Type::parse(parser)?;
+
+        if !parser.matches_ignore_whitespace(&[TokenTy::Colon]) {
+            return Err(
+                ParserErrorKind::ExpectedTraitBounds.at(parser.peek_fragment_or_rest_cloned())
+            );
+        }
+
+        let bounds = parse_bounds(parser)?;
+
+        Ok(WherePredicate {
+            // SAFETY: There's always at least one bound.
+            matching_source: Fragment::cover(
+                ty.matching_source(),
+                &bounds.last().unwrap().full_path,
+            ),
+            ty,
+            bounds,
+        })
+    }
+
+    /// Parse an optional `where` clause, i.e. `where T: Shape, U: Clone`. If the next token (ignoring whitespace) is
+    /// not `where`, an empty list is returned and the parser is not advanced.
+    pub fn parse_clause(parser: &mut Parser) -> Result<Vec<Self>, ParserError> {
+        if !parser.matches_ignore_whitespace(&[TokenTy::KwWhere]) {
+            return Ok(Vec::new());
+        }
+
+        parser.consume_optional_whitespace();
+        parser.advance(1);
+        let mut predicates = Vec::new();
+
+        loop {
+            parser.consume_at_least_one_whitespace()?;
+            predicates.push(WherePredicate::parse(parser)?);
+
+            if !parser.matches_ignore_whitespace(&[TokenTy::Comma]) {
+                return Ok(predicates);
+            }
+
+            parser.consume_optional_whitespace();
+            parser.advance(1);
+
+            // Allow a trailing comma before the body.
+            if parser.matches_ignore_whitespace(&[TokenTy::LeftCurly])
+                || parser.matches_ignore_whitespace(&[TokenTy::Semi])
+            {
+                return Ok(predicates);
+            }
+        }
+    }
+}
+
+/// Parse the bounds after a `:`, i.e. `: cmp::Ord + Clone`. The next token (ignoring whitespace) must be the `:`.
+fn parse_bounds(parser: &mut Parser) -> Result<Vec<Path>, ParserError> {
+    parser.consume_optional_whitespace();
+    parser.advance(1);
+    let mut bounds = Vec::new();
+
+    loop {
+        parser.consume_optional_whitespace();
+        bounds.push(Path::parse(parser)?);
+
+        if !parser.matches_ignore_whitespace(&[TokenTy::Plus]) {
+            return Ok(bounds);
+        }
+
+        parser.consume_optional_whitespace();
+        parser.advance(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::decl::generic::{GenericParam, WherePredicate},
+        lexer::Lexer,
+        parser::{Parser, error::ParserErrorKind},
+    };
+
+    #[test]
+    fn test_generic_params() {
+        let mut parser = Parser::new(Lexer::new_test("<T, U: cmp::Ord + Clone,>"));
+        let params = GenericParam::parse_list(&mut parser).unwrap();
+        assert_eq!(params.len(), 2);
+        assert_eq!(params[0].name.fragment.as_str(), "T");
+        assert!(params[0].bounds.is_empty());
+        assert_eq!(params[1].bounds[0].full_path.as_str(), "cmp::Ord");
+        assert_eq!(params[1].matching_source.as_str(), "U: cmp::Ord + Clone");
+        assert_eq!(parser.bytes_remaining(), 0);
+    }
+
+    #[test]
+    fn test_where_clause() {
+        let mut parser = Parser::new(Lexer::new_test("where T: Shape, Box<T>: Shape + Clone, {"));
+        let predicates = WherePredicate::parse_clause(&mut parser).unwrap();
+        assert_eq!(predicates.len(), 2);
+        assert_eq!(predicates[0].matching_source.as_str(), "T: Shape");
+        assert_eq!(predicates[1].ty.matching_source().as_str(), "Box<T>");
+        assert_eq!(predicates[1].bounds[1].full_path.as_str(), "Clone");
+        assert_eq!(parser.bytes_remaining(), 2);
+
+        let mut parser = Parser::new(Lexer::new_test("{"));
+        assert!(
+            WherePredicate::parse_clause(&mut parser)
+                .unwrap()
+                .is_empty()
+        );
+
+        let mut parser = Parser::new(Lexer::new_test("where T {"));
+        let err = WherePredicate::parse_clause(&mut parser).unwrap_err();
+        assert_eq!(err.kind, ParserErrorKind::ExpectedTraitBounds);
+    }
+}
@@ -0,0 +1,124 @@
+//! Parser implementation for `impl` blocks.
+
+use crate::{
+    ast::{
+        decl::{
+            generic::{GenericParam, WherePredicate},
+            implementation::ImplDecl,
+        },
+        ty::Type,
+    },
+    lexer::token::TokenTy,
+    parser::{
+        Parser,
+        decl::{parse_decl_start, parse_method_list},
+        error::{ParserError, ParserErrorKind},
+    },
+    source_tracking::fragment::Fragment,
+};
+
+impl ImplDecl {
+    /// Parse an `impl` block, including any doc comments and visibility modifier in front of it.
+    pub fn parse(parser: &mut Parser) -> Result<Self, ParserError> {
+        let start =
+            parse_decl_start(parser, &[TokenTy::KwImpl], ParserErrorKind::ExpectedImplDeclaration)?;
+
+        parser.consume_optional_whitespace();
+        let generic_params = GenericParam::parse_list(parser)?;
+        parser.consume_optional_whitespace();
+        let first_ty = Type::parse(parser)?;
+
+        // The first type is the trait if it's followed by `for`.
+        let (trait_path, self_ty) = match parser.matches_ignore_whitespace(&[TokenTy::KwFor]) {
+            false => (None, first_ty),
+
+            true => {
+                let Type::Named(named) = first_ty else {
+                    return Err(ParserErrorKind::ExpectedPath
+                        .at(first_ty.matching_source().clone())
+                        .with_help("only traits can be implemented for a type"));
+                };
+
+                if !named.generic_tys.is_empty() {
+                    return Err(ParserErrorKind::ExpectedPath
+                        .at(named.matching_source)
+                        .with_help("traits don't take generic arguments"));
+                }
+
+                parser.consume_optional_whitespace();
+                parser.advance(1);
+                parser.consume_at_least_one_whitespace()?;
+                (Some(named.name), Type::parse(parser)?)
+            }
+        };
+
+        let header = Fragment::cover(&start.keyword.fragment, self_ty.matching_source());
+        let where_clause = WherePredicate::parse_clause(parser)?;
+        parser.consume_optional_whitespace();
+        let (methods, close) = parse_method_list(parser)?;
+
+        Ok(ImplDecl {
+            matching_source: Fragment::cover(&start.start, &close.fragment),
+            visibility: start.visibility,
+            docs: start.docs,
+            header,
+            generic_params,
+            trait_path,
+            self_ty,
+            where_clause,
+            methods,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::decl::{Decl, implementation::ImplDecl},
+        lexer::Lexer,
+        parser::{Parser, error::ParserErrorKind},
+    };
+
+    #[test]
+    fn test_inherent_impl() {
+        let mut parser = Parser::new(Lexer::new_test(
+            "impl Point {\n    func sum(self: @Point) -> u64 { self.x + self.y }\n}",
+        ));
+
+        let Decl::Impl(implementation) = Decl::parse(&mut parser).unwrap() else {
+            panic!("expected an impl block");
+        };
+
+        assert!(implementation.trait_path.is_none());
+        assert_eq!(implementation.header.as_str(), "impl Point");
+        assert_eq!(implementation.methods.len(), 1);
+        assert_eq!(parser.bytes_remaining(), 0);
+    }
+
+    #[test]
+    fn test_trait_impl() {
+        let mut parser = Parser::new(Lexer::new_test(
+            "impl<T> shapes::Shape for Box<T> where T: shapes::Shape {}",
+        ));
+
+        let implementation = ImplDecl::parse(&mut parser).unwrap();
+        assert_eq!(implementation.header.as_str(), "impl<T> shapes::Shape for Box<T>");
+        assert_eq!(implementation.generic_params.len(), 1);
+        assert_eq!(implementation.trait_path.unwrap().full_path.as_str(), "shapes::Shape");
+        assert_eq!(implementation.self_ty.matching_source().as_str(), "Box<T>");
+        assert_eq!(implementation.where_clause.len(), 1);
+        assert!(implementation.methods.is_empty());
+        assert_eq!(parser.bytes_remaining(), 0);
+    }
+
+    #[test]
+    fn test_bad_impls() {
+        let mut parser = Parser::new(Lexer::new_test("impl @Shape for Point {}"));
+        let err = ImplDecl::parse(&mut parser).unwrap_err();
+        assert_eq!(err.kind, ParserErrorKind::ExpectedPath);
+
+        let mut parser = Parser::new(Lexer::new_test("impl Point"));
+        let err = ImplDecl::parse(&mut parser).unwrap_err();
+        assert_eq!(err.kind, ParserErrorKind::ExpectedMethodList);
+    }
+}
@@ -2,22 +2,26 @@
 
 use crate::{
     ast::{decl::import::ImportDecl, identifier::Identifier, path::Path},
-    lexer::token::{Token, TokenTy},
+    lexer::token::TokenTy,
     parser::{
         Parser,
+        decl::parse_decl_start,
         error::{ParserError, ParserErrorKind},
     },
     source_tracking::fragment::Fragment,
 };
 
 impl ImportDecl {
-    /// Parse an import declaration.
+    /// Parse an import declaration, including any doc comments and visibility modifier
+    /// in front of it.
     ///
-    /// This will advance the parser if `use` is seen -- if a valid formed import does not follow,
+    /// This will advance the parser if `pub` or `use` is seen -- if a valid formed import does not follow,
     /// the parser may be left in the middle of a malformed declaration.
     pub fn parse(parser: &mut Parser) -> Result<Self, ParserError> {
-        let use_kw: Token = parser.next_if_is(TokenTy::KwUse).ok_or(
-            ParserErrorKind::ExpectedImportDeclaration.at(parser.peek_fragment_or_rest_cloned()),
+        let start = parse_decl_start(
+            parser,
+            &[TokenTy::KwUse],
+            ParserErrorKind::ExpectedImportDeclaration,
         )?;
 
         // Require a whitespace after the keyword.
@@ -63,7 +67,9 @@ impl ImportDecl {
 
         if let Some(semi) = parser.next_if_is(TokenTy::Semi) {
             Ok(ImportDecl {
-                matching_source: Fragment::cover(&use_kw.fragment, &semi.fragment),
+                matching_source: Fragment::cover(&start.start, &semi.fragment),
+                visibility: start.visibility,
+                docs: start.docs,
                 imported_item: path,
                 imported_as,
             })
@@ -76,7 +82,11 @@ impl ImportDecl {
 
 #[cfg(test)]
 mod tests {
-    use crate::{ast::decl::import::ImportDecl, lexer::Lexer, parser::Parser};
+    use crate::{
+        ast::decl::{Visibility, import::ImportDecl},
+        lexer::Lexer,
+        parser::Parser,
+    };
 
     #[test]
     fn test_import() {
@@ -125,4 +135,13 @@ mod tests {
         assert_eq!(import_decl.imported_item.tail[0].fragment.as_str(), "util");
         assert_eq!(import_decl.imported_as.unwrap().fragment.as_str(), "u");
     }
+
+    #[test]
+    fn test_pub_import() {
+        let mut parser = Parser::new(Lexer::new_test("pub use wright::util;"));
+        let import_decl = ImportDecl::parse(&mut parser).unwrap();
+        assert!(parser.lexer.remaining.is_empty());
+        assert!(matches!(import_decl.visibility, Visibility::Public { .. }));
+        assert_eq!(import_decl.matching_source.as_str(), "pub use wright::util;");
+    }
 }
@@ -0,0 +1,95 @@
+//! Parser implementation for `mod name;` and `mod name { ... }` declarations.
+
+use crate::{
+    ast::{decl::module::ModuleDecl, identifier::Identifier, module::Module},
+    lexer::token::TokenTy,
+    parser::{
+        Parser,
+        decl::parse_decl_start,
+        error::{ParserError, ParserErrorKind},
+    },
+    source_tracking::fragment::Fragment,
+};
+
+impl ModuleDecl {
+    /// Parse a module declaration, including any doc comments and visibility modifier in front of it.
+    ///
+    /// Both `mod name;` (for modules in other files) and inline `mod name { ... }` declarations are accepted.
+    pub fn parse(parser: &mut Parser) -> Result<Self, ParserError> {
+        let start = parse_decl_start(
+            parser,
+            &[TokenTy::KwMod],
+            ParserErrorKind::ExpectedModuleDeclaration,
+        )?;
+
+        parser.consume_at_least_one_whitespace()?;
+        let name = Identifier::parse(parser)?;
+        parser.consume_optional_whitespace();
+
+        if let Some(semi) = parser.next_if_is(TokenTy::Semi) {
+            return Ok(ModuleDecl {
+                matching_source: Fragment::cover(&start.start, &semi.fragment),
+                visibility: start.visibility,
+                docs: start.docs,
+                name,
+                body: None,
+            });
+        }
+
+        let body = Module::parse_inline(parser)?;
+
+        Ok(ModuleDecl {
+            matching_source: Fragment::cover(&start.start, &body.matching_source),
+            visibility: start.visibility,
+            docs: start.docs,
+            name,
+            body: Some(body),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::decl::{Decl, module::ModuleDecl},
+        lexer::Lexer,
+        parser::{Parser, error::ParserErrorKind},
+    };
+
+    #[test]
+    fn test_external_module() {
+        let mut parser = Parser::new(Lexer::new_test("pub mod net;"));
+        let decl = ModuleDecl::parse(&mut parser).unwrap();
+        assert!(decl.is_external());
+        assert_eq!(decl.name.fragment.as_str(), "net");
+        assert!(!decl.visibility.is_private());
+        assert_eq!(parser.bytes_remaining(), 0);
+    }
+
+    #[test]
+    fn test_inline_module() {
+        let mut parser = Parser::new(Lexer::new_test(
+            "mod math {\n    //! Math.\n    pub type Byte = u8;\n    mod inner {}\n}",
+        ));
+
+        let decl = ModuleDecl::parse(&mut parser).unwrap();
+        let body = decl.body.unwrap();
+        assert_eq!(body.docs.text(), "Math.");
+        assert_eq!(body.items.len(), 2);
+        assert!(
+            matches!(&body.items[1], Decl::Module(inner) if inner.body.as_ref().unwrap().items.is_empty())
+        );
+        assert_eq!(parser.bytes_remaining(), 0);
+    }
+
+    #[test]
+    fn test_bad_module_decls() {
+        let mut parser = Parser::new(Lexer::new_test("mod net"));
+        let err = ModuleDecl::parse(&mut parser).unwrap_err();
+        assert_eq!(err.kind, ParserErrorKind::ExpectedModuleBody);
+
+        let mut parser = Parser::new(Lexer::new_test("mod net { type A;"));
+        let err = ModuleDecl::parse(&mut parser).unwrap_err();
+        assert_eq!(err.kind, ParserErrorKind::UnterminatedModuleBody);
+    }
+}
@@ -0,0 +1,129 @@
+//! Parser implementation for `record` declarations, and the field lists shared with `union` declarations.
+
+use crate::{
+    ast::{
+        decl::{
+            generic::GenericParam,
+            record::{FieldDecl, RecordDecl},
+            repr::Repr,
+        },
+        identifier::Identifier,
+        ty::Type,
+    },
+    lexer::token::{Token, TokenTy},
+    parser::{
+        Parser,
+        decl::parse_decl_start,
+        error::{ParserError, ParserErrorKind},
+    },
+    source_tracking::fragment::Fragment,
+};
+
+impl RecordDecl {
+    /// Parse a record declaration, including any doc comments and visibility modifier in front of it.
+    pub fn parse(parser: &mut Parser) -> Result<Self, ParserError> {
+        let start = parse_decl_start(
+            parser,
+            &[TokenTy::KwRecord],
+            ParserErrorKind::ExpectedRecordDeclaration,
+        )?;
+
+        parser.consume_at_least_one_whitespace()?;
+        let name = Identifier::parse(parser)?;
+        parser.consume_optional_whitespace();
+        let generic_params = GenericParam::parse_list(parser)?;
+        parser.consume_optional_whitespace();
+        let repr = Repr::parse_optional(parser, false)?;
+        parser.consume_optional_whitespace();
+        let (fields, close) = FieldDecl::parse_list(parser)?;
+
+        Ok(RecordDecl {
+            matching_source: Fragment::cover(&start.start, &close.fragment),
+            visibility: start.visibility,
+            docs: start.docs,
+            name,
+            generic_params,
+            repr,
+            fields,
+        })
+    }
+}
+
+impl FieldDecl {
+    /// Parse a single field, i.e. `x: u8`.
+    pub fn parse(parser: &mut Parser) -> Result<Self, ParserError> {
+        let name = Identifier::parse(parser)?;
+
+        if !parser.matches_ignore_whitespace(&[TokenTy::Colon]) {
+            return Err(
+                ParserErrorKind::ExpectedTypeAnnotation.at(parser.peek_fragment_or_rest_cloned())
+            );
+        }
+
+        parser.consume_optional_whitespace();
+        parser.advance(1);
+        parser.consume_optional_whitespace();
+        let ty = Type::parse(parser)?;
+
+        Ok(FieldDecl {
+            matching_source: Fragment::cover(&name.fragment, ty.matching_source()),
+            name,
+            ty,
+        })
+    }
+
+    /// Parse a comma separated list of fields in curly braces, i.e. `{ x: u8, y: u8 }`. Returns the fields and the
+    /// closing curly brace.
+    pub(crate) fn parse_list(parser: &mut Parser) -> Result<(Vec<Self>, Token), ParserError> {
+        if parser.next_if_is(TokenTy::LeftCurly).is_none() {
+            return Err(
+                ParserErrorKind::ExpectedFieldList.at(parser.peek_fragment_or_rest_cloned())
+            );
+        }
+
+        parser.parse_comma_separated(
+            TokenTy::RightCurly,
+            ParserErrorKind::UnterminatedFieldList,
+            FieldDecl::parse,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::decl::record::RecordDecl,
+        lexer::Lexer,
+        parser::{Parser, error::ParserErrorKind},
+    };
+
+    #[test]
+    fn test_record() {
+        let mut parser =
+            Parser::new(Lexer::new_test("pub record Pair<T> {\n    a: T,\n    b: @u8,\n}"));
+        let record = RecordDecl::parse(&mut parser).unwrap();
+        assert_eq!(record.name.fragment.as_str(), "Pair");
+        assert_eq!(record.generic_params.len(), 1);
+        assert_eq!(record.fields.len(), 2);
+        assert_eq!(record.fields[1].matching_source.as_str(), "b: @u8");
+        assert_eq!(parser.bytes_remaining(), 0);
+    }
+
+    #[test]
+    fn test_unterminated_record() {
+        let mut parser = Parser::new(Lexer::new_test("record A { a: u8 b: u8 }"));
+        let err = RecordDecl::parse(&mut parser).unwrap_err();
+        assert_eq!(err.kind, ParserErrorKind::UnterminatedFieldList);
+    }
+
+    #[test]
+    fn test_record_repr() {
+        let mut parser = Parser::new(Lexer::new_test("record Header repr(packed) { tag: u8 }"));
+        let record = RecordDecl::parse(&mut parser).unwrap();
+        assert!(record.repr.unwrap().is_packed());
+
+        let mut parser = Parser::new(Lexer::new_test("record Header repr(u8) { tag: u8 }"));
+        let err = RecordDecl::parse(&mut parser).unwrap_err();
+        assert_eq!(err.kind, ParserErrorKind::ReprHintNotAllowed);
+    }
+}
@@ -0,0 +1,165 @@
+//! Parser implementation for the `repr(...)` clauses of records, unions, and enums.
+
+use crate::{
+    ast::{
+        decl::repr::{Repr, ReprHint, ReprHintKind},
+        ty::AtomicTy,
+    },
+    lexer::token::TokenTy,
+    parser::{
+        Parser,
+        error::{ParserError, ParserErrorKind},
+    },
+    source_tracking::fragment::Fragment,
+};
+
+impl Repr {
+    /// Parse a `repr(...)` clause if the next token is `repr`, or return [None] without advancing the parser.
+    ///
+    /// Integer hints are only accepted if `allows_int` is set (for unions and enums, which have a tag), and a clause
+    /// can't have two different integer hints.
+    pub fn parse_optional(
+        parser: &mut Parser,
+        allows_int: bool,
+    ) -> Result<Option<Self>, ParserError> {
+        let Some(repr_kw) = parser.next_if_is(TokenTy::KwRepr) else {
+            return Ok(None);
+        };
+
+        parser.consume_optional_whitespace();
+
+        if parser.next_if_is(TokenTy::LeftParen).is_none() {
+            return Err(
+                ParserErrorKind::ExpectedReprHints.at(parser.peek_fragment_or_rest_cloned())
+            );
+        }
+
+        let (hints, close) = parser.parse_comma_separated(
+            TokenTy::RightParen,
+            ParserErrorKind::UnterminatedReprHints,
+            ReprHint::parse,
+        )?;
+
+        for (index, hint) in hints.iter().enumerate() {
+            let ReprHintKind::Int(variant) = hint.kind else {
+                continue;
+            };
+
+            if !allows_int {
+                return Err(ParserErrorKind::ReprHintNotAllowed
+                    .at(hint.matching_source.clone())
+                    .with_help("only unions and enums have a tag to give an integer type"));
+            }
+
+            let conflicts = (hints[..index].iter())
+                .any(|other| matches!(other.kind, ReprHintKind::Int(other) if other != variant));
+
+            if conflicts {
+                return Err(ParserErrorKind::ConflictingReprHints
+                    .at(hint.matching_source.clone())
+                    .with_help("the tag can only have one integer type"));
+            }
+        }
+
+        Ok(Some(Repr {
+            matching_source: Fragment::cover(&repr_kw.fragment, &close.fragment),
+            hints,
+        }))
+    }
+}
+
+impl ReprHint {
+    /// Parse a single hint -- `c`, `packed`, or an integer type.
+    pub fn parse(parser: &mut Parser) -> Result<Self, ParserError> {
+        if let Ok(atomic) = AtomicTy::parse(parser) {
+            if !atomic.variant.is_integer() {
+                return Err(ParserErrorKind::ExpectedReprHint
+                    .at(atomic.matching_source)
+                    .with_help("the tag of a union or enum must have an integer type"));
+            }
+
+            return Ok(ReprHint {
+                matching_source: atomic.matching_source,
+                kind: ReprHintKind::Int(atomic.variant),
+            });
+        }
+
+        let kind = match parser
+            .peek()
+            .map(|token| (token.variant, token.fragment.as_str()))
+        {
+            Some((TokenTy::Identifier, "c")) => ReprHintKind::C,
+            Some((TokenTy::Identifier, "packed")) => ReprHintKind::Packed,
+            _ => {
+                return Err(
+                    ParserErrorKind::ExpectedReprHint.at(parser.peek_fragment_or_rest_cloned())
+                );
+            }
+        };
+
+        // SAFETY: We just peeked an identifier, which is never an unknown token.
+        let token = unsafe { parser.next_token().unwrap_unchecked().unwrap_unchecked() };
+
+        Ok(ReprHint {
+            matching_source: token.fragment,
+            kind,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{
+            decl::repr::{Repr, ReprHintKind},
+            ty::AtomicTyVariant,
+        },
+        lexer::Lexer,
+        parser::{Parser, error::ParserErrorKind},
+    };
+
+    #[test]
+    fn test_repr() {
+        let mut parser = Parser::new(Lexer::new_test("repr(c, packed, u8)"));
+        let repr = Repr::parse_optional(&mut parser, true).unwrap().unwrap();
+        assert_eq!(repr.matching_source.as_str(), "repr(c, packed, u8)");
+
+        let kinds: Vec<_> = repr.hints.iter().map(|hint| hint.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                ReprHintKind::C,
+                ReprHintKind::Packed,
+                ReprHintKind::Int(AtomicTyVariant::U8)
+            ]
+        );
+
+        assert!(repr.is_c() && repr.is_packed());
+        assert_eq!(repr.int(), Some(AtomicTyVariant::U8));
+        assert_eq!(parser.bytes_remaining(), 0);
+    }
+
+    #[test]
+    fn test_no_repr() {
+        let mut parser = Parser::new(Lexer::new_test("{ a: u8 }"));
+        assert!(Repr::parse_optional(&mut parser, true).unwrap().is_none());
+        assert_eq!(parser.bytes_remaining(), "{ a: u8 }".len());
+    }
+
+    #[test]
+    fn test_bad_hints() {
+        let error = |source, allows_int| {
+            let mut parser = Parser::new(Lexer::new_test(source));
+            Repr::parse_optional(&mut parser, allows_int)
+                .unwrap_err()
+                .kind
+        };
+
+        assert_eq!(error("repr(rust)", true), ParserErrorKind::ExpectedReprHint);
+        assert_eq!(error("repr(f32)", true), ParserErrorKind::ExpectedReprHint);
+        assert_eq!(error("repr(u8)", false), ParserErrorKind::ReprHintNotAllowed);
+        assert_eq!(error("repr(u8, i16)", true), ParserErrorKind::ConflictingReprHints);
+        assert_eq!(error("repr(c", true), ParserErrorKind::UnterminatedReprHints);
+        assert_eq!(error("repr c", true), ParserErrorKind::ExpectedReprHints);
+    }
+}
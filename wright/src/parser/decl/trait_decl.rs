@@ -0,0 +1,79 @@
+//! Parser implementation for `trait` declarations.
+
+use crate::{
+    ast::{decl::trait_decl::TraitDecl, identifier::Identifier},
+    lexer::token::TokenTy,
+    parser::{
+        Parser,
+        decl::{parse_decl_start, parse_method_list},
+        error::{ParserError, ParserErrorKind},
+    },
+    source_tracking::fragment::Fragment,
+};
+
+impl TraitDecl {
+    /// Parse a trait declaration, including any doc comments and visibility modifier in front of it.
+    pub fn parse(parser: &mut Parser) -> Result<Self, ParserError> {
+        let start = parse_decl_start(
+            parser,
+            &[TokenTy::KwTrait],
+            ParserErrorKind::ExpectedTraitDeclaration,
+        )?;
+
+        parser.consume_at_least_one_whitespace()?;
+        let name = Identifier::parse(parser)?;
+        parser.consume_optional_whitespace();
+        let (methods, close) = parse_method_list(parser)?;
+
+        Ok(TraitDecl {
+            matching_source: Fragment::cover(&start.start, &close.fragment),
+            visibility: start.visibility,
+            docs: start.docs,
+            name,
+            methods,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::decl::{Decl, trait_decl::TraitDecl},
+        lexer::Lexer,
+        parser::{Parser, error::ParserErrorKind},
+    };
+
+    #[test]
+    fn test_trait() {
+        let mut parser = Parser::new(Lexer::new_test(
+            "pub trait Shape {\n    /// The area.\n    func area(self: @Self) -> u64;\n\n    func double(self: @Self) -> u64 { self.area() * 2 }\n}",
+        ));
+
+        let Decl::Trait(trait_decl) = Decl::parse(&mut parser).unwrap() else {
+            panic!("expected a trait");
+        };
+
+        assert_eq!(trait_decl.name.fragment.as_str(), "Shape");
+        assert_eq!(trait_decl.methods.len(), 2);
+        assert_eq!(trait_decl.methods[0].docs.text(), "The area.");
+        assert!(trait_decl.methods[0].body.is_none());
+        assert!(trait_decl.methods[1].body.is_some());
+        assert_eq!(parser.bytes_remaining(), 0);
+    }
+
+    #[test]
+    fn test_bad_traits() {
+        let mut parser = Parser::new(Lexer::new_test("trait Shape;"));
+        let err = TraitDecl::parse(&mut parser).unwrap_err();
+        assert_eq!(err.kind, ParserErrorKind::ExpectedMethodList);
+
+        let mut parser =
+            Parser::new(Lexer::new_test("trait Shape { func area(self: @Self) -> u64;"));
+        let err = TraitDecl::parse(&mut parser).unwrap_err();
+        assert_eq!(err.kind, ParserErrorKind::UnterminatedMethodList);
+
+        let mut parser = Parser::new(Lexer::new_test("trait Shape { type Area = u64; }"));
+        let err = TraitDecl::parse(&mut parser).unwrap_err();
+        assert_eq!(err.kind, ParserErrorKind::ExpectedFunctionDeclaration);
+    }
+}
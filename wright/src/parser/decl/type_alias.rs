@@ -0,0 +1,97 @@
+//! Parser implementation for `type Name = Type;` declarations.
+
+use crate::{
+    ast::{decl::type_alias::TypeAlias, identifier::Identifier, ty::Type},
+    lexer::token::TokenTy,
+    parser::{
+        Parser,
+        decl::parse_decl_start,
+        error::{ParserError, ParserErrorKind},
+    },
+    source_tracking::fragment::Fragment,
+};
+
+impl TypeAlias {
+    /// Parse a type alias declaration, including any doc comments and visibility modifier
+    /// in front of it.
+    ///
+    /// Both `type Name = Type;` and abstract `type Name;` declarations are accepted.
+    pub fn parse(parser: &mut Parser) -> Result<Self, ParserError> {
+        let start = parse_decl_start(
+            parser,
+            &[TokenTy::KwType],
+            ParserErrorKind::ExpectedTypeAliasDeclaration,
+        )?;
+
+        parser.consume_at_least_one_whitespace()?;
+        let new_type_name = Identifier::parse(parser)?;
+        parser.consume_optional_whitespace();
+
+        // Parse the aliased type if there is one.
+        let target_type = match parser.next_if_is(TokenTy::Eq) {
+            Some(_) => {
+                parser.consume_optional_whitespace();
+                let target_type = Type::parse(parser)?;
+                parser.consume_optional_whitespace();
+                Some(target_type)
+            }
+
+            None => None,
+        };
+
+        match parser.next_if_is(TokenTy::Semi) {
+            Some(semi) => Ok(TypeAlias {
+                matching_source: Fragment::cover(&start.start, &semi.fragment),
+                visibility: start.visibility,
+                docs: start.docs,
+                new_type_name,
+                target_type,
+            }),
+
+            None => Err(ParserErrorKind::TypeAliasMustEndWithSemicolon
+                .at(parser.peek_fragment_or_rest_cloned())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{decl::type_alias::TypeAlias, ty::AtomicTyVariant},
+        lexer::Lexer,
+        parser::{Parser, error::ParserErrorKind},
+    };
+
+    #[test]
+    fn test_type_alias() {
+        let mut parser = Parser::new(Lexer::new_test("type Byte = u8;"));
+        let alias = TypeAlias::parse(&mut parser).unwrap();
+        assert!(alias.visibility.is_private());
+        assert_eq!(alias.new_type_name.fragment.as_str(), "Byte");
+        assert_eq!(
+            alias
+                .target_type
+                .unwrap()
+                .downcast_primitive()
+                .unwrap()
+                .variant,
+            AtomicTyVariant::U8
+        );
+        assert_eq!(parser.bytes_remaining(), 0);
+    }
+
+    #[test]
+    fn test_abstract_type_alias() {
+        let mut parser = Parser::new(Lexer::new_test("pub type Void ;"));
+        let alias = TypeAlias::parse(&mut parser).unwrap();
+        assert!(alias.target_type.is_none());
+        assert_eq!(alias.matching_source.as_str(), "pub type Void ;");
+    }
+
+    #[test]
+    fn test_type_alias_missing_semi() {
+        let mut parser = Parser::new(Lexer::new_test("type Byte = u8"));
+        let err = TypeAlias::parse(&mut parser).unwrap_err();
+        assert_eq!(err.kind, ParserErrorKind::TypeAliasMustEndWithSemicolon);
+    }
+}
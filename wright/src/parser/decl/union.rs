@@ -0,0 +1,79 @@
+//! Parser implementation for `union` declarations.
+
+use crate::{
+    ast::{
+        decl::{generic::GenericParam, record::FieldDecl, repr::Repr, union::UnionDecl},
+        identifier::Identifier,
+    },
+    lexer::token::TokenTy,
+    parser::{
+        Parser,
+        decl::parse_decl_start,
+        error::{ParserError, ParserErrorKind},
+    },
+    source_tracking::fragment::Fragment,
+};
+
+impl UnionDecl {
+    /// Parse a union declaration, including any doc comments and visibility modifier in front of it.
+    ///
+    /// Variants are written the same way as record fields, i.e. `union Number { small: u8, big: u64 }`. A
+    /// `repr(...)` clause can come after the generic parameters, and can give the tag an integer type.
+    pub fn parse(parser: &mut Parser) -> Result<Self, ParserError> {
+        let start = parse_decl_start(
+            parser,
+            &[TokenTy::KwUnion],
+            ParserErrorKind::ExpectedUnionDeclaration,
+        )?;
+
+        parser.consume_at_least_one_whitespace()?;
+        let name = Identifier::parse(parser)?;
+        parser.consume_optional_whitespace();
+        let generic_params = GenericParam::parse_list(parser)?;
+        parser.consume_optional_whitespace();
+        let repr = Repr::parse_optional(parser, true)?;
+        parser.consume_optional_whitespace();
+        let (variants, close) = FieldDecl::parse_list(parser)?;
+
+        Ok(UnionDecl {
+            matching_source: Fragment::cover(&start.start, &close.fragment),
+            visibility: start.visibility,
+            docs: start.docs,
+            name,
+            generic_params,
+            repr,
+            variants,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{decl::Decl, ty::AtomicTyVariant},
+        lexer::Lexer,
+        parser::Parser,
+    };
+
+    #[test]
+    fn test_union() {
+        let mut parser = Parser::new(Lexer::new_test("union Maybe<T> { some: T, none: bool }"));
+        let Decl::Union(union) = Decl::parse(&mut parser).unwrap() else {
+            panic!("expected a union");
+        };
+
+        assert_eq!(union.name.fragment.as_str(), "Maybe");
+        assert_eq!(union.variants[0].name.fragment.as_str(), "some");
+        assert!(union.repr.is_none());
+        assert_eq!(parser.bytes_remaining(), 0);
+
+        let mut parser = Parser::new(Lexer::new_test("union Number repr(c, u8) { small: u8 }"));
+        let Decl::Union(union) = Decl::parse(&mut parser).unwrap() else {
+            panic!("expected a union");
+        };
+
+        let repr = union.repr.unwrap();
+        assert!(repr.is_c());
+        assert_eq!(repr.int(), Some(AtomicTyVariant::U8));
+    }
+}
@@ -0,0 +1,208 @@
+//! Parsing doc comments into [Docs].
+
+use crate::{
+    ast::docs::Docs,
+    lexer::token::{Token, TokenTy},
+    parser::Parser,
+    source_tracking::fragment::Fragment,
+};
+use std::sync::Arc;
+
+/// The [TokenTy]s of outer doc comments, which document the item that follows them.
+pub const OUTER_DOC_COMMENTS: &[TokenTy] =
+    &[TokenTy::OuterDocComment, TokenTy::OuterBlockDocComment];
+
+/// The [TokenTy]s of inner doc comments, which document the module they're in.
+pub const INNER_DOC_COMMENTS: &[TokenTy] =
+    &[TokenTy::InnerDocComment, TokenTy::InnerBlockDocComment];
+
+impl Docs {
+    /// Parse any outer doc comments (`///` and `/** */`) from the [Parser], along with any whitespace between them.
+    ///
+    /// If the next token is not an outer doc comment, this leaves the [Parser] unadvanced and returns empty [Docs].
+    pub fn parse_outer(parser: &mut Parser) -> Self {
+        Docs::from_comments(&collect_doc_comments(parser, OUTER_DOC_COMMENTS))
+    }
+
+    /// Parse any inner doc comments (`//!` and `/*! */`) from the [Parser], along with any whitespace between them.
+    ///
+    /// If the next token is not an inner doc comment, this leaves the [Parser] unadvanced and returns empty [Docs].
+    pub fn parse_inner(parser: &mut Parser) -> Self {
+        Docs::from_comments(&collect_doc_comments(parser, INNER_DOC_COMMENTS))
+    }
+
+    /// Construct [Docs] from a series of doc comment [Token]s, stripping comment markers and common indentation.
+    pub fn from_comments(comments: &[Token]) -> Self {
+        let mut lines: Vec<Fragment> = Vec::new();
+
+        for comment in comments {
+            match comment.variant {
+                TokenTy::OuterDocComment | TokenTy::InnerDocComment => {
+                    // Strip the `///` or `//!` and any trailing carriage return.
+                    let (_, content) = comment.fragment.split_at(3);
+                    lines.push(content.trim_end());
+                }
+
+                TokenTy::OuterBlockDocComment | TokenTy::InnerBlockDocComment => {
+                    lines.extend(block_comment_lines(&comment.fragment));
+                }
+
+                _ => {}
+            }
+        }
+
+        // Strip the indentation that's common to every non-blank line. This is the longest run of whitespace
+        // characters that starts every one of them, so that it always ends on a character boundary.
+        let common_indent: usize = lines
+            .iter()
+            .map(Fragment::as_str)
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| &line[..line.len() - line.trim_start().len()])
+            .reduce(|common, indent| {
+                let shared: usize = (common.chars().zip(indent.chars()))
+                    .take_while(|(a, b)| a == b)
+                    .map(|(a, _)| a.len_utf8())
+                    .sum();
+
+                &common[..shared]
+            })
+            .map_or(0, str::len);
+
+        let lines = lines
+            .into_iter()
+            .map(|line| {
+                if line.as_str().trim().is_empty() {
+                    line.split_at(0).0
+                } else {
+                    line.split_at(common_indent).1
+                }
+            })
+            .collect();
+
+        Docs { lines }
+    }
+}
+
+/// Collect a series of doc comment [Token]s of the given kinds, skipping the whitespace between them.
+pub(crate) fn collect_doc_comments(parser: &mut Parser, kinds: &[TokenTy]) -> Vec<Token> {
+    let mut comments = Vec::new();
+
+    // Only chew through whitespace if it's between doc comments.
+    while parser
+        .peek_next_not_whitespace()
+        .is_some_and(|token| kinds.contains(&token.variant))
+        && (!comments.is_empty() || parser.peek_variant().is_some_and(|v| kinds.contains(&v)))
+    {
+        parser.consume_optional_whitespace();
+        // SAFETY: We just peeked a doc comment, which is never an unknown token.
+        let comment = unsafe { parser.next_token().unwrap_unchecked().unwrap_unchecked() };
+        comments.push(comment);
+    }
+
+    comments
+}
+
+/// Split the content of a block doc comment (`/** ... */` or `/*! ... */`) into lines, stripping the comment
+/// markers, any leading `*` decorations, and any blank lines at the start and end.
+fn block_comment_lines(comment: &Fragment) -> Vec<Fragment> {
+    // Strip the `/**` or `/*!` and the `*/`.
+    let content = Fragment {
+        source: Arc::clone(&comment.source),
+        range: (comment.range.start + 3)..(comment.range.end - 2),
+    };
+
+    // Split on newlines.
+    let mut lines: Vec<Fragment> = Vec::new();
+    let mut line_start: usize = content.range.start;
+
+    for (index, _) in content.as_str().match_indices('\n') {
+        let line_end = content.range.start + index;
+
+        lines.push(Fragment {
+            source: Arc::clone(&content.source),
+            range: line_start..line_end,
+        });
+
+        line_start = line_end + 1;
+    }
+
+    lines.push(Fragment {
+        source: Arc::clone(&content.source),
+        range: line_start..content.range.end,
+    });
+
+    let mut lines: Vec<Fragment> = lines.into_iter().map(Fragment::trim_end).collect();
+
+    // If every non-blank line after the first is decorated with a leading `*`, strip the decorations.
+    let is_decorated = |line: &Fragment| line.as_str().trim_start().starts_with('*');
+
+    if lines.len() > 1
+        && lines[1..]
+            .iter()
+            .filter(|line| !line.is_empty())
+            .all(is_decorated)
+    {
+        for line in lines[1..].iter_mut().filter(|line| !line.is_empty()) {
+            let decoration_end = line.len() - line.as_str().trim_start().len() + 1;
+            *line = line.split_at(decoration_end).1;
+        }
+    }
+
+    // Remove blank lines at either end.
+    while lines
+        .last()
+        .is_some_and(|line| line.as_str().trim().is_empty())
+    {
+        lines.pop();
+    }
+
+    let leading_blank_lines = lines
+        .iter()
+        .take_while(|line| line.as_str().trim().is_empty())
+        .count();
+
+    lines.split_off(leading_blank_lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ast::docs::Docs, lexer::Lexer, parser::Parser};
+
+    #[test]
+    fn test_line_doc_comments() {
+        let mut parser =
+            Parser::new(Lexer::new_test("/// First line.\n///\n///     indented\nrecord"));
+        let docs = Docs::parse_outer(&mut parser);
+        assert_eq!(docs.lines().len(), 3);
+        assert_eq!(docs.text(), "First line.\n\n    indented");
+        assert_eq!(docs.lines()[0].as_str(), "First line.");
+        // The whitespace before `record` is left alone.
+        assert_eq!(parser.bytes_remaining(), "\nrecord".len());
+    }
+
+    #[test]
+    fn test_block_doc_comment() {
+        let mut parser =
+            Parser::new(Lexer::new_test("/**\n * Summary\n * more.\n *\n * Details.\n */"));
+        let docs = Docs::parse_outer(&mut parser);
+        assert_eq!(docs.text(), "Summary\nmore.\n\nDetails.");
+        assert_eq!(docs.summary(), "Summary more.");
+    }
+
+    #[test]
+    fn test_inner_is_not_outer() {
+        let mut parser = Parser::new(Lexer::new_test("//! Module docs."));
+        assert!(Docs::parse_outer(&mut parser).is_empty());
+        assert_eq!(Docs::parse_inner(&mut parser).text(), "Module docs.");
+    }
+
+    #[test]
+    fn test_unicode_indentation() {
+        // A non-breaking space isn't shared with the other line's indentation, so nothing is stripped.
+        let mut parser = Parser::new(Lexer::new_test("///\u{a0}a\n/// b\nrecord"));
+        assert_eq!(Docs::parse_outer(&mut parser).text(), "\u{a0}a\n b");
+
+        let mut parser = Parser::new(Lexer::new_test("///\u{a0} a\n///\u{a0}  b\nrecord"));
+        assert_eq!(Docs::parse_outer(&mut parser).text(), "a\n b");
+    }
+}
@@ -12,20 +12,76 @@ use std::borrow::Cow;
 #[allow(missing_docs)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ParserErrorKind {
+    ConstraintMustEndWithSemicolon,
+    ConflictingReprHints,
     EncounteredUnknownToken,
     EncounteredUnterminatedComment,
     EncounteredUnterminatedString,
     ExpectedAtomicTypeSignature,
+    ExpectedBlock,
     ExpectedBooleanLiteral,
+    ExpectedConstraint,
+    ExpectedConstraintBody,
+    ExpectedConstraintDeclaration,
+    ExpectedConstraintDefinition,
+    ExpectedDeclaration,
+    ExpectedEnumDeclaration,
+    ExpectedExpression,
+    ExpectedFieldList,
+    ExpectedFieldName,
+    ExpectedFieldValue,
+    ExpectedFunctionBody,
+    ExpectedFunctionDeclaration,
+    ExpectedFunctionParameters,
+    ExpectedFunctionTypeSignature,
     ExpectedIdentifier,
+    ExpectedImplDeclaration,
     ExpectedImportDeclaration,
+    ExpectedInAfterForBinding,
     ExpectedIntegerLiteral,
+    ExpectedLetBindingValue,
+    ExpectedMatchArmSeparator,
+    ExpectedMatchArms,
+    ExpectedMatchArrow,
+    ExpectedMethodList,
+    ExpectedModuleBody,
+    ExpectedModuleDeclaration,
     ExpectedPath,
+    ExpectedPattern,
+    ExpectedRange,
+    ExpectedRangePatternEnd,
+    ExpectedRecordDeclaration,
     ExpectedReferenceTypeSignature,
+    ExpectedReprHint,
+    ExpectedReprHints,
+    ExpectedSemicolon,
+    ExpectedStringLiteral,
+    ExpectedTraitBounds,
+    ExpectedTraitDeclaration,
+    ExpectedTypeAliasDeclaration,
+    ExpectedTypeAnnotation,
     ExpectedTypeSignature,
+    ExpectedUnionDeclaration,
+    ExpectedVariantList,
+    ExpectedVisibilityRestriction,
     ExpectedWhitespace,
     ImportMustEndWithSemicolon,
+    LetMustEndWithSemicolon,
+    ReprHintNotAllowed,
+    TypeAliasMustEndWithSemicolon,
+    UnterminatedBlock,
+    UnterminatedCallArguments,
+    UnterminatedConstraintParameter,
+    UnterminatedFieldList,
+    UnterminatedFunctionParameters,
+    UnterminatedGenericParameters,
     UnterminatedGenericTypeSignature,
+    UnterminatedMethodList,
+    UnterminatedModuleBody,
+    UnterminatedParentheses,
+    UnterminatedReprHints,
+    UnterminatedVariantList,
+    UnterminatedVisibilityRestriction,
 }
 
 impl ParserErrorKind {
@@ -34,6 +90,8 @@ impl ParserErrorKind {
         use ParserErrorKind::*;
 
         match self {
+            ConstraintMustEndWithSemicolon => "constraint compositions must end with a semicolon",
+            ConflictingReprHints => "conflicting `repr` hints",
             EncounteredUnknownToken => "encountered unknown token",
             EncounteredUnterminatedComment => {
                 "encountered unterminated multiline comment while parsing"
@@ -42,16 +100,70 @@ impl ParserErrorKind {
                 "encountered unterminated string literal while parsing"
             }
             ExpectedAtomicTypeSignature => "expected atomic primitive type",
+            ExpectedBlock => "expected block",
             ExpectedBooleanLiteral => "expected boolean literal",
+            ExpectedConstraint => "expected path to a constraint",
+            ExpectedConstraintBody => "expected constraint body",
+            ExpectedConstraintDeclaration => "expected constraint declaration",
+            ExpectedConstraintDefinition => "expected `(` or `=` after constraint name",
+            ExpectedDeclaration => "expected declaration",
+            ExpectedEnumDeclaration => "expected enum declaration",
+            ExpectedExpression => "expected expression",
+            ExpectedFieldList => "expected a list of fields in curly braces",
+            ExpectedFieldName => "expected field name after `.`",
+            ExpectedFieldValue => "expected `:` and a value for the field",
+            ExpectedFunctionBody => "expected function body or `;`",
+            ExpectedFunctionDeclaration => "expected function declaration",
+            ExpectedFunctionParameters => "expected `(` to start function parameters",
+            ExpectedFunctionTypeSignature => "expected function type signature",
             ExpectedIdentifier => "expected identifier",
+            ExpectedImplDeclaration => "expected impl declaration",
             ExpectedImportDeclaration => "expected import declaration",
+            ExpectedInAfterForBinding => "expected `in` after the binding of a `for` loop",
             ExpectedIntegerLiteral => "expected integer literal",
+            ExpectedLetBindingValue => "expected `=` followed by a value in let binding",
+            ExpectedMatchArmSeparator => "expected `,` or `}` after match arm",
+            ExpectedMatchArms => "expected `{` to start the arms of a `match`",
+            ExpectedMatchArrow => "expected `=>` after the pattern of a match arm",
+            ExpectedMethodList => "expected method list",
+            ExpectedModuleBody => "expected `;` or a module body in curly braces",
+            ExpectedModuleDeclaration => "expected module declaration",
             ExpectedPath => "expected path or identifier",
+            ExpectedPattern => "expected pattern",
+            ExpectedRange => "expected `..` or `..=` in the range of a `for` loop",
+            ExpectedRangePatternEnd => "expected an integer literal at the end of a range pattern",
+            ExpectedRecordDeclaration => "expected record declaration",
             ExpectedReferenceTypeSignature => "expected reference type signature",
+            ExpectedReprHint => "expected `c`, `packed`, or an integer type in `repr(...)`",
+            ExpectedReprHints => "expected `(` after `repr`",
+            ExpectedSemicolon => "expected `;` after expression",
+            ExpectedStringLiteral => "expected string literal",
+            ExpectedTraitBounds => "expected trait bounds",
+            ExpectedTraitDeclaration => "expected trait declaration",
+            ExpectedTypeAliasDeclaration => "expected type alias declaration",
+            ExpectedTypeAnnotation => "expected `:` followed by a type signature",
             ExpectedTypeSignature => "expected type signature",
+            ExpectedUnionDeclaration => "expected union declaration",
+            ExpectedVariantList => "expected a list of variants in curly braces",
+            ExpectedVisibilityRestriction => "expected `mod` in visibility restriction",
             ExpectedWhitespace => "expected whitespace character(s)",
             ImportMustEndWithSemicolon => "import declarations must end with a semicolon",
+            LetMustEndWithSemicolon => "let bindings must end with a semicolon",
+            ReprHintNotAllowed => "this `repr` hint can't be used here",
+            TypeAliasMustEndWithSemicolon => "type alias declarations must end with a semicolon",
+            UnterminatedBlock => "block must end with a `}`",
+            UnterminatedCallArguments => "call arguments must end with a `)`",
+            UnterminatedConstraintParameter => "expected `)` after constraint parameter",
+            UnterminatedFieldList => "unterminated list of fields",
+            UnterminatedFunctionParameters => "function parameters must end with a `)`",
+            UnterminatedGenericParameters => "generic parameters must end with a `>`",
             UnterminatedGenericTypeSignature => "generic type signature must end with a `>`",
+            UnterminatedMethodList => "unterminated method list",
+            UnterminatedModuleBody => "unterminated module body",
+            UnterminatedParentheses => "parenthesized expression must end with a `)`",
+            UnterminatedReprHints => "`repr` hints must end with a `)`",
+            UnterminatedVariantList => "unterminated list of variants",
+            UnterminatedVisibilityRestriction => "visibility restriction must end with a `)`",
         }
     }
 
@@ -0,0 +1,317 @@
+//! Expression parsing.
+//!
+//! Binary operators are parsed using [precedence climbing], with unary prefix operators and postfix calls and field
+//! accesses binding tighter than any binary operator.
+//!
+//! [precedence climbing]: https://en.wikipedia.org/wiki/Operator-precedence_parser#Precedence_climbing_method
+
+use crate::{
+    ast::{
+        expr::{
+            BinaryExpr, BinaryOperation, CallExpr, Expr, FieldExpr, IsExpr, UnaryExpr,
+            UnaryOperation,
+        },
+        identifier::Identifier,
+        ty::Type,
+    },
+    lexer::token::TokenTy,
+    parser::{
+        Parser,
+        error::{ParserError, ParserErrorKind},
+    },
+    source_tracking::fragment::Fragment,
+};
+
+mod primary;
+
+/// Get the binary operation represented by a [TokenTy], if there is one.
+const fn binary_operation(variant: TokenTy) -> Option<BinaryOperation> {
+    use BinaryOperation::*;
+
+    Some(match variant {
+        TokenTy::Plus => Add,
+        TokenTy::Minus => Subtract,
+        TokenTy::Star => Multiply,
+        TokenTy::Div => Divide,
+        TokenTy::Mod => Modulo,
+        TokenTy::And => BitwiseAnd,
+        TokenTy::Or => BitwiseOr,
+        TokenTy::Xor => BitwiseXor,
+        TokenTy::LtLt => ShiftLeft,
+        TokenTy::GtGt => ShiftRight,
+        TokenTy::AndAnd => LogicalAnd,
+        TokenTy::OrOr => LogicalOr,
+        TokenTy::EqEq => Equal,
+        TokenTy::BangEq => NotEqual,
+        TokenTy::Lt => Less,
+        TokenTy::LtEq => LessOrEqual,
+        TokenTy::Gt => Greater,
+        TokenTy::GtEq => GreaterOrEqual,
+        _ => return None,
+    })
+}
+
+/// Get the precedence of a binary operation -- operations with higher precedence bind more tightly.
+const fn precedence(op: BinaryOperation) -> u8 {
+    use BinaryOperation::*;
+
+    match op {
+        LogicalOr => 1,
+        LogicalAnd => 2,
+        Equal | NotEqual | Less | LessOrEqual | Greater | GreaterOrEqual => 3,
+        BitwiseOr => 4,
+        BitwiseXor => 5,
+        BitwiseAnd => 6,
+        ShiftLeft | ShiftRight => 7,
+        Add | Subtract => 8,
+        Multiply | Divide | Modulo => 9,
+    }
+}
+
+/// Get the unary operation represented by a [TokenTy], if there is one.
+const fn unary_operation(variant: TokenTy) -> Option<UnaryOperation> {
+    Some(match variant {
+        TokenTy::At => UnaryOperation::Reference,
+        TokenTy::Star => UnaryOperation::Dereference,
+        TokenTy::Minus => UnaryOperation::Negate,
+        TokenTy::Bang => UnaryOperation::BooleanNot,
+        TokenTy::Tilde => UnaryOperation::BitwiseNot,
+        _ => return None,
+    })
+}
+
+impl Expr {
+    /// Parse an expression. The [Parser] is left unadvanced if there is no expression here.
+    pub fn parse(parser: &mut Parser) -> Result<Self, ParserError> {
+        parse_binary(parser, 0)
+    }
+}
+
+/// The precedence of `is` tests, which is the same as comparisons.
+const IS_PRECEDENCE: u8 = 3;
+
+/// Parse a series of binary operations (and `is` tests) that all have at least the given precedence.
+fn parse_binary(parser: &mut Parser, min_precedence: u8) -> Result<Expr, ParserError> {
+    let mut lhs = parse_unary(parser)?;
+
+    while let Some(next) = parser.peek_next_not_whitespace().map(|token| token.variant) {
+        if next == TokenTy::KwIs {
+            if IS_PRECEDENCE < min_precedence {
+                break;
+            }
+
+            lhs = parse_is(parser, lhs)?;
+            continue;
+        }
+
+        let Some(op) = binary_operation(next) else {
+            break;
+        };
+
+        let op_precedence = precedence(op);
+
+        if op_precedence < min_precedence {
+            break;
+        }
+
+        parser.consume_optional_whitespace();
+        // SAFETY: We just peeked the operator.
+        let op_token = unsafe { parser.next_token().unwrap_unchecked().unwrap_unchecked() };
+        parser.consume_optional_whitespace();
+
+        // All binary operators are left-associative.
+        let rhs = parse_binary(parser, op_precedence + 1).map_err(|err| {
+            err.with_help(format!("expected an expression after `{}`", op.symbol()))
+        })?;
+
+        lhs = Expr::Binary(BinaryExpr {
+            matching_source: Fragment::cover(lhs.matching_source(), rhs.matching_source()),
+            op,
+            op_fragment: op_token.fragment,
+            lhs: Box::new(lhs),
+            rhs: Box::new(rhs),
+        });
+    }
+
+    Ok(lhs)
+}
+
+/// Parse the `is` keyword and the type or variant after it, testing the given value.
+fn parse_is(parser: &mut Parser, value: Expr) -> Result<Expr, ParserError> {
+    parser.consume_optional_whitespace();
+    // The caller checked for the keyword.
+    parser.advance(1);
+    parser.consume_optional_whitespace();
+
+    let target = Type::parse(parser)
+        .map_err(|err| err.with_help("expected a variant or type after `is`"))?;
+
+    Ok(Expr::Is(IsExpr {
+        matching_source: Fragment::cover(value.matching_source(), target.matching_source()),
+        value: Box::new(value),
+        target,
+    }))
+}
+
+/// Parse an expression with any number of prefix unary operators.
+fn parse_unary(parser: &mut Parser) -> Result<Expr, ParserError> {
+    let Some(op) = parser.peek_variant().and_then(unary_operation) else {
+        return parse_postfix(parser);
+    };
+
+    // SAFETY: We just peeked the operator.
+    let op_token = unsafe { parser.next_token().unwrap_unchecked().unwrap_unchecked() };
+    parser.consume_optional_whitespace();
+
+    // `@mut` borrows its operand mutably.
+    let op = match op == UnaryOperation::Reference && parser.next_if_is(TokenTy::KwMut).is_some() {
+        true => {
+            parser.consume_optional_whitespace();
+            UnaryOperation::MutableReference
+        }
+
+        false => op,
+    };
+
+    let operand = parse_unary(parser)?;
+
+    Ok(Expr::Unary(UnaryExpr {
+        matching_source: Fragment::cover(&op_token.fragment, operand.matching_source()),
+        op,
+        operand: Box::new(operand),
+    }))
+}
+
+/// Parse a primary expression followed by any number of calls and field accesses.
+fn parse_postfix(parser: &mut Parser) -> Result<Expr, ParserError> {
+    let mut expr = primary::parse_primary(parser)?;
+
+    loop {
+        if parser.matches_ignore_whitespace(&[TokenTy::LeftParen]) {
+            parser.consume_optional_whitespace();
+            parser.advance(1);
+
+            let (args, close_paren) = parser.with_record_literals(true, |parser| {
+                parser.parse_comma_separated(
+                    TokenTy::RightParen,
+                    ParserErrorKind::UnterminatedCallArguments,
+                    Expr::parse,
+                )
+            })?;
+
+            expr = Expr::Call(CallExpr {
+                matching_source: Fragment::cover(expr.matching_source(), &close_paren.fragment),
+                callee: Box::new(expr),
+                args,
+            });
+        } else if parser.matches_ignore_whitespace(&[TokenTy::Dot]) {
+            parser.consume_optional_whitespace();
+            parser.advance(1);
+            parser.consume_optional_whitespace();
+
+            let field = Identifier::parse(parser).map_err(|_| {
+                ParserErrorKind::ExpectedFieldName.at(parser.peek_fragment_or_rest_cloned())
+            })?;
+
+            expr = Expr::Field(FieldExpr {
+                matching_source: Fragment::cover(expr.matching_source(), &field.fragment),
+                base: Box::new(expr),
+                field,
+            });
+        } else {
+            return Ok(expr);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::expr::{Atom, BinaryOperation, Expr, UnaryOperation},
+        lexer::Lexer,
+        parser::{Parser, error::ParserErrorKind},
+    };
+
+    fn parse(source: &'static str) -> Expr {
+        let mut parser = Parser::new(Lexer::new_test(source));
+        let expr = Expr::parse(&mut parser).unwrap();
+        assert_eq!(parser.bytes_remaining(), 0);
+        expr
+    }
+
+    #[test]
+    fn test_precedence() {
+        let Expr::Binary(or) = parse("a || b && 1 + 2 * 3 == c") else {
+            panic!("expected binary expression");
+        };
+
+        assert_eq!(or.op, BinaryOperation::LogicalOr);
+        let Expr::Binary(and) = *or.rhs else { panic!() };
+        let Expr::Binary(eq) = *and.rhs else { panic!() };
+        assert_eq!(eq.op, BinaryOperation::Equal);
+        assert_eq!(eq.lhs.matching_source().as_str(), "1 + 2 * 3");
+    }
+
+    #[test]
+    fn test_left_associative() {
+        let Expr::Binary(sub) = parse("a - b - c") else {
+            panic!()
+        };
+        assert_eq!(sub.lhs.matching_source().as_str(), "a - b");
+        assert!(matches!(*sub.rhs, Expr::Atom(Atom::Identifier(_))));
+    }
+
+    #[test]
+    fn test_unary_and_postfix() {
+        let Expr::Unary(neg) = parse("-io::read(x, @y).len") else {
+            panic!()
+        };
+        assert_eq!(neg.op, UnaryOperation::Negate);
+        let Expr::Field(field) = *neg.operand else {
+            panic!()
+        };
+        assert_eq!(field.field.fragment.as_str(), "len");
+        let Expr::Call(call) = *field.base else {
+            panic!()
+        };
+        assert!(matches!(*call.callee, Expr::Path(_)));
+        assert_eq!(call.args.len(), 2);
+
+        let Expr::Unary(borrow) = parse("@mut point.x") else {
+            panic!()
+        };
+        assert_eq!(borrow.op, UnaryOperation::MutableReference);
+        assert_eq!(borrow.operand.matching_source().as_str(), "point.x");
+    }
+
+    #[test]
+    fn test_is() {
+        let Expr::Binary(and) = parse("n is Number::small && n.small > 1 == b is bool") else {
+            panic!()
+        };
+        assert_eq!(and.op, BinaryOperation::LogicalAnd);
+
+        let Expr::Is(is_expr) = *and.lhs else {
+            panic!()
+        };
+        assert_eq!(is_expr.value.matching_source().as_str(), "n");
+        assert_eq!(is_expr.target.matching_source().as_str(), "Number::small");
+
+        // `is` has the same precedence as comparisons, and is left-associative with them.
+        let Expr::Is(is_expr) = *and.rhs else {
+            panic!()
+        };
+        assert_eq!(is_expr.value.matching_source().as_str(), "n.small > 1 == b");
+
+        let mut parser = Parser::new(Lexer::new_test("n is"));
+        let err = Expr::parse(&mut parser).unwrap_err();
+        assert_eq!(err.kind, ParserErrorKind::ExpectedTypeSignature);
+    }
+
+    #[test]
+    fn test_missing_operand() {
+        let mut parser = Parser::new(Lexer::new_test("1 +"));
+        let err = Expr::parse(&mut parser).unwrap_err();
+        assert_eq!(err.kind, ParserErrorKind::ExpectedExpression);
+    }
+}
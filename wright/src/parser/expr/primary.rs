@@ -0,0 +1,559 @@
+//! Parsing for primary expressions -- atoms, paths, parenthesized expressions, blocks, and control flow.
+
+use crate::{
+    ast::{
+        expr::{
+            Atom, ClosureExpr, ClosureParam, Expr, FieldInit, ForExpr, IfExpr, MatchArm, MatchExpr,
+            ParensExpr, RangeExpr, RecordExpr, UnsafeExpr, WhileExpr,
+        },
+        identifier::Identifier,
+        literal::{BooleanLiteral, IntegerLiteral, StringLiteral},
+        path::Path,
+        pattern::Pattern,
+        stmt::Block,
+        ty::Type,
+    },
+    lexer::token::TokenTy,
+    parser::{
+        Parser,
+        error::{ParserError, ParserErrorKind},
+    },
+    source_tracking::fragment::Fragment,
+};
+
+/// Parse a primary expression. The [Parser] is left unadvanced if there is no expression here.
+pub(super) fn parse_primary(parser: &mut Parser) -> Result<Expr, ParserError> {
+    match parser.peek_variant() {
+        Some(TokenTy::IntegerLiteral) => {
+            IntegerLiteral::parse(parser).map(|lit| Expr::Atom(Atom::IntegerLiteral(lit)))
+        }
+
+        Some(TokenTy::KwTrue | TokenTy::KwFalse) => {
+            BooleanLiteral::parse(parser).map(|lit| Expr::Atom(Atom::BooleanLiteral(lit)))
+        }
+
+        Some(TokenTy::StringLiteral { .. }) => {
+            StringLiteral::parse(parser).map(|lit| Expr::Atom(Atom::StringLiteral(lit)))
+        }
+
+        Some(TokenTy::Identifier) => {
+            let path = Path::parse(parser)?;
+
+            if at_record_literal(parser) {
+                return RecordExpr::parse_fields(parser, path).map(Expr::Record);
+            }
+
+            // Single identifiers are atoms, anything longer is a path.
+            Ok(match path.tail.is_empty() {
+                true => Expr::Atom(Atom::Identifier(path.head)),
+                false => Expr::Path(path),
+            })
+        }
+
+        Some(TokenTy::LeftParen) => parser.with_record_literals(true, parse_parens),
+        Some(TokenTy::LeftCurly) => parser
+            .with_record_literals(true, Block::parse)
+            .map(Expr::Block),
+        Some(TokenTy::KwIf) => IfExpr::parse(parser).map(Expr::If),
+        Some(TokenTy::KwMatch) => MatchExpr::parse(parser).map(Expr::Match),
+        Some(TokenTy::KwWhile) => WhileExpr::parse(parser).map(Expr::While),
+        Some(TokenTy::KwFor) => ForExpr::parse(parser).map(Expr::For),
+        Some(TokenTy::KwUnsafe) => UnsafeExpr::parse(parser).map(Expr::Unsafe),
+        Some(TokenTy::KwFunc) => ClosureExpr::parse(parser).map(Expr::Closure),
+
+        _ => Err(ParserErrorKind::ExpectedExpression.at(parser.peek_fragment_or_rest_cloned())),
+    }
+}
+
+/// Check if the tokens after a path start a record literal -- an opening curly brace followed by either a closing one
+/// or a field name and colon.
+fn at_record_literal(parser: &mut Parser) -> bool {
+    parser.record_literals_allowed()
+        && (parser.matches_ignore_whitespace(&[TokenTy::LeftCurly, TokenTy::RightCurly])
+            || parser.matches_ignore_whitespace(&[
+                TokenTy::LeftCurly,
+                TokenTy::Identifier,
+                TokenTy::Colon,
+            ]))
+}
+
+impl RecordExpr {
+    /// Parse the fields of a record literal, after its name has already been parsed.
+    fn parse_fields(parser: &mut Parser, name: Path) -> Result<Self, ParserError> {
+        parser.consume_optional_whitespace();
+        // The caller checked for the curly brace.
+        parser.advance(1);
+
+        let (fields, close) = parser.with_record_literals(true, |parser| {
+            parser.parse_comma_separated(
+                TokenTy::RightCurly,
+                ParserErrorKind::UnterminatedFieldList,
+                FieldInit::parse,
+            )
+        })?;
+
+        Ok(RecordExpr {
+            matching_source: Fragment::cover(&name.full_path, &close.fragment),
+            name,
+            fields,
+        })
+    }
+}
+
+impl FieldInit {
+    /// Parse the value given to a field in a record literal, i.e. `x: 1`.
+    pub fn parse(parser: &mut Parser) -> Result<Self, ParserError> {
+        let name = Identifier::parse(parser)?;
+
+        if !parser.matches_ignore_whitespace(&[TokenTy::Colon]) {
+            return Err(
+                ParserErrorKind::ExpectedFieldValue.at(parser.peek_fragment_or_rest_cloned())
+            );
+        }
+
+        parser.consume_optional_whitespace();
+        parser.advance(1);
+        parser.consume_optional_whitespace();
+        let value = Expr::parse(parser)?;
+
+        Ok(FieldInit {
+            matching_source: Fragment::cover(&name.fragment, value.matching_source()),
+            name,
+            value,
+        })
+    }
+}
+
+/// Parse an expression wrapped in parentheses.
+fn parse_parens(parser: &mut Parser) -> Result<Expr, ParserError> {
+    // SAFETY: The caller peeked the opening parenthesis.
+    let open = unsafe { parser.next_token().unwrap_unchecked().unwrap_unchecked() };
+    parser.consume_optional_whitespace();
+    let inner = Expr::parse(parser)?;
+    parser.consume_optional_whitespace();
+
+    match parser.next_if_is(TokenTy::RightParen) {
+        Some(close) => Ok(Expr::Parens(ParensExpr {
+            matching_source: Fragment::cover(&open.fragment, &close.fragment),
+            inner: Box::new(inner),
+        })),
+
+        None => {
+            Err(ParserErrorKind::UnterminatedParentheses.at(parser.peek_fragment_or_rest_cloned()))
+        }
+    }
+}
+
+impl IfExpr {
+    /// Parse an `if` expression with any `else if` and `else` branches.
+    pub fn parse(parser: &mut Parser) -> Result<Self, ParserError> {
+        let if_kw = parser.next_if_is(TokenTy::KwIf).ok_or_else(|| {
+            ParserErrorKind::ExpectedExpression.at(parser.peek_fragment_or_rest_cloned())
+        })?;
+
+        parser.consume_at_least_one_whitespace()?;
+        let condition = parser.with_record_literals(false, Expr::parse)?;
+        parser.consume_optional_whitespace();
+        let then_block = Block::parse(parser)?;
+
+        let else_branch = if parser.matches_ignore_whitespace(&[TokenTy::KwElse]) {
+            parser.consume_optional_whitespace();
+            parser.advance(1);
+            parser.consume_optional_whitespace();
+
+            match parser.peek_variant() {
+                Some(TokenTy::KwIf) => Some(Box::new(Expr::If(IfExpr::parse(parser)?))),
+                _ => Some(Box::new(Expr::Block(Block::parse(parser)?))),
+            }
+        } else {
+            None
+        };
+
+        let end = else_branch
+            .as_deref()
+            .map(Expr::matching_source)
+            .unwrap_or(&then_block.matching_source);
+
+        Ok(IfExpr {
+            matching_source: Fragment::cover(&if_kw.fragment, end),
+            condition: Box::new(condition),
+            then_block,
+            else_branch,
+        })
+    }
+}
+
+impl MatchExpr {
+    /// Parse a `match` expression and its arms.
+    pub fn parse(parser: &mut Parser) -> Result<Self, ParserError> {
+        let match_kw = parser.next_if_is(TokenTy::KwMatch).ok_or_else(|| {
+            ParserErrorKind::ExpectedExpression.at(parser.peek_fragment_or_rest_cloned())
+        })?;
+
+        parser.consume_at_least_one_whitespace()?;
+        let scrutinee = parser.with_record_literals(false, Expr::parse)?;
+        parser.consume_optional_whitespace();
+
+        if parser.next_if_is(TokenTy::LeftCurly).is_none() {
+            return Err(
+                ParserErrorKind::ExpectedMatchArms.at(parser.peek_fragment_or_rest_cloned())
+            );
+        }
+
+        let mut arms = Vec::new();
+
+        let close = loop {
+            parser.consume_optional_whitespace();
+
+            if let Some(close) = parser.next_if_is(TokenTy::RightCurly) {
+                break close;
+            }
+
+            let arm = parser.with_record_literals(true, MatchArm::parse)?;
+            parser.consume_optional_whitespace();
+
+            // Arms are separated by commas, which can be left out after arms with block-like bodies.
+            let separated = parser.next_if_is(TokenTy::Comma).is_some()
+                || parser.peek_variant() == Some(TokenTy::RightCurly)
+                || arm.body.is_block_like();
+
+            if !separated {
+                return Err(ParserErrorKind::ExpectedMatchArmSeparator
+                    .at(parser.peek_fragment_or_rest_cloned()));
+            }
+
+            arms.push(arm);
+        };
+
+        Ok(MatchExpr {
+            matching_source: Fragment::cover(&match_kw.fragment, &close.fragment),
+            scrutinee: Box::new(scrutinee),
+            arms,
+        })
+    }
+}
+
+impl MatchArm {
+    /// Parse an arm of a `match` expression, i.e. `n if n > 10 => big(n)`.
+    pub fn parse(parser: &mut Parser) -> Result<Self, ParserError> {
+        let pattern = Pattern::parse(parser)?;
+
+        let guard = if parser.matches_ignore_whitespace(&[TokenTy::KwIf]) {
+            parser.consume_optional_whitespace();
+            parser.advance(1);
+            parser.consume_at_least_one_whitespace()?;
+            Some(Expr::parse(parser)?)
+        } else {
+            None
+        };
+
+        parser.consume_optional_whitespace();
+
+        if parser.next_if_is(TokenTy::DoubleArrow).is_none() {
+            return Err(
+                ParserErrorKind::ExpectedMatchArrow.at(parser.peek_fragment_or_rest_cloned())
+            );
+        }
+
+        parser.consume_optional_whitespace();
+        let body = Expr::parse(parser)?;
+
+        Ok(MatchArm {
+            matching_source: Fragment::cover(pattern.matching_source(), body.matching_source()),
+            pattern,
+            guard,
+            body,
+        })
+    }
+}
+
+impl WhileExpr {
+    /// Parse a `while` loop.
+    pub fn parse(parser: &mut Parser) -> Result<Self, ParserError> {
+        let while_kw = parser.next_if_is(TokenTy::KwWhile).ok_or_else(|| {
+            ParserErrorKind::ExpectedExpression.at(parser.peek_fragment_or_rest_cloned())
+        })?;
+
+        parser.consume_at_least_one_whitespace()?;
+        let condition = parser.with_record_literals(false, Expr::parse)?;
+        parser.consume_optional_whitespace();
+        let body = Block::parse(parser)?;
+
+        Ok(WhileExpr {
+            matching_source: Fragment::cover(&while_kw.fragment, &body.matching_source),
+            condition: Box::new(condition),
+            body,
+        })
+    }
+}
+
+impl UnsafeExpr {
+    /// Parse an `unsafe` block.
+    pub fn parse(parser: &mut Parser) -> Result<Self, ParserError> {
+        let unsafe_kw = parser.next_if_is(TokenTy::KwUnsafe).ok_or_else(|| {
+            ParserErrorKind::ExpectedExpression.at(parser.peek_fragment_or_rest_cloned())
+        })?;
+
+        parser.consume_optional_whitespace();
+        let body = parser.with_record_literals(true, Block::parse)?;
+
+        Ok(UnsafeExpr {
+            matching_source: Fragment::cover(&unsafe_kw.fragment, &body.matching_source),
+            body,
+        })
+    }
+}
+
+impl ForExpr {
+    /// Parse a `for` loop, i.e. `for i in 1..=100 { ... }`.
+    pub fn parse(parser: &mut Parser) -> Result<Self, ParserError> {
+        let for_kw = parser.next_if_is(TokenTy::KwFor).ok_or_else(|| {
+            ParserErrorKind::ExpectedExpression.at(parser.peek_fragment_or_rest_cloned())
+        })?;
+
+        parser.consume_at_least_one_whitespace()?;
+        let binding = Identifier::parse(parser)?;
+        parser.consume_optional_whitespace();
+
+        if parser.next_if_is(TokenTy::KwIn).is_none() {
+            return Err(ParserErrorKind::ExpectedInAfterForBinding
+                .at(parser.peek_fragment_or_rest_cloned()));
+        }
+
+        parser.consume_at_least_one_whitespace()?;
+        let range = parser.with_record_literals(false, RangeExpr::parse)?;
+        parser.consume_optional_whitespace();
+        let body = Block::parse(parser)?;
+
+        Ok(ForExpr {
+            matching_source: Fragment::cover(&for_kw.fragment, &body.matching_source),
+            binding,
+            range,
+            body,
+        })
+    }
+}
+
+impl RangeExpr {
+    /// Parse a range, i.e. `0..10` or `1..=100`.
+    pub fn parse(parser: &mut Parser) -> Result<Self, ParserError> {
+        let start = Expr::parse(parser)?;
+        parser.consume_optional_whitespace();
+
+        let is_inclusive = match parser.peek_variant() {
+            Some(TokenTy::DotDot) => false,
+            Some(TokenTy::DotDotEq) => true,
+            _ => {
+                return Err(
+                    ParserErrorKind::ExpectedRange.at(parser.peek_fragment_or_rest_cloned())
+                );
+            }
+        };
+
+        parser.advance(1);
+        parser.consume_optional_whitespace();
+        let end = Expr::parse(parser)?;
+
+        Ok(RangeExpr {
+            matching_source: Fragment::cover(start.matching_source(), end.matching_source()),
+            start: Box::new(start),
+            end: Box::new(end),
+            is_inclusive,
+        })
+    }
+}
+
+impl ClosureExpr {
+    /// Parse a closure, i.e. `func(x, y: u8) -> u8 { x + y }`.
+    pub fn parse(parser: &mut Parser) -> Result<Self, ParserError> {
+        let func_kw = parser.next_if_is(TokenTy::KwFunc).ok_or_else(|| {
+            ParserErrorKind::ExpectedExpression.at(parser.peek_fragment_or_rest_cloned())
+        })?;
+
+        parser.consume_optional_whitespace();
+
+        if parser.next_if_is(TokenTy::LeftParen).is_none() {
+            return Err(ParserErrorKind::ExpectedFunctionParameters
+                .at(parser.peek_fragment_or_rest_cloned()));
+        }
+
+        let (params, _) = parser.parse_comma_separated(
+            TokenTy::RightParen,
+            ParserErrorKind::UnterminatedFunctionParameters,
+            ClosureParam::parse,
+        )?;
+
+        let return_ty = if parser.matches_ignore_whitespace(&[TokenTy::SingleArrow]) {
+            parser.consume_optional_whitespace();
+            parser.advance(1);
+            parser.consume_optional_whitespace();
+            Some(Type::parse(parser)?)
+        } else {
+            None
+        };
+
+        parser.consume_optional_whitespace();
+
+        if parser.peek_variant() != Some(TokenTy::LeftCurly) {
+            return Err(
+                ParserErrorKind::ExpectedFunctionBody.at(parser.peek_fragment_or_rest_cloned())
+            );
+        }
+
+        let body = parser.with_record_literals(true, Block::parse)?;
+
+        Ok(ClosureExpr {
+            matching_source: Fragment::cover(&func_kw.fragment, &body.matching_source),
+            params,
+            return_ty,
+            body,
+        })
+    }
+}
+
+impl ClosureParam {
+    /// Parse a parameter of a closure, with or without a type annotation.
+    pub fn parse(parser: &mut Parser) -> Result<Self, ParserError> {
+        let name = Identifier::parse(parser)?;
+
+        if !parser.matches_ignore_whitespace(&[TokenTy::Colon]) {
+            return Ok(ClosureParam {
+                matching_source: name.fragment.clone(),
+                name,
+                ty: None,
+            });
+        }
+
+        parser.consume_optional_whitespace();
+        parser.advance(1);
+        parser.consume_optional_whitespace();
+        let ty = Type::parse(parser)?;
+
+        Ok(ClosureParam {
+            matching_source: Fragment::cover(&name.fragment, ty.matching_source()),
+            name,
+            ty: Some(ty),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::expr::{Atom, Expr},
+        lexer::Lexer,
+        parser::{Parser, error::ParserErrorKind},
+    };
+
+    #[test]
+    fn test_else_if() {
+        let mut parser = Parser::new(Lexer::new_test(
+            "if i % 15 == 0 { a } else if (i % 5 == 0) { b } else { c }",
+        ));
+
+        let Expr::If(if_expr) = Expr::parse(&mut parser).unwrap() else {
+            panic!()
+        };
+        assert_eq!(parser.bytes_remaining(), 0);
+        let Some(Expr::If(else_if)) = if_expr.else_branch.as_deref() else {
+            panic!()
+        };
+        assert!(matches!(*else_if.condition, Expr::Parens(_)));
+        assert!(matches!(else_if.else_branch.as_deref(), Some(Expr::Block(_))));
+    }
+
+    #[test]
+    fn test_record_literals() {
+        let mut parser = Parser::new(Lexer::new_test("geo::Point { x: 1, y: Pair {} }"));
+        let Expr::Record(record) = Expr::parse(&mut parser).unwrap() else {
+            panic!("expected a record literal")
+        };
+        assert_eq!(record.name.full_path.as_str(), "geo::Point");
+        assert_eq!(record.fields.len(), 2);
+        assert!(matches!(record.fields[1].value, Expr::Record(_)));
+
+        // Conditions can't contain record literals (outside of parentheses).
+        let mut parser = Parser::new(Lexer::new_test("if done {} else { (Point { x: 1 }) }"));
+        let Expr::If(if_expr) = Expr::parse(&mut parser).unwrap() else {
+            panic!("expected an if expression")
+        };
+        assert!(matches!(*if_expr.condition, Expr::Atom(Atom::Identifier(_))));
+        assert_eq!(parser.bytes_remaining(), 0);
+    }
+
+    #[test]
+    fn test_literals() {
+        let mut parser = Parser::new(Lexer::new_test("\"hi\""));
+        let expr = Expr::parse(&mut parser).unwrap();
+        assert!(matches!(expr, Expr::Atom(Atom::StringLiteral(lit)) if lit.value == "hi"));
+    }
+
+    #[test]
+    fn test_closure() {
+        let mut parser = Parser::new(Lexer::new_test("func(x, y: u8) -> u8 { x + y }"));
+        let Expr::Closure(closure) = Expr::parse(&mut parser).unwrap() else {
+            panic!("expected a closure")
+        };
+        assert_eq!(parser.bytes_remaining(), 0);
+        assert_eq!(closure.params.len(), 2);
+        assert!(closure.params[0].ty.is_none());
+        assert_eq!(closure.params[1].matching_source.as_str(), "y: u8");
+        assert!(closure.return_ty.is_some());
+        assert!(closure.body.tail.is_some());
+    }
+
+    #[test]
+    fn test_match() {
+        let mut parser = Parser::new(Lexer::new_test(
+            "match shape { Shape { circle: r } if r > 10 => { big() } Shape { square } => 2, _ => 3 }",
+        ));
+        let Expr::Match(match_expr) = Expr::parse(&mut parser).unwrap() else {
+            panic!("expected a match")
+        };
+        assert_eq!(parser.bytes_remaining(), 0);
+        assert_eq!(match_expr.scrutinee.matching_source().as_str(), "shape");
+        assert_eq!(match_expr.arms.len(), 3);
+        assert_eq!(
+            match_expr.arms[0]
+                .guard
+                .as_ref()
+                .unwrap()
+                .matching_source()
+                .as_str(),
+            "r > 10"
+        );
+        assert!(matches!(match_expr.arms[0].body, Expr::Block(_)));
+        assert_eq!(match_expr.arms[1].matching_source.as_str(), "Shape { square } => 2");
+
+        let mut parser = Parser::new(Lexer::new_test("match x { 1 => a 2 => b }"));
+        assert_eq!(
+            Expr::parse(&mut parser).unwrap_err().kind,
+            ParserErrorKind::ExpectedMatchArmSeparator
+        );
+
+        let mut parser = Parser::new(Lexer::new_test("match x { 1 -> a }"));
+        assert_eq!(Expr::parse(&mut parser).unwrap_err().kind, ParserErrorKind::ExpectedMatchArrow);
+    }
+
+    #[test]
+    fn test_for() {
+        let mut parser = Parser::new(Lexer::new_test("for i in 1..=n + 1 { f(i); }"));
+        let Expr::For(for_expr) = Expr::parse(&mut parser).unwrap() else {
+            panic!("expected a for loop")
+        };
+        assert_eq!(parser.bytes_remaining(), 0);
+        assert_eq!(for_expr.binding.fragment.as_str(), "i");
+        assert!(for_expr.range.is_inclusive);
+        assert_eq!(for_expr.range.matching_source.as_str(), "1..=n + 1");
+        assert_eq!(for_expr.range.start.matching_source().as_str(), "1");
+
+        let mut parser = Parser::new(Lexer::new_test("for i in 0..10 {}"));
+        let Expr::For(for_expr) = Expr::parse(&mut parser).unwrap() else {
+            panic!("expected a for loop")
+        };
+        assert!(!for_expr.range.is_inclusive);
+
+        let mut parser = Parser::new(Lexer::new_test("for i in 10 {}"));
+        assert_eq!(Expr::parse(&mut parser).unwrap_err().kind, ParserErrorKind::ExpectedRange);
+    }
+}
@@ -39,7 +39,7 @@ mod tests {
     #[test]
     fn test_parse_ident_fail() {
         for fail in ["12", "+", " ", " test", "_", "record"] {
-            let mut parser = Parser::new(Lexer::new_test(&fail));
+            let mut parser = Parser::new(Lexer::new_test(fail));
             let error = Identifier::parse(&mut parser).unwrap_err();
             assert_eq!(error.kind, ParserErrorKind::ExpectedIdentifier);
         }
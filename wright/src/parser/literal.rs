@@ -2,3 +2,4 @@
 
 mod boolean;
 mod integer;
+mod string;
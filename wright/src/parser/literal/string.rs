@@ -0,0 +1,66 @@
+//! String literal parsing implementation.
+
+use crate::{
+    ast::literal::StringLiteral,
+    lexer::token::TokenTy,
+    parser::{
+        Parser,
+        error::{ParserError, ParserErrorKind},
+    },
+};
+
+impl StringLiteral {
+    /// Parse a string literal from the given [Parser], replacing any escape sequences in its value.
+    ///
+    /// Unrecognized escape sequences are left in the value as they're written.
+    pub fn parse(parser: &mut Parser) -> Result<Self, ParserError> {
+        let token = parser
+            .next_if_is(TokenTy::StringLiteral { terminated: true })
+            .ok_or_else(|| {
+                ParserErrorKind::ExpectedStringLiteral.at(parser.peek_fragment_or_rest_cloned())
+            })?;
+
+        // Strip the quotes.
+        let content = &token.fragment.as_str()[1..token.fragment.len() - 1];
+        let mut value = String::with_capacity(content.len());
+        let mut chars = content.chars();
+
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                value.push(c);
+                continue;
+            }
+
+            match chars.next() {
+                Some('n') => value.push('\n'),
+                Some('r') => value.push('\r'),
+                Some('t') => value.push('\t'),
+                Some('0') => value.push('\0'),
+                Some(c @ ('\\' | '"' | '\'')) => value.push(c),
+                Some(other) => {
+                    value.push('\\');
+                    value.push(other);
+                }
+                None => value.push('\\'),
+            }
+        }
+
+        Ok(StringLiteral {
+            fragment: token.fragment,
+            value,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ast::literal::StringLiteral, lexer::Lexer, parser::Parser};
+
+    #[test]
+    fn test_string_literal() {
+        let mut parser = Parser::new(Lexer::new_test(r#""Hello\n\"World\"\q""#));
+        let lit = StringLiteral::parse(&mut parser).unwrap();
+        assert_eq!(lit.value, "Hello\n\"World\"\\q");
+        assert_eq!(parser.bytes_remaining(), 0);
+    }
+}
@@ -0,0 +1,150 @@
+//! Parsing for whole [Module]s of source code.
+
+use crate::{
+    ast::{decl::Decl, docs::Docs, module::Module},
+    lexer::token::TokenTy,
+    parser::{
+        Parser,
+        decl::at_decl,
+        docs::{INNER_DOC_COMMENTS, OUTER_DOC_COMMENTS, collect_doc_comments},
+        error::{ParserError, ParserErrorKind},
+    },
+    reporting::{Diagnostic, Highlight},
+    source_tracking::fragment::Fragment,
+};
+
+impl Module {
+    /// Parse everything remaining in the [Parser] as a [Module].
+    ///
+    /// Inner doc comments anywhere in the module are attached to the module itself. Outer doc comments that are not
+    /// followed by a declaration produce a warning (see [Parser::take_warnings]) and are otherwise ignored.
+    pub fn parse(parser: &mut Parser) -> Result<Self, ParserError> {
+        let start: Fragment = parser.peek_fragment_or_rest_cloned();
+        let (docs, items) = parse_items(parser, |parser| parser.peek_variant().is_none())?;
+
+        // Modules run to the end of their source.
+        let end: Fragment = parser.peek_fragment_or_rest_cloned();
+
+        Ok(Module {
+            matching_source: Fragment::cover(&start, &end),
+            docs,
+            items,
+        })
+    }
+
+    /// Parse the body of an inline module, from its opening curly brace to its closing one. The [Module]'s
+    /// matching source includes both braces.
+    ///
+    /// The parser is left unadvanced if the next token is not an opening curly brace.
+    pub fn parse_inline(parser: &mut Parser) -> Result<Self, ParserError> {
+        let Some(open) = parser.next_if_is(TokenTy::LeftCurly) else {
+            return Err(
+                ParserErrorKind::ExpectedModuleBody.at(parser.peek_fragment_or_rest_cloned())
+            );
+        };
+
+        let (docs, items) = parse_items(parser, |parser| {
+            matches!(parser.peek_variant(), None | Some(TokenTy::RightCurly))
+        })?;
+
+        match parser.next_if_is(TokenTy::RightCurly) {
+            Some(close) => Ok(Module {
+                matching_source: Fragment::cover(&open.fragment, &close.fragment),
+                docs,
+                items,
+            }),
+
+            None => Err(ParserErrorKind::UnterminatedModuleBody
+                .at(open.fragment)
+                .with_help("this module body is never closed")),
+        }
+    }
+}
+
+/// Parse declarations and doc comments until `at_end` returns true (checked after skipping whitespace).
+///
+/// Inner doc comments are collected and returned along with the declarations.
+fn parse_items(
+    parser: &mut Parser,
+    at_end: impl Fn(&mut Parser) -> bool,
+) -> Result<(Docs, Vec<Decl>), ParserError> {
+    let mut docs = Docs::default();
+    let mut items = Vec::new();
+
+    loop {
+        parser.consume_optional_whitespace();
+
+        if at_end(parser) {
+            break;
+        }
+
+        match parser.peek_variant() {
+            Some(variant) if INNER_DOC_COMMENTS.contains(&variant) => {
+                docs.lines.extend(Docs::parse_inner(parser).lines);
+            }
+
+            Some(variant) if OUTER_DOC_COMMENTS.contains(&variant) && !at_decl(parser) => {
+                let comments = collect_doc_comments(parser, OUTER_DOC_COMMENTS);
+                // SAFETY: We just peeked at least one doc comment.
+                let (first, last) = unsafe {
+                    (comments.first().unwrap_unchecked(), comments.last().unwrap_unchecked())
+                };
+
+                parser.warn(
+                    Diagnostic::warning()
+                        .with_message("doc comment is not followed by an item")
+                        .with_highlights([Highlight::primary(
+                            Fragment::cover(&first.fragment, &last.fragment),
+                            "this doc comment does not document anything",
+                        )])
+                        .with_notes([
+                            "outer doc comments (`///` and `/** */`) document the item that follows them \
+                            -- use `//!` to document the enclosing module, or `//` for a regular comment",
+                        ]),
+                );
+            }
+
+            _ => items.push(Decl::parse(parser)?),
+        }
+    }
+
+    Ok((docs, items))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{decl::Decl, module::Module},
+        lexer::Lexer,
+        parser::Parser,
+        reporting::Severity,
+    };
+
+    #[test]
+    fn test_module_docs() {
+        let mut parser = Parser::new(Lexer::new_test(
+            "//! The module.\n\n/// A byte.\ntype Byte = u8;\n\n//! More about the module.\nuse wright::io;\n",
+        ));
+
+        let module = Module::parse(&mut parser).unwrap();
+        assert_eq!(parser.bytes_remaining(), 0);
+        assert_eq!(module.docs.text(), "The module.\nMore about the module.");
+        assert_eq!(module.items.len(), 2);
+        assert!(matches!(module.items[0], Decl::TypeAlias(_)));
+        assert_eq!(module.items[0].docs().text(), "A byte.");
+        assert!(module.items[1].docs().is_empty());
+        assert!(parser.take_warnings().is_empty());
+    }
+
+    #[test]
+    fn test_dangling_doc_comment() {
+        let mut parser = Parser::new(Lexer::new_test("type Byte = u8;\n/// Nothing here.\n"));
+        let module = Module::parse(&mut parser).unwrap();
+        assert_eq!(module.items.len(), 1);
+
+        let warnings = parser.take_warnings();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].0.severity, Severity::Warning);
+        assert_eq!(warnings[0].0.labels[0].range.len(), "/// Nothing here.".len());
+    }
+}
@@ -85,7 +85,7 @@ mod test_path {
 
         for source in sources {
             dbg!(source);
-            let source_ref = map.add(Source::new_from_static_str(FileName::None, *source));
+            let source_ref = map.add(Source::new_from_static_str(FileName::None, source));
             let lexer = Lexer::new(source_ref);
             let mut parser = Parser::new(lexer);
             let path = Path::parse(&mut parser).unwrap();
@@ -0,0 +1,231 @@
+//! Pattern parsing.
+
+use crate::{
+    ast::{
+        identifier::Identifier,
+        literal::{BooleanLiteral, IntegerLiteral},
+        path::Path,
+        pattern::{FieldPattern, IntegerPattern, OrPattern, Pattern, RangePattern, RecordPattern},
+    },
+    lexer::token::TokenTy,
+    parser::{
+        Parser,
+        error::{ParserError, ParserErrorKind},
+    },
+    source_tracking::fragment::Fragment,
+};
+
+impl Pattern {
+    /// Parse a pattern, including any alternatives separated by `|`.
+    pub fn parse(parser: &mut Parser) -> Result<Self, ParserError> {
+        let first = parse_single(parser)?;
+
+        if !parser.matches_ignore_whitespace(&[TokenTy::Or]) {
+            return Ok(first);
+        }
+
+        let mut alternatives = vec![first];
+
+        while parser.matches_ignore_whitespace(&[TokenTy::Or]) {
+            parser.consume_optional_whitespace();
+            parser.advance(1);
+            parser.consume_optional_whitespace();
+            alternatives.push(parse_single(parser)?);
+        }
+
+        Ok(Pattern::Or(OrPattern {
+            matching_source: Fragment::cover(
+                alternatives[0].matching_source(),
+                alternatives[alternatives.len() - 1].matching_source(),
+            ),
+            alternatives,
+        }))
+    }
+}
+
+/// Parse a pattern without any alternatives.
+fn parse_single(parser: &mut Parser) -> Result<Pattern, ParserError> {
+    match parser.peek_variant() {
+        Some(TokenTy::Underscore) => {
+            // SAFETY: We just peeked the underscore.
+            let underscore = unsafe { parser.next_token().unwrap_unchecked().unwrap_unchecked() };
+            Ok(Pattern::Wildcard(underscore.fragment))
+        }
+
+        Some(TokenTy::KwTrue | TokenTy::KwFalse) => {
+            BooleanLiteral::parse(parser).map(Pattern::Boolean)
+        }
+
+        Some(TokenTy::IntegerLiteral | TokenTy::Minus) => {
+            let start = IntegerPattern::parse(parser)?;
+
+            let is_inclusive = match parser.peek_next_not_whitespace().map(|token| token.variant) {
+                Some(TokenTy::DotDot) => false,
+                Some(TokenTy::DotDotEq) => true,
+                _ => return Ok(Pattern::Integer(start)),
+            };
+
+            parser.consume_optional_whitespace();
+            parser.advance(1);
+            parser.consume_optional_whitespace();
+
+            let end = IntegerPattern::parse(parser).map_err(|_| {
+                ParserErrorKind::ExpectedRangePatternEnd.at(parser.peek_fragment_or_rest_cloned())
+            })?;
+
+            Ok(Pattern::Range(RangePattern {
+                matching_source: Fragment::cover(&start.matching_source, &end.matching_source),
+                start,
+                end,
+                is_inclusive,
+            }))
+        }
+
+        Some(TokenTy::Identifier) => {
+            let path = Path::parse(parser)?;
+
+            if parser.matches_ignore_whitespace(&[TokenTy::LeftCurly]) {
+                return RecordPattern::parse_fields(parser, path).map(Pattern::Record);
+            }
+
+            Ok(match path.tail.is_empty() {
+                true => Pattern::Binding(path.head),
+                false => Pattern::Variant(path),
+            })
+        }
+
+        _ => Err(ParserErrorKind::ExpectedPattern.at(parser.peek_fragment_or_rest_cloned())),
+    }
+}
+
+impl IntegerPattern {
+    /// Parse an integer literal in a pattern, with an optional `-` in front of it.
+    pub fn parse(parser: &mut Parser) -> Result<Self, ParserError> {
+        let minus = parser.next_if_is(TokenTy::Minus);
+
+        if minus.is_some() {
+            parser.consume_optional_whitespace();
+        }
+
+        let literal = IntegerLiteral::parse(parser)?;
+
+        Ok(IntegerPattern {
+            matching_source: match &minus {
+                Some(minus) => Fragment::cover(&minus.fragment, &literal.fragment),
+                None => literal.fragment.clone(),
+            },
+            is_negative: minus.is_some(),
+            literal,
+        })
+    }
+}
+
+impl RecordPattern {
+    /// Parse the fields of a record pattern, after its name has already been parsed.
+    fn parse_fields(parser: &mut Parser, name: Path) -> Result<Self, ParserError> {
+        parser.consume_optional_whitespace();
+        // The caller checked for the curly brace.
+        parser.advance(1);
+
+        let (fields, close) = parser.parse_comma_separated(
+            TokenTy::RightCurly,
+            ParserErrorKind::UnterminatedFieldList,
+            FieldPattern::parse,
+        )?;
+
+        Ok(RecordPattern {
+            matching_source: Fragment::cover(&name.full_path, &close.fragment),
+            name,
+            fields,
+        })
+    }
+}
+
+impl FieldPattern {
+    /// Parse the pattern for a field in a record pattern, i.e. `x: 0`, or just `x` to bind the field to its name.
+    pub fn parse(parser: &mut Parser) -> Result<Self, ParserError> {
+        let name = Identifier::parse(parser)?;
+
+        if !parser.matches_ignore_whitespace(&[TokenTy::Colon]) {
+            return Ok(FieldPattern {
+                matching_source: name.fragment.clone(),
+                pattern: Pattern::Binding(name.clone()),
+                name,
+            });
+        }
+
+        parser.consume_optional_whitespace();
+        parser.advance(1);
+        parser.consume_optional_whitespace();
+        let pattern = Pattern::parse(parser)?;
+
+        Ok(FieldPattern {
+            matching_source: Fragment::cover(&name.fragment, pattern.matching_source()),
+            name,
+            pattern,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::pattern::Pattern,
+        lexer::Lexer,
+        parser::{Parser, error::ParserErrorKind},
+    };
+
+    fn parse(source: &'static str) -> Pattern {
+        let mut parser = Parser::new(Lexer::new_test(source));
+        let pattern = Pattern::parse(&mut parser).unwrap();
+        assert_eq!(parser.bytes_remaining(), 0);
+        pattern
+    }
+
+    #[test]
+    fn test_patterns() {
+        assert!(matches!(parse("_"), Pattern::Wildcard(_)));
+        assert!(matches!(parse("x"), Pattern::Binding(_)));
+        assert!(matches!(parse("Color::Red"), Pattern::Variant(_)));
+
+        let Pattern::Integer(integer) = parse("- 5") else {
+            panic!("expected an integer pattern");
+        };
+        assert_eq!(integer.value(), (-5).into());
+
+        let Pattern::Range(range) = parse("-128..0") else {
+            panic!("expected a range pattern");
+        };
+        assert!(!range.is_inclusive);
+        assert_eq!(range.start.value(), (-128).into());
+    }
+
+    #[test]
+    fn test_records_and_alternatives() {
+        let Pattern::Or(or) = parse("Point { x: 0 | 1, y } | Number { small: _ }") else {
+            panic!("expected alternatives");
+        };
+        assert_eq!(or.alternatives.len(), 2);
+
+        let Pattern::Record(point) = &or.alternatives[0] else {
+            panic!("expected a record pattern");
+        };
+        assert_eq!(point.name.full_path.as_str(), "Point");
+        assert!(matches!(point.fields[0].pattern, Pattern::Or(_)));
+        assert!(
+            matches!(&point.fields[1].pattern, Pattern::Binding(y) if y.fragment.as_str() == "y")
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        for (source, kind) in [
+            ("=>", ParserErrorKind::ExpectedPattern),
+            ("1..=x", ParserErrorKind::ExpectedRangePatternEnd),
+            ("Point { x: }", ParserErrorKind::ExpectedPattern),
+        ] {
+            let mut parser = Parser::new(Lexer::new_test(source));
+            assert_eq!(Pattern::parse(&mut parser).unwrap_err().kind, kind, "{source}");
+        }
+    }
+}
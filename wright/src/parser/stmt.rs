@@ -0,0 +1,253 @@
+//! Parsing for statements and blocks.
+
+use crate::{
+    ast::{
+        expr::{BinaryOperation, Expr},
+        identifier::Identifier,
+        stmt::{AssignStmt, Block, ExprStmt, LetStmt, Stmt},
+        ty::Type,
+    },
+    lexer::token::TokenTy,
+    parser::{
+        Parser,
+        error::{ParserError, ParserErrorKind},
+    },
+    source_tracking::fragment::Fragment,
+};
+
+impl Block {
+    /// Parse a block of statements, i.e. `{ let x = 1; x + 1 }`.
+    pub fn parse(parser: &mut Parser) -> Result<Self, ParserError> {
+        let open = parser.next_if_is(TokenTy::LeftCurly).ok_or_else(|| {
+            ParserErrorKind::ExpectedBlock.at(parser.peek_fragment_or_rest_cloned())
+        })?;
+
+        let mut stmts = Vec::new();
+
+        loop {
+            parser.consume_optional_whitespace();
+
+            if let Some(close) = parser.next_if_is(TokenTy::RightCurly) {
+                return Ok(Block {
+                    matching_source: Fragment::cover(&open.fragment, &close.fragment),
+                    stmts,
+                    tail: None,
+                });
+            }
+
+            if parser.peek_variant().is_none() {
+                return Err(ParserErrorKind::UnterminatedBlock
+                    .at(open.fragment.clone())
+                    .with_help("this block is never closed"));
+            }
+
+            if matches!(parser.peek_variant(), Some(TokenTy::KwLet | TokenTy::KwVar)) {
+                stmts.push(Stmt::Let(LetStmt::parse(parser)?));
+                continue;
+            }
+
+            let expr = Expr::parse(parser)?;
+            parser.consume_optional_whitespace();
+
+            if let Some(op) = parser.peek_variant().and_then(assignment_op) {
+                stmts.push(Stmt::Assign(AssignStmt::parse_rest(parser, expr, op)?));
+                continue;
+            }
+
+            if let Some(semi) = parser.next_if_is(TokenTy::Semi) {
+                stmts.push(Stmt::Expr(ExprStmt {
+                    matching_source: Fragment::cover(expr.matching_source(), &semi.fragment),
+                    expr,
+                }));
+
+                continue;
+            }
+
+            // An expression right before the closing curly is the tail of the block.
+            if let Some(close) = parser.next_if_is(TokenTy::RightCurly) {
+                return Ok(Block {
+                    matching_source: Fragment::cover(&open.fragment, &close.fragment),
+                    stmts,
+                    tail: Some(Box::new(expr)),
+                });
+            }
+
+            // Expressions that end in a block don't need a semicolon.
+            if expr.is_block_like() {
+                stmts.push(Stmt::Expr(ExprStmt {
+                    matching_source: expr.matching_source().clone(),
+                    expr,
+                }));
+
+                continue;
+            }
+
+            return Err(
+                ParserErrorKind::ExpectedSemicolon.at(parser.peek_fragment_or_rest_cloned())
+            );
+        }
+    }
+}
+
+impl LetStmt {
+    /// Parse a `let` or `var` binding, i.e. `let x: u8 = 5;`.
+    pub fn parse(parser: &mut Parser) -> Result<Self, ParserError> {
+        let keyword = match parser.peek_variant() {
+            Some(TokenTy::KwLet | TokenTy::KwVar) => {
+                // SAFETY: We just peeked the keyword.
+                unsafe { parser.next_token().unwrap_unchecked().unwrap_unchecked() }
+            }
+
+            _ => {
+                return Err(
+                    ParserErrorKind::ExpectedExpression.at(parser.peek_fragment_or_rest_cloned())
+                );
+            }
+        };
+
+        parser.consume_at_least_one_whitespace()?;
+        let name = Identifier::parse(parser)?;
+
+        let ty = if parser.matches_ignore_whitespace(&[TokenTy::Colon]) {
+            parser.consume_optional_whitespace();
+            parser.advance(1);
+            parser.consume_optional_whitespace();
+            Some(Type::parse(parser)?)
+        } else {
+            None
+        };
+
+        parser.consume_optional_whitespace();
+
+        if parser.next_if_is(TokenTy::Eq).is_none() {
+            return Err(
+                ParserErrorKind::ExpectedLetBindingValue.at(parser.peek_fragment_or_rest_cloned())
+            );
+        }
+
+        parser.consume_optional_whitespace();
+        let value = Expr::parse(parser)?;
+        parser.consume_optional_whitespace();
+
+        match parser.next_if_is(TokenTy::Semi) {
+            Some(semi) => Ok(LetStmt {
+                matching_source: Fragment::cover(&keyword.fragment, &semi.fragment),
+                is_mutable: keyword.variant == TokenTy::KwVar,
+                name,
+                ty,
+                value,
+            }),
+
+            None => {
+                Err(ParserErrorKind::LetMustEndWithSemicolon
+                    .at(parser.peek_fragment_or_rest_cloned()))
+            }
+        }
+    }
+}
+
+/// Get the operation of an assignment operator -- [None] inside [Some] for `=`, or the operation of a compound
+/// assignment like `+=`.
+fn assignment_op(token: TokenTy) -> Option<Option<BinaryOperation>> {
+    use BinaryOperation::*;
+
+    Some(match token {
+        TokenTy::Eq => None,
+        TokenTy::PlusEq => Some(Add),
+        TokenTy::MinusEq => Some(Subtract),
+        TokenTy::StarEq => Some(Multiply),
+        TokenTy::DivEq => Some(Divide),
+        TokenTy::ModEq => Some(Modulo),
+        TokenTy::AndEq => Some(BitwiseAnd),
+        TokenTy::OrEq => Some(BitwiseOr),
+        TokenTy::XorEq => Some(BitwiseXor),
+        _ => return None,
+    })
+}
+
+impl AssignStmt {
+    /// Parse the rest of an assignment after its target, starting at the assignment operator.
+    fn parse_rest(
+        parser: &mut Parser,
+        target: Expr,
+        op: Option<BinaryOperation>,
+    ) -> Result<Self, ParserError> {
+        // SAFETY: The caller peeked the operator.
+        let operator = unsafe { parser.next_token().unwrap_unchecked().unwrap_unchecked() };
+        parser.consume_optional_whitespace();
+        let value = Expr::parse(parser)?;
+        parser.consume_optional_whitespace();
+
+        match parser.next_if_is(TokenTy::Semi) {
+            Some(semi) => Ok(AssignStmt {
+                matching_source: Fragment::cover(target.matching_source(), &semi.fragment),
+                target,
+                op,
+                op_fragment: operator.fragment,
+                value,
+            }),
+
+            None => {
+                Err(ParserErrorKind::ExpectedSemicolon.at(parser.peek_fragment_or_rest_cloned()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{
+            expr::BinaryOperation,
+            stmt::{Block, Stmt},
+        },
+        lexer::Lexer,
+        parser::{Parser, error::ParserErrorKind},
+    };
+
+    #[test]
+    fn test_block() {
+        let mut parser = Parser::new(Lexer::new_test(
+            "{\n    let x: u8 = 5;\n    var y = x;\n    if y { f(); }\n    x + y\n}",
+        ));
+
+        let block = Block::parse(&mut parser).unwrap();
+        assert_eq!(block.stmts.len(), 3);
+        assert!(matches!(&block.stmts[1], Stmt::Let(let_stmt) if let_stmt.is_mutable));
+        assert_eq!(block.tail.unwrap().matching_source().as_str(), "x + y");
+        assert_eq!(parser.bytes_remaining(), 0);
+    }
+
+    #[test]
+    fn test_assignment() {
+        let mut parser = Parser::new(Lexer::new_test("{ x = 1; point.y += x * 2; *r = 3; }"));
+        let block = Block::parse(&mut parser).unwrap();
+        assert_eq!(block.stmts.len(), 3);
+
+        let Stmt::Assign(assign) = &block.stmts[1] else {
+            panic!("expected an assignment")
+        };
+        assert_eq!(assign.target.matching_source().as_str(), "point.y");
+        assert_eq!(assign.op, Some(BinaryOperation::Add));
+        assert_eq!(assign.op_fragment.as_str(), "+=");
+        assert_eq!(assign.value.matching_source().as_str(), "x * 2");
+
+        let mut parser = Parser::new(Lexer::new_test("{ x = 1 }"));
+        let err = Block::parse(&mut parser).unwrap_err();
+        assert_eq!(err.kind, ParserErrorKind::ExpectedSemicolon);
+    }
+
+    #[test]
+    fn test_missing_semicolon() {
+        let mut parser = Parser::new(Lexer::new_test("{ f() g() }"));
+        let err = Block::parse(&mut parser).unwrap_err();
+        assert_eq!(err.kind, ParserErrorKind::ExpectedSemicolon);
+    }
+
+    #[test]
+    fn test_unterminated_block() {
+        let mut parser = Parser::new(Lexer::new_test("{ let x = 1;"));
+        let err = Block::parse(&mut parser).unwrap_err();
+        assert_eq!(err.kind, ParserErrorKind::UnterminatedBlock);
+    }
+}
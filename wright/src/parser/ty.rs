@@ -1,6 +1,6 @@
 //! Parser implementation for parsing types.
 
-use crate::ast::ty::{AtomicTy, NamedTy, ReferenceTy, Type};
+use crate::ast::ty::{AtomicTy, ConstrainedTy, FunctionTy, NamedTy, ReferenceTy, Type};
 
 use super::{
     Parser,
@@ -8,6 +8,7 @@ use super::{
 };
 
 mod constrained_ty;
+mod function;
 mod named;
 mod primitive;
 mod reference;
@@ -24,15 +25,22 @@ impl Type {
         let reference_ty_parse_fn =
             |parser: &mut Parser| ReferenceTy::parse(parser).map(Type::Reference);
         let named_ty_parse_fn = |parser: &mut Parser| NamedTy::parse(parser).map(Type::Named);
+        let function_ty_parse_fn =
+            |parser: &mut Parser| FunctionTy::parse(parser).map(Type::Function);
 
-        let order = &[atomic_ty_parse_fn, reference_ty_parse_fn, named_ty_parse_fn];
+        let order = &[
+            atomic_ty_parse_fn,
+            reference_ty_parse_fn,
+            named_ty_parse_fn,
+            function_ty_parse_fn,
+        ];
 
         for parse_fn in order {
             let initial_bytes_remaining = parser.bytes_remaining();
 
             match (parse_fn)(parser) {
                 // Successful parse.
-                Ok(t) => return Ok(t),
+                Ok(t) => return ConstrainedTy::parse_suffix(parser, t),
 
                 // Partial parse with error.
                 Err(err) if parser.bytes_remaining() != initial_bytes_remaining => return Err(err),
@@ -1 +1,100 @@
+//! Parser implementation for constrained types, i.e. `u8 constrain is_even + is_small`.
 
+use crate::{
+    ast::{
+        path::Path,
+        ty::{ConstrainedTy, Type},
+    },
+    lexer::token::TokenTy,
+    parser::{
+        Parser,
+        error::{ParserError, ParserErrorKind},
+    },
+    source_tracking::fragment::Fragment,
+};
+
+impl ConstrainedTy {
+    /// Parse the `constrain ...` suffix of a type signature, given the already parsed base type.
+    ///
+    /// If the next non-whitespace token is not the `constrain` keyword, the base type is returned unchanged and the
+    /// parser is not advanced.
+    pub fn parse_suffix(parser: &mut Parser, base_ty: Type) -> Result<Type, ParserError> {
+        if !parser.matches_ignore_whitespace(&[TokenTy::KwConstrain]) {
+            return Ok(base_ty);
+        }
+
+        // Chew through the whitespace and keyword.
+        parser.consume_optional_whitespace();
+        parser.advance(1);
+
+        let constraining_items = ConstrainedTy::parse_constraining_items(parser)?;
+
+        // SAFETY: There is always at least one constraining item when we get here.
+        let last = unsafe { constraining_items.last().unwrap_unchecked() };
+
+        Ok(Type::Constrained(ConstrainedTy {
+            matching_source: Fragment::cover(base_ty.matching_source(), &last.full_path),
+            base_ty: Box::new(base_ty),
+            constraining_items,
+        }))
+    }
+
+    /// Parse one or more paths to constraints separated by `+`, i.e. `is_even + math::is_small`. This is used
+    /// after the `constrain` keyword, and for the bases of [constraint declarations].
+    ///
+    /// [constraint declarations]: crate::ast::decl::constraint::ConstraintDecl
+    pub(crate) fn parse_constraining_items(parser: &mut Parser) -> Result<Vec<Path>, ParserError> {
+        let mut constraining_items: Vec<Path> = Vec::new();
+
+        loop {
+            parser.consume_optional_whitespace();
+
+            let item = Path::parse(parser).map_err(|_| {
+                ParserErrorKind::ExpectedConstraint
+                    .at(parser.peek_fragment_or_rest_cloned())
+                    .with_help(
+                        "constraints are combined with `+`, i.e. `u8 constrain is_even + is_small`",
+                    )
+            })?;
+
+            constraining_items.push(item);
+
+            if !parser.matches_ignore_whitespace(&[TokenTy::Plus]) {
+                break;
+            }
+
+            parser.consume_optional_whitespace();
+            parser.advance(1);
+        }
+
+        Ok(constraining_items)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::ty::{AtomicTyVariant, Type},
+        lexer::Lexer,
+        parser::{Parser, error::ParserErrorKind},
+    };
+
+    #[test]
+    fn test_constrained_ty() {
+        let mut parser = Parser::new(Lexer::new_test("u8 constrain is_even + math::is_small"));
+        let ty = Type::parse(&mut parser).unwrap();
+        let constrained = ty.downcast_constrained_ty().unwrap();
+        assert_eq!(constrained.base_ty.downcast_primitive().unwrap().variant, AtomicTyVariant::U8);
+        assert_eq!(constrained.constraining_items.len(), 2);
+        assert_eq!(constrained.constraining_items[1].full_path.as_str(), "math::is_small");
+        assert_eq!(ty.matching_source().as_str(), "u8 constrain is_even + math::is_small");
+        assert_eq!(parser.bytes_remaining(), 0);
+    }
+
+    #[test]
+    fn test_missing_constraint() {
+        let mut parser = Parser::new(Lexer::new_test("u8 constrain ;"));
+        let err = Type::parse(&mut parser).unwrap_err();
+        assert_eq!(err.kind, ParserErrorKind::ExpectedConstraint);
+    }
+}
@@ -0,0 +1,79 @@
+//! Function type signatures, i.e. `func(u8) -> bool`.
+
+use crate::{
+    ast::ty::{FunctionTy, Type},
+    lexer::token::TokenTy,
+    parser::{
+        Parser,
+        error::{ParserError, ParserErrorKind},
+    },
+    source_tracking::fragment::Fragment,
+};
+
+impl FunctionTy {
+    /// Parse a function type signature. The parser is left unmodified if it isn't at the `func` keyword.
+    pub fn parse(parser: &mut Parser) -> Result<Self, ParserError> {
+        let Some(func_kw) = parser.next_if_is(TokenTy::KwFunc) else {
+            return Err(ParserErrorKind::ExpectedFunctionTypeSignature
+                .at(parser.peek_fragment_or_rest_cloned()));
+        };
+
+        parser.consume_optional_whitespace();
+
+        if parser.next_if_is(TokenTy::LeftParen).is_none() {
+            return Err(ParserErrorKind::ExpectedFunctionParameters
+                .at(parser.peek_fragment_or_rest_cloned()));
+        }
+
+        let (params, close_paren) = parser.parse_comma_separated(
+            TokenTy::RightParen,
+            ParserErrorKind::UnterminatedFunctionParameters,
+            Type::parse,
+        )?;
+
+        let return_ty = if parser.matches_ignore_whitespace(&[TokenTy::SingleArrow]) {
+            parser.consume_optional_whitespace();
+            parser.advance(1);
+            parser.consume_optional_whitespace();
+            Some(Box::new(Type::parse(parser)?))
+        } else {
+            None
+        };
+
+        let end = return_ty
+            .as_deref()
+            .map(Type::matching_source)
+            .unwrap_or(&close_paren.fragment);
+
+        Ok(FunctionTy {
+            matching_source: Fragment::cover(&func_kw.fragment, end),
+            params,
+            return_ty,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ast::ty::Type, lexer::Lexer, parser::Parser};
+
+    #[test]
+    fn test_function_ty() {
+        let mut parser = Parser::new(Lexer::new_test("func(u8, @T) -> func() -> bool"));
+        let ty = Type::parse(&mut parser).unwrap();
+        let function = ty.downcast_function().unwrap();
+
+        assert_eq!(parser.bytes_remaining(), 0);
+        assert_eq!(function.params.len(), 2);
+        assert!(function.params[1].downcast_reference().is_some());
+
+        let ret = function
+            .return_ty
+            .as_deref()
+            .unwrap()
+            .downcast_function()
+            .unwrap();
+        assert!(ret.params.is_empty());
+        assert_eq!(ret.matching_source.as_str(), "func() -> bool");
+    }
+}
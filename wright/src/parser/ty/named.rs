@@ -3,7 +3,7 @@ use crate::{
         path::Path,
         ty::{NamedTy, Type},
     },
-    lexer::token::TokenTy,
+    lexer::token::{Token, TokenTy},
     parser::{
         Parser,
         error::{ParserError, ParserErrorKind},
@@ -34,48 +34,28 @@ impl NamedTy {
                 // Push the type to the list of generics.
                 generic_tys.push(t);
 
-                // Check if it was the last one.
-                const ENDING_SEQUENCES: &[&[TokenTy]] =
-                    &[&[TokenTy::Comma, TokenTy::Gt], &[TokenTy::Gt]];
-
-                for seq in ENDING_SEQUENCES {
-                    if parser.matches_ignore_whitespace(seq) {
-                        // Chew through remaining tokens
-                        for _ in 0..(seq.len() - 1) {
-                            parser.consume_optional_whitespace();
-                            parser.advance(1);
-                        }
-
-                        parser.consume_optional_whitespace();
-
-                        // SAFETY: We have confirmed that there's a token here, and it's not an unknown.
-                        let last_token =
-                            unsafe { parser.next_token().unwrap_unchecked().unwrap_unchecked() };
-
-                        // Compute the fragment to cover the whole thing.
-                        let matching_source =
-                            Fragment::cover(&path.full_path, &last_token.fragment);
-
-                        return Ok(NamedTy {
-                            matching_source,
-                            name: path,
-                            generic_tys,
-                            // generic_consts: (),
-                        });
-                    }
+                // Check if it was the last one. A `>>` here closes this list and an enclosing one, so split it.
+                parser.consume_optional_whitespace();
+                parser.split_closing_angle();
+
+                if let Some(last_token) = parser.next_if_is(TokenTy::Gt) {
+                    return Ok(NamedTy::with_generics(path, generic_tys, &last_token));
                 }
 
                 // If it wasn't the last one (we get here):
                 // Try to parse a comma and then another generic type or fail.
-                if !parser.matches_ignore_whitespace(&[TokenTy::Comma]) {
+                if parser.next_if_is(TokenTy::Comma).is_none() {
                     let fragment = parser.peek_fragment_or_rest_cloned();
                     return Err(ParserErrorKind::UnterminatedGenericTypeSignature.at(fragment));
                 }
 
-                // Chew through whitespace and comma
-                parser.consume_optional_whitespace();
-                parser.advance(1);
+                // Allow a trailing comma before the closing angle bracket.
                 parser.consume_optional_whitespace();
+                parser.split_closing_angle();
+
+                if let Some(last_token) = parser.next_if_is(TokenTy::Gt) {
+                    return Ok(NamedTy::with_generics(path, generic_tys, &last_token));
+                }
 
                 // Loop back and consume next type.
             }
@@ -90,6 +70,16 @@ impl NamedTy {
             // generic_consts: (),
         })
     }
+
+    /// Build a [NamedTy] whose list of generics was closed by `last_token`.
+    fn with_generics(name: Path, generic_tys: Vec<Type>, last_token: &Token) -> NamedTy {
+        NamedTy {
+            matching_source: Fragment::cover(&name.full_path, &last_token.fragment),
+            name,
+            generic_tys,
+            // generic_consts: (),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -140,4 +130,19 @@ mod tests {
             "OtherType<ThirdType>"
         );
     }
+
+    #[test]
+    fn test_nested_generics() {
+        let mut parser = Parser::new(Lexer::new_test("Opt<Opt<T>> rest"));
+        let named_ty = NamedTy::parse(&mut parser).unwrap();
+        assert_eq!(named_ty.matching_source.as_str(), "Opt<Opt<T>>");
+        assert_eq!(named_ty.generic_tys[0].matching_source().as_str(), "Opt<T>");
+        assert_eq!(parser.bytes_remaining(), " rest".len());
+
+        let mut parser = Parser::new(Lexer::new_test("Map<K, Opt<Opt<V>>,>"));
+        let named_ty = NamedTy::parse(&mut parser).unwrap();
+        assert_eq!(named_ty.generic_tys.len(), 2);
+        assert_eq!(named_ty.generic_tys[1].matching_source().as_str(), "Opt<Opt<V>>");
+        assert_eq!(parser.bytes_remaining(), 0);
+    }
 }
@@ -11,7 +11,7 @@ use crate::{
 };
 
 impl ReferenceTy {
-    /// Attempt to parse a reference type signature, i.e. `@u64`.
+    /// Attempt to parse a reference type signature, i.e. `@u64` or `@mut u64`.
     ///
     /// This will leave the parser unmodified and return an error if it doesn't match the `@` symbol, however
     /// if it does match the `@` symbol it will advance the parser and then may still return an error if the
@@ -24,6 +24,12 @@ impl ReferenceTy {
 
         parser.consume_optional_whitespace();
 
+        let is_mutable = parser.next_if_is(TokenTy::KwMut).is_some();
+
+        if is_mutable {
+            parser.consume_optional_whitespace();
+        }
+
         let referenced_type = Type::parse(parser)?;
 
         Ok(ReferenceTy {
@@ -31,6 +37,7 @@ impl ReferenceTy {
                 &at_symbol.fragment,
                 referenced_type.matching_source(),
             ),
+            is_mutable,
             target_ty: Box::new(referenced_type),
         })
     }
@@ -50,6 +57,7 @@ mod tests {
         let result = ReferenceTy::parse(&mut parser).unwrap();
 
         assert_eq!(result.matching_source.as_str(), "@u64");
+        assert!(!result.is_mutable);
         assert_eq!(result.target_ty.downcast_primitive().unwrap().variant, AtomicTyVariant::U64);
     }
 
@@ -62,6 +70,16 @@ mod tests {
         assert!(result.target_ty.downcast_reference().is_some());
     }
 
+    #[test]
+    fn test_mutable_reference() {
+        let mut parser = Parser::new(Lexer::new_test("@mut @u8"));
+        let result = ReferenceTy::parse(&mut parser).unwrap();
+
+        assert_eq!(result.matching_source.as_str(), "@mut @u8");
+        assert!(result.is_mutable);
+        assert!(!result.target_ty.downcast_reference().unwrap().is_mutable);
+    }
+
     #[test]
     fn test_u8_ref() {
         let mut parser = Parser::new(Lexer::new_test("@u8"));
@@ -49,4 +49,15 @@ impl SourceMap {
     pub fn get(&self, id: SourceId) -> Option<SourceRef> {
         self.inner.get(&id).map(|source| Arc::clone(&source))
     }
+
+    /// Find a [Source] in this [SourceMap] by the name it's displayed with (see [FileName]).
+    ///
+    /// This is `O(n)` in the number of [Source]s, since they're stored by [Source::id].
+    ///
+    /// [FileName]: filename::FileName
+    pub fn find_by_name(&self, name: &str) -> Option<SourceRef> {
+        (self.inner.iter())
+            .find(|entry| entry.value().name().to_string() == name)
+            .map(|entry| Arc::clone(entry.value()))
+    }
 }
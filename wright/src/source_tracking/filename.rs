@@ -7,7 +7,7 @@ use std::path::PathBuf;
 #[derive(Debug, Display, Clone)]
 pub enum FileName {
     /// A real file on the user's computer.
-    #[display("{}", "_0.display()")]
+    #[display("{}", _0.display())]
     Real(PathBuf),
     /// A named test-case in this crate's source code.
     Test(&'static str),
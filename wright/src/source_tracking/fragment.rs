@@ -2,7 +2,12 @@
 
 use super::SourceRef;
 use derive_more::Display;
-use std::{ops::Range, str::Chars, sync::Arc};
+use std::{
+    hash::{Hash, Hasher},
+    ops::Range,
+    str::Chars,
+    sync::Arc,
+};
 
 #[cfg(doc)]
 use crate::source_tracking::source::Source;
@@ -11,7 +16,7 @@ use crate::source_tracking::source::Source;
 ///
 /// This can be part of (or all of) a [Source].
 #[derive(Clone, Debug, Display)]
-#[display("{}", "self.as_str()")]
+#[display("{}", self.as_str())]
 pub struct Fragment {
     /// The [Source] that this fragment is in.
     pub source: SourceRef,
@@ -276,6 +281,15 @@ impl PartialEq for Fragment {
 
 impl Eq for Fragment {}
 
+impl Hash for Fragment {
+    /// Fragments are hashed consistently with [Fragment]'s [PartialEq] implementation -- using the id of the
+    /// [Source] and the [Fragment::range]. This makes it possible to key side tables by [Fragment].
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.source.id.hash(state);
+        self.range.hash(state);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::Fragment;
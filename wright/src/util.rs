@@ -1,3 +1,5 @@
 //! Some utility functions/modules shared by various binaries for things like checking unicode support.
 
+#[cfg(feature = "analysis")]
+pub mod edit_distance;
 pub mod supports_unicode;
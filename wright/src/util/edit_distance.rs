@@ -0,0 +1,76 @@
+//! Edit distance between strings, used to suggest names when one can't be found (i.e. "did you mean `x`?").
+
+/// Compute the [optimal string alignment distance] between two strings -- the minimum number of single character
+/// insertions, deletions, substitutions, and swaps of adjacent characters needed to change one into the other.
+///
+/// [optimal string alignment distance]: https://en.wikipedia.org/wiki/Damerau%E2%80%93Levenshtein_distance
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // Swaps need to look two rows back, so the whole distance matrix is kept.
+    let mut matrix = vec![vec![0; b.len() + 1]; a.len() + 1];
+
+    for (i, row) in matrix.iter_mut().enumerate() {
+        row[0] = i;
+    }
+
+    for (j, cell) in matrix[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let substitution = matrix[i - 1][j - 1] + usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = substitution
+                .min(matrix[i - 1][j] + 1)
+                .min(matrix[i][j - 1] + 1);
+
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(matrix[i - 2][j - 2] + 1);
+            }
+
+            matrix[i][j] = distance;
+        }
+    }
+
+    matrix[a.len()][b.len()]
+}
+
+/// Find the candidate closest to `name` by [edit_distance], if any is close enough to be a likely typo.
+///
+/// Candidates are considered close enough if they're within a third of the length of `name` (and always at least
+/// one edit). Ties are broken by whichever candidate comes first.
+pub fn best_match<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    let max_distance = (name.chars().count() / 3).max(1);
+
+    candidates
+        .into_iter()
+        .filter(|candidate| *candidate != name)
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{best_match, edit_distance};
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("println", "println"), 0);
+        assert_eq!(edit_distance("conut", "count"), 1);
+    }
+
+    #[test]
+    fn test_best_match() {
+        assert_eq!(best_match("prinln", ["print", "println", "main"]), Some("println"));
+        assert_eq!(best_match("x", ["y", "xy"]), Some("y"));
+        assert_eq!(best_match("fizzbuzz", ["main"]), None);
+    }
+}
@@ -0,0 +1,238 @@
+//! Wright's bytecode and the virtual machine that runs it.
+//!
+//! The [MIR](crate::mir) of a checked package is [compile]d to a bytecode [Module] -- a constant pool, a type
+//! table, and a function table of register-based code (see [bytecode]) -- which a [Vm] then runs:
+//!
+//! ```text
+//! let module = vm::compile(&program, &package.resolution, &package.typeck);
+//! let result = Vm::new(&module).run(&mut std::io::stdout())?;
+//! ```
+//!
+//! A module can be written to a `.wbc` file and loaded again (see [wbc]), which checks that it's well formed with
+//! [verify] before it's run. Compiled modules carry debug info that maps their instructions back to the source code
+//! they came from, which is where a [Trap] reports it happened, and which [Module::disassemble] interleaves with
+//! the instructions.
+//!
+//! Each call gets a window of registers on a shared stack, and the VM's dispatch loop keeps the running function's
+//! code and registers at hand, only looking them up again when a call starts or returns. Records and unions live
+//! on the heap behind reference counts, and are copied when they're changed while shared.
+//!
+//! Programs that do something the language doesn't define, like overflowing an integer or dividing by zero, stop
+//! with a [Trap]. So do the few things the compiler can't run yet: using the value of a `const` (constants aren't
+//! evaluated yet), and calling a trait method through a generic parameter on a value that doesn't carry its type
+//! at runtime (a floating point number or a function).
+
+use crate::{
+    analysis::{resolve::Resolution, typeck::TypeckResults},
+    mir::Program,
+};
+
+pub mod bytecode;
+mod compile;
+mod disassemble;
+mod interpreter;
+pub mod value;
+mod verify;
+pub mod wbc;
+
+pub use bytecode::Module;
+pub use disassemble::Disassembly;
+pub use interpreter::{Trap, Vm};
+pub use value::Value;
+pub use verify::verify;
+
+/// Compile the MIR of a package (which must have been checked without errors) to bytecode.
+pub fn compile(program: &Program, resolution: &Resolution, typeck: &TypeckResults) -> Module {
+    compile::compile(program, resolution, typeck)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Module, Trap, Value, Vm, bytecode::IntTy, compile, verify, wbc};
+    use crate::analysis::testing::lower_source;
+
+    /// Check and compile a single module.
+    fn compile_source(source: &'static str) -> Module {
+        let (package, program) = lower_source(source);
+        let module = compile(&program, &package.resolution, &package.typeck);
+        assert_eq!(verify(&module), Vec::<String>::new());
+
+        // Every module runs the same once it's been written out and read back in.
+        let loaded = wbc::load(&wbc::write(&module)).unwrap();
+        assert_eq!(loaded, module);
+        loaded
+    }
+
+    /// Run the `main` function of a module, returning its result and what it printed.
+    fn run(source: &'static str) -> (Result<Value, Trap>, String) {
+        let module = compile_source(source);
+        let mut out = Vec::new();
+        let result = Vm::new(&module).run(&mut out);
+        (result, String::from_utf8(out).unwrap())
+    }
+
+    /// Run the `main` function of a module, which must not trap, returning what it printed.
+    fn output(source: &'static str) -> String {
+        let (result, out) = run(source);
+        result.unwrap();
+        out
+    }
+
+    #[test]
+    fn test_arithmetic_and_calls() {
+        let out = output(
+            "use wright::io::println;\n\
+            func fib(n: u64) -> u64 { if n < 2 { n } else { fib(n - 1) + fib(n - 2) } }\n\
+            func main() {\n\
+                let zero: u8 = 0;\n\
+                let one: u16 = 1;\n\
+                println(fib(20)); println(7 / 2); println(-7 % 3); println(~zero); println(one << 15);\n\
+            }",
+        );
+
+        assert_eq!(out, "6765\n3\n-1\n255\n32768\n");
+    }
+
+    #[test]
+    fn test_loops_and_slots() {
+        let out = output(
+            "use wright::io::println;\n\
+            func main() {\n\
+                var total: u64 = 0;\n\
+                for i in 1..=10 { total += i; }\n\
+                var n = 0;\n\
+                while n < 3 { n += 1; }\n\
+                println(total); println(n);\n\
+            }",
+        );
+
+        assert_eq!(out, "55\n3\n");
+    }
+
+    #[test]
+    fn test_records_and_methods() {
+        let out = output(
+            "use wright::io::println;\n\
+            record Point { x: u64, y: u64 }\n\
+            impl Point { func grow(self: @mut Self) { self.x = self.x + 1; } }\n\
+            trait Shape { func area(self: @Self) -> u64; func double(self: @Self) -> u64 { self.area() * 2 } }\n\
+            impl Shape for Point { func area(self: @Self) -> u64 { self.x * self.y } }\n\
+            func total<T: Shape>(shape: @T) -> u64 { shape.area() + shape.double() }\n\
+            func main() {\n\
+                var p = Point { y: 3, x: 1 };\n\
+                let q = p;\n\
+                p.grow();\n\
+                println(p); println(q.x);\n\
+                println(p.double()); println(total(@p));\n\
+            }",
+        );
+
+        assert_eq!(out, "Point { x: 2, y: 3 }\n1\n12\n18\n");
+    }
+
+    #[test]
+    fn test_primitive_methods() {
+        let out = output(
+            "use wright::io::println;\n\
+            trait Show { func show(self: Self) -> u64; }\n\
+            impl Show for u64 { func show(self: Self) -> u64 { self + 1 } }\n\
+            impl Show for u8 { func show(self: Self) -> u64 { 8 } }\n\
+            impl Show for bool { func show(self: Self) -> u64 { if self { 1 } else { 0 } } }\n\
+            func twice<T: Show>(value: @T) -> u64 { value.show() * 2 }\n\
+            func echo<T>(value: T) { println(value); }\n\
+            func main() {\n\
+                let five: u64 = 5;\n\
+                let small: u8 = 1;\n\
+                let yes = true;\n\
+                let big: u64 = 18446744073709551615;\n\
+                println(twice(@five)); println(twice(@small)); println(twice(@yes)); echo(big);\n\
+            }",
+        );
+
+        assert_eq!(out, "12\n16\n2\n18446744073709551615\n");
+    }
+
+    #[test]
+    fn test_unions_and_enums() {
+        let out = output(
+            "use wright::io::println;\n\
+            union Number { small: u8, big: u64 }\n\
+            enum Color { Red, Green, Blue }\n\
+            func size(n: Number) -> u64 { match n { Number { small: 0 } => 0, Number { big } => big, _ => 1 } }\n\
+            func code(color: Color) -> u8 { match color { Color::Red => 1, Color::Green | Color::Blue => 2 } }\n\
+            func main() {\n\
+                println(size(Number { small: 0 })); println(size(Number { small: 5 })); println(size(Number { big: 9 }));\n\
+                println(code(Color::Red)); println(code(Color::Blue)); println(Color::Green);\n\
+                println(Number { big: 4 }); println(Number { small: 4 } is u8);\n\
+            }",
+        );
+
+        assert_eq!(out, "0\n1\n9\n1\n2\nColor::Green\nNumber { big: 4 }\ntrue\n");
+    }
+
+    #[test]
+    fn test_closures() {
+        let out = output(
+            "use wright::io::println;\n\
+            func apply(f: func(u8) -> u8, x: u8) -> u8 { f(x) }\n\
+            func main() {\n\
+                let offset = 3;\n\
+                var count = 0;\n\
+                let add = func(x: u8) -> u8 { count += 1; x + offset };\n\
+                println(apply(add, 4) + apply(add, 1) + count);\n\
+                let print = println;\n\
+                print(\"done\");\n\
+            }",
+        );
+
+        assert_eq!(out, "13\ndone\n");
+    }
+
+    #[test]
+    fn test_traps() {
+        let cases = [
+            ("func main() -> u8 { let x: u8 = 255; x + 1 }", "arithmetic overflow"),
+            ("func main() -> i8 { let x: i8 = -128; -x }", "arithmetic overflow"),
+            ("func main() -> u32 { let x: u32 = 0; 1 / x }", "division by zero"),
+            (
+                "func main() -> u8 { let x: u8 = 8; 1 << x }",
+                "shift by at least the number of bits in the type",
+            ),
+            (
+                "func f(n: u64) -> u64 { f(n + 1) }\nfunc main() -> u64 { f(0) }",
+                "stack overflow",
+            ),
+        ];
+
+        for (source, message) in cases {
+            let (result, _) = run(source);
+            assert_eq!(result.unwrap_err().message, message, "{source}");
+        }
+    }
+
+    #[test]
+    fn test_debug_info() {
+        let module = compile_source("func main() -> u8 {\nlet x: u8 = 255;\nlet y = x + 1;\ny\n}");
+        let trap = Vm::new(&module).run(&mut Vec::new()).unwrap_err();
+
+        let debug = module.debug.as_ref().unwrap();
+        let location = debug
+            .location(module.entry.unwrap(), trap.offset as u32)
+            .unwrap();
+        assert_eq!((location.line, location.column), (3, 9));
+    }
+
+    #[test]
+    fn test_call_with_arguments() {
+        let module = compile_source("func add(a: i64, b: i64) -> i64 { a + b }");
+        let add = module.function_named("add").unwrap();
+        let i64 = IntTy {
+            bits: 64,
+            signed: true,
+        };
+
+        let args = [Value::Int(2, i64), Value::Int(-5, i64)];
+        let result = Vm::new(&module).call(add, &args, &mut Vec::new());
+        assert_eq!(result, Ok(Value::Int(-3, i64)));
+    }
+}
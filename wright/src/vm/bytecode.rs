@@ -0,0 +1,735 @@
+//! The Wright bytecode: a [Module] of [Function]s, each a flat list of [Instruction]s over numbered registers.
+//!
+//! Every function has a fixed number of registers, each with a [Type] from the module's type table, and its
+//! parameters arrive in the first of them. Instructions refer to registers, to entries of the module's constant
+//! pool, type table, and function table, and to the field paths and switch tables of their function -- all by
+//! index, so a module is plain data that can be written out and read back in.
+
+use super::disassemble::Disassembly;
+use crate::{analysis::builtins::Builtin, source_tracking::SourceMap};
+use std::hash::{Hash, Hasher};
+
+/// A compiled program.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Module {
+    /// The constant pool, by [ConstId].
+    pub constants: Vec<Constant>,
+    /// The type table, by [TypeId].
+    pub types: Vec<Type>,
+    /// The function table, by [FunctionId].
+    pub functions: Vec<Function>,
+    /// The dispatch tables of the trait methods that are called through generic parameters, by [MethodId].
+    pub methods: Vec<MethodTable>,
+    /// The function that running the module starts at -- the root module's `main` function, if it has one.
+    pub entry: Option<FunctionId>,
+    /// Where each function's instructions came from in the source code, unless it's been left out.
+    pub debug: Option<DebugInfo>,
+}
+
+impl Module {
+    /// Get a function.
+    pub fn function(&self, id: FunctionId) -> &Function {
+        &self.functions[id.0 as usize]
+    }
+
+    /// Get a type from the type table.
+    pub fn ty(&self, id: TypeId) -> &Type {
+        &self.types[id.0 as usize]
+    }
+
+    /// Get a constant from the constant pool.
+    pub fn constant(&self, id: ConstId) -> &Constant {
+        &self.constants[id.0 as usize]
+    }
+
+    /// Disassemble this module into a listing of its instructions, with lines of source code from the given
+    /// sources interleaved with them (using the debug info).
+    pub fn disassemble<'a>(&'a self, sources: Option<&'a SourceMap>) -> Disassembly<'a> {
+        Disassembly {
+            module: self,
+            sources,
+        }
+    }
+
+    /// Find a function by its name, i.e. `main` or `shapes::Square::area`.
+    pub fn function_named(&self, name: &str) -> Option<FunctionId> {
+        (self.functions.iter())
+            .position(|function| function.name == name)
+            .map(|index| FunctionId(index as u32))
+    }
+}
+
+/// Where the instructions of a [Module] came from in the source code.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DebugInfo {
+    /// The name of every source file, by [Location::file].
+    pub files: Vec<String>,
+    /// The locations of each function's instructions, by [FunctionId], sorted by offset. A location applies to
+    /// the instructions from its offset up to the next location's.
+    pub functions: Vec<Vec<Location>>,
+}
+
+impl DebugInfo {
+    /// Get where the instruction at an offset in a function came from.
+    pub fn location(&self, function: FunctionId, offset: u32) -> Option<&Location> {
+        let locations = self.functions.get(function.0 as usize)?;
+        let index = locations.partition_point(|location| location.offset <= offset);
+        locations.get(index.checked_sub(1)?)
+    }
+
+    /// Get the name of the file a location is in.
+    pub fn file(&self, location: &Location) -> &str {
+        self.files
+            .get(location.file as usize)
+            .map_or("<unknown>", String::as_str)
+    }
+}
+
+/// The location in the source code of a run of instructions in a [Function].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Location {
+    /// The index of the first instruction of the run.
+    pub offset: u32,
+    /// The index of the file in [DebugInfo::files].
+    pub file: u32,
+    /// The line number (starting at 1).
+    pub line: u32,
+    /// The column number, in characters (starting at 1).
+    pub column: u32,
+}
+
+/// The index of a [Function] in a [Module].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FunctionId(pub u32);
+
+/// The index of a [Type] in the type table of a [Module].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TypeId(pub u32);
+
+/// The index of a [Constant] in the constant pool of a [Module].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ConstId(pub u32);
+
+/// The index of a [MethodTable] in a [Module].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MethodId(pub u32);
+
+/// A register of a [Function].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Reg(pub u32);
+
+/// The index of a field path in a [Function] (see [Function::paths]).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PathId(pub u32);
+
+/// The index of a [SwitchTable] in a [Function].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SwitchId(pub u32);
+
+/// A function, method, or closure.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Function {
+    /// The name of the function, as it's named in the MIR.
+    pub name: String,
+    /// The number of parameters, which are passed in the first registers. Closures take the values they capture
+    /// before their own parameters.
+    pub params: u32,
+    /// The type of every register, by [Reg].
+    pub registers: Vec<TypeId>,
+    /// The type of the returned value.
+    pub ret: TypeId,
+    /// The instructions. Jumps go to indices in this list.
+    pub code: Vec<Instruction>,
+    /// The field paths used by [Instruction::Load], [Instruction::Store], and [Instruction::Offset], by [PathId].
+    /// Each step is the index of a field of a record, or of a variant of a union.
+    pub paths: Vec<Vec<u32>>,
+    /// The tables used by [Instruction::Switch], by [SwitchId].
+    pub switches: Vec<SwitchTable>,
+}
+
+impl Function {
+    /// Get a field path.
+    pub fn path(&self, id: PathId) -> &[u32] {
+        &self.paths[id.0 as usize]
+    }
+
+    /// Get a switch table.
+    pub fn switch(&self, id: SwitchId) -> &SwitchTable {
+        &self.switches[id.0 as usize]
+    }
+}
+
+/// Where an [Instruction::Switch] jumps for each value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SwitchTable {
+    /// The instruction to jump to for each value, sorted by value. Unsigned integers are compared by their bits,
+    /// and enums by the index of their variant.
+    pub cases: Vec<(i64, u32)>,
+    /// The instruction to jump to for any other value.
+    pub default: u32,
+}
+
+impl SwitchTable {
+    /// Get the instruction to jump to for a value.
+    pub fn target(&self, value: i64) -> u32 {
+        match self.cases.binary_search_by_key(&value, |(case, _)| *case) {
+            Ok(index) => self.cases[index].1,
+            Err(_) => self.default,
+        }
+    }
+}
+
+/// The functions that a trait method runs, for each type of receiver it's called with.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MethodTable {
+    /// The name of the method, i.e. `Shape::area`.
+    pub name: String,
+    /// The function for each record, union, and enum type that implements the trait, sorted by type.
+    pub impls: Vec<(TypeId, FunctionId)>,
+}
+
+impl MethodTable {
+    /// Get the function that runs for a receiver of the given type.
+    pub fn find(&self, ty: TypeId) -> Option<FunctionId> {
+        (self.impls.binary_search_by_key(&ty, |(ty, _)| *ty))
+            .ok()
+            .map(|index| self.impls[index].1)
+    }
+}
+
+/// A constant in the constant pool.
+#[derive(Clone, Debug)]
+pub enum Constant {
+    /// The value of expressions that don't produce one.
+    Void,
+    /// `true` or `false`.
+    Bool(bool),
+    /// An integer of the given type, in the representation of [Instruction::Int].
+    Int(i64, IntTy),
+    /// A floating point number.
+    Float(f64),
+    /// A string.
+    Str(String),
+    /// A function.
+    Function(FunctionId),
+    /// A builtin function.
+    Builtin(Builtin),
+    /// A variant of an enum type, by its index.
+    Variant(TypeId, u32),
+}
+
+// Floats are compared by their bits, so that equal constants can be shared in the constant pool.
+impl PartialEq for Constant {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Constant::Void, Constant::Void) => true,
+            (Constant::Bool(a), Constant::Bool(b)) => a == b,
+            (Constant::Int(a, a_ty), Constant::Int(b, b_ty)) => a == b && a_ty == b_ty,
+            (Constant::Float(a), Constant::Float(b)) => a.to_bits() == b.to_bits(),
+            (Constant::Str(a), Constant::Str(b)) => a == b,
+            (Constant::Function(a), Constant::Function(b)) => a == b,
+            (Constant::Builtin(a), Constant::Builtin(b)) => a == b,
+            (Constant::Variant(a, i), Constant::Variant(b, j)) => a == b && i == j,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Constant {}
+
+impl Hash for Constant {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+
+        match self {
+            Constant::Void => {}
+            Constant::Bool(value) => value.hash(state),
+            Constant::Int(value, ty) => (value, ty).hash(state),
+            Constant::Float(value) => value.to_bits().hash(state),
+            Constant::Str(value) => value.hash(state),
+            Constant::Function(function) => function.hash(state),
+            Constant::Builtin(builtin) => builtin.hash(state),
+            Constant::Variant(ty, index) => (ty, index).hash(state),
+        }
+    }
+}
+
+/// An entry of the type table, which gives the type of every register and the layout of records and unions.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Type {
+    /// The type of expressions that don't produce a value.
+    Void,
+    /// `bool`.
+    Bool,
+    /// `char`.
+    Char,
+    /// The type of string literals.
+    Str,
+    /// An integer type.
+    Int(IntTy),
+    /// A floating point type.
+    Float(FloatTy),
+    /// A reference to a value of a type.
+    Reference(TypeId),
+    /// A function, closure, or builtin function.
+    Function,
+    /// A record, with its name (including its generic arguments) and the name and type of each field, in order.
+    Record {
+        /// The name of the record.
+        name: String,
+        /// The fields.
+        fields: Vec<(String, TypeId)>,
+    },
+    /// A union, with its name (including its generic arguments) and the name and type of each variant, in order.
+    Union {
+        /// The name of the union.
+        name: String,
+        /// The variants.
+        variants: Vec<(String, TypeId)>,
+    },
+    /// An enum, with its name and the names of its variants, in order.
+    Enum {
+        /// The name of the enum.
+        name: String,
+        /// The variants.
+        variants: Vec<String>,
+    },
+    /// A generic parameter or abstract type, which can hold a value of any type.
+    Opaque(String),
+}
+
+/// An integer type. Integers are held in an `i64`: signed integers sign extended, and unsigned integers zero
+/// extended (so `u64`s above `i64::MAX` are held as their bits).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct IntTy {
+    /// The number of bits: 8, 16, 32, or 64.
+    pub bits: u8,
+    /// Whether the type is signed.
+    pub signed: bool,
+}
+
+impl IntTy {
+    /// Get the smallest and largest values of this type.
+    pub const fn bounds(self) -> (i128, i128) {
+        match self.signed {
+            true => (-(1 << (self.bits - 1)), (1 << (self.bits - 1)) - 1),
+            false => (0, (1 << self.bits) - 1),
+        }
+    }
+
+    /// Get the value of an integer of this type.
+    pub const fn widen(self, value: i64) -> i128 {
+        match self.signed {
+            true => value as i128,
+            false => value as u64 as i128,
+        }
+    }
+
+    /// Get the representation of a value of this type, or [None] if it's out of range.
+    pub const fn narrow(self, value: i128) -> Option<i64> {
+        let (min, max) = self.bounds();
+
+        match value >= min && value <= max {
+            true => Some(value as u64 as i64),
+            false => None,
+        }
+    }
+
+    /// Get the representation of a value truncated to this type's number of bits.
+    pub const fn wrap(self, value: i128) -> i64 {
+        let shift = 128 - self.bits as u32;
+
+        match self.signed {
+            true => ((value << shift) >> shift) as i64,
+            false => (((value << shift) as u128) >> shift) as u64 as i64,
+        }
+    }
+}
+
+/// A floating point type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FloatTy {
+    /// `f32`. Results are rounded to single precision.
+    F32,
+    /// `f64`.
+    F64,
+}
+
+/// An operation on two integers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum IntOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    And,
+    Or,
+    Xor,
+    Shl,
+    Shr,
+}
+
+/// An operation on two floating point numbers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum FloatOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+/// An operation on two `bool`s.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum BoolOp {
+    And,
+    Or,
+    Xor,
+}
+
+/// A comparison.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// How the operands of an [Instruction::Compare] are compared.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CompareKind {
+    /// As integers of the given type.
+    Int(IntTy),
+    /// As floating point numbers.
+    Float,
+    /// As values of any other type: `bool`s, `char`s, strings, and enums can be ordered, and anything else can
+    /// only be compared for equality.
+    Value,
+}
+
+/// An instruction. Registers are read before the destination register is written, so the destination can be one
+/// of the operands.
+///
+/// Arithmetic traps when the result doesn't fit in its type, when dividing by zero, and when shifting by at least
+/// the number of bits in the type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Instruction {
+    /// Load a constant.
+    LoadConst {
+        /// The destination.
+        dst: Reg,
+        /// The constant.
+        constant: ConstId,
+    },
+    /// Copy a register.
+    Move {
+        /// The destination.
+        dst: Reg,
+        /// The source.
+        src: Reg,
+    },
+    /// An operation on two integers of the given type.
+    Int {
+        /// The operation.
+        op: IntOp,
+        /// The type of the integers.
+        ty: IntTy,
+        /// The destination.
+        dst: Reg,
+        /// The left operand.
+        lhs: Reg,
+        /// The right operand.
+        rhs: Reg,
+    },
+    /// Negate an integer of the given type.
+    IntNeg {
+        /// The type of the integer.
+        ty: IntTy,
+        /// The destination.
+        dst: Reg,
+        /// The operand.
+        src: Reg,
+    },
+    /// Invert the bits of an integer of the given type.
+    IntNot {
+        /// The type of the integer.
+        ty: IntTy,
+        /// The destination.
+        dst: Reg,
+        /// The operand.
+        src: Reg,
+    },
+    /// An operation on two floating point numbers of the given type.
+    Float {
+        /// The operation.
+        op: FloatOp,
+        /// The type of the numbers.
+        ty: FloatTy,
+        /// The destination.
+        dst: Reg,
+        /// The left operand.
+        lhs: Reg,
+        /// The right operand.
+        rhs: Reg,
+    },
+    /// Negate a floating point number.
+    FloatNeg {
+        /// The destination.
+        dst: Reg,
+        /// The operand.
+        src: Reg,
+    },
+    /// An operation on two `bool`s.
+    Bool {
+        /// The operation.
+        op: BoolOp,
+        /// The destination.
+        dst: Reg,
+        /// The left operand.
+        lhs: Reg,
+        /// The right operand.
+        rhs: Reg,
+    },
+    /// Invert a `bool`.
+    BoolNot {
+        /// The destination.
+        dst: Reg,
+        /// The operand.
+        src: Reg,
+    },
+    /// Compare two values, producing a `bool`.
+    Compare {
+        /// The comparison.
+        op: CompareOp,
+        /// How the values are compared.
+        kind: CompareKind,
+        /// The destination.
+        dst: Reg,
+        /// The left operand.
+        lhs: Reg,
+        /// The right operand.
+        rhs: Reg,
+    },
+    /// Allocate a fresh cell of memory, and put a reference to it in a register.
+    Alloc {
+        /// The destination.
+        dst: Reg,
+    },
+    /// Read the value at a field path from what a reference points to.
+    Load {
+        /// The destination.
+        dst: Reg,
+        /// The reference.
+        ptr: Reg,
+        /// The path.
+        path: PathId,
+    },
+    /// Write a value to a field path of what a reference points to. The last step of the path can be a different
+    /// variant of a union than it holds, which changes the variant.
+    Store {
+        /// The reference.
+        ptr: Reg,
+        /// The path.
+        path: PathId,
+        /// The value.
+        src: Reg,
+    },
+    /// Make a reference to a field path of what a reference points to.
+    Offset {
+        /// The destination.
+        dst: Reg,
+        /// The reference.
+        ptr: Reg,
+        /// The path.
+        path: PathId,
+    },
+    /// Release the value in the cell a reference points to, once the binding it holds goes out of scope.
+    Drop {
+        /// The reference.
+        ptr: Reg,
+    },
+    /// Make a record from its fields, which are in consecutive registers.
+    MakeRecord {
+        /// The destination.
+        dst: Reg,
+        /// The type of the record.
+        ty: TypeId,
+        /// The register holding the first field.
+        start: Reg,
+        /// The number of fields.
+        count: u32,
+    },
+    /// Read a field of a record.
+    GetField {
+        /// The destination.
+        dst: Reg,
+        /// The record.
+        src: Reg,
+        /// The index of the field.
+        field: u32,
+    },
+    /// Make a union holding one of its variants.
+    MakeUnion {
+        /// The destination.
+        dst: Reg,
+        /// The type of the union.
+        ty: TypeId,
+        /// The index of the variant.
+        variant: u32,
+        /// The value of the variant.
+        src: Reg,
+    },
+    /// Check if a union holds a variant, producing a `bool`.
+    IsVariant {
+        /// The destination.
+        dst: Reg,
+        /// The union.
+        src: Reg,
+        /// The index of the variant.
+        variant: u32,
+    },
+    /// Read the value of a union's variant, trapping if the union holds a different variant.
+    GetVariant {
+        /// The destination.
+        dst: Reg,
+        /// The union.
+        src: Reg,
+        /// The index of the variant.
+        variant: u32,
+    },
+    /// Make a closure, capturing values from consecutive registers.
+    MakeClosure {
+        /// The destination.
+        dst: Reg,
+        /// The function of the closure.
+        function: FunctionId,
+        /// The register holding the first captured value.
+        start: Reg,
+        /// The number of captured values.
+        count: u32,
+    },
+    /// Call a function, closure, or builtin function in a register, with arguments in consecutive registers.
+    Call {
+        /// The destination of the returned value.
+        dst: Reg,
+        /// The register holding what's called.
+        callee: Reg,
+        /// The register holding the first argument.
+        start: Reg,
+        /// The number of arguments.
+        count: u32,
+    },
+    /// Call a function, with arguments in consecutive registers.
+    CallDirect {
+        /// The destination of the returned value.
+        dst: Reg,
+        /// The function.
+        function: FunctionId,
+        /// The register holding the first argument.
+        start: Reg,
+        /// The number of arguments.
+        count: u32,
+    },
+    /// Call a trait method, with the function found by the type of the first argument (the receiver, which is
+    /// followed through references).
+    CallMethod {
+        /// The destination of the returned value.
+        dst: Reg,
+        /// The method.
+        method: MethodId,
+        /// The register holding the first argument.
+        start: Reg,
+        /// The number of arguments.
+        count: u32,
+    },
+    /// Write a value of the given type to the output, for `wright::io::print` and `wright::io::println`.
+    Print {
+        /// The destination of the (void) result.
+        dst: Reg,
+        /// The value.
+        src: Reg,
+        /// The type of the value.
+        ty: TypeId,
+        /// Whether a newline is written after the value.
+        newline: bool,
+    },
+    /// Continue at an instruction.
+    Jump {
+        /// The index of the instruction.
+        target: u32,
+    },
+    /// Continue at an instruction if a `bool` is `true`.
+    JumpIf {
+        /// The condition.
+        condition: Reg,
+        /// The index of the instruction.
+        target: u32,
+    },
+    /// Continue at an instruction if a `bool` is `false`.
+    JumpIfNot {
+        /// The condition.
+        condition: Reg,
+        /// The index of the instruction.
+        target: u32,
+    },
+    /// Continue at the instruction that a switch table gives for an integer or enum.
+    Switch {
+        /// The value.
+        src: Reg,
+        /// The switch table.
+        table: SwitchId,
+    },
+    /// Return a value from the function.
+    Return {
+        /// The value.
+        src: Reg,
+    },
+    /// Stop with an error, described by a string constant.
+    Trap {
+        /// The description.
+        message: ConstId,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IntTy;
+
+    #[test]
+    fn test_int_ty() {
+        let u8 = IntTy {
+            bits: 8,
+            signed: false,
+        };
+
+        let i8 = IntTy {
+            bits: 8,
+            signed: true,
+        };
+
+        let u64 = IntTy {
+            bits: 64,
+            signed: false,
+        };
+
+        assert_eq!(u8.bounds(), (0, 255));
+        assert_eq!(i8.bounds(), (-128, 127));
+        assert_eq!(u8.narrow(256), None);
+        assert_eq!(i8.narrow(-128), Some(-128));
+        assert_eq!(u8.wrap(-1), 255);
+        assert_eq!(i8.wrap(255), -1);
+
+        // `u64`s above `i64::MAX` are held as their bits.
+        let max = u64.narrow(u64::MAX as i128).unwrap();
+        assert_eq!(max, -1);
+        assert_eq!(u64.widen(max), u64::MAX as i128);
+    }
+}
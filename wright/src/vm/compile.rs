@@ -0,0 +1,1134 @@
+//! Compiling the MIR to bytecode.
+//!
+//! Every MIR value gets a register of its own (the value's number), followed by a register for each slot, which
+//! holds a reference to the slot's memory, and then the temporary registers that constants and arguments are
+//! moved into. Blocks are laid out in order, so jumps to the next block fall through, and the arguments of jumps
+//! are moved into the registers of the target block's parameters.
+//!
+//! Each instruction is attributed to the MIR instruction (or terminator) it's compiled from, whose span gives its
+//! [Location] in the debug info.
+
+use super::bytecode::{
+    BoolOp, CompareKind, CompareOp, ConstId, Constant, DebugInfo, FloatOp, FloatTy, Function,
+    FunctionId, Instruction, IntOp, IntTy, Location, MethodId, MethodTable, Module, PathId, Reg,
+    SwitchId, SwitchTable, Type, TypeId,
+};
+use crate::{
+    analysis::{
+        builtins::Builtin,
+        resolve::{DefId, DefKind, Resolution},
+        typeck::{TypeckResults, ty::Ty},
+    },
+    ast::{
+        expr::{BinaryOperation, UnaryOperation},
+        ty::AtomicTyVariant,
+    },
+    mir::{
+        BlockId, Body, Const, Inst, Jump, Operand, Place, PlaceBase, Program, Projection, Rvalue,
+        Terminator, Value, qualified_name,
+    },
+    source_tracking::fragment::Fragment,
+};
+use std::collections::HashMap;
+
+/// Compile every body of a program.
+pub fn compile(program: &Program, resolution: &Resolution, typeck: &TypeckResults) -> Module {
+    let mut compiler = Compiler {
+        program,
+        resolution,
+        typeck,
+        module: Module::default(),
+        debug: DebugInfo::default(),
+        files: HashMap::new(),
+        functions: HashMap::new(),
+        types: HashMap::new(),
+        type_tys: Vec::new(),
+        constants: HashMap::new(),
+        methods: HashMap::new(),
+    };
+
+    for (index, body) in program.bodies.iter().enumerate() {
+        if let Some(def) = body.def {
+            compiler.functions.insert(def, FunctionId(index as u32));
+        }
+    }
+
+    for body in &program.bodies {
+        let (function, locations) = FunctionCompiler::new(&mut compiler, body).compile();
+        compiler.module.functions.push(function);
+        compiler.debug.functions.push(locations);
+    }
+
+    compiler.fill_method_tables();
+
+    compiler.module.entry = (program.bodies.iter())
+        .position(|body| body.def.is_some() && body.name == "main")
+        .map(|index| FunctionId(index as u32));
+
+    compiler.module.debug = Some(compiler.debug);
+    compiler.module
+}
+
+/// The state of compiling a program.
+struct Compiler<'a> {
+    /// The program being compiled.
+    program: &'a Program,
+    /// The result of name resolution.
+    resolution: &'a Resolution,
+    /// The result of type checking.
+    typeck: &'a TypeckResults,
+    /// The module being built.
+    module: Module,
+    /// The debug info of the module being built.
+    debug: DebugInfo,
+    /// The index of every source file in the debug info, by name.
+    files: HashMap<String, u32>,
+    /// The function compiled from the body of every function and method.
+    functions: HashMap<DefId, FunctionId>,
+    /// The entry of the type table for every type.
+    types: HashMap<Ty, TypeId>,
+    /// The type of every entry of the type table, by [TypeId].
+    type_tys: Vec<Ty>,
+    /// The entry of the constant pool for every constant.
+    constants: HashMap<Constant, ConstId>,
+    /// The dispatch table of every trait method that's called through a generic parameter.
+    methods: HashMap<DefId, MethodId>,
+}
+
+impl Compiler<'_> {
+    /// Get the entry of the type table for a type, adding it (and the types of its fields) if it's new.
+    fn ty(&mut self, ty: &Ty) -> TypeId {
+        if let Some(id) = self.types.get(ty) {
+            return *id;
+        }
+
+        // Records and unions can contain references to themselves, so their entry is added before their fields'.
+        let id = TypeId(self.module.types.len() as u32);
+        let name = ty.display(self.resolution).to_string();
+        self.module.types.push(Type::Opaque(name.clone()));
+        self.type_tys.push(ty.clone());
+        self.types.insert(ty.clone(), id);
+
+        let entry = match ty {
+            Ty::Atomic(AtomicTyVariant::Bool) => Type::Bool,
+            Ty::Atomic(AtomicTyVariant::Char) => Type::Char,
+            Ty::Atomic(AtomicTyVariant::F32) => Type::Float(FloatTy::F32),
+            Ty::Atomic(AtomicTyVariant::F64) => Type::Float(FloatTy::F64),
+            Ty::Atomic(variant) => match int_ty(*variant) {
+                Some(int) => Type::Int(int),
+                None => Type::Opaque(name),
+            },
+
+            Ty::Reference { target, .. } => Type::Reference(self.ty(target)),
+            Ty::Function { .. } => Type::Function,
+            Ty::Str => Type::Str,
+            Ty::Void => Type::Void,
+
+            Ty::Named { def, .. } if self.resolution.def(*def).kind == DefKind::Enum => {
+                Type::Enum {
+                    name,
+                    variants: (self.resolution.variants(*def).iter())
+                        .map(|variant| self.resolution.def(*variant).name.clone())
+                        .collect(),
+                }
+            }
+
+            Ty::Named { .. } => match self.typeck.fields(ty) {
+                Some((is_union, fields)) => {
+                    let fields = (fields.into_iter())
+                        .map(|(field, field_ty)| (field.to_owned(), self.ty(&field_ty)))
+                        .collect();
+
+                    match is_union {
+                        true => Type::Union {
+                            name,
+                            variants: fields,
+                        },
+                        false => Type::Record { name, fields },
+                    }
+                }
+
+                // Abstract types can hold anything.
+                None => Type::Opaque(name),
+            },
+
+            Ty::Param(_) | Ty::Infer(_) | Ty::Integer(_) | Ty::Error => Type::Opaque(name),
+        };
+
+        self.module.types[id.0 as usize] = entry;
+        id
+    }
+
+    /// Get the entry of the constant pool for a constant, adding it if it's new.
+    fn constant(&mut self, constant: Constant) -> ConstId {
+        if let Some(id) = self.constants.get(&constant) {
+            return *id;
+        }
+
+        let id = ConstId(self.module.constants.len() as u32);
+        self.module.constants.push(constant.clone());
+        self.constants.insert(constant, id);
+        id
+    }
+
+    /// Get the dispatch table of a trait method, adding it if it's new. Its functions are filled in once every
+    /// type is known (see [Compiler::fill_method_tables]).
+    fn method(&mut self, method: DefId) -> MethodId {
+        if let Some(id) = self.methods.get(&method) {
+            return *id;
+        }
+
+        let id = MethodId(self.module.methods.len() as u32);
+
+        self.module.methods.push(MethodTable {
+            name: self.name(method),
+            impls: Vec::new(),
+        });
+
+        self.methods.insert(method, id);
+        id
+    }
+
+    /// Fill in the function that every trait method in a dispatch table runs for every type that implements the
+    /// trait and that values carry at runtime (see [Value::runtime_ty](super::Value::runtime_ty)).
+    fn fill_method_tables(&mut self) {
+        for (method, id) in &self.methods {
+            let mut impls = Vec::new();
+
+            for (index, ty) in self.type_tys.iter().enumerate() {
+                let entry = &self.module.types[index];
+
+                let is_carried = matches!(
+                    entry,
+                    Type::Record { .. }
+                        | Type::Union { .. }
+                        | Type::Enum { .. }
+                        | Type::Bool
+                        | Type::Char
+                        | Type::Str
+                        | Type::Int(_)
+                );
+
+                if !is_carried {
+                    continue;
+                }
+
+                let function = (self.typeck.implementation(*method, ty))
+                    .and_then(|implementation| self.functions.get(&implementation));
+
+                if let Some(function) = function {
+                    impls.push((TypeId(index as u32), *function));
+                }
+            }
+
+            self.module.methods[id.0 as usize].impls = impls;
+        }
+    }
+
+    /// Get the location in the source code that a span starts at (except for its offset, which is left as 0).
+    fn location(&mut self, span: &Fragment) -> Location {
+        let name = span.source.name().to_string();
+
+        let file = *self.files.entry(name).or_insert_with_key(|name| {
+            self.debug.files.push(name.clone());
+            self.debug.files.len() as u32 - 1
+        });
+
+        let line = span.source.line_index(span.range.start);
+        let line_start = span.source.line_starts()[line];
+        let column = span.source.source().as_str()[line_start..span.range.start]
+            .chars()
+            .count();
+
+        Location {
+            offset: 0,
+            file,
+            line: line as u32 + 1,
+            column: column as u32 + 1,
+        }
+    }
+
+    /// Get the name of a definition, as it's printed in the MIR.
+    fn name(&self, def: DefId) -> String {
+        match self.program.names.get(&def) {
+            Some(name) => name.clone(),
+            None => qualified_name(self.resolution, def),
+        }
+    }
+
+    /// Get the index and type of a field of a record (or a variant of a union).
+    fn field(&self, ty: &Ty, name: &str) -> (u32, Ty) {
+        let (_, fields) = self.typeck.fields(ty).unwrap_or_default();
+
+        (fields.into_iter().enumerate())
+            .find(|(_, (field, _))| *field == name)
+            .map(|(index, (_, ty))| (index as u32, ty))
+            .unwrap_or((0, Ty::Error))
+    }
+
+    /// Check if a type is a union.
+    fn is_union(&self, ty: &Ty) -> bool {
+        (self.typeck.fields(ty)).is_some_and(|(is_union, _)| is_union)
+    }
+}
+
+/// Get the [IntTy] of an atomic type, if it's an integer type.
+fn int_ty(variant: AtomicTyVariant) -> Option<IntTy> {
+    let (_, max) = variant.integer_bounds()?;
+    let bits = (max.ilog2() + 1) as u8;
+    let signed = variant.is_signed_integer();
+
+    Some(IntTy {
+        bits: if signed { bits + 1 } else { bits },
+        signed,
+    })
+}
+
+/// Check if a type mentions a generic parameter anywhere.
+fn is_generic(ty: &Ty) -> bool {
+    match ty {
+        Ty::Param(_) => true,
+        Ty::Reference { target, .. } => is_generic(target),
+        Ty::Named { args, .. } => args.iter().any(is_generic),
+        Ty::Function { params, ret } => params.iter().any(is_generic) || is_generic(ret),
+        _ => false,
+    }
+}
+
+/// Get the type of the value a reference (or a reference to a reference, and so on) points to.
+fn strip_references(mut ty: &Ty) -> &Ty {
+    while let Ty::Reference { target, .. } = ty {
+        ty = target;
+    }
+
+    ty
+}
+
+/// What a call with a constant callee runs.
+enum Callee {
+    /// A function.
+    Function(FunctionId),
+    /// A trait method, found by the type of the receiver when it's called.
+    Method(MethodId),
+    /// A builtin function.
+    Builtin(Builtin),
+    /// Nothing that can run -- the call traps with this message.
+    Missing(String),
+}
+
+/// The state of compiling a body.
+struct FunctionCompiler<'c, 'a> {
+    /// The compiler of the program.
+    compiler: &'c mut Compiler<'a>,
+    /// The body.
+    body: &'a Body,
+    /// The type of every register.
+    registers: Vec<TypeId>,
+    /// The instructions so far.
+    code: Vec<Instruction>,
+    /// The field paths used so far.
+    paths: Vec<Vec<u32>>,
+    /// The switch tables so far, which jump to blocks until they're fixed up.
+    switches: Vec<SwitchTable>,
+    /// Where every block starts, once it's been compiled.
+    block_starts: Vec<u32>,
+    /// The instructions that jump to blocks, which are fixed up to jump to where the block starts.
+    block_jumps: Vec<usize>,
+    /// Ranges of temporary registers with the given types, and whether they're used by the current instruction.
+    temps: Vec<(Vec<TypeId>, Reg, bool)>,
+    /// The location that instructions are attributed to.
+    location: Option<Location>,
+    /// The location of every run of instructions so far.
+    locations: Vec<Location>,
+}
+
+impl<'c, 'a> FunctionCompiler<'c, 'a> {
+    /// Set up the compilation of a body.
+    fn new(compiler: &'c mut Compiler<'a>, body: &'a Body) -> Self {
+        let mut registers: Vec<TypeId> = (body.values.iter()).map(|ty| compiler.ty(ty)).collect();
+
+        for slot in &body.slots {
+            registers.push(compiler.ty(&Ty::reference(slot.ty.clone(), true)));
+        }
+
+        FunctionCompiler {
+            compiler,
+            body,
+            registers,
+            code: Vec::new(),
+            paths: Vec::new(),
+            switches: Vec::new(),
+            block_starts: Vec::new(),
+            block_jumps: Vec::new(),
+            temps: Vec::new(),
+            location: None,
+            locations: Vec::new(),
+        }
+    }
+
+    /// Compile the body, returning the function and the location of every run of its instructions.
+    fn compile(mut self) -> (Function, Vec<Location>) {
+        // Parameters are the first values, so they arrive in the first registers.
+        debug_assert!(
+            (self.body.params.iter().enumerate()).all(|(index, param)| param.0 as usize == index)
+        );
+
+        // Slots are allocated where the body starts.
+        if let Some(entry) = self.body.blocks.first() {
+            self.locate(entry.spans.first().unwrap_or(&entry.terminator_span));
+        }
+
+        for slot in 0..self.body.slots.len() {
+            let dst = self.slot(slot as u32);
+            self.emit(Instruction::Alloc { dst });
+        }
+
+        let blocks: Vec<BlockId> = self.body.block_ids().collect();
+
+        for (index, block) in blocks.iter().enumerate() {
+            self.block_starts.push(self.code.len() as u32);
+            let next = blocks.get(index + 1).copied();
+
+            let data = self.body.block(*block);
+
+            for (inst, span) in data.insts.iter().zip(&data.spans) {
+                self.release_temps();
+                self.locate(span);
+                self.inst(inst);
+            }
+
+            self.release_temps();
+            self.locate(&data.terminator_span);
+            self.terminator(&data.terminator, next);
+        }
+
+        // Fix up jumps to blocks, which hold the ID of the block until now.
+        for index in std::mem::take(&mut self.block_jumps) {
+            match &mut self.code[index] {
+                Instruction::Jump { target }
+                | Instruction::JumpIf { target, .. }
+                | Instruction::JumpIfNot { target, .. } => {
+                    *target = self.block_starts[*target as usize]
+                }
+                _ => unreachable!("only jumps jump to blocks"),
+            }
+        }
+
+        for table in &mut self.switches {
+            for (_, target) in &mut table.cases {
+                *target = self.block_starts[*target as usize];
+            }
+
+            table.default = self.block_starts[table.default as usize];
+        }
+
+        let ret = self.compiler.ty(&self.body.ret);
+
+        let function = Function {
+            name: self.body.name.clone(),
+            params: self.body.params.len() as u32,
+            registers: self.registers,
+            ret,
+            code: self.code,
+            paths: self.paths,
+            switches: self.switches,
+        };
+
+        (function, self.locations)
+    }
+
+    /// Attribute the instructions added from now on to the source code of a span.
+    fn locate(&mut self, span: &Fragment) {
+        self.location = Some(self.compiler.location(span));
+    }
+
+    /// Add an instruction.
+    fn emit(&mut self, instruction: Instruction) {
+        let offset = self.code.len() as u32;
+
+        if let Some(location) = self.location {
+            let is_new = (self.locations.last()).is_none_or(|last| {
+                (last.file, last.line, last.column)
+                    != (location.file, location.line, location.column)
+            });
+
+            if is_new {
+                self.locations.push(Location { offset, ..location });
+            }
+        }
+
+        self.code.push(instruction);
+    }
+
+    /// Add a jump to a block.
+    fn emit_block_jump(&mut self, instruction: Instruction) {
+        self.block_jumps.push(self.code.len());
+        self.emit(instruction);
+    }
+
+    /// Add an instruction that traps with a message.
+    fn emit_trap(&mut self, message: String) {
+        let message = self.compiler.constant(Constant::Str(message));
+        self.emit(Instruction::Trap { message });
+    }
+
+    /// Get the register holding a reference to a slot.
+    fn slot(&self, slot: u32) -> Reg {
+        Reg((self.body.values.len() + slot as usize) as u32)
+    }
+
+    /// Get a range of consecutive temporary registers with the given types, which is free until the next
+    /// instruction of the MIR.
+    fn temps(&mut self, tys: Vec<TypeId>) -> Reg {
+        for (range, start, used) in &mut self.temps {
+            if !*used && *range == tys {
+                *used = true;
+                return *start;
+            }
+        }
+
+        let start = Reg(self.registers.len() as u32);
+        self.registers.extend(&tys);
+        self.temps.push((tys, start, true));
+        start
+    }
+
+    /// Get a temporary register of the given type.
+    fn temp(&mut self, ty: TypeId) -> Reg {
+        self.temps(vec![ty])
+    }
+
+    /// Free every temporary register.
+    fn release_temps(&mut self) {
+        (self.temps.iter_mut()).for_each(|(_, _, used)| *used = false);
+    }
+
+    /// Get the register holding an operand, loading constants into a temporary register.
+    fn operand(&mut self, operand: &Operand) -> Reg {
+        match operand {
+            Operand::Value(value) => Reg(value.0),
+            Operand::Const(constant) => {
+                let ty = self.compiler.ty(&constant.ty());
+                let dst = self.temp(ty);
+                self.load_const(dst, constant);
+                dst
+            }
+        }
+    }
+
+    /// Put an operand in a register.
+    fn operand_into(&mut self, dst: Reg, operand: &Operand) {
+        match operand {
+            Operand::Value(value) if value.0 == dst.0 => {}
+            Operand::Value(value) => self.emit(Instruction::Move {
+                dst,
+                src: Reg(value.0),
+            }),
+            Operand::Const(constant) => self.load_const(dst, constant),
+        }
+    }
+
+    /// Put operands in a range of consecutive temporary registers, returning the first one.
+    fn operand_range(&mut self, operands: &[&Operand]) -> Reg {
+        let tys = (operands.iter())
+            .map(|operand| self.body.operand_ty(operand))
+            .collect::<Vec<_>>();
+        let tys = tys.iter().map(|ty| self.compiler.ty(ty)).collect();
+        let start = self.temps(tys);
+
+        for (index, operand) in operands.iter().enumerate() {
+            self.operand_into(Reg(start.0 + index as u32), operand);
+        }
+
+        start
+    }
+
+    /// Load a constant into a register.
+    fn load_const(&mut self, dst: Reg, constant: &Const) {
+        let constant = match constant {
+            Const::Int(value, ty) => {
+                let int = ty.atomic().and_then(int_ty);
+                let value = i128::try_from(value).ok();
+                let narrowed = int.zip(value).and_then(|(int, value)| int.narrow(value));
+
+                match int.zip(narrowed) {
+                    Some((int, value)) => Constant::Int(value, int),
+                    None => {
+                        return self
+                            .emit_trap(format!("the integer {constant:?} doesn't fit its type"));
+                    }
+                }
+            }
+
+            Const::Bool(value) => Constant::Bool(*value),
+            Const::Str(value) => Constant::Str(value.clone()),
+            Const::Void => Constant::Void,
+            Const::Variant(_, index, ty) => Constant::Variant(self.compiler.ty(ty), *index),
+
+            Const::Def(def, _) => match self.compiler.resolution.builtin(*def) {
+                Some(builtin) => Constant::Builtin(builtin),
+                None => match self.compiler.functions.get(def) {
+                    Some(function) => Constant::Function(*function),
+                    None => {
+                        let message = self.missing(*def);
+                        return self.emit_trap(message);
+                    }
+                },
+            },
+        };
+
+        let constant = self.compiler.constant(constant);
+        self.emit(Instruction::LoadConst { dst, constant });
+    }
+
+    /// Describe why a definition without a body can't be used as a value.
+    fn missing(&self, def: DefId) -> String {
+        let name = self.compiler.name(def);
+
+        match self.compiler.resolution.def(def).kind {
+            DefKind::Constant => {
+                format!(
+                    "the value of the constant `{name}` isn't known, since constants aren't evaluated yet"
+                )
+            }
+            DefKind::Method => format!(
+                "the trait method `{name}` can't be used as a value, since the type it's called on isn't known"
+            ),
+            kind => format!("`{name}` is a {} without a body", kind.describe()),
+        }
+    }
+
+    /// Compile an instruction.
+    fn inst(&mut self, inst: &Inst) {
+        match inst {
+            Inst::Assign(value, rvalue) => self.rvalue(Reg(value.0), rvalue),
+
+            Inst::Store(place, operand) => {
+                let src = self.operand(operand);
+                let (ptr, path) = self.place(place);
+                self.emit(Instruction::Store { ptr, path, src });
+            }
+
+            Inst::Drop(slot) => {
+                let ptr = self.slot(slot.0);
+                self.emit(Instruction::Drop { ptr });
+            }
+        }
+    }
+
+    /// Compile a place, returning the register holding the reference it starts from and the field path from
+    /// there. Dereferences after the first are loaded into temporary registers along the way.
+    fn place(&mut self, place: &Place) -> (Reg, PathId) {
+        let (mut ptr, mut ty, projections) = match place.base {
+            PlaceBase::Slot(slot) => (
+                self.slot(slot.0),
+                self.body.slots[slot.0 as usize].ty.clone(),
+                &place.projections[..],
+            ),
+
+            PlaceBase::Value(value) => (
+                Reg(value.0),
+                strip_one_reference(self.body.value_ty(value)),
+                &place.projections[1..],
+            ),
+        };
+
+        let mut path = Vec::new();
+
+        for projection in projections {
+            match projection {
+                Projection::Field(field) => {
+                    let (index, field_ty) = self.compiler.field(&ty, field);
+                    path.push(index);
+                    ty = field_ty;
+                }
+
+                Projection::Deref => {
+                    let reference = self.compiler.ty(&ty);
+                    let dst = self.temp(reference);
+                    let path = self.path(std::mem::take(&mut path));
+                    self.emit(Instruction::Load { dst, ptr, path });
+                    ptr = dst;
+                    ty = strip_one_reference(&ty);
+                }
+            }
+        }
+
+        (ptr, self.path(path))
+    }
+
+    /// Get the ID of a field path, adding it if it's new.
+    fn path(&mut self, path: Vec<u32>) -> PathId {
+        match self.paths.iter().position(|existing| *existing == path) {
+            Some(index) => PathId(index as u32),
+            None => {
+                self.paths.push(path);
+                PathId(self.paths.len() as u32 - 1)
+            }
+        }
+    }
+
+    /// Compile the computation of a value into a register.
+    fn rvalue(&mut self, dst: Reg, rvalue: &Rvalue) {
+        match rvalue {
+            Rvalue::Use(operand) => self.operand_into(dst, operand),
+
+            Rvalue::Load(place) => {
+                let (ptr, path) = self.place(place);
+                self.emit(Instruction::Load { dst, ptr, path });
+            }
+
+            Rvalue::Ref { place, .. } => {
+                let (ptr, path) = self.place(place);
+                self.emit(Instruction::Offset { dst, ptr, path });
+            }
+
+            Rvalue::Unary(op, operand) => self.unary(dst, *op, operand),
+            Rvalue::Binary(op, lhs, rhs) => self.binary(dst, *op, lhs, rhs),
+            Rvalue::Call { callee, args } => self.call(dst, callee, args),
+
+            Rvalue::Aggregate { fields } => {
+                let ty = self.body.value_ty(Value(dst.0)).clone();
+                let (is_union, layout) = self.compiler.typeck.fields(&ty).unwrap_or_default();
+                let type_id = self.compiler.ty(&ty);
+
+                if is_union {
+                    let Some((name, operand)) = fields.first() else {
+                        return self.emit_trap("a union is made without a variant".to_owned());
+                    };
+
+                    let (variant, _) = self.compiler.field(&ty, name);
+                    let src = self.operand(operand);
+
+                    return self.emit(Instruction::MakeUnion {
+                        dst,
+                        ty: type_id,
+                        variant,
+                        src,
+                    });
+                }
+
+                // The fields are given in any order, but records hold them in the order they're declared.
+                let void = Operand::Const(Const::Void);
+                let operands: Vec<&Operand> = (layout.iter())
+                    .map(|(name, _)| {
+                        (fields.iter())
+                            .find(|(field, _)| field == name)
+                            .map_or(&void, |(_, operand)| operand)
+                    })
+                    .collect();
+
+                let start = self.operand_range(&operands);
+
+                self.emit(Instruction::MakeRecord {
+                    dst,
+                    ty: type_id,
+                    start,
+                    count: operands.len() as u32,
+                });
+            }
+
+            Rvalue::Field(operand, field) => {
+                let ty = self.body.operand_ty(operand);
+                let (index, _) = self.compiler.field(&ty, field);
+                let src = self.operand(operand);
+
+                self.emit(match self.compiler.is_union(&ty) {
+                    true => Instruction::GetVariant {
+                        dst,
+                        src,
+                        variant: index,
+                    },
+                    false => Instruction::GetField {
+                        dst,
+                        src,
+                        field: index,
+                    },
+                });
+            }
+
+            Rvalue::IsVariant(operand, variant) => {
+                let ty = self.body.operand_ty(operand);
+                let (variant, _) = self.compiler.field(&ty, variant);
+                let src = self.operand(operand);
+                self.emit(Instruction::IsVariant { dst, src, variant });
+            }
+
+            Rvalue::Closure { body, captures } => {
+                let captures: Vec<&Operand> = captures.iter().collect();
+                let start = self.operand_range(&captures);
+
+                self.emit(Instruction::MakeClosure {
+                    dst,
+                    function: FunctionId(body.0),
+                    start,
+                    count: captures.len() as u32,
+                });
+            }
+        }
+    }
+
+    /// Compile a unary operation.
+    fn unary(&mut self, dst: Reg, op: UnaryOperation, operand: &Operand) {
+        let ty = self.body.operand_ty(operand);
+        let src = self.operand(operand);
+        let atomic = ty.atomic();
+
+        let instruction = match (op, atomic.and_then(int_ty)) {
+            (UnaryOperation::Negate, Some(ty)) => Instruction::IntNeg { ty, dst, src },
+            (UnaryOperation::BitwiseNot, Some(ty)) => Instruction::IntNot { ty, dst, src },
+            (UnaryOperation::Negate, None) if atomic.is_some_and(AtomicTyVariant::is_float) => {
+                Instruction::FloatNeg { dst, src }
+            }
+            (UnaryOperation::BooleanNot | UnaryOperation::BitwiseNot, None) if ty == Ty::BOOL => {
+                Instruction::BoolNot { dst, src }
+            }
+            _ => return self.unsupported(op.symbol(), &ty),
+        };
+
+        self.emit(instruction);
+    }
+
+    /// Compile a binary operation.
+    fn binary(&mut self, dst: Reg, op: BinaryOperation, lhs: &Operand, rhs: &Operand) {
+        let ty = self.body.operand_ty(lhs);
+        let atomic = ty.atomic();
+        let float = match atomic {
+            Some(AtomicTyVariant::F32) => Some(FloatTy::F32),
+            Some(AtomicTyVariant::F64) => Some(FloatTy::F64),
+            _ => None,
+        };
+
+        let lhs = self.operand(lhs);
+        let rhs = self.operand(rhs);
+
+        let instruction = if let Some(compare) = compare_op(op) {
+            let kind = match (atomic.and_then(int_ty), float) {
+                (Some(int), _) => CompareKind::Int(int),
+                (None, Some(_)) => CompareKind::Float,
+                (None, None) => CompareKind::Value,
+            };
+
+            Instruction::Compare {
+                op: compare,
+                kind,
+                dst,
+                lhs,
+                rhs,
+            }
+        } else if let Some(int) = atomic.and_then(int_ty) {
+            let Some(op) = int_op(op) else {
+                return self.unsupported(op.symbol(), &ty);
+            };
+
+            Instruction::Int {
+                op,
+                ty: int,
+                dst,
+                lhs,
+                rhs,
+            }
+        } else if let Some(float) = float {
+            let op = match op {
+                BinaryOperation::Add => FloatOp::Add,
+                BinaryOperation::Subtract => FloatOp::Sub,
+                BinaryOperation::Multiply => FloatOp::Mul,
+                BinaryOperation::Divide => FloatOp::Div,
+                BinaryOperation::Modulo => FloatOp::Rem,
+                _ => return self.unsupported(op.symbol(), &ty),
+            };
+
+            Instruction::Float {
+                op,
+                ty: float,
+                dst,
+                lhs,
+                rhs,
+            }
+        } else if ty == Ty::BOOL {
+            let op = match op {
+                BinaryOperation::BitwiseAnd | BinaryOperation::LogicalAnd => BoolOp::And,
+                BinaryOperation::BitwiseOr | BinaryOperation::LogicalOr => BoolOp::Or,
+                BinaryOperation::BitwiseXor => BoolOp::Xor,
+                _ => return self.unsupported(op.symbol(), &ty),
+            };
+
+            Instruction::Bool { op, dst, lhs, rhs }
+        } else {
+            return self.unsupported(op.symbol(), &ty);
+        };
+
+        self.emit(instruction);
+    }
+
+    /// Trap on an operation that isn't supported for a type, which type checking should have ruled out.
+    fn unsupported(&mut self, op: &str, ty: &Ty) {
+        let ty = ty.display(self.compiler.resolution).to_string();
+        self.emit_trap(format!("`{op}` isn't supported for `{ty}`"));
+    }
+
+    /// Compile a call.
+    fn call(&mut self, dst: Reg, callee: &Operand, args: &[Operand]) {
+        let target = match callee {
+            Operand::Const(Const::Def(def, _)) => Some(self.callee(*def, args)),
+            _ => None,
+        };
+
+        let args: Vec<&Operand> = args.iter().collect();
+
+        match target {
+            Some(Callee::Function(function)) => {
+                let start = self.operand_range(&args);
+                let count = args.len() as u32;
+                self.emit(Instruction::CallDirect {
+                    dst,
+                    function,
+                    start,
+                    count,
+                });
+            }
+
+            Some(Callee::Method(method)) => {
+                let start = self.operand_range(&args);
+                let count = args.len() as u32;
+                self.emit(Instruction::CallMethod {
+                    dst,
+                    method,
+                    start,
+                    count,
+                });
+            }
+
+            Some(Callee::Builtin(builtin)) => {
+                let void = Operand::Const(Const::Void);
+                let arg = args.first().copied().unwrap_or(&void);
+                let ty = self.body.operand_ty(arg);
+                let ty = self.compiler.ty(&ty);
+                let src = self.operand(arg);
+
+                self.emit(Instruction::Print {
+                    dst,
+                    src,
+                    ty,
+                    newline: builtin == Builtin::Println,
+                });
+            }
+
+            Some(Callee::Missing(message)) => self.emit_trap(message),
+
+            None => {
+                let callee = self.operand(callee);
+                let start = self.operand_range(&args);
+                let count = args.len() as u32;
+                self.emit(Instruction::Call {
+                    dst,
+                    callee,
+                    start,
+                    count,
+                });
+            }
+        }
+    }
+
+    /// Find what a call of a definition runs. Trait methods called on a receiver of a known type run the method
+    /// of the implementation for that type, and otherwise are looked up by the receiver's type when they're called.
+    fn callee(&mut self, def: DefId, args: &[Operand]) -> Callee {
+        if let Some(builtin) = self.compiler.resolution.builtin(def) {
+            return Callee::Builtin(builtin);
+        }
+
+        let receiver = args.first().map(|receiver| self.body.operand_ty(receiver));
+
+        let receiver = match receiver {
+            Some(receiver) if self.compiler.typeck.trait_of_method(def).is_some() => receiver,
+            _ => {
+                return match self.compiler.functions.get(&def) {
+                    Some(function) => Callee::Function(*function),
+                    None => Callee::Missing(self.missing(def)),
+                };
+            }
+        };
+
+        let receiver = strip_references(&receiver);
+
+        if is_generic(receiver) {
+            return Callee::Method(self.compiler.method(def));
+        }
+
+        let function = (self.compiler.typeck.implementation(def, receiver))
+            .and_then(|implementation| self.compiler.functions.get(&implementation));
+
+        match function {
+            Some(function) => Callee::Function(*function),
+            None => Callee::Missing(format!(
+                "`{}` isn't implemented for `{}`",
+                self.compiler.name(def),
+                receiver.display(self.compiler.resolution)
+            )),
+        }
+    }
+
+    /// Compile a terminator, where the given block (if any) is compiled next.
+    fn terminator(&mut self, terminator: &Terminator, next: Option<BlockId>) {
+        match terminator {
+            Terminator::Goto(jump) => self.jump(jump, next),
+
+            Terminator::Branch {
+                condition,
+                then,
+                otherwise,
+            } => {
+                let condition = self.operand(condition);
+
+                if !then.args.is_empty() || !otherwise.args.is_empty() {
+                    // The arguments of each edge are moved on the way to the block.
+                    let branch = self.code.len();
+                    self.emit(Instruction::JumpIfNot {
+                        condition,
+                        target: 0,
+                    });
+
+                    self.jump(then, None);
+
+                    let otherwise_start = self.code.len() as u32;
+                    if let Instruction::JumpIfNot { target, .. } = &mut self.code[branch] {
+                        *target = otherwise_start;
+                    }
+
+                    return self.jump(otherwise, next);
+                }
+
+                match Some(then.target) == next {
+                    true => self.emit_block_jump(Instruction::JumpIfNot {
+                        condition,
+                        target: otherwise.target.0,
+                    }),
+
+                    false => {
+                        self.emit_block_jump(Instruction::JumpIf {
+                            condition,
+                            target: then.target.0,
+                        });
+
+                        self.jump(otherwise, next);
+                    }
+                }
+            }
+
+            Terminator::Switch {
+                value,
+                cases,
+                otherwise,
+            } => {
+                let src = self.operand(value);
+
+                let mut cases: Vec<(i64, u32)> = (cases.iter())
+                    .filter_map(|(case, target)| Some((switch_case(case)?, target.0)))
+                    .collect();
+
+                cases.sort_unstable();
+
+                self.switches.push(SwitchTable {
+                    cases,
+                    default: otherwise.0,
+                });
+
+                let table = SwitchId(self.switches.len() as u32 - 1);
+                self.emit(Instruction::Switch { src, table });
+            }
+
+            Terminator::Return(value) => {
+                let src = self.operand(value);
+                self.emit(Instruction::Return { src });
+            }
+
+            Terminator::Unreachable => {
+                self.emit_trap("reached code that should be unreachable".to_owned())
+            }
+        }
+    }
+
+    /// Compile a jump to a block, moving its arguments into the registers of the block's parameters. Nothing is
+    /// needed to jump to the given next block but the moves.
+    fn jump(&mut self, jump: &Jump, next: Option<BlockId>) {
+        let params = &self.body.block(jump.target).params;
+
+        // If an argument is the parameter of another argument, it would be overwritten before it's moved, so the
+        // arguments are moved into temporary registers first.
+        let conflict = (jump.args.iter().enumerate()).any(|(index, arg)| {
+            (params.iter().enumerate())
+                .any(|(other, param)| other != index && *arg == Operand::Value(*param))
+        });
+
+        if conflict {
+            let args: Vec<&Operand> = jump.args.iter().collect();
+            let start = self.operand_range(&args);
+
+            for (index, param) in params.iter().enumerate() {
+                self.emit(Instruction::Move {
+                    dst: Reg(param.0),
+                    src: Reg(start.0 + index as u32),
+                });
+            }
+        } else {
+            for (param, arg) in params.iter().zip(&jump.args) {
+                self.operand_into(Reg(param.0), arg);
+            }
+        }
+
+        if Some(jump.target) != next {
+            self.emit_block_jump(Instruction::Jump {
+                target: jump.target.0,
+            });
+        }
+    }
+}
+
+/// Get the type of the value a reference points to.
+fn strip_one_reference(ty: &Ty) -> Ty {
+    match ty {
+        Ty::Reference { target, .. } => (**target).clone(),
+        _ => Ty::Error,
+    }
+}
+
+/// Get the value of a case of a switch, in the representation of [Instruction::Int] (or as the index of a
+/// variant). Cases too large for any integer type are left out, since no value can match them.
+fn switch_case(case: &num::BigInt) -> Option<i64> {
+    let case = i128::try_from(case).ok()?;
+
+    match case < 0 {
+        true => i64::try_from(case).ok(),
+        false => u64::try_from(case).ok().map(|case| case as i64),
+    }
+}
+
+/// Get the comparison a binary operation makes, if it's a comparison.
+const fn compare_op(op: BinaryOperation) -> Option<CompareOp> {
+    Some(match op {
+        BinaryOperation::Equal => CompareOp::Eq,
+        BinaryOperation::NotEqual => CompareOp::Ne,
+        BinaryOperation::Less => CompareOp::Lt,
+        BinaryOperation::LessOrEqual => CompareOp::Le,
+        BinaryOperation::Greater => CompareOp::Gt,
+        BinaryOperation::GreaterOrEqual => CompareOp::Ge,
+        _ => return None,
+    })
+}
+
+/// Get the integer operation of a binary operation.
+const fn int_op(op: BinaryOperation) -> Option<IntOp> {
+    Some(match op {
+        BinaryOperation::Add => IntOp::Add,
+        BinaryOperation::Subtract => IntOp::Sub,
+        BinaryOperation::Multiply => IntOp::Mul,
+        BinaryOperation::Divide => IntOp::Div,
+        BinaryOperation::Modulo => IntOp::Rem,
+        BinaryOperation::BitwiseAnd => IntOp::And,
+        BinaryOperation::BitwiseOr => IntOp::Or,
+        BinaryOperation::BitwiseXor => IntOp::Xor,
+        BinaryOperation::ShiftLeft => IntOp::Shl,
+        BinaryOperation::ShiftRight => IntOp::Shr,
+        _ => return None,
+    })
+}
@@ -0,0 +1,717 @@
+//! The disassembly of a bytecode [Module], as printed by `wright debug bytecode`:
+//!
+//! ```text
+//! entry: f0 main
+//!
+//! constants:
+//!     c0 = 255_u8
+//!     c1 = 1_u8
+//!
+//! types:
+//!     t0 = u8
+//!
+//! func f0 main() -> u8 {
+//!     registers: r0: u8, r1: u8, r2: u8
+//!     ; main.wr:3: x + 1
+//!     0000  const r1, c0 ; 255_u8
+//!     0001  const r2, c1 ; 1_u8
+//!     0002  add.u8 r0, r1, r2
+//!     ; main.wr:1: func main() -> u8 {
+//!     0003  return r0
+//! }
+//! ```
+//!
+//! Each instruction's offset is its index in the function, which is what jumps go to. Lines of source code are
+//! interleaved with the instructions from the module's debug info, when it has any and the sources are given.
+
+use super::bytecode::{
+    BoolOp, CompareKind, CompareOp, Constant, FloatOp, FloatTy, Function, FunctionId, Instruction,
+    IntOp, IntTy, Module, PathId, Reg, Type, TypeId,
+};
+use crate::source_tracking::{SourceMap, SourceRef};
+use std::fmt::{self, Display};
+
+/// A [Module] that can be displayed as a listing of its instructions. See [Module::disassemble].
+#[derive(Clone, Copy, Debug)]
+pub struct Disassembly<'a> {
+    pub(super) module: &'a Module,
+    pub(super) sources: Option<&'a SourceMap>,
+}
+
+impl Display for Disassembly<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let module = self.module;
+
+        match module.entry {
+            Some(entry) => writeln!(f, "entry: f{} {}", entry.0, self.function_name(entry))?,
+            None => writeln!(f, "entry: none")?,
+        }
+
+        if !module.constants.is_empty() {
+            writeln!(f, "\nconstants:")?;
+
+            for (index, constant) in module.constants.iter().enumerate() {
+                writeln!(f, "    c{index} = {}", self.constant(constant))?;
+            }
+        }
+
+        if !module.types.is_empty() {
+            writeln!(f, "\ntypes:")?;
+
+            for (index, ty) in module.types.iter().enumerate() {
+                writeln!(f, "    t{index} = {}", self.type_entry(ty))?;
+            }
+        }
+
+        if !module.methods.is_empty() {
+            writeln!(f, "\nmethods:")?;
+
+            for (index, table) in module.methods.iter().enumerate() {
+                write!(f, "    m{index} {}:", table.name)?;
+
+                for (position, (ty, function)) in table.impls.iter().enumerate() {
+                    let separator = if position > 0 { "," } else { "" };
+                    write!(f, "{separator} {} => f{}", type_name(self.module, *ty), function.0)?;
+                }
+
+                writeln!(f)?;
+            }
+        }
+
+        // The source of each file in the debug info, if it's known.
+        let files: Vec<Option<SourceRef>> = match (&module.debug, self.sources) {
+            (Some(debug), Some(sources)) => (debug.files.iter())
+                .map(|file| sources.find_by_name(file))
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        for index in 0..module.functions.len() {
+            writeln!(f)?;
+            self.function(f, FunctionId(index as u32), &files)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Disassembly<'_> {
+    /// Write a function.
+    fn function(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        id: FunctionId,
+        files: &[Option<SourceRef>],
+    ) -> fmt::Result {
+        let function = self.module.function(id);
+        write!(f, "func f{} {}(", id.0, function.name)?;
+
+        // Invalid modules can claim more parameters than there are registers.
+        for index in 0..function.params.min(function.registers.len() as u32) {
+            let separator = if index > 0 { ", " } else { "" };
+            write!(f, "{separator}{}", self.register(function, Reg(index)))?;
+        }
+
+        if function.params as usize > function.registers.len() {
+            let separator = if function.registers.is_empty() {
+                ""
+            } else {
+                ", "
+            };
+            write!(f, "{separator}{} parameters", function.params)?;
+        }
+
+        writeln!(f, ") -> {} {{", type_name(self.module, function.ret))?;
+        f.write_str("    registers:")?;
+
+        for index in 0..function.registers.len() {
+            let separator = if index > 0 { "," } else { "" };
+            write!(f, "{separator} {}", self.register(function, Reg(index as u32)))?;
+        }
+
+        writeln!(f)?;
+
+        let debug = self.module.debug.as_ref();
+        let mut locations = (debug.and_then(|debug| debug.functions.get(id.0 as usize)))
+            .into_iter()
+            .flatten()
+            .peekable();
+        let mut last_line = None;
+
+        for (offset, instruction) in function.code.iter().enumerate() {
+            let mut location = None;
+
+            while let Some(next) = locations.next_if(|next| next.offset as usize <= offset) {
+                location = Some(next);
+            }
+
+            // Runs of instructions from the same line only get the line once.
+            if let (Some(debug), Some(location)) = (debug, location) {
+                if last_line != Some((location.file, location.line)) {
+                    last_line = Some((location.file, location.line));
+                    write!(f, "    ; {}:{}", debug.file(location), location.line)?;
+
+                    let source = files.get(location.file as usize).and_then(Option::as_ref);
+                    let index = (location.line as usize).saturating_sub(1);
+
+                    if let Some(source) = source.filter(|source| index < source.count_lines()) {
+                        let line = SourceRef::clone(source).get_line(index);
+                        write!(f, ": {}", line.as_str().trim())?;
+                    }
+
+                    writeln!(f)?;
+                }
+            }
+
+            write!(f, "    {offset:04}  ")?;
+            self.instruction(f, function, *instruction)?;
+            writeln!(f)?;
+        }
+
+        writeln!(f, "}}")
+    }
+
+    /// Write an instruction.
+    fn instruction(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        function: &Function,
+        instruction: Instruction,
+    ) -> fmt::Result {
+        match instruction {
+            Instruction::LoadConst { dst, constant } => {
+                write!(f, "const r{}, c{}", dst.0, constant.0)?;
+
+                if let Some(value) = self.module.constants.get(constant.0 as usize) {
+                    write!(f, " ; {}", self.constant(value))?;
+                }
+
+                Ok(())
+            }
+
+            Instruction::Move { dst, src } => write!(f, "move r{}, r{}", dst.0, src.0),
+
+            Instruction::Int {
+                op,
+                ty,
+                dst,
+                lhs,
+                rhs,
+            } => write!(
+                f,
+                "{}.{} r{}, r{}, r{}",
+                int_mnemonic(op),
+                int_name(ty),
+                dst.0,
+                lhs.0,
+                rhs.0
+            ),
+
+            Instruction::IntNeg { ty, dst, src } => {
+                write!(f, "neg.{} r{}, r{}", int_name(ty), dst.0, src.0)
+            }
+
+            Instruction::IntNot { ty, dst, src } => {
+                write!(f, "not.{} r{}, r{}", int_name(ty), dst.0, src.0)
+            }
+
+            Instruction::Float {
+                op,
+                ty,
+                dst,
+                lhs,
+                rhs,
+            } => write!(
+                f,
+                "{}.{} r{}, r{}, r{}",
+                float_mnemonic(op),
+                float_name(ty),
+                dst.0,
+                lhs.0,
+                rhs.0
+            ),
+
+            Instruction::FloatNeg { dst, src } => write!(f, "fneg r{}, r{}", dst.0, src.0),
+
+            Instruction::Bool { op, dst, lhs, rhs } => {
+                let mnemonic = match op {
+                    BoolOp::And => "and",
+                    BoolOp::Or => "or",
+                    BoolOp::Xor => "xor",
+                };
+
+                write!(f, "{mnemonic} r{}, r{}, r{}", dst.0, lhs.0, rhs.0)
+            }
+
+            Instruction::BoolNot { dst, src } => write!(f, "not r{}, r{}", dst.0, src.0),
+
+            Instruction::Compare {
+                op,
+                kind,
+                dst,
+                lhs,
+                rhs,
+            } => {
+                f.write_str(compare_mnemonic(op))?;
+
+                match kind {
+                    CompareKind::Int(ty) => write!(f, ".{}", int_name(ty))?,
+                    CompareKind::Float => f.write_str(".float")?,
+                    CompareKind::Value => {}
+                }
+
+                write!(f, " r{}, r{}, r{}", dst.0, lhs.0, rhs.0)
+            }
+
+            Instruction::Alloc { dst } => write!(f, "alloc r{}", dst.0),
+
+            Instruction::Load { dst, ptr, path } => {
+                write!(f, "load r{}, {}", dst.0, self.path(function, ptr, path))
+            }
+
+            Instruction::Store { ptr, path, src } => {
+                write!(f, "store {}, r{}", self.path(function, ptr, path), src.0)
+            }
+
+            Instruction::Offset { dst, ptr, path } => {
+                write!(f, "offset r{}, {}", dst.0, self.path(function, ptr, path))
+            }
+
+            Instruction::Drop { ptr } => write!(f, "drop r{}", ptr.0),
+
+            Instruction::MakeRecord {
+                dst,
+                ty,
+                start,
+                count,
+            } => {
+                write!(f, "record r{}, t{}", dst.0, ty.0)?;
+                self.registers(f, function, start, count)?;
+                write!(f, " ; {}", type_name(self.module, ty))
+            }
+
+            Instruction::GetField { dst, src, field } => {
+                write!(f, "get_field r{}, r{}, {field}", dst.0, src.0)?;
+                self.field_comment(f, function, src, field)
+            }
+
+            Instruction::MakeUnion {
+                dst,
+                ty,
+                variant,
+                src,
+            } => {
+                write!(f, "union r{}, t{}, {variant}, r{}", dst.0, ty.0, src.0)?;
+
+                match self.module.types.get(ty.0 as usize) {
+                    Some(Type::Union { name, variants }) => match variants.get(variant as usize) {
+                        Some((variant, _)) => write!(f, " ; {name}.{variant}"),
+                        None => write!(f, " ; {name}"),
+                    },
+                    _ => Ok(()),
+                }
+            }
+
+            Instruction::IsVariant { dst, src, variant } => {
+                write!(f, "is_variant r{}, r{}, {variant}", dst.0, src.0)?;
+                self.field_comment(f, function, src, variant)
+            }
+
+            Instruction::GetVariant { dst, src, variant } => {
+                write!(f, "get_variant r{}, r{}, {variant}", dst.0, src.0)?;
+                self.field_comment(f, function, src, variant)
+            }
+
+            Instruction::MakeClosure {
+                dst,
+                function: closure,
+                start,
+                count,
+            } => {
+                write!(f, "closure r{}, f{}", dst.0, closure.0)?;
+                self.registers(f, function, start, count)?;
+                write!(f, " ; {}", self.function_name(closure))
+            }
+
+            Instruction::Call {
+                dst,
+                callee,
+                start,
+                count,
+            } => {
+                write!(f, "call r{}, r{}", dst.0, callee.0)?;
+                self.registers(f, function, start, count)
+            }
+
+            Instruction::CallDirect {
+                dst,
+                function: callee,
+                start,
+                count,
+            } => {
+                write!(f, "call r{}, f{}", dst.0, callee.0)?;
+                self.registers(f, function, start, count)?;
+                write!(f, " ; {}", self.function_name(callee))
+            }
+
+            Instruction::CallMethod {
+                dst,
+                method,
+                start,
+                count,
+            } => {
+                write!(f, "call_method r{}, m{}", dst.0, method.0)?;
+                self.registers(f, function, start, count)?;
+
+                match self.module.methods.get(method.0 as usize) {
+                    Some(table) => write!(f, " ; {}", table.name),
+                    None => Ok(()),
+                }
+            }
+
+            Instruction::Print {
+                dst,
+                src,
+                ty,
+                newline,
+            } => {
+                let mnemonic = if newline { "println" } else { "print" };
+                write!(
+                    f,
+                    "{mnemonic} r{}, r{}, t{} ; {}",
+                    dst.0,
+                    src.0,
+                    ty.0,
+                    type_name(self.module, ty)
+                )
+            }
+
+            Instruction::Jump { target } => write!(f, "jump {target:04}"),
+
+            Instruction::JumpIf { condition, target } => {
+                write!(f, "jump_if r{}, {target:04}", condition.0)
+            }
+
+            Instruction::JumpIfNot { condition, target } => {
+                write!(f, "jump_if_not r{}, {target:04}", condition.0)
+            }
+
+            Instruction::Switch { src, table } => {
+                write!(f, "switch r{}", src.0)?;
+
+                let Some(table) = function.switches.get(table.0 as usize) else {
+                    return write!(f, ", s{}", table.0);
+                };
+
+                f.write_str(" [")?;
+
+                for (index, (case, target)) in table.cases.iter().enumerate() {
+                    let separator = if index > 0 { ", " } else { "" };
+                    write!(f, "{separator}{case} => {target:04}")?;
+                }
+
+                write!(f, "], otherwise {:04}", table.default)
+            }
+
+            Instruction::Return { src } => write!(f, "return r{}", src.0),
+
+            Instruction::Trap { message } => {
+                write!(f, "trap c{}", message.0)?;
+
+                match self.module.constants.get(message.0 as usize) {
+                    Some(constant) => write!(f, " ; {}", self.constant(constant)),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+
+    /// Write a list of consecutive registers, in parentheses. A list that goes past the function's registers
+    /// (which only happens in invalid modules) is written as its first register and length instead, since listing
+    /// every register in it could take forever.
+    fn registers(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        function: &Function,
+        start: Reg,
+        count: u32,
+    ) -> fmt::Result {
+        if start.0 as usize + count as usize > function.registers.len() {
+            return write!(f, "(r{}, {count} registers)", start.0);
+        }
+
+        f.write_str("(")?;
+
+        for index in 0..count {
+            let separator = if index > 0 { ", " } else { "" };
+            write!(f, "{separator}r{}", start.0 + index)?;
+        }
+
+        f.write_str(")")
+    }
+
+    /// Write the name of a field of a record (or a variant of a union) in a register as a comment, if it's known.
+    fn field_comment(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        function: &Function,
+        reg: Reg,
+        index: u32,
+    ) -> fmt::Result {
+        let ty = function.registers.get(reg.0 as usize);
+
+        match ty.and_then(|ty| self.module.types.get(ty.0 as usize)) {
+            Some(Type::Record { fields: list, .. } | Type::Union { variants: list, .. }) => {
+                match list.get(index as usize) {
+                    Some((name, _)) => write!(f, " ; {name}"),
+                    None => Ok(()),
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Describe a register and its field path, like `r3.0.1` (or just `r3` for an empty path).
+    fn path(&self, function: &Function, ptr: Reg, path: PathId) -> String {
+        let mut text = format!("r{}", ptr.0);
+
+        match function.paths.get(path.0 as usize) {
+            Some(steps) => steps.iter().for_each(|step| text += &format!(".{step}")),
+            None => text += &format!(".p{}", path.0),
+        }
+
+        text
+    }
+
+    /// Describe a register with its type, like `r0: u8`.
+    fn register(&self, function: &Function, reg: Reg) -> String {
+        match function.registers.get(reg.0 as usize) {
+            Some(ty) => format!("r{}: {}", reg.0, type_name(self.module, *ty)),
+            None => format!("r{}", reg.0),
+        }
+    }
+
+    /// Get the name of a function.
+    fn function_name(&self, function: FunctionId) -> &str {
+        (self.module.functions.get(function.0 as usize))
+            .map_or("<missing>", |function| function.name.as_str())
+    }
+
+    /// Describe a constant.
+    fn constant(&self, constant: &Constant) -> String {
+        match constant {
+            Constant::Void => "()".to_owned(),
+            Constant::Bool(value) => value.to_string(),
+            Constant::Int(value, ty) => format!("{}_{}", ty.widen(*value), int_name(*ty)),
+            Constant::Float(value) => format!("{value:?}"),
+            Constant::Str(value) => format!("{value:?}"),
+            Constant::Function(function) => {
+                format!("func f{} {}", function.0, self.function_name(*function))
+            }
+            Constant::Builtin(builtin) => format!("builtin wright::io::{}", builtin.name()),
+            Constant::Variant(ty, index) => match self.module.types.get(ty.0 as usize) {
+                Some(Type::Enum { name, variants }) => match variants.get(*index as usize) {
+                    Some(variant) => format!("{name}::{variant}"),
+                    None => format!("{name}::#{index}"),
+                },
+                _ => format!("t{}::#{index}", ty.0),
+            },
+        }
+    }
+
+    /// Describe an entry of the type table in full.
+    fn type_entry(&self, ty: &Type) -> String {
+        let fields = |fields: &[(String, TypeId)]| {
+            (fields.iter())
+                .map(|(name, ty)| format!("{name}: t{}", ty.0))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        match ty {
+            Type::Record { name, fields: list } => format!("record {name} {{ {} }}", fields(list)),
+            Type::Union { name, variants } => format!("union {name} {{ {} }}", fields(variants)),
+            Type::Enum { name, variants } => format!("enum {name} {{ {} }}", variants.join(", ")),
+            Type::Opaque(name) => format!("opaque {name}"),
+            Type::Reference(target) => format!("@t{}", target.0),
+            _ => short_name(ty),
+        }
+    }
+}
+
+/// Get the name of a type in the type table, the way it's written in wright (or `t{index}` if it doesn't exist).
+pub(crate) fn type_name(module: &Module, ty: TypeId) -> String {
+    match module.types.get(ty.0 as usize) {
+        Some(Type::Reference(target)) => format!("@{}", type_name(module, *target)),
+        Some(entry) => short_name(entry),
+        None => format!("t{}", ty.0),
+    }
+}
+
+/// Get the name of a type, without the contents of records, unions, and enums.
+fn short_name(ty: &Type) -> String {
+    match ty {
+        Type::Void => "void".to_owned(),
+        Type::Bool => "bool".to_owned(),
+        Type::Char => "char".to_owned(),
+        Type::Str => "str".to_owned(),
+        Type::Int(int) => int_name(*int),
+        Type::Float(float) => float_name(*float).to_owned(),
+        Type::Reference(target) => format!("@t{}", target.0),
+        Type::Function => "func".to_owned(),
+        Type::Record { name, .. }
+        | Type::Union { name, .. }
+        | Type::Enum { name, .. }
+        | Type::Opaque(name) => name.clone(),
+    }
+}
+
+/// Get the name of an integer type, like `u8`.
+fn int_name(ty: IntTy) -> String {
+    format!("{}{}", if ty.signed { 'i' } else { 'u' }, ty.bits)
+}
+
+/// Get the name of a floating point type.
+const fn float_name(ty: FloatTy) -> &'static str {
+    match ty {
+        FloatTy::F32 => "f32",
+        FloatTy::F64 => "f64",
+    }
+}
+
+/// Get the mnemonic of an integer operation.
+const fn int_mnemonic(op: IntOp) -> &'static str {
+    match op {
+        IntOp::Add => "add",
+        IntOp::Sub => "sub",
+        IntOp::Mul => "mul",
+        IntOp::Div => "div",
+        IntOp::Rem => "rem",
+        IntOp::And => "and",
+        IntOp::Or => "or",
+        IntOp::Xor => "xor",
+        IntOp::Shl => "shl",
+        IntOp::Shr => "shr",
+    }
+}
+
+/// Get the mnemonic of a floating point operation.
+const fn float_mnemonic(op: FloatOp) -> &'static str {
+    match op {
+        FloatOp::Add => "fadd",
+        FloatOp::Sub => "fsub",
+        FloatOp::Mul => "fmul",
+        FloatOp::Div => "fdiv",
+        FloatOp::Rem => "frem",
+    }
+}
+
+/// Get the mnemonic of a comparison.
+const fn compare_mnemonic(op: CompareOp) -> &'static str {
+    match op {
+        CompareOp::Eq => "eq",
+        CompareOp::Ne => "ne",
+        CompareOp::Lt => "lt",
+        CompareOp::Le => "le",
+        CompareOp::Gt => "gt",
+        CompareOp::Ge => "ge",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        analysis::{resolve::resolve, typeck},
+        ast::module::Module,
+        hir,
+        lexer::Lexer,
+        mir,
+        parser::Parser,
+        source_tracking::{SourceMap, filename::FileName, source::Source},
+        vm::{
+            self,
+            bytecode::{FunctionId, Instruction, Reg},
+        },
+    };
+
+    #[test]
+    fn test_disassemble() {
+        let source = "use wright::io::println;\n\
+            enum Color { Red, Green }\n\
+            func pick(color: Color) -> u8 {\n    match color { Color::Red => 1, Color::Green => 2 }\n}\n\
+            func main() { println(pick(Color::Green)); }";
+
+        let map = SourceMap::new();
+        let source_ref = map.add(Source::new_from_static_str(FileName::Test("colors"), source));
+        let module = Module::parse(&mut Parser::new(Lexer::new(source_ref))).unwrap();
+        let modules = || [(Vec::new(), &module)];
+
+        let resolution = resolve(modules());
+        let typeck = typeck::check(modules(), &resolution);
+        let hir = hir::lower(modules(), &resolution);
+        let program = mir::lower(&hir, &resolution, &typeck);
+        let module = vm::compile(&program, &resolution, &typeck);
+
+        let expected = "\
+entry: f1 main
+
+constants:
+    c0 = 1_u8
+    c1 = 2_u8
+    c2 = \"reached code that should be unreachable\"
+    c3 = Color::Green
+    c4 = ()
+
+types:
+    t0 = enum Color { Red, Green }
+    t1 = u8
+    t2 = void
+
+func f0 pick(r0: Color) -> u8 {
+    registers: r0: Color, r1: u8
+    ; colors:4: match color { Color::Red => 1, Color::Green => 2 }
+    0000  switch r0 [0 => 0001, 1 => 0003], otherwise 0005
+    0001  const r1, c0 ; 1_u8
+    0002  jump 0004
+    0003  const r1, c1 ; 2_u8
+    ; colors:3: func pick(color: Color) -> u8 {
+    0004  return r1
+    ; colors:4: match color { Color::Red => 1, Color::Green => 2 }
+    0005  trap c2 ; \"reached code that should be unreachable\"
+}
+
+func f1 main() -> void {
+    registers: r0: u8, r1: void, r2: Color, r3: void
+    ; colors:6: func main() { println(pick(Color::Green)); }
+    0000  const r2, c3 ; Color::Green
+    0001  call r0, f0(r2) ; pick
+    0002  println r1, r0, t1 ; u8
+    0003  const r3, c4 ; ()
+    0004  return r3
+}
+";
+
+        assert_eq!(module.disassemble(Some(&map)).to_string(), expected);
+
+        // Without the sources, only the locations are shown.
+        let listing = module.disassemble(None).to_string();
+        assert!(listing.contains("    ; colors:4\n    0000  switch"));
+
+        // Corrupted counts aren't listed register by register.
+        let mut module = module;
+        let main = &mut module.functions[1];
+        main.params = u32::MAX;
+        main.code[1] = Instruction::CallDirect {
+            dst: Reg(0),
+            function: FunctionId(0),
+            start: Reg(2),
+            count: 2_164_260_865,
+        };
+
+        let listing = module.disassemble(None).to_string();
+        assert!(listing.contains(
+            "func f1 main(r0: u8, r1: void, r2: Color, r3: void, 4294967295 parameters)"
+        ));
+        assert!(listing.contains("0001  call r0, f0(r2, 2164260865 registers) ; pick"));
+    }
+}
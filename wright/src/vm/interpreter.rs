@@ -0,0 +1,633 @@
+//! The dispatch loop that runs bytecode.
+
+use super::{
+    bytecode::{
+        BoolOp, CompareKind, CompareOp, Constant, FloatOp, FloatTy, FunctionId, Instruction, IntOp,
+        IntTy, Location, Module, Reg,
+    },
+    disassemble,
+    value::{self, Closure, Pointer, Record, Union, Value},
+};
+use crate::analysis::builtins::Builtin;
+use std::{cmp::Ordering, fmt, io::Write, rc::Rc};
+
+/// The most calls that can be in progress at once, before the program traps with a stack overflow.
+const MAX_DEPTH: usize = 10_000;
+
+/// An error that stops a program: an arithmetic error, a stack overflow, and so on.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Trap {
+    /// What went wrong.
+    pub message: String,
+    /// The name of the function that was running.
+    pub function: String,
+    /// The index of the instruction that was running in the function.
+    pub offset: usize,
+}
+
+impl Trap {
+    /// Get the name of the source file and the location where the trap happened, if the module has debug info.
+    pub fn location<'m>(&self, module: &'m Module) -> Option<(&'m str, &'m Location)> {
+        let debug = module.debug.as_ref()?;
+        let function = module.function_named(&self.function)?;
+        let location = debug.location(function, self.offset as u32)?;
+        Some((debug.file(location), location))
+    }
+}
+
+impl fmt::Display for Trap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (in `{}` at {})", self.message, self.function, self.offset)
+    }
+}
+
+impl std::error::Error for Trap {}
+
+/// A call in progress, other than the innermost one.
+#[derive(Clone, Copy, Debug)]
+struct Frame {
+    /// The function.
+    function: FunctionId,
+    /// The index of the instruction to continue at.
+    pc: usize,
+    /// Where the function's registers start on the stack.
+    base: usize,
+    /// The register of the caller that the returned value goes in (for calls made by this frame).
+    dst: Reg,
+}
+
+/// A virtual machine that runs the functions of a [Module], which must be well formed (see
+/// [verify](super::verify())). Modules from [compile](super::compile()) and [load](super::wbc::load) are.
+#[derive(Debug)]
+pub struct Vm<'m> {
+    /// The module.
+    module: &'m Module,
+    /// The value of every constant in the module's constant pool.
+    constants: Vec<Value>,
+    /// The registers of every call in progress.
+    stack: Vec<Value>,
+    /// The calls in progress, other than the innermost one.
+    frames: Vec<Frame>,
+}
+
+impl<'m> Vm<'m> {
+    /// Make a virtual machine for a module.
+    pub fn new(module: &'m Module) -> Self {
+        let constants = (module.constants.iter())
+            .map(|constant| match constant {
+                Constant::Void => Value::Void,
+                Constant::Bool(value) => Value::Bool(*value),
+                Constant::Int(value, ty) => Value::Int(*value, *ty),
+                Constant::Float(value) => Value::Float(*value),
+                Constant::Str(value) => Value::Str(Rc::from(value.as_str())),
+                Constant::Function(function) => Value::Function(*function),
+                Constant::Builtin(builtin) => Value::Builtin(*builtin),
+                Constant::Variant(ty, index) => Value::Variant(*ty, *index),
+            })
+            .collect();
+
+        Vm {
+            module,
+            constants,
+            stack: Vec::new(),
+            frames: Vec::new(),
+        }
+    }
+
+    /// Run the module's entry function, writing anything it prints to the given output.
+    pub fn run(&mut self, out: &mut dyn Write) -> Result<Value, Trap> {
+        match self.module.entry {
+            Some(entry) => self.call(entry, &[], out),
+            None => Err(Trap {
+                message: "the module has no `main` function".to_owned(),
+                function: String::new(),
+                offset: 0,
+            }),
+        }
+    }
+
+    /// Call a function with the given arguments, writing anything it prints to the given output.
+    pub fn call(
+        &mut self,
+        function: FunctionId,
+        args: &[Value],
+        out: &mut dyn Write,
+    ) -> Result<Value, Trap> {
+        let target = self.module.function(function);
+
+        if args.len() != target.params as usize {
+            return Err(Trap {
+                message: format!(
+                    "`{}` takes {} arguments, but is given {}",
+                    target.name,
+                    target.params,
+                    args.len()
+                ),
+                function: String::new(),
+                offset: 0,
+            });
+        }
+
+        self.stack.clear();
+        self.frames.clear();
+        self.stack.resize(target.registers.len(), Value::Void);
+        self.stack[..args.len()].clone_from_slice(args);
+
+        let result = self.run_loop(function, out);
+        self.stack.clear();
+        self.frames.clear();
+        result
+    }
+
+    /// Run until the given function (the bottom of the stack) returns.
+    fn run_loop(&mut self, function: FunctionId, out: &mut dyn Write) -> Result<Value, Trap> {
+        let module = self.module;
+        let mut function = function;
+        let mut current = module.function(function);
+        let mut code = &current.code[..];
+        let mut base = 0;
+        let mut pc = 0;
+
+        macro_rules! reg {
+            ($reg:expr) => {
+                self.stack[base + $reg.0 as usize]
+            };
+        }
+
+        macro_rules! trap {
+            ($($message:tt)*) => {
+                return Err(Trap {
+                    message: format!($($message)*),
+                    function: current.name.clone(),
+                    offset: pc - 1,
+                })
+            };
+        }
+
+        macro_rules! int {
+            ($reg:expr) => {
+                match &reg!($reg) {
+                    Value::Int(value, _) => *value,
+                    other => trap!("expected an integer, found {other:?}"),
+                }
+            };
+        }
+
+        macro_rules! float {
+            ($reg:expr) => {
+                match &reg!($reg) {
+                    Value::Float(value) => *value,
+                    other => trap!("expected a floating point number, found {other:?}"),
+                }
+            };
+        }
+
+        macro_rules! boolean {
+            ($reg:expr) => {
+                match &reg!($reg) {
+                    Value::Bool(value) => *value,
+                    other => trap!("expected a `bool`, found {other:?}"),
+                }
+            };
+        }
+
+        macro_rules! pointer {
+            ($reg:expr) => {
+                match &reg!($reg) {
+                    Value::Ref(pointer) => Rc::clone(pointer),
+                    other => trap!("expected a reference, found {other:?}"),
+                }
+            };
+        }
+
+        // Call a function, with its first arguments coming from the given values and the rest from registers.
+        macro_rules! call {
+            ($dst:expr, $callee:expr, $prefix:expr, $start:expr, $count:expr) => {{
+                let callee: FunctionId = $callee;
+                let prefix: &[Value] = $prefix;
+
+                if self.frames.len() >= MAX_DEPTH {
+                    trap!("stack overflow");
+                }
+
+                let new_base = base + current.registers.len();
+                let target = module.function(callee);
+
+                // Calls through values (and methods) aren't checked until they happen.
+                let arity = prefix.len() + $count as usize;
+                if arity != target.params as usize {
+                    trap!(
+                        "`{}` takes {} arguments, but is given {arity}",
+                        target.name,
+                        target.params
+                    );
+                }
+                self.stack.truncate(new_base);
+                self.stack.extend_from_slice(prefix);
+
+                for index in 0..$count as usize {
+                    let arg = self.stack[base + $start.0 as usize + index].clone();
+                    self.stack.push(arg);
+                }
+
+                self.stack
+                    .resize(new_base + target.registers.len(), Value::Void);
+
+                self.frames.push(Frame {
+                    function,
+                    pc,
+                    base,
+                    dst: $dst,
+                });
+
+                function = callee;
+                current = target;
+                code = &current.code[..];
+                base = new_base;
+                pc = 0;
+            }};
+        }
+
+        loop {
+            let Some(instruction) = code.get(pc) else {
+                pc += 1;
+                trap!("ran past the end of the function");
+            };
+
+            pc += 1;
+
+            match *instruction {
+                Instruction::LoadConst { dst, constant } => {
+                    reg!(dst) = self.constants[constant.0 as usize].clone()
+                }
+
+                Instruction::Move { dst, src } => reg!(dst) = reg!(src).clone(),
+
+                Instruction::Int {
+                    op,
+                    ty,
+                    dst,
+                    lhs,
+                    rhs,
+                } => {
+                    let (lhs, rhs) = (int!(lhs), int!(rhs));
+
+                    reg!(dst) = match int_op(op, ty, lhs, rhs) {
+                        Ok(value) => Value::Int(value, ty),
+                        Err(message) => trap!("{message}"),
+                    };
+                }
+
+                Instruction::IntNeg { ty, dst, src } => {
+                    reg!(dst) = match ty.narrow(-ty.widen(int!(src))) {
+                        Some(value) => Value::Int(value, ty),
+                        None => trap!("arithmetic overflow"),
+                    };
+                }
+
+                Instruction::IntNot { ty, dst, src } => {
+                    reg!(dst) = Value::Int(ty.wrap(!ty.widen(int!(src))), ty);
+                }
+
+                Instruction::Float {
+                    op,
+                    ty,
+                    dst,
+                    lhs,
+                    rhs,
+                } => {
+                    let (lhs, rhs) = (float!(lhs), float!(rhs));
+
+                    let value = match op {
+                        FloatOp::Add => lhs + rhs,
+                        FloatOp::Sub => lhs - rhs,
+                        FloatOp::Mul => lhs * rhs,
+                        FloatOp::Div => lhs / rhs,
+                        FloatOp::Rem => lhs % rhs,
+                    };
+
+                    reg!(dst) = Value::Float(match ty {
+                        FloatTy::F32 => value as f32 as f64,
+                        FloatTy::F64 => value,
+                    });
+                }
+
+                Instruction::FloatNeg { dst, src } => reg!(dst) = Value::Float(-float!(src)),
+
+                Instruction::Bool { op, dst, lhs, rhs } => {
+                    let (lhs, rhs) = (boolean!(lhs), boolean!(rhs));
+
+                    reg!(dst) = Value::Bool(match op {
+                        BoolOp::And => lhs & rhs,
+                        BoolOp::Or => lhs | rhs,
+                        BoolOp::Xor => lhs ^ rhs,
+                    });
+                }
+
+                Instruction::BoolNot { dst, src } => reg!(dst) = Value::Bool(!boolean!(src)),
+
+                Instruction::Compare {
+                    op,
+                    kind,
+                    dst,
+                    lhs,
+                    rhs,
+                } => {
+                    let ordering = match kind {
+                        CompareKind::Int(ty) => Some(ty.widen(int!(lhs)).cmp(&ty.widen(int!(rhs)))),
+                        CompareKind::Float => float!(lhs).partial_cmp(&float!(rhs)),
+                        CompareKind::Value => match compare_values(&reg!(lhs), &reg!(rhs)) {
+                            Ok(ordering) => ordering,
+                            Err(()) if matches!(op, CompareOp::Eq | CompareOp::Ne) => {
+                                (reg!(lhs) == reg!(rhs)).then_some(Ordering::Equal)
+                            }
+                            Err(()) => trap!("these values can't be ordered"),
+                        },
+                    };
+
+                    reg!(dst) = Value::Bool(match op {
+                        CompareOp::Eq => ordering == Some(Ordering::Equal),
+                        CompareOp::Ne => ordering != Some(Ordering::Equal),
+                        CompareOp::Lt => ordering == Some(Ordering::Less),
+                        CompareOp::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+                        CompareOp::Gt => ordering == Some(Ordering::Greater),
+                        CompareOp::Ge => {
+                            matches!(ordering, Some(Ordering::Greater | Ordering::Equal))
+                        }
+                    });
+                }
+
+                Instruction::Alloc { dst } => reg!(dst) = Value::Ref(Rc::new(Pointer::alloc())),
+
+                Instruction::Load { dst, ptr, path } => {
+                    let pointer = pointer!(ptr);
+
+                    reg!(dst) = match pointer.load(current.path(path)) {
+                        Ok(value) => value,
+                        Err(message) => trap!("{message}"),
+                    };
+                }
+
+                Instruction::Store { ptr, path, src } => {
+                    let pointer = pointer!(ptr);
+                    let value = reg!(src).clone();
+
+                    if let Err(message) = pointer.store(current.path(path), value) {
+                        trap!("{message}");
+                    }
+                }
+
+                Instruction::Offset { dst, ptr, path } => {
+                    let pointer = pointer!(ptr);
+                    reg!(dst) = Value::Ref(Rc::new(pointer.offset(current.path(path))));
+                }
+
+                Instruction::Drop { ptr } => {
+                    let pointer = pointer!(ptr);
+                    *pointer.cell.borrow_mut() = Value::Void;
+                }
+
+                Instruction::MakeRecord {
+                    dst,
+                    ty,
+                    start,
+                    count,
+                } => {
+                    let start = base + start.0 as usize;
+                    let fields = self.stack[start..start + count as usize].to_vec();
+                    reg!(dst) = Value::Record(Rc::new(Record { ty, fields }));
+                }
+
+                Instruction::GetField { dst, src, field } => {
+                    reg!(dst) = match &reg!(src) {
+                        Value::Record(record) => match record.fields.get(field as usize) {
+                            Some(value) => value.clone(),
+                            None => trap!("the record doesn't have field {field}"),
+                        },
+                        other => trap!("expected a record, found {other:?}"),
+                    };
+                }
+
+                Instruction::MakeUnion {
+                    dst,
+                    ty,
+                    variant,
+                    src,
+                } => {
+                    let value = reg!(src).clone();
+                    reg!(dst) = Value::Union(Rc::new(Union { ty, variant, value }));
+                }
+
+                Instruction::IsVariant { dst, src, variant } => {
+                    reg!(dst) = match &reg!(src) {
+                        Value::Union(union) => Value::Bool(union.variant == variant),
+                        other => trap!("expected a union, found {other:?}"),
+                    };
+                }
+
+                Instruction::GetVariant { dst, src, variant } => {
+                    reg!(dst) = match &reg!(src) {
+                        Value::Union(union) if union.variant == variant => union.value.clone(),
+                        Value::Union(_) => trap!("the union doesn't hold the variant that's used"),
+                        other => trap!("expected a union, found {other:?}"),
+                    };
+                }
+
+                Instruction::MakeClosure {
+                    dst,
+                    function,
+                    start,
+                    count,
+                } => {
+                    let start = base + start.0 as usize;
+                    let captures = self.stack[start..start + count as usize].to_vec();
+                    reg!(dst) = Value::Closure(Rc::new(Closure { function, captures }));
+                }
+
+                Instruction::Call {
+                    dst,
+                    callee,
+                    start,
+                    count,
+                } => match reg!(callee).clone() {
+                    Value::Function(callee) => call!(dst, callee, &[], start, count),
+                    Value::Closure(closure) => {
+                        call!(dst, closure.function, &closure.captures, start, count)
+                    }
+
+                    Value::Builtin(builtin) => {
+                        let mut text = String::new();
+
+                        if count > 0 {
+                            let arg = &reg!(start);
+
+                            // Builtins called through a value don't know the type of their argument.
+                            match arg.runtime_ty(module) {
+                                Some(ty) => value::format(module, ty, arg, &mut text),
+                                None => value::format_untyped(module, arg, &mut text),
+                            }
+                        }
+
+                        if builtin == Builtin::Println {
+                            text.push('\n');
+                        }
+
+                        if let Err(error) = out.write_all(text.as_bytes()) {
+                            trap!("failed to write output: {error}");
+                        }
+
+                        reg!(dst) = Value::Void;
+                    }
+
+                    other => trap!("expected a function, found {other:?}"),
+                },
+
+                Instruction::CallDirect {
+                    dst,
+                    function: callee,
+                    start,
+                    count,
+                } => call!(dst, callee, &[], start, count),
+
+                Instruction::CallMethod {
+                    dst,
+                    method,
+                    start,
+                    count,
+                } => {
+                    let table = &module.methods[method.0 as usize];
+
+                    let mut receiver = reg!(start).clone();
+
+                    while let Value::Ref(pointer) = &receiver {
+                        receiver = match pointer.load(&[]) {
+                            Ok(value) => value,
+                            Err(message) => trap!("{message}"),
+                        };
+                    }
+
+                    let ty = receiver.runtime_ty(module);
+
+                    match ty.and_then(|ty| table.find(ty)) {
+                        Some(callee) => call!(dst, callee, &[], start, count),
+                        None => match ty {
+                            Some(ty) => trap!(
+                                "`{}` isn't implemented for `{}`",
+                                table.name,
+                                disassemble::type_name(module, ty)
+                            ),
+                            None => trap!("`{}` isn't implemented for this value", table.name),
+                        },
+                    }
+                }
+
+                Instruction::Print {
+                    dst,
+                    src,
+                    ty,
+                    newline,
+                } => {
+                    let mut text = String::new();
+                    value::format(module, ty, &reg!(src), &mut text);
+
+                    if newline {
+                        text.push('\n');
+                    }
+
+                    if let Err(error) = out.write_all(text.as_bytes()) {
+                        trap!("failed to write output: {error}");
+                    }
+
+                    reg!(dst) = Value::Void;
+                }
+
+                Instruction::Jump { target } => pc = target as usize,
+
+                Instruction::JumpIf { condition, target } => {
+                    if boolean!(condition) {
+                        pc = target as usize;
+                    }
+                }
+
+                Instruction::JumpIfNot { condition, target } => {
+                    if !boolean!(condition) {
+                        pc = target as usize;
+                    }
+                }
+
+                Instruction::Switch { src, table } => {
+                    let value = match &reg!(src) {
+                        Value::Int(value, _) => *value,
+                        Value::Variant(_, index) => *index as i64,
+                        other => trap!("expected an integer or enum, found {other:?}"),
+                    };
+
+                    pc = current.switch(table).target(value) as usize;
+                }
+
+                Instruction::Return { src } => {
+                    let value = std::mem::take(&mut reg!(src));
+
+                    let Some(frame) = self.frames.pop() else {
+                        return Ok(value);
+                    };
+
+                    self.stack.truncate(base);
+                    function = frame.function;
+                    current = module.function(function);
+                    code = &current.code[..];
+                    base = frame.base;
+                    pc = frame.pc;
+                    reg!(frame.dst) = value;
+                }
+
+                Instruction::Trap { message } => match module.constant(message) {
+                    Constant::Str(message) => trap!("{message}"),
+                    other => trap!("{other:?}"),
+                },
+            }
+        }
+    }
+}
+
+/// Run an operation on two integers of the given type.
+fn int_op(op: IntOp, ty: IntTy, lhs: i64, rhs: i64) -> Result<i64, &'static str> {
+    let (a, b) = (ty.widen(lhs), ty.widen(rhs));
+
+    let result = match op {
+        IntOp::Add => a + b,
+        IntOp::Sub => a - b,
+        IntOp::Mul => a * b,
+        IntOp::Div | IntOp::Rem if b == 0 => return Err("division by zero"),
+        IntOp::Div => a / b,
+        IntOp::Rem => a % b,
+        IntOp::And => return Ok(lhs & rhs),
+        IntOp::Or => return Ok(lhs | rhs),
+        IntOp::Xor => return Ok(lhs ^ rhs),
+
+        IntOp::Shl | IntOp::Shr if b < 0 || b >= ty.bits as i128 => {
+            return Err("shift by at least the number of bits in the type");
+        }
+
+        IntOp::Shl => return Ok(ty.wrap(a << b)),
+        IntOp::Shr => a >> b,
+    };
+
+    ty.narrow(result).ok_or("arithmetic overflow")
+}
+
+/// Order two values that aren't numbers. Returns an error if they can't be ordered.
+fn compare_values(lhs: &Value, rhs: &Value) -> Result<Option<Ordering>, ()> {
+    match (lhs, rhs) {
+        (Value::Bool(a), Value::Bool(b)) => Ok(Some(a.cmp(b))),
+        (Value::Char(a), Value::Char(b)) => Ok(Some(a.cmp(b))),
+        (Value::Str(a), Value::Str(b)) => Ok(Some(a.cmp(b))),
+        (Value::Variant(a, i), Value::Variant(b, j)) if a == b => Ok(Some(i.cmp(j))),
+        (Value::Int(a, ty), Value::Int(b, _)) => Ok(Some(ty.widen(*a).cmp(&ty.widen(*b)))),
+        (Value::Ref(a), Value::Ref(b)) => match (a.load(&[]), b.load(&[])) {
+            (Ok(a), Ok(b)) => compare_values(&a, &b),
+            _ => Err(()),
+        },
+        _ => Err(()),
+    }
+}
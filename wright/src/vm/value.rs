@@ -0,0 +1,270 @@
+//! The values held in the registers of the [Vm](super::Vm).
+//!
+//! Records and unions are shared between registers until one of them is changed through a reference, at which
+//! point the changed one is copied (so assigning a record copies it, as far as the program can tell). Memory that
+//! can be referenced -- the slots of bindings that are assigned to or borrowed -- is a shared cell, and references
+//! point to a field path inside of one.
+
+use super::bytecode::{FunctionId, IntTy, Module, Type, TypeId};
+use crate::analysis::builtins::Builtin;
+use std::{cell::RefCell, fmt::Write, rc::Rc};
+
+/// A value.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Value {
+    /// The value of expressions that don't produce one. This is also the value of registers that haven't been
+    /// written yet.
+    #[default]
+    Void,
+    /// A `bool`.
+    Bool(bool),
+    /// An integer of the given type, represented as described by [IntTy].
+    Int(i64, IntTy),
+    /// A floating point number.
+    Float(f64),
+    /// A `char`.
+    Char(char),
+    /// A string.
+    Str(Rc<str>),
+    /// A record.
+    Record(Rc<Record>),
+    /// A union.
+    Union(Rc<Union>),
+    /// A variant of an enum: the enum's type and the index of the variant.
+    Variant(TypeId, u32),
+    /// A function.
+    Function(FunctionId),
+    /// A builtin function.
+    Builtin(Builtin),
+    /// A closure.
+    Closure(Rc<Closure>),
+    /// A reference.
+    Ref(Rc<Pointer>),
+}
+
+impl Value {
+    /// Get the type of a value that carries one, which decides what trait methods called on it run. Records,
+    /// unions, and enums carry their entry of the type table; the entries of `bool`s, `char`s, strings, and
+    /// integers are looked up in the module's type table.
+    pub fn runtime_ty(&self, module: &Module) -> Option<TypeId> {
+        let entry = match self {
+            Value::Record(record) => return Some(record.ty),
+            Value::Union(union) => return Some(union.ty),
+            Value::Variant(ty, _) => return Some(*ty),
+            Value::Bool(_) => Type::Bool,
+            Value::Char(_) => Type::Char,
+            Value::Str(_) => Type::Str,
+            Value::Int(_, int) => Type::Int(*int),
+            _ => return None,
+        };
+
+        let index = module.types.iter().position(|ty| *ty == entry)?;
+        Some(TypeId(index as u32))
+    }
+}
+
+/// The contents of a record value.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Record {
+    /// The type of the record.
+    pub ty: TypeId,
+    /// The value of each field, in order.
+    pub fields: Vec<Value>,
+}
+
+/// The contents of a union value.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Union {
+    /// The type of the union.
+    pub ty: TypeId,
+    /// The index of the variant the union holds.
+    pub variant: u32,
+    /// The value of the variant.
+    pub value: Value,
+}
+
+/// The contents of a closure value.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Closure {
+    /// The function that runs when the closure is called.
+    pub function: FunctionId,
+    /// The captured values, passed to the function before the closure's arguments.
+    pub captures: Vec<Value>,
+}
+
+/// Where a reference points: a field path inside of a cell of memory.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pointer {
+    /// The cell.
+    pub cell: Rc<RefCell<Value>>,
+    /// The index of each field (of a record) or variant (of a union) on the way to the referenced value.
+    pub path: Vec<u32>,
+}
+
+impl Pointer {
+    /// Make a reference to a new cell holding [Value::Void].
+    pub fn alloc() -> Pointer {
+        Pointer {
+            cell: Rc::new(RefCell::new(Value::Void)),
+            path: Vec::new(),
+        }
+    }
+
+    /// Make a reference to a field path of the value this points to.
+    pub fn offset(&self, path: &[u32]) -> Pointer {
+        let mut offset = self.clone();
+        offset.path.extend_from_slice(path);
+        offset
+    }
+
+    /// Read the value at a field path of the value this points to.
+    pub fn load(&self, path: &[u32]) -> Result<Value, &'static str> {
+        let cell = self.cell.borrow();
+        let mut value = &*cell;
+
+        for step in self.path.iter().chain(path) {
+            value = project(value, *step)?;
+        }
+
+        Ok(value.clone())
+    }
+
+    /// Write a value to a field path of the value this points to. If the last step is a variant of a union, the
+    /// union is changed to hold that variant.
+    pub fn store(&self, path: &[u32], new: Value) -> Result<(), &'static str> {
+        let mut cell = self.cell.borrow_mut();
+        let mut steps = self.path.iter().chain(path).copied().peekable();
+        let mut value = &mut *cell;
+
+        while let Some(step) = steps.next() {
+            if steps.peek().is_some() {
+                value = project_mut(value, step)?;
+                continue;
+            }
+
+            match value {
+                Value::Record(record) => {
+                    let field = (Rc::make_mut(record).fields.get_mut(step as usize))
+                        .ok_or("a field path leads out of a record")?;
+                    *field = new;
+                }
+
+                Value::Union(union) => {
+                    let union = Rc::make_mut(union);
+                    union.variant = step;
+                    union.value = new;
+                }
+
+                _ => return Err("a field path leads into a value that isn't a record or union"),
+            }
+
+            return Ok(());
+        }
+
+        *value = new;
+        Ok(())
+    }
+}
+
+/// Take one step of a field path.
+fn project(value: &Value, step: u32) -> Result<&Value, &'static str> {
+    match value {
+        Value::Record(record) => {
+            (record.fields.get(step as usize)).ok_or("a field path leads out of a record")
+        }
+        Value::Union(union) if union.variant == step => Ok(&union.value),
+        Value::Union(_) => Err("a union doesn't hold the variant that's used"),
+        _ => Err("a field path leads into a value that isn't a record or union"),
+    }
+}
+
+/// Take one step of a field path to a value that's changed, copying the record or union it leads into if it's
+/// shared.
+fn project_mut(value: &mut Value, step: u32) -> Result<&mut Value, &'static str> {
+    match value {
+        Value::Record(record) => (Rc::make_mut(record).fields.get_mut(step as usize))
+            .ok_or("a field path leads out of a record"),
+        Value::Union(union) if union.variant == step => Ok(&mut Rc::make_mut(union).value),
+        Value::Union(_) => Err("a union doesn't hold the variant that's used"),
+        _ => Err("a field path leads into a value that isn't a record or union"),
+    }
+}
+
+/// Write a value of the given type the way `wright::io::print` does: integers in decimal, strings without quotes,
+/// records and unions with their fields (like `Point { x: 1, y: 2 }`), enums as `Color::Red`, and references as
+/// the value they point to.
+pub fn format(module: &Module, ty: TypeId, value: &Value, out: &mut String) {
+    match (module.ty(ty), value) {
+        (Type::Int(int), Value::Int(value, _)) => _ = write!(out, "{}", int.widen(*value)),
+        (Type::Reference(target), Value::Ref(pointer)) => match pointer.load(&[]) {
+            Ok(value) => format(module, *target, &value, out),
+            Err(_) => out.push_str("<invalid reference>"),
+        },
+
+        (Type::Record { name, fields }, Value::Record(record)) => {
+            out.push_str(name);
+            out.push_str(" {");
+
+            for (index, ((field, ty), value)) in fields.iter().zip(&record.fields).enumerate() {
+                out.push_str(if index > 0 { ", " } else { " " });
+                out.push_str(field);
+                out.push_str(": ");
+                format(module, *ty, value, out);
+            }
+
+            out.push_str(" }");
+        }
+
+        (Type::Union { name, variants }, Value::Union(union)) => {
+            let (variant, ty) = &variants[union.variant as usize];
+            _ = write!(out, "{name} {{ {variant}: ");
+            format(module, *ty, &union.value, out);
+            out.push_str(" }");
+        }
+
+        (Type::Enum { name, variants }, Value::Variant(_, index)) => {
+            _ = write!(out, "{name}::{}", variants[*index as usize]);
+        }
+
+        // Values of generic types are written with the type they carry, if they carry one.
+        (_, value) => match value.runtime_ty(module) {
+            Some(runtime) if runtime != ty => format(module, runtime, value, out),
+            _ => format_untyped(module, value, out),
+        },
+    }
+}
+
+/// Write a value without knowing its type.
+pub fn format_untyped(module: &Module, value: &Value, out: &mut String) {
+    match value {
+        Value::Void => out.push_str("()"),
+        Value::Bool(value) => _ = write!(out, "{value}"),
+        Value::Int(value, ty) => _ = write!(out, "{}", ty.widen(*value)),
+        Value::Float(value) => _ = write!(out, "{value}"),
+        Value::Char(value) => out.push(*value),
+        Value::Str(value) => out.push_str(value),
+        Value::Function(function) => _ = write!(out, "<func {}>", module.function(*function).name),
+        Value::Builtin(builtin) => _ = write!(out, "<func wright::io::{}>", builtin.name()),
+        Value::Closure(closure) => {
+            _ = write!(out, "<closure {}>", module.function(closure.function).name)
+        }
+
+        Value::Ref(pointer) => match pointer.load(&[]) {
+            Ok(value) => format_untyped(module, &value, out),
+            Err(_) => out.push_str("<invalid reference>"),
+        },
+
+        Value::Record(_) | Value::Union(_) | Value::Variant(..) => {
+            let ty = value
+                .runtime_ty(module)
+                .expect("records, unions, and enums carry their type");
+
+            match (module.ty(ty), value) {
+                (Type::Record { .. }, Value::Record(_))
+                | (Type::Union { .. }, Value::Union(_))
+                | (Type::Enum { .. }, Value::Variant(..)) => format(module, ty, value, out),
+                _ => out.push_str("<value>"),
+            }
+        }
+    }
+}
@@ -0,0 +1,917 @@
+//! Checking that a bytecode [Module] is well formed before it runs -- modules read from files can't be trusted
+//! the way the compiler's output can.
+
+use super::bytecode::{
+    CompareKind, Constant, Function, FunctionId, Instruction, IntOp, IntTy, Module, PathId, Reg,
+    Type, TypeId,
+};
+
+/// Check that a module is well formed, returning a message for each problem found:
+///
+/// - Every index of a constant, type, function, method, field path, and switch table refers to one that exists.
+/// - Every register an instruction uses is in its function's register window, so each call's stack depth is known
+///   up front (it's the number of registers), and every function has room for its parameters.
+/// - Every jump goes to an instruction of its function, and control can't run past the last instruction.
+/// - Every instruction's operands have the types it expects, where generic (opaque) types stand for any type, and
+///   direct calls pass as many arguments as the function takes.
+/// - The entry function takes no parameters, and the debug info (if there is any) refers to real instructions.
+pub fn verify(module: &Module) -> Vec<String> {
+    let mut verifier = Verifier {
+        module,
+        errors: Vec::new(),
+    };
+
+    verifier.check_types();
+    verifier.check_signatures();
+
+    // The rest of the checks look types up (including those of other functions' registers, through calls), so they
+    // only run once every type that's used exists.
+    if !verifier.errors.is_empty() {
+        return verifier.errors;
+    }
+
+    verifier.check_module();
+
+    for index in 0..module.functions.len() {
+        FunctionVerifier::new(&mut verifier, FunctionId(index as u32)).run();
+    }
+
+    verifier.errors
+}
+
+/// The state of verifying a module.
+struct Verifier<'m> {
+    /// The module being verified.
+    module: &'m Module,
+    /// The problems found.
+    errors: Vec<String>,
+}
+
+impl Verifier<'_> {
+    /// Check that the type table only refers to types that exist, and that no reference refers to itself.
+    fn check_types(&mut self) {
+        let count = self.module.types.len();
+
+        for (index, ty) in self.module.types.iter().enumerate() {
+            let ids: Vec<TypeId> = match ty {
+                Type::Reference(target) => vec![*target],
+                Type::Record { fields, .. } => fields.iter().map(|(_, ty)| *ty).collect(),
+                Type::Union { variants, .. } => variants.iter().map(|(_, ty)| *ty).collect(),
+                _ => Vec::new(),
+            };
+
+            if ids.iter().any(|id| id.0 as usize >= count) {
+                self.errors
+                    .push(format!("type {index} refers to a type that doesn't exist"));
+                continue;
+            }
+
+            // A chain of references longer than the type table goes around in a circle.
+            let mut target = TypeId(index as u32);
+
+            for _ in 0..=count {
+                match self.module.types.get(target.0 as usize) {
+                    Some(Type::Reference(next)) => target = *next,
+                    _ => break,
+                }
+            }
+
+            if matches!(self.module.types.get(target.0 as usize), Some(Type::Reference(_))) {
+                self.errors
+                    .push(format!("type {index} is a reference to itself"));
+            }
+        }
+    }
+
+    /// Check that the registers and return type of every function have types that exist.
+    fn check_signatures(&mut self) {
+        let count = self.module.types.len();
+
+        for function in &self.module.functions {
+            let mut tys = function.registers.iter().chain([&function.ret]);
+
+            if tys.any(|ty| ty.0 as usize >= count) {
+                (self.errors).push(format!(
+                    "`{}` has a register or return type that doesn't exist",
+                    function.name
+                ));
+            }
+        }
+    }
+
+    /// Check the constant pool, the dispatch tables, the entry function, and the debug info.
+    fn check_module(&mut self) {
+        let module = self.module;
+
+        for (index, constant) in module.constants.iter().enumerate() {
+            match constant {
+                Constant::Function(function) if !self.is_function(*function) => self
+                    .errors
+                    .push(format!("constant {index} is a function that doesn't exist")),
+
+                Constant::Variant(ty, variant) => match module.types.get(ty.0 as usize) {
+                    Some(Type::Enum { variants, .. }) if (*variant as usize) < variants.len() => {}
+                    _ => self.errors.push(format!(
+                        "constant {index} is a variant of an enum that doesn't exist"
+                    )),
+                },
+
+                _ => {}
+            }
+        }
+
+        for table in &module.methods {
+            let is_valid = (table.impls.iter()).all(|(ty, function)| {
+                (ty.0 as usize) < module.types.len() && self.is_function(*function)
+            });
+
+            if !is_valid {
+                (self.errors).push(format!(
+                    "the dispatch table of `{}` refers to a type or function that doesn't exist",
+                    table.name
+                ));
+            }
+
+            if !table.impls.is_sorted_by(|(a, _), (b, _)| a < b) {
+                (self.errors)
+                    .push(format!("the dispatch table of `{}` isn't sorted by type", table.name));
+            }
+        }
+
+        match module.entry {
+            Some(entry) if !self.is_function(entry) => {
+                self.errors
+                    .push("the entry function doesn't exist".to_owned());
+            }
+
+            Some(entry) if module.function(entry).params > 0 => {
+                self.errors
+                    .push("the entry function takes parameters".to_owned());
+            }
+
+            _ => {}
+        }
+
+        if let Some(debug) = &module.debug {
+            if debug.functions.len() > module.functions.len() {
+                (self.errors)
+                    .push("the debug info describes more functions than there are".to_owned());
+            }
+
+            for (function, locations) in module.functions.iter().zip(&debug.functions) {
+                let is_valid = (locations.iter()).all(|location| {
+                    (location.offset as usize) < function.code.len()
+                        && (location.file as usize) < debug.files.len()
+                });
+
+                if !is_valid || !locations.is_sorted_by(|a, b| a.offset < b.offset) {
+                    (self.errors).push(format!(
+                        "the debug info of `{}` doesn't match its instructions",
+                        function.name
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Check if a function exists.
+    fn is_function(&self, function: FunctionId) -> bool {
+        (function.0 as usize) < self.module.functions.len()
+    }
+
+    /// Check if a value of one type can be used where another type is expected. Generic types stand for any type,
+    /// and so do references to them; references are compatible no matter whether they're mutable.
+    ///
+    /// Records and unions are compared by their fields, since a generic one (`Pair<T>`) has a different entry from
+    /// each of its instances (`Pair<i32>`).
+    fn compatible(&self, a: TypeId, b: TypeId) -> bool {
+        self.compatible_in(a, b, &mut Vec::new())
+    }
+
+    /// Check if two types are compatible, assuming the pairs in `assumed` are -- which they're added to while
+    /// their fields are compared, so that records which contain themselves don't go around in a circle.
+    fn compatible_in(&self, a: TypeId, b: TypeId, assumed: &mut Vec<(TypeId, TypeId)>) -> bool {
+        if a == b || assumed.contains(&(a, b)) {
+            return true;
+        }
+
+        let (Some(a_entry), Some(b_entry)) =
+            (self.module.types.get(a.0 as usize), self.module.types.get(b.0 as usize))
+        else {
+            return false;
+        };
+
+        match (a_entry, b_entry) {
+            (Type::Opaque(_), _) | (_, Type::Opaque(_)) => true,
+            (Type::Reference(a), Type::Reference(b)) => self.compatible_in(*a, *b, assumed),
+
+            (
+                Type::Record {
+                    name: a_name,
+                    fields: a_fields,
+                },
+                Type::Record {
+                    name: b_name,
+                    fields: b_fields,
+                },
+            )
+            | (
+                Type::Union {
+                    name: a_name,
+                    variants: a_fields,
+                },
+                Type::Union {
+                    name: b_name,
+                    variants: b_fields,
+                },
+            ) => {
+                // The generic arguments are compared through the fields' types.
+                let same_base = a_name.split('<').next() == b_name.split('<').next();
+
+                if !same_base || a_fields.len() != b_fields.len() {
+                    return false;
+                }
+
+                assumed.push((a, b));
+
+                (a_fields.iter().zip(b_fields)).all(|((a_field, a_ty), (b_field, b_ty))| {
+                    a_field == b_field && self.compatible_in(*a_ty, *b_ty, assumed)
+                })
+            }
+
+            (a, b) => a == b,
+        }
+    }
+}
+
+/// The state of verifying a function.
+struct FunctionVerifier<'v, 'm> {
+    /// The verifier of the module.
+    verifier: &'v mut Verifier<'m>,
+    /// The function being verified.
+    function: &'m Function,
+    /// The index of the instruction being verified.
+    offset: usize,
+}
+
+impl<'v, 'm> FunctionVerifier<'v, 'm> {
+    /// Set up the verification of a function.
+    fn new(verifier: &'v mut Verifier<'m>, function: FunctionId) -> Self {
+        let function = verifier.module.function(function);
+
+        FunctionVerifier {
+            verifier,
+            function,
+            offset: 0,
+        }
+    }
+
+    /// Verify the function.
+    fn run(mut self) {
+        let function = self.function;
+
+        if function.params as usize > function.registers.len() {
+            self.function_error("has fewer registers than parameters");
+        }
+
+        for table in &function.switches {
+            let targets = table.cases.iter().map(|(_, target)| target);
+
+            if targets
+                .chain([&table.default])
+                .any(|target| *target as usize >= function.code.len())
+            {
+                self.function_error("has a switch table that jumps out of the function");
+            }
+
+            if !table.cases.is_sorted_by(|(a, _), (b, _)| a < b) {
+                self.function_error("has a switch table that isn't sorted");
+            }
+        }
+
+        match function.code.last() {
+            Some(
+                Instruction::Jump { .. }
+                | Instruction::Return { .. }
+                | Instruction::Trap { .. }
+                | Instruction::Switch { .. },
+            ) => {}
+            _ => self.function_error("can run past its last instruction"),
+        }
+
+        for (offset, instruction) in function.code.iter().enumerate() {
+            self.offset = offset;
+            self.instruction(*instruction);
+        }
+    }
+
+    /// Record a problem with the function as a whole.
+    fn function_error(&mut self, message: &str) {
+        (self.verifier.errors).push(format!("`{}` {message}", self.function.name));
+    }
+
+    /// Record a problem with the instruction being verified.
+    fn error(&mut self, message: String) {
+        (self.verifier.errors)
+            .push(format!("in `{}` at {}, {message}", self.function.name, self.offset));
+    }
+
+    /// Get the type of a register, if it's in the function's register window.
+    fn reg(&mut self, reg: Reg) -> Option<TypeId> {
+        match self.function.registers.get(reg.0 as usize) {
+            Some(ty) => Some(*ty),
+            None => {
+                self.error(format!("register {} is out of the register window", reg.0));
+                None
+            }
+        }
+    }
+
+    /// Check that a range of consecutive registers is in the function's register window, and get their types.
+    fn regs(&mut self, start: Reg, count: u32) -> Option<&'m [TypeId]> {
+        let end = (start.0 as usize).checked_add(count as usize)?;
+
+        match self.function.registers.get(start.0 as usize..end) {
+            Some(tys) => Some(tys),
+            None => {
+                self.error(format!("registers {}.. are out of the register window", start.0));
+                None
+            }
+        }
+    }
+
+    /// Get an entry of the type table, if it exists.
+    fn ty(&mut self, ty: TypeId) -> Option<&'m Type> {
+        let module = self.verifier.module;
+
+        match module.types.get(ty.0 as usize) {
+            Some(entry) => Some(entry),
+            None => {
+                self.error(format!("type {} doesn't exist", ty.0));
+                None
+            }
+        }
+    }
+
+    /// Check that a register holds a value of a type that's compatible with the given one.
+    fn expect(&mut self, reg: Reg, ty: TypeId) {
+        if let Some(actual) = self.reg(reg) {
+            if !self.verifier.compatible(actual, ty) {
+                self.error(format!("register {} has the wrong type", reg.0));
+            }
+        }
+    }
+
+    /// Check that a register holds a value whose type entry matches a pattern (or is generic).
+    fn expect_kind(&mut self, reg: Reg, what: &str, matches: impl Fn(&Type) -> bool) {
+        if let Some(ty) = self.reg(reg) {
+            let entry = self.verifier.module.ty(ty);
+
+            if !matches!(entry, Type::Opaque(_)) && !matches(entry) {
+                self.error(format!("register {} should be {what}", reg.0));
+            }
+        }
+    }
+
+    /// Check that a register holds an integer of the given type.
+    fn expect_int(&mut self, reg: Reg, int: IntTy) {
+        self.expect_kind(reg, "an integer of the right type", |ty| *ty == Type::Int(int));
+    }
+
+    /// Check that a register holds a `bool`.
+    fn expect_bool(&mut self, reg: Reg) {
+        self.expect_kind(reg, "a `bool`", |ty| *ty == Type::Bool);
+    }
+
+    /// Check that a register holds a reference, and get the type it refers to (or [None] if it's generic).
+    fn expect_reference(&mut self, reg: Reg) -> Option<TypeId> {
+        self.expect_kind(reg, "a reference", |ty| matches!(ty, Type::Reference(_)));
+
+        match self.verifier.module.ty(self.reg(reg)?) {
+            Type::Reference(target) => Some(*target),
+            _ => None,
+        }
+    }
+
+    /// Check that a jump goes to an instruction of the function.
+    fn expect_target(&mut self, target: u32) {
+        if target as usize >= self.function.code.len() {
+            self.error(format!("jumps to {target}, outside of the function"));
+        }
+    }
+
+    /// Get the type at the end of a field path, starting from a type (or [None] if it's generic).
+    fn walk_path(&mut self, start: TypeId, path: PathId) -> Option<TypeId> {
+        let Some(steps) = self.function.paths.get(path.0 as usize) else {
+            self.error(format!("field path {} doesn't exist", path.0));
+            return None;
+        };
+
+        let mut ty = start;
+
+        for step in steps {
+            ty = match self.ty(ty)? {
+                Type::Record { fields: list, .. } | Type::Union { variants: list, .. } => {
+                    match list.get(*step as usize) {
+                        Some((_, ty)) => *ty,
+                        None => {
+                            self.error(format!(
+                                "field path {} leads out of a record or union",
+                                path.0
+                            ));
+                            return None;
+                        }
+                    }
+                }
+
+                Type::Opaque(_) => return None,
+
+                _ => {
+                    self.error(format!("field path {} leads into a value without fields", path.0));
+                    return None;
+                }
+            };
+        }
+
+        Some(ty)
+    }
+
+    /// Get the fields of a record or the variants of a union in a register, if its type is known.
+    fn fields(&mut self, reg: Reg, is_union: bool) -> Option<&'m [(String, TypeId)]> {
+        let module = self.verifier.module;
+
+        match (module.ty(self.reg(reg)?), is_union) {
+            (Type::Record { fields, .. }, false) => Some(fields),
+            (Type::Union { variants, .. }, true) => Some(variants),
+            (Type::Opaque(_), _) => None,
+            _ => {
+                let what = if is_union { "a union" } else { "a record" };
+                self.error(format!("register {} should be {what}", reg.0));
+                None
+            }
+        }
+    }
+
+    /// Check that a function exists, and get the types of its parameters and what it returns.
+    fn callee(&mut self, function: FunctionId) -> Option<(&'m [TypeId], TypeId)> {
+        match self.verifier.module.functions.get(function.0 as usize) {
+            Some(callee) => {
+                let params = callee
+                    .registers
+                    .get(..callee.params as usize)
+                    .unwrap_or_default();
+                Some((params, callee.ret))
+            }
+
+            None => {
+                self.error(format!("function {} doesn't exist", function.0));
+                None
+            }
+        }
+    }
+
+    /// Verify an instruction.
+    fn instruction(&mut self, instruction: Instruction) {
+        let module = self.verifier.module;
+
+        match instruction {
+            Instruction::LoadConst { dst, constant } => {
+                let Some(constant) = module.constants.get(constant.0 as usize) else {
+                    return self.error(format!("constant {} doesn't exist", constant.0));
+                };
+
+                self.expect_kind(dst, "the type of the constant", |ty| match constant {
+                    Constant::Void => *ty == Type::Void,
+                    Constant::Bool(_) => *ty == Type::Bool,
+                    Constant::Int(_, int) => *ty == Type::Int(*int),
+                    Constant::Float(_) => matches!(ty, Type::Float(_)),
+                    Constant::Str(_) => *ty == Type::Str,
+                    Constant::Function(_) | Constant::Builtin(_) => *ty == Type::Function,
+                    Constant::Variant(variant_ty, _) => {
+                        module.types.get(variant_ty.0 as usize) == Some(ty)
+                    }
+                });
+            }
+
+            Instruction::Move { dst, src } => {
+                if let Some(ty) = self.reg(src) {
+                    self.expect(dst, ty);
+                }
+            }
+
+            Instruction::Int {
+                op,
+                ty,
+                dst,
+                lhs,
+                rhs,
+            } => {
+                self.expect_int(dst, ty);
+                self.expect_int(lhs, ty);
+
+                match op {
+                    IntOp::Shl | IntOp::Shr => {
+                        self.expect_kind(rhs, "an integer", |ty| matches!(ty, Type::Int(_)))
+                    }
+                    _ => self.expect_int(rhs, ty),
+                }
+            }
+
+            Instruction::IntNeg { ty, dst, src } | Instruction::IntNot { ty, dst, src } => {
+                self.expect_int(dst, ty);
+                self.expect_int(src, ty);
+            }
+
+            Instruction::Float {
+                ty, dst, lhs, rhs, ..
+            } => {
+                for reg in [dst, lhs, rhs] {
+                    self.expect_kind(reg, "a floating point number of the right type", |entry| {
+                        *entry == Type::Float(ty)
+                    });
+                }
+            }
+
+            Instruction::FloatNeg { dst, src } => {
+                for reg in [dst, src] {
+                    self.expect_kind(reg, "a floating point number", |ty| {
+                        matches!(ty, Type::Float(_))
+                    });
+                }
+            }
+
+            Instruction::Bool { dst, lhs, rhs, .. } => {
+                [dst, lhs, rhs]
+                    .into_iter()
+                    .for_each(|reg| self.expect_bool(reg));
+            }
+
+            Instruction::BoolNot { dst, src } => {
+                self.expect_bool(dst);
+                self.expect_bool(src);
+            }
+
+            Instruction::Compare {
+                kind,
+                dst,
+                lhs,
+                rhs,
+                ..
+            } => {
+                self.expect_bool(dst);
+
+                match kind {
+                    CompareKind::Int(ty) => {
+                        self.expect_int(lhs, ty);
+                        self.expect_int(rhs, ty);
+                    }
+
+                    CompareKind::Float => {
+                        for reg in [lhs, rhs] {
+                            self.expect_kind(reg, "a floating point number", |ty| {
+                                matches!(ty, Type::Float(_))
+                            });
+                        }
+                    }
+
+                    CompareKind::Value => {
+                        if let Some(ty) = self.reg(lhs) {
+                            self.expect(rhs, ty);
+                        }
+                    }
+                }
+            }
+
+            Instruction::Alloc { dst } => {
+                self.expect_reference(dst);
+            }
+
+            Instruction::Load { dst, ptr, path } => {
+                let target = self.expect_reference(ptr);
+
+                if let Some(ty) = target.and_then(|target| self.walk_path(target, path)) {
+                    self.expect(dst, ty);
+                }
+            }
+
+            Instruction::Store { ptr, path, src } => {
+                let target = self.expect_reference(ptr);
+
+                if let Some(ty) = target.and_then(|target| self.walk_path(target, path)) {
+                    self.expect(src, ty);
+                }
+            }
+
+            Instruction::Offset { dst, ptr, path } => {
+                let target = self.expect_reference(ptr);
+                let field = target.and_then(|target| self.walk_path(target, path));
+
+                match (self.expect_reference(dst), field) {
+                    (Some(dst_target), Some(field))
+                        if !self.verifier.compatible(dst_target, field) =>
+                    {
+                        self.error(format!("register {} has the wrong type", dst.0))
+                    }
+                    _ => {}
+                }
+            }
+
+            Instruction::Drop { ptr } => {
+                self.expect_reference(ptr);
+            }
+
+            Instruction::MakeRecord {
+                dst,
+                ty,
+                start,
+                count,
+            } => {
+                let Some(Type::Record { fields, .. }) = self.ty(ty) else {
+                    return self.error(format!("type {} should be a record", ty.0));
+                };
+
+                if fields.len() != count as usize {
+                    return self.error(format!(
+                        "a record with {} fields is made from {count}",
+                        fields.len()
+                    ));
+                }
+
+                if self.regs(start, count).is_some() {
+                    for (index, (_, field)) in fields.iter().enumerate() {
+                        self.expect(Reg(start.0 + index as u32), *field);
+                    }
+                }
+
+                self.expect(dst, ty);
+            }
+
+            Instruction::GetField { dst, src, field } => {
+                if let Some(fields) = self.fields(src, false) {
+                    match fields.get(field as usize) {
+                        Some((_, ty)) => self.expect(dst, *ty),
+                        None => self.error(format!("field {field} doesn't exist")),
+                    }
+                }
+            }
+
+            Instruction::MakeUnion {
+                dst,
+                ty,
+                variant,
+                src,
+            } => {
+                let Some(Type::Union { variants, .. }) = self.ty(ty) else {
+                    return self.error(format!("type {} should be a union", ty.0));
+                };
+
+                match variants.get(variant as usize) {
+                    Some((_, variant_ty)) => self.expect(src, *variant_ty),
+                    None => self.error(format!("variant {variant} doesn't exist")),
+                }
+
+                self.expect(dst, ty);
+            }
+
+            Instruction::IsVariant { dst, src, variant } => {
+                self.expect_bool(dst);
+
+                if let Some(variants) = self.fields(src, true) {
+                    if variant as usize >= variants.len() {
+                        self.error(format!("variant {variant} doesn't exist"));
+                    }
+                }
+            }
+
+            Instruction::GetVariant { dst, src, variant } => {
+                if let Some(variants) = self.fields(src, true) {
+                    match variants.get(variant as usize) {
+                        Some((_, ty)) => self.expect(dst, *ty),
+                        None => self.error(format!("variant {variant} doesn't exist")),
+                    }
+                }
+            }
+
+            Instruction::MakeClosure {
+                dst,
+                function,
+                start,
+                count,
+            } => {
+                self.expect_kind(dst, "a function", |ty| *ty == Type::Function);
+
+                if let (Some((params, _)), Some(captures)) =
+                    (self.callee(function), self.regs(start, count))
+                {
+                    if captures.len() > params.len() {
+                        self.error(format!(
+                            "a closure captures {count} values, but its function takes fewer"
+                        ));
+                    }
+                }
+            }
+
+            Instruction::Call {
+                dst,
+                callee,
+                start,
+                count,
+            } => {
+                self.reg(dst);
+                self.expect_kind(callee, "a function", |ty| *ty == Type::Function);
+                self.regs(start, count);
+            }
+
+            Instruction::CallDirect {
+                dst,
+                function,
+                start,
+                count,
+            } => {
+                let Some((params, ret)) = self.callee(function) else {
+                    return;
+                };
+
+                if params.len() != count as usize {
+                    return self.error(format!(
+                        "a function taking {} arguments is called with {count}",
+                        params.len()
+                    ));
+                }
+
+                if self.regs(start, count).is_some() {
+                    for (index, param) in params.iter().enumerate() {
+                        self.expect(Reg(start.0 + index as u32), *param);
+                    }
+                }
+
+                self.expect(dst, ret);
+            }
+
+            Instruction::CallMethod {
+                dst,
+                method,
+                start,
+                count,
+            } => {
+                self.reg(dst);
+
+                if method.0 as usize >= module.methods.len() {
+                    self.error(format!("method {} doesn't exist", method.0));
+                }
+
+                // Methods are found by the type of their receiver, so there must be one.
+                if count == 0 {
+                    self.error("a method is called without a receiver".to_owned());
+                }
+
+                self.regs(start, count);
+            }
+
+            Instruction::Print { dst, src, ty, .. } => {
+                self.reg(dst);
+
+                if self.ty(ty).is_some() {
+                    self.expect(src, ty);
+                }
+            }
+
+            Instruction::Jump { target } => self.expect_target(target),
+
+            Instruction::JumpIf { condition, target }
+            | Instruction::JumpIfNot { condition, target } => {
+                self.expect_bool(condition);
+                self.expect_target(target);
+            }
+
+            Instruction::Switch { src, table } => {
+                self.expect_kind(src, "an integer or enum", |ty| {
+                    matches!(ty, Type::Int(_) | Type::Enum { .. })
+                });
+
+                if table.0 as usize >= self.function.switches.len() {
+                    self.error(format!("switch table {} doesn't exist", table.0));
+                }
+            }
+
+            Instruction::Return { src } => self.expect(src, self.function.ret),
+
+            Instruction::Trap { message } => {
+                if !matches!(module.constants.get(message.0 as usize), Some(Constant::Str(_))) {
+                    self.error("a trap's message isn't a string constant".to_owned());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::verify;
+    use crate::vm::bytecode::{
+        ConstId, Constant, Function, FunctionId, Instruction, IntOp, IntTy, Module, Reg, Type,
+        TypeId,
+    };
+
+    const U8: IntTy = IntTy {
+        bits: 8,
+        signed: false,
+    };
+
+    /// Verify a module with a `main` function that takes a `u8` and has a `u8` and a `bool` register besides.
+    fn verify_code(code: Vec<Instruction>) -> Vec<String> {
+        let module = Module {
+            constants: vec![Constant::Int(1, U8), Constant::Bool(true)],
+            types: vec![Type::Int(U8), Type::Bool],
+            functions: vec![Function {
+                name: "main".to_owned(),
+                params: 1,
+                registers: vec![TypeId(0), TypeId(0), TypeId(1)],
+                ret: TypeId(0),
+                code,
+                paths: Vec::new(),
+                switches: Vec::new(),
+            }],
+            ..Module::default()
+        };
+
+        verify(&module)
+    }
+
+    #[test]
+    fn test_valid() {
+        let code = vec![
+            Instruction::LoadConst {
+                dst: Reg(1),
+                constant: ConstId(0),
+            },
+            Instruction::Int {
+                op: IntOp::Add,
+                ty: U8,
+                dst: Reg(1),
+                lhs: Reg(0),
+                rhs: Reg(1),
+            },
+            Instruction::CallDirect {
+                dst: Reg(1),
+                function: FunctionId(0),
+                start: Reg(1),
+                count: 1,
+            },
+            Instruction::Return { src: Reg(1) },
+        ];
+
+        assert_eq!(verify_code(code), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_invalid() {
+        let cases = [
+            (
+                vec![Instruction::Return { src: Reg(3) }],
+                "in `main` at 0, register 3 is out of the register window",
+            ),
+            (
+                vec![Instruction::Jump { target: 1 }],
+                "in `main` at 0, jumps to 1, outside of the function",
+            ),
+            (
+                vec![Instruction::Move {
+                    dst: Reg(0),
+                    src: Reg(0),
+                }],
+                "`main` can run past its last instruction",
+            ),
+            (
+                vec![Instruction::Return { src: Reg(2) }],
+                "in `main` at 0, register 2 has the wrong type",
+            ),
+            (
+                vec![
+                    Instruction::LoadConst {
+                        dst: Reg(1),
+                        constant: ConstId(1),
+                    },
+                    Instruction::Return { src: Reg(0) },
+                ],
+                "in `main` at 0, register 1 should be the type of the constant",
+            ),
+            (
+                vec![
+                    Instruction::CallDirect {
+                        dst: Reg(1),
+                        function: FunctionId(0),
+                        start: Reg(0),
+                        count: 2,
+                    },
+                    Instruction::Return { src: Reg(1) },
+                ],
+                "in `main` at 0, a function taking 1 arguments is called with 2",
+            ),
+        ];
+
+        for (code, message) in cases {
+            assert_eq!(verify_code(code), vec![message.to_owned()]);
+        }
+    }
+}
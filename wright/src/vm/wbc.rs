@@ -0,0 +1,856 @@
+//! The `.wbc` file format, which holds a bytecode [Module] so that it can be compiled once and run later.
+//!
+//! A file starts with a header -- the magic number `\0WBC`, the [VERSION] of the format as a `u16`, and the entry
+//! function as a `u32` (`u32::MAX` if there isn't one) -- followed by sections, each a one-byte tag, the length of
+//! its contents in bytes as a `u32`, and its contents:
+//!
+//! | Tag | Section                                     | Required |
+//! |-----|---------------------------------------------|----------|
+//! | 1   | The constant pool.                          | Yes      |
+//! | 2   | The type table (including record layouts). | Yes      |
+//! | 3   | The function table.                         | Yes      |
+//! | 4   | The dispatch tables of trait methods.       | Yes      |
+//! | 5   | The debug info.                             | No       |
+//!
+//! Every number is little endian. Strings are a `u32` length followed by that many bytes of UTF-8, lists are a
+//! `u32` count followed by their elements, and enums (including [Instruction]s) are a one-byte tag followed by
+//! their fields, in the order they're declared.
+//!
+//! [load] checks the module with [verify](super::verify()) after reading it, so that a module from a file that's
+//! been corrupted (or written by something else) is rejected before it runs.
+
+use super::bytecode::{
+    BoolOp, CompareKind, CompareOp, ConstId, Constant, DebugInfo, FloatOp, FloatTy, Function,
+    FunctionId, Instruction, IntOp, IntTy, Location, MethodId, MethodTable, Module, PathId, Reg,
+    SwitchId, SwitchTable, Type, TypeId,
+};
+use crate::analysis::builtins::Builtin;
+use std::fmt;
+
+/// The magic number that every `.wbc` file starts with.
+pub const MAGIC: [u8; 4] = *b"\0WBC";
+
+/// The version of the format that's written, and the only version that can be read. It changes whenever the
+/// bytecode or its encoding does.
+pub const VERSION: u16 = 2;
+
+/// The tag of each section.
+const CONSTANTS: u8 = 1;
+const TYPES: u8 = 2;
+const FUNCTIONS: u8 = 3;
+const METHODS: u8 = 4;
+const DEBUG: u8 = 5;
+
+/// An error from loading a `.wbc` file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LoadError {
+    /// The file doesn't start with the magic number, so it isn't a `.wbc` file.
+    NotBytecode,
+    /// The file was written with a different version of the format.
+    UnsupportedVersion(u16),
+    /// The file couldn't be read as a module.
+    Malformed {
+        /// The offset in the file where the problem was found.
+        offset: usize,
+        /// What's wrong.
+        message: String,
+    },
+    /// The module was read, but isn't well formed, for each of these reasons.
+    Invalid(Vec<String>),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::NotBytecode => write!(f, "not a wright bytecode file"),
+            LoadError::UnsupportedVersion(version) => write!(
+                f,
+                "the bytecode file is version {version}, but this version of wright only runs version {VERSION}"
+            ),
+            LoadError::Malformed { offset, message } => {
+                write!(f, "malformed bytecode file at byte {offset}: {message}")
+            }
+            LoadError::Invalid(problems) => {
+                write!(f, "invalid bytecode: {}", problems.join("; "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+/// Write a module in the `.wbc` format. The debug info is written if the module has any.
+pub fn write(module: &Module) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&MAGIC);
+    VERSION.encode(&mut out);
+    module
+        .entry
+        .map_or(u32::MAX, |entry| entry.0)
+        .encode(&mut out);
+
+    section(&mut out, CONSTANTS, &module.constants);
+    section(&mut out, TYPES, &module.types);
+    section(&mut out, FUNCTIONS, &module.functions);
+    section(&mut out, METHODS, &module.methods);
+
+    if let Some(debug) = &module.debug {
+        section(&mut out, DEBUG, debug);
+    }
+
+    out
+}
+
+/// Write a section with the given tag.
+fn section(out: &mut Vec<u8>, tag: u8, contents: &impl Encode) {
+    out.push(tag);
+    let length = out.len();
+    out.extend_from_slice(&[0; 4]);
+    contents.encode(out);
+
+    let size = (out.len() - length - 4) as u32;
+    out[length..length + 4].copy_from_slice(&size.to_le_bytes());
+}
+
+/// Read a module from the `.wbc` format, without checking that it's well formed (see [load]).
+pub fn read(bytes: &[u8]) -> Result<Module, LoadError> {
+    if !bytes.starts_with(&MAGIC) {
+        return Err(LoadError::NotBytecode);
+    }
+
+    let mut reader = Reader {
+        bytes,
+        offset: MAGIC.len(),
+    };
+
+    let version = u16::decode(&mut reader)?;
+
+    if version != VERSION {
+        return Err(LoadError::UnsupportedVersion(version));
+    }
+
+    let entry = u32::decode(&mut reader)?;
+
+    let mut module = Module {
+        entry: (entry != u32::MAX).then_some(FunctionId(entry)),
+        ..Module::default()
+    };
+
+    let mut seen = Vec::new();
+
+    while reader.offset < bytes.len() {
+        let start = reader.offset;
+        let tag = u8::decode(&mut reader)?;
+        let length = u32::decode(&mut reader)? as usize;
+        let end = reader.offset.saturating_add(length);
+
+        if end > bytes.len() {
+            return Err(reader.error(format!("section {tag} runs past the end of the file")));
+        }
+
+        if seen.contains(&tag) {
+            return Err(Reader::error_at(start, format!("section {tag} appears more than once")));
+        }
+
+        seen.push(tag);
+
+        let mut section = Reader {
+            bytes: &bytes[..end],
+            offset: reader.offset,
+        };
+
+        match tag {
+            CONSTANTS => module.constants = Vec::decode(&mut section)?,
+            TYPES => module.types = Vec::decode(&mut section)?,
+            FUNCTIONS => module.functions = Vec::decode(&mut section)?,
+            METHODS => module.methods = Vec::decode(&mut section)?,
+            DEBUG => module.debug = Some(DebugInfo::decode(&mut section)?),
+            _ => return Err(Reader::error_at(start, format!("unknown section {tag}"))),
+        }
+
+        if section.offset != end {
+            return Err(section.error(format!("section {tag} has bytes left over")));
+        }
+
+        reader.offset = end;
+    }
+
+    for tag in [CONSTANTS, TYPES, FUNCTIONS, METHODS] {
+        if !seen.contains(&tag) {
+            return Err(reader.error(format!("section {tag} is missing")));
+        }
+    }
+
+    Ok(module)
+}
+
+/// Read a module from the `.wbc` format, and check that it's well formed.
+pub fn load(bytes: &[u8]) -> Result<Module, LoadError> {
+    let module = read(bytes)?;
+    let problems = super::verify(&module);
+
+    match problems.is_empty() {
+        true => Ok(module),
+        false => Err(LoadError::Invalid(problems)),
+    }
+}
+
+/// Reading the contents of a file.
+struct Reader<'b> {
+    /// The bytes that can be read.
+    bytes: &'b [u8],
+    /// The offset of the next byte to read.
+    offset: usize,
+}
+
+impl Reader<'_> {
+    /// Read a number of bytes.
+    fn take(&mut self, count: usize) -> Result<&[u8], LoadError> {
+        let end = self.offset.saturating_add(count);
+
+        match self.bytes.get(self.offset..end) {
+            Some(bytes) => {
+                self.offset = end;
+                Ok(bytes)
+            }
+            None => Err(self.error("unexpected end of section".to_owned())),
+        }
+    }
+
+    /// Read an array of bytes.
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], LoadError> {
+        Ok(self
+            .take(N)?
+            .try_into()
+            .expect("`take` reads as many bytes as it's asked for"))
+    }
+
+    /// Make an error at the current offset.
+    fn error(&self, message: String) -> LoadError {
+        Reader::error_at(self.offset, message)
+    }
+
+    /// Make an error at an offset.
+    fn error_at(offset: usize, message: String) -> LoadError {
+        LoadError::Malformed { offset, message }
+    }
+
+    /// Make an error for an unknown tag of an enum.
+    fn bad_tag<T>(&self, what: &str, tag: u8) -> Result<T, LoadError> {
+        Err(Reader::error_at(self.offset - 1, format!("unknown {what} tag {tag}")))
+    }
+}
+
+/// Something that can be written to and read from a file.
+trait Encode: Sized {
+    /// Write this to the end of a file.
+    fn encode(&self, out: &mut Vec<u8>);
+
+    /// Read this from a file.
+    fn decode(reader: &mut Reader) -> Result<Self, LoadError>;
+}
+
+/// Implement [Encode] for numbers, in little endian.
+macro_rules! encode_numbers {
+    ($($ty:ty),*) => {$(
+        impl Encode for $ty {
+            fn encode(&self, out: &mut Vec<u8>) {
+                out.extend_from_slice(&self.to_le_bytes());
+            }
+
+            fn decode(reader: &mut Reader) -> Result<Self, LoadError> {
+                Ok(<$ty>::from_le_bytes(reader.array()?))
+            }
+        }
+    )*};
+}
+
+encode_numbers!(u8, u16, u32, i64, f64);
+
+/// Implement [Encode] for the `u32` newtypes of indices.
+macro_rules! encode_indices {
+    ($($ty:ident),*) => {$(
+        impl Encode for $ty {
+            fn encode(&self, out: &mut Vec<u8>) {
+                self.0.encode(out);
+            }
+
+            fn decode(reader: &mut Reader) -> Result<Self, LoadError> {
+                Ok($ty(u32::decode(reader)?))
+            }
+        }
+    )*};
+}
+
+encode_indices!(FunctionId, TypeId, ConstId, MethodId, Reg, PathId, SwitchId);
+
+/// Implement [Encode] for enums without fields, each written as its index in the given list of variants.
+macro_rules! encode_tags {
+    ($($ty:ident { $($variant:ident),* $(,)? })*) => {$(
+        impl Encode for $ty {
+            fn encode(&self, out: &mut Vec<u8>) {
+                const VARIANTS: &[$ty] = &[$($ty::$variant),*];
+                let index = VARIANTS.iter().position(|variant| variant == self).unwrap();
+                out.push(index as u8);
+            }
+
+            fn decode(reader: &mut Reader) -> Result<Self, LoadError> {
+                const VARIANTS: &[$ty] = &[$($ty::$variant),*];
+
+                match u8::decode(reader)? {
+                    tag if (tag as usize) < VARIANTS.len() => Ok(VARIANTS[tag as usize]),
+                    tag => reader.bad_tag(stringify!($ty), tag),
+                }
+            }
+        }
+    )*};
+}
+
+encode_tags! {
+    FloatTy { F32, F64 }
+    IntOp { Add, Sub, Mul, Div, Rem, And, Or, Xor, Shl, Shr }
+    FloatOp { Add, Sub, Mul, Div, Rem }
+    BoolOp { And, Or, Xor }
+    CompareOp { Eq, Ne, Lt, Le, Gt, Ge }
+}
+
+impl Encode for bool {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.push(*self as u8);
+    }
+
+    fn decode(reader: &mut Reader) -> Result<Self, LoadError> {
+        match u8::decode(reader)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            tag => reader.bad_tag("bool", tag),
+        }
+    }
+}
+
+impl Encode for String {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (self.len() as u32).encode(out);
+        out.extend_from_slice(self.as_bytes());
+    }
+
+    fn decode(reader: &mut Reader) -> Result<Self, LoadError> {
+        let length = u32::decode(reader)? as usize;
+        let start = reader.offset;
+        let bytes = reader.take(length)?;
+
+        String::from_utf8(bytes.to_vec())
+            .map_err(|_| Reader::error_at(start, "a string isn't valid UTF-8".to_owned()))
+    }
+}
+
+impl<T: Encode> Encode for Vec<T> {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (self.len() as u32).encode(out);
+        self.iter().for_each(|element| element.encode(out));
+    }
+
+    fn decode(reader: &mut Reader) -> Result<Self, LoadError> {
+        let count = u32::decode(reader)? as usize;
+
+        // Every element takes at least a byte, which keeps a bad count from allocating too much.
+        if count > reader.bytes.len() - reader.offset {
+            return Err(reader
+                .error(format!("a list of {count} elements runs past the end of the section")));
+        }
+
+        (0..count).map(|_| T::decode(reader)).collect()
+    }
+}
+
+impl<A: Encode, B: Encode> Encode for (A, B) {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.0.encode(out);
+        self.1.encode(out);
+    }
+
+    fn decode(reader: &mut Reader) -> Result<Self, LoadError> {
+        Ok((A::decode(reader)?, B::decode(reader)?))
+    }
+}
+
+impl Encode for IntTy {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.bits.encode(out);
+        self.signed.encode(out);
+    }
+
+    fn decode(reader: &mut Reader) -> Result<Self, LoadError> {
+        let bits = u8::decode(reader)?;
+
+        if !matches!(bits, 8 | 16 | 32 | 64) {
+            return Err(reader.error(format!("an integer type has {bits} bits")));
+        }
+
+        Ok(IntTy {
+            bits,
+            signed: bool::decode(reader)?,
+        })
+    }
+}
+
+impl Encode for CompareKind {
+    fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            CompareKind::Int(ty) => {
+                out.push(0);
+                ty.encode(out);
+            }
+            CompareKind::Float => out.push(1),
+            CompareKind::Value => out.push(2),
+        }
+    }
+
+    fn decode(reader: &mut Reader) -> Result<Self, LoadError> {
+        match u8::decode(reader)? {
+            0 => Ok(CompareKind::Int(IntTy::decode(reader)?)),
+            1 => Ok(CompareKind::Float),
+            2 => Ok(CompareKind::Value),
+            tag => reader.bad_tag("comparison kind", tag),
+        }
+    }
+}
+
+impl Encode for Builtin {
+    // Builtins are written by name, so that adding one doesn't change the others.
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.name().to_owned().encode(out);
+    }
+
+    fn decode(reader: &mut Reader) -> Result<Self, LoadError> {
+        let name = String::decode(reader)?;
+
+        (Builtin::ALL.into_iter())
+            .find(|builtin| builtin.name() == name)
+            .ok_or_else(|| reader.error(format!("unknown builtin function `{name}`")))
+    }
+}
+
+impl Encode for Constant {
+    fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            Constant::Void => out.push(0),
+            Constant::Bool(value) => {
+                out.push(1);
+                value.encode(out);
+            }
+            Constant::Int(value, ty) => {
+                out.push(2);
+                value.encode(out);
+                ty.encode(out);
+            }
+            Constant::Float(value) => {
+                out.push(3);
+                value.encode(out);
+            }
+            Constant::Str(value) => {
+                out.push(4);
+                value.encode(out);
+            }
+            Constant::Function(function) => {
+                out.push(5);
+                function.encode(out);
+            }
+            Constant::Builtin(builtin) => {
+                out.push(6);
+                builtin.encode(out);
+            }
+            Constant::Variant(ty, index) => {
+                out.push(7);
+                ty.encode(out);
+                index.encode(out);
+            }
+        }
+    }
+
+    fn decode(reader: &mut Reader) -> Result<Self, LoadError> {
+        Ok(match u8::decode(reader)? {
+            0 => Constant::Void,
+            1 => Constant::Bool(bool::decode(reader)?),
+            2 => Constant::Int(i64::decode(reader)?, IntTy::decode(reader)?),
+            3 => Constant::Float(f64::decode(reader)?),
+            4 => Constant::Str(String::decode(reader)?),
+            5 => Constant::Function(FunctionId::decode(reader)?),
+            6 => Constant::Builtin(Builtin::decode(reader)?),
+            7 => Constant::Variant(TypeId::decode(reader)?, u32::decode(reader)?),
+            tag => return reader.bad_tag("constant", tag),
+        })
+    }
+}
+
+impl Encode for Type {
+    fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            Type::Void => out.push(0),
+            Type::Bool => out.push(1),
+            Type::Char => out.push(2),
+            Type::Str => out.push(3),
+            Type::Int(ty) => {
+                out.push(4);
+                ty.encode(out);
+            }
+            Type::Float(ty) => {
+                out.push(5);
+                ty.encode(out);
+            }
+            Type::Reference(target) => {
+                out.push(6);
+                target.encode(out);
+            }
+            Type::Function => out.push(7),
+            Type::Record { name, fields } => {
+                out.push(8);
+                name.encode(out);
+                fields.encode(out);
+            }
+            Type::Union { name, variants } => {
+                out.push(9);
+                name.encode(out);
+                variants.encode(out);
+            }
+            Type::Enum { name, variants } => {
+                out.push(10);
+                name.encode(out);
+                variants.encode(out);
+            }
+            Type::Opaque(name) => {
+                out.push(11);
+                name.encode(out);
+            }
+        }
+    }
+
+    fn decode(reader: &mut Reader) -> Result<Self, LoadError> {
+        Ok(match u8::decode(reader)? {
+            0 => Type::Void,
+            1 => Type::Bool,
+            2 => Type::Char,
+            3 => Type::Str,
+            4 => Type::Int(IntTy::decode(reader)?),
+            5 => Type::Float(FloatTy::decode(reader)?),
+            6 => Type::Reference(TypeId::decode(reader)?),
+            7 => Type::Function,
+            8 => Type::Record {
+                name: String::decode(reader)?,
+                fields: Vec::decode(reader)?,
+            },
+            9 => Type::Union {
+                name: String::decode(reader)?,
+                variants: Vec::decode(reader)?,
+            },
+            10 => Type::Enum {
+                name: String::decode(reader)?,
+                variants: Vec::decode(reader)?,
+            },
+            11 => Type::Opaque(String::decode(reader)?),
+            tag => return reader.bad_tag("type", tag),
+        })
+    }
+}
+
+impl Encode for Function {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.name.encode(out);
+        self.params.encode(out);
+        self.registers.encode(out);
+        self.ret.encode(out);
+        self.code.encode(out);
+        self.paths.encode(out);
+        self.switches.encode(out);
+    }
+
+    fn decode(reader: &mut Reader) -> Result<Self, LoadError> {
+        Ok(Function {
+            name: String::decode(reader)?,
+            params: u32::decode(reader)?,
+            registers: Vec::decode(reader)?,
+            ret: TypeId::decode(reader)?,
+            code: Vec::decode(reader)?,
+            paths: Vec::decode(reader)?,
+            switches: Vec::decode(reader)?,
+        })
+    }
+}
+
+impl Encode for SwitchTable {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.cases.encode(out);
+        self.default.encode(out);
+    }
+
+    fn decode(reader: &mut Reader) -> Result<Self, LoadError> {
+        Ok(SwitchTable {
+            cases: Vec::decode(reader)?,
+            default: u32::decode(reader)?,
+        })
+    }
+}
+
+impl Encode for MethodTable {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.name.encode(out);
+        self.impls.encode(out);
+    }
+
+    fn decode(reader: &mut Reader) -> Result<Self, LoadError> {
+        Ok(MethodTable {
+            name: String::decode(reader)?,
+            impls: Vec::decode(reader)?,
+        })
+    }
+}
+
+impl Encode for DebugInfo {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.files.encode(out);
+        self.functions.encode(out);
+    }
+
+    fn decode(reader: &mut Reader) -> Result<Self, LoadError> {
+        Ok(DebugInfo {
+            files: Vec::decode(reader)?,
+            functions: Vec::decode(reader)?,
+        })
+    }
+}
+
+impl Encode for Location {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.offset.encode(out);
+        self.file.encode(out);
+        self.line.encode(out);
+        self.column.encode(out);
+    }
+
+    fn decode(reader: &mut Reader) -> Result<Self, LoadError> {
+        Ok(Location {
+            offset: u32::decode(reader)?,
+            file: u32::decode(reader)?,
+            line: u32::decode(reader)?,
+            column: u32::decode(reader)?,
+        })
+    }
+}
+
+/// Implement [Encode] for [Instruction], with each variant's opcode and the fields written after it.
+macro_rules! encode_instructions {
+    ($($opcode:literal => $variant:ident { $($field:ident),* $(,)? })*) => {
+        impl Encode for Instruction {
+            fn encode(&self, out: &mut Vec<u8>) {
+                match self {
+                    $(Instruction::$variant { $($field),* } => {
+                        out.push($opcode);
+                        $($field.encode(out);)*
+                    })*
+                }
+            }
+
+            fn decode(reader: &mut Reader) -> Result<Self, LoadError> {
+                match u8::decode(reader)? {
+                    $($opcode => Ok(Instruction::$variant { $($field: Encode::decode(reader)?),* }),)*
+                    opcode => reader.bad_tag("opcode", opcode),
+                }
+            }
+        }
+    };
+}
+
+encode_instructions! {
+    0 => LoadConst { dst, constant }
+    1 => Move { dst, src }
+    2 => Int { op, ty, dst, lhs, rhs }
+    3 => IntNeg { ty, dst, src }
+    4 => IntNot { ty, dst, src }
+    5 => Float { op, ty, dst, lhs, rhs }
+    6 => FloatNeg { dst, src }
+    7 => Bool { op, dst, lhs, rhs }
+    8 => BoolNot { dst, src }
+    9 => Compare { op, kind, dst, lhs, rhs }
+    10 => Alloc { dst }
+    11 => Load { dst, ptr, path }
+    12 => Store { ptr, path, src }
+    13 => Offset { dst, ptr, path }
+    14 => Drop { ptr }
+    15 => MakeRecord { dst, ty, start, count }
+    16 => GetField { dst, src, field }
+    17 => MakeUnion { dst, ty, variant, src }
+    18 => IsVariant { dst, src, variant }
+    19 => GetVariant { dst, src, variant }
+    20 => MakeClosure { dst, function, start, count }
+    21 => Call { dst, callee, start, count }
+    22 => CallDirect { dst, function, start, count }
+    23 => CallMethod { dst, method, start, count }
+    24 => Print { dst, src, ty, newline }
+    25 => Jump { target }
+    26 => JumpIf { condition, target }
+    27 => JumpIfNot { condition, target }
+    28 => Switch { src, table }
+    29 => Return { src }
+    30 => Trap { message }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LoadError, MAGIC, load, read, write};
+    use crate::vm::bytecode::{
+        ConstId, Constant, DebugInfo, Function, FunctionId, Instruction, IntTy, Location, Module,
+        Reg, Type, TypeId,
+    };
+
+    const U8: IntTy = IntTy {
+        bits: 8,
+        signed: false,
+    };
+
+    /// A module whose `main` returns 7.
+    fn module() -> Module {
+        Module {
+            constants: vec![Constant::Int(7, U8), Constant::Str("boom".to_owned())],
+            types: vec![Type::Int(U8)],
+            functions: vec![Function {
+                name: "main".to_owned(),
+                params: 0,
+                registers: vec![TypeId(0)],
+                ret: TypeId(0),
+                code: vec![
+                    Instruction::LoadConst {
+                        dst: Reg(0),
+                        constant: ConstId(0),
+                    },
+                    Instruction::Return { src: Reg(0) },
+                ],
+                paths: Vec::new(),
+                switches: Vec::new(),
+            }],
+            methods: Vec::new(),
+            entry: Some(FunctionId(0)),
+            debug: Some(DebugInfo {
+                files: vec!["main.wr".to_owned()],
+                functions: vec![vec![Location {
+                    offset: 0,
+                    file: 0,
+                    line: 1,
+                    column: 20,
+                }]],
+            }),
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let module = module();
+        let bytes = write(&module);
+        assert!(bytes.starts_with(&MAGIC));
+        assert_eq!(load(&bytes), Ok(module.clone()));
+
+        // The debug info is optional.
+        let stripped = Module {
+            debug: None,
+            ..module
+        };
+
+        let stripped_bytes = write(&stripped);
+        assert!(stripped_bytes.len() < bytes.len());
+        assert_eq!(load(&stripped_bytes), Ok(stripped));
+    }
+
+    #[test]
+    fn test_malformed() {
+        let bytes = write(&module());
+
+        assert_eq!(read(b"#!/bin/sh"), Err(LoadError::NotBytecode));
+
+        let mut future = bytes.clone();
+        future[4] = 3;
+        assert_eq!(read(&future), Err(LoadError::UnsupportedVersion(3)));
+
+        // Cutting the file off anywhere after the header is caught (except between sections, when the rest is
+        // the optional debug info).
+        let stripped = write(&Module {
+            debug: None,
+            ..module()
+        });
+
+        for end in 10..stripped.len() {
+            assert!(matches!(read(&stripped[..end]), Err(LoadError::Malformed { .. })), "{end}");
+        }
+
+        // So is an unknown opcode. The code of `main` is the opcode of `LoadConst` (0), its two operands (0u32
+        // each), and the opcode of `Return` (29).
+        let mut unknown = bytes.clone();
+        let opcode = (bytes.windows(10))
+            .position(|window| window == [0, 0, 0, 0, 0, 0, 0, 0, 0, 29])
+            .unwrap();
+        unknown[opcode] = 200;
+
+        match read(&unknown) {
+            Err(LoadError::Malformed { offset, message }) => {
+                assert_eq!((offset, message.as_str()), (opcode, "unknown opcode tag 200"))
+            }
+            other => panic!("{other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_corrupted() {
+        // `main` also calls a function, so that corrupting the callee's types is checked through the call.
+        let mut module = module();
+        module.functions[0].code.insert(
+            1,
+            Instruction::CallDirect {
+                dst: Reg(0),
+                function: FunctionId(1),
+                start: Reg(0),
+                count: 1,
+            },
+        );
+        module.functions.push(Function {
+            name: "id".to_owned(),
+            params: 1,
+            registers: vec![TypeId(0)],
+            ret: TypeId(0),
+            code: vec![Instruction::Return { src: Reg(0) }],
+            paths: Vec::new(),
+            switches: Vec::new(),
+        });
+
+        let bytes = write(&module);
+        assert_eq!(load(&bytes), Ok(module));
+
+        // Loading a file that's been cut off or had any byte changed returns (at worst) an error rather than
+        // panicking.
+        for index in 0..bytes.len() {
+            let _ = load(&bytes[..index]);
+
+            for value in [0x00, 0x01, 0x7f, 0xfe, 0xff, bytes[index] ^ 0x80] {
+                let mut corrupted = bytes.clone();
+                corrupted[index] = value;
+                let _ = load(&corrupted);
+            }
+        }
+    }
+
+    #[test]
+    fn test_verified_on_load() {
+        let mut module = module();
+        module.functions[0].code[0] = Instruction::LoadConst {
+            dst: Reg(0),
+            constant: ConstId(1),
+        };
+
+        // Reading doesn't check the module, but loading does.
+        let bytes = write(&module);
+        assert!(read(&bytes).is_ok());
+        assert_eq!(
+            load(&bytes),
+            Err(LoadError::Invalid(vec![
+                "in `main` at 0, register 0 should be the type of the constant".to_owned()
+            ]))
+        );
+    }
+}
@@ -0,0 +1,120 @@
+//! Transpiling every `tests/mir/*.wr` file and the hello world example to C, and checking that the system's C
+//! compiler accepts it (and that hello world runs).
+
+#![cfg(feature = "codegen")]
+
+use std::{fs, path::Path, process::Command};
+use wright::{
+    codegen::c,
+    package::{check_package, load_package},
+    source_tracking::SourceMap,
+};
+
+/// Transpile the package with the given root file.
+fn transpile(path: &Path) -> String {
+    let source_map = SourceMap::new();
+    let package = check_package(load_package(&source_map, path).unwrap());
+    assert_eq!(package.errors(), 0, "{} has errors", path.display());
+
+    let program = package.lower();
+    c::transpile(&program, &package.resolution, &package.typeck, true)
+}
+
+/// Compile C source with the system's C compiler, with the given extra arguments. Returns false if there's no C
+/// compiler.
+fn compile(code: &str, dir: &Path, args: &[&str]) -> bool {
+    let c_file = dir.join("main.c");
+    fs::write(&c_file, code).unwrap();
+
+    let Ok(output) = Command::new("cc")
+        .current_dir(dir)
+        .args(["-std=c11", "-Wall", "-Wextra", "-Werror"])
+        .args(args)
+        .arg(&c_file)
+        .arg("-lm")
+        .output()
+    else {
+        eprintln!("skipping the test, since there's no C compiler (`cc`)");
+        return false;
+    };
+
+    assert!(output.status.success(), "{}\n{code}", String::from_utf8_lossy(&output.stderr));
+
+    true
+}
+
+#[test]
+fn test_transpile_and_compile() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let dir = std::env::temp_dir().join(format!("wright-c-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    let files: Vec<_> = (fs::read_dir(root.join("tests/mir")).unwrap())
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "wr"))
+        .collect();
+
+    assert!(!files.is_empty());
+
+    for path in files {
+        let code = transpile(&path);
+        let file = format!("\"{}\"", path.display());
+        assert!(
+            code.lines()
+                .any(|line| line.starts_with("#line ") && line.ends_with(&file))
+        );
+
+        // Most of these don't have a `main` function, so they're only compiled to an object file.
+        if !compile(&code, &dir, &["-c", "-Wno-unused-function", "-o", "main.o"]) {
+            return;
+        }
+    }
+
+    let hello = transpile(&root.join("../examples/hello-world.wr"));
+
+    if compile(&hello, &dir, &["-o", "main"]) {
+        let output = Command::new(dir.join("main")).output().unwrap();
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), "Hello World!\n");
+    }
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_trap_lines() {
+    let dir = std::env::temp_dir().join(format!("wright-c-traps-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    // Each trap comes after other statements lowered from the same line, and from lines before it.
+    let cases = [
+        (
+            "func main() -> u8 {\n    let a: u8 = 200; let b: u8 = a / 2;\n    let c = b + a / 4 + a;\n    c\n}",
+            "arithmetic overflow",
+            3,
+        ),
+        (
+            "func div(a: u8, b: u8) -> u8 { a / b }\n\nfunc main() -> u8 {\n    let x = div(4, 2) + div(6, 3);\n    x + div(1, 0)\n}",
+            "division by zero",
+            1,
+        ),
+    ];
+
+    for (index, (source, message, line)) in cases.into_iter().enumerate() {
+        let path = dir.join(format!("trap_{index}.wr"));
+        fs::write(&path, source).unwrap();
+
+        if !compile(&transpile(&path), &dir, &["-o", "main"]) {
+            return;
+        }
+
+        let output = Command::new(dir.join("main")).output().unwrap();
+        assert!(!output.status.success());
+        assert_eq!(
+            String::from_utf8(output.stderr).unwrap(),
+            format!("error: {message}, at {}:{line}\n", path.display())
+        );
+    }
+
+    fs::remove_dir_all(&dir).unwrap();
+}
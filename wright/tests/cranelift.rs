@@ -0,0 +1,102 @@
+//! Running every example and `tests/mir/*.wr` file with a `main` function in the VM and compiled in memory with
+//! Cranelift, and checking that they print the same and stop with the same trap. Hello world is also compiled to an
+//! object file, which is linked with the runtime by the system's C compiler and run.
+
+#![cfg(all(feature = "cranelift", feature = "vm"))]
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+use wright::{
+    codegen::cranelift,
+    package::{check_package, load_package},
+    source_tracking::SourceMap,
+    vm::{self, Vm},
+};
+
+/// How a program ran: what it printed, and the message of the trap that stopped it (if any).
+type Outcome = (String, Option<String>);
+
+/// Run the package with the given root file in the VM and with Cranelift's JIT. It must not have errors.
+fn run_both(path: &Path) -> (Outcome, Outcome) {
+    let source_map = SourceMap::new();
+    let package = check_package(load_package(&source_map, path).unwrap());
+    assert_eq!(package.errors(), 0, "{} has errors", path.display());
+
+    let program = package.lower();
+
+    let module = vm::compile(&program, &package.resolution, &package.typeck);
+    let mut vm_out = Vec::new();
+    let vm_trap = Vm::new(&module).run(&mut vm_out).err();
+
+    let mut jit_out = Vec::new();
+    let jit_trap =
+        match cranelift::run(&program, &package.resolution, &package.typeck, &mut jit_out) {
+            Ok(()) => None,
+            Err(cranelift::Error::Trap(trap)) => Some(trap.message),
+            Err(error) => panic!("{}: {error}", path.display()),
+        };
+
+    (
+        (String::from_utf8(vm_out).unwrap(), vm_trap.map(|trap| trap.message)),
+        (String::from_utf8(jit_out).unwrap(), jit_trap),
+    )
+}
+
+/// Get the `.wr` files in a directory (relative to the crate) that have a `main` function.
+fn programs(dir: &str) -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join(dir);
+
+    (fs::read_dir(dir).unwrap())
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "wr"))
+        .filter(|path| fs::read_to_string(path).unwrap().contains("func main("))
+        .collect()
+}
+
+#[test]
+fn test_jit_matches_vm() {
+    let examples = programs("../examples");
+    assert!(examples.iter().any(|path| path.ends_with("hello-world.wr")));
+
+    for path in examples.into_iter().chain(programs("tests/mir")) {
+        let (vm, jit) = run_both(&path);
+        assert_eq!(jit, vm, "{}", path.display());
+    }
+}
+
+#[test]
+fn test_object_file() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../examples/hello-world.wr");
+    let source_map = SourceMap::new();
+    let package = check_package(load_package(&source_map, &path).unwrap());
+    assert_eq!(package.errors(), 0);
+
+    let program = package.lower();
+    let object = cranelift::object(&program, &package.resolution, &package.typeck).unwrap();
+
+    let dir = std::env::temp_dir().join(format!("wright-cranelift-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("main.o"), object).unwrap();
+    fs::write(dir.join("runtime.c"), cranelift::RUNTIME).unwrap();
+
+    let compiled = Command::new("cc")
+        .current_dir(&dir)
+        .args(["-std=c11", "-o", "main", "main.o", "runtime.c", "-lm"])
+        .output();
+
+    let Ok(compiled) = compiled else {
+        eprintln!("skipping the test, since there's no C compiler (`cc`)");
+        return;
+    };
+
+    assert!(compiled.status.success(), "{}", String::from_utf8_lossy(&compiled.stderr));
+
+    let output = Command::new(dir.join("main")).output().unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "Hello World!\n");
+}
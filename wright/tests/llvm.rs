@@ -0,0 +1,103 @@
+//! Generating LLVM IR for every `tests/mir/*.wr` file and the hello world example, and checking that `llc` accepts
+//! it (and that hello world runs, once it's linked with the system's C compiler).
+
+#![cfg(feature = "codegen")]
+
+use std::{fs, path::Path, process::Command};
+use wright::{
+    codegen::llvm,
+    package::{check_package, load_package},
+    source_tracking::SourceMap,
+};
+
+/// Generate LLVM IR for the package with the given root file.
+fn emit(path: &Path) -> String {
+    let source_map = SourceMap::new();
+    let package = check_package(load_package(&source_map, path).unwrap());
+    assert_eq!(package.errors(), 0, "{} has errors", path.display());
+
+    let program = package.lower();
+    llvm::emit(&program, &package.resolution, &package.typeck, true)
+}
+
+/// Compile LLVM IR to `main.o` with `llc`. Returns false if there's no `llc`.
+fn compile(code: &str, dir: &Path) -> bool {
+    let ll_file = dir.join("main.ll");
+    fs::write(&ll_file, code).unwrap();
+
+    let Ok(version) = Command::new("llc").arg("--version").output() else {
+        eprintln!("skipping the test, since there's no `llc`");
+        return false;
+    };
+
+    // Opaque pointers are the default since LLVM 15.
+    let version = String::from_utf8_lossy(&version.stdout);
+    let major = (version.split("version ").nth(1))
+        .and_then(|version| version.split('.').next())
+        .and_then(|major| major.trim().parse::<u32>().ok())
+        .unwrap_or(15);
+
+    let output = Command::new("llc")
+        .current_dir(dir)
+        .args(["-relocation-model=pic", "-filetype=obj", "-o", "main.o"])
+        .args((major < 15).then_some("-opaque-pointers"))
+        .arg(&ll_file)
+        .output()
+        .unwrap();
+
+    assert!(output.status.success(), "{}\n{code}", String::from_utf8_lossy(&output.stderr));
+
+    true
+}
+
+#[test]
+fn test_emit_and_compile() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let dir = std::env::temp_dir().join(format!("wright-llvm-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    let files: Vec<_> = (fs::read_dir(root.join("tests/mir")).unwrap())
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "wr"))
+        .collect();
+
+    assert!(!files.is_empty());
+
+    for path in files {
+        let code = emit(&path);
+        let file = format!("!DIFile(filename: \"{}\"", path.display());
+        assert!(code.contains(&file));
+        assert!(code.contains(", !dbg !"));
+
+        if !compile(&code, &dir) {
+            return;
+        }
+    }
+
+    let hello = emit(&root.join("../examples/hello-world.wr"));
+
+    if compile(&hello, &dir) {
+        link_and_run(&dir);
+    }
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+/// Link `main.o` (hello world) with the system's C compiler, and check what it prints. Skips it if there's no C
+/// compiler, but a C compiler that fails to link it fails the test.
+fn link_and_run(dir: &Path) {
+    let Ok(linked) = Command::new("cc")
+        .current_dir(dir)
+        .args(["-o", "main", "main.o"])
+        .output()
+    else {
+        eprintln!("skipping running hello world, since there's no C compiler (`cc`) to link it");
+        return;
+    };
+
+    assert!(linked.status.success(), "{}", String::from_utf8_lossy(&linked.stderr));
+
+    let output = Command::new(dir.join("main")).output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "Hello World!\n");
+}
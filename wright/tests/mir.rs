@@ -0,0 +1,70 @@
+//! Golden tests of lowering to MIR: every `tests/mir/*.wr` file is checked, lowered, and verified, and its MIR is
+//! compared with the `.mir` file next to it. Run with `WRIGHT_BLESS=1` to write the current output to the `.mir`
+//! files instead.
+
+#![cfg(feature = "analysis")]
+
+use std::{fs, path::Path};
+use wright::{
+    mir,
+    package::{check_package, load_package},
+    source_tracking::SourceMap,
+};
+
+/// Check, lower, and verify a file, returning its MIR in textual form.
+fn lower_file(path: &Path) -> String {
+    let source_map = SourceMap::new();
+    let package = check_package(load_package(&source_map, path).unwrap());
+    assert_eq!(package.errors(), 0, "{} has errors", path.display());
+
+    let program = package.lower();
+
+    for body in &program.bodies {
+        assert_eq!(
+            mir::verify(body),
+            Vec::<String>::new(),
+            "{} lowers to invalid MIR",
+            path.display()
+        );
+    }
+
+    program.display(&package.resolution).to_string()
+}
+
+#[test]
+fn test_mir_golden_files() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/mir");
+    let bless = std::env::var_os("WRIGHT_BLESS").is_some();
+
+    let mut files: Vec<_> = (fs::read_dir(&dir).unwrap())
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "wr"))
+        .collect();
+
+    files.sort();
+    assert!(!files.is_empty());
+
+    let mut mismatches = Vec::new();
+
+    for file in files {
+        let actual = lower_file(&file);
+        let expected_path = file.with_extension("mir");
+
+        if bless {
+            fs::write(&expected_path, &actual).unwrap();
+            continue;
+        }
+
+        let expected = fs::read_to_string(&expected_path).unwrap_or_default();
+
+        if actual != expected {
+            mismatches.push(format!("{}:\n{actual}", expected_path.display()));
+        }
+    }
+
+    assert!(
+        mismatches.is_empty(),
+        "the MIR doesn't match (run with WRIGHT_BLESS=1 to update):\n\n{}",
+        mismatches.join("\n")
+    );
+}
@@ -0,0 +1,39 @@
+//! Compiling every `tests/mir/*.wr` file to bytecode, and checking that it verifies and survives being written to
+//! a `.wbc` file and loaded again.
+
+#![cfg(feature = "vm")]
+
+use std::{fs, path::Path};
+use wright::{
+    package::{check_package, load_package},
+    source_tracking::SourceMap,
+    vm::{self, wbc},
+};
+
+#[test]
+fn test_compile_and_load() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/mir");
+
+    let files: Vec<_> = (fs::read_dir(&dir).unwrap())
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "wr"))
+        .collect();
+
+    assert!(!files.is_empty());
+
+    for path in files {
+        let source_map = SourceMap::new();
+        let package = check_package(load_package(&source_map, &path).unwrap());
+        assert_eq!(package.errors(), 0, "{} has errors", path.display());
+
+        let program = package.lower();
+        let module = vm::compile(&program, &package.resolution, &package.typeck);
+
+        assert_eq!(vm::verify(&module), Vec::<String>::new(), "{}", path.display());
+
+        let debug = module.debug.as_ref().unwrap();
+        assert_eq!(debug.files, [path.display().to_string()]);
+
+        assert_eq!(wbc::load(&wbc::write(&module)), Ok(module), "{}", path.display());
+    }
+}
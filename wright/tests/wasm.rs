@@ -0,0 +1,154 @@
+//! Generating a WebAssembly module for every example and `tests/mir/*.wr` file, and checking that it's valid. The
+//! ones with a `main` function are also run in the VM and under wasmi, and must print the same and stop with the
+//! same trap.
+
+#![cfg(all(feature = "codegen", feature = "vm"))]
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use wasmi::{Caller, Engine, Extern, Linker, Module, Store};
+use wright::{
+    codegen::wasm,
+    package::{check_package, load_package},
+    source_tracking::SourceMap,
+    vm::{self, Vm},
+};
+
+/// How a program ran: what it printed, and the message of the trap that stopped it (if any).
+type Outcome = (String, Option<String>);
+
+/// What the host has seen a module do.
+#[derive(Default)]
+struct Host {
+    out: String,
+    trap: Option<String>,
+}
+
+/// Read a string out of the memory of the module that's calling the host.
+fn read(caller: &Caller<'_, Host>, address: i32, length: i32) -> String {
+    let memory = (caller.get_export("memory"))
+        .and_then(Extern::into_memory)
+        .unwrap();
+    let bytes = &memory.data(caller)[address as usize..(address + length) as usize];
+    String::from_utf8(bytes.to_vec()).unwrap()
+}
+
+/// Run the `main` function of a module with wasmi.
+fn run_wasm(bytes: &[u8]) -> Outcome {
+    let engine = Engine::default();
+    let module = Module::new(&engine, bytes).unwrap();
+    let mut store = Store::new(&engine, Host::default());
+    let mut linker = Linker::<Host>::new(&engine);
+
+    (linker.func_wrap(
+        "wright::io",
+        "print_str",
+        |mut caller: Caller<'_, Host>, address, length| {
+            let string = read(&caller, address, length);
+            caller.data_mut().out.push_str(&string);
+        },
+    ))
+    .unwrap();
+
+    (linker.func_wrap("wright::io", "print_i64", |mut caller: Caller<'_, Host>, value: i64| {
+        caller.data_mut().out.push_str(&value.to_string());
+    }))
+    .unwrap();
+
+    (linker.func_wrap("wright::io", "print_u64", |mut caller: Caller<'_, Host>, value: i64| {
+        caller.data_mut().out.push_str(&(value as u64).to_string());
+    }))
+    .unwrap();
+
+    (linker.func_wrap("wright::io", "print_f64", |mut caller: Caller<'_, Host>, value: f64| {
+        caller.data_mut().out.push_str(&value.to_string());
+    }))
+    .unwrap();
+
+    (linker.func_wrap("wright::io", "print_char", |mut caller: Caller<'_, Host>, value: i32| {
+        caller
+            .data_mut()
+            .out
+            .push(char::from_u32(value as u32).unwrap());
+    }))
+    .unwrap();
+
+    (linker.func_wrap("wright::io", "print_bool", |mut caller: Caller<'_, Host>, value: i32| {
+        caller.data_mut().out.push_str(&(value != 0).to_string());
+    }))
+    .unwrap();
+
+    (linker.func_wrap(
+        "wright::runtime",
+        "trap",
+        |mut caller: Caller<'_, Host>, message, message_length, _: i32, _: i32, _: i32| {
+            let message = read(&caller, message, message_length);
+            caller.data_mut().trap = Some(message.clone());
+            Err::<(), _>(wasmi::Error::new(message))
+        },
+    ))
+    .unwrap();
+
+    (linker.func_wrap("wright::runtime", "fmod", |a: f64, b: f64| a % b)).unwrap();
+
+    let instance = (linker.instantiate(&mut store, &module))
+        .unwrap()
+        .start(&mut store)
+        .unwrap();
+    let main = instance.get_typed_func::<(), ()>(&store, "main").unwrap();
+    let result = main.call(&mut store, ());
+    let Host { out, trap } = store.into_data();
+    assert_eq!(result.is_err(), trap.is_some(), "{result:?}");
+    (out, trap)
+}
+
+/// Generate a WebAssembly module for the package with the given root file, which must not have errors. If it has a
+/// `main` function, it's also run in the VM and under wasmi.
+fn check(path: &Path) -> Option<(Outcome, Outcome)> {
+    let source_map = SourceMap::new();
+    let package = check_package(load_package(&source_map, path).unwrap());
+    assert_eq!(package.errors(), 0, "{} has errors", path.display());
+
+    let program = package.lower();
+
+    let bytes = wasm::emit(&program, &package.resolution, &package.typeck, true);
+
+    if let Err(error) = wasmparser::validate(&bytes) {
+        panic!("{}: {error}", path.display());
+    }
+
+    if !fs::read_to_string(path).unwrap().contains("func main(") {
+        return None;
+    }
+
+    let module = vm::compile(&program, &package.resolution, &package.typeck);
+    let mut vm_out = Vec::new();
+    let vm_trap = Vm::new(&module).run(&mut vm_out).err();
+    let vm = (String::from_utf8(vm_out).unwrap(), vm_trap.map(|trap| trap.message));
+
+    Some((vm, run_wasm(&bytes)))
+}
+
+/// Get the `.wr` files in a directory (relative to the crate).
+fn sources(dir: &str) -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join(dir);
+
+    (fs::read_dir(dir).unwrap())
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "wr"))
+        .collect()
+}
+
+#[test]
+fn test_wasm_matches_vm() {
+    let examples = sources("../examples");
+    assert!(examples.iter().any(|path| path.ends_with("hello-world.wr")));
+
+    for path in examples.into_iter().chain(sources("tests/mir")) {
+        if let Some((vm, wasm)) = check(&path) {
+            assert_eq!(wasm, vm, "{}", path.display());
+        }
+    }
+}